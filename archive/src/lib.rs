@@ -0,0 +1,317 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::release;
+use radicle_common::cobs::Timestamp;
+use radicle_common::sync::refs;
+use radicle_common::{exit, fmt, git, json, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "archive",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad archive [<urn>] --output <file> [--rev <rev>] [--manifest <file>]
+
+    Exports a snapshot of a project from local storage as a source archive, like
+    `git archive`. The archive format is chosen from the output file extension, eg.
+    `.tar.gz`, `.tar` or `.zip`.
+
+    Next to the archive, a JSON manifest is written, for provenance: it holds the
+    project URN, the exported commit and tree, the SHA-256 checksum of the archive, and
+    the signed refs of every delegate that published the commit, which can be verified
+    against the delegate keys.
+
+    By default, the head of the default branch is exported: yours, or else that of a
+    delegate. A branch or tag name, or a commit id, can be given with `--rev`.
+
+    If no URN is given, the project of the current working copy is exported.
+
+Options
+
+    --output, -o <file>   Archive file to write
+    --rev <rev>           Branch, tag or commit to export (default: the default branch)
+    --manifest <file>     Manifest file to write (default: <output>.manifest.json)
+    --help                Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub rev: Option<String>,
+    pub output: PathBuf,
+    pub manifest: Option<PathBuf>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut rev: Option<String> = None;
+        let mut output: Option<PathBuf> = None;
+        let mut manifest: Option<PathBuf> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("rev") => {
+                    rev = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("output") | Short('o') => {
+                    output = Some(PathBuf::from(parser.value()?));
+                }
+                Long("manifest") => {
+                    manifest = Some(PathBuf::from(parser.value()?));
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    urn = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                urn,
+                rev,
+                output: output
+                    .ok_or_else(|| anyhow!("an output file must be given with `--output`"))?,
+                manifest,
+            },
+            vec![],
+        ))
+    }
+}
+
+/// A delegate that published the exported commit.
+struct Attestation {
+    peer: PeerId,
+    /// Refs of the delegate pointing to the commit, eg. `heads/master`.
+    refs: Vec<String>,
+    /// Signed refs of the delegate, as JSON.
+    signed: json::Value,
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("could not determine project"),
+                hint: "hint: run this command within a project working copy, or specify a URN",
+            })?,
+    };
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| Error::WithHint {
+        err: exit::Error::NotFound(term::tr!("project {} not found in local storage", urn)).into(),
+        hint: "hint: fetch the project with `rad sync --fetch`",
+    })?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+
+    let (rev, head) = resolve(&monorepo, &proj, storage.peer_id(), options.rev.as_deref())?;
+    let commit = monorepo.find_commit(head)?;
+    let output = std::env::current_dir()?.join(&options.output);
+    let manifest = options.manifest.clone().unwrap_or_else(|| {
+        let mut name = options.output.clone().into_os_string();
+        name.push(".manifest.json");
+        PathBuf::from(name)
+    });
+
+    let spinner = term::spinner(term::tr!(
+        "Exporting {} of {}...",
+        term::format::secondary(fmt::oid(&head)),
+        term::format::highlight(&proj.name)
+    ));
+    let prefix = format!("--prefix={}/", proj.name);
+    let path = output.to_string_lossy();
+    let oid = head.to_string();
+
+    if let Err(err) = git::git(
+        profile.paths().git_dir(),
+        ["archive", &prefix, "--output", &path, &oid],
+    ) {
+        return Err(spinner.error(err));
+    }
+    spinner.finish();
+
+    let attestations = attestations(&monorepo, &proj, storage.peer_id(), head);
+    let checksum = release::checksum(&output)?;
+    let name = options
+        .output
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let document = json::json!({
+        "urn": urn.to_string(),
+        "name": proj.name,
+        "rev": rev,
+        "head": head.to_string(),
+        "tree": commit.tree_id().to_string(),
+        "archive": {
+            "name": name,
+            "sha256": checksum,
+        },
+        "delegates": attestations.iter().map(|a| json::json!({
+            "peer": a.peer.default_encoding(),
+            "refs": a.refs,
+            "signedRefs": a.signed,
+        })).collect::<Vec<_>>(),
+        "timestamp": Timestamp::now().as_secs(),
+    });
+    fs::write(&manifest, json::to_string_pretty(&document)? + "\n")
+        .with_context(|| term::tr!("could not write manifest to {}", manifest.display()))?;
+
+    term::success!(
+        "Archive of {} at {} written to {}",
+        term::format::highlight(&proj.name),
+        term::format::secondary(fmt::oid(&head)),
+        term::format::highlight(options.output.display())
+    );
+    term::info!("{} {}", term::format::dim("SHA-256"), checksum);
+    term::success!(
+        "Manifest written to {}",
+        term::format::highlight(manifest.display())
+    );
+
+    if attestations.is_empty() {
+        term::warning(&term::tr!(
+            "No delegate has published {}: the manifest attests to nothing",
+            fmt::oid(&head)
+        ));
+    } else {
+        for a in &attestations {
+            term::info!(
+                "{} {} {}",
+                term::format::dim("Signed by delegate"),
+                term::format::tertiary(fmt::peer(&a.peer)),
+                term::format::dim(a.refs.join(", "))
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Resolve the revision to export, in the project namespace. Returns the name of the
+/// revision, and the commit it points to.
+fn resolve(
+    monorepo: &git::Repository,
+    proj: &project::Metadata,
+    local: &PeerId,
+    rev: Option<&str>,
+) -> anyhow::Result<(String, git::Oid)> {
+    let namespace = format!("refs/namespaces/{}/refs", proj.urn.encode_id());
+    let peel = |name: &str| {
+        monorepo
+            .find_reference(name)
+            .and_then(|r| r.peel_to_commit())
+            .map(|c| c.id())
+            .ok()
+    };
+
+    let rev = match rev {
+        Some(rev) => rev.to_owned(),
+        None => {
+            let branch = proj.default_branch.to_string();
+            let mut heads = vec![format!("{}/heads/{}", namespace, branch)];
+
+            heads.extend(
+                proj.remotes
+                    .iter()
+                    .filter(|p| *p != local)
+                    .map(|p| format!("{}/remotes/{}/heads/{}", namespace, p, branch)),
+            );
+            let head = heads.iter().find_map(|h| peel(h)).ok_or_else(|| {
+                exit::Error::NotFound(term::tr!("default branch '{}' not found", branch))
+            })?;
+
+            return Ok((branch, head));
+        }
+    };
+
+    for name in [
+        format!("{}/heads/{}", namespace, rev),
+        format!("{}/tags/{}", namespace, rev),
+    ] {
+        if let Some(head) = peel(&name) {
+            return Ok((rev, head));
+        }
+    }
+    let head = monorepo
+        .revparse_single(&rev)
+        .and_then(|obj| obj.peel_to_commit())
+        .map(|c| c.id())
+        .map_err(|_| {
+            exit::Error::NotFound(term::tr!(
+                "revision '{}' not found in project {}",
+                rev,
+                proj.urn
+            ))
+        })?;
+
+    Ok((rev, head))
+}
+
+/// Get the delegates whose signed refs point to the given commit.
+fn attestations(
+    monorepo: &git::Repository,
+    proj: &project::Metadata,
+    local: &PeerId,
+    head: git::Oid,
+) -> Vec<Attestation> {
+    let namespace = format!("refs/namespaces/{}/refs", proj.urn.encode_id());
+    let mut attestations = Vec::new();
+
+    for peer in &proj.remotes {
+        let sigrefs = if peer == local {
+            format!("{}/{}", namespace, refs::SIGREFS)
+        } else {
+            format!("{}/remotes/{}/{}", namespace, peer, refs::SIGREFS)
+        };
+        let (signed, raw) = match refs::load(monorepo, &sigrefs, peer) {
+            Ok(result) => result,
+            Err(_) => continue,
+        };
+        let names = signed
+            .refs
+            .iter_categorised()
+            .filter(|((_, oid), _)| git::Oid::from(**oid) == head)
+            .map(|((name, _), category)| format!("{}/{}", category.as_str(), name.as_str()))
+            .collect::<Vec<_>>();
+
+        if names.is_empty() {
+            continue;
+        }
+        if let Ok(signed) = json::from_slice(&raw) {
+            attestations.push(Attestation {
+                peer: *peer,
+                refs: names,
+                signed,
+            });
+        }
+    }
+    attestations.sort_by_key(|a| a.peer.default_encoding());
+    attestations
+}
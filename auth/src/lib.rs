@@ -1,14 +1,18 @@
 #![allow(clippy::or_fun_call)]
 use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::Context as _;
 use radicle_common::signer::ToSigner;
 
-use librad::PeerId;
+use librad::git::refs::Refs;
+use librad::git::Urn;
+use librad::{PeerId, SecretKey};
 
-use radicle_common::args::{Args, Error, Help};
-use radicle_common::{config, git, keys, person, profile};
+use radicle_common::args::{self, Args, Error, Help};
+use radicle_common::{backup, config, exit, git, keys, person, profile, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -19,19 +23,80 @@ pub const HELP: Help = Help {
 Usage
 
     rad auth [--init | --active] [<options>...] [<peer-id>]
+    rad auth --list
+    rad auth --switch <profile>
+    rad auth --change-passphrase [--stdin]
+    rad auth [--init] --hardware <pkcs11-provider | fido2>
+    rad auth --import <file> [--stdin]
+    rad auth --rotate-key [--stdin]
 
     A passphrase may be given via the environment variable `RAD_PASSPHRASE` or
     via the standard input stream if `--stdin` is used. Using one of these
     methods disables the passphrase prompt.
 
+    Other commands that need to sign, when the key isn't in ssh-agent, read the
+    passphrase from `RAD_PASSPHRASE` as well, or else from the standard input stream
+    when there is no terminal to prompt on.
+
     If `--init` is used, a name may be given via the `--name` option. Using
     this disables the input prompt.
 
+    With `--agent-ttl`, the key is removed from ssh-agent after the given duration,
+    eg. `90s`, `30m`, `8h` or `1d`, after which you need to authenticate again. With
+    `--agent-confirm`, ssh-agent asks for confirmation every time the key is used,
+    which requires an `ssh-askpass` program. If the key is already in ssh-agent, these
+    constraints replace its current ones.
+
+    With `--switch`, the given profile is made the active profile, without
+    authenticating. Profiles are given by profile id, peer id or user name.
+    To use another profile for a single command, pass `--profile <profile>` to
+    `rad`, eg. `rad --profile work sync`, or set `RAD_PROFILE`.
+
+    With `--change-passphrase`, the secret key of the active profile is re-encrypted
+    with a new passphrase. The key, and thus your identity, stays the same. If the key
+    is in ssh-agent, it is updated as well. With `--stdin`, the current and the new
+    passphrase are read from the first and second line of the standard input stream.
+
+    With `--hardware`, the keys of a PKCS#11 provider, eg. a hardware security key,
+    are added to ssh-agent, and radicle signs via ssh-agent with the key held by the
+    token. The token must hold the profile's Ed25519 key, after which the copy of the
+    key on disk can be removed. With `--init`, the profile is created for the token's
+    key, and no key is generated or stored on disk.
+
+    With `--hardware fido2`, the resident keys of a FIDO2 authenticator are added to
+    ssh-agent, and git is configured to sign commits and tags with its `ed25519-sk`
+    key: the configuration of the current working copy, or when run elsewhere, the
+    global git configuration, after asking. Since the signatures of these keys differ
+    from plain Ed25519 signatures, they can't replace the profile's key, which still
+    signs radicle refs.
+
+    With `--import`, a profile archive created with `rad self export` is restored as a
+    new profile, and made the active profile. The archive's passphrase is asked for,
+    and stays the passphrase of the restored key.
+
+    With `--rotate-key`, a new key is generated to replace the active profile's key,
+    eg. if it was compromised or the device is retired. The new key is added to your
+    personal identity with a revision signed by both keys, after which the old key is
+    removed from it, so that your identity stays the same. A new profile holding the
+    new key and a copy of your storage is created and activated, and your refs are
+    re-signed with the new key. The old profile is kept, and can be removed once the
+    new identity is published with `rad sync --self`. Projects that list your old key
+    as a delegate directly, rather than via your identity, must be updated with
+    `rad delegate`.
+
 Options
 
     --init                  Initialize a new identity
     --active                Authenticate with the currently active profile
     --stdin                 Read passphrase from stdin (default: false)
+    --list                  List all profiles
+    --switch <profile>      Make the given profile the active profile
+    --change-passphrase     Change the passphrase of the active profile's key
+    --hardware <provider>   Sign with a key held by a PKCS#11 provider, or `fido2`
+    --import <file>         Restore a profile from an archive
+    --rotate-key            Replace the active profile's key with a new key
+    --agent-ttl <duration>  Remove the key from ssh-agent after the given duration
+    --agent-confirm         Confirm every use of the key in ssh-agent
     --name <name>           Use given name (default: none)
     --help                  Print help
 "#,
@@ -44,6 +109,14 @@ pub struct Options {
     pub stdin: bool,
     pub name: Option<String>,
     pub peer_id: Option<PeerId>,
+    pub list: bool,
+    pub switch: Option<String>,
+    pub change_passphrase: bool,
+    pub hardware: Option<keys::Hardware>,
+    pub import: Option<PathBuf>,
+    pub rotate_key: bool,
+    pub agent_ttl: Option<Duration>,
+    pub agent_confirm: bool,
 }
 
 impl Args for Options {
@@ -55,6 +128,14 @@ impl Args for Options {
         let mut stdin = false;
         let mut name = None;
         let mut peer_id = None;
+        let mut list = false;
+        let mut switch = None;
+        let mut change_passphrase = false;
+        let mut hardware = None;
+        let mut import = None;
+        let mut rotate_key = false;
+        let mut agent_ttl = None;
+        let mut agent_confirm = false;
         let mut parser = lexopt::Parser::from_args(args);
 
         while let Some(arg) = parser.next()? {
@@ -68,6 +149,44 @@ impl Args for Options {
                 Long("stdin") => {
                     stdin = true;
                 }
+                Long("list") => {
+                    list = true;
+                }
+                Long("change-passphrase") => {
+                    change_passphrase = true;
+                }
+                Long("hardware") if hardware.is_none() => {
+                    hardware = Some(keys::Hardware::from(PathBuf::from(parser.value()?)));
+                }
+                Long("rotate-key") => {
+                    rotate_key = true;
+                }
+                Long("agent-ttl") if agent_ttl.is_none() => {
+                    let ttl = args::parse_duration("agent-ttl", parser.value()?)?;
+
+                    if ttl.as_secs() == 0 {
+                        anyhow::bail!("`--agent-ttl` must be at least one second");
+                    }
+                    if ttl.as_secs() > u32::MAX as u64 {
+                        anyhow::bail!("`--agent-ttl` is too long");
+                    }
+                    agent_ttl = Some(ttl);
+                }
+                Long("agent-confirm") => {
+                    agent_confirm = true;
+                }
+                Long("import") if import.is_none() => {
+                    import = Some(PathBuf::from(parser.value()?));
+                }
+                Long("switch") if switch.is_none() => {
+                    let val = parser
+                        .value()?
+                        .to_str()
+                        .ok_or(anyhow::anyhow!("invalid profile specified with `--switch`"))?
+                        .to_owned();
+
+                    switch = Some(val);
+                }
                 Long("name") if init && name.is_none() => {
                     let val = parser
                         .value()?
@@ -92,6 +211,58 @@ impl Args for Options {
             }
         }
 
+        if (list || switch.is_some()) && (init || active || peer_id.is_some()) {
+            anyhow::bail!("`--list` and `--switch` cannot be used with other options");
+        }
+        if list && switch.is_some() {
+            anyhow::bail!("`--list` and `--switch` cannot be used together");
+        }
+        if change_passphrase && (init || list || switch.is_some() || peer_id.is_some()) {
+            anyhow::bail!("`--change-passphrase` cannot be used with other options");
+        }
+        if hardware.is_some()
+            && (list || switch.is_some() || peer_id.is_some() || change_passphrase)
+        {
+            anyhow::bail!("`--hardware` cannot be used with other options than `--init`");
+        }
+        if init && hardware == Some(keys::Hardware::Fido2) {
+            return Err(Error::WithHint {
+                err: anyhow::anyhow!("a FIDO2 key can't be a profile's signing key"),
+                hint: "hint: create the profile first, then sign commits with the FIDO2 key with `rad auth --hardware fido2`",
+            }
+            .into());
+        }
+        if import.is_some()
+            && (init
+                || active
+                || list
+                || switch.is_some()
+                || peer_id.is_some()
+                || change_passphrase
+                || hardware.is_some())
+        {
+            anyhow::bail!("`--import` cannot be used with other options");
+        }
+        if rotate_key
+            && (init
+                || list
+                || switch.is_some()
+                || peer_id.is_some()
+                || change_passphrase
+                || hardware.is_some()
+                || import.is_some())
+        {
+            anyhow::bail!("`--rotate-key` cannot be used with other options");
+        }
+        if (agent_ttl.is_some() || agent_confirm)
+            && (list || switch.is_some() || change_passphrase || hardware.is_some() || rotate_key)
+        {
+            anyhow::bail!(
+                "`--agent-ttl` and `--agent-confirm` cannot be used with `--list`, `--switch`, \
+                `--change-passphrase`, `--hardware` or `--rotate-key`"
+            );
+        }
+
         Ok((
             Options {
                 init,
@@ -99,18 +270,57 @@ impl Args for Options {
                 stdin,
                 name,
                 peer_id,
+                list,
+                switch,
+                change_passphrase,
+                hardware,
+                import,
+                rotate_key,
+                agent_ttl,
+                agent_confirm,
             },
             vec![],
         ))
     }
 }
 
+impl Options {
+    /// Constraints on the key, when added to ssh-agent.
+    fn constraints(&self) -> keys::AgentConstraints {
+        keys::AgentConstraints {
+            lifetime: self.agent_ttl,
+            confirm: self.agent_confirm,
+        }
+    }
+}
+
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profiles = match profile::list() {
         Ok(profiles) => profiles,
         _ => vec![],
     };
 
+    if options.list {
+        return list(&profiles);
+    }
+    if let Some(query) = &options.switch {
+        return switch(query);
+    }
+    if options.change_passphrase {
+        return change_passphrase(options, ctx);
+    }
+    if let Some(hardware) = &options.hardware {
+        if !options.init {
+            return self::hardware(hardware, ctx);
+        }
+    }
+    if let Some(input) = &options.import {
+        return import(input, options.stdin, options.constraints());
+    }
+    if options.rotate_key {
+        return rotate_key(options, ctx);
+    }
+
     if options.init || profiles.is_empty() {
         if options.peer_id.is_some() {
             anyhow::bail!("you may not specify a peer id when initializing a new identity");
@@ -126,9 +336,13 @@ pub fn init(options: Options) -> anyhow::Result<()> {
 
     let sock = keys::ssh_auth_sock();
     let home = profile::home();
+    let constraints = options.constraints();
 
+    if sock.is_err() && !constraints.is_empty() {
+        anyhow::bail!("`--agent-ttl` and `--agent-confirm` require ssh-agent to be running");
+    }
     if git::check_version().is_err() {
-        term::warning(&format!(
+        term::warning(&term::tr!(
             "Your git version is unsupported, please upgrade to {} or later",
             git::VERSION_REQUIRED,
         ));
@@ -141,29 +355,46 @@ pub fn init(options: Options) -> anyhow::Result<()> {
             .unwrap_or_else(|| term::text_input("Name", None).unwrap()),
     )?;
 
-    let passphrase = term::read_passphrase(options.stdin, true)?;
-    let secret = keys::pwhash(passphrase.clone());
-
-    let mut spinner = term::spinner("Creating your 🌱 Ed25519 keypair...");
-    let (profile, peer_id) = profile::create(home, secret.clone())?;
-
-    let signer = if let Ok(sock) = sock {
-        spinner.finish();
-        spinner = term::spinner("Adding to ssh-agent...");
+    let (profile, peer_id, signer) = if let Some(hardware) = &options.hardware {
+        let sock = sock.map_err(|_| {
+            anyhow::anyhow!("ssh-agent must be running to sign with a hardware key")
+        })?;
+        hardware.add()?;
 
-        keys::add(&profile, secret, sock.clone())?;
+        // No key is generated: the profile is created for the token's key.
+        let key = hardware_key(hardware)?;
+        let spinner = term::spinner("Creating your 🌱 profile for the hardware key...");
+        let profile = profile::create_without_key(home, &key.peer)?;
         let signer = sock.to_signer(&profile)?;
-
         spinner.finish();
-        signer
+
+        (profile, key.peer, signer)
     } else {
-        let signer = keys::load_secret_key(&profile, passphrase)?.to_signer(&profile)?;
+        let passphrase = term::read_passphrase(options.stdin, true)?;
+        let secret = keys::pwhash(passphrase.clone());
 
-        spinner.finish();
-        signer
+        let mut spinner = term::spinner("Creating your 🌱 Ed25519 keypair...");
+        let (profile, peer_id) = profile::create(home, secret.clone())?;
+
+        let signer = if let Ok(sock) = sock {
+            spinner.finish();
+            spinner = term::spinner("Adding to ssh-agent...");
+
+            keys::add_constrained(&profile, secret, sock.clone(), constraints)?;
+            let signer = sock.to_signer(&profile)?;
+
+            spinner.finish();
+            signer
+        } else {
+            let signer = keys::load_secret_key(&profile, passphrase)?.to_signer(&profile)?;
+
+            spinner.finish();
+            signer
+        };
+        (profile, peer_id, signer)
     };
 
-    spinner = term::spinner("Setting up config...");
+    let spinner = term::spinner("Setting up config...");
     config::Config::init(&profile)?;
     spinner.finish();
 
@@ -225,7 +456,7 @@ pub fn authenticate(
                 Ok(s) => *s.peer_id() == peer_id,
                 Err(_) => false,
             })
-            .ok_or_else(|| anyhow::anyhow!("Identity '{}' not found", peer_id))?
+            .ok_or_else(|| exit::Error::NotFound(term::tr!("Identity '{}' not found", peer_id)))?
     } else if profiles.len() > 1 && !options.active {
         if let Some(p) = term::profile_select(profiles, &profile) {
             p
@@ -236,7 +467,7 @@ pub fn authenticate(
         &profile
     };
 
-    term::headline(&format!(
+    term::headline(&term::tr!(
         "🌱 Authenticating as {}",
         term::display::Identity::new(selection).styled()
     ));
@@ -249,8 +480,10 @@ pub fn authenticate(
     }
 
     let profile = selection;
+    let constraints = options.constraints();
+
     if let Ok(sock) = keys::ssh_auth_sock() {
-        if !keys::is_ready(profile, sock.clone())? {
+        if !keys::is_ready(profile, sock.clone())? || !constraints.is_empty() {
             term::warning("Adding your radicle key to ssh-agent...");
 
             // TODO: We should show the spinner on the passphrase prompt,
@@ -259,14 +492,24 @@ pub fn authenticate(
             let secret = keys::pwhash(passphrase);
 
             let spinner = term::spinner("Unlocking...");
-            keys::add(profile, secret, sock).context("invalid passphrase supplied")?;
+            keys::add_constrained(profile, secret, sock, constraints)
+                .context("invalid passphrase supplied")?;
             spinner.finish();
 
             term::success!("Radicle key added to ssh-agent");
+            if let Some(ttl) = constraints.lifetime {
+                term::info!(
+                    "The key will be removed from ssh-agent in {}.",
+                    term::format::highlight(format_duration(ttl))
+                );
+            }
         } else {
             term::success!("Signing key already in ssh-agent");
         }
     } else {
+        if !constraints.is_empty() {
+            anyhow::bail!("`--agent-ttl` and `--agent-confirm` require ssh-agent to be running");
+        }
         term::warning("Radicle key won't be added to ssh-agent since it's not running.");
         term::blank();
     };
@@ -274,6 +517,357 @@ pub fn authenticate(
     Ok(())
 }
 
+pub fn list(profiles: &[profile::Profile]) -> anyhow::Result<()> {
+    if profiles.is_empty() {
+        term::info!("{}", term::format::italic("No profiles found."));
+        term::tip!("To create a profile, run `rad auth --init`.");
+
+        return Ok(());
+    }
+    let active = profile::default().ok();
+    let mut table = term::Table::default();
+
+    for p in profiles {
+        let name = profile::name(Some(p)).unwrap_or_default();
+        let peer_id = profile::read_only(p)
+            .map(|s| s.peer_id().to_string())
+            .unwrap_or_default();
+
+        table.push([
+            term::format::bold(name),
+            term::format::tertiary(peer_id),
+            term::format::dim(p.id()),
+            if active.as_ref().map(|a| a.id()) == Some(p.id()) {
+                term::format::badge_primary("active")
+            } else {
+                String::new()
+            },
+        ]);
+    }
+    table.render();
+
+    Ok(())
+}
+
+pub fn switch(query: &str) -> anyhow::Result<()> {
+    let profile = profile::find(query)?;
+
+    profile::set(profile.id())?;
+    term::success!(
+        "Profile {} activated",
+        term::display::Identity::new(&profile).styled()
+    );
+
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        if !keys::is_ready(&profile, sock).unwrap_or(false) {
+            term::tip!("To add the profile's key to ssh-agent, run `rad auth --active`.");
+        }
+    }
+    Ok(())
+}
+
+pub fn change_passphrase(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+
+    term::headline(&term::tr!(
+        "🌱 Changing the passphrase of {}",
+        term::display::Identity::new(&profile).styled()
+    ));
+
+    let (old, new) = if options.stdin {
+        (term::secret_stdin()?, term::secret_stdin()?)
+    } else {
+        (
+            term::secret_input_with_prompt("Current passphrase"),
+            term::secret_input_with_confirmation_prompt("New passphrase"),
+        )
+    };
+
+    let spinner = term::spinner("Re-encrypting key...");
+    keys::change_passphrase(&profile, old, new.clone())?;
+    spinner.finish();
+
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        if keys::is_ready(&profile, sock.clone())? {
+            let spinner = term::spinner("Updating ssh-agent...");
+            keys::add(&profile, keys::pwhash(new), sock)?;
+            spinner.finish();
+        }
+    }
+    term::success!("Passphrase changed");
+    term::tip!("If you use `RAD_PASSPHRASE` in scripts, remember to update it.");
+
+    Ok(())
+}
+
+/// Get the key held by a hardware key to create a profile for, letting the user pick one if
+/// it holds several.
+fn hardware_key(hardware: &keys::Hardware) -> anyhow::Result<keys::AgentKey> {
+    let mut keys = hardware.keys()?;
+    let lines = keys.iter().map(|k| k.key.clone()).collect::<Vec<_>>();
+
+    let ix = match lines.len() {
+        0 => anyhow::bail!(term::tr!("no Ed25519 key found on {}", hardware)),
+        1 => 0,
+        _ => term::select_with_prompt("Which key do you want to use?", &lines, &lines[0])
+            .and_then(|line| lines.iter().position(|l| l == line))
+            .ok_or_else(|| exit::Error::Aborted("init aborted".to_owned()))?,
+    };
+    Ok(keys.swap_remove(ix))
+}
+
+pub fn hardware(hardware: &keys::Hardware, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let sock = keys::ssh_auth_sock()
+        .map_err(|_| anyhow::anyhow!("ssh-agent must be running to sign with a hardware key"))?;
+
+    term::headline(&term::tr!(
+        "🌱 Enrolling hardware key for {}",
+        term::display::Identity::new(&profile).styled()
+    ));
+    hardware.add()?;
+
+    if let keys::Hardware::Fido2 = hardware {
+        let key = hardware.keys()?.into_iter().next().ok_or_else(|| {
+            anyhow::anyhow!(term::tr!("no `ed25519-sk` key found on {}", hardware))
+        })?;
+
+        let workdir = git::Repository::discover(".")
+            .ok()
+            .and_then(|repo| repo.workdir().map(|w| w.to_path_buf()));
+        let (repo, scope) = match &workdir {
+            Some(workdir) => (Some(workdir.as_path()), "local"),
+            None => (None, "global"),
+        };
+        if repo.is_none()
+            && !term::confirm(
+                "Configure git to sign commits and tags with the FIDO2 key in all repositories?",
+            )?
+        {
+            term::tip!("To configure a single repository, run this command from its working copy.");
+            return Ok(());
+        }
+        for (key, value) in git::configure_agent_signing(repo, &key.key)? {
+            term::success!(
+                "Set {} to {} in the {} git config",
+                term::format::highlight(key),
+                term::format::tertiary(value),
+                scope
+            );
+        }
+        term::success!("Commits and tags are signed with the FIDO2 key, via ssh-agent");
+        term::info!("Radicle refs are still signed with the profile's key.");
+
+        return Ok(());
+    }
+
+    if !keys::is_ready(&profile, sock)? {
+        return Err(Error::WithHint {
+            err: anyhow::anyhow!("the hardware key doesn't hold your radicle signing key"),
+            hint: "hint: the token must hold the profile's Ed25519 key",
+        }
+        .into());
+    }
+    term::success!("Signing key provided by hardware key, via ssh-agent");
+
+    let key_file = profile.paths().keys_dir().join(keys::KEY_FILE);
+    if key_file.exists()
+        && term::abort("Remove the copy of the key on disk, so that it's only on the token?")?
+    {
+        keys::remove_key_file(&profile)?;
+        term::success!("Key removed from {}", key_file.display());
+        term::warning("Signing now requires the token, and `RAD_PASSPHRASE` can't be used anymore");
+    }
+
+    Ok(())
+}
+
+pub fn import(
+    input: &Path,
+    stdin: bool,
+    constraints: keys::AgentConstraints,
+) -> anyhow::Result<()> {
+    term::headline("Importing your 🌱 profile");
+
+    let passphrase = term::read_passphrase(stdin, false)?;
+    let spinner = term::spinner("Decrypting archive...");
+    let (profile, peer_id, urn) = match backup::import(profile::home(), input, passphrase.clone()) {
+        Ok(imported) => imported,
+        Err(err) => return Err(spinner.error(err)),
+    };
+    spinner.finish();
+
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        let spinner = term::spinner("Adding to ssh-agent...");
+        keys::add_constrained(&profile, keys::pwhash(passphrase), sock, constraints)?;
+        spinner.finish();
+    }
+
+    term::success!(
+        "Profile {} imported and activated.",
+        term::format::highlight(&profile.id().to_string())
+    );
+    term::blank();
+    term::info!(
+        "Your radicle Peer ID is {}.",
+        term::format::highlight(&peer_id.to_string())
+    );
+    term::info!(
+        "Your personal 🌱 URN is {}.",
+        term::format::highlight(&urn.to_string())
+    );
+    term::blank();
+    term::tip!(
+        "To get your projects back, clone them from a seed with {}.",
+        term::format::secondary("`rad clone`")
+    );
+
+    Ok(())
+}
+
+pub fn rotate_key(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let old = ctx.profile()?;
+
+    term::headline(&term::tr!(
+        "🌱 Rotating the key of {}",
+        term::display::Identity::new(&old).styled()
+    ));
+
+    let passphrase = term::read_passphrase(options.stdin, false)?;
+    let old_key =
+        keys::load_secret_key(&old, passphrase.clone()).context("invalid passphrase supplied")?;
+    let old_storage = keys::storage(&old, old_key.to_signer(&old)?)?;
+    let old_peer = *old_storage.peer_id();
+    let urn = person::local(&old_storage)?.urn();
+
+    let spinner = term::spinner("Creating your new 🌱 Ed25519 keypair...");
+    let key = SecretKey::new();
+    let (new, new_peer) = profile::create_with_key(
+        profile::home(),
+        keys::pwhash(passphrase.clone()),
+        key.clone(),
+    )?;
+    let new_storage = keys::storage(&new, key.to_signer(&new)?)?;
+
+    backup::copy_files(&old, &new)?;
+    fetch(
+        &old,
+        &new,
+        &[
+            "+refs/namespaces/*:refs/namespaces/*".to_owned(),
+            "+refs/rad/remotes/*:refs/rad/remotes/*".to_owned(),
+        ],
+    )?;
+    spinner.finish();
+
+    // A revision is only valid if signed by a quorum of both the previous and the new
+    // keys of the identity. We hold both keys, so we collect the signatures by merging
+    // the revisions back and forth between the old and the new storage.
+    let spinner = term::spinner("Adding the new key to your identity...");
+    person::set_delegations(
+        &old_storage,
+        [*old_peer.as_public_key(), *new_peer.as_public_key()],
+    )?;
+    fetch(&old, &new, &[identity_refspec(&urn, &old_peer)])?;
+
+    let person = person::merge(&new_storage, &urn, old_peer)?;
+    person::set_local(&new_storage, &person)?;
+    spinner.finish();
+
+    let spinner = term::spinner("Removing the old key from your identity...");
+    person::set_delegations(&new_storage, [*new_peer.as_public_key()])?;
+    fetch(&new, &old, &[identity_refspec(&urn, &new_peer)])?;
+    person::merge(&old_storage, &urn, new_peer)?;
+    fetch(&old, &new, &[identity_refspec(&urn, &old_peer)])?;
+
+    let person = person::merge(&new_storage, &urn, old_peer)?;
+    person::set_local(&new_storage, &person)?;
+    spinner.finish();
+
+    let spinner = term::spinner("Re-signing your refs with the new key...");
+    let monorepo = git::Repository::open_bare(new.paths().git_dir())?;
+    let head = monorepo
+        .find_reference(&format!("refs/namespaces/{}/refs/rad/id", urn.encode_id()))?
+        .target()
+        .ok_or_else(|| anyhow::anyhow!(term::tr!("identity {} has no revision", urn)))?;
+
+    Refs::update(&new_storage, &urn)?;
+    for (project, _, _) in project::list(&new_storage)? {
+        // Point the project's copy of your identity to the new revision.
+        let name = format!(
+            "refs/namespaces/{}/refs/rad/ids/{}",
+            project.encode_id(),
+            urn.encode_id()
+        );
+        if monorepo.find_reference(&name).is_ok() {
+            monorepo.reference(&name, head, true, "rad: rotate key")?;
+        }
+        Refs::update(&new_storage, &project)?;
+    }
+    spinner.finish();
+
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        let spinner = term::spinner("Updating ssh-agent...");
+        if keys::is_ready(&old, sock.clone())? {
+            keys::remove(&old, keys::pwhash(passphrase.clone()), sock.clone())?;
+        }
+        keys::add(&new, keys::pwhash(passphrase), sock)?;
+        spinner.finish();
+    }
+
+    term::success!(
+        "Key rotated. Profile {} created and activated.",
+        term::format::highlight(&new.id().to_string())
+    );
+    term::blank();
+    term::info!(
+        "Your new radicle Peer ID is {}.",
+        term::format::highlight(&new_peer.to_string())
+    );
+    term::info!(
+        "Your personal 🌱 URN is unchanged: {}.",
+        term::format::highlight(&urn.to_string())
+    );
+    term::blank();
+    term::tip!(
+        "To publish your updated identity, run {}, then sync your projects.",
+        term::format::secondary("`rad sync --self`")
+    );
+    term::tip!(
+        "The old profile {} is no longer needed once published.",
+        term::format::dim(old.id())
+    );
+
+    Ok(())
+}
+
+/// Fetch refs from the storage of one profile into the storage of another.
+fn fetch(
+    from: &profile::Profile,
+    to: &profile::Profile,
+    refspecs: &[String],
+) -> anyhow::Result<()> {
+    let mut args = vec![
+        "fetch".to_owned(),
+        "--quiet".to_owned(),
+        from.paths().git_dir().display().to_string(),
+    ];
+    args.extend(refspecs.iter().cloned());
+
+    git::git(to.paths().git_dir(), args).context("failed to copy refs between profiles")?;
+
+    Ok(())
+}
+
+/// Refspec fetching the identity refs of a peer into its remote.
+fn identity_refspec(urn: &Urn, peer: &PeerId) -> String {
+    format!(
+        "+refs/namespaces/{id}/refs/rad/*:refs/namespaces/{id}/refs/remotes/{peer}/rad/*",
+        id = urn.encode_id(),
+        peer = peer.default_encoding()
+    )
+}
+
 fn sanitize_name(name: String) -> anyhow::Result<String> {
     if name.contains(char::is_whitespace) {
         anyhow::bail!("Name cannot contain whitespaces");
@@ -281,6 +875,18 @@ fn sanitize_name(name: String) -> anyhow::Result<String> {
     Ok(name)
 }
 
+/// Format a duration in the largest whole unit, eg. `30m`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    for (unit, size) in [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60)] {
+        if secs % size == 0 {
+            return format!("{}{}", secs / size, unit);
+        }
+    }
+    format!("{}s", secs)
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -297,6 +903,14 @@ mod tests {
             stdin: false,
             name: Some(name.to_owned()),
             peer_id: None,
+            list: false,
+            switch: None,
+            change_passphrase: false,
+            hardware: None,
+            import: None,
+            rotate_key: false,
+            agent_ttl: None,
+            agent_confirm: false,
         }
     }
 
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use librad::git::storage::Storage;
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::alias::Aliases;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::patch::PatchId;
+use radicle_common::sshsig::{self, Signature};
+use radicle_common::{cobs, exit, fmt, git, keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "blame",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad blame <file> [--rev <rev>] [--lines <start>,<end>]
+
+    Shows which revision last modified each line of a file, like `git blame`, but
+    with the radicle identity of the commit author instead of its git author, as
+    well as the patch that introduced the line, if any.
+
+    A commit is attributed to a peer if it was signed with the peer's key. Unsigned
+    commits are attributed to the peer that published them, if the commit is only
+    found on one peer's branches, or if the peer's name matches the git author.
+    Otherwise, the git author is shown, dimmed. Signed commits are marked with `✓`.
+
+Options
+
+    --rev <rev>             Annotate the file as of the given revision (default: HEAD)
+    --lines <start>,<end>   Only annotate the given range of lines, eg. `10,20`
+    --help                  Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub path: PathBuf,
+    pub rev: Option<String>,
+    pub lines: Option<String>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut path: Option<PathBuf> = None;
+        let mut rev: Option<String> = None;
+        let mut lines: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("rev") => {
+                    rev = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("lines") => {
+                    lines = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if path.is_none() => {
+                    path = Some(PathBuf::from(val));
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                path: path.ok_or_else(|| anyhow!("a file to annotate must be provided"))?,
+                rev,
+                lines,
+            },
+            vec![],
+        ))
+    }
+}
+
+/// A line of the annotated file.
+struct Line {
+    commit: git::Oid,
+    number: usize,
+    content: String,
+}
+
+/// A commit that last modified some lines of the file.
+#[derive(Default)]
+struct Commit {
+    author: String,
+}
+
+/// A radicle peer that a commit can be attributed to.
+struct Peer {
+    id: PeerId,
+    name: String,
+    /// Names of the peer, to be compared with git author names.
+    names: Vec<String>,
+    /// Whether this is our own peer, whose branches are not under `remotes`.
+    local: bool,
+}
+
+/// Radicle identity a commit was attributed to.
+struct Attribution {
+    name: String,
+    signed: bool,
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let (urn, repo) = project::cwd().map_err(|_| Error::WithHint {
+        err: anyhow!("this command must be run within a project working copy"),
+        hint: "hint: checkout a project with `rad checkout`",
+    })?;
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found in local storage", urn))
+    })?;
+
+    let (lines, commits) = blame(&options)?;
+    let peers = peers(&proj, &storage)?;
+    let patches = patches(&cobs::store(&profile, &storage)?, &monorepo, &urn, &commits)?;
+
+    let mut attributions = HashMap::new();
+    for (oid, commit) in &commits {
+        if let Some(attribution) = attribute(*oid, commit, &peers, &repo, &monorepo, &urn) {
+            attributions.insert(*oid, attribution);
+        }
+    }
+
+    let mut table = term::Table::default();
+    for line in lines {
+        let commit = &commits[&line.commit];
+        let (author, signed) = match attributions.get(&line.commit) {
+            Some(a) => (term::format::tertiary(&a.name), a.signed),
+            None => (term::format::dim(&commit.author), false),
+        };
+        let patch = patches
+            .get(&line.commit)
+            .map(|id| term::format::secondary(fmt::cob(id)))
+            .unwrap_or_default();
+
+        table.push([
+            if line.commit.is_zero() {
+                term::format::dim("-------")
+            } else {
+                term::format::secondary(fmt::oid(&line.commit))
+            },
+            if signed {
+                term::format::positive("✓")
+            } else {
+                String::new()
+            },
+            author,
+            patch,
+            term::format::dim(line.number),
+            line.content,
+        ]);
+    }
+    table.render();
+
+    Ok(())
+}
+
+/// Run `git blame` on the file. Returns its lines, and the commits that last modified them.
+fn blame(options: &Options) -> anyhow::Result<(Vec<Line>, HashMap<git::Oid, Commit>)> {
+    let mut args = vec![String::from("blame"), String::from("--line-porcelain")];
+    if let Some(lines) = &options.lines {
+        args.push(format!("-L{}", lines));
+    }
+    if let Some(rev) = &options.rev {
+        args.push(rev.clone());
+    }
+    args.push(String::from("--"));
+    args.push(options.path.to_string_lossy().to_string());
+
+    let output = git::git(Path::new("."), args)?;
+    let mut lines = Vec::new();
+    let mut commits: HashMap<git::Oid, Commit> = HashMap::new();
+    let mut current: Option<(git::Oid, usize)> = None;
+
+    // With `--line-porcelain`, each line is preceded by a header with the commit,
+    // the line number, and the commit information.
+    for l in output.lines() {
+        if let Some(content) = l.strip_prefix('\t') {
+            if let Some((commit, number)) = current.take() {
+                lines.push(Line {
+                    commit,
+                    number,
+                    content: content.to_owned(),
+                });
+            }
+        } else if let Some((oid, _)) = current {
+            let commit = commits.entry(oid).or_default();
+
+            if let Some(author) = l.strip_prefix("author ") {
+                commit.author = author.to_owned();
+            }
+        } else {
+            let mut header = l.split_whitespace();
+            let oid = header.next().and_then(|s| git::Oid::from_str(s).ok());
+            let number = header.nth(1).and_then(|s| s.parse().ok());
+
+            if let (Some(oid), Some(number)) = (oid, number) {
+                commits.entry(oid).or_default();
+                current = Some((oid, number));
+            }
+        }
+    }
+    Ok((lines, commits))
+}
+
+/// Get the peers commits can be attributed to: ourselves, the tracked peers and the
+/// delegates of the project.
+fn peers(proj: &project::Metadata, storage: &Storage) -> anyhow::Result<Vec<Peer>> {
+    let aliases = Aliases::cwd();
+    let mut peers = Vec::new();
+    let mut infos = project::tracked(proj, storage)?;
+
+    for d in &proj.delegates {
+        let ids = match d {
+            project::Delegate::Direct { id } => vec![*id],
+            project::Delegate::Indirect { ids, .. } => ids.iter().copied().collect(),
+        };
+        for id in ids {
+            infos
+                .entry(id)
+                .or_insert_with(|| project::PeerInfo::get(&id, proj, storage));
+        }
+    }
+    let own = infos
+        .remove(storage.peer_id())
+        .unwrap_or_else(|| project::PeerInfo::get(storage.peer_id(), proj, storage));
+    peers.push(Peer {
+        id: own.id,
+        name: String::from("you"),
+        names: own.person.iter().map(|p| p.name.clone()).collect(),
+        local: true,
+    });
+
+    for info in infos.into_values() {
+        peers.push(Peer {
+            id: info.id,
+            name: aliases.name_or(&info.id, || info.name()),
+            names: info.person.iter().map(|p| p.name.clone()).collect(),
+            local: false,
+        });
+    }
+    Ok(peers)
+}
+
+/// Attribute a commit to a peer, using its signature, or the branches it was published on.
+fn attribute(
+    oid: git::Oid,
+    commit: &Commit,
+    peers: &[Peer],
+    repo: &git::Repository,
+    monorepo: &git::Repository,
+    urn: &Urn,
+) -> Option<Attribution> {
+    if oid.is_zero() {
+        // Uncommitted changes.
+        return None;
+    }
+    if let Some(peer) = signer(oid, peers, repo) {
+        return Some(Attribution {
+            name: peer.name.clone(),
+            signed: true,
+        });
+    }
+
+    let namespace = format!("refs/namespaces/{}/refs", urn.encode_id());
+    let publishers = peers
+        .iter()
+        .filter(|peer| {
+            let glob = if peer.local {
+                format!("{}/heads/*", namespace)
+            } else {
+                format!(
+                    "{}/remotes/{}/heads/*",
+                    namespace,
+                    peer.id.default_encoding()
+                )
+            };
+            published(monorepo, &glob, oid)
+        })
+        .collect::<Vec<_>>();
+
+    let peer = match publishers.as_slice() {
+        [peer] => Some(*peer),
+        _ => publishers
+            .iter()
+            .find(|p| p.names.iter().any(|n| n == &commit.author))
+            .copied(),
+    };
+    peer.map(|p| Attribution {
+        name: p.name.clone(),
+        signed: false,
+    })
+}
+
+/// Find the peer who signed a commit, if the commit has a valid SSH signature.
+fn signer<'a>(oid: git::Oid, peers: &'a [Peer], repo: &git::Repository) -> Option<&'a Peer> {
+    let (signature, data) = repo.extract_signature(&oid, None).ok()?;
+    let signature = Signature::from_armored(signature.as_str()?).ok()?;
+
+    if signature.namespace != sshsig::NAMESPACE_GIT || signature.verify(&data).is_err() {
+        return None;
+    }
+    peers.iter().find(|p| signature.is_signed_by(&p.id))
+}
+
+/// Check whether a commit is reachable from any of the references matching a glob.
+fn published(monorepo: &git::Repository, glob: &str, oid: git::Oid) -> bool {
+    let refs = match monorepo.references_glob(glob) {
+        Ok(refs) => refs,
+        Err(_) => return false,
+    };
+    refs.flatten()
+        .filter_map(|r| r.target())
+        .any(|head| head == oid || monorepo.graph_descendant_of(head, oid).unwrap_or(false))
+}
+
+/// Find the patches that introduced the given commits. When a commit is part of
+/// more than one patch, the latest patch is used.
+fn patches(
+    cobs: &cobs::Store,
+    monorepo: &git::Repository,
+    urn: &Urn,
+    commits: &HashMap<git::Oid, Commit>,
+) -> anyhow::Result<HashMap<git::Oid, PatchId>> {
+    let mut introduced = HashMap::new();
+
+    for (id, patch) in cobs.patches().all(urn)? {
+        for revision in patch.revisions.iter() {
+            let mut walk = monorepo.revwalk()?;
+
+            if walk.push(revision.oid.into()).is_err() || walk.hide(revision.base.into()).is_err() {
+                continue;
+            }
+            for oid in walk.flatten() {
+                if commits.contains_key(&oid) {
+                    introduced.insert(oid, id);
+                }
+            }
+        }
+    }
+    Ok(introduced)
+}
@@ -0,0 +1,239 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::tracking::git::tracking;
+use librad::PeerId;
+
+use radicle_common::alias::Aliases;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::block::{self, Blocklist};
+use radicle_common::profile::Profile;
+use radicle_common::{fmt, git, keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "block",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad block <peer-id> [--no-confirm]
+    rad block --remove <peer-id>
+    rad block --list
+
+    Blocking a peer tracks it in all projects with a tracking config that refuses its
+    data, so that none of it is fetched, and removes its refs from local storage. Its
+    `rad/` refs are kept, since they may be needed to verify a project. When run within
+    a project working copy, the peer's remotes and branches are removed from the working
+    copy as well, after confirmation.
+
+    Refs of blocked peers are still dropped if they are fetched, eg. from a seed over
+    HTTP, and the peer's patches, issues, reviews and comments are hidden.
+
+    The block list applies to all projects, and is stored in the profile's `blocked.toml`.
+
+Options
+
+    --list              List blocked peers
+    --remove            Unblock the given peer
+    --no-confirm        Don't ask for confirmation before removing refs and branches
+    --help              Print help
+"#,
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    Block { peer: PeerId, confirm: bool },
+    Unblock { peer: PeerId },
+    List,
+}
+
+/// Tool options.
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut peer: Option<PeerId> = None;
+        let mut list = false;
+        let mut remove = false;
+        let mut confirm = true;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("list") => {
+                    list = true;
+                }
+                Long("remove") => {
+                    remove = true;
+                }
+                Long("no-confirm") => {
+                    confirm = false;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if peer.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    if let Ok(val) = PeerId::from_str(&val) {
+                        peer = Some(val);
+                    } else {
+                        return Err(anyhow!(term::tr!("invalid <peer-id> '{}'", val)));
+                    }
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match (peer, list) {
+            (None, true) if !remove => Operation::List,
+            (Some(_), true) => anyhow::bail!("`--list` cannot be used with a <peer-id>"),
+            (Some(peer), false) if remove => Operation::Unblock { peer },
+            (Some(peer), false) => Operation::Block { peer, confirm },
+            _ => return Err(Error::Usage.into()),
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+
+    match options.op {
+        Operation::Block { peer, confirm } => {
+            block(peer, confirm, &profile)?;
+        }
+        Operation::Unblock { peer } => {
+            unblock(peer, &profile)?;
+
+            term::success!(
+                "Peer {} unblocked",
+                term::format::tertiary(fmt::peer(&peer))
+            );
+            term::tip!("To fetch the peer's refs again, track it with `rad track`.");
+        }
+        Operation::List => {
+            list(&Blocklist::load(&profile)?);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn block(peer: PeerId, confirm: bool, profile: &Profile) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+
+    if &peer == storage.peer_id() {
+        anyhow::bail!("you can't block yourself");
+    }
+    let mut blocklist = Blocklist::load(profile)?;
+    if blocklist.contains(&peer) {
+        anyhow::bail!(term::tr!("peer {} is already blocked", peer));
+    }
+
+    // In a working copy, untrack the peer first, so that its remotes and branches
+    // are removed along with its refs.
+    if let Ok((urn, repo)) = project::cwd() {
+        if let Some(proj) = project::get(&storage, &urn)? {
+            if project::tracked(&proj, &storage)?.contains_key(&peer) {
+                rad_untrack::execute(
+                    &urn,
+                    Some(&repo),
+                    rad_untrack::Options {
+                        peer: Some(peer.default_encoding()),
+                        confirm,
+                    },
+                    profile,
+                )?;
+            }
+        }
+    }
+
+    blocklist.insert(&peer);
+    blocklist.write(profile)?;
+
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let mut pruned = 0;
+
+    // Projects fetched later are handled when they are fetched.
+    for (urn, _, _) in project::list(&storage)? {
+        blocklist.track(&storage, &urn)?;
+        pruned += project::prune_refs(&monorepo, &urn, &peer, |_| true)?;
+    }
+
+    term::success!("Peer {} blocked", term::format::tertiary(fmt::peer(&peer)));
+    if pruned > 0 {
+        term::success!(
+            "Removed {} remaining ref(s) of the peer from storage",
+            pruned
+        );
+    }
+
+    Ok(())
+}
+
+pub fn unblock(peer: PeerId, profile: &Profile) -> anyhow::Result<()> {
+    let mut blocklist = Blocklist::load(profile)?;
+    if !blocklist.remove(&peer) {
+        anyhow::bail!(term::tr!("peer {} is not blocked", peer));
+    }
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+
+    // Only remove the tracking entries that refuse the peer's data, including those of
+    // projects that weren't fetched yet.
+    let urns = tracking::tracked(storage.read_only(), None)?
+        .filter_map(Result::ok)
+        .filter(|t| t.peer_id() == Some(peer) && block::is_blocked(t.config()))
+        .map(|t| t.urn().clone())
+        .collect::<Vec<_>>();
+
+    for urn in urns {
+        tracking::untrack(
+            &storage,
+            &urn,
+            peer,
+            tracking::UntrackArgs {
+                policy: tracking::policy::Untrack::Any,
+                prune: false,
+            },
+        )??;
+    }
+    blocklist.write(profile)?;
+
+    Ok(())
+}
+
+fn list(blocklist: &Blocklist) {
+    let aliases = Aliases::cwd();
+    let mut table = term::Table::default();
+
+    for peer in blocklist.peers() {
+        table.push([
+            term::format::tertiary(peer),
+            aliases
+                .get(&peer)
+                .map(term::format::highlight)
+                .unwrap_or_default(),
+        ]);
+    }
+
+    if blocklist.is_empty() {
+        term::info!("{}", term::format::italic("No peers blocked."));
+    } else {
+        table.render();
+    }
+}
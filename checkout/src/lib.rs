@@ -1,14 +1,17 @@
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use anyhow::anyhow;
 use anyhow::Context as _;
 
 use librad::git::Urn;
+use librad::PeerId;
 
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::nonempty::NonEmpty;
 use radicle_common::Interactive;
-use radicle_common::{fmt, keys, profile, project};
+use radicle_common::{exit, fmt, git, keys, profile, project, sync, tokio};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -18,17 +21,39 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad checkout <urn> [<option>...]
+    rad checkout [<urn>] [--peer <peer-id>] [<option>...]
+    rad checkout --peer <peer-id>
+
+    Creates a working copy of a project. The working copy is based on your own
+    default branch if you have one, or else on the default branch of the project's
+    delegate. With `--peer`, it is based on the default branch of the given peer
+    instead, eg. to review their fork. If the project was fetched partially, eg. with
+    `rad clone --filter`, the working copy is filtered in the same way.
+
+    If no URN is given, the project is picked among the projects in local storage,
+    as listed by `rad ls`, by typing part of its name.
+
+    With `--recurse-submodules`, the submodules of the project that are radicle projects,
+    ie. whose URL is a project URN, eg. `rad:git:<id>`, are checked out as well, at the
+    commit recorded in the project, and so on recursively. Submodule projects that are
+    not in local storage are fetched from the project's seeds first.
+
+    Within an existing working copy, `rad checkout --peer <peer-id>` sets up a remote
+    for the given peer, and creates a branch tracking the peer's default branch.
 
 Options
 
-    --no-confirm    Don't ask for confirmation during checkout
-    --help          Print help
+    --peer <peer-id>        Checkout the default branch of the given peer
+    --recurse-submodules    Checkout the submodules that are radicle projects, recursively
+    --no-confirm            Don't ask for confirmation during checkout
+    --help                  Print help
 "#,
 };
 
 pub struct Options {
-    pub urn: Urn,
+    pub urn: Option<Urn>,
+    pub peer: Option<PeerId>,
+    pub recurse_submodules: bool,
     pub interactive: Interactive,
 }
 
@@ -39,6 +64,8 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut urn = None;
+        let mut peer: Option<PeerId> = None;
+        let mut recurse_submodules = false;
         let mut interactive = Interactive::Yes;
 
         while let Some(arg) = parser.next()? {
@@ -46,6 +73,17 @@ impl Args for Options {
                 Long("no-confirm") => {
                     interactive = Interactive::No;
                 }
+                Long("recurse-submodules") => {
+                    recurse_submodules = true;
+                }
+                Long("peer") if peer.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let val =
+                        PeerId::from_str(&val).context(format!("invalid peer id '{}'", val))?;
+
+                    peer = Some(val);
+                }
                 Long("help") => return Err(Error::Help.into()),
                 Value(val) if urn.is_none() => {
                     let val = val.to_string_lossy();
@@ -59,7 +97,9 @@ impl Args for Options {
 
         Ok((
             Options {
-                urn: urn.ok_or_else(|| anyhow!("a project URN to checkout must be provided"))?,
+                urn,
+                peer,
+                recurse_submodules,
                 interactive,
             },
             vec![],
@@ -69,9 +109,15 @@ impl Args for Options {
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
+
+    if options.urn.is_none() {
+        if let Some(peer) = options.peer {
+            return checkout_peer(&peer, &profile);
+        }
+    }
     let path = execute(options, &profile)?;
 
-    term::headline(&format!(
+    term::headline(&term::tr!(
         "🌱 Project checkout successful under ./{}",
         term::format::highlight(path.file_name().unwrap_or_default().to_string_lossy())
     ));
@@ -82,28 +128,53 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<PathBuf> {
     let signer = term::signer(profile)?;
     let storage = keys::storage(profile, signer.clone())?;
-    let project = project::get(&storage, &options.urn)?
-        .context("project could not be found in local storage")?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => select(&storage)?
+            .ok_or_else(|| anyhow!("a project URN to checkout must be provided"))?,
+    };
+    let project =
+        project::get(&storage, &urn)?.context("project could not be found in local storage")?;
     let path = PathBuf::from(project.name.clone());
     let interactive = options.interactive;
 
     if path.exists() {
+        if options.peer.is_some() {
+            return Err(Error::WithHint {
+                err: anyhow!("the local path {:?} already exists", path.as_path()),
+                hint: "hint: to checkout the peer's branch in your existing working copy, run `rad checkout --peer <peer-id>` in it",
+            }
+            .into());
+        }
         anyhow::bail!("the local path {:?} already exists", path.as_path());
     }
 
-    term::headline(&format!(
+    term::headline(&term::tr!(
         "Initializing local checkout for 🌱 {} ({})",
-        term::format::highlight(&options.urn),
+        term::format::highlight(&urn),
         project.name,
     ));
 
+    // If a peer is given, we checkout its head, unless it's us.
     // If we have a local head, we should checkout our local "fork", so we don't specify
     // a peer.
     // If we *don't* have a local head, we have to checkout a delegate's head. If there is
     // only one delegate, the choice is easy.
-    let peer = if project::get_local_head(&storage, &options.urn, &project.default_branch)?
-        .is_some()
-    {
+    let peer = if let Some(peer) = options.peer.filter(|p| p != storage.peer_id()) {
+        if project::get_remote_head(&storage, &urn, &peer, &project.default_branch)
+            .ok()
+            .flatten()
+            .is_none()
+        {
+            return Err(remote_head_not_found(&project.default_branch, &peer));
+        }
+        term::success!(
+            "Remote {} branch found via {}...",
+            project.default_branch,
+            term::format::highlight(peer)
+        );
+        Some(peer)
+    } else if project::get_local_head(&storage, &urn, &project.default_branch)?.is_some() {
         term::success!("Local {} branch found...", project.default_branch);
         None
     } else {
@@ -127,7 +198,7 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
         &storage,
         profile.paths().clone(),
         signer.clone(),
-        &options.urn,
+        &urn,
         peer,
         path.clone(),
     ) {
@@ -175,5 +246,243 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
         }
     }
 
+    if options.recurse_submodules {
+        checkout_submodules(&repo, None, profile)?;
+    }
+
     Ok(path)
 }
+
+/// Checkout the submodules of a working copy that are radicle projects, recursively.
+/// Submodule projects that are not in local storage are first fetched from the given
+/// seed, or from the seeds of the profile.
+pub fn checkout_submodules(
+    repo: &git::Repository,
+    seed: Option<&sync::Seed<String>>,
+    profile: &profile::Profile,
+) -> anyhow::Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("the repository has no working directory"))?;
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer.clone())?;
+
+    for submodule in project::submodules(repo)? {
+        let path = workdir.join(&submodule.path);
+        let urn = submodule.urn.clone();
+
+        term::blank();
+        term::headline(&term::tr!(
+            "Checking out submodule {} ({})",
+            term::format::highlight(submodule.path.display()),
+            urn
+        ));
+
+        if project::get(&storage, &urn)?.is_none() {
+            let seeds = match seed {
+                Some(seed) => NonEmpty::new(seed.clone()),
+                None => term::sync::seeds(profile, &urn)?,
+            };
+            let rt = tokio::runtime::Runtime::new()?;
+            let result = term::sync::sync(
+                urn.clone(),
+                seeds,
+                sync::Mode::Fetch,
+                profile,
+                signer.clone(),
+                &rt,
+            )?;
+
+            term::sync::report(&result);
+        }
+        let proj = match project::get(&storage, &urn)? {
+            Some(proj) => proj,
+            None => {
+                term::warning(&term::tr!(
+                    "Skipping submodule {}: project {} could not be fetched",
+                    submodule.path.display(),
+                    urn
+                ));
+                continue;
+            }
+        };
+        let peer = if project::get_local_head(&storage, &urn, &proj.default_branch)?.is_some() {
+            None
+        } else {
+            match proj.remotes.iter().collect::<Vec<_>>()[..] {
+                [peer] => Some(*peer),
+                _ => {
+                    term::warning(&term::tr!(
+                        "Skipping submodule {}: the project has no single delegate to checkout",
+                        submodule.path.display()
+                    ));
+                    continue;
+                }
+            }
+        };
+
+        // The working copy's checkout leaves an empty directory in place of the submodule.
+        if path.exists() && fs::remove_dir(&path).is_err() {
+            term::warning(&term::tr!(
+                "Skipping submodule {}: the path already exists, and is not empty",
+                submodule.path.display()
+            ));
+            continue;
+        }
+
+        let spinner = term::spinner("Performing checkout...");
+        if let Err(err) = project::checkout(
+            &storage,
+            profile.paths().clone(),
+            signer.clone(),
+            &urn,
+            peer,
+            path.clone(),
+        ) {
+            return Err(spinner.error(err));
+        }
+        spinner.finish();
+
+        if let Some(head) = submodule.head {
+            if git::git(
+                &path,
+                ["checkout", "--quiet", "--detach", &head.to_string()],
+            )
+            .is_err()
+            {
+                term::warning(&term::tr!(
+                    "Commit {} of submodule {} was not found, the default branch is checked out instead",
+                    fmt::oid(&head),
+                    submodule.path.display()
+                ));
+            }
+        }
+        register_submodule(workdir, &submodule)?;
+
+        term::success!(
+            "Submodule {} checked out",
+            term::format::highlight(submodule.path.display())
+        );
+
+        let repo = git::Repository::open(&path)?;
+        checkout_submodules(&repo, seed, profile)?;
+    }
+
+    Ok(())
+}
+
+/// Register a submodule that was checked out as a standalone working copy, so that git
+/// manages it like any submodule: its URL is set to the project's URL, which is fetched
+/// from local storage, and its git directory is moved under the working copy's.
+fn register_submodule(workdir: &Path, submodule: &project::Submodule) -> anyhow::Result<()> {
+    let path = submodule.path.to_string_lossy();
+
+    git::git(workdir, ["submodule", "init", "--", &path])?;
+    git::git(
+        workdir,
+        [
+            "config",
+            &format!("submodule.{}.url", submodule.name),
+            &format!("rad://{}.git", submodule.urn.encode_id()),
+        ],
+    )?;
+    git::git(workdir, ["submodule", "absorbgitdirs", "--", &path])?;
+
+    Ok(())
+}
+
+/// Setup a remote and tracking branch for a peer in the working copy of the current directory.
+fn checkout_peer(peer: &PeerId, profile: &profile::Profile) -> anyhow::Result<()> {
+    let (urn, repo) = project::cwd().map_err(|_| Error::WithHint {
+        err: exit::Error::NotProject.into(),
+        hint: "hint: to create a new working copy, specify the project URN",
+    })?;
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer.clone())?;
+    let project =
+        project::get(&storage, &urn)?.context("project could not be found in local storage")?;
+
+    if peer == storage.peer_id() {
+        anyhow::bail!(
+            "you can't checkout your own peer; your branches are already in your working copy"
+        );
+    }
+    if project::get_remote_head(&storage, &urn, peer, &project.default_branch)
+        .ok()
+        .flatten()
+        .is_none()
+    {
+        return Err(remote_head_not_found(&project.default_branch, peer));
+    }
+    if let Some((remote, _)) = git::remotes(&repo)?.into_iter().find(|(_, p)| p == peer) {
+        anyhow::bail!(term::tr!(
+            "remote {} already exists for {}; run `git fetch {}` to update it",
+            remote,
+            fmt::peer(peer),
+            remote
+        ));
+    }
+
+    let name = if let Some(person) = project::person(&storage, urn.clone(), peer)? {
+        person.subject().name.to_string()
+    } else {
+        peer.default_encoding()
+    };
+    let setup = project::SetupRemote {
+        project: &project,
+        repo: &repo,
+        signer,
+        fetch: true,
+        upstream: true,
+    };
+    if let Some((remote, branch)) = setup.run(peer, &name, profile)? {
+        term::success!("Remote {} set", term::format::highlight(remote.name));
+        term::success!(
+            "Remote-tracking branch {} created for {}",
+            term::format::highlight(&branch),
+            term::format::tertiary(fmt::peer(peer))
+        );
+        term::tip!("To switch to it, run `git checkout {}`.", branch);
+    }
+
+    Ok(())
+}
+
+fn remote_head_not_found(branch: &impl std::fmt::Display, peer: &PeerId) -> anyhow::Error {
+    Error::WithHint {
+        err: anyhow!(term::tr!(
+            "the {} branch of {} was not found in local storage",
+            branch,
+            fmt::peer(peer)
+        )),
+        hint:
+            "hint: track the peer with `rad track`, and fetch its refs with `rad sync --fetch-peers`",
+    }
+    .into()
+}
+
+/// Let the user pick one of the projects in local storage.
+fn select<S>(storage: &S) -> anyhow::Result<Option<Urn>>
+where
+    S: AsRef<librad::git::storage::ReadOnly>,
+{
+    let mut projects = project::list(storage)?;
+    projects.sort_by(|(_, a, _), (_, b, _)| a.name.cmp(&b.name));
+
+    let items = projects
+        .iter()
+        .map(|(urn, meta, _)| {
+            format!(
+                "{} {} {}",
+                term::format::bold(&meta.name),
+                term::format::highlight(urn),
+                term::format::italic(&meta.description)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(
+        term::fuzzy_select("Which project do you want to checkout?", &items)
+            .map(|i| projects.swap_remove(i).0),
+    )
+}
@@ -0,0 +1,303 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::patch::{CheckStatus, Patch};
+use radicle_common::cobs::{self, Identifier};
+use radicle_common::{exit, fmt, git, keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "ci",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad ci run [--on-patch <id>]
+
+    Runs the CI command of the project on a clean checkout, in a temporary worktree of
+    the current working copy. The command is defined in `.radicle/ci.yml`, at the root
+    of the working copy, eg.
+
+        name: ci
+        command: cargo build && cargo test
+
+    The command is run with `sh -c`, at the root of the checkout. The name of the check
+    is optional, and defaults to `ci`.
+
+    By default, the command is run on the `HEAD` commit. With `--on-patch`, it is run
+    on the latest revision of the given patch instead, and the result is recorded as a
+    check on the revision, that is shown by `rad patch --list`. The commits of the
+    patch must have been fetched into the working copy, eg. with `rad remote add`.
+
+    The command fails if the check fails.
+
+Options
+
+    --on-patch <id>     Run the check on the latest revision of a patch, and record
+                        the result
+    --help              Print help
+"#,
+};
+
+/// Path of the CI configuration, relative to the root of the working copy.
+pub const CONFIG_PATH: &str = ".radicle/ci.yml";
+
+/// CI configuration of a project.
+#[derive(Debug, serde::Deserialize)]
+pub struct Config {
+    /// Name of the check.
+    #[serde(default = "Config::default_name")]
+    pub name: String,
+    /// Shell command to run.
+    pub command: String,
+}
+
+impl Config {
+    fn default_name() -> String {
+        String::from("ci")
+    }
+
+    /// Load the configuration of the given working copy.
+    pub fn load(workdir: &Path) -> anyhow::Result<Self> {
+        let path = workdir.join(CONFIG_PATH);
+        let contents = fs::read_to_string(&path).map_err(|_| Error::WithHint {
+            err: anyhow!(term::tr!(
+                "could not read CI configuration from {}",
+                path.display()
+            )),
+            hint: "hint: define the CI command of the project in `.radicle/ci.yml`",
+        })?;
+
+        serde_yaml::from_str(&contents)
+            .with_context(|| term::tr!("invalid CI configuration in {}", path.display()))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Run,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Run { patch: Option<Identifier> },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut patch: Option<Identifier> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("on-patch") if op == Some(OperationName::Run) => {
+                    let val = parser.value()?;
+                    let val = val
+                        .to_str()
+                        .ok_or_else(|| anyhow!("patch id specified is not UTF-8"))?;
+
+                    patch = Some(
+                        Identifier::from_str(val)
+                            .map_err(|_| anyhow!(term::tr!("invalid patch id '{}'", val)))?,
+                    );
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "r" | "run" => op = Some(OperationName::Run),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match op {
+            Some(OperationName::Run) => Operation::Run { patch },
+            None => {
+                return Err(Error::WithHint {
+                    err: anyhow!("an operation must be provided"),
+                    hint: "hint: run `rad ci --help` to see the supported operations",
+                }
+                .into())
+            }
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let (urn, repo) = project::cwd().map_err(|_| Error::WithHint {
+        err: exit::Error::NotProject.into(),
+        hint: "hint: run this command within a project working copy",
+    })?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working copy"))?;
+    let config = Config::load(workdir)?;
+
+    match options.op {
+        Operation::Run { patch: None } => {
+            let head = repo.head()?.peel_to_commit()?.id();
+            let (status, summary) = check(&repo, &config, head)?;
+
+            if status == CheckStatus::Failure {
+                anyhow::bail!(term::tr!("check {} failed: {}", config.name, summary));
+            }
+        }
+        Operation::Run {
+            patch: Some(identifier),
+        } => {
+            let profile = ctx.profile()?;
+            let signer = term::signer(&profile)?;
+            let storage = keys::storage(&profile, signer)?;
+            let cobs = cobs::store(&profile, &storage)?;
+            let patches = cobs.patches();
+            let (id, patch) = cobs.resolve::<Patch>(&urn, &identifier)?.ok_or_else(|| {
+                exit::Error::NotFound(term::tr!("patch '{}' not found", identifier))
+            })?;
+            let (revision_ix, revision) = patch.latest();
+            let oid = *revision.oid;
+
+            term::info!(
+                "Checking {} {} {}",
+                term::format::highlight(fmt::cob(&id)),
+                term::format::dim(format!("R{}", revision_ix)),
+                term::format::italic(&patch.title)
+            );
+            if repo.find_commit(oid).is_err() {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("commit {} of the patch is not in the working copy", oid)),
+                    hint: "hint: fetch the branches of the patch author, eg. with `rad remote add <peer> --fetch`",
+                }
+                .into());
+            }
+
+            let (status, summary) = check(&repo, &config, oid)?;
+            patches.check(
+                &urn,
+                &id,
+                revision_ix,
+                config.name.as_str(),
+                status,
+                revision.oid,
+                summary.as_str(),
+            )?;
+            term::success!(
+                "Check {} recorded on patch {}",
+                term::format::highlight(&config.name),
+                term::format::highlight(fmt::cob(&id))
+            );
+            if status == CheckStatus::Failure {
+                anyhow::bail!(term::tr!("check {} failed: {}", config.name, summary));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run the CI command on a checkout of the given commit, in a temporary worktree.
+/// Returns the outcome, with a summary of it.
+fn check(
+    repo: &git::Repository,
+    config: &Config,
+    oid: git::Oid,
+) -> anyhow::Result<(CheckStatus, String)> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working copy"))?;
+    let worktree = Worktree::add(workdir, oid)?;
+
+    term::info!(
+        "Running {} on {}: {}",
+        term::format::highlight(&config.name),
+        term::format::secondary(fmt::oid(&oid)),
+        term::format::dim(&config.command)
+    );
+    term::blank();
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(&config.command)
+        .current_dir(&worktree.path)
+        .env("RAD_CI_COMMIT", oid.to_string())
+        .status()
+        .with_context(|| term::tr!("could not run `{}`", config.command))?;
+
+    term::blank();
+
+    let result = if status.success() {
+        term::success!("Check {} passed", term::format::highlight(&config.name));
+
+        (
+            CheckStatus::Success,
+            format!("`{}` succeeded", config.command),
+        )
+    } else {
+        let summary = match status.code() {
+            Some(code) => format!("`{}` exited with code {}", config.command, code),
+            None => format!("`{}` was terminated", config.command),
+        };
+        term::info!(
+            "{} Check {} failed: {}",
+            term::format::negative("✗"),
+            term::format::highlight(&config.name),
+            summary
+        );
+
+        (CheckStatus::Failure, summary)
+    };
+    Ok(result)
+}
+
+/// A temporary worktree, removed when dropped.
+struct Worktree<'a> {
+    repo: &'a Path,
+    path: PathBuf,
+}
+
+impl<'a> Worktree<'a> {
+    /// Add a worktree with a detached checkout of the given commit.
+    fn add(repo: &'a Path, oid: git::Oid) -> anyhow::Result<Self> {
+        let path =
+            std::env::temp_dir().join(format!("rad-ci-{}-{}", fmt::oid(&oid), std::process::id()));
+        let worktree = path.to_string_lossy().to_string();
+
+        git::git(
+            repo,
+            ["worktree", "add", "--detach", &worktree, &oid.to_string()],
+        )
+        .context("could not check out commit")?;
+
+        Ok(Self { repo, path })
+    }
+}
+
+impl<'a> Drop for Worktree<'a> {
+    fn drop(&mut self) {
+        let path = self.path.to_string_lossy().to_string();
+
+        if git::git(self.repo, ["worktree", "remove", "--force", &path]).is_err() {
+            term::warning(&term::tr!("Failed to remove worktree {}", path));
+        }
+    }
+}
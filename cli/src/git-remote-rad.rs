@@ -1,17 +1,20 @@
 #![allow(clippy::extra_unused_lifetimes)]
 use librad::crypto::keystore::pinentry::SecUtf8;
+use librad::crypto::BoxedSigner;
 #[cfg(not(feature = "ethereum"))]
 use librad::git::local::url::LocalUrl;
 use librad::profile::{LnkHome, LNK_HOME};
 use link_identities::git::Urn;
 use radicle_git_helpers::remote_helper;
 
-use radicle_common::{keys, profile, signer::ToSigner as _};
+use radicle_common::profile::Profile;
+use radicle_common::{git, identity, keys, profile, project, signer::ToSigner as _, sync, tokio};
 
 use anyhow::anyhow;
 #[cfg(feature = "ethereum")]
 use futures_lite::future;
 
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::process;
 use std::str::FromStr;
@@ -26,16 +29,50 @@ enum Remote {
     Project {
         urn: Urn,
     },
+    /// A project on a seed, eg. `rad://<peer-id>@<host>[:<port>]/<project-id>`.
+    Seed {
+        seed: sync::Seed<String>,
+        urn: Urn,
+    },
 }
 
 /// Failure exit code.
 const EXIT_FAILURE: i32 = 1;
+/// URL scheme of project remotes.
+const URL_SCHEME: &str = "rad://";
+
+impl Remote {
+    /// Parse a seed remote URL, ie. a URL with a seed address, of the form
+    /// `<peer-id>@<host>[:<port>]`, followed by a project id. Returns `None` if the URL
+    /// has no seed address.
+    fn from_seed_url(input: &str) -> Option<anyhow::Result<Self>> {
+        let (addr, id) = input.strip_prefix(URL_SCHEME)?.split_once('/')?;
+        if !addr.contains('@') {
+            return None;
+        }
+        let id = id.trim_end_matches('/').trim_end_matches(".git");
+
+        Some(
+            sync::Seed::from_str(addr)
+                .map_err(|_| anyhow!("Invalid seed address {:?}", addr))
+                .and_then(|seed| {
+                    let urn = Urn::try_from_id(id)
+                        .map_err(|_| anyhow!("Invalid project identifier {:?}", id))?;
+
+                    Ok(Self::Seed { seed, urn })
+                }),
+        )
+    }
+}
 
 impl FromStr for Remote {
     type Err = anyhow::Error;
 
     #[cfg(not(feature = "ethereum"))]
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(remote) = Self::from_seed_url(input) {
+            return remote;
+        }
         let url: LocalUrl = LocalUrl::from_str(input)?;
 
         Ok(Self::Project { urn: url.urn })
@@ -47,6 +84,9 @@ impl FromStr for Remote {
         use anyhow::Context as _;
         use ethers::types::{Address, NameOrAddress};
 
+        if let Some(remote) = Self::from_seed_url(input) {
+            return remote;
+        }
         if let Ok(url) = url::Url::parse(input) {
             if url.scheme() != ethereum::URL_SCHEME {
                 bail!("Invalid URL scheme {:?}", url.scheme());
@@ -142,15 +182,8 @@ fn run(remote: Remote) -> anyhow::Result<()> {
         }
         Remote::Project { urn: _urn } => {
             let profile = profile::default()?;
-            let signer = if let Ok(sock) = keys::ssh_auth_sock() {
-                sock.to_signer(&profile)?
-            } else if let Ok(pass) = env::var(keys::RAD_PASSPHRASE) {
-                keys::load_secret_key(&profile, SecUtf8::from(pass))?.to_signer(&profile)?
-            } else {
-                fatal(anyhow!("no signers found: ssh-agent is not running"));
-            };
             let config = remote_helper::Config {
-                signer: Some(signer),
+                signer: Some(signer(&profile)?),
             };
 
             // This is a workaround because the remote helper library
@@ -161,7 +194,152 @@ fn run(remote: Remote) -> anyhow::Result<()> {
             }
             remote_helper::run(config)
         }
+        Remote::Seed { seed, urn } => {
+            let profile = profile::default()?;
+            let signer = signer(&profile)?;
+            let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+            let signed_refs = format!("refs/namespaces/{}/refs/rad/signed_refs", urn.encode_id());
+
+            // Fetch the project from the seed first, so that the monorepo is up to date
+            // with the seed, whether git is about to fetch from, or push to it.
+            eprintln!("Fetching {} from {}...", urn, seed.addrs);
+            sync_seed(&seed, &urn, sync::Mode::Fetch, &profile, signer.clone())?;
+
+            let storage = profile::read_only(&profile)?;
+            if sync::refs::adopt(&storage, &monorepo, &urn)?.is_some() {
+                eprintln!("Adopted project identity from {}", seed.addrs);
+            }
+            adopt_default_branch(&profile, &monorepo, &urn)?;
+
+            let before = monorepo.refname_to_id(&signed_refs).ok();
+
+            // Then, let the local remote helper transfer objects between the working copy
+            // and the monorepo. Its standard streams are those of this process, so that it
+            // speaks to git directly.
+            let remote = env::args().nth(1).unwrap_or_default();
+            let status = process::Command::new(env::current_exe()?)
+                .arg(remote)
+                .arg(format!("{}{}.git", URL_SCHEME, urn.encode_id()))
+                .status()?;
+
+            if !status.success() {
+                process::exit(status.code().unwrap_or(EXIT_FAILURE));
+            }
+
+            // Finally, if refs were pushed, our signed refs were updated, and are pushed on
+            // to the seed.
+            if monorepo.refname_to_id(&signed_refs).ok() != before {
+                eprintln!("Pushing {} to {}...", urn, seed.addrs);
+                sync_seed(&seed, &urn, sync::Mode::Push, &profile, signer)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Get a signer, from ssh-agent, or from the secret key, if the passphrase is set.
+fn signer(profile: &Profile) -> anyhow::Result<BoxedSigner> {
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        Ok(sock.to_signer(profile)?)
+    } else if let Ok(pass) = env::var(keys::RAD_PASSPHRASE) {
+        Ok(keys::load_secret_key(profile, SecUtf8::from(pass))?.to_signer(profile)?)
+    } else {
+        Err(anyhow!("no signers found: ssh-agent is not running"))
+    }
+}
+
+/// Sync a project with a single seed. Fails if the seed can't be reached, or if syncing
+/// with it failed.
+fn sync_seed(
+    seed: &sync::Seed<String>,
+    urn: &Urn,
+    mode: sync::Mode,
+    profile: &Profile,
+    signer: BoxedSigner,
+) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let settings = sync::Settings::load(profile);
+    let results = rt.block_on(async {
+        let (seeds, _errors) = sync::Seeds::resolve(std::iter::once(seed)).await;
+        let client = sync::client(signer, profile).await?;
+
+        Ok::<_, anyhow::Error>(
+            sync::sync(&client, profile, urn.clone(), seeds, mode, settings, |_| {}).await,
+        )
+    })?;
+    let result = results
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("seed {} could not be resolved", seed.addrs))?;
+
+    if let Some(Err(err)) = result.fetch {
+        return Err(anyhow!("fetch from {} failed: {}", seed.addrs, err));
+    }
+    if let Some(Err(err)) = result.push {
+        return Err(anyhow!("push to {} failed: {}", seed.addrs, err));
+    }
+    Ok(())
+}
+
+/// Create our copy of the project's default branch from its delegates', if we have none,
+/// so that there is a branch to clone. The branch is only adopted at a head that a
+/// quorum of delegates agree on; branches of other peers are never adopted.
+fn adopt_default_branch(
+    profile: &Profile,
+    monorepo: &git::Repository,
+    urn: &Urn,
+) -> anyhow::Result<()> {
+    let storage = profile::read_only(profile)?;
+    let proj = match project::get(&storage, urn)? {
+        Some(proj) => proj,
+        None => return Err(anyhow!("project {} was not found on the seed", urn)),
+    };
+    let branch = proj.default_branch.to_string();
+
+    if project::get_local_head(&storage, urn, &branch)?.is_some() {
+        return Ok(());
+    }
+
+    // Each delegate votes once for each head its devices have the branch at.
+    let mut votes: HashMap<git::Oid, usize> = HashMap::new();
+    for delegate in &proj.delegates {
+        let heads = proj
+            .remotes
+            .iter()
+            .filter(|peer| delegate.contains(peer))
+            .filter_map(|peer| {
+                project::get_remote_head(&storage, urn, peer, &branch)
+                    .ok()
+                    .flatten()
+            })
+            .collect::<HashSet<_>>();
+
+        for head in heads {
+            *votes.entry(head).or_default() += 1;
+        }
     }
+    let quorum = identity::quorum(proj.delegates.len());
+    let head = votes
+        .into_iter()
+        .find(|(_, n)| *n >= quorum)
+        .map(|(head, _)| head)
+        .ok_or_else(|| {
+            anyhow!(
+                "no quorum of delegates of {} agree on the head of '{}': \
+                 check out the branch of a delegate with `rad checkout --peer`",
+                urn,
+                branch
+            )
+        })?;
+
+    monorepo.reference(
+        &format!("refs/namespaces/{}/refs/heads/{}", urn.encode_id(), branch),
+        head,
+        false,
+        "git-remote-rad: default branch of the project delegates",
+    )?;
+
+    Ok(())
 }
 
 #[cfg(feature = "ethereum")]
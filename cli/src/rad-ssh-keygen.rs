@@ -0,0 +1,122 @@
+//! Drop-in replacement for `ssh-keygen`, installed as git's `gpg.ssh.program` by
+//! `rad git configure-signing`.
+//!
+//! Signing requests (`-Y sign`) for the radicle key are handled by signing with the
+//! radicle key, via ssh-agent or the key file. All other requests, eg. signature
+//! verification, are passed on to `ssh-keygen`.
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process;
+
+use anyhow::anyhow;
+
+use radicle_common::{keys, profile, sshsig};
+use radicle_terminal as term;
+
+/// Failure exit code.
+const EXIT_FAILURE: i32 = 1;
+
+/// A signing request, as made by git.
+struct Request {
+    namespace: String,
+    key: Option<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+fn fatal(err: anyhow::Error) -> ! {
+    eprintln!("rad-ssh-keygen: {}", err);
+    process::exit(EXIT_FAILURE);
+}
+
+fn main() {
+    let args = env::args_os().skip(1).collect::<Vec<_>>();
+
+    match parse(&args) {
+        Some(request) => match sign(request) {
+            Ok(true) => {}
+            Ok(false) => passthrough(&args),
+            Err(err) => fatal(err),
+        },
+        None => passthrough(&args),
+    }
+}
+
+/// Run `ssh-keygen` with the given arguments, and exit with its exit code.
+fn passthrough(args: &[OsString]) -> ! {
+    match process::Command::new("ssh-keygen").args(args).status() {
+        Ok(status) => process::exit(status.code().unwrap_or(EXIT_FAILURE)),
+        Err(err) => fatal(anyhow!(term::tr!("failed to run `ssh-keygen`: {}", err))),
+    }
+}
+
+/// Parse a signing request. Returns `None` for other requests.
+fn parse(args: &[OsString]) -> Option<Request> {
+    let mut args = args.iter();
+    let mut sign = false;
+    let mut namespace = None;
+    let mut key = None;
+    let mut files = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("-Y") => sign = args.next()? == "sign",
+            Some("-n") => namespace = Some(args.next()?.to_string_lossy().into_owned()),
+            Some("-f") => key = Some(PathBuf::from(args.next()?)),
+            Some("-O") => {
+                args.next()?;
+            }
+            Some("-U") | Some("-q") => {}
+            Some(flag) if flag.starts_with('-') => return None,
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    if sign {
+        Some(Request {
+            namespace: namespace?,
+            key,
+            files,
+        })
+    } else {
+        None
+    }
+}
+
+/// Sign the request with the radicle key. Returns `false` if the request is for
+/// another key.
+fn sign(request: Request) -> anyhow::Result<bool> {
+    let profile = profile::default()?;
+    let peer = *profile::read_only(&profile)?.peer_id();
+
+    if let Some(path) = &request.key {
+        // Git passes the configured signing key, either as-is or as a file holding
+        // the public key. Only sign when it's the radicle key.
+        let ssh_key = keys::to_ssh_key(&peer)?;
+        let key = fs::read_to_string(path).unwrap_or_default();
+
+        if !key.trim().starts_with(&ssh_key) {
+            return Ok(false);
+        }
+    }
+    let signer = term::signer(&profile)?;
+
+    if request.files.is_empty() {
+        let mut message = Vec::new();
+        io::stdin().read_to_end(&mut message)?;
+
+        let signature = sshsig::sign(&signer, &peer, &request.namespace, &message)?;
+        io::stdout().write_all(signature.as_bytes())?;
+    }
+    for file in &request.files {
+        let message = fs::read(file)?;
+        let signature = sshsig::sign(&signer, &peer, &request.namespace, &message)?;
+        let mut path = file.clone().into_os_string();
+
+        path.push(".sig");
+        fs::write(&path, signature)?;
+    }
+    Ok(true)
+}
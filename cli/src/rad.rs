@@ -1,10 +1,13 @@
+use std::env;
 use std::ffi::OsString;
-use std::{io::ErrorKind, iter, process};
+use std::path::Path;
+use std::{iter, process};
 
 use anyhow::anyhow;
 use rad_help::*;
-use radicle_common::profile;
+use radicle_common::{args, exit, offline, plugin, profile, timing};
 use radicle_terminal as term;
+use radicle_terminal::verbosity::Verbosity;
 
 pub const NAME: &str = "rad";
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -24,6 +27,7 @@ fn main() {
         Err(err) => {
             if let Some(err) = err {
                 radicle_terminal::error(&format!("Error: rad: {}", err));
+                process::exit(exit::code(&err).into());
             }
             process::exit(1);
         }
@@ -35,6 +39,7 @@ fn parse_args() -> anyhow::Result<Command> {
 
     let mut parser = lexopt::Parser::from_env();
     let mut command = None;
+    let mut verbosity = None;
 
     while let Some(arg) = parser.next()? {
         match arg {
@@ -44,6 +49,42 @@ fn parse_args() -> anyhow::Result<Command> {
             Long("version") => {
                 command = Some(Command::Version);
             }
+            Long("profile") if command.is_none() => {
+                let name = parser.value()?;
+
+                // Commands load the profile via `profile::default`, which honors this.
+                env::set_var(profile::RAD_PROFILE, name);
+            }
+            Long("color") if command.is_none() => {
+                let value = parser.value()?;
+                let color: term::format::Color = args::parse_value("color", value)?;
+
+                // Commands decide whether to color output via `term::format::init_colors`,
+                // which honors this.
+                color.set();
+            }
+            Long("yes") | Short('y') | Long("no-confirm") if command.is_none() => {
+                // Confirmations, via `term::ask`, honor this.
+                env::set_var(term::io::RAD_NO_CONFIRM, "1");
+            }
+            Long("ascii") if command.is_none() => {
+                // Output via `term` functions honors this, see `term::ascii`.
+                env::set_var(term::ascii::RAD_ASCII, "1");
+            }
+            Long("offline") if command.is_none() => {
+                // The configuration, and so `offline::is_enabled`, honors this.
+                env::set_var(offline::RAD_OFFLINE, "1");
+            }
+            Long("quiet") | Short('q') if command.is_none() => {
+                verbosity = Some(Verbosity::Quiet);
+            }
+            Long("verbose") | Short('v') if command.is_none() => {
+                verbosity = Some(verbosity.unwrap_or(Verbosity::Normal).more());
+            }
+            Long("json") | Long("porcelain") if command.is_none() => {
+                // Commands check the output mode via `term::output::mode`, which honors this.
+                term::output::set(term::output::Mode::Json);
+            }
             Value(val) if command.is_none() => {
                 if val == *"." {
                     command = Some(Command::Other(vec![OsString::from("inspect")]));
@@ -59,6 +100,11 @@ fn parse_args() -> anyhow::Result<Command> {
         }
     }
 
+    if let Some(verbosity) = verbosity {
+        // Commands check the verbosity via `Verbosity::get`, which honors this.
+        verbosity.set();
+    }
+
     Ok(command.unwrap_or_else(|| Command::Other(vec![])))
 }
 
@@ -79,6 +125,8 @@ fn print_help() -> anyhow::Result<()> {
 }
 
 fn run(command: Command) -> Result<(), Option<anyhow::Error>> {
+    term::format::init_colors();
+
     match command {
         Command::Version => {
             print_version();
@@ -111,6 +159,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "archive" => {
+            term::run_command_args::<rad_archive::Options, _>(
+                rad_archive::HELP,
+                "Archive",
+                rad_archive::run,
+                args.to_vec(),
+            );
+        }
         "auth" => {
             term::run_command_args::<rad_auth::Options, _>(
                 rad_auth::HELP,
@@ -119,6 +175,22 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "blame" => {
+            term::run_command_args::<rad_blame::Options, _>(
+                rad_blame::HELP,
+                "Blame",
+                rad_blame::run,
+                args.to_vec(),
+            );
+        }
+        "block" => {
+            term::run_command_args::<rad_block::Options, _>(
+                rad_block::HELP,
+                "Blocking",
+                rad_block::run,
+                args.to_vec(),
+            );
+        }
         "checkout" => {
             term::run_command_args::<rad_checkout::Options, _>(
                 rad_checkout::HELP,
@@ -127,6 +199,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "ci" => {
+            term::run_command_args::<rad_ci::Options, _>(
+                rad_ci::HELP,
+                "CI",
+                rad_ci::run,
+                args.to_vec(),
+            );
+        }
         "clone" => {
             term::run_command_args::<rad_clone::Options, _>(
                 rad_clone::HELP,
@@ -135,6 +215,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "cob" => {
+            term::run_command_args::<rad_cob::Options, _>(
+                rad_cob::HELP,
+                "Command",
+                rad_cob::run,
+                args.to_vec(),
+            );
+        }
         "comment" => {
             term::run_command_args::<rad_comment::Options, _>(
                 rad_comment::HELP,
@@ -143,6 +231,44 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "completions" => {
+            term::run_command_args::<rad_completions::Options, _>(
+                rad_completions::HELP,
+                "Completions",
+                |options, ctx: fn() -> anyhow::Result<profile::Profile>| {
+                    rad_completions::run(options, ctx, ALL)
+                },
+                args.to_vec(),
+            );
+        }
+        "config" => {
+            term::run_command_args::<rad_config::Options, _>(
+                rad_config::HELP,
+                "Config",
+                rad_config::run,
+                args.to_vec(),
+            );
+        }
+        "debug" => {
+            // Nb. Not listed in `rad help`, as it is only meant for diagnosing issues.
+            return run_debug(args);
+        }
+        "delegate" => {
+            term::run_command_args::<rad_delegate::Options, _>(
+                rad_delegate::HELP,
+                "Delegate",
+                rad_delegate::run,
+                args.to_vec(),
+            );
+        }
+        "doctor" => {
+            term::run_command_args::<rad_doctor::Options, _>(
+                rad_doctor::HELP,
+                "Doctor",
+                rad_doctor::run,
+                args.to_vec(),
+            );
+        }
         "edit" => {
             term::run_command_args::<rad_edit::Options, _>(
                 rad_edit::HELP,
@@ -160,6 +286,54 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "export" => {
+            term::run_command_args::<rad_export::Options, _>(
+                rad_export::HELP,
+                "Export",
+                rad_export::run,
+                args.to_vec(),
+            );
+        }
+        "feed" => {
+            term::run_command_args::<rad_feed::Options, _>(
+                rad_feed::HELP,
+                "Feed",
+                rad_feed::run,
+                args.to_vec(),
+            );
+        }
+        "fork" => {
+            term::run_command_args::<rad_fork::Options, _>(
+                rad_fork::HELP,
+                "Fork",
+                rad_fork::run,
+                args.to_vec(),
+            );
+        }
+        "gc" => {
+            term::run_command_args::<rad_gc::Options, _>(
+                rad_gc::HELP,
+                "Gc",
+                rad_gc::run,
+                args.to_vec(),
+            );
+        }
+        "git" => {
+            term::run_command_args::<rad_git::Options, _>(
+                rad_git::HELP,
+                "Git",
+                rad_git::run,
+                args.to_vec(),
+            );
+        }
+        "github" => {
+            term::run_command_args::<rad_github::Options, _>(
+                rad_github::HELP,
+                "GitHub",
+                rad_github::run,
+                args.to_vec(),
+            );
+        }
         #[cfg(feature = "ethereum")]
         "gov" => {
             term::run_command_args::<rad_gov::Options, _>(
@@ -177,6 +351,22 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "hooks" => {
+            term::run_command_args::<rad_hooks::Options, _>(
+                rad_hooks::HELP,
+                "Hooks",
+                rad_hooks::run,
+                args.to_vec(),
+            );
+        }
+        "id" => {
+            term::run_command_args::<rad_id::Options, _>(
+                rad_id::HELP,
+                "Identity",
+                rad_id::run,
+                args.to_vec(),
+            );
+        }
         "init" => {
             term::run_command_args::<rad_init::Options, _>(
                 rad_init::HELP,
@@ -217,6 +407,30 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "mirror" => {
+            term::run_command_args::<rad_mirror::Options, _>(
+                rad_mirror::HELP,
+                "Mirror",
+                rad_mirror::run,
+                args.to_vec(),
+            );
+        }
+        "notify" => {
+            term::run_command_args::<rad_notify::Options, _>(
+                rad_notify::HELP,
+                "Notify",
+                rad_notify::run,
+                args.to_vec(),
+            );
+        }
+        "org" => {
+            term::run_command_args::<rad_org::Options, _>(
+                rad_org::HELP,
+                "Org",
+                rad_org::run,
+                args.to_vec(),
+            );
+        }
         "patch" => {
             term::run_command_args::<rad_patch::Options, _>(
                 rad_patch::HELP,
@@ -249,6 +463,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "release" => {
+            term::run_command_args::<rad_release::Options, _>(
+                rad_release::HELP,
+                "Release",
+                rad_release::run,
+                args.to_vec(),
+            );
+        }
         "remote" => {
             term::run_command_args::<rad_remote::Options, _>(
                 rad_remote::HELP,
@@ -273,6 +495,22 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "search" => {
+            term::run_command_args::<rad_search::Options, _>(
+                rad_search::HELP,
+                "Search",
+                rad_search::run,
+                args.to_vec(),
+            );
+        }
+        "seed" => {
+            term::run_command_args::<rad_seed::Options, _>(
+                rad_seed::HELP,
+                "Command",
+                rad_seed::run,
+                args.to_vec(),
+            );
+        }
         "self" => {
             term::run_command_args::<rad_self::Options, _>(
                 rad_self::HELP,
@@ -281,6 +519,22 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "serve" => {
+            term::run_command_args::<rad_serve::Options, _>(
+                rad_serve::HELP,
+                "Serve",
+                rad_serve::run,
+                args.to_vec(),
+            );
+        }
+        "setup" => {
+            term::run_command_args::<rad_setup::Options, _>(
+                rad_setup::HELP,
+                "Setup",
+                rad_setup::run,
+                args.to_vec(),
+            );
+        }
         "sync" => {
             term::run_command_args::<rad_sync::Options, _>(
                 rad_sync::HELP,
@@ -297,6 +551,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "tui" => {
+            term::run_command_args::<rad_tui::Options, _>(
+                rad_tui::HELP,
+                "TUI",
+                rad_tui::run,
+                args.to_vec(),
+            );
+        }
         "untrack" => {
             term::run_command_args::<rad_untrack::Options, _>(
                 rad_untrack::HELP,
@@ -305,25 +567,87 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "verify" => {
+            term::run_command_args::<rad_verify::Options, _>(
+                rad_verify::HELP,
+                "Verification",
+                rad_verify::run,
+                args.to_vec(),
+            );
+        }
+        "workspace" => {
+            term::run_command_args::<rad_workspace::Options, _>(
+                rad_workspace::HELP,
+                "Workspace",
+                rad_workspace::run,
+                args.to_vec(),
+            );
+        }
         _ => {
-            let exe = format!("{}-{}", NAME, exe);
-            let status = process::Command::new(exe.clone()).args(args).status();
+            // Commands that aren't built in are looked up in `PATH`, see `plugin`.
+            let path = plugin::find(exe).ok_or_else(|| {
+                Some(anyhow!(term::tr!(
+                    "command `{}` not found, see `rad help` for the available commands",
+                    exe
+                )))
+            })?;
+            let mut command = process::Command::new(&path);
+            command
+                .args(args)
+                .envs(plugin::context())
+                .env(plugin::RAD_VERSION, VERSION);
 
-            match status {
-                Ok(status) => {
-                    if !status.success() {
-                        return Err(None);
-                    }
-                }
-                Err(err) => {
-                    if let ErrorKind::NotFound = err.kind() {
-                        return Err(Some(anyhow!("command `{}` not found", exe)));
-                    } else {
-                        return Err(Some(err.into()));
-                    }
-                }
-            }
+            return Err(Some(run_external(command, &path)));
         }
     }
     Ok(())
 }
+
+/// Run `rad debug profile <command> [<arg>...]`: run a built-in command, and report the
+/// time it spent in storage reads, collaborative object parsing and network requests,
+/// see `timing`.
+fn run_debug(args: &[OsString]) -> Result<(), Option<anyhow::Error>> {
+    const USAGE: &str = "usage: rad debug profile <command> [<arg>...]";
+
+    let (op, args) = args.split_first().ok_or_else(|| Some(anyhow!(USAGE)))?;
+    if op != "profile" {
+        return Err(Some(anyhow!(term::tr!(
+            "unknown operation '{}', {}",
+            op.to_string_lossy(),
+            USAGE
+        ))));
+    }
+    let exe = args
+        .first()
+        .and_then(|exe| exe.to_str())
+        .ok_or_else(|| Some(anyhow!(USAGE)))?;
+    if !ALL.iter().any(|help| help.name == exe) {
+        return Err(Some(anyhow!(term::tr!(
+            "`{}` is not a built-in command, only built-in commands can be profiled",
+            exe
+        ))));
+    }
+    timing::enable();
+
+    run_other(exe, &args[1..])
+}
+
+/// Run an external command in place of `rad`. Only returns if it couldn't be run.
+#[cfg(unix)]
+fn run_external(mut command: process::Command, path: &Path) -> anyhow::Error {
+    use std::os::unix::process::CommandExt as _;
+
+    let err = command.exec();
+    anyhow!(term::tr!("could not run `{}`: {}", path.display(), err))
+}
+
+/// Run an external command, and exit with its exit code. Only returns if it couldn't
+/// be run.
+#[cfg(not(unix))]
+fn run_external(mut command: process::Command, path: &Path) -> anyhow::Error {
+    match command.status() {
+        // Nb. The command's exit code is passed on, for scripts.
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(err) => anyhow!(term::tr!("could not run `{}`: {}", path.display(), err)),
+    }
+}
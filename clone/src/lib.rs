@@ -1,17 +1,18 @@
 #![allow(clippy::or_fun_call)]
 use std::convert::TryFrom;
 use std::ffi::OsString;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::anyhow;
-use librad::git::tracking;
 use librad::git::Urn;
 use url::Url;
 
-use radicle_common::args::{Args, Error, Help};
+use radicle_common::args::{self, Args, Error, Help};
+use radicle_common::project::TrackingPolicy;
 use radicle_common::seed;
 use radicle_common::Interactive;
-use radicle_common::{git, identity, keys, profile, project, sync};
+use radicle_common::{exit, git, identity, keys, profile, project, sync};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -23,11 +24,43 @@ Usage
 
     rad clone <urn | url> [--seed <addr>] [<option>...]
 
+    The <url> may be the URL of a project on a seed, eg. `https://<seed>/<id>`,
+    `rad://<seed>/<id>`, or a web URL, eg. `https://app.radicle.xyz/seeds/<seed>/<urn>`.
+    The seed's peer id is queried from the seed, unless given in the URL, eg.
+    `rad://<peer-id>@<seed>/<id>`. The seed is saved as the default seed of the project,
+    and the project delegates are tracked. Other URLs are cloned as git repositories.
+
+    The peers tracked, and thus fetched when syncing the project, are decided by the
+    tracking policy given with `--track`, which is saved in the working copy:
+
+        delegates-only  Track the project delegates, and peers tracked with `rad track` (default)
+        all             Track all peers
+        manual          Only track peers tracked with `rad track`
+
+    With `--transport http`, the project is fetched from the seed's HTTP git endpoint,
+    for networks where the seed's peer port is blocked. Over HTTP, large projects can
+    be cloned partially: `--filter <filter>`, eg. `blob:none`, leaves out the objects
+    matched by the filter, which are then fetched from the seed on demand. The working
+    copy is as partial as the clone. See `rad sync --help` for the supported filters.
+
+    With `--recurse-submodules`, the submodules of the project that are radicle projects,
+    ie. whose URL is a project URN, eg. `rad:git:<id>`, are cloned from the seed as well,
+    and checked out at the commit recorded in the project, recursively.
+
+    Projects on a seed can also be cloned with stock git, eg.
+    `git clone rad://<peer-id>@<seed>/<id>`, through the `git-remote-rad` helper. The
+    project is then fetched from the seed into local storage on every `git fetch`, and
+    pushed to the seed on every `git push`.
+
 Options
 
-    --no-confirm    Don't ask for confirmation during clone
-    --seed <addr>   Seed to clone from
-    --help          Print help
+    --no-confirm          Don't ask for confirmation during clone
+    --seed <addr>         Seed to clone from; saved as the default seed of the project
+    --transport <name>    Transport to clone over: `link` (default) or `http`
+    --track <policy>      Tracking policy of the project (default: delegates-only)
+    --filter <filter>     Only fetch the objects not matched by <filter>, over HTTP
+    --recurse-submodules  Clone the submodules that are radicle projects, recursively
+    --help                Print help
 
 "#,
 };
@@ -35,13 +68,24 @@ Options
 #[derive(Debug)]
 enum Origin {
     Radicle(identity::Origin),
+    Seed(Url),
     Git(Url),
 }
 
+/// How a project is fetched when cloned.
+#[derive(Debug, Default)]
+pub struct Fetch {
+    pub transport: sync::Transport,
+    pub filter: Option<sync::Filter>,
+}
+
 #[derive(Debug)]
 pub struct Options {
     origin: Origin,
     interactive: Interactive,
+    fetch: Fetch,
+    policy: TrackingPolicy,
+    recurse_submodules: bool,
 }
 
 impl Args for Options {
@@ -52,15 +96,36 @@ impl Args for Options {
         let mut origin: Option<Origin> = None;
         let mut interactive = Interactive::Yes;
         let mut seed = None;
+        let mut fetch = Fetch::default();
+        let mut policy = TrackingPolicy::default();
+        let mut recurse_submodules = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("seed") if seed.is_none() => {
                     seed = Some(seed::parse_value(&mut parser)?);
                 }
+                Long("transport") => {
+                    let value = parser.value()?;
+
+                    fetch.transport = args::parse_value("transport", value)?;
+                }
+                Long("filter") => {
+                    let value = parser.value()?;
+
+                    fetch.filter = Some(args::parse_value("filter", value)?);
+                }
+                Long("track") => {
+                    let value = parser.value()?;
+
+                    policy = args::parse_value("track", value)?;
+                }
                 Long("no-confirm") => {
                     interactive = Interactive::No;
                 }
+                Long("recurse-submodules") => {
+                    recurse_submodules = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -75,6 +140,16 @@ impl Args for Options {
                                 Ok(_) if seed.is_some() => {
                                     anyhow::bail!("`--seed` cannot be specified when a URL is given as origin");
                                 }
+                                Ok(url)
+                                    if url.scheme() == project::URL_SCHEME
+                                        && !url.username().is_empty() =>
+                                {
+                                    let o = identity::Origin::try_from(url)?;
+                                    origin = Some(Origin::Radicle(o));
+                                }
+                                Ok(url) if identity::seed_url(&url).is_some() => {
+                                    origin = Some(Origin::Seed(url));
+                                }
                                 Ok(url) if url.scheme() == project::URL_SCHEME => {
                                     let o = identity::Origin::try_from(url)?;
                                     origin = Some(Origin::Radicle(o));
@@ -96,6 +171,14 @@ impl Args for Options {
             anyhow!("to clone, a URN or URL must be provided; see `rad clone --help`")
         })?;
 
+        if fetch.filter.is_some() && fetch.transport != sync::Transport::Http {
+            return Err(Error::WithHint {
+                err: anyhow!("`--filter` is only supported over HTTP"),
+                hint: "hint: use `--transport http` to clone from the seed's git endpoint",
+            }
+            .into());
+        }
+
         let origin = if let Origin::Radicle(identity::Origin { urn, seed: None }) = origin {
             Origin::Radicle(identity::Origin { urn, seed })
         } else {
@@ -106,6 +189,9 @@ impl Args for Options {
             Options {
                 origin,
                 interactive,
+                fetch,
+                policy,
+                recurse_submodules,
             },
             vec![],
         ))
@@ -113,40 +199,84 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    execute(options, ctx)?;
+
+    Ok(())
+}
+
+/// Clone a project or repository, and return the path of its working copy.
+pub fn execute(options: Options, ctx: impl term::Context) -> anyhow::Result<PathBuf> {
     match options.origin {
-        Origin::Radicle(origin) => {
-            clone_project(origin.urn, origin.seed, options.interactive, ctx)?;
+        Origin::Radicle(origin) => clone_project(
+            origin.urn,
+            origin.seed,
+            options.fetch,
+            options.policy,
+            options.recurse_submodules,
+            options.interactive,
+            ctx,
+        ),
+        Origin::Seed(url) => {
+            let spinner = term::spinner(term::tr!(
+                "Resolving seed of {}...",
+                term::format::highlight(&url)
+            ));
+            let origin = match identity::Origin::from_seed_url(&url) {
+                Ok(origin) => {
+                    spinner.finish();
+                    origin
+                }
+                Err(err) => return Err(spinner.error(err)),
+            };
+
+            clone_project(
+                origin.urn,
+                origin.seed,
+                options.fetch,
+                options.policy,
+                options.recurse_submodules,
+                options.interactive,
+                ctx,
+            )
         }
         Origin::Git(url) => {
             let profile = ctx.profile()?;
-            clone_repository(url, &profile)?;
+            clone_repository(url, &profile)
         }
     }
-    Ok(())
 }
 
 pub fn clone_project(
     urn: Urn,
     seed: Option<sync::Seed<String>>,
+    fetch: Fetch,
+    policy: TrackingPolicy,
+    recurse_submodules: bool,
     interactive: Interactive,
     ctx: impl term::Context,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<PathBuf> {
     let profile = ctx.profile()?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let refs_before = namespace_refs(&monorepo, &urn)?;
 
     rad_sync::run(
         rad_sync::Options {
             origin: Some(identity::Origin {
                 urn: urn.clone(),
-                seed,
+                seed: seed.clone(),
             }),
             verbose: true,
+            transport: fetch.transport,
+            filter: fetch.filter,
             ..rad_sync::Options::default()
         },
         profile.clone(),
     )?;
     let path = rad_checkout::execute(
         rad_checkout::Options {
-            urn: urn.clone(),
+            urn: Some(urn.clone()),
+            peer: None,
+            recurse_submodules: false,
             interactive,
         },
         &profile,
@@ -154,31 +284,58 @@ pub fn clone_project(
 
     let signer = term::signer(&profile)?;
     let storage = keys::storage(&profile, signer)?;
-    let cfg = tracking::config::Config::default();
-    let project = project::get(&storage, &urn)?
-        .ok_or_else(|| anyhow!("couldn't load project {} from local state", urn))?;
-
-    // Track all project delegates.
-    for peer in project.remotes {
-        tracking::track(
-            &storage,
-            &urn,
-            Some(peer),
-            cfg.clone(),
-            tracking::policy::Track::Any,
-        )??;
+    let project = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("couldn't load project {} from local state", urn))
+    })?;
+
+    if policy.apply(&storage, &project)?.untracked_all {
+        term::success!("Tracking relationship with all peers removed");
     }
-    term::success!("Tracking for project delegates configured");
+    policy.set(&path)?;
 
-    term::headline(&format!(
+    let refs_created = namespace_refs(&monorepo, &urn)?.saturating_sub(refs_before);
+    let peers_tracked = project::tracked(&project, &storage)?.len();
+
+    term::success!(
+        "Tracking policy {} configured",
+        term::format::highlight(policy)
+    );
+
+    if let Some(seed) = &seed {
+        seed::set_seeds(&path, &[seed.clone()])?;
+        term::success!("Default seed for project configured");
+    }
+    if recurse_submodules {
+        let repo = git::Repository::open(&path)?;
+        rad_checkout::checkout_submodules(&repo, seed.as_ref(), &profile)?;
+    }
+
+    term::headline(&term::tr!(
         "🌱 Project clone successful under ./{}",
         term::format::highlight(path.file_name().unwrap_or_default().to_string_lossy())
     ));
+    term::info!(
+        "{} ref(s) created, {}",
+        term::format::bold(refs_created),
+        match policy {
+            TrackingPolicy::All => String::from("tracking all peers"),
+            _ => format!("{} peer(s) tracked", term::format::bold(peers_tracked)),
+        }
+    );
 
-    Ok(())
+    Ok(path)
+}
+
+/// Count the refs of a project in the monorepo.
+fn namespace_refs(monorepo: &git::Repository, urn: &Urn) -> anyhow::Result<usize> {
+    let refs = monorepo
+        .references_glob(&format!("refs/namespaces/{}/*", urn.encode_id()))?
+        .count();
+
+    Ok(refs)
 }
 
-pub fn clone_repository(url: Url, profile: &profile::Profile) -> anyhow::Result<()> {
+pub fn clone_repository(url: Url, profile: &profile::Profile) -> anyhow::Result<PathBuf> {
     let proj = url
         .path_segments()
         .ok_or(anyhow!("couldn't get segments of URL"))?
@@ -187,24 +344,25 @@ pub fn clone_repository(url: Url, profile: &profile::Profile) -> anyhow::Result<
     let proj = proj.strip_suffix(".git").unwrap_or(proj);
     let destination = std::env::current_dir()?.join(proj);
 
-    let spinner = term::spinner(&format!(
+    let spinner = term::spinner(&term::tr!(
         "Cloning git repository {}...",
         term::format::highlight(&url)
     ));
     git::clone(url.as_str(), &destination)?;
     spinner.finish();
 
-    if term::confirm(format!(
+    if term::confirm(term::tr!(
         "Initialize new 🌱 project in {}?",
         term::format::highlight(destination.display())
-    )) {
+    ))? {
         let options = rad_init::Options {
             path: Some(destination.as_path().into()),
+            interactive: Interactive::Yes,
             ..Default::default()
         };
         rad_init::init(options, profile)?;
     }
-    Ok(())
+    Ok(destination)
 }
 
 #[cfg(test)]
@@ -251,6 +409,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_args_seed_url() {
+        let tests = vec![
+            "https://pine.radicle.garden/hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y.git",
+            "rad://pine.radicle.garden/hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y",
+        ];
+
+        for arg in tests {
+            let (opts, _) = Options::from_args(vec![arg.into()]).unwrap();
+            assert!(matches!(opts.origin, Origin::Seed(_)), "{}", arg);
+        }
+
+        let (opts, _) =
+            Options::from_args(vec!["https://github.com/radicle-dev/radicle-cli.git".into()])
+                .unwrap();
+        assert!(matches!(opts.origin, Origin::Git(_)));
+    }
+
     #[test]
     fn test_args_error() {
         let tests = vec![
@@ -264,6 +440,11 @@ mod test {
                 "--seed",
                 "whyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@illow.radicle.garden",
             ],
+            vec![
+                "rad:git:hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y",
+                "--transport",
+                "smtp",
+            ],
         ];
 
         for args in tests {
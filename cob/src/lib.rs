@@ -0,0 +1,276 @@
+#![allow(clippy::or_fun_call)]
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::issue::{self, Issue};
+use radicle_common::cobs::patch::Patch;
+use radicle_common::cobs::snapshot::Stats;
+use radicle_common::{cobs, exit, keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "cob",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad cob snapshot [<urn>] [--dry-run] [--all]
+    rad cob snapshot [<urn>] --clear
+
+    Materializes settled collaborative objects (merged or archived patches,
+    closed issues) into snapshots, so that reading them doesn't require loading
+    their whole change history. Snapshots are a read cache: object ids and
+    change histories are kept intact, so this increases disk usage by the size
+    of the snapshots. A snapshot is only used for as long as no new change is
+    made to its object. Snapshots of objects that are no longer in storage are
+    removed.
+
+    Listings, eg. `rad patch`, cache the objects they load, keyed by the refs of
+    each object, so that repeated listings don't load unchanged objects again.
+    `--clear` removes these cached listings along with the snapshots.
+
+    If no <urn> is specified, the current project is used.
+
+Options
+
+    --dry-run       Report what would be snapshotted, without writing anything
+    --all           Snapshot all objects, including open patches and issues
+    --clear         Remove all snapshots and cached listings of the project,
+                    freeing the disk space they use
+    --help          Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Snapshot,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Snapshot {
+        dry_run: bool,
+        all: bool,
+        clear: bool,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut urn: Option<Urn> = None;
+        let mut dry_run = false;
+        let mut all = false;
+        let mut clear = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("dry-run") if op == Some(OperationName::Snapshot) => {
+                    dry_run = true;
+                }
+                Long("all") if op == Some(OperationName::Snapshot) => {
+                    all = true;
+                }
+                Long("clear") if op == Some(OperationName::Snapshot) => {
+                    clear = true;
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "snapshot" => op = Some(OperationName::Snapshot),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val)
+                        .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?;
+
+                    urn = Some(val);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.ok_or(Error::Usage)? {
+            OperationName::Snapshot => {
+                if clear && (dry_run || all) {
+                    anyhow::bail!("`--clear` cannot be combined with other options");
+                }
+                Operation::Snapshot {
+                    dry_run,
+                    all,
+                    clear,
+                }
+            }
+        };
+
+        Ok((Options { urn, op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let urn = if let Some(urn) = options.urn {
+        urn
+    } else {
+        project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| anyhow!("a project URN must be specified outside of a working copy"))?
+    };
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found in local storage", urn))
+    })?;
+    let cobs = cobs::store(&profile, &storage)?;
+
+    match options.op {
+        Operation::Snapshot { clear: true, .. } => {
+            let count = cobs.clear_snapshots(&urn)?;
+            let listings = cobs.clear_listings(&urn)?;
+
+            term::success!(
+                "Removed {} snapshot(s) and {} cached listing(s) for {}",
+                term::format::highlight(count),
+                term::format::highlight(listings),
+                term::format::highlight(&proj.name)
+            );
+        }
+        Operation::Snapshot { dry_run, all, .. } => {
+            snapshot(&cobs, &proj, dry_run, all)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn snapshot(
+    cobs: &cobs::Store,
+    project: &project::Metadata,
+    dry_run: bool,
+    all: bool,
+) -> anyhow::Result<()> {
+    let urn = &project.urn;
+    let mut snapshotted: Vec<(&str, Stats)> = Vec::new();
+    let mut skipped = 0;
+
+    let spinner = term::spinner(term::tr!(
+        "Snapshotting collaborative objects of {}...",
+        term::format::highlight(&project.name)
+    ));
+    for (id, patch) in cobs.patches().all(urn)? {
+        if !all && !is_settled_patch(&patch) {
+            skipped += 1;
+            continue;
+        }
+        if let Some(s) = cobs.write_snapshot::<Patch>(urn, &id, dry_run)? {
+            snapshotted.push(("patch", s));
+        }
+    }
+    for (id, issue) in cobs.issues().all(urn)? {
+        if !all && !is_settled_issue(&issue) {
+            skipped += 1;
+            continue;
+        }
+        if let Some(s) = cobs.write_snapshot::<Issue>(urn, &id, dry_run)? {
+            snapshotted.push(("issue", s));
+        }
+    }
+    let pruned = if dry_run {
+        0
+    } else {
+        cobs.prune_snapshots(urn)?
+    };
+    spinner.finish();
+
+    if pruned > 0 {
+        term::info!(
+            "Removed {} snapshot(s) of objects that are no longer in storage",
+            term::format::highlight(pruned)
+        );
+    }
+    if snapshotted.is_empty() {
+        term::info!("{}", term::format::italic("Nothing to snapshot."));
+        return Ok(());
+    }
+
+    let mut table = term::Table::default();
+    for (kind, s) in &snapshotted {
+        table.push([
+            term::format::dim(kind),
+            term::format::tertiary(radicle_common::fmt::cob(&s.id)),
+            format!("{} change(s)", s.changes),
+            format!(
+                "{} {} {}",
+                term::format::dim(bytes(s.history)),
+                term::format::dim("+"),
+                term::format::secondary(bytes(s.size))
+            ),
+        ]);
+    }
+    term::blank();
+    table.render();
+    term::blank();
+
+    let size: usize = snapshotted.iter().map(|(_, s)| s.size).sum();
+    if dry_run {
+        term::info!(
+            "{} object(s) would be snapshotted, using {} of additional disk space",
+            snapshotted.len(),
+            term::format::highlight(bytes(size))
+        );
+    } else {
+        term::success!(
+            "Snapshotted {} object(s), using {} of additional disk space",
+            snapshotted.len(),
+            term::format::highlight(bytes(size))
+        );
+    }
+    if skipped > 0 {
+        term::tip!(
+            "{} open object(s) were skipped; use `--all` to snapshot them too.",
+            skipped
+        );
+    }
+
+    Ok(())
+}
+
+/// Patches are settled once merged or archived.
+fn is_settled_patch(patch: &Patch) -> bool {
+    let (_, revision) = patch.latest();
+
+    patch.is_archived() || !revision.merges.is_empty()
+}
+
+/// Issues are settled once closed.
+fn is_settled_issue(issue: &Issue) -> bool {
+    matches!(issue.state(), issue::State::Closed { .. })
+}
+
+fn bytes(n: usize) -> String {
+    if n >= 1024 * 1024 {
+        format!("{:.1} MiB", n as f64 / (1024. * 1024.))
+    } else if n >= 1024 {
+        format!("{:.1} KiB", n as f64 / 1024.)
+    } else {
+        format!("{} B", n)
+    }
+}
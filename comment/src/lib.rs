@@ -7,7 +7,8 @@ use anyhow::anyhow;
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::{
     cobs::{self, issue, patch, CommentId},
-    keys, project,
+    hooks::Event,
+    json, keys, project,
 };
 use radicle_terminal as term;
 use radicle_terminal::patch::Comment;
@@ -69,7 +70,7 @@ impl Args for Options {
 
                     id = Some(
                         cobs::Identifier::from_str(val)
-                            .map_err(|_| anyhow!("invalid object id '{}'", val))?,
+                            .map_err(|_| anyhow!(term::tr!("invalid object id '{}'", val)))?,
                     );
                 }
                 _ => {
@@ -109,6 +110,16 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         } else {
             cobs.issues().comment(&project, &id, &message)?;
         }
+        term::hooks::emit(
+            &profile,
+            Event::IssueCommented,
+            &project,
+            json::json!({
+                "id": id.to_string(),
+                "body": message,
+                "replyTo": options.reply_index.map(usize::from),
+            }),
+        );
     } else if let Some((id, patch)) = cobs.resolve::<patch::Patch>(&project, &cob_id)? {
         if let Some(reply_to_index) = options.reply_index {
             cobs.patches()
@@ -117,8 +128,19 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             cobs.patches()
                 .comment(&project, &id, patch.version(), &message)?;
         }
+        term::hooks::emit(
+            &profile,
+            Event::PatchCommented,
+            &project,
+            json::json!({
+                "id": id.to_string(),
+                "revision": patch.version(),
+                "body": message,
+                "replyTo": options.reply_index.map(usize::from),
+            }),
+        );
     } else {
-        anyhow::bail!("Couldn't find issue or patch {}", cob_id);
+        anyhow::bail!(term::tr!("Couldn't find issue or patch {}", cob_id));
     }
 
     Ok(())
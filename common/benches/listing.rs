@@ -0,0 +1,69 @@
+//! Benchmarks of the listing cache, which patch and issue listings go through for every
+//! object, see `cobs::cache`. Run them with `cargo bench -p radicle-common --bench listing`.
+use std::str::FromStr;
+
+use automerge::transaction::Transactable;
+use automerge::{Automerge, AutomergeError, ROOT};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use librad::collaborative_objects::ObjectId;
+use radicle_common::cobs::cache::Listing;
+
+/// Number of objects listed.
+const OBJECTS: usize = 500;
+
+fn listing(c: &mut Criterion) {
+    let mut objects = (0..OBJECTS).map(object).collect::<Vec<_>>();
+
+    c.bench_function("listing/insert", |b| {
+        b.iter(|| {
+            let mut listing = Listing::default();
+
+            for (id, tips, doc) in objects.iter_mut() {
+                listing.insert(id, tips, doc);
+            }
+            listing
+        })
+    });
+
+    let mut listing = Listing::default();
+    for (id, tips, doc) in objects.iter_mut() {
+        listing.insert(id, tips, doc);
+    }
+    let bytes = serde_json::to_vec(&listing).unwrap();
+
+    c.bench_function("listing/read", |b| {
+        b.iter(|| serde_json::from_slice::<Listing>(black_box(&bytes)).unwrap())
+    });
+    c.bench_function("listing/write", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&listing)).unwrap())
+    });
+    c.bench_function("listing/get", |b| {
+        b.iter(|| {
+            objects
+                .iter()
+                .filter_map(|(id, tips, _)| listing.get(id, tips))
+                .count()
+        })
+    });
+}
+
+/// Create an object, with a document like an issue's, and the tips of its change graph.
+fn object(i: usize) -> (ObjectId, Vec<git2::Oid>, Automerge) {
+    let oid = git2::Oid::hash_object(git2::ObjectType::Blob, &i.to_be_bytes()).unwrap();
+    let id = ObjectId::from_str(&oid.to_string()).unwrap();
+    let mut doc = Automerge::new();
+
+    doc.transact::<_, _, AutomergeError>(|tx| {
+        tx.put(ROOT, "title", format!("Issue #{}", i))?;
+        tx.put(ROOT, "state", "open")?;
+        tx.put(ROOT, "body", "Lorem ipsum dolor sit amet. ".repeat(20))?;
+        Ok(())
+    })
+    .unwrap_or_else(|_| panic!("failed to create the document of object {}", i));
+
+    (id, vec![oid], doc)
+}
+
+criterion_group!(benches, listing);
+criterion_main!(benches);
@@ -0,0 +1,131 @@
+//! Benchmarks of the work syncing does besides transfers, for every ref or progress
+//! update, see `sync`. Run them with `cargo bench -p radicle-common --bench sync`.
+//!
+//! Nb. Transfers themselves depend on the network and seeds, and aren't benchmarked.
+use std::fs;
+use std::str::FromStr;
+use std::time;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use librad::PeerId;
+use radicle_common::sync::pack::Incoming;
+use radicle_common::sync::refs::{self, Refs};
+use radicle_common::sync::scope::Scope;
+use radicle_common::sync::throttle::Throttle;
+use radicle_common::{Url, Urn};
+
+/// Number of refs of each kind, ie. branches, tags and patches, of a peer.
+const REFS: usize = 200;
+/// Number of packfiles in the pack directory.
+const PACKS: usize = 100;
+
+const LOCAL: &str = "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa";
+const REMOTE: &str = "hyd7wpd8p5aqnm9htsfoatxkckmw6ingnsdudns9code5xq17h1rhw";
+const PROJECT: &str = "rad:git:hnrkbjokbt439jk3p1dsi67u3mca85yiy7fiy";
+
+/// Progress updates, as done periodically while fetching.
+fn progress(c: &mut Criterion) {
+    c.bench_function("sync/throttle", |b| {
+        let mut throttle = Throttle::new(time::Duration::from_secs(9), Some(1024 * 1024));
+        let mut received = 0;
+
+        b.iter(|| {
+            received += 4096;
+            throttle.update(black_box(received));
+
+            (throttle.delay(), throttle.is_stalled())
+        })
+    });
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("objects").join("pack");
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut incoming = Incoming::new(tmp.path());
+    for i in 0..PACKS {
+        fs::write(dir.join(format!("pack-{}.pack", i)), vec![0; 4096]).unwrap();
+    }
+    c.bench_function("sync/pack-received", |b| b.iter(|| incoming.received()));
+}
+
+/// Selecting the refs to sync, as done for every ref advertised by a seed.
+fn matches(c: &mut Criterion) {
+    let peer = PeerId::from_str(REMOTE).unwrap();
+    let names = names();
+    let scope = Scope::new(vec![String::from("heads/master"), String::from("tags/*")]);
+    let cob = Refs::cob(&format!("patch/{}", oid(REFS - 1))).unwrap();
+
+    c.bench_function("sync/scope-matches", |b| {
+        b.iter(|| names.iter().filter(|n| scope.matches(n)).count())
+    });
+    c.bench_function("sync/refs-matches", |b| {
+        b.iter(|| names.iter().filter(|n| cob.matches(&peer, n)).count())
+    });
+}
+
+/// Planning a sync, ie. comparing the refs advertised by a seed with the monorepo, as
+/// done by `rad sync --dry-run`. The seed is a local repository.
+fn plan(c: &mut Criterion) {
+    let tmp = tempfile::tempdir().unwrap();
+    let urn = Urn::from_str(PROJECT).unwrap();
+    let local = PeerId::from_str(LOCAL).unwrap();
+    let remote = PeerId::from_str(REMOTE).unwrap();
+    let seed = Url::from_directory_path(tmp.path().join("seed")).unwrap();
+    let monorepo = git2::Repository::init_bare(tmp.path().join("monorepo")).unwrap();
+    let upstream =
+        git2::Repository::init_bare(tmp.path().join("seed").join(urn.encode_id())).unwrap();
+    let namespace = format!("refs/namespaces/{}/refs", urn.encode_id());
+
+    let (ours, theirs) = (commit(&monorepo), commit(&upstream));
+    for name in names() {
+        // The remote peer's refs are fetched, and ours are pushed.
+        upstream
+            .reference(
+                &format!("refs/remotes/{}/{}", remote, name),
+                theirs,
+                true,
+                "",
+            )
+            .unwrap();
+        monorepo
+            .reference(&format!("{}/{}", namespace, name), ours, true, "")
+            .unwrap();
+    }
+
+    c.bench_function("sync/plan", |b| {
+        b.iter(|| refs::plan(&monorepo, &seed, None, &urn, &local, &Refs::All).unwrap())
+    });
+}
+
+/// Get the names of the refs of a peer, relative to the peer: branches, tags and patches.
+fn names() -> Vec<String> {
+    (0..REFS)
+        .flat_map(|i| {
+            [
+                format!("heads/branch-{}", i),
+                format!("tags/v{}", i),
+                format!("cobs/xyz.radicle.patch/{}", oid(i)),
+            ]
+        })
+        .collect()
+}
+
+/// Get the id of the `i`th patch.
+fn oid(i: usize) -> git2::Oid {
+    git2::Oid::hash_object(git2::ObjectType::Blob, &i.to_be_bytes()).unwrap()
+}
+
+/// Create a commit in a repository, without updating any ref.
+fn commit(repo: &git2::Repository) -> git2::Oid {
+    let sig = git2::Signature::new("radicle", "radicle@localhost", &git2::Time::new(0, 0)).unwrap();
+    let tree = repo
+        .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+
+    repo.commit(None, &sig, &sig, "Initial commit", &tree, &[])
+        .unwrap()
+}
+
+criterion_group!(benches, progress, matches, plan);
+criterion_main!(benches);
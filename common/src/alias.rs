@@ -0,0 +1,85 @@
+//! Peer aliases.
+//!
+//! Aliases are human-readable names given to peers locally. They are stored in the
+//! working copy's git config, under `rad.peer.<peer-id>.alias`, alongside the other
+//! peer configuration.
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context as _;
+
+use librad::PeerId;
+
+use crate::git;
+use crate::seed::CONFIG_PEER_KEY;
+
+/// Peer aliases of a working copy.
+#[derive(Debug, Default, Clone)]
+pub struct Aliases {
+    aliases: HashMap<PeerId, String>,
+}
+
+impl Aliases {
+    /// Load the aliases of the working copy in the current directory.
+    /// Outside of a working copy, there are no aliases.
+    pub fn cwd() -> Self {
+        git::repository()
+            .map(|repo| Self::load(&repo))
+            .unwrap_or_default()
+    }
+
+    /// Load the aliases of the given repository.
+    pub fn load(repo: &git::Repository) -> Self {
+        let mut aliases = HashMap::new();
+        let pattern = format!(r"^{}\..*\.alias$", CONFIG_PEER_KEY.replace('.', r"\."));
+
+        if let Ok(entries) = repo.config().and_then(|c| c.entries(Some(&pattern))) {
+            for entry in (&entries).flatten() {
+                let peer = entry
+                    .name()
+                    .and_then(|n| n.strip_prefix(CONFIG_PEER_KEY))
+                    .and_then(|n| n.strip_prefix('.'))
+                    .and_then(|n| n.strip_suffix(".alias"))
+                    .and_then(|p| PeerId::from_str(p).ok());
+
+                if let (Some(peer), Some(alias)) = (peer, entry.value()) {
+                    aliases.insert(peer, alias.to_owned());
+                }
+            }
+        }
+        Self { aliases }
+    }
+
+    /// Get the alias of a peer.
+    pub fn get(&self, peer: &PeerId) -> Option<&str> {
+        self.aliases.get(peer).map(|a| a.as_str())
+    }
+
+    /// Get the alias of a peer, or its compact peer id if it has no alias.
+    pub fn name(&self, peer: &PeerId) -> String {
+        self.name_or(peer, || crate::fmt::peer(peer))
+    }
+
+    /// Get the alias of a peer, or the given name if it has no alias.
+    pub fn name_or(&self, peer: &PeerId, name: impl FnOnce() -> String) -> String {
+        self.get(peer).map(|a| a.to_owned()).unwrap_or_else(name)
+    }
+
+    /// Get the peer with the given alias.
+    pub fn peer(&self, alias: &str) -> Option<PeerId> {
+        self.aliases
+            .iter()
+            .find(|(_, a)| a.as_str() == alias)
+            .map(|(peer, _)| *peer)
+    }
+}
+
+/// Set the alias of a peer within the repository at the given path.
+pub fn set(path: &Path, peer: &PeerId, alias: &str) -> Result<(), anyhow::Error> {
+    let key = format!("{}.{}.alias", CONFIG_PEER_KEY, peer.default_encoding());
+
+    git::git(path, ["config", "--local", &key, alias])
+        .map(|_| ())
+        .context("failed to save peer alias")
+}
@@ -1,5 +1,7 @@
 use std::ffi::OsString;
+use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::anyhow;
 
@@ -45,7 +47,7 @@ pub trait Args: Sized {
 
 pub fn parse_value<T: FromStr>(flag: &str, value: OsString) -> anyhow::Result<T>
 where
-    <T as FromStr>::Err: std::error::Error,
+    <T as FromStr>::Err: fmt::Display,
 {
     value
         .into_string()
@@ -54,6 +56,76 @@ where
         .map_err(|e| anyhow!("invalid value specified for '--{}' ({})", flag, e))
 }
 
+/// Parse a duration, given in seconds, or with a `s`, `m`, `h` or `d` unit, eg. `30m`.
+pub fn parse_duration(flag: &str, value: OsString) -> anyhow::Result<Duration> {
+    let value = value
+        .into_string()
+        .map_err(|_| anyhow!("the value specified for '--{}' is not valid unicode", flag))?;
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value.as_str(), "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => anyhow::bail!(
+            "invalid value specified for '--{}' (unknown unit '{}')",
+            flag,
+            unit
+        ),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|e| anyhow!("invalid value specified for '--{}' ({})", flag, e))?;
+
+    Ok(Duration::from_secs(number * multiplier))
+}
+
+/// Number of items on a page of a listing, when only `--page` is given.
+pub const PAGE_SIZE: usize = 50;
+
+/// Page of a listing, given with `--limit <n>` and `--page <n>`. By default, all items
+/// are listed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// Maximum number of items listed.
+    pub limit: Option<usize>,
+    /// Page number, from zero.
+    pub index: usize,
+}
+
+impl Page {
+    /// Create a page from the values of `--limit` and `--page`, whose pages are numbered
+    /// from one.
+    pub fn new(limit: Option<usize>, page: Option<usize>) -> anyhow::Result<Self> {
+        if limit == Some(0) {
+            anyhow::bail!("invalid value specified for '--limit' (must be at least 1)");
+        }
+        let index = match page {
+            Some(0) => anyhow::bail!("invalid value specified for '--page' (pages start at 1)"),
+            Some(page) => page - 1,
+            None => 0,
+        };
+        let limit = if page.is_some() {
+            Some(limit.unwrap_or(PAGE_SIZE))
+        } else {
+            limit
+        };
+
+        Ok(Self { limit, index })
+    }
+
+    /// Get the items of the page, from the items of the whole listing. Items are only
+    /// taken from the iterator until the page is full.
+    pub fn of<I: Iterator>(&self, items: I) -> std::iter::Take<std::iter::Skip<I>> {
+        let limit = self.limit.unwrap_or(usize::MAX);
+
+        items.skip(self.index.saturating_mul(limit)).take(limit)
+    }
+}
+
 pub fn format(arg: lexopt::Arg) -> OsString {
     match arg {
         lexopt::Arg::Long(flag) => format!("--{}", flag).into(),
@@ -71,3 +143,41 @@ pub fn finish(unparsed: Vec<OsString>) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        let parse = |s: &str| parse_duration("ttl", OsString::from(s));
+
+        assert_eq!(parse("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse("30m").unwrap(), Duration::from_secs(30 * 60));
+        assert_eq!(parse("8h").unwrap(), Duration::from_secs(8 * 60 * 60));
+        assert_eq!(parse("2d").unwrap(), Duration::from_secs(2 * 24 * 60 * 60));
+        assert!(parse("").is_err());
+        assert!(parse("h").is_err());
+        assert!(parse("1w").is_err());
+        assert!(parse("1.5h").is_err());
+    }
+
+    #[test]
+    fn test_page() {
+        let page = |limit, page| {
+            Page::new(limit, page)
+                .unwrap()
+                .of(1..=120)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(page(None, None).len(), 120);
+        assert_eq!(page(Some(10), None), (1..=10).collect::<Vec<_>>());
+        assert_eq!(page(Some(10), Some(3)), (21..=30).collect::<Vec<_>>());
+        assert_eq!(page(None, Some(3)), (101..=120).collect::<Vec<_>>());
+        assert!(page(Some(10), Some(13)).is_empty());
+        assert!(Page::new(Some(0), None).is_err());
+        assert!(Page::new(None, Some(0)).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Profile backups.
+//!
+//! A profile archive holds everything needed to restore a profile on another machine:
+//! the profile's secret key, the user's personal identity, and the profile-scoped
+//! configuration files. Projects are not included, since they can be fetched from seeds.
+//!
+//! Archives are encrypted with the profile passphrase, which is verified on export, so
+//! that an archive can only be restored by someone who could use the profile anyway.
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::{anyhow, Context as _};
+use serde::{Deserialize, Serialize};
+
+use librad::crypto::keystore::crypto::{Crypto, Pwhash};
+use librad::crypto::keystore::pinentry::SecUtf8;
+use librad::crypto::keystore::{FileStorage, Keystore as _};
+use librad::crypto::{PublicKey, SecretKey};
+use librad::git::Urn;
+use librad::profile::{LnkHome, Profile};
+use librad::PeerId;
+
+use crate::signer::{ToSigner, ZeroizingSecretKey};
+use crate::sync::scope;
+use crate::{block, config, credentials, exit, git, hooks, keys, notify, person, profile};
+
+/// Archive format version.
+pub const VERSION: u32 = 1;
+
+/// Profile-scoped files included in archives.
+const FILES: &[&str] = &[
+    config::FILE_NAME_PROFILE,
+    credentials::FILE_NAME,
+    block::FILE_NAME,
+    scope::FILE_NAME,
+    hooks::FILE_NAME,
+    notify::FILE_NAME,
+];
+
+/// An encrypted profile archive, as stored on disk.
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    version: u32,
+    /// Sealed [`Contents`].
+    sealed: <Pwhash<SecUtf8> as Crypto>::SecretBox,
+}
+
+/// Contents of a profile archive.
+#[derive(Serialize, Deserialize)]
+struct Contents {
+    /// The profile's key file, itself encrypted with the profile passphrase.
+    key: String,
+    /// URN of the user's personal identity.
+    urn: String,
+    /// The personal identity, as a git bundle of its namespace in storage.
+    identity: String,
+    /// Profile-scoped files, by file name.
+    files: BTreeMap<String, String>,
+}
+
+/// Export a profile to an encrypted archive at the given path. Fails if the passphrase
+/// doesn't unlock the profile's key.
+pub fn export(profile: &Profile, passphrase: SecUtf8, output: &Path) -> anyhow::Result<()> {
+    keys::load_secret_key(profile, passphrase.clone()).context("invalid passphrase supplied")?;
+
+    let storage = profile::read_only(profile)?;
+    let urn = storage
+        .config()?
+        .user()?
+        .ok_or_else(|| anyhow!("profile has no personal identity"))?;
+
+    let key = fs::read(profile.paths().keys_dir().join(keys::KEY_FILE))?;
+    let identity = bundle(profile, &urn)?;
+
+    let mut files = BTreeMap::new();
+    for name in FILES {
+        match fs::read_to_string(profile::scoped_path(profile, name)) {
+            Ok(contents) => {
+                files.insert(name.to_string(), contents);
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let contents = Contents {
+        key: base64::encode(key),
+        urn: urn.to_string(),
+        identity: base64::encode(identity),
+        files,
+    };
+    let sealed = keys::pwhash(passphrase)
+        .seal(serde_json::to_vec(&contents)?)
+        .map_err(|e| anyhow!("failed to encrypt archive: {}", e))?;
+    let archive = Archive {
+        version: VERSION,
+        sealed,
+    };
+
+    profile::write_private(output, serde_json::to_string(&archive)?)
+        .with_context(|| format!("failed to write archive to '{}'", output.display()))?;
+
+    Ok(())
+}
+
+/// Import a profile from an encrypted archive, and make it the active profile.
+/// Fails if the passphrase is invalid, or if a profile with the same key already exists.
+pub fn import(
+    home: LnkHome,
+    input: &Path,
+    passphrase: SecUtf8,
+) -> anyhow::Result<(Profile, PeerId, Urn)> {
+    let archive: Archive = serde_json::from_slice(
+        &fs::read(input).with_context(|| format!("failed to read '{}'", input.display()))?,
+    )
+    .context("invalid profile archive")?;
+
+    if archive.version != VERSION {
+        anyhow::bail!(
+            "unsupported profile archive version {}, expected {}",
+            archive.version,
+            VERSION
+        );
+    }
+    let contents = keys::pwhash(passphrase.clone())
+        .unseal(archive.sealed)
+        .map_err(|_| anyhow!("invalid passphrase supplied"))?;
+    let contents: Contents =
+        serde_json::from_slice(contents.unsecure()).context("invalid profile archive")?;
+    let urn: Urn = contents
+        .urn
+        .parse()
+        .context("invalid identity in archive")?;
+    let key = base64::decode(&contents.key).context("invalid key in archive")?;
+    let identity = base64::decode(&contents.identity).context("invalid identity in archive")?;
+
+    // Check the key before creating anything.
+    let tmp = tempfile::NamedTempFile::new()?;
+    fs::write(tmp.path(), &key)?;
+    let keypair =
+        FileStorage::<_, PublicKey, SecretKey, _>::new(tmp.path(), keys::pwhash(passphrase))
+            .get_key();
+
+    let secret_key = keypair.context("invalid key in archive")?.secret_key;
+    let peer_id = PeerId::from(secret_key.clone());
+
+    for existing in Profile::list(&home)? {
+        if let Ok(storage) = profile::read_only(&existing) {
+            if *storage.peer_id() == peer_id {
+                anyhow::bail!(
+                    "a profile with peer id {} already exists: {}",
+                    peer_id,
+                    existing.id()
+                );
+            }
+        }
+    }
+
+    // The profile is built in a temporary directory next to the other profiles, and moved
+    // into place once complete, so that a failed import leaves no trace.
+    let profile = Profile::new(&home)?;
+    let dir = profile::dir(&profile);
+    let result = dir
+        .parent()
+        .ok_or_else(|| anyhow!("invalid profile directory '{}'", dir.display()))
+        .and_then(|parent| {
+            let staging = tempfile::Builder::new()
+                .prefix(".import-")
+                .tempdir_in(parent)?;
+            let staged = Profile::new(&LnkHome::Root(staging.path().to_path_buf()))?;
+
+            restore(&staged, &key, secret_key, &urn, &identity, &contents.files)?;
+
+            fs::remove_dir_all(&dir)?;
+            fs::rename(profile::dir(&staged), &dir)?;
+
+            Ok(())
+        });
+    if let Err(err) = result {
+        fs::remove_dir_all(&dir).ok();
+        return Err(err);
+    }
+    Profile::set(&home, profile.id().clone())?;
+
+    Ok((profile, peer_id, urn))
+}
+
+/// Restore the contents of an archive into a new, empty profile.
+fn restore(
+    profile: &Profile,
+    key: &[u8],
+    secret_key: SecretKey,
+    urn: &Urn,
+    identity: &[u8],
+    files: &BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    let keys_dir = profile.paths().keys_dir();
+
+    fs::create_dir_all(keys_dir)?;
+    fs::write(keys_dir.join(keys::KEY_FILE), key)?;
+
+    for (name, contents) in files {
+        // Only restore known files, so that an archive can't write outside the profile.
+        if FILES.contains(&name.as_str()) {
+            profile::write_private(&profile::scoped_path(profile, name), contents.clone())?;
+        }
+    }
+
+    let signer = ZeroizingSecretKey::new(secret_key).to_signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+
+    unbundle(profile, urn, identity)?;
+
+    let person = person::get(&storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(format!("identity {} not found in archive", urn)))?;
+    person::set_local(&storage, &person)?;
+
+    Ok(())
+}
+
+/// Copy the profile-scoped files, eg. the configuration, from one profile to another.
+pub fn copy_files(from: &Profile, to: &Profile) -> anyhow::Result<()> {
+    for name in FILES {
+        match fs::read_to_string(profile::scoped_path(from, name)) {
+            Ok(contents) => profile::write_private(&profile::scoped_path(to, name), contents)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Bundle the namespace of an identity in storage.
+fn bundle(profile: &Profile, urn: &Urn) -> anyhow::Result<Vec<u8>> {
+    let monorepo = profile.paths().git_dir();
+    let repo = git::Repository::open_bare(monorepo)?;
+    let glob = format!("refs/namespaces/{}/*", urn.encode_id());
+
+    let mut args = vec!["bundle".to_owned(), "create".to_owned()];
+    let tmp = tempfile::NamedTempFile::new()?;
+    args.push(tmp.path().display().to_string());
+
+    for r in repo.references_glob(&glob)? {
+        if let Some(name) = r?.name() {
+            args.push(name.to_owned());
+        }
+    }
+    if args.len() == 3 {
+        anyhow::bail!(exit::Error::NotFound(format!(
+            "identity {} not found in storage",
+            urn
+        )));
+    }
+
+    git::git(monorepo, args).context("failed to bundle identity")?;
+    let bundle = fs::read(tmp.path())?;
+
+    Ok(bundle)
+}
+
+/// Fetch the namespace of an identity from a bundle into storage.
+fn unbundle(profile: &Profile, urn: &Urn, bundle: &[u8]) -> anyhow::Result<()> {
+    let tmp = tempfile::NamedTempFile::new()?;
+    let path = tmp.path().display().to_string();
+    let refspec = format!(
+        "refs/namespaces/{id}/*:refs/namespaces/{id}/*",
+        id = urn.encode_id()
+    );
+    fs::write(tmp.path(), bundle)?;
+
+    git::git(
+        profile.paths().git_dir(),
+        ["fetch", path.as_str(), refspec.as_str()],
+    )
+    .context("failed to restore identity")?;
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Blocked peers.
+//!
+//! The block list is stored in the profile, and applies to all projects. Blocked peers are
+//! tracked with a tracking config that refuses all of their data, so that it isn't fetched
+//! in the first place, and their contributions to collaborative objects, including comments,
+//! are hidden.
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use librad::git::storage::Storage;
+use librad::git::tracking::git::tracking;
+use librad::git::Urn;
+use librad::profile::Profile;
+use librad::PeerId;
+use serde::{Deserialize, Serialize};
+
+use crate::cobs::{Comment, Replies};
+use crate::{git, profile, project};
+
+/// Block list file name, in the profile scope.
+pub const FILE_NAME: &str = "blocked.toml";
+
+/// Tracking config of blocked peers, with which none of their data or collaborative objects
+/// are replicated.
+pub fn config() -> tracking::config::Config {
+    tracking::config::Config {
+        data: false,
+        cobs: tracking::config::Cobs::deny_all(),
+    }
+}
+
+/// Check whether a tracking config is that of a blocked peer. See [`config`].
+pub fn is_blocked(config: &tracking::config::Config) -> bool {
+    *config == self::config()
+}
+
+/// Peers blocked by the user.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Blocklist {
+    #[serde(default)]
+    peer: BTreeSet<String>,
+}
+
+impl Blocklist {
+    /// Load the block list of the profile. If there is none yet, returns an empty list.
+    pub fn load(profile: &Profile) -> Result<Self, anyhow::Error> {
+        profile::load_toml(profile, FILE_NAME)
+    }
+
+    /// Write the block list.
+    pub fn write(&self, profile: &Profile) -> Result<(), anyhow::Error> {
+        profile::store_toml(profile, FILE_NAME, self)
+    }
+
+    pub fn path(profile: &Profile) -> PathBuf {
+        profile::scoped_path(profile, FILE_NAME)
+    }
+
+    /// Check whether a peer is blocked.
+    pub fn contains(&self, peer: &PeerId) -> bool {
+        self.peer.contains(&peer.default_encoding())
+    }
+
+    /// Block a peer. Returns `false` if the peer was already blocked.
+    pub fn insert(&mut self, peer: &PeerId) -> bool {
+        self.peer.insert(peer.default_encoding())
+    }
+
+    /// Unblock a peer. Returns `false` if the peer wasn't blocked.
+    pub fn remove(&mut self, peer: &PeerId) -> bool {
+        self.peer.remove(&peer.default_encoding())
+    }
+
+    /// Get the blocked peers.
+    pub fn peers(&self) -> Vec<PeerId> {
+        self.peer
+            .iter()
+            .filter_map(|p| PeerId::from_str(p).ok())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peer.is_empty()
+    }
+
+    /// Track the blocked peers in a project with the tracking config of blocked peers, so
+    /// that their data is refused when the project is fetched. Replaces their existing
+    /// tracking entries, if any.
+    pub fn track(&self, storage: &Storage, urn: &Urn) -> Result<(), anyhow::Error> {
+        for peer in self.peers() {
+            tracking::track(
+                storage,
+                urn,
+                Some(peer),
+                config(),
+                tracking::policy::Track::Any,
+            )??;
+        }
+        Ok(())
+    }
+
+    /// Delete the refs of blocked peers from a project's namespace in the monorepo, in case
+    /// they were fetched, eg. from a seed. Their `rad/` refs are kept, see
+    /// [`project::prunable_refs`]. Returns the number of refs deleted.
+    pub fn prune(&self, monorepo: &git::Repository, urn: &Urn) -> Result<usize, anyhow::Error> {
+        let mut pruned = 0;
+
+        for peer in self.peers() {
+            pruned += project::prune_refs(monorepo, urn, &peer, |_| true)?;
+        }
+        Ok(pruned)
+    }
+
+    /// Get the comments of a discussion, and their replies, that weren't authored by
+    /// blocked peers. Nb. Comments are referred to by their index in the discussion, so
+    /// this is only meant for rendering.
+    pub fn discussion(&self, discussion: &[Comment<Replies>]) -> Vec<Comment<Replies>> {
+        discussion
+            .iter()
+            .filter(|c| !self.contains(&c.author.peer))
+            .map(|c| {
+                let mut c = c.clone();
+                c.replies.retain(|r| !self.contains(&r.author.peer));
+                c
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blocklist() {
+        let peer =
+            PeerId::from_str("hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa").unwrap();
+        let other =
+            PeerId::from_str("hyd7wpd8p5aqnm9htsfoatxkckmw6ingnsdudns9code5xq17h1rhw").unwrap();
+        let mut blocklist = Blocklist::default();
+
+        assert!(blocklist.insert(&peer));
+        assert!(!blocklist.insert(&peer));
+        assert!(blocklist.contains(&peer));
+        assert!(!blocklist.contains(&other));
+
+        let blocklist: Blocklist =
+            toml::from_str(&toml::to_string_pretty(&blocklist).unwrap()).unwrap();
+        assert_eq!(blocklist.peers(), vec![peer]);
+
+        let mut blocklist = blocklist;
+        assert!(blocklist.remove(&peer));
+        assert!(!blocklist.remove(&peer));
+        assert!(blocklist.is_empty());
+    }
+}
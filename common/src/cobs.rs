@@ -1,7 +1,10 @@
+pub mod cache;
 pub mod issue;
 pub mod label;
 pub mod patch;
+pub mod release;
 pub mod shared;
+pub mod snapshot;
 pub mod user;
 
 pub use shared::*;
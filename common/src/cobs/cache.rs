@@ -0,0 +1,284 @@
+//! On-disk cache of collaborative object listings.
+//!
+//! Listing the objects of a type, eg. all patches of a project, materializes every
+//! object from its change graph. The materialized documents are cached per project and
+//! type, and keyed by the tips of each object's change graph, like snapshots, see
+//! [`crate::cobs::snapshot`]. A cached document is used as long as its tips match the refs in
+//! storage; objects that changed are materialized again, and objects that were removed
+//! are dropped from the cache.
+//!
+//! Author profiles are cached as well, keyed by the tip of the author's identity, so that
+//! listings don't load every author's identity document.
+//!
+//! The cache is only an optimization: if it can't be read or written, objects and
+//! authors are loaded from storage.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use automerge::Automerge;
+use serde::{Deserialize, Serialize};
+
+use librad::collaborative_objects::{ObjectId, TypeName};
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+
+use crate::cobs::shared::{Author, AuthorProfile, ResolveError};
+use crate::timing::{self, Category};
+
+/// Directory, under the COB cache, in which listings are stored.
+pub const LISTINGS_DIR: &str = "listings";
+
+/// File, under the listings directory, in which author profiles are stored.
+pub const AUTHORS_FILE: &str = "authors.json";
+
+/// A cached, materialized object.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// Tips of the change graph that were materialized.
+    pub tips: Vec<String>,
+    /// The materialized automerge document, base64-encoded.
+    pub doc: String,
+}
+
+/// The cached objects of a type, in a project.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Listing {
+    /// Cached objects, by object id.
+    pub objects: BTreeMap<String, Entry>,
+}
+
+impl Listing {
+    /// Get the document of an object, if it is cached and up to date.
+    pub fn get(&self, id: &ObjectId, tips: &[git2::Oid]) -> Option<Automerge> {
+        let entry = self.objects.get(&id.to_string())?;
+
+        if entry.tips != strings(tips) {
+            return None;
+        }
+        let bytes = base64::decode(&entry.doc).ok()?;
+
+        Automerge::load(&bytes).ok()
+    }
+
+    /// Cache the document of an object.
+    pub fn insert(&mut self, id: &ObjectId, tips: &[git2::Oid], doc: &mut Automerge) {
+        self.objects.insert(
+            id.to_string(),
+            Entry {
+                tips: strings(tips),
+                doc: base64::encode(doc.save()),
+            },
+        );
+    }
+
+    /// Drop the objects that aren't in storage anymore. Returns whether any were dropped.
+    pub fn retain(&mut self, objects: &[(ObjectId, Vec<git2::Oid>)]) -> bool {
+        let ids = objects
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect::<BTreeSet<_>>();
+        let len = self.objects.len();
+
+        self.objects.retain(|id, _| ids.contains(id));
+        self.objects.len() != len
+    }
+}
+
+/// Get the path of a listing.
+pub fn path(dir: &Path, project: &Urn, typename: &TypeName) -> PathBuf {
+    dir.join(project.encode_id())
+        .join(format!("{}.json", typename))
+}
+
+/// Get the objects of a type, with the sorted tips of their change graphs, across all
+/// remotes.
+pub fn tips(
+    repo: &git2::Repository,
+    project: &Urn,
+    typename: &TypeName,
+) -> Result<Vec<(ObjectId, Vec<git2::Oid>)>, git2::Error> {
+    let namespace = project.encode_id();
+    let globs = [
+        format!("refs/namespaces/{}/refs/cobs/{}/*", namespace, typename),
+        format!(
+            "refs/namespaces/{}/refs/remotes/*/cobs/{}/*",
+            namespace, typename
+        ),
+    ];
+    let mut objects: BTreeMap<String, (ObjectId, Vec<git2::Oid>)> = BTreeMap::new();
+
+    for glob in &globs {
+        for reference in repo.references_glob(glob)? {
+            let reference = reference?;
+            let name = reference.name().and_then(|name| name.rsplit('/').next());
+            let id = name.and_then(|id| ObjectId::from_str(id).ok());
+
+            if let (Some(name), Some(id), Some(oid)) = (name, id, reference.target()) {
+                objects
+                    .entry(name.to_owned())
+                    .or_insert_with(|| (id, Vec::new()))
+                    .1
+                    .push(oid);
+            }
+        }
+    }
+
+    Ok(objects
+        .into_iter()
+        .map(|(_, (id, mut tips))| {
+            tips.sort();
+            tips.dedup();
+
+            (id, tips)
+        })
+        .collect())
+}
+
+/// Sort objects by the time they were created, ie. the time of their first change, which
+/// is the commit the object id refers to. Unlike the objects' timestamps, this doesn't
+/// require materializing them. Objects whose first change can't be read are sorted first.
+pub fn sort_by_creation(repo: &git2::Repository, objects: &mut [(ObjectId, Vec<git2::Oid>)]) {
+    objects.sort_by_cached_key(|(id, _)| {
+        git2::Oid::from_str(&id.to_string())
+            .and_then(|oid| repo.find_commit(oid))
+            .map(|commit| commit.time().seconds())
+            .unwrap_or_default()
+    });
+}
+
+/// Read a listing. Returns an empty listing if there is none, or if it can't be read.
+pub fn read(dir: &Path, project: &Urn, typename: &TypeName) -> Listing {
+    fs::read(path(dir, project, typename))
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Write a listing.
+pub fn write(dir: &Path, project: &Urn, typename: &TypeName, listing: &Listing) -> io::Result<()> {
+    let path = path(dir, project, typename);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec(listing)?)
+}
+
+/// Remove all listings of a project. Returns the number of listings removed.
+pub fn clear(dir: &Path, project: &Urn) -> io::Result<usize> {
+    let dir = dir.join(project.encode_id());
+    let count = match fs::read_dir(&dir) {
+        Ok(entries) => entries.count(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    fs::remove_dir_all(dir)?;
+
+    Ok(count)
+}
+
+/// A cached author profile.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedProfile {
+    /// Tip of the author's identity when the profile was cached.
+    tip: String,
+    profile: AuthorProfile,
+}
+
+/// Author profiles, cached across projects. Changes are kept in memory until
+/// [`Authors::write`] is called.
+#[derive(Debug)]
+pub struct Authors {
+    path: PathBuf,
+    repo: Option<git2::Repository>,
+    profiles: BTreeMap<String, CachedProfile>,
+    changed: bool,
+}
+
+impl Authors {
+    /// Load the author cache.
+    pub fn load(dir: &Path, monorepo: &Path) -> Self {
+        let path = dir.join(AUTHORS_FILE);
+        let profiles = fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            repo: git2::Repository::open_bare(monorepo).ok(),
+            profiles,
+            changed: false,
+        }
+    }
+
+    /// Resolve an author's profile, from the cache if the author's identity hasn't
+    /// changed since it was cached.
+    pub fn resolve<S: AsRef<ReadOnly>>(
+        &mut self,
+        author: &mut Author,
+        storage: &S,
+    ) -> Result<(), ResolveError> {
+        if author.profile.is_some() {
+            return Ok(());
+        }
+        if let Some(profile) = self.get(author.urn()) {
+            author.profile = Some(profile);
+            return Ok(());
+        }
+        timing::measure(Category::Storage, || author.resolve(storage))?;
+        self.insert(author);
+
+        Ok(())
+    }
+
+    /// Get the cached profile of an author, if the author's identity hasn't changed
+    /// since it was cached.
+    pub fn get(&self, urn: &Urn) -> Option<AuthorProfile> {
+        let tip = self.tip(urn)?;
+        let cached = self.profiles.get(&urn.to_string())?;
+
+        (cached.tip == tip).then(|| cached.profile.clone())
+    }
+
+    /// Cache the profile of a resolved author.
+    pub fn insert(&mut self, author: &Author) {
+        if let (Some(tip), Some(profile)) = (self.tip(author.urn()), &author.profile) {
+            self.profiles.insert(
+                author.urn().to_string(),
+                CachedProfile {
+                    tip,
+                    profile: profile.clone(),
+                },
+            );
+            self.changed = true;
+        }
+    }
+
+    /// Write the cache, if profiles were added to it.
+    pub fn write(&self) -> io::Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec(&self.profiles)?)
+    }
+
+    /// Get the tip of an identity.
+    fn tip(&self, urn: &Urn) -> Option<String> {
+        let repo = self.repo.as_ref()?;
+        let name = format!("refs/namespaces/{}/refs/rad/id", urn.encode_id());
+        let oid = repo.refname_to_id(&name).ok()?;
+
+        Some(oid.to_string())
+    }
+}
+
+fn strings(oids: &[git2::Oid]) -> Vec<String> {
+    oids.iter().map(|o| o.to_string()).collect()
+}
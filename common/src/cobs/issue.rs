@@ -1,6 +1,6 @@
 #![allow(clippy::large_enum_variant)]
 use std::collections::{HashMap, HashSet};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::ops::{ControlFlow, Deref};
 use std::str::FromStr;
 
@@ -105,6 +105,14 @@ impl Issue {
         self.state
     }
 
+    /// Get the name of the issue's state, as shown to users: `open` or `closed`.
+    pub fn state_name(&self) -> &'static str {
+        match self.state {
+            State::Open => "open",
+            State::Closed { .. } => "closed",
+        }
+    }
+
     pub fn description(&self) -> &str {
         &self.comment.body
     }
@@ -161,6 +169,12 @@ impl Cob for Issue {
 
         Ok(issue)
     }
+
+    fn from_doc(doc: &Automerge) -> Result<Self, anyhow::Error> {
+        let issue = Issue::try_from(doc.clone())?;
+
+        Ok(issue)
+    }
 }
 
 impl TryFrom<&History> for Issue {
@@ -356,14 +370,13 @@ impl<'a> IssueStore<'a> {
         Ok(())
     }
 
-    pub fn all(&self, project: &Urn) -> Result<Vec<(IssueId, Issue)>, Error> {
-        let cobs = self.store.list(project, &TYPENAME)?;
+    /// Iterate over all issues, from the oldest, materializing them as they are reached.
+    pub fn iter(&self, project: &Urn) -> Result<Objects<'a, 'a, Issue>, Error> {
+        self.store.iter::<Issue>(project)
+    }
 
-        let mut issues = Vec::new();
-        for cob in cobs {
-            let issue: Result<Issue, _> = cob.history().try_into();
-            issues.push((*cob.id(), issue.unwrap()));
-        }
+    pub fn all(&self, project: &Urn) -> Result<Vec<(IssueId, Issue)>, Error> {
+        let mut issues = self.store.all::<Issue>(project)?;
         issues.sort_by_key(|(_, i)| i.timestamp);
 
         Ok(issues)
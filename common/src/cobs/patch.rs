@@ -1,6 +1,6 @@
 #![allow(clippy::too_many_arguments)]
 use std::collections::{HashMap, HashSet};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::fmt;
 use std::ops::{ControlFlow, Deref, RangeInclusive};
 use std::str::FromStr;
@@ -121,6 +121,22 @@ impl Patch {
         matches!(self.state, State::Archived)
     }
 
+    /// Check whether any revision of the patch was merged.
+    pub fn is_merged(&self) -> bool {
+        self.revisions.iter().any(|r| !r.merges.is_empty())
+    }
+
+    /// Get the name of the patch's state, as shown to users: `draft`, `open`, `merged`
+    /// or `archived`.
+    pub fn state_name(&self) -> &'static str {
+        match self.state {
+            State::Draft => "draft",
+            State::Archived => "archived",
+            State::Proposed if self.is_merged() => "merged",
+            State::Proposed => "open",
+        }
+    }
+
     pub fn description(&self) -> &str {
         self.latest().1.description()
     }
@@ -144,6 +160,12 @@ impl Cob for Patch {
     fn from_history(history: &History) -> Result<Self, anyhow::Error> {
         Patch::try_from(history)
     }
+
+    fn from_doc(doc: &Automerge) -> Result<Self, anyhow::Error> {
+        let patch = Patch::try_from(Document::new(doc))?;
+
+        Ok(patch)
+    }
 }
 
 impl TryFrom<Document<'_>> for Patch {
@@ -430,19 +452,54 @@ impl<'a> PatchStore<'a> {
         Ok(merge)
     }
 
+    pub fn check(
+        &self,
+        project: &Urn,
+        patch_id: &PatchId,
+        revision_ix: RevisionIx,
+        name: impl Into<String>,
+        status: CheckStatus,
+        commit: git::Oid,
+        summary: impl Into<String>,
+    ) -> Result<Check, Error> {
+        let timestamp = Timestamp::now();
+        let check = Check {
+            peer: self.peer_id,
+            name: name.into(),
+            status,
+            commit,
+            summary: summary.into(),
+            timestamp,
+        };
+
+        let mut patch = self.get_raw(project, patch_id)?.unwrap();
+        let changes = events::check(&mut patch, revision_ix, &check)?;
+
+        cobs::update(
+            *patch_id,
+            project,
+            "Check revision",
+            changes,
+            &self.whoami,
+            self.store,
+        )?;
+
+        Ok(check)
+    }
+
     pub fn count(&self, project: &Urn) -> Result<usize, Error> {
         let cobs = self.store.list(project, &TYPENAME)?;
 
         Ok(cobs.len())
     }
 
+    /// Iterate over all patchs, from the oldest, materializing them as they are reached.
+    pub fn iter(&self, project: &Urn) -> Result<Objects<'a, 'a, Patch>, Error> {
+        self.store.iter::<Patch>(project)
+    }
+
     pub fn all(&self, project: &Urn) -> Result<Vec<(PatchId, Patch)>, Error> {
-        let mut patches = Vec::new();
-        let cobs = self.store.list(project, &TYPENAME)?;
-        for cob in cobs {
-            let patch: Result<Patch, _> = cob.history().try_into();
-            patches.push((*cob.id(), patch.unwrap()));
-        }
+        let mut patches = self.store.all::<Patch>(project)?;
         patches.sort_by_key(|(_, p)| p.timestamp);
 
         Ok(patches)
@@ -516,6 +573,8 @@ pub struct Revision<T = (), P = PeerId> {
     pub reviews: HashMap<Urn, Review>,
     /// Merges of this revision into other repositories.
     pub merges: Vec<Merge<P>>,
+    /// Checks run on this revision, eg. CI builds.
+    pub checks: Vec<Check<P>>,
     /// Code changeset for this revision.
     pub changeset: T,
     /// When this revision was created.
@@ -540,6 +599,7 @@ impl Revision {
             discussion: Discussion::default(),
             reviews: HashMap::default(),
             merges: Vec::default(),
+            checks: Vec::default(),
             changeset: (),
             timestamp,
         }
@@ -567,6 +627,10 @@ impl Revision {
             self.discussion.is_empty(),
             "Cannot put revision with non-empty discussion"
         );
+        assert!(
+            self.checks.is_empty(),
+            "Cannot put revision with non-empty checks"
+        );
 
         tx.put(&id, "id", self.id.to_string())?;
         tx.put(&id, "peer", self.peer.to_string())?;
@@ -578,6 +642,7 @@ impl Revision {
         tx.put_object(&id, "discussion", ObjType::List)?;
         tx.put_object(&id, "reviews", ObjType::Map)?;
         tx.put_object(&id, "merges", ObjType::List)?;
+        tx.put_object(&id, "checks", ObjType::List)?;
         tx.put(&id, "timestamp", self.timestamp)?;
 
         Ok(())
@@ -607,6 +672,60 @@ pub struct Merge<P = PeerId> {
     pub timestamp: Timestamp,
 }
 
+/// Outcome of a check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// The check passed.
+    Success,
+    /// The check failed.
+    Failure,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Success => write!(f, "success"),
+            Self::Failure => write!(f, "failure"),
+        }
+    }
+}
+
+impl From<CheckStatus> for ScalarValue {
+    fn from(status: CheckStatus) -> Self {
+        ScalarValue::from(status.to_string())
+    }
+}
+
+impl<'a> FromValue<'a> for CheckStatus {
+    fn from_value(value: Value<'a>) -> Result<Self, ValueError> {
+        let status = value.to_str().ok_or(ValueError::InvalidType)?;
+
+        match status {
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            _ => Err(ValueError::InvalidValue(value.to_string())),
+        }
+    }
+}
+
+/// A check run on a patch revision, eg. a CI build.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check<P = PeerId> {
+    /// Peer that ran the check.
+    pub peer: P,
+    /// Name of the check, eg. `ci`.
+    pub name: String,
+    /// Outcome of the check.
+    pub status: CheckStatus,
+    /// Commit the check was run on.
+    pub commit: git::Oid,
+    /// Summary of the outcome, eg. the command that failed.
+    pub summary: String,
+    /// When the check was completed.
+    pub timestamp: Timestamp,
+}
+
 /// A patch review verdict.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -752,6 +871,11 @@ mod lookup {
         let discussion: Discussion =
             doc.list(&revision_id, "discussion", shared::lookup::thread)?;
         let merges: Vec<Merge> = doc.list(&revision_id, "merges", self::merge)?;
+        // Nb. Revisions created before checks were introduced have no checks list.
+        let checks: Vec<Check> = match doc.list(&revision_id, "checks", self::check) {
+            Err(DocumentError::PropertyNotFound(_)) => Vec::new(),
+            result => result?,
+        };
 
         // Reviews.
         let mut reviews: HashMap<Urn, Review> = HashMap::new();
@@ -771,11 +895,30 @@ mod lookup {
             discussion,
             reviews,
             merges,
+            checks,
             changeset: (),
             timestamp,
         })
     }
 
+    pub fn check(doc: Document, obj_id: &automerge::ObjId) -> Result<Check, DocumentError> {
+        let peer = doc.val(&obj_id, "peer")?;
+        let name = doc.val(&obj_id, "name")?;
+        let status = doc.val(&obj_id, "status")?;
+        let commit = doc.val(&obj_id, "commit")?;
+        let summary = doc.val(&obj_id, "summary")?;
+        let timestamp = doc.val(&obj_id, "timestamp")?;
+
+        Ok(Check {
+            peer,
+            name,
+            status,
+            commit,
+            summary,
+            timestamp,
+        })
+    }
+
     pub fn merge(doc: Document, obj_id: &automerge::ObjId) -> Result<Merge, DocumentError> {
         let peer = doc.val(&obj_id, "peer")?;
         let commit = doc.val(&obj_id, "commit")?;
@@ -1067,6 +1210,44 @@ mod events {
 
         Ok(EntryContents::Automerge(change))
     }
+
+    pub fn check(
+        patch: &mut Automerge,
+        revision_ix: RevisionIx,
+        check: &Check,
+    ) -> Result<EntryContents, AutomergeError> {
+        patch
+            .transact_with::<_, _, AutomergeError, _, ()>(
+                |_| CommitOptions::default().with_message("Check revision".to_owned()),
+                |tx| {
+                    let (_, obj_id) = tx.get(ObjId::Root, "patch")?.unwrap();
+                    let (_, revisions_id) = tx.get(&obj_id, "revisions")?.unwrap();
+                    let (_, revision_id) = tx.get(&revisions_id, revision_ix)?.unwrap();
+                    // Nb. Revisions created before checks were introduced have no checks list.
+                    let checks_id = match tx.get(&revision_id, "checks")? {
+                        Some((_, checks_id)) => checks_id,
+                        None => tx.put_object(&revision_id, "checks", ObjType::List)?,
+                    };
+
+                    let length = tx.length(&checks_id);
+                    let check_id = tx.insert_object(&checks_id, length, ObjType::Map)?;
+
+                    tx.put(&check_id, "peer", check.peer.to_string())?;
+                    tx.put(&check_id, "name", check.name.as_str())?;
+                    tx.put(&check_id, "status", check.status)?;
+                    tx.put(&check_id, "commit", check.commit.to_string())?;
+                    tx.put(&check_id, "summary", check.summary.as_str())?;
+                    tx.put(&check_id, "timestamp", check.timestamp)?;
+
+                    Ok(())
+                },
+            )
+            .map_err(|failure| failure.error)?;
+
+        let change = patch.get_last_local_change().unwrap().raw_bytes().to_vec();
+
+        Ok(EntryContents::Automerge(change))
+    }
 }
 
 #[cfg(test)]
@@ -1143,6 +1324,48 @@ mod test {
         assert_eq!(merges[0].commit, base);
     }
 
+    #[test]
+    fn test_patch_check() {
+        let (storage, profile, whoami, project) = test::setup::profile();
+        let cobs = Store::new(whoami, profile.paths(), &storage);
+        let patches = cobs.patches();
+        let target = MergeTarget::Upstream;
+        let oid = git::Oid::from(git2::Oid::zero());
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let patch_id = patches
+            .create(
+                &project.urn(),
+                "My first patch",
+                "Blah blah blah.",
+                target,
+                base,
+                oid,
+                &[],
+            )
+            .unwrap();
+
+        patches
+            .check(
+                &project.urn(),
+                &patch_id,
+                0,
+                "ci",
+                CheckStatus::Failure,
+                oid,
+                "`cargo test` failed",
+            )
+            .unwrap();
+        let patch = patches.get(&project.urn(), &patch_id).unwrap().unwrap();
+        let checks = patch.revisions.head.checks;
+
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].peer, *storage.peer_id());
+        assert_eq!(checks[0].name, "ci");
+        assert_eq!(checks[0].status, CheckStatus::Failure);
+        assert_eq!(checks[0].commit, oid);
+        assert_eq!(checks[0].summary, "`cargo test` failed");
+    }
+
     #[test]
     fn test_patch_review() {
         let (storage, profile, whoami, project) = test::setup::profile();
@@ -0,0 +1,348 @@
+#![allow(clippy::large_enum_variant)]
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io;
+use std::ops::{ControlFlow, Deref};
+use std::path::Path;
+use std::str::FromStr;
+
+use automerge::{Automerge, AutomergeError, ObjType};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+
+use librad::collaborative_objects::{
+    CollaborativeObjects, EntryContents, History, NewObjectSpec, ObjectId, TypeName,
+};
+use librad::git::identities::local::LocalIdentity;
+use librad::git::Urn;
+use radicle_git_ext as git;
+
+use crate::cobs::shared::*;
+use crate::sshsig;
+
+lazy_static! {
+    pub static ref TYPENAME: TypeName = FromStr::from_str("xyz.radicle.release").unwrap();
+}
+
+/// Namespace of artifact signatures. This is the default namespace of `ssh-keygen -Y`
+/// for files, so that signatures can also be checked with `ssh-keygen -Y verify -n file`.
+pub const SIGNATURE_NAMESPACE: &str = "file";
+
+/// Identifier for a release.
+pub type ReleaseId = ObjectId;
+
+/// A file distributed with a release, eg. a binary or a source archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// File name of the artifact.
+    pub name: String,
+    /// SHA-256 checksum of the artifact, hex-encoded.
+    pub sha256: String,
+}
+
+impl Artifact {
+    /// Compute the artifact of a file.
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?
+            .to_string_lossy()
+            .to_string();
+
+        Ok(Self {
+            name,
+            sha256: checksum(path)?,
+        })
+    }
+}
+
+/// Compute the SHA-256 checksum of a file, hex-encoded.
+pub fn checksum(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = sha2::Sha256::new();
+
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Get the manifest of the given artifacts, in the format of `sha256sum`. This is what
+/// is signed when a release is created.
+pub fn manifest(artifacts: &[Artifact]) -> String {
+    let mut lines = artifacts
+        .iter()
+        .map(|a| format!("{}  {}\n", a.sha256, a.name))
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.concat()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub author: Author,
+    /// Name of the release tag, eg. `v1.0.0`.
+    pub tag: String,
+    /// Commit the tag points to.
+    pub commit: git::Oid,
+    pub notes: String,
+    pub artifacts: Vec<Artifact>,
+    /// Armored SSH signature of the artifact manifest, made with the author's device key.
+    pub signature: String,
+    pub timestamp: Timestamp,
+}
+
+impl Release {
+    pub fn author(&self) -> &Author {
+        &self.author
+    }
+
+    pub fn manifest(&self) -> String {
+        manifest(&self.artifacts)
+    }
+
+    /// Verify the signature of the artifact manifest. Returns the parsed signature, whose
+    /// signer should then be checked.
+    pub fn verify(&self) -> anyhow::Result<sshsig::Signature> {
+        let signature = sshsig::Signature::from_armored(&self.signature)?;
+
+        if signature.namespace != SIGNATURE_NAMESPACE {
+            anyhow::bail!("unexpected signature namespace `{}`", signature.namespace);
+        }
+        signature.verify(self.manifest().as_bytes())?;
+
+        Ok(signature)
+    }
+}
+
+impl Cob for Release {
+    fn type_name() -> &'static TypeName {
+        &TYPENAME
+    }
+
+    fn from_history(history: &History) -> Result<Self, anyhow::Error> {
+        let doc = history.traverse(Automerge::new(), |mut doc, entry| {
+            match entry.contents() {
+                EntryContents::Automerge(bytes) => {
+                    match automerge::Change::from_bytes(bytes.clone()) {
+                        Ok(change) => {
+                            doc.apply_changes([change]).ok();
+                        }
+                        Err(_err) => {
+                            // Ignore
+                        }
+                    }
+                }
+            }
+            ControlFlow::Continue(doc)
+        });
+        let release = Release::try_from(doc)?;
+
+        Ok(release)
+    }
+
+    fn from_doc(doc: &Automerge) -> Result<Self, anyhow::Error> {
+        let release = Release::try_from(doc.clone())?;
+
+        Ok(release)
+    }
+}
+
+impl TryFrom<&History> for Release {
+    type Error = anyhow::Error;
+
+    fn try_from(history: &History) -> Result<Self, Self::Error> {
+        Release::from_history(history)
+    }
+}
+
+impl TryFrom<Automerge> for Release {
+    type Error = DocumentError;
+
+    fn try_from(doc: Automerge) -> Result<Self, Self::Error> {
+        let doc = Document::new(&doc);
+        let (_obj, obj_id) = doc.get(automerge::ObjId::Root, "release")?;
+        let peer = doc.val(&obj_id, "peer")?;
+        let author = doc
+            .val(&obj_id, "author")
+            .map(|urn: Urn| Author::new(urn, peer))?;
+        let tag = doc.val(&obj_id, "tag")?;
+        let commit = doc.val(&obj_id, "commit")?;
+        let notes = doc.val(&obj_id, "notes")?;
+        let signature = doc.val(&obj_id, "signature")?;
+        let timestamp = doc.val(&obj_id, "timestamp")?;
+        let artifacts = doc.list(&obj_id, "artifacts", |doc, id| {
+            Ok(Artifact {
+                name: doc.val(id, "name")?,
+                sha256: doc.val(id, "sha256")?,
+            })
+        })?;
+
+        Ok(Self {
+            author,
+            tag,
+            commit,
+            notes,
+            artifacts,
+            signature,
+            timestamp,
+        })
+    }
+}
+
+pub struct ReleaseStore<'a> {
+    store: &'a Store<'a>,
+}
+
+impl<'a> Deref for ReleaseStore<'a> {
+    type Target = Store<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.store
+    }
+}
+
+impl<'a> ReleaseStore<'a> {
+    pub fn new(store: &'a Store<'a>) -> Self {
+        Self { store }
+    }
+
+    pub fn create(
+        &self,
+        project: &Urn,
+        tag: &str,
+        commit: git::Oid,
+        notes: &str,
+        artifacts: &[Artifact],
+        signature: &str,
+    ) -> Result<ReleaseId, Error> {
+        let author = self.author();
+        let timestamp = Timestamp::now();
+        let history = events::create(&author, tag, commit, notes, artifacts, signature, timestamp)?;
+
+        cobs::create(history, project, &self.whoami, self.store)
+    }
+
+    pub fn all(&self, project: &Urn) -> Result<Vec<(ReleaseId, Release)>, Error> {
+        let cobs = self.store.list(project, &TYPENAME)?;
+
+        let mut releases = Vec::new();
+        for cob in cobs {
+            let release: Result<Release, _> = cob.history().try_into();
+            if let Ok(release) = release {
+                releases.push((*cob.id(), release));
+            }
+        }
+        releases.sort_by_key(|(_, r)| r.timestamp);
+
+        Ok(releases)
+    }
+
+    pub fn get(&self, namespace: &Urn, id: &ObjectId) -> anyhow::Result<Option<Release>> {
+        self.store.get::<Release>(namespace, id)
+    }
+
+    /// Get the latest release of the given tag.
+    pub fn find(&self, project: &Urn, tag: &str) -> Result<Option<(ReleaseId, Release)>, Error> {
+        let release = self
+            .all(project)?
+            .into_iter()
+            .rev()
+            .find(|(_, r)| r.tag == tag);
+
+        Ok(release)
+    }
+}
+
+mod cobs {
+    use super::*;
+
+    pub(super) fn create(
+        history: EntryContents,
+        project: &Urn,
+        whoami: &LocalIdentity,
+        store: &CollaborativeObjects,
+    ) -> Result<ReleaseId, Error> {
+        let cob = store.create(
+            whoami,
+            project,
+            NewObjectSpec {
+                typename: TYPENAME.clone(),
+                message: Some("Create release".to_owned()),
+                history,
+            },
+        )?;
+
+        Ok(*cob.id())
+    }
+}
+
+mod events {
+    use super::*;
+    use automerge::{
+        transaction::{CommitOptions, Transactable},
+        ObjId,
+    };
+
+    pub fn create(
+        author: &Author,
+        tag: &str,
+        commit: git::Oid,
+        notes: &str,
+        artifacts: &[Artifact],
+        signature: &str,
+        timestamp: Timestamp,
+    ) -> Result<EntryContents, AutomergeError> {
+        let mut doc = Automerge::new();
+        let _release = doc
+            .transact_with::<_, _, AutomergeError, _, ()>(
+                |_| CommitOptions::default().with_message("Create release".to_owned()),
+                |tx| {
+                    let release = tx.put_object(ObjId::Root, "release", ObjType::Map)?;
+
+                    tx.put(&release, "tag", tag)?;
+                    tx.put(&release, "commit", commit.to_string())?;
+                    tx.put(&release, "notes", notes.trim())?;
+                    tx.put(&release, "signature", signature)?;
+                    tx.put(&release, "author", author.urn().to_string())?;
+                    tx.put(&release, "peer", author.peer.default_encoding())?;
+                    tx.put(&release, "timestamp", timestamp)?;
+
+                    let list = tx.put_object(&release, "artifacts", ObjType::List)?;
+                    for (i, artifact) in artifacts.iter().enumerate() {
+                        let id = tx.insert_object(&list, i, ObjType::Map)?;
+
+                        tx.put(&id, "name", artifact.name.as_str())?;
+                        tx.put(&id, "sha256", artifact.sha256.as_str())?;
+                    }
+
+                    Ok(release)
+                },
+            )
+            .map_err(|failure| failure.error)?
+            .result;
+
+        Ok(EntryContents::Automerge(doc.save_incremental()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let artifacts = vec![
+            Artifact {
+                name: String::from("rad.tar.gz"),
+                sha256: String::from("bb"),
+            },
+            Artifact {
+                name: String::from("rad.zip"),
+                sha256: String::from("aa"),
+            },
+        ];
+        assert_eq!(manifest(&artifacts), "aa  rad.zip\nbb  rad.tar.gz\n");
+        assert_eq!(manifest(&[]), "");
+    }
+}
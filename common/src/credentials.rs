@@ -0,0 +1,79 @@
+//! Seed and GitHub credentials.
+//!
+//! Credentials are stored per seed host in the profile, in a file separate from the
+//! profile configuration, and readable only by the user. The GitHub token, used to
+//! bridge patches to pull requests, is stored in the same file.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use librad::profile::Profile;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::profile;
+
+/// Credentials file name, in the profile scope.
+pub const FILE_NAME: &str = "credentials.toml";
+/// User name sent along with tokens, for seeds that expect HTTP basic authentication.
+pub const USERNAME: &str = "rad";
+
+/// Credential of a seed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credential {
+    /// Access token.
+    pub token: String,
+}
+
+/// Credentials of all seeds, keyed by seed host, and of GitHub.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Credentials {
+    #[serde(default)]
+    pub seed: BTreeMap<String, Credential>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github: Option<Credential>,
+}
+
+impl Credentials {
+    /// Load the credentials of the profile. If there are none yet, returns empty credentials.
+    pub fn load(profile: &Profile) -> Result<Self, anyhow::Error> {
+        profile::load_toml(profile, FILE_NAME)
+    }
+
+    /// Write the credentials. The file is only readable by the user.
+    pub fn write(&self, profile: &Profile) -> Result<(), anyhow::Error> {
+        profile::store_toml(profile, FILE_NAME, self)
+    }
+
+    pub fn path(profile: &Profile) -> PathBuf {
+        profile::scoped_path(profile, FILE_NAME)
+    }
+
+    /// Get the credential of a seed host.
+    pub fn get(&self, host: &str) -> Option<&Credential> {
+        self.seed.get(host)
+    }
+
+    /// Get the credential of the seed at the given URL.
+    pub fn for_url(&self, url: &Url) -> Option<&Credential> {
+        url.host_str().and_then(|host| self.get(host))
+    }
+}
+
+/// Git remote callbacks that authenticate with the given credential, if the remote asks
+/// for credentials.
+pub fn callbacks(credential: Option<&Credential>) -> git2::RemoteCallbacks<'_> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+
+    if let Some(credential) = credential {
+        callbacks.credentials(move |_url, _username, allowed| {
+            if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+                git2::Cred::userpass_plaintext(USERNAME, &credential.token)
+            } else {
+                Err(git2::Error::from_str(
+                    "seed requested an unsupported credential type",
+                ))
+            }
+        });
+    }
+    callbacks
+}
@@ -0,0 +1,86 @@
+//! Exit codes of commands, so that scripts can tell failures apart without parsing error
+//! messages.
+//!
+//! | Code | Meaning                                                          |
+//! |------|------------------------------------------------------------------|
+//! | 0    | Success                                                          |
+//! | 1    | Any other failure, eg. invalid arguments                         |
+//! | 2    | Not in a project, ie. a working copy with a `rad` remote         |
+//! | 3    | Not found, eg. a project, patch or issue                         |
+//! | 4    | Aborted by the user, or a confirmation couldn't be asked         |
+//! | 5    | Network failure, eg. seeds couldn't be reached, or offline mode  |
+use std::io;
+
+use crate::args;
+
+/// Exit code of a command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Code {
+    Success = 0,
+    Failure = 1,
+    NotProject = 2,
+    NotFound = 3,
+    Aborted = 4,
+    Network = 5,
+}
+
+impl From<Code> for i32 {
+    fn from(code: Code) -> Self {
+        code as i32
+    }
+}
+
+/// An error that makes a command exit with a specific code.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("this command must be run in the context of a project")]
+    NotProject,
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Aborted(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("{0} requires the network, but offline mode is on")]
+    Offline(String),
+}
+
+impl Error {
+    pub fn code(&self) -> Code {
+        match self {
+            Self::NotProject => Code::NotProject,
+            Self::NotFound(_) => Code::NotFound,
+            Self::Aborted(_) => Code::Aborted,
+            Self::Network(_) | Self::Offline(_) => Code::Network,
+        }
+    }
+}
+
+/// Get the exit code of a command that failed with the given error.
+pub fn code(err: &anyhow::Error) -> Code {
+    if let Some(err) = err.downcast_ref::<Error>() {
+        return err.code();
+    }
+    if let Some(args::Error::WithHint { err, .. }) = err.downcast_ref::<args::Error>() {
+        return code(err);
+    }
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<Error>() {
+            return err.code();
+        }
+        if let Some(err) = cause.downcast_ref::<io::Error>() {
+            if matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::AddrNotAvailable
+                    | io::ErrorKind::TimedOut
+            ) {
+                return Code::Network;
+            }
+        }
+    }
+    Code::Failure
+}
@@ -16,8 +16,8 @@ use librad::reflike;
 use librad::{crypto::BoxedSigner, PeerId};
 
 pub use git2::{
-    build::CheckoutBuilder, AnnotatedCommit, Commit, Direction, ErrorCode, MergeAnalysis,
-    MergeOptions, Oid, Reference, Repository, Signature,
+    build::CheckoutBuilder, AnnotatedCommit, BranchType, Commit, Direction, ErrorCode,
+    MergeAnalysis, MergeOptions, Oid, Progress, Reference, Repository, Signature,
 };
 pub use librad::git::local::transport;
 pub use librad::git::types::remote::LocalFetchspec;
@@ -28,10 +28,14 @@ pub use lnk_identities::git::set_upstream;
 use crate::keys;
 
 pub const CONFIG_COMMIT_GPG_SIGN: &str = "commit.gpgsign";
+pub const CONFIG_TAG_GPG_SIGN: &str = "tag.gpgsign";
 pub const CONFIG_SIGNING_KEY: &str = "user.signingkey";
 pub const CONFIG_GPG_FORMAT: &str = "gpg.format";
 pub const CONFIG_GPG_SSH_PROGRAM: &str = "gpg.ssh.program";
 pub const CONFIG_GPG_SSH_ALLOWED_SIGNERS: &str = "gpg.ssh.allowedSignersFile";
+pub const CONFIG_COMMIT_GRAPH: &str = "core.commitGraph";
+/// Program installed as `gpg.ssh.program` to sign commits with the radicle key.
+pub const RAD_SSH_PROGRAM: &str = "rad-ssh-keygen";
 
 /// Minimum required git version.
 pub const VERSION_REQUIRED: Version = Version {
@@ -113,12 +117,50 @@ pub fn repository() -> Result<Repository, anyhow::Error> {
     }
 }
 
+/// Get the common git dir of a repository, ie. the directory holding the configuration,
+/// refs and hooks shared by all of its worktrees. For a linked worktree, ie. one created
+/// with `git worktree add`, this is the git dir of the main worktree, otherwise it is the
+/// repository's own git dir.
+pub fn common_dir(repo: &Repository) -> PathBuf {
+    let path = repo.path();
+
+    if repo.is_worktree() {
+        if let Ok(common) = std::fs::read_to_string(path.join("commondir")) {
+            let common = path.join(common.trim());
+
+            return common.canonicalize().unwrap_or(common);
+        }
+    }
+    path.to_path_buf()
+}
+
 /// Execute a git command by spawning a child process.
 pub fn git<S: AsRef<std::ffi::OsStr>>(
     repo: &std::path::Path,
     args: impl IntoIterator<Item = S>,
 ) -> Result<String, anyhow::Error> {
-    let output = Command::new("git").current_dir(repo).args(args).output()?;
+    git_with_config(repo, args, &[])
+}
+
+/// Execute a git command by spawning a child process, with the given configuration.
+/// The configuration is passed through the environment, so that secrets, eg. access
+/// tokens, don't show up in the command line of the process.
+pub fn git_with_config<S: AsRef<std::ffi::OsStr>>(
+    repo: &std::path::Path,
+    args: impl IntoIterator<Item = S>,
+    config: &[(&str, String)],
+) -> Result<String, anyhow::Error> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(repo).args(args);
+
+    if !config.is_empty() {
+        cmd.env("GIT_CONFIG_COUNT", config.len().to_string());
+    }
+    for (i, (key, value)) in config.iter().enumerate() {
+        cmd.env(format!("GIT_CONFIG_KEY_{}", i), key);
+        cmd.env(format!("GIT_CONFIG_VALUE_{}", i), value);
+    }
+    let output = cmd.output()?;
 
     if output.status.success() {
         let out = if output.stdout.is_empty() {
@@ -135,6 +177,31 @@ pub fn git<S: AsRef<std::ffi::OsStr>>(
     )))
 }
 
+/// Write the commit-graph of the given repo, from all its refs, and enable its use.
+/// The commit-graph speeds up history traversal, eg. merge-base and ahead/behind
+/// computations, by git as well as libgit2.
+///
+/// Commits that are already in the commit-graph are read from it, so updating it, eg.
+/// after a fetch, is cheap.
+pub fn write_commit_graph(repo: &Path) -> Result<(), anyhow::Error> {
+    git(repo, ["config", "--local", CONFIG_COMMIT_GRAPH, "true"])?;
+    git(
+        repo,
+        ["commit-graph", "write", "--reachable", "--no-progress"],
+    )?;
+
+    Ok(())
+}
+
+/// Check whether the given repo has a commit-graph. Nb. only a single commit-graph file
+/// is used by libgit2, not split commit-graph chains.
+pub fn has_commit_graph(repo: &Path) -> bool {
+    repo.join("objects")
+        .join("info")
+        .join("commit-graph")
+        .is_file()
+}
+
 /// Configure SSH signing in the given git repo, for the given peer.
 pub fn configure_signing(repo: &Path, peer_id: &PeerId) -> Result<(), anyhow::Error> {
     let key = keys::to_ssh_key(peer_id)?;
@@ -159,6 +226,86 @@ pub fn configure_signing(repo: &Path, peer_id: &PeerId) -> Result<(), anyhow::Er
     Ok(())
 }
 
+/// Configure git to sign commits and tags with the radicle key of the given peer, using
+/// [`RAD_SSH_PROGRAM`], so that the key doesn't need to be in ssh-agent. Configures the
+/// given repo, or the global git config if no repo is given.
+pub fn configure_rad_signing(repo: Option<&Path>, peer_id: &PeerId) -> Result<(), anyhow::Error> {
+    let key = keys::to_ssh_key(peer_id)?;
+    let (path, scope) = match repo {
+        Some(repo) => (repo, "--local"),
+        None => (Path::new("."), "--global"),
+    };
+
+    git(path, ["config", scope, CONFIG_SIGNING_KEY, &key])?;
+    git(path, ["config", scope, CONFIG_GPG_FORMAT, "ssh"])?;
+    git(
+        path,
+        ["config", scope, CONFIG_GPG_SSH_PROGRAM, RAD_SSH_PROGRAM],
+    )?;
+    git(path, ["config", scope, CONFIG_COMMIT_GPG_SIGN, "true"])?;
+    git(path, ["config", scope, CONFIG_TAG_GPG_SIGN, "true"])?;
+
+    Ok(())
+}
+
+/// Configure git to sign commits and tags with the given SSH key, via ssh-agent, eg. a
+/// FIDO2 key, given as printed by `ssh-add -L`. Configures the given repo, or the global
+/// git config if no repo is given. Returns the keys set, with their values.
+pub fn configure_agent_signing(
+    repo: Option<&Path>,
+    key: &str,
+) -> Result<Vec<(&'static str, String)>, anyhow::Error> {
+    let (path, scope) = match repo {
+        Some(repo) => (repo, "--local"),
+        None => (Path::new("."), "--global"),
+    };
+    let config = vec![
+        (CONFIG_SIGNING_KEY, format!("key::{}", key)),
+        (CONFIG_GPG_FORMAT, String::from("ssh")),
+        (CONFIG_GPG_SSH_PROGRAM, String::from("ssh-keygen")),
+        (CONFIG_COMMIT_GPG_SIGN, String::from("true")),
+        (CONFIG_TAG_GPG_SIGN, String::from("true")),
+    ];
+    for (key, value) in &config {
+        git(path, ["config", scope, *key, value.as_str()])?;
+    }
+    Ok(config)
+}
+
+/// Install a git hook in the given repository, eg. `pre-push`.
+/// If a hook with the same name exists, it is only overwritten if it contains `marker`,
+/// ie. if it was installed by us. Returns the path of the hook.
+///
+/// Nb. Hooks are shared by all worktrees, so they are installed in the common git dir.
+pub fn install_hook(
+    repo: &Repository,
+    name: &str,
+    script: &str,
+    marker: &str,
+) -> Result<PathBuf, anyhow::Error> {
+    let dir = common_dir(repo).join("hooks");
+    let path = dir.join(name);
+
+    match std::fs::read_to_string(&path) {
+        Ok(existing) if !existing.contains(marker) => {
+            anyhow::bail!("a `{}` hook already exists at {}", name, path.display());
+        }
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, script)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
 /// Write a `.gitsigners` file in the given repository.
 /// Fails if the file already exists.
 pub fn write_gitsigners<'a>(
@@ -253,9 +400,12 @@ pub fn remotes(repo: &git2::Repository) -> anyhow::Result<Vec<(String, PeerId)>>
 }
 
 /// Get the repository's "rad" remote.
+/// Within a linked worktree, the remote is looked up in the common git dir, if the
+/// worktree's configuration doesn't have it.
 pub fn rad_remote(repo: &Repository) -> anyhow::Result<Remote<LocalUrl>> {
     match Remote::<LocalUrl>::find(repo, reflike!("rad")) {
         Ok(Some(remote)) => Ok(remote),
+        Ok(None) if repo.is_worktree() => rad_remote(&Repository::open(common_dir(repo))?),
         Ok(None) => Err(anyhow!(
             "could not find radicle remote in git config. Did you forget to run `rad init`?"
         )),
@@ -321,6 +471,28 @@ pub fn fetch_remote(
     Ok(())
 }
 
+/// Progress of a git transfer. Unlike [`Progress`], it can be sent across threads.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub indexed_deltas: usize,
+    pub total_deltas: usize,
+    pub received_bytes: usize,
+}
+
+impl From<Progress<'_>> for TransferProgress {
+    fn from(p: Progress<'_>) -> Self {
+        Self {
+            received_objects: p.received_objects(),
+            total_objects: p.total_objects(),
+            indexed_deltas: p.indexed_deltas(),
+            total_deltas: p.total_deltas(),
+            received_bytes: p.received_bytes(),
+        }
+    }
+}
+
 /// Clone the given repository via `git clone` into a directory.
 pub fn clone(repo: &str, destination: &Path) -> Result<String, anyhow::Error> {
     git(
@@ -0,0 +1,381 @@
+//! Minimal client of the GitLab REST API, covering what is needed to import the issues
+//! and merge requests of a GitLab project.
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::args::Error;
+use crate::cobs::Label;
+
+/// Environment variable holding the GitLab access token.
+pub const TOKEN_ENV: &str = "GITLAB_TOKEN";
+/// Host of projects given without one.
+pub const DEFAULT_HOST: &str = "gitlab.com";
+
+/// Number of items requested per page, which is the maximum allowed.
+const PER_PAGE: usize = 100;
+
+/// A GitLab project, eg. `gitlab.com/group/project`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// Base URL of the GitLab instance, eg. `https://gitlab.com`.
+    pub host: String,
+    /// Path of the project, including its groups, eg. `group/project`.
+    pub path: String,
+}
+
+impl Project {
+    /// Web URL of the project.
+    pub fn url(&self) -> String {
+        format!("{}/{}", self.host, self.path)
+    }
+
+    /// Git URL of the project.
+    pub fn git_url(&self) -> String {
+        format!("{}.git", self.url())
+    }
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.url())
+    }
+}
+
+impl FromStr for Project {
+    type Err = anyhow::Error;
+
+    /// Parse a project given as `<group>/<project>` on `gitlab.com`, or as the URL of
+    /// the project on any GitLab instance, eg. `https://gitlab.example.com/group/project`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().trim_end_matches('/');
+        let s = s.strip_suffix(".git").unwrap_or(s);
+        let (host, path) = if s.starts_with("https://") || s.starts_with("http://") {
+            let url = url::Url::parse(s).map_err(|_| anyhow!("invalid project URL '{}'", s))?;
+            let host = url
+                .host_str()
+                .ok_or_else(|| anyhow!("invalid project URL '{}': missing host", s))?;
+            let host = match url.port() {
+                Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+                None => format!("{}://{}", url.scheme(), host),
+            };
+            (host, url.path().trim_matches('/').to_owned())
+        } else {
+            (format!("https://{}", DEFAULT_HOST), s.to_owned())
+        };
+
+        if !path.contains('/') || path.split('/').any(|p| p.is_empty()) {
+            anyhow::bail!(
+                "invalid GitLab project '{}': expected `<group>/<project>`, or a project URL",
+                s
+            );
+        }
+        Ok(Self { host, path })
+    }
+}
+
+/// An issue, or a merge request.
+#[derive(Debug, Clone)]
+pub struct Item {
+    /// Project-local id, ie. the number in `#<iid>` or `!<iid>`.
+    pub iid: u64,
+    pub title: String,
+    pub description: String,
+    /// State, eg. `opened`, `closed`, or `merged` for merge requests.
+    pub state: String,
+    labels: Vec<String>,
+    /// Username of the author.
+    pub author: String,
+    /// Link to the item, which also identifies it.
+    pub url: String,
+    /// Creation time, in RFC 3339 format.
+    pub created: String,
+    /// Branch a merge request targets.
+    pub target_branch: Option<String>,
+}
+
+impl Item {
+    fn from_json(val: &serde_json::Value) -> anyhow::Result<Self> {
+        Ok(Self {
+            iid: val["iid"]
+                .as_u64()
+                .ok_or_else(|| anyhow!("missing 'iid' in GitLab response"))?,
+            title: val["title"].as_str().unwrap_or_default().to_owned(),
+            description: val["description"].as_str().unwrap_or_default().to_owned(),
+            state: val["state"].as_str().unwrap_or_default().to_owned(),
+            labels: val["labels"]
+                .as_array()
+                .map(|ls| {
+                    ls.iter()
+                        .filter_map(|l| l.as_str().map(|l| l.to_owned()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            author: author(&val["author"]),
+            url: val["web_url"].as_str().unwrap_or_default().to_owned(),
+            created: val["created_at"].as_str().unwrap_or_default().to_owned(),
+            target_branch: val["target_branch"].as_str().map(|b| b.to_owned()),
+        })
+    }
+
+    /// Description of the imported item, referring to the original, which is how
+    /// items that were already imported are recognized.
+    pub fn imported_description(&self) -> String {
+        let mut body = self.description.trim().to_owned();
+
+        if !body.is_empty() {
+            body.push_str("\n\n---\n");
+        }
+        body.push_str(&format!(
+            "Imported from {}, opened by @{} on {}.",
+            self.url,
+            self.author,
+            date(&self.created)
+        ));
+        body
+    }
+
+    /// Check whether a description is that of an import of the item.
+    pub fn is_imported_as(&self, description: &str) -> bool {
+        description.contains(&format!("Imported from {},", self.url))
+    }
+
+    /// Labels of the item. Whitespace, which labels can't have, is replaced with `-`.
+    pub fn labels(&self) -> Vec<Label> {
+        self.labels
+            .iter()
+            .filter_map(|l| Label::new(l.split_whitespace().collect::<Vec<_>>().join("-")).ok())
+            .collect()
+    }
+}
+
+/// A note in a discussion thread.
+#[derive(Debug, Clone)]
+pub struct Note {
+    /// Username of the author.
+    pub author: String,
+    pub body: String,
+    /// Creation time, in RFC 3339 format.
+    pub created: String,
+}
+
+impl Note {
+    /// Body of the imported note, crediting its author.
+    pub fn imported_body(&self) -> String {
+        format!(
+            "**@{}** commented on GitLab, on {}:\n\n{}",
+            self.author,
+            date(&self.created),
+            self.body.trim()
+        )
+    }
+}
+
+/// A discussion thread, ie. a note and the replies to it.
+pub type Thread = Vec<Note>;
+
+/// Client of the API, for a single project.
+pub struct Client {
+    agent: ureq::Agent,
+    token: String,
+    project: Project,
+}
+
+impl Client {
+    pub fn new(token: &str, project: Project) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            token: token.to_owned(),
+            project,
+        }
+    }
+
+    /// Get the token from the environment.
+    pub fn token() -> anyhow::Result<String> {
+        match env::var(TOKEN_ENV) {
+            Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_owned()),
+            _ => Err(Error::WithHint {
+                err: anyhow!("a GitLab access token is needed"),
+                hint: "hint: set `GITLAB_TOKEN` to a token with the `read_api` and `read_repository` scopes",
+            }
+            .into()),
+        }
+    }
+
+    pub fn project(&self) -> &Project {
+        &self.project
+    }
+
+    /// Get all issues of the project, open or closed, oldest first.
+    pub fn issues(&self) -> anyhow::Result<Vec<Item>> {
+        self.get_all("issues?state=all&order_by=created_at&sort=asc")?
+            .iter()
+            .map(Item::from_json)
+            .collect()
+    }
+
+    /// Get all merge requests of the project, in any state, oldest first.
+    pub fn merge_requests(&self) -> anyhow::Result<Vec<Item>> {
+        self.get_all("merge_requests?state=all&order_by=created_at&sort=asc")?
+            .iter()
+            .map(Item::from_json)
+            .collect()
+    }
+
+    /// Get the discussion threads of an issue, without system notes, eg. label changes.
+    pub fn issue_discussions(&self, iid: u64) -> anyhow::Result<Vec<Thread>> {
+        self.discussions(&format!("issues/{}/discussions?", iid))
+    }
+
+    /// Get the discussion threads of a merge request, without system notes.
+    pub fn merge_request_discussions(&self, iid: u64) -> anyhow::Result<Vec<Thread>> {
+        self.discussions(&format!("merge_requests/{}/discussions?", iid))
+    }
+
+    /// Fetch refs of the project into a repository, eg. the head of a merge request.
+    /// The token is passed through the environment, so that it doesn't show up in the
+    /// process list.
+    pub fn fetch(&self, repo: &Path, refspecs: &[String]) -> anyhow::Result<()> {
+        let output = Command::new("git")
+            .current_dir(repo)
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+            .env(
+                "GIT_CONFIG_VALUE_0",
+                format!(
+                    "Authorization: Basic {}",
+                    base64::encode(format!("oauth2:{}", self.token))
+                ),
+            )
+            .args(["fetch", "--quiet", "--no-tags", &self.project.git_url()])
+            .args(refspecs)
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "failed to fetch from {}: {}",
+                self.project,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    fn discussions(&self, path: &str) -> anyhow::Result<Vec<Thread>> {
+        let threads = self
+            .get_all(path)?
+            .iter()
+            .map(|d| {
+                d["notes"]
+                    .as_array()
+                    .map(|notes| {
+                        notes
+                            .iter()
+                            .filter(|n| !n["system"].as_bool().unwrap_or(false))
+                            .map(|n| Note {
+                                author: author(&n["author"]),
+                                body: n["body"].as_str().unwrap_or_default().to_owned(),
+                                created: n["created_at"].as_str().unwrap_or_default().to_owned(),
+                            })
+                            .collect::<Thread>()
+                    })
+                    .unwrap_or_default()
+            })
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        Ok(threads)
+    }
+
+    fn request(&self, path: &str) -> ureq::Request {
+        self.agent
+            .get(&format!(
+                "{}/api/v4/projects/{}/{}",
+                self.project.host,
+                self.project.path.replace('/', "%2F"),
+                path
+            ))
+            .set("PRIVATE-TOKEN", &self.token)
+            .set("User-Agent", "radicle-cli")
+    }
+
+    /// Get all the items of a listing, page by page. The path must include a query.
+    fn get_all(&self, path: &str) -> anyhow::Result<Vec<serde_json::Value>> {
+        let mut items = Vec::new();
+
+        for page in 1.. {
+            let val = self
+                .request(&format!("{}&per_page={}&page={}", path, PER_PAGE, page))
+                .call()
+                .map_err(error)?
+                .into_json::<serde_json::Value>()?;
+            let batch = val
+                .as_array()
+                .ok_or_else(|| anyhow!("unexpected response from GitLab: expected a list"))?;
+
+            items.extend(batch.iter().cloned());
+            if batch.len() < PER_PAGE {
+                break;
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// Date part of an RFC 3339 time.
+fn date(time: &str) -> &str {
+    time.split('T').next().unwrap_or(time)
+}
+
+fn author(val: &serde_json::Value) -> String {
+    val["username"].as_str().unwrap_or("ghost").to_owned()
+}
+
+/// Turn a request error into an error with the message returned by GitLab, if any.
+fn error(err: ureq::Error) -> anyhow::Error {
+    match err {
+        ureq::Error::Status(status, response) => {
+            let message = response
+                .into_json::<serde_json::Value>()
+                .ok()
+                .and_then(|v| match &v["message"] {
+                    serde_json::Value::String(m) => Some(m.clone()),
+                    serde_json::Value::Null => v["error"].as_str().map(|e| e.to_owned()),
+                    other => Some(other.to_string()),
+                })
+                .unwrap_or_default();
+
+            anyhow!("GitLab request failed with status {}: {}", status, message)
+        }
+        err => anyhow!("GitLab request failed: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_project_from_str() {
+        let project = Project::from_str("group/sub/project").unwrap();
+        assert_eq!(project.host, "https://gitlab.com");
+        assert_eq!(project.path, "group/sub/project");
+
+        let project =
+            Project::from_str("https://gitlab.example.com:8443/group/project.git/").unwrap();
+        assert_eq!(project.host, "https://gitlab.example.com:8443");
+        assert_eq!(project.path, "group/project");
+        assert_eq!(
+            project.git_url(),
+            "https://gitlab.example.com:8443/group/project.git"
+        );
+
+        assert!(Project::from_str("project").is_err());
+        assert!(Project::from_str("https://gitlab.com/project").is_err());
+        assert!(Project::from_str("group//project").is_err());
+    }
+}
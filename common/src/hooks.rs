@@ -0,0 +1,225 @@
+//! Webhooks.
+//!
+//! Webhooks are HTTP endpoints that are notified of events, eg. a patch being created or
+//! refs being fetched, with a JSON payload. They are stored in the profile, in
+//! `hooks.toml`, and are notified by the commands that cause the events.
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use librad::git::Urn;
+use librad::profile::Profile;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::cobs::Timestamp;
+use crate::{offline, profile};
+
+/// Webhooks file name, in the profile scope.
+pub const FILE_NAME: &str = "hooks.toml";
+/// Timeout of a webhook request.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An event webhooks can be notified of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Event {
+    #[serde(rename = "patch.created")]
+    PatchCreated,
+    #[serde(rename = "patch.updated")]
+    PatchUpdated,
+    #[serde(rename = "patch.commented")]
+    PatchCommented,
+    #[serde(rename = "patch.merged")]
+    PatchMerged,
+    #[serde(rename = "issue.created")]
+    IssueCreated,
+    #[serde(rename = "issue.commented")]
+    IssueCommented,
+    #[serde(rename = "issue.state")]
+    IssueState,
+    #[serde(rename = "refs.pushed")]
+    RefsPushed,
+    #[serde(rename = "refs.fetched")]
+    RefsFetched,
+    /// Sent when testing a webhook.
+    #[serde(rename = "ping")]
+    Ping,
+}
+
+impl Event {
+    /// All events, except `ping`.
+    pub const ALL: &'static [Event] = &[
+        Event::PatchCreated,
+        Event::PatchUpdated,
+        Event::PatchCommented,
+        Event::PatchMerged,
+        Event::IssueCreated,
+        Event::IssueCommented,
+        Event::IssueState,
+        Event::RefsPushed,
+        Event::RefsFetched,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PatchCreated => "patch.created",
+            Self::PatchUpdated => "patch.updated",
+            Self::PatchCommented => "patch.commented",
+            Self::PatchMerged => "patch.merged",
+            Self::IssueCreated => "issue.created",
+            Self::IssueCommented => "issue.commented",
+            Self::IssueState => "issue.state",
+            Self::RefsPushed => "refs.pushed",
+            Self::RefsFetched => "refs.fetched",
+            Self::Ping => "ping",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Event {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .chain(std::iter::once(&Self::Ping))
+            .find(|e| e.as_str() == s)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("unknown event '{}'", s))
+    }
+}
+
+/// A webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Endpoint the events are posted to.
+    pub url: Url,
+    /// Events the webhook is notified of. If empty, it is notified of all events.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<Event>,
+    /// Project the webhook is limited to, if any.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_project",
+        deserialize_with = "deserialize_project"
+    )]
+    pub project: Option<Urn>,
+}
+
+impl Hook {
+    /// Check whether the webhook should be notified of an event of the given project.
+    pub fn wants(&self, event: Event, project: &Urn) -> bool {
+        (self.events.is_empty() || self.events.contains(&event) || event == Event::Ping)
+            && self.project.as_ref().map_or(true, |p| p == project)
+    }
+
+    /// Post an event to the webhook. Fails in offline mode.
+    pub fn post(&self, event: Event, project: &Urn, data: serde_json::Value) -> anyhow::Result<()> {
+        offline::check("posting to webhooks")?;
+
+        let payload = serde_json::json!({
+            "event": event.as_str(),
+            "project": project.to_string(),
+            "timestamp": Timestamp::now().as_secs(),
+            "data": data,
+        });
+
+        ureq::post(self.url.as_str())
+            .timeout(TIMEOUT)
+            .set("User-Agent", "radicle-cli")
+            .set("X-Radicle-Event", event.as_str())
+            .send_json(payload)
+            .map_err(|err| match err {
+                ureq::Error::Status(status, _) => {
+                    anyhow::anyhow!("{} responded with status {}", self.url, status)
+                }
+                err => anyhow::anyhow!("{}: {}", self.url, err),
+            })?;
+
+        Ok(())
+    }
+}
+
+pub(crate) fn serialize_project<S>(project: &Option<Urn>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match project {
+        Some(urn) => serializer.serialize_str(&urn.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub(crate) fn deserialize_project<'de, D>(deserializer: D) -> Result<Option<Urn>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    crate::project::deserialize_urn(deserializer).map(Some)
+}
+
+/// Webhooks of the profile.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Hooks {
+    #[serde(default)]
+    pub hook: Vec<Hook>,
+}
+
+impl Hooks {
+    /// Load the webhooks of the profile. If there are none yet, returns no webhooks.
+    pub fn load(profile: &Profile) -> Result<Self, anyhow::Error> {
+        profile::load_toml(profile, FILE_NAME)
+    }
+
+    pub fn write(&self, profile: &Profile) -> Result<(), anyhow::Error> {
+        profile::store_toml(profile, FILE_NAME, self)
+    }
+
+    pub fn path(profile: &Profile) -> PathBuf {
+        profile::scoped_path(profile, FILE_NAME)
+    }
+
+    /// Check whether any webhook should be notified of an event of the given project.
+    pub fn wants(&self, event: Event, project: &Urn) -> bool {
+        self.hook.iter().any(|h| h.wants(event, project))
+    }
+
+    /// Post an event to all the webhooks that should be notified of it. Returns the
+    /// delivery errors, if any. In offline mode, nothing is posted, and the only error
+    /// is [`exit::Error::Offline`], if any webhook should have been notified.
+    ///
+    /// [`exit::Error::Offline`]: crate::exit::Error::Offline
+    pub fn emit(&self, event: Event, project: &Urn, data: serde_json::Value) -> Vec<anyhow::Error> {
+        if !self.wants(event, project) {
+            return vec![];
+        }
+        if let Err(err) = offline::check("posting to webhooks") {
+            return vec![err.into()];
+        }
+        self.hook
+            .iter()
+            .filter(|h| h.wants(event, project))
+            .filter_map(|h| h.post(event, project, data.clone()).err())
+            .collect()
+    }
+}
+
+/// Notify the webhooks of the profile of an event. Returns the delivery errors, if any.
+pub fn emit(
+    profile: &Profile,
+    event: Event,
+    project: &Urn,
+    data: serde_json::Value,
+) -> Vec<anyhow::Error> {
+    match Hooks::load(profile) {
+        Ok(hooks) => hooks.emit(event, project, data),
+        Err(err) => vec![err],
+    }
+}
@@ -10,6 +10,13 @@ use librad::PeerId;
 use crate::project::URL_SCHEME;
 use crate::{seed, sync};
 
+/// Number of delegate signatures a revision of an identity needs to take effect, given
+/// the number of delegates of its current revision, eg. a project's delegates, an org's
+/// members, or the devices of a person.
+pub fn quorum(delegates: usize) -> usize {
+    delegates / 2 + 1
+}
+
 /// Identity origin.
 ///
 /// Represents a location from which an identity can be fetched.
@@ -28,6 +35,53 @@ impl Origin {
     pub fn from_urn(urn: Urn) -> Self {
         Self { urn, seed: None }
     }
+
+    /// Create an origin from a seed URL, eg. `https://<host>/<id>.git` or `rad://<host>/<id>`,
+    /// or from a web URL, eg. `https://app.radicle.xyz/seeds/<host>/<urn>`.
+    ///
+    /// The seed's peer id can be hinted as the user of the URL, eg. `https://<peer>@<host>/<id>`.
+    /// Otherwise, it is queried from the seed's API.
+    pub fn from_seed_url(url: &Url) -> Result<Self, anyhow::Error> {
+        let (host, urn) =
+            seed_url(url).ok_or_else(|| anyhow!("'{}' is not a seed project URL", url))?;
+        let peer = match url.username() {
+            "" => seed::get_seed_id(Url::parse(&format!("https://{}", host))?)?,
+            peer => PeerId::from_str(peer)
+                .map_err(|_| anyhow!("invalid seed URL '{}': invalid peer id", url))?,
+        };
+        let port = match (url.scheme(), url.port()) {
+            (URL_SCHEME, Some(port)) => port,
+            _ => seed::DEFAULT_SEED_P2P_PORT,
+        };
+
+        Ok(Self {
+            urn,
+            seed: Some(sync::Seed {
+                peer,
+                addrs: format!("{}:{}", host, port),
+                label: None,
+            }),
+        })
+    }
+}
+
+/// Get the seed host and project URN referenced by a seed URL or web URL.
+/// See [`Origin::from_seed_url`]. Returns `None` if the URL doesn't reference a project.
+pub fn seed_url(url: &Url) -> Option<(String, Urn)> {
+    let segments = url
+        .path_segments()?
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    let urn = |s: &str| {
+        let s = s.strip_suffix(".git").unwrap_or(s);
+        Urn::from_str(s).or_else(|_| Urn::try_from_id(s)).ok()
+    };
+
+    match (url.scheme(), segments.as_slice()) {
+        ("http" | "https", ["seeds", host, id, ..]) => Some((host.to_string(), urn(id)?)),
+        (URL_SCHEME | "http" | "https", [id]) => Some((url.host_str()?.to_owned(), urn(id)?)),
+        _ => None,
+    }
 }
 
 impl FromStr for Origin {
@@ -133,6 +187,66 @@ mod test {
         assert_eq!(origin.urn, expected_urn);
     }
 
+    #[test]
+    fn test_seed_url() {
+        let urn = Urn::try_from_id("hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y").unwrap();
+
+        for url in [
+            "https://pine.radicle.garden/hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y.git",
+            "https://pine.radicle.garden/rad:git:hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y",
+            "rad://pine.radicle.garden/hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y",
+            "https://app.radicle.xyz/seeds/pine.radicle.garden/rad:git:hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y/tree",
+        ] {
+            assert_eq!(
+                seed_url(&Url::parse(url).unwrap()),
+                Some((String::from("pine.radicle.garden"), urn.clone())),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(
+            seed_url(&Url::parse("https://github.com/radicle-dev/radicle-cli.git").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_origin_from_seed_url() {
+        let urn = Urn::try_from_id("hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y").unwrap();
+        let peer =
+            PeerId::from_str("hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa").unwrap();
+
+        for (url, addrs) in [
+            ("rad://hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8777/hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y", "pine.radicle.garden:8777"),
+            // Without a port, the default peer port is used.
+            ("rad://hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden/hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y", "pine.radicle.garden:8776"),
+            // The port of an HTTP URL is not the seed's peer port.
+            ("https://hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8443/hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y.git", "pine.radicle.garden:8776"),
+        ] {
+            let origin = Origin::from_seed_url(&Url::parse(url).unwrap()).unwrap();
+
+            assert_eq!(origin.urn, urn, "{}", url);
+            assert_eq!(
+                origin.seed,
+                Some(sync::Seed {
+                    peer,
+                    addrs: String::from(addrs),
+                    label: None,
+                }),
+                "{}",
+                url
+            );
+        }
+
+        for url in [
+            "https://github.com/radicle-dev/radicle-cli.git",
+            "rad://pine.radicle.garden:8776/not-a-project",
+            "rad://invalid@pine.radicle.garden/hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y",
+        ] {
+            Origin::from_seed_url(&Url::parse(url).unwrap()).unwrap_err();
+        }
+    }
+
     #[test]
     fn test_origin_from_str() {
         let origin = Origin::from_str("rad:git:hnrkbjg7r54q48sqsaho1n4qfxhi4nbmdh51y").unwrap();
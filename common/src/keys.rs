@@ -1,4 +1,9 @@
 //! SSH and key-related functions.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
 use anyhow::{Context as _, Error, Result};
 
 use zeroize::Zeroizing;
@@ -9,14 +14,17 @@ use librad::crypto::keystore::pinentry::{Pinentry, SecUtf8};
 use librad::crypto::keystore::{FileStorage, Keystore};
 use librad::git::storage::Storage;
 use librad::profile::Profile;
-use librad::{PeerId, PublicKey};
+use librad::{PeerId, PublicKey, SecretKey};
 
 use lnk_clib::keys;
 use lnk_clib::keys::ssh::SshAuthSock;
+use thrussh_agent::Constraint;
 
 pub use lnk_clib::keys::LIBRAD_KEY_FILE as KEY_FILE;
 
-use crate::signer::{ToSigner, ZeroizingSecretKey};
+use crate::profile;
+use crate::signer::{ReadOnlySigner, ToSigner, ZeroizingSecretKey};
+use crate::timing::{self, Category};
 
 /// Env var used to pass down the passphrase to the git-remote-helper when
 /// ssh-agent isn't present.
@@ -31,17 +39,71 @@ pub fn storage(profile: &Profile, signer: impl ToSigner) -> Result<Storage, Erro
         }
         Err(err) => anyhow::bail!(err),
     };
-    let storage = Storage::open(profile.paths(), signer)?;
+    let storage = timing::measure(Category::Storage, || Storage::open(profile.paths(), signer))?;
 
     Ok(storage)
 }
 
+/// Get the radicle storage, for reading only. No signer is needed, so the key doesn't
+/// have to be in ssh-agent, and no passphrase is asked for. Anything that signs, eg.
+/// creating a patch, fails with [`crate::signer::ReadOnlyError`].
+pub fn storage_read_only(profile: &Profile) -> Result<Storage, Error> {
+    let peer = *profile::read_only(profile)?.peer_id();
+
+    storage(profile, ReadOnlySigner::new(&peer))
+}
+
+/// Constraints on a key added to ssh-agent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AgentConstraints {
+    /// Remove the key from ssh-agent after this time.
+    pub lifetime: Option<Duration>,
+    /// Ask for confirmation, via `ssh-askpass`, every time the key is used.
+    pub confirm: bool,
+}
+
+impl AgentConstraints {
+    /// Whether no constraint is set.
+    pub fn is_empty(&self) -> bool {
+        self.lifetime.is_none() && !self.confirm
+    }
+
+    fn to_vec(self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+
+        if let Some(lifetime) = self.lifetime {
+            constraints.push(Constraint::KeyLifetime {
+                seconds: lifetime.as_secs() as u32,
+            });
+        }
+        if self.confirm {
+            constraints.push(Constraint::Confirm);
+        }
+        constraints
+    }
+}
+
 /// Add a profile's radicle signing key to ssh-agent.
 pub fn add<P: Pinentry>(profile: &Profile, pass: Pwhash<P>, sock: SshAuthSock) -> Result<(), Error>
 where
     <P as Pinentry>::Error: std::fmt::Debug + std::error::Error + Send + Sync + 'static,
 {
-    keys::ssh::add_signer(profile, sock, pass, vec![]).context("could not add ssh key")?;
+    add_constrained(profile, pass, sock, AgentConstraints::default())
+}
+
+/// Add a profile's radicle signing key to ssh-agent, with the given constraints. If
+/// the key is already in ssh-agent, its constraints are replaced.
+pub fn add_constrained<P: Pinentry>(
+    profile: &Profile,
+    pass: Pwhash<P>,
+    sock: SshAuthSock,
+    constraints: AgentConstraints,
+) -> Result<(), Error>
+where
+    <P as Pinentry>::Error: std::fmt::Debug + std::error::Error + Send + Sync + 'static,
+{
+    keys::ssh::add_signer(profile, sock, pass, constraints.to_vec())
+        .context("could not add ssh key")?;
 
     Ok(())
 }
@@ -128,6 +190,167 @@ pub fn load_secret_key(
     Ok(ZeroizingSecretKey::new(keypair.secret_key))
 }
 
+/// Re-encrypt the secret key of a profile with a new passphrase. The key itself, and
+/// thus the identity, is unchanged. Fails if the old passphrase is invalid.
+pub fn change_passphrase(
+    profile: &Profile,
+    old: SecUtf8,
+    new: SecUtf8,
+) -> Result<(), anyhow::Error> {
+    let path = profile.paths().keys_dir().join(KEY_FILE);
+    let old_storage: FileStorage<_, PublicKey, SecretKey, _> = FileStorage::new(&path, pwhash(old));
+    let keypair = old_storage
+        .get_key()
+        .context("invalid passphrase supplied")?;
+
+    // Write the re-encrypted key next to the old one, and swap them, so that the key
+    // is never lost if writing fails.
+    let tmp = path.with_extension("new");
+    if tmp.exists() {
+        fs::remove_file(&tmp)?;
+    }
+    let mut new_storage: FileStorage<_, PublicKey, SecretKey, _> =
+        FileStorage::new(&tmp, pwhash(new));
+    new_storage.put_key(keypair.secret_key)?;
+    fs::rename(&tmp, &path)?;
+
+    Ok(())
+}
+
+/// A hardware key, to sign with via ssh-agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hardware {
+    /// A PKCS#11 provider, eg. a smart card or security token, holding an Ed25519 key.
+    Pkcs11(PathBuf),
+    /// A FIDO2 authenticator, holding resident `ed25519-sk` keys.
+    Fido2,
+}
+
+impl From<PathBuf> for Hardware {
+    fn from(value: PathBuf) -> Self {
+        if value == Path::new("fido2") {
+            Self::Fido2
+        } else {
+            Self::Pkcs11(value)
+        }
+    }
+}
+
+impl Hardware {
+    /// Add the keys of the hardware key to ssh-agent. The user is prompted for the
+    /// token's PIN by `ssh-add`.
+    pub fn add(&self) -> Result<(), anyhow::Error> {
+        let mut cmd = Command::new("ssh-add");
+        match self {
+            Self::Pkcs11(provider) => cmd.arg("-s").arg(provider),
+            Self::Fido2 => cmd.arg("-K"),
+        };
+        let status = cmd.status().context("failed to run `ssh-add`")?;
+
+        if !status.success() {
+            anyhow::bail!("`ssh-add` failed to add the keys of {}", self);
+        }
+        Ok(())
+    }
+
+    /// Get the keys of the hardware key held by ssh-agent, once added. PKCS#11 keys are
+    /// told apart from other keys by their comment, which is the provider's path.
+    pub fn keys(&self) -> Result<Vec<AgentKey>, anyhow::Error> {
+        let keys = agent_keys()?.into_iter().filter(|k| match self {
+            Self::Pkcs11(provider) => !k.sk && Path::new(&k.comment) == provider,
+            Self::Fido2 => k.sk,
+        });
+        Ok(keys.collect())
+    }
+}
+
+impl std::fmt::Display for Hardware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pkcs11(provider) => write!(f, "'{}'", provider.display()),
+            Self::Fido2 => write!(f, "the FIDO2 authenticator"),
+        }
+    }
+}
+
+/// An Ed25519 key held by ssh-agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentKey {
+    /// Peer id of the key.
+    pub peer: PeerId,
+    /// Whether this is a FIDO2 `ed25519-sk` key. Its signatures aren't plain Ed25519
+    /// signatures, so it can't be a profile's signing key.
+    pub sk: bool,
+    /// The key, as printed by `ssh-add -L`, without its comment.
+    pub key: String,
+    pub comment: String,
+}
+
+/// Get the Ed25519 keys held by ssh-agent, including FIDO2 `ed25519-sk` keys.
+pub fn agent_keys() -> Result<Vec<AgentKey>, anyhow::Error> {
+    // Nb. Fails if ssh-agent holds no keys, which is fine.
+    let output = Command::new("ssh-add")
+        .arg("-L")
+        .output()
+        .context("failed to run `ssh-add`")?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_agent_key)
+        .collect())
+}
+
+/// Parse a line of `ssh-add -L`, if it's an Ed25519 key.
+fn parse_agent_key(line: &str) -> Option<AgentKey> {
+    let mut fields = line.splitn(3, ' ');
+    let kind = fields.next()?;
+    let sk = match kind {
+        "ssh-ed25519" => false,
+        "sk-ssh-ed25519@openssh.com" => true,
+        _ => return None,
+    };
+    let encoded = fields.next()?;
+    let blob = base64::decode(encoded).ok()?;
+
+    // The key type, followed by the key, each prefixed with their length.
+    let mut rest = blob.as_slice();
+    if ssh_string(&mut rest)? != kind.as_bytes() {
+        return None;
+    }
+    let key = PublicKey::from_slice(ssh_string(&mut rest)?)?;
+
+    Some(AgentKey {
+        peer: PeerId::from(key),
+        sk,
+        key: format!("{} {}", kind, encoded),
+        comment: fields.next().unwrap_or_default().trim().to_owned(),
+    })
+}
+
+/// Read a length-prefixed string of the SSH wire format.
+fn ssh_string<'a>(buf: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if buf.len() < 4 {
+        return None;
+    }
+    let (len, rest) = buf.split_at(4);
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (string, rest) = rest.split_at(len);
+    *buf = rest;
+
+    Some(string)
+}
+
+/// Remove the secret key file of a profile, once the key is held by a hardware key.
+/// Signing then requires the hardware key, via ssh-agent.
+pub fn remove_key_file(profile: &Profile) -> Result<(), anyhow::Error> {
+    fs::remove_file(profile.paths().keys_dir().join(KEY_FILE))?;
+
+    Ok(())
+}
+
 pub fn read_env_passphrase() -> Result<SecUtf8, anyhow::Error> {
     let env_var = std::env::var(RAD_PASSPHRASE)?;
     let input: Zeroizing<String> = Zeroizing::new(env_var);
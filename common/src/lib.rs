@@ -1,20 +1,35 @@
 //! Common radicle utilities.
 #![allow(clippy::or_fun_call)]
+pub mod alias;
 pub mod args;
+pub mod backup;
+pub mod block;
 pub mod cobs;
 pub mod config;
+pub mod credentials;
+pub mod exit;
 pub mod git;
+pub mod gitlab;
+pub mod hooks;
 pub mod identity;
 pub mod keys;
 pub mod logger;
+pub mod markup;
+pub mod notify;
+pub mod offline;
+pub mod org;
 pub mod patch;
 pub mod person;
+pub mod plugin;
 pub mod profile;
 pub mod project;
+pub mod search;
 pub mod seed;
 pub mod signer;
+pub mod sshsig;
 pub mod sync;
 pub mod test;
+pub mod timing;
 
 #[cfg(feature = "ethereum")]
 pub mod ethereum;
@@ -0,0 +1,298 @@
+//! Chat notifications.
+//!
+//! Channels are chat rooms, eg. on Matrix or Slack, that are sent a message when a patch
+//! is opened or merged, or an issue is opened or closed, with a link to it on the web
+//! gateway. They are stored in the profile, in `notify.toml`, and are notified of the
+//! same events as webhooks, by the commands that cause them.
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use librad::git::Urn;
+use librad::profile::Profile;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::hooks::{Event, TIMEOUT};
+use crate::{offline, profile, project, sync};
+
+/// Channels file name, in the profile scope.
+pub const FILE_NAME: &str = "notify.toml";
+
+/// Kind of channel, ie. how messages are posted to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// A Matrix room, through a webhook bridge, eg. hookshot. Posted `text` and `html`.
+    Matrix,
+    /// A Slack channel, through an incoming webhook. Posted `text`, as mrkdwn.
+    Slack,
+    /// Any other endpoint. Posted `text`, along with the event details.
+    Webhook,
+}
+
+impl Kind {
+    pub const ALL: &'static [Kind] = &[Kind::Matrix, Kind::Slack, Kind::Webhook];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Matrix => "matrix",
+            Self::Slack => "slack",
+            Self::Webhook => "webhook",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|k| k.as_str() == s)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("unknown channel kind '{}'", s))
+    }
+}
+
+/// A message about an event of a project.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub event: Event,
+    /// Name of the project, or its URN if unknown.
+    pub project: String,
+    /// What happened, eg. "Patch opened".
+    pub action: &'static str,
+    /// Title of the patch or issue.
+    pub title: String,
+    /// Link to the patch or issue on the web gateway, if any seed is configured.
+    pub link: Option<String>,
+}
+
+impl Message {
+    /// Get the message about an event, given the event data. Returns `None` for events
+    /// channels aren't notified of.
+    pub fn new(event: Event, project: String, data: &serde_json::Value) -> Option<Self> {
+        let action = match event {
+            Event::PatchCreated => "Patch opened",
+            Event::PatchMerged => "Patch merged",
+            Event::IssueCreated => "Issue opened",
+            Event::IssueState => match data["state"]["status"].as_str() {
+                Some("closed") => "Issue closed",
+                Some("open") => "Issue reopened",
+                _ => return None,
+            },
+            Event::Ping => "Notifications are working",
+            _ => return None,
+        };
+
+        Some(Self {
+            event,
+            project,
+            action,
+            title: data["title"].as_str().unwrap_or_default().to_owned(),
+            link: None,
+        })
+    }
+
+    /// Plain text rendering.
+    pub fn text(&self) -> String {
+        let mut text = format!("[{}] {}", self.project, self.action);
+
+        if !self.title.is_empty() {
+            text.push_str(&format!(": {}", self.title));
+        }
+        if let Some(link) = &self.link {
+            text.push_str(&format!(" {}", link));
+        }
+        text
+    }
+
+    /// Slack mrkdwn rendering.
+    fn mrkdwn(&self) -> String {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let mut text = format!("*[{}]* {}", escape(&self.project), self.action);
+
+        match (&self.link, self.title.is_empty()) {
+            (Some(link), true) => text.push_str(&format!(" <{}>", link)),
+            (Some(link), false) => text.push_str(&format!(": <{}|{}>", link, escape(&self.title))),
+            (None, true) => {}
+            (None, false) => text.push_str(&format!(": {}", escape(&self.title))),
+        }
+        text
+    }
+
+    /// HTML rendering.
+    fn html(&self) -> String {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+        let mut html = format!("<b>[{}]</b> {}", escape(&self.project), self.action);
+        let title = if self.title.is_empty() {
+            self.link.clone().unwrap_or_default()
+        } else {
+            self.title.clone()
+        };
+
+        match &self.link {
+            Some(link) => html.push_str(&format!(
+                ": <a href=\"{}\">{}</a>",
+                escape(link),
+                escape(&title)
+            )),
+            None if !title.is_empty() => html.push_str(&format!(": {}", escape(&title))),
+            None => {}
+        }
+        html
+    }
+}
+
+/// A channel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Channel {
+    pub kind: Kind,
+    /// Endpoint messages are posted to.
+    pub target: Url,
+    /// Project the channel is limited to, if any.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::hooks::serialize_project",
+        deserialize_with = "crate::hooks::deserialize_project"
+    )]
+    pub project: Option<Urn>,
+}
+
+impl Channel {
+    /// Check whether the channel should be notified of the events of the given project.
+    pub fn wants(&self, project: &Urn) -> bool {
+        self.project.as_ref().map_or(true, |p| p == project)
+    }
+
+    /// Post a message to the channel. Fails in offline mode.
+    pub fn post(&self, project: &Urn, message: &Message) -> anyhow::Result<()> {
+        offline::check("notifying channels")?;
+
+        let payload = match self.kind {
+            Kind::Matrix => serde_json::json!({
+                "text": message.text(),
+                "html": message.html(),
+            }),
+            Kind::Slack => serde_json::json!({
+                "text": message.mrkdwn(),
+            }),
+            Kind::Webhook => serde_json::json!({
+                "text": message.text(),
+                "event": message.event.as_str(),
+                "project": project.to_string(),
+                "title": message.title,
+                "link": message.link,
+            }),
+        };
+
+        ureq::post(self.target.as_str())
+            .timeout(TIMEOUT)
+            .set("User-Agent", "radicle-cli")
+            .send_json(payload)
+            .map_err(|err| match err {
+                ureq::Error::Status(status, _) => {
+                    anyhow::anyhow!("{} responded with status {}", self.target, status)
+                }
+                err => anyhow::anyhow!("{}: {}", self.target, err),
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Channels of the profile.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Channels {
+    #[serde(default)]
+    pub channel: Vec<Channel>,
+}
+
+impl Channels {
+    /// Load the channels of the profile. If there are none yet, returns no channels.
+    pub fn load(profile: &Profile) -> Result<Self, anyhow::Error> {
+        profile::load_toml(profile, FILE_NAME)
+    }
+
+    pub fn write(&self, profile: &Profile) -> Result<(), anyhow::Error> {
+        profile::store_toml(profile, FILE_NAME, self)
+    }
+
+    pub fn path(profile: &Profile) -> PathBuf {
+        profile::scoped_path(profile, FILE_NAME)
+    }
+}
+
+/// Get the message about an event of a project, with the project name and a link to the
+/// patch or issue. Returns `None` for events channels aren't notified of.
+pub fn message(
+    profile: &Profile,
+    event: Event,
+    project: &Urn,
+    data: &serde_json::Value,
+) -> Option<Message> {
+    let name = profile::read_only(profile)
+        .ok()
+        .and_then(|storage| project::get(&storage, project).ok().flatten())
+        .map_or_else(|| project.to_string(), |p| p.name);
+    let mut message = Message::new(event, name, data)?;
+    let path = match event {
+        Event::PatchCreated | Event::PatchMerged => "patches",
+        Event::IssueCreated | Event::IssueState => "issues",
+        _ => "",
+    };
+
+    message.link = sync::gateway_url(profile, project).map(|url| match data["id"].as_str() {
+        Some(id) if !path.is_empty() => format!("{}/{}/{}", url, path, id),
+        _ => url,
+    });
+    Some(message)
+}
+
+/// Notify the channels of the profile of an event. Returns the delivery errors, if any.
+/// In offline mode, no channel is notified, see [`Hooks::emit`](crate::hooks::Hooks::emit).
+pub fn emit(
+    profile: &Profile,
+    event: Event,
+    project: &Urn,
+    data: &serde_json::Value,
+) -> Vec<anyhow::Error> {
+    let channels = match Channels::load(profile) {
+        Ok(channels) => channels,
+        Err(err) => return vec![err],
+    };
+    if !channels.channel.iter().any(|c| c.wants(project)) {
+        return vec![];
+    }
+    if let Err(err) = offline::check("notifying channels") {
+        return vec![err.into()];
+    }
+    let message = match message(profile, event, project, data) {
+        Some(message) => message,
+        None => return vec![],
+    };
+
+    channels
+        .channel
+        .iter()
+        .filter(|c| c.wants(project))
+        .filter_map(|c| c.post(project, &message).err())
+        .collect()
+}
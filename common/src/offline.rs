@@ -0,0 +1,25 @@
+//! Offline mode, for air-gapped machines, or when travelling.
+//!
+//! Offline mode is turned on with the global `--offline` option of `rad`, or with
+//! `offline = true` in the configuration. Commands then work from local storage only:
+//! they don't sync their changes, and those that can't work without the network fail
+//! with [`exit::Error::Offline`], instead of trying to reach seeds.
+use crate::config::Config;
+use crate::exit;
+
+/// Environment var that turns offline mode on, as the `offline` configuration key.
+pub const RAD_OFFLINE: &str = "RAD_OFFLINE";
+
+/// Whether offline mode is on.
+pub fn is_enabled() -> bool {
+    Config::current().map_or(false, |cfg| cfg.offline)
+}
+
+/// Fail if offline mode is on, for an operation that requires the network, eg.
+/// "syncing".
+pub fn check(operation: &str) -> Result<(), exit::Error> {
+    if is_enabled() {
+        return Err(exit::Error::Offline(operation.to_owned()));
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Org identities.
+//!
+//! An org is a personal identity held by the keys of several members, marked by an
+//! [`Org`] payload extension. Like any personal identity, it can be a project delegate,
+//! and a new revision of it only takes effect once signed by a majority of its keys.
+use anyhow::{anyhow, Result};
+
+use librad::canonical::Cstring;
+use librad::crypto::{BoxedSigner, PublicKey};
+use librad::git::identities::{self, Person, SomeIdentity};
+use librad::git::storage::{ReadOnly, Storage};
+use librad::git::Urn;
+use librad::identities::payload;
+use librad::identities::payload::HasNamespace;
+use librad::profile::Profile;
+
+use crate::exit;
+use lnk_identities::person;
+
+lazy_static::lazy_static! {
+    static ref ORG_NAMESPACE: url::Url = "https://radicle.xyz/org/v1"
+        .parse()
+        .expect("static URL malformed");
+}
+
+/// Org payload.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct Org {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl HasNamespace for Org {
+    fn namespace() -> &'static url::Url {
+        &ORG_NAMESPACE
+    }
+}
+
+/// Create an org, held by the signer key and the given member keys.
+pub fn create(
+    profile: &Profile,
+    name: &str,
+    org: Org,
+    members: Vec<PublicKey>,
+    signer: BoxedSigner,
+    storage: &Storage,
+) -> Result<Person> {
+    let paths = profile.paths().clone();
+    let payload = payload::Person {
+        name: Cstring::from(name),
+    };
+    let ext = payload::Ext {
+        namespace: Org::namespace().clone(),
+        val: serde_json::to_value(org)?,
+    };
+    let person = person::create::<payload::Person>(
+        storage,
+        paths,
+        signer,
+        payload,
+        vec![ext],
+        members,
+        person::Creation::New { path: None },
+    )?;
+
+    Ok(person)
+}
+
+/// Get an org, if the URN is that of an org.
+pub fn get<S>(storage: &S, urn: &Urn) -> Result<Option<(Person, Org)>>
+where
+    S: AsRef<ReadOnly>,
+{
+    match identities::person::get(storage, urn)? {
+        Some(person) => match person.payload().get_ext::<Org>()? {
+            Some(org) => Ok(Some((person, org))),
+            None => Err(anyhow!("{} is a person, not an org", urn)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// List the orgs in local storage.
+pub fn list<S>(storage: &S) -> Result<Vec<(Person, Org)>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let mut orgs = Vec::new();
+
+    for identity in identities::any::list(storage)? {
+        if let SomeIdentity::Person(person) = identity? {
+            if let Ok(Some(org)) = person.payload().get_ext::<Org>() {
+                orgs.push((person, org));
+            }
+        }
+    }
+    Ok(orgs)
+}
+
+/// Set the member keys of an org, keeping its payload. The new revision is signed by
+/// the storage signer. Returns the updated org.
+pub fn set_members(
+    storage: &Storage,
+    urn: &Urn,
+    keys: impl IntoIterator<Item = PublicKey>,
+) -> Result<Person> {
+    let (org, _) = get(storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(format!("org {} not found", urn)))?;
+    let exts = org
+        .payload()
+        .exts()
+        .map(|(namespace, val)| payload::Ext {
+            namespace: namespace.clone(),
+            val: val.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let new = person::update(storage, urn, None, None, exts, Some(keys.into_iter()))?;
+
+    Ok(new)
+}
@@ -3,23 +3,26 @@ use anyhow::{Context, Error, Result};
 
 use librad::{canonical::Cstring, git::identities::local::LocalIdentity};
 
-use librad::git::identities::Person;
+use librad::git::identities::{self, Person};
 use librad::git::storage::Storage;
 
-use librad::crypto::BoxedSigner;
+use librad::crypto::{BoxedSigner, PublicKey};
 use librad::identities::payload;
 use librad::identities::payload::HasNamespace;
 use librad::profile::Profile;
 
 use lnk_identities::{self, local, person};
 
-pub use librad::git::identities::person::verify;
+pub use librad::git::identities::person::{merge, verify};
 pub use person::get;
 
 lazy_static::lazy_static! {
     static ref ENS_NAMESPACE: url::Url = "https://radicle.xyz/ethereum/ens/v1"
         .parse()
         .expect("static URL malformed");
+    static ref LINKS_NAMESPACE: url::Url = "https://radicle.xyz/links/v1"
+        .parse()
+        .expect("static URL malformed");
 }
 
 /// ENS payload.
@@ -34,6 +37,21 @@ impl HasNamespace for Ens {
     }
 }
 
+/// Links payload, eg. the avatar of a person.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct Links {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<url::Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<url::Url>,
+}
+
+impl HasNamespace for Links {
+    fn namespace() -> &'static url::Url {
+        &LINKS_NAMESPACE
+    }
+}
+
 /// Create a personal identity.
 pub fn create(
     profile: &Profile,
@@ -104,3 +122,62 @@ pub fn set_ens_payload(ens: Ens, storage: &Storage) -> Result<Person> {
 
     Ok(new)
 }
+
+/// Set the keys of the local identity, keeping its payload. The new revision is signed
+/// by the storage signer. Returns the updated person.
+pub fn set_delegations(
+    storage: &Storage,
+    keys: impl IntoIterator<Item = PublicKey>,
+) -> Result<Person> {
+    let id = local::default(storage)?;
+    let exts = id
+        .payload()
+        .exts()
+        .map(|(namespace, val)| payload::Ext {
+            namespace: namespace.clone(),
+            val: val.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let new = person::update(
+        storage,
+        &id.urn(),
+        Some(id.urn()),
+        None,
+        exts,
+        Some(keys.into_iter()),
+    )?;
+
+    Ok(new)
+}
+
+/// Update the name and links of the local identity. Links that aren't given are kept.
+/// Returns the updated person.
+pub fn set_metadata(
+    storage: &Storage,
+    name: Option<&str>,
+    avatar: Option<url::Url>,
+    url: Option<url::Url>,
+) -> Result<Person> {
+    let id = local::default(storage)?;
+    let mut payload = id.payload().clone();
+
+    if let Some(name) = name {
+        payload.subject.name = Cstring::from(name);
+    }
+    if avatar.is_some() || url.is_some() {
+        let mut links = payload.get_ext::<Links>()?.unwrap_or_default();
+
+        if avatar.is_some() {
+            links.avatar = avatar;
+        }
+        if url.is_some() {
+            links.url = url;
+        }
+        payload.set_ext(links)?;
+    }
+
+    let new = identities::person::update(storage, &id.urn(), None, payload, None)?;
+
+    Ok(new)
+}
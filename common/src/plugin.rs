@@ -0,0 +1,100 @@
+//! External commands, ie. `rad-<name>` executables in `PATH`, which extend `rad` with
+//! commands of their own: `rad <name> [<arg>...]` runs `rad-<name> [<arg>...]` when
+//! `<name>` isn't a built-in command.
+//!
+//! External commands inherit the environment of `rad`, including the variables set by its
+//! global options, eg. `RAD_PROFILE` or `RAD_OUTPUT`, and are given the context they
+//! run in through the variables below.
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{profile, project};
+
+/// Prefix of the executables of external commands.
+pub const PREFIX: &str = "rad-";
+
+/// Environment var with the version of `rad`.
+pub const RAD_VERSION: &str = "RAD_VERSION";
+/// Environment var with the id of the profile, if any.
+pub const RAD_PROFILE_ID: &str = "RAD_PROFILE_ID";
+/// Environment var with the directory of the profile, if any.
+pub const RAD_PROFILE_PATH: &str = "RAD_PROFILE_PATH";
+/// Environment var with the URN of the project of the working directory, if any.
+pub const RAD_PROJECT: &str = "RAD_PROJECT";
+/// Environment var with the root of the project's working copy, if any.
+pub const RAD_PROJECT_PATH: &str = "RAD_PROJECT_PATH";
+
+/// Find the executable of an external command in `PATH`.
+pub fn find(name: &str) -> Option<PathBuf> {
+    which(&format!("{}{}", PREFIX, name))
+}
+
+/// Find an executable in `PATH`, eg. `git-remote-rad`.
+pub fn which(name: &str) -> Option<PathBuf> {
+    let file = format!("{}{}", name, env::consts::EXE_SUFFIX);
+
+    env::var_os("PATH")
+        .iter()
+        .flat_map(env::split_paths)
+        .map(|dir| dir.join(&file))
+        .find(|path| is_executable(path))
+}
+
+/// Get the names of the external commands in `PATH`, sorted, without duplicates.
+pub fn list() -> Vec<String> {
+    let mut names = env::var_os("PATH")
+        .iter()
+        .flat_map(env::split_paths)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_executable(&entry.path()))
+        .filter_map(|entry| {
+            let file = entry.file_name().into_string().ok()?;
+            let name = file.strip_prefix(PREFIX)?;
+            let name = name.strip_suffix(env::consts::EXE_SUFFIX).unwrap_or(name);
+
+            (!name.is_empty()).then(|| name.to_owned())
+        })
+        .collect::<Vec<_>>();
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Get the environment that gives an external command its context: the profile, and
+/// the project of the working directory. Missing context is left out.
+pub fn context() -> Vec<(&'static str, OsString)> {
+    let mut vars = Vec::new();
+
+    if let Ok(profile) = profile::default() {
+        vars.push((RAD_PROFILE_ID, OsString::from(profile.id().to_string())));
+        if let Some(dir) = profile.paths().seeds_file().parent() {
+            vars.push((RAD_PROFILE_PATH, dir.as_os_str().to_owned()));
+        }
+    }
+    if let Ok((urn, repo)) = project::cwd() {
+        vars.push((RAD_PROJECT, OsString::from(urn.to_string())));
+        if let Some(dir) = repo.workdir() {
+            vars.push((RAD_PROJECT_PATH, dir.as_os_str().to_owned()));
+        }
+    }
+    vars
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    fs::metadata(path).map_or(false, |m| {
+        m.is_file() && m.permissions().mode() & 0o111 != 0
+    })
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
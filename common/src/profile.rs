@@ -1,5 +1,7 @@
 //! User profile related functions.
 use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::{env, fmt, path};
 
 use anyhow::{anyhow, Error, Result};
@@ -15,13 +17,32 @@ use librad::PeerId;
 use librad::{git::storage::ReadOnly, git::Storage, keystore::crypto::Crypto};
 
 use crate::args;
+use crate::exit;
 use crate::keys;
+use crate::signer::ReadOnlySigner;
+use crate::timing::{self, Category};
 
 /// Environment var that sets the radicle home directory.
 pub const RAD_HOME: &str = "RAD_HOME";
+/// Environment var that selects the profile to use, instead of the active profile.
+/// Set by the `--profile` option of `rad`.
+pub const RAD_PROFILE: &str = "RAD_PROFILE";
 
 /// Create a new profile.
 pub fn create<C: Crypto>(home: impl Into<LnkHome>, crypto: C) -> Result<(Profile, PeerId)>
+where
+    C::Error: fmt::Debug + fmt::Display + Send + Sync + 'static,
+    C::SecretBox: Serialize + DeserializeOwned,
+{
+    create_with_key(home, crypto, SecretKey::new())
+}
+
+/// Create a new profile for the given secret key.
+pub fn create_with_key<C: Crypto>(
+    home: impl Into<LnkHome>,
+    crypto: C,
+    key: SecretKey,
+) -> Result<(Profile, PeerId)>
 where
     C::Error: fmt::Debug + fmt::Display + Send + Sync + 'static,
     C::SecretBox: Serialize + DeserializeOwned,
@@ -31,7 +52,6 @@ where
 
     Profile::set(&home, profile.id().clone())?;
 
-    let key = SecretKey::new();
     let mut store: FileStorage<C, PublicKey, SecretKey, _> =
         FileStorage::new(&profile.paths().keys_dir().join(keys::KEY_FILE), crypto);
 
@@ -41,6 +61,18 @@ where
     Ok((profile, PeerId::from(key)))
 }
 
+/// Create a new profile for a key that isn't stored on disk, eg. one held by a hardware
+/// key, which is only available via ssh-agent.
+pub fn create_without_key(home: impl Into<LnkHome>, peer: &PeerId) -> Result<Profile> {
+    let home = home.into();
+    let profile = Profile::new(&home)?;
+
+    Profile::set(&home, profile.id().clone())?;
+    Storage::open(profile.paths(), ReadOnlySigner::new(peer))?;
+
+    Ok(profile)
+}
+
 /// Get the radicle home.
 pub fn home() -> LnkHome {
     let home = env::var(RAD_HOME)
@@ -50,8 +82,13 @@ pub fn home() -> LnkHome {
     home.unwrap_or_default()
 }
 
-/// Get the default profile. Fails if there is no profile.
+/// Get the default profile: the profile selected via `RAD_PROFILE` if set, otherwise
+/// the active profile. Fails if there is no profile.
 pub fn default() -> Result<Profile, Error> {
+    if let Ok(query) = env::var(RAD_PROFILE) {
+        return find(&query);
+    }
+
     let error = args::Error::WithHint {
         err: anyhow!("Could not load radicle profile"),
         hint: "To setup your radicle profile, run `rad auth`.",
@@ -97,6 +134,31 @@ pub fn set(id: &ProfileId) -> Result<(), Error> {
     Ok(())
 }
 
+/// Find a profile by profile id, peer id, or user name.
+pub fn find(query: &str) -> Result<Profile, Error> {
+    for profile in list()? {
+        if profile.id().to_string() == query {
+            return Ok(profile);
+        }
+        if let Ok(storage) = read_only(&profile) {
+            if storage.peer_id().to_string() == query {
+                return Ok(profile);
+            }
+            if let Ok(config) = storage.config() {
+                if config.user_name().ok().as_deref() == Some(query) {
+                    return Ok(profile);
+                }
+            }
+        }
+    }
+
+    Err(args::Error::WithHint {
+        err: exit::Error::NotFound(format!("profile '{}' not found", query)).into(),
+        hint: "To list your profiles, run `rad auth --list`.",
+    }
+    .into())
+}
+
 /// Get a profile.
 pub fn get(id: &ProfileId) -> Result<Profile, Error> {
     let error = args::Error::WithHint {
@@ -121,7 +183,66 @@ pub fn remove(profile: &Profile) -> Result<(), Error> {
 
 /// Open read-only storage.
 pub fn read_only(profile: &Profile) -> Result<ReadOnly, Error> {
-    let storage = ReadOnly::open(profile.paths())?;
+    let storage = timing::measure(Category::Storage, || ReadOnly::open(profile.paths()))?;
 
     Ok(storage)
 }
+
+/// Directory of a profile, holding its storage, keys and profile-scoped files.
+pub fn dir(profile: &Profile) -> PathBuf {
+    // This is a bit of a hack, since we don't have a way of getting
+    // the profile root.
+    profile
+        .paths()
+        .seeds_file()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Path of a file in the profile scope, eg. the profile configuration.
+pub fn scoped_path(profile: &Profile, name: &str) -> PathBuf {
+    dir(profile).join(name)
+}
+
+/// Load a TOML file in the profile scope. If it doesn't exist yet, returns the default
+/// value.
+pub fn load_toml<T: DeserializeOwned + Default>(profile: &Profile, name: &str) -> Result<T> {
+    match fs::read_to_string(scoped_path(profile, name)) {
+        Ok(contents) => Ok(toml::from_str(&contents)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Write a TOML file in the profile scope. The file is only readable by the user.
+pub fn store_toml<T: Serialize>(profile: &Profile, name: &str, value: &T) -> Result<()> {
+    let contents = toml::to_string_pretty(value)?;
+    write_private(&scoped_path(profile, name), contents)?;
+
+    Ok(())
+}
+
+/// Write a file that is only readable by the user.
+pub(crate) fn write_private(path: &Path, contents: String) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        // The file may have been created with broader permissions.
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(contents.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        fs::write(path, contents)
+    }
+}
@@ -3,9 +3,10 @@ use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::iter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context as _, Result};
 use either::Either;
 use serde::{Deserialize, Serialize};
 use url::Url;
@@ -21,7 +22,7 @@ use librad::git::types::remote::Remote;
 use librad::git::types::{Namespace, Reference};
 use librad::git::Urn;
 use librad::git_ext::{OneLevel, RefLike};
-use librad::identities::payload::{self, ProjectPayload};
+use librad::identities::payload::{self, HasNamespace, ProjectPayload};
 use librad::identities::SomeIdentity;
 use librad::identities::{Person, VerifiedProject};
 use librad::paths::Paths;
@@ -32,8 +33,11 @@ use lnk_identities;
 use lnk_identities::working_copy_dir::WorkingCopyDir;
 
 use crate as common;
+use crate::alias::Aliases;
+use crate::exit;
 use crate::person::Ens;
-use crate::{git, person};
+use crate::sync::refs::PROMISOR_PREFIX;
+use crate::{block, git, person};
 
 /// URL scheme for radicle resources.
 pub const URL_SCHEME: &str = "rad";
@@ -142,6 +146,29 @@ impl Delegate {
     }
 }
 
+lazy_static::lazy_static! {
+    static ref POLICY_NAMESPACE: Url = "https://radicle.xyz/policy/v1"
+        .parse()
+        .expect("static URL malformed");
+}
+
+/// Project policy payload, decided by the delegates, and enforced by the tools of
+/// every peer.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Policy {
+    /// Whether only delegates may update the default branch directly. Other peers
+    /// propose their changes to it as patches.
+    #[serde(default)]
+    pub protect_default_branch: bool,
+}
+
+impl HasNamespace for Policy {
+    fn namespace() -> &'static Url {
+        &POLICY_NAMESPACE
+    }
+}
+
 /// Project metadata.
 ///
 /// Can be constructed from a [`librad::identities::Project`].
@@ -160,6 +187,9 @@ pub struct Metadata {
     pub delegates: Vec<Delegate>,
     /// List of remotes.
     pub remotes: HashSet<PeerId>,
+    /// Project policy.
+    #[serde(default)]
+    pub policy: Policy,
 }
 
 impl Metadata {
@@ -188,10 +218,20 @@ impl Metadata {
         peer_self(storage, self.urn.clone(), peer)
     }
 
+    /// Check whether the given peer belongs to a project delegate.
+    pub fn is_delegate(&self, peer: &PeerId) -> bool {
+        self.delegates.iter().any(|d| d.contains(peer))
+    }
+
+    /// Check whether the given peer may update the default branch directly.
+    pub fn can_push_default_branch(&self, peer: &PeerId) -> bool {
+        !self.policy.protect_default_branch || self.is_delegate(peer)
+    }
+
     /// Get a [`VerifiedProject`] from project metadata.
     pub fn verified(&self, storage: &Storage) -> anyhow::Result<VerifiedProject> {
         identities::project::verify(storage, &self.urn)?
-            .ok_or_else(|| anyhow::anyhow!("project {} not found", self.urn))
+            .ok_or_else(|| exit::Error::NotFound(format!("project {} not found", self.urn)).into())
     }
 }
 
@@ -208,6 +248,13 @@ impl TryFrom<librad::identities::Project> for Metadata {
 
     fn try_from(project: librad::identities::Project) -> Result<Self, Self::Error> {
         let subject = project.subject();
+        // Nb. An invalid policy is ignored, rather than making the project unreadable.
+        let policy = project
+            .payload()
+            .get_ext::<Policy>()
+            .ok()
+            .flatten()
+            .unwrap_or_default();
         let remotes = project
             .delegations()
             .iter()
@@ -258,6 +305,7 @@ impl TryFrom<librad::identities::Project> for Metadata {
             default_branch,
             delegates,
             remotes,
+            policy,
         })
     }
 }
@@ -318,6 +366,7 @@ pub fn checkout<S>(
 where
     S: AsRef<ReadOnly>,
 {
+    let monorepo = paths.git_dir().to_path_buf();
     let repo = crate::identities::project::checkout(
         storage,
         paths,
@@ -332,10 +381,40 @@ where
         ["config", "--remove-section", "remote.__tmp_/rad"],
     )
     .ok();
+    inherit_partial(&monorepo, &repo, urn)?;
 
     Ok(repo)
 }
 
+/// Make a working copy as partial as its project in the monorepo, eg. after a filtered
+/// fetch: the seed that omitted objects are fetched from is set up as a promisor remote.
+fn inherit_partial(monorepo: &Path, repo: &git::Repository, urn: &Urn) -> anyhow::Result<()> {
+    let monorepo = git::Repository::open_bare(monorepo)?;
+    let name = format!("{}/{}", PROMISOR_PREFIX, urn.encode_id());
+    let url = match monorepo.find_remote(&name) {
+        Ok(remote) => match remote.url() {
+            Some(url) => url.to_owned(),
+            None => return Ok(()),
+        },
+        Err(_) => return Ok(()),
+    };
+    let filter = monorepo
+        .config()?
+        .get_string(&format!("remote.{}.partialclonefilter", name))
+        .ok();
+    let mut config = repo.config()?;
+
+    repo.remote(PROMISOR_PREFIX, &url)?;
+    config.set_bool(&format!("remote.{}.promisor", PROMISOR_PREFIX), true)?;
+    if let Some(filter) = filter {
+        config.set_str(
+            &format!("remote.{}.partialclonefilter", PROMISOR_PREFIX),
+            &filter,
+        )?;
+    }
+    Ok(())
+}
+
 /// List projects on the local device. Includes the project head if available.
 pub fn list<S>(storage: &S) -> anyhow::Result<Vec<(Urn, Metadata, Option<git::Oid>)>>
 where
@@ -423,6 +502,25 @@ where
     Ok(reference.target())
 }
 
+/// Get the head of the project's default branch: ours, or else that of a delegate.
+pub fn head<S>(storage: &S, proj: &Metadata) -> Option<git::Oid>
+where
+    S: AsRef<ReadOnly>,
+{
+    let branch = proj.default_branch.to_string();
+
+    get_local_head(storage, &proj.urn, &branch)
+        .ok()
+        .flatten()
+        .or_else(|| {
+            proj.remotes.iter().find_map(|peer| {
+                get_remote_head(storage, &proj.urn, peer, &branch)
+                    .ok()
+                    .flatten()
+            })
+        })
+}
+
 /// Get project metadata.
 pub fn get<S>(storage: &S, urn: &Urn) -> anyhow::Result<Option<Metadata>>
 where
@@ -434,6 +532,21 @@ where
     Ok(meta)
 }
 
+/// Set the policy of a project, keeping the rest of its payload. The new revision is
+/// signed by the storage signer, and only takes effect once signed by a quorum of
+/// delegates. Returns the updated project.
+pub fn set_policy(storage: &Storage, urn: &Urn, policy: Policy) -> anyhow::Result<Project> {
+    let project = project::get(storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(format!("project {} not found", urn)))?;
+    let mut payload = project.payload().clone();
+
+    payload.set_ext(policy)?;
+
+    let project = project::update(storage, urn, None, payload, None)?;
+
+    Ok(project)
+}
+
 /// Get the personal identity associated with a project's peer.
 pub fn person<S>(storage: &S, project: Urn, peer: &PeerId) -> anyhow::Result<Option<Person>>
 where
@@ -488,6 +601,11 @@ pub fn find_remote(
     repo: &git::Repository,
     urn: &Urn,
 ) -> anyhow::Result<Option<String>> {
+    // by remote name
+    if repo.find_remote(name).is_ok() {
+        return Ok(Some(name.to_owned()));
+    }
+
     if let Ok(peer_) = name.parse() {
         // by Peer ID
         for (name, peer) in git::remotes(repo)? {
@@ -498,7 +616,14 @@ pub fn find_remote(
         return Ok(None);
     }
 
-    // by person's name
+    // by peer alias or person's name
+    if let Some(peer_) = Aliases::load(repo).peer(name) {
+        for (remote, peer) in git::remotes(repo)? {
+            if peer == peer_ {
+                return Ok(Some(remote));
+            }
+        }
+    }
     for (name, peer) in git::remotes(repo)? {
         if let Some(person) = person(&storage, urn.clone(), &peer)? {
             if person.subject().name.to_string() == *name {
@@ -526,14 +651,58 @@ pub fn remote(urn: &Urn, peer: &PeerId, name: &str) -> Result<Remote<LocalUrl>,
 }
 
 /// Get the project URN and repository of the current working directory.
+/// Within a linked worktree, the repository is the worktree's, with its own HEAD. Fails
+/// with [`exit::Error::NotProject`] outside of a project.
 pub fn cwd() -> anyhow::Result<(Urn, git::Repository)> {
-    let repo = git::repository()?;
-    let urn = git::rad_remote(&repo)?.url.urn;
+    let repo = git::repository().context(exit::Error::NotProject)?;
+    let urn = git::rad_remote(&repo)
+        .context(exit::Error::NotProject)?
+        .url
+        .urn;
 
     Ok((urn, repo))
 }
 
-/// Get the tracked peers of a project, including information about these peers.
+/// A submodule of a working copy that is a radicle project, ie. whose URL is a project
+/// URN, eg. `rad:git:<id>`, or a project URL, eg. `rad://<id>.git`.
+#[derive(Debug, Clone)]
+pub struct Submodule {
+    /// Name of the submodule, as configured in `.gitmodules`.
+    pub name: String,
+    /// Path of the submodule, relative to the working copy.
+    pub path: PathBuf,
+    /// Project of the submodule.
+    pub urn: Urn,
+    /// Commit of the submodule recorded in the working copy's HEAD, if any.
+    pub head: Option<git::Oid>,
+}
+
+/// Get the submodules of a working copy that are radicle projects.
+pub fn submodules(repo: &git::Repository) -> anyhow::Result<Vec<Submodule>> {
+    let mut submodules = Vec::new();
+
+    for submodule in repo.submodules()? {
+        let url = submodule.url().unwrap_or_default();
+        let urn = match Urn::from_str(url) {
+            Ok(urn) => urn,
+            Err(_) => match LocalUrl::from_str(url) {
+                Ok(url) => url.urn,
+                Err(_) => continue,
+            },
+        };
+
+        submodules.push(Submodule {
+            name: submodule.name().unwrap_or_default().to_owned(),
+            path: submodule.path().to_path_buf(),
+            urn,
+            head: submodule.head_id(),
+        });
+    }
+    Ok(submodules)
+}
+
+/// Get the tracked peers of a project, including information about these peers. Blocked
+/// peers, which are tracked so that their data is refused, are left out.
 pub fn tracked<S>(project: &Metadata, storage: &S) -> anyhow::Result<HashMap<PeerId, PeerInfo>>
 where
     S: AsRef<ReadOnly>,
@@ -543,6 +712,9 @@ where
 
     for tracked in entries {
         let tracked = tracked?;
+        if block::is_blocked(tracked.config()) {
+            continue;
+        }
         if let Some(peer) = tracked.peer_id() {
             remotes.insert(peer, PeerInfo::get(&peer, project, storage));
         }
@@ -550,6 +722,198 @@ where
     Ok(remotes)
 }
 
+/// Get the remote refs of a peer in a project's namespace that may be pruned, as their full
+/// names along with their names relative to the peer, eg. `heads/master`. The peer's `rad/`
+/// refs are left out: they include its view of the project identity, which is needed to
+/// verify the project if the peer is a delegate.
+pub fn prunable_refs(
+    monorepo: &git::Repository,
+    urn: &Urn,
+    peer: &PeerId,
+) -> Result<Vec<(String, String)>> {
+    let prefix = format!(
+        "refs/namespaces/{}/refs/remotes/{}/",
+        urn.encode_id(),
+        peer.default_encoding()
+    );
+    let mut refs = Vec::new();
+
+    for r in monorepo.references_glob(&format!("{}*", prefix))? {
+        let r = r?;
+        let name = match r.name() {
+            Some(name) => name,
+            None => continue,
+        };
+        if let Some(relative) = name.strip_prefix(&prefix) {
+            if !relative.starts_with("rad/") {
+                refs.push((name.to_owned(), relative.to_owned()));
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// Delete the remote refs of a peer from a project's namespace, given a predicate on their
+/// names relative to the peer. See [`prunable_refs`]. Returns the number of refs deleted.
+pub fn prune_refs(
+    monorepo: &git::Repository,
+    urn: &Urn,
+    peer: &PeerId,
+    prune: impl Fn(&str) -> bool,
+) -> Result<usize> {
+    let mut pruned = 0;
+
+    for (name, relative) in prunable_refs(monorepo, urn, peer)? {
+        if prune(&relative) {
+            monorepo.find_reference(&name)?.delete()?;
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+/// Git config key of a project's tracking policy, in its working copy.
+pub const CONFIG_TRACKING_POLICY_KEY: &str = "rad.tracking";
+
+/// Tracking entries changed by applying a policy, see [`TrackingPolicy::apply`].
+#[derive(Debug, Default)]
+pub struct Applied {
+    /// Newly tracked peers, where `None` stands for all peers.
+    pub tracked: Vec<Option<PeerId>>,
+    /// Whether all peers were untracked, as the policy doesn't track all peers.
+    /// Peers tracked explicitly are still tracked.
+    pub untracked_all: bool,
+}
+
+/// Policy deciding which peers of a project are tracked, and thus fetched when syncing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrackingPolicy {
+    /// Track the project delegates, as well as peers tracked explicitly.
+    Delegates,
+    /// Track all peers.
+    All,
+    /// Only track peers tracked explicitly.
+    Manual,
+}
+
+impl Default for TrackingPolicy {
+    fn default() -> Self {
+        Self::Delegates
+    }
+}
+
+impl fmt::Display for TrackingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delegates => write!(f, "delegates-only"),
+            Self::All => write!(f, "all"),
+            Self::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+impl std::str::FromStr for TrackingPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delegates-only" | "delegates" => Ok(Self::Delegates),
+            "all" => Ok(Self::All),
+            "manual" => Ok(Self::Manual),
+            other => Err(anyhow!(
+                "unknown tracking policy '{}': expected `delegates-only`, `all` or `manual`",
+                other
+            )),
+        }
+    }
+}
+
+impl TrackingPolicy {
+    /// Get the tracking policy of the working copy at the given path.
+    /// If none is configured, the default policy is returned.
+    pub fn get(path: &Path) -> Result<Self> {
+        match git::git(path, ["config", "--get", CONFIG_TRACKING_POLICY_KEY]) {
+            Ok(value) => value.trim().parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Set the tracking policy of the working copy at the given path.
+    pub fn set(&self, path: &Path) -> Result<()> {
+        let value = self.to_string();
+
+        git::git(
+            path,
+            [
+                "config",
+                "--local",
+                CONFIG_TRACKING_POLICY_KEY,
+                value.as_str(),
+            ],
+        )
+        .map(|_| ())
+        .map_err(|e| anyhow!("failed to save tracking policy: {}", e))
+    }
+
+    /// Track the peers of a project required by the policy, when the policy is set.
+    /// Existing tracking entries of peers are left as they are, but unless the policy
+    /// tracks all peers, the entry tracking all peers is removed, as replication would
+    /// otherwise keep fetching every peer.
+    pub fn apply(&self, storage: &Storage, project: &Metadata) -> Result<Applied> {
+        let peers = match self {
+            Self::Delegates => project.remotes.iter().copied().map(Some).collect(),
+            Self::All => vec![None],
+            Self::Manual => vec![],
+        };
+        let mut applied = Applied::default();
+
+        for peer in peers {
+            let result = tracking::track(
+                storage,
+                &project.urn,
+                peer,
+                tracking::config::Config::default(),
+                tracking::policy::Track::MustNotExist,
+            )?;
+            if result.is_ok() {
+                applied.tracked.push(peer);
+            }
+        }
+        if *self != Self::All {
+            applied.untracked_all = tracking::untrack(
+                storage,
+                &project.urn,
+                None,
+                tracking::UntrackArgs {
+                    policy: tracking::policy::Untrack::MustExist,
+                    prune: false,
+                },
+            )?
+            .is_ok();
+        }
+        Ok(applied)
+    }
+
+    /// Get the peers whose refs are fetched under this policy, or `None` if the refs of
+    /// all peers are.
+    pub fn peers<S>(&self, project: &Metadata, storage: &S) -> Result<Option<Vec<PeerId>>>
+    where
+        S: AsRef<ReadOnly>,
+    {
+        let mut peers = tracked(project, storage)?.into_keys().collect::<Vec<_>>();
+
+        match self {
+            Self::All => return Ok(None),
+            Self::Delegates => peers.extend(project.remotes.iter().copied()),
+            Self::Manual => {}
+        }
+        peers.sort();
+        peers.dedup();
+
+        Ok(Some(peers))
+    }
+}
+
 pub fn peer_prefix(name: &str) -> String {
     format!("{}/{}", PEER_PREFIX, name)
 }
@@ -0,0 +1,386 @@
+//! Local search index, over the names and descriptions of the projects in storage, and
+//! the titles and descriptions of their issues and patches, for `rad search`.
+//!
+//! The index is an inverted index, from terms to the documents they appear in, stored
+//! in the profile directory. Projects are indexed along with their issues and patches,
+//! and a project is only indexed again once its refs changed, so that updating the
+//! index, eg. after a sync, only reads the projects that were synced.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+
+use librad::git::Storage;
+use librad::git::Urn;
+use librad::profile::Profile;
+
+use crate::{cobs, keys, profile, project};
+
+/// Name of the file the index is stored in, in the profile directory.
+pub const FILE_NAME: &str = "search.json";
+
+/// Kind of an indexed document.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Project,
+    Issue,
+    Patch,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Issue => "issue",
+            Self::Patch => "patch",
+        }
+    }
+}
+
+/// An indexed document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Document {
+    pub kind: Kind,
+    /// URN of the project.
+    pub project: String,
+    /// Object id, of issues and patches.
+    pub id: Option<String>,
+    /// Name of a project, or title of an issue or patch.
+    pub title: String,
+    /// Description.
+    pub body: String,
+}
+
+/// A document matching a query.
+#[derive(Debug)]
+pub struct Match<'a> {
+    pub document: &'a Document,
+    /// How well the document matches: query terms found in the title count more than
+    /// those found in the body.
+    pub score: usize,
+}
+
+/// Documents of an indexed project.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Entry {
+    /// Digest of the project's refs, when it was indexed.
+    version: String,
+    documents: Vec<Document>,
+}
+
+/// The search index.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    /// Indexed projects, by URN.
+    projects: BTreeMap<String, Entry>,
+    /// Terms, and the documents they appear in, as a project URN and the position of
+    /// the document in the project's documents.
+    terms: BTreeMap<String, BTreeSet<(String, usize)>>,
+}
+
+impl Index {
+    /// Load the index of the profile. If there is none yet, returns an empty index.
+    pub fn load(profile: &Profile) -> Result<Self, anyhow::Error> {
+        match fs::read(Self::path(profile)) {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write the index.
+    pub fn write(&self, profile: &Profile) -> Result<(), anyhow::Error> {
+        fs::write(Self::path(profile), serde_json::to_vec(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn path(profile: &Profile) -> PathBuf {
+        profile::scoped_path(profile, FILE_NAME)
+    }
+
+    /// Number of indexed documents.
+    pub fn len(&self) -> usize {
+        self.projects.values().map(|e| e.documents.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the document of an indexed project.
+    pub fn project(&self, urn: &str) -> Option<&Document> {
+        self.projects
+            .get(urn)?
+            .documents
+            .iter()
+            .find(|d| d.kind == Kind::Project)
+    }
+
+    /// Index all projects in storage that changed since they were indexed, and drop the
+    /// projects that aren't in storage anymore. Returns the number of projects indexed.
+    pub fn update_all(&mut self, profile: &Profile, storage: &Storage) -> anyhow::Result<usize> {
+        let projects = project::list(storage)?;
+        let urns = projects
+            .iter()
+            .map(|(urn, _, _)| urn.to_string())
+            .collect::<BTreeSet<_>>();
+        let removed = self
+            .projects
+            .keys()
+            .filter(|urn| !urns.contains(*urn))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for urn in removed {
+            self.remove(&urn);
+        }
+
+        let mut count = 0;
+        for (urn, _, _) in projects {
+            if self.update(profile, storage, &urn)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Index a project, if it changed since it was indexed. Returns whether it was
+    /// indexed.
+    pub fn update(
+        &mut self,
+        profile: &Profile,
+        storage: &Storage,
+        urn: &Urn,
+    ) -> anyhow::Result<bool> {
+        let version = version(profile, urn)?;
+
+        if self
+            .projects
+            .get(&urn.to_string())
+            .map_or(false, |e| e.version == version)
+        {
+            return Ok(false);
+        }
+        let project = match project::get(storage, urn)? {
+            Some(project) => project,
+            None => {
+                self.remove(&urn.to_string());
+                return Ok(true);
+            }
+        };
+        let cobs = cobs::store(profile, storage)?;
+        let mut documents = vec![Document {
+            kind: Kind::Project,
+            project: urn.to_string(),
+            id: None,
+            title: project.name,
+            body: project.description,
+        }];
+
+        for (id, issue) in cobs.issues().all(urn)? {
+            documents.push(Document {
+                kind: Kind::Issue,
+                project: urn.to_string(),
+                id: Some(id.to_string()),
+                title: issue.title().to_owned(),
+                body: issue.description().to_owned(),
+            });
+        }
+        for (id, patch) in cobs.patches().all(urn)? {
+            documents.push(Document {
+                kind: Kind::Patch,
+                project: urn.to_string(),
+                id: Some(id.to_string()),
+                title: patch.title.clone(),
+                body: patch.description().to_owned(),
+            });
+        }
+        self.insert(&urn.to_string(), version, documents);
+
+        Ok(true)
+    }
+
+    /// Find the documents that contain all terms of the query, best matches first.
+    /// The last term of the query also matches the terms it is a prefix of, so that
+    /// partial words match, eg. `sync` matches "synced".
+    pub fn search(&self, query: &str, project: Option<&Urn>) -> Vec<Match<'_>> {
+        let query = terms(query).collect::<Vec<_>>();
+        let project = project.map(|urn| urn.to_string());
+        let mut found: Option<BTreeSet<&(String, usize)>> = None;
+
+        for (i, term) in query.iter().enumerate() {
+            let postings = if i == query.len() - 1 {
+                self.terms
+                    .range(term.clone()..)
+                    .take_while(|(t, _)| t.starts_with(term.as_str()))
+                    .flat_map(|(_, postings)| postings)
+                    .collect::<BTreeSet<_>>()
+            } else {
+                self.terms
+                    .get(term)
+                    .map(|postings| postings.iter().collect())
+                    .unwrap_or_default()
+            };
+            found = Some(match found {
+                Some(found) => found.intersection(&postings).copied().collect(),
+                None => postings,
+            });
+        }
+
+        let mut matches = found
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(urn, _)| project.as_ref().map_or(true, |p| p == urn))
+            .filter_map(|(urn, ix)| self.projects.get(urn)?.documents.get(*ix))
+            .map(|document| Match {
+                document,
+                score: score(document, &query),
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|a, b| b.score.cmp(&a.score));
+        matches
+    }
+
+    /// Replace the documents of a project.
+    fn insert(&mut self, urn: &str, version: String, documents: Vec<Document>) {
+        self.remove(urn);
+
+        for (ix, document) in documents.iter().enumerate() {
+            for term in terms(&document.title).chain(terms(&document.body)) {
+                self.terms
+                    .entry(term)
+                    .or_default()
+                    .insert((urn.to_owned(), ix));
+            }
+        }
+        self.projects
+            .insert(urn.to_owned(), Entry { version, documents });
+    }
+
+    /// Remove the documents of a project.
+    fn remove(&mut self, urn: &str) {
+        if self.projects.remove(urn).is_none() {
+            return;
+        }
+        for postings in self.terms.values_mut() {
+            postings.retain(|(u, _)| u != urn);
+        }
+        self.terms.retain(|_, postings| !postings.is_empty());
+    }
+}
+
+/// Update the index with a project, eg. after it was synced. Nb. storage is opened for
+/// reading only, so the profile's key isn't needed.
+pub fn update(profile: &Profile, urn: &Urn) -> anyhow::Result<()> {
+    let storage = keys::storage_read_only(profile)?;
+    let mut index = Index::load(profile)?;
+
+    if index.update(profile, &storage, urn)? {
+        index.write(profile)?;
+    }
+    Ok(())
+}
+
+/// Get the version of a project, as a digest of its refs, which changes whenever the
+/// project, or any of its issues or patches, changes.
+fn version(profile: &Profile, urn: &Urn) -> anyhow::Result<String> {
+    let repo = git2::Repository::open_bare(profile.paths().git_dir())?;
+    let mut refs = Vec::new();
+
+    for r in repo
+        .references_glob(&format!("refs/namespaces/{}/*", urn.encode_id()))?
+        .flatten()
+    {
+        if let (Some(name), Some(oid)) = (r.name(), r.target()) {
+            refs.push(format!("{} {}\n", name, oid));
+        }
+    }
+    refs.sort();
+
+    let digest = sha2::Sha256::digest(refs.concat().as_bytes());
+
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Split text into terms, ie. lowercase words of letters and digits.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+}
+
+/// Score a document for the terms of a query.
+fn score(document: &Document, query: &[String]) -> usize {
+    let title = terms(&document.title).collect::<Vec<_>>();
+    let body = terms(&document.body).collect::<Vec<_>>();
+
+    query
+        .iter()
+        .map(|q| {
+            let matches = |t: &String| t.starts_with(q.as_str());
+
+            title.iter().filter(|t| matches(t)).count() * 3
+                + body.iter().filter(|t| matches(t)).count()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn document(kind: Kind, project: &str, title: &str, body: &str) -> Document {
+        Document {
+            kind,
+            project: project.to_owned(),
+            id: None,
+            title: title.to_owned(),
+            body: body.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let mut index = Index::default();
+
+        index.insert(
+            "acme",
+            "1".to_owned(),
+            vec![
+                document(Kind::Project, "acme", "Acme", "Rockets, and sync engines"),
+                document(Kind::Issue, "acme", "Sync is slow", "Syncing takes hours."),
+                document(Kind::Patch, "acme", "Speed up rockets", ""),
+            ],
+        );
+        index.insert(
+            "heartwood",
+            "1".to_owned(),
+            vec![document(Kind::Project, "heartwood", "Heartwood", "Sync")],
+        );
+
+        let titles = |query: &str| {
+            index
+                .search(query, None)
+                .into_iter()
+                .map(|m| m.document.title.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles("slow sync"), vec!["Sync is slow"]);
+        assert_eq!(titles("ROCKET"), vec!["Speed up rockets", "Acme"]);
+        assert_eq!(titles("sync")[0], "Sync is slow");
+        assert_eq!(titles("sync").len(), 3);
+        assert!(titles("rocket sync slow").is_empty());
+        assert!(titles("").is_empty());
+
+        index.insert("acme", "2".to_owned(), vec![]);
+        assert_eq!(titles("sync"), vec!["Heartwood"]);
+        assert!(index.terms.get("rockets").is_none());
+        assert_eq!(index.len(), 1);
+    }
+}
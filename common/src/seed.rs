@@ -11,7 +11,7 @@ use url::{Host, Url};
 
 use crate::args::Error;
 use crate::sync::Seed;
-use crate::{git, project};
+use crate::{git, offline, project, timing};
 
 pub const CONFIG_SEED_KEY: &str = "rad.seed";
 pub const CONFIG_PEER_KEY: &str = "rad.peer";
@@ -238,6 +238,43 @@ pub fn set_peer_seed(seed: &Seed<String>, peer_id: &PeerId) -> Result<(), anyhow
         .context("failed to save seed configuration")
 }
 
+/// Set the project's default seeds within the repository at the given path,
+/// replacing any previously configured seeds.
+pub fn set_seeds(path: &Path, seeds: &[Seed<String>]) -> Result<(), anyhow::Error> {
+    // Nb. This fails if no seeds were configured, which is fine.
+    git::git(path, ["config", "--local", "--unset-all", CONFIG_SEED_KEY]).ok();
+
+    for seed in seeds {
+        let seed = seed.to_string();
+        let args = ["config", "--local", "--add", CONFIG_SEED_KEY, seed.as_str()];
+
+        git::git(path, args).context("failed to save seed configuration")?;
+    }
+    Ok(())
+}
+
+/// Get the project's default seeds within the repository at the given path.
+/// Returns an empty list if no seeds are configured.
+pub fn get_seeds(path: &Path) -> Result<Vec<Seed<String>>, anyhow::Error> {
+    let output = match git::git(path, ["config", "--get-all", CONFIG_SEED_KEY]) {
+        Ok(output) => output,
+        Err(_) => return Ok(vec![]),
+    };
+
+    output
+        .lines()
+        .map(|line| {
+            Seed::from_str(line.trim()).map_err(|_| {
+                anyhow!(
+                    "`{}` is not set to a valid seed: '{}'",
+                    CONFIG_SEED_KEY,
+                    line
+                )
+            })
+        })
+        .collect()
+}
+
 /// Get the configured "peer" seed within the local repository.
 pub fn get_peer_seed(peer_id: &PeerId) -> Result<Url, anyhow::Error> {
     let path = Path::new(".");
@@ -251,12 +288,12 @@ pub fn get_peer_seed(peer_id: &PeerId) -> Result<Url, anyhow::Error> {
 }
 
 /// Query a seed node for its [`PeerId`].
-pub fn get_seed_id(mut seed: Url) -> Result<PeerId, anyhow::Error> {
-    seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
-    seed = seed.join("/v1/peer")?;
+pub fn get_seed_id(seed: Url) -> Result<PeerId, anyhow::Error> {
+    let seed = api_url(seed, "/v1/peer")?;
 
-    let agent = ureq::Agent::new();
-    let obj: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
+    offline::check("querying seeds")?;
+
+    let obj = get_json(&seed)?;
 
     let id = obj
         .get("id")
@@ -268,34 +305,68 @@ pub fn get_seed_id(mut seed: Url) -> Result<PeerId, anyhow::Error> {
     Ok(id)
 }
 
+/// Query a seed node for the version of its HTTP API.
+pub fn get_seed_version(seed: Url) -> Result<String, anyhow::Error> {
+    let seed = api_url(seed, "/")?;
+
+    offline::check("querying seeds")?;
+
+    let obj = get_json(&seed)?;
+
+    let version = obj
+        .get("version")
+        .ok_or(anyhow!("missing 'version' in seed API response"))?
+        .as_str()
+        .ok_or(anyhow!("'version' is not a string"))?;
+
+    Ok(version.to_owned())
+}
+
 /// Query a seed node for a project commit.
-pub fn get_commit(
-    mut seed: Url,
-    project: &Urn,
-    commit: &git::Oid,
-) -> Result<Commit, anyhow::Error> {
-    seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
-    seed = seed.join(&format!("/v1/projects/{}/commits/{}", project, commit))?;
-
-    let agent = ureq::Agent::new();
-    let val: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
+pub fn get_commit(seed: Url, project: &Urn, commit: &git::Oid) -> Result<Commit, anyhow::Error> {
+    let seed = api_url(
+        seed,
+        &format!("/v1/projects/{}/commits/{}", project, commit),
+    )?;
+
+    offline::check("querying seeds")?;
+
+    let val = get_json(&seed)?;
     let commit = serde_json::from_value(val)?;
 
     Ok(commit)
 }
 
 /// Query a seed node for a project's remotes.
-pub fn get_remotes(mut seed: Url, project: &Urn) -> Result<Vec<project::PeerInfo>, anyhow::Error> {
-    seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
-    seed = seed.join(&format!("/v1/projects/{}/remotes", project))?;
+pub fn get_remotes(seed: Url, project: &Urn) -> Result<Vec<project::PeerInfo>, anyhow::Error> {
+    let seed = api_url(seed, &format!("/v1/projects/{}/remotes", project))?;
+
+    offline::check("querying seeds")?;
 
-    let agent = ureq::Agent::new();
-    let val: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
+    let val = get_json(&seed)?;
     let response = serde_json::from_value(val)?;
 
     Ok(response)
 }
 
+/// Get the URL of an endpoint of a seed node's HTTP API.
+fn api_url(mut seed: Url, path: &str) -> Result<Url, anyhow::Error> {
+    seed.set_port(Some(DEFAULT_SEED_API_PORT))
+        .map_err(|_| anyhow!("seed URL '{}' cannot have a port", seed))?;
+
+    Ok(seed.join(path)?)
+}
+
+/// Get a JSON value from a seed node's HTTP API.
+fn get_json(url: &Url) -> Result<serde_json::Value, anyhow::Error> {
+    timing::measure(timing::Category::Network, || {
+        let agent = ureq::Agent::new();
+        let val = agent.get(url.as_str()).call()?.into_json()?;
+
+        Ok(val)
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -341,4 +412,14 @@ mod test {
         assert_eq!(addr.urn, None);
         assert_eq!(addr.port(), DEFAULT_SEED_P2P_PORT);
     }
+
+    #[test]
+    fn test_api_url() {
+        let seed = Url::parse("https://willow.radicle.garden").unwrap();
+        let url = api_url(seed, "/v1/peer").unwrap();
+        assert_eq!(url.as_str(), "https://willow.radicle.garden:8777/v1/peer");
+
+        let seed = Url::parse("file:///willow.radicle.garden").unwrap();
+        assert!(api_url(seed, "/v1/peer").is_err());
+    }
 }
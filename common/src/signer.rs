@@ -4,7 +4,7 @@ use librad::crypto::keystore::sign::ed25519;
 use librad::crypto::BoxedSignError;
 use librad::crypto::BoxedSigner;
 use librad::profile::Profile;
-use librad::SecretKey;
+use librad::{PeerId, SecretKey};
 
 use lnk_clib::keys;
 use lnk_clib::keys::ssh::SshAuthSock;
@@ -40,6 +40,12 @@ impl ToSigner for ZeroizingSecretKey {
     }
 }
 
+impl ToSigner for ReadOnlySigner {
+    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, keys::ssh::Error> {
+        Ok(BoxedSigner::new(self))
+    }
+}
+
 /// Secret key that is zeroed when dropped.
 #[derive(Clone)]
 pub struct ZeroizingSecretKey {
@@ -79,3 +85,50 @@ impl librad::Signer for ZeroizingSecretKey {
             .map_err(BoxedSignError::from_std_error)
     }
 }
+
+/// Error signing with a [`ReadOnlySigner`].
+#[derive(Debug, thiserror::Error)]
+#[error("storage was opened for reading only, and can't be signed with")]
+pub struct ReadOnlyError;
+
+/// Signer of a peer whose key isn't available, which fails to sign. Reading some data,
+/// eg. collaborative objects, takes a [`librad::git::Storage`], which is opened with a
+/// signer: opening it with this one doesn't require the key, and so never asks for a
+/// passphrase. See [`crate::keys::storage_read_only`].
+#[derive(Clone)]
+pub struct ReadOnlySigner {
+    key: ed25519::PublicKey,
+}
+
+impl ReadOnlySigner {
+    pub fn new(peer: &PeerId) -> Self {
+        let mut key = [0; 32];
+        key.copy_from_slice(peer.as_public_key().as_ref());
+
+        Self {
+            key: ed25519::PublicKey(key),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ed25519::Signer for ReadOnlySigner {
+    type Error = BoxedSignError;
+
+    fn public_key(&self) -> ed25519::PublicKey {
+        self.key
+    }
+
+    async fn sign(&self, _data: &[u8]) -> Result<ed25519::Signature, Self::Error> {
+        Err(BoxedSignError::from_std_error(ReadOnlyError))
+    }
+}
+
+impl librad::Signer for ReadOnlySigner {
+    fn sign_blocking(
+        &self,
+        _data: &[u8],
+    ) -> Result<librad::keystore::sign::Signature, <Self as ed25519::Signer>::Error> {
+        Err(BoxedSignError::from_std_error(ReadOnlyError))
+    }
+}
@@ -0,0 +1,210 @@
+//! SSH signatures.
+//!
+//! Signatures in the format of `ssh-keygen -Y sign`, as used by git for SSH commit
+//! signing, made with radicle keys. See `PROTOCOL.sshsig` in the OpenSSH sources.
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+use anyhow::{anyhow, Context as _};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use sha2::Digest;
+
+use librad::PeerId;
+
+/// Signature namespace used by git.
+pub const NAMESPACE_GIT: &str = "git";
+
+const MAGIC: &[u8] = b"SSHSIG";
+const VERSION: u32 = 1;
+const KEY_TYPE: &str = "ssh-ed25519";
+const HASH_ALGORITHM: &str = "sha512";
+const ARMOR_BEGIN: &str = "-----BEGIN SSH SIGNATURE-----";
+const ARMOR_END: &str = "-----END SSH SIGNATURE-----";
+
+/// A parsed SSH signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Ed25519 public key of the signer.
+    pub key: Vec<u8>,
+    /// Namespace of the signature, eg. `git`.
+    pub namespace: String,
+    /// Algorithm the message was hashed with.
+    pub hash_algorithm: String,
+    /// Ed25519 signature.
+    pub signature: Vec<u8>,
+}
+
+impl Signature {
+    /// Parse an armored signature.
+    pub fn from_armored(armored: &str) -> Result<Self, anyhow::Error> {
+        let body = armored
+            .trim()
+            .strip_prefix(ARMOR_BEGIN)
+            .and_then(|s| s.strip_suffix(ARMOR_END))
+            .ok_or_else(|| anyhow!("not an SSH signature"))?;
+        let bytes = base64::decode(body.split_whitespace().collect::<String>())?;
+
+        Self::decode(&bytes).context("invalid SSH signature")
+    }
+
+    fn decode(mut bytes: &[u8]) -> io::Result<Self> {
+        let mut magic = [0; 6];
+        bytes.read_exact(&mut magic)?;
+
+        if magic != MAGIC || bytes.read_u32::<BigEndian>()? != VERSION {
+            return Err(invalid("unsupported signature format"));
+        }
+        let key = read_key(&mut read_string(&mut bytes)?.as_slice())?;
+        let namespace = String::from_utf8_lossy(&read_string(&mut bytes)?).into_owned();
+        let _reserved = read_string(&mut bytes)?;
+        let hash_algorithm = String::from_utf8_lossy(&read_string(&mut bytes)?).into_owned();
+        let signature = read_key(&mut read_string(&mut bytes)?.as_slice())?;
+
+        Ok(Self {
+            key,
+            namespace,
+            hash_algorithm,
+            signature,
+        })
+    }
+
+    /// Check that the signature is valid for the given message.
+    pub fn verify(&self, message: &[u8]) -> Result<(), anyhow::Error> {
+        let data = signed_data(&self.namespace, &self.hash_algorithm, message)?;
+        let key = ed25519_zebra::VerificationKey::try_from(self.key.as_slice())
+            .map_err(|_| anyhow!("invalid signer key"))?;
+        let signature = ed25519_zebra::Signature::try_from(self.signature.as_slice())
+            .map_err(|_| anyhow!("invalid signature"))?;
+
+        key.verify(&signature, &data)
+            .map_err(|_| anyhow!("bad signature"))
+    }
+
+    /// Check whether the signature was made by the key of the given peer.
+    pub fn is_signed_by(&self, peer: &PeerId) -> bool {
+        self.key.as_slice() == peer.as_public_key().as_ref()
+    }
+
+    /// The SSH fingerprint of the signer key, as shown by `ssh-keygen -l`.
+    pub fn fingerprint(&self) -> String {
+        let mut blob = Vec::new();
+        write_string(&mut blob, KEY_TYPE.as_bytes());
+        write_string(&mut blob, &self.key);
+
+        let sha = sha2::Sha256::digest(&blob).to_vec();
+        format!("SHA256:{}", base64::encode(sha).trim_end_matches('='))
+    }
+}
+
+/// Sign a message with a radicle signer, and return the armored signature.
+pub fn sign(
+    signer: &impl librad::Signer,
+    peer: &PeerId,
+    namespace: &str,
+    message: &[u8],
+) -> Result<String, anyhow::Error> {
+    let data = signed_data(namespace, HASH_ALGORITHM, message)?;
+    let signature = signer
+        .sign_blocking(&data)
+        .map_err(|e| anyhow!("failed to sign: {}", e))?;
+
+    let mut key = Vec::new();
+    write_string(&mut key, KEY_TYPE.as_bytes());
+    write_string(&mut key, peer.as_public_key().as_ref());
+
+    let mut sig = Vec::new();
+    write_string(&mut sig, KEY_TYPE.as_bytes());
+    write_string(&mut sig, &signature.0);
+
+    let mut blob = MAGIC.to_vec();
+    blob.write_u32::<BigEndian>(VERSION)?;
+    write_string(&mut blob, &key);
+    write_string(&mut blob, namespace.as_bytes());
+    write_string(&mut blob, &[]);
+    write_string(&mut blob, HASH_ALGORITHM.as_bytes());
+    write_string(&mut blob, &sig);
+
+    let encoded = base64::encode(blob);
+    let mut armored = String::from(ARMOR_BEGIN);
+    armored.push('\n');
+
+    for line in encoded.as_bytes().chunks(70) {
+        armored.push_str(&String::from_utf8_lossy(line));
+        armored.push('\n');
+    }
+    armored.push_str(ARMOR_END);
+    armored.push('\n');
+
+    Ok(armored)
+}
+
+/// The data actually signed, for a message.
+fn signed_data(
+    namespace: &str,
+    hash_algorithm: &str,
+    message: &[u8],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let hash = match hash_algorithm {
+        "sha512" => sha2::Sha512::digest(message).to_vec(),
+        "sha256" => sha2::Sha256::digest(message).to_vec(),
+        other => anyhow::bail!("unsupported hash algorithm '{}'", other),
+    };
+    let mut data = MAGIC.to_vec();
+
+    write_string(&mut data, namespace.as_bytes());
+    write_string(&mut data, &[]);
+    write_string(&mut data, hash_algorithm.as_bytes());
+    write_string(&mut data, &hash);
+
+    Ok(data)
+}
+
+/// Read an Ed25519 key or signature blob, returning the raw key or signature.
+fn read_key(bytes: &mut &[u8]) -> io::Result<Vec<u8>> {
+    if read_string(bytes)? != KEY_TYPE.as_bytes() {
+        return Err(invalid("only Ed25519 signatures are supported"));
+    }
+    read_string(bytes)
+}
+
+fn read_string(bytes: &mut &[u8]) -> io::Result<Vec<u8>> {
+    let len = bytes.read_u32::<BigEndian>()? as usize;
+    if len > bytes.len() {
+        return Err(invalid("truncated signature"));
+    }
+    let (string, rest) = bytes.split_at(len);
+    *bytes = rest;
+
+    Ok(string.to_vec())
+}
+
+fn write_string(buf: &mut Vec<u8>, string: &[u8]) {
+    buf.extend_from_slice(&(string.len() as u32).to_be_bytes());
+    buf.extend_from_slice(string);
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use librad::SecretKey;
+
+    #[test]
+    fn test_sign_verify() {
+        let key = SecretKey::new();
+        let peer = PeerId::from(key.clone());
+        let other = PeerId::from(SecretKey::new());
+
+        let armored = sign(&key, &peer, NAMESPACE_GIT, b"tree 1234\n").unwrap();
+        let signature = Signature::from_armored(&armored).unwrap();
+
+        assert_eq!(signature.namespace, NAMESPACE_GIT);
+        assert!(signature.is_signed_by(&peer));
+        assert!(!signature.is_signed_by(&other));
+        assert!(signature.verify(b"tree 1234\n").is_ok());
+        assert!(signature.verify(b"tree 5678\n").is_err());
+    }
+}
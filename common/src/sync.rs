@@ -1,11 +1,20 @@
+pub mod pack;
 mod push;
+pub mod refs;
+pub mod scope;
+pub mod throttle;
 
+use std::cell::RefCell;
 use std::convert::TryInto;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time;
 
 use anyhow::anyhow;
+use futures::stream::{self, StreamExt};
 
 use librad::crypto::BoxedSigner;
 use librad::git::Urn;
@@ -24,9 +33,99 @@ use lnk_clib::seed::store::FileStore;
 
 pub use lnk_clib::seed::{Seed, Seeds};
 pub use lnk_sync::Mode;
+pub use refs::Refs;
 
 use crate::config;
 use crate::nonempty::NonEmpty;
+use crate::offline;
+use crate::project;
+use crate::seed;
+
+use self::throttle::Throttle;
+
+/// Host of the web gateway, that shows projects as they are on a seed.
+pub const GATEWAY_HOST: &str = "app.radicle.xyz";
+/// Maximum number of seeds synced with concurrently.
+pub const MAX_CONCURRENT_SEEDS: usize = 4;
+/// Interval at which the bytes received by fetches are reported.
+pub const PROGRESS_INTERVAL: time::Duration = time::Duration::from_millis(250);
+/// Maximum delay between retries.
+pub const MAX_RETRY_DELAY: time::Duration = time::Duration::from_secs(60);
+
+/// Transport used to sync with seeds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Transport {
+    /// The native peer-to-peer protocol.
+    Link,
+    /// The seeds' smart HTTP git endpoints. Pushes are authenticated with the seed's
+    /// credential, if the seed asks for one.
+    Http,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self::Link
+    }
+}
+
+impl std::str::FromStr for Transport {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "link" => Ok(Self::Link),
+            "http" | "https" => Ok(Self::Http),
+            other => Err(anyhow!(
+                "unknown transport '{}': expected `link` or `http`",
+                other
+            )),
+        }
+    }
+}
+
+/// Object filter of partial fetches, in the syntax of `git fetch --filter`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Omit all blobs, ie. `blob:none`.
+    BlobNone,
+    /// Omit blobs larger than the given number of bytes, ie. `blob:limit=<n>`.
+    BlobLimit(u64),
+    /// Omit trees and blobs deeper than the given depth, ie. `tree:<depth>`.
+    Tree(u32),
+}
+
+impl std::str::FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow!(
+                "invalid filter '{}': expected `blob:none`, `blob:limit=<bytes>` or `tree:<depth>`",
+                s
+            )
+        };
+
+        if s == "blob:none" {
+            Ok(Self::BlobNone)
+        } else if let Some(limit) = s.strip_prefix("blob:limit=") {
+            limit.parse().map(Self::BlobLimit).map_err(|_| invalid())
+        } else if let Some(depth) = s.strip_prefix("tree:") {
+            depth.parse().map(Self::Tree).map_err(|_| invalid())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BlobNone => write!(f, "blob:none"),
+            Self::BlobLimit(limit) => write!(f, "blob:limit={}", limit),
+            Self::Tree(depth) => write!(f, "tree:{}", depth),
+        }
+    }
+}
 
 /// Sync result of a seed.
 #[derive(Debug)]
@@ -34,48 +133,364 @@ pub struct SyncResult {
     pub seed: Seed<Vec<SocketAddr>>,
     pub fetch: Option<Result<replication::Success, client::error::Replicate>>,
     pub push: Option<Result<push::Success, push::Error>>,
+    /// Number of attempts made to sync with the seed.
+    pub attempts: u32,
+}
+
+/// Sync settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Timeout of each operation with a seed.
+    pub timeout: time::Duration,
+    /// Retry policy of failed operations.
+    pub retry: Retry,
+    /// Maximum transfer rate of git transfers, in bytes per second.
+    pub rate_limit: Option<u64>,
+    /// Object filter of branches fetched over HTTP, for partial fetches.
+    pub filter: Option<Filter>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::from(&config::SyncConfig::default())
+    }
+}
+
+impl From<&config::SyncConfig> for Settings {
+    fn from(cfg: &config::SyncConfig) -> Self {
+        Self {
+            timeout: time::Duration::from_secs(cfg.timeout),
+            retry: Retry::from(cfg),
+            rate_limit: cfg.rate_limit.map(|kib| kib * 1024),
+            filter: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Load the sync settings configured for the profile.
+    pub fn load(profile: &Profile) -> Self {
+        config::Config::load(profile)
+            .map(|cfg| Self::from(&cfg.sync))
+            .unwrap_or_default()
+    }
+
+    /// Whether branches are fetched partially, ie. filtered.
+    pub fn is_partial(&self) -> bool {
+        self.filter.is_some()
+    }
+}
+
+/// Whether commands sync their changes to seeds, unless told otherwise with `--[no-]sync`.
+/// See the `sync.auto` configuration key, which `RAD_NO_SYNC` and offline mode turn off.
+pub fn is_auto() -> bool {
+    config::Config::current().map_or(true, |cfg| cfg.sync.auto && !cfg.offline)
+}
+
+/// Get the seed configured with `sync.seed`, if any, for commands to sync with when no
+/// seed is given with `--seed`.
+pub fn default_seed() -> anyhow::Result<Option<Seed<String>>> {
+    let cfg = config::Config::current()?;
+
+    cfg.sync
+        .seed
+        .as_ref()
+        .map(|seed| {
+            Seed::from_str(seed)
+                .map_err(|_| anyhow!("invalid seed address '{}' in `sync.seed`", seed))
+        })
+        .transpose()
+}
+
+/// Retry policy for syncing with seeds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Retry {
+    /// Number of times a failed operation is retried.
+    pub retries: u32,
+    /// Delay before the first retry. Doubled after every retry, up to [`MAX_RETRY_DELAY`].
+    pub backoff: time::Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::from(&config::SyncConfig::default())
+    }
+}
+
+impl From<&config::SyncConfig> for Retry {
+    fn from(cfg: &config::SyncConfig) -> Self {
+        Self {
+            retries: cfg.retries,
+            backoff: time::Duration::from_millis(cfg.backoff),
+        }
+    }
+}
+
+impl Retry {
+    /// Delay to wait before the given retry, starting at `1`.
+    pub fn delay(&self, retry: u32) -> time::Duration {
+        self.backoff
+            .checked_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+    }
+}
+
+impl SyncResult {
+    /// Get the refs that were fetched from, or pushed to the seed.
+    pub fn refs(&self) -> Vec<RefUpdate> {
+        let mut refs = Vec::new();
+
+        if let Some(Ok(success)) = &self.fetch {
+            for update in success.updated_refs() {
+                refs.push(match update {
+                    replication::Updated::Direct { name, target } => RefUpdate {
+                        direction: Direction::Fetch,
+                        name: name.to_string(),
+                        oid: Some(target.to_string()),
+                    },
+                    replication::Updated::Symbolic { name, target } => RefUpdate {
+                        direction: Direction::Fetch,
+                        name: name.to_string(),
+                        oid: Some(target.to_string()),
+                    },
+                    replication::Updated::Prune { name } => RefUpdate {
+                        direction: Direction::Fetch,
+                        name: name.to_string(),
+                        oid: None,
+                    },
+                });
+            }
+        }
+        if let Some(Ok(success)) = &self.push {
+            for r in &success.refs {
+                refs.push(RefUpdate {
+                    direction: Direction::Push,
+                    name: r.name.to_string(),
+                    oid: Some(r.oid.to_string()),
+                });
+            }
+            for name in &success.pruned {
+                refs.push(RefUpdate {
+                    direction: Direction::Push,
+                    name: name.to_string(),
+                    oid: None,
+                });
+            }
+        }
+        refs
+    }
+
+    /// Get the validation errors of the fetched refs, eg. refs that were rejected because
+    /// they didn't match their peer's signed refs. Such refs are not applied.
+    pub fn rejected(&self) -> Vec<String> {
+        if let Some(Ok(success)) = &self.fetch {
+            success
+                .validation_errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Check whether all attempted operations with the seed succeeded.
+    pub fn is_success(&self) -> bool {
+        !matches!(self.fetch, Some(Err(_))) && !matches!(self.push, Some(Err(_)))
+    }
+}
+
+/// Direction of a ref update.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    Fetch,
+    Push,
+}
+
+/// A ref that was updated during a sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefUpdate {
+    pub direction: Direction,
+    /// Name of the ref.
+    pub name: String,
+    /// New target of the ref, or `None` if it was pruned.
+    pub oid: Option<String>,
+}
+
+/// Sync progress event, reported while syncing.
+#[derive(Debug)]
+pub enum Progress<'a> {
+    /// Fetching from the given seed.
+    Fetching(&'a Seed<Vec<SocketAddr>>),
+    /// Pushing to the given seed.
+    Pushing(&'a Seed<Vec<SocketAddr>>),
+    /// Progress message sent by the seed over the transport's sideband,
+    /// eg. objects and bytes transferred.
+    Message(&'a Seed<Vec<SocketAddr>>, &'a str),
+    /// Bytes received so far from the given seed.
+    Received(&'a Seed<Vec<SocketAddr>>, u64),
+    /// Attempt with the given seed failed; retrying after the given delay.
+    Retrying(&'a Seed<Vec<SocketAddr>>, u32, time::Duration),
 }
 
 /// Sync the given URN with the provided list of seeds.
+/// Up to [`MAX_CONCURRENT_SEEDS`] seeds are synced with concurrently; results are
+/// returned in the order of the given seeds. Progress is reported via the `progress` callback.
 pub async fn sync<S, E>(
     client: &Client<S, E>,
+    profile: &Profile,
     urn: Urn,
     seeds: Seeds,
     mode: Mode,
-    timeout: time::Duration,
+    settings: Settings,
+    progress: impl FnMut(Progress),
 ) -> Vec<SyncResult>
 where
     S: Signer + Clone,
     E: ConnectPeer + Clone + Send + Sync + 'static,
 {
-    let mut syncs = Vec::with_capacity(seeds.len());
-    let is_push = mode.is_push();
-    let is_fetch = mode.is_fetch();
     let Seeds(seeds) = seeds;
+    // Nb. The seed futures are all polled from the current task, so a `RefCell` suffices.
+    let progress = RefCell::new(progress);
+    let monorepo = profile.paths().git_dir();
 
-    for seed in seeds {
-        let fetch = if is_fetch {
-            match tokio::time::timeout(timeout, client.replicate(seed.clone(), urn.clone(), None))
-                .await
-            {
-                Ok(result) => Some(result),
-                Err(_) => Some(Err(client::error::Replicate::NoConnection(
-                    client::error::NoConnection(seed.peer),
-                ))),
+    stream::iter(seeds)
+        .map(|seed| sync_seed(client, monorepo, &urn, seed, mode, settings, &progress))
+        .buffered(MAX_CONCURRENT_SEEDS)
+        .collect()
+        .await
+}
+
+/// Sync the given URN with a single seed.
+async fn sync_seed<S, E>(
+    client: &Client<S, E>,
+    monorepo: &Path,
+    urn: &Urn,
+    seed: Seed<Vec<SocketAddr>>,
+    mode: Mode,
+    settings: Settings,
+    progress: &RefCell<impl FnMut(Progress)>,
+) -> SyncResult
+where
+    S: Signer + Clone,
+    E: ConnectPeer + Clone + Send + Sync + 'static,
+{
+    let Settings { timeout, retry, .. } = settings;
+    let mut attempts = 0;
+
+    let fetch = if mode.is_fetch() {
+        let (result, n) = with_retry(retry, &seed, progress, || {
+            let seed = &seed;
+            (progress.borrow_mut())(Progress::Fetching(seed));
+
+            replicate(client, monorepo, urn, seed, timeout, move |bytes| {
+                (progress.borrow_mut())(Progress::Received(seed, bytes))
+            })
+        })
+        .await;
+        attempts = attempts.max(n);
+
+        Some(result)
+    } else {
+        None
+    };
+
+    let push = if mode.is_push() {
+        let (result, n) = with_retry(retry, &seed, progress, || {
+            let seed = &seed;
+            (progress.borrow_mut())(Progress::Pushing(seed));
+
+            push::push(client, urn.clone(), seed.clone(), timeout, move |msg| {
+                (progress.borrow_mut())(Progress::Message(seed, msg))
+            })
+        })
+        .await;
+        attempts = attempts.max(n);
+
+        Some(result)
+    } else {
+        None
+    };
+
+    SyncResult {
+        seed,
+        fetch,
+        push,
+        attempts,
+    }
+}
+
+/// Fetch the given URN from a seed into the monorepo, reporting the bytes received
+/// every [`PROGRESS_INTERVAL`]. The fetch is aborted once no bytes are received for
+/// longer than the timeout.
+///
+/// Nb. Replication can't be throttled to the rate limit.
+async fn replicate<S, E>(
+    client: &Client<S, E>,
+    monorepo: &Path,
+    urn: &Urn,
+    seed: &Seed<Vec<SocketAddr>>,
+    timeout: time::Duration,
+    mut progress: impl FnMut(u64),
+) -> Result<replication::Success, client::error::Replicate>
+where
+    S: Signer + Clone,
+    E: ConnectPeer + Clone + Send + Sync + 'static,
+{
+    let mut incoming = pack::Incoming::new(monorepo);
+    let mut throttle = Throttle::new(timeout, None);
+    let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+    let replicate = client.replicate(seed.clone(), urn.clone(), None);
+    futures::pin_mut!(replicate);
+
+    loop {
+        tokio::select! {
+            result = &mut replicate => break result,
+            _ = interval.tick() => {
+                let received = incoming.received();
+
+                if received > 0 {
+                    progress(received);
+                }
+                throttle.update(received);
+
+                if throttle.is_stalled() {
+                    break Err(client::error::Replicate::NoConnection(
+                        client::error::NoConnection(seed.peer),
+                    ));
+                }
             }
-        } else {
-            None
-        };
+        }
+    }
+}
 
-        let push = if is_push {
-            Some(push::push(client, urn.clone(), seed.clone(), timeout).await)
-        } else {
-            None
-        };
+/// Run the given operation until it succeeds, or the retries are exhausted, waiting
+/// an exponentially increasing delay between attempts. Returns the last result, along
+/// with the number of attempts made.
+async fn with_retry<T, E, F, Fut>(
+    retry: Retry,
+    seed: &Seed<Vec<SocketAddr>>,
+    progress: &RefCell<impl FnMut(Progress)>,
+    mut op: F,
+) -> (Result<T, E>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        let result = op().await;
+        attempt += 1;
 
-        syncs.push(SyncResult { seed, fetch, push })
+        if result.is_ok() || attempt > retry.retries {
+            return (result, attempt);
+        }
+        let delay = retry.delay(attempt);
+
+        (progress.borrow_mut())(Progress::Retrying(seed, attempt, delay));
+        tokio::time::sleep(delay).await;
     }
-    syncs
 }
 
 /// Create a sync client.
@@ -83,6 +498,8 @@ pub async fn client(
     signer: BoxedSigner,
     profile: &Profile,
 ) -> anyhow::Result<Client<BoxedSigner, SendOnly>> {
+    offline::check("syncing with seeds")?;
+
     let spawner = Spawner::from_current().ok_or(anyhow!("cannot create spawner"))?;
     let network = Network::default();
     let config = client::Config {
@@ -98,15 +515,42 @@ pub async fn client(
     Ok(client)
 }
 
-/// Get the seeds configured for the profile.
-/// First checks local (working copy) config, then global.
-pub fn seeds(profile: &Profile) -> anyhow::Result<NonEmpty<Seed<String>>> {
+/// Get the seeds to sync the given project with.
+/// First checks the project's git config (`rad.seed`), if run from its working copy, then
+/// the configuration, see [`config`], then the profile's seeds. Seeds that are configured,
+/// but invalid, are skipped.
+pub fn seeds(profile: &Profile, urn: &Urn) -> anyhow::Result<NonEmpty<Seed<String>>> {
+    seeds_with(profile, urn, |_| {})
+}
+
+/// Like [`seeds`], reporting the configured seeds that are invalid via `warn`.
+pub fn seeds_with(
+    profile: &Profile,
+    urn: &Urn,
+    mut warn: impl FnMut(anyhow::Error),
+) -> anyhow::Result<NonEmpty<Seed<String>>> {
+    // Nb. The working copy's seeds are those of its own project, not of any URN.
+    if matches!(project::cwd(), Ok((cwd, _)) if &cwd == urn) {
+        match seed::get_seeds(Path::new(".")) {
+            Ok(seeds) => {
+                if let Ok(seeds) = seeds.try_into() {
+                    return Ok(seeds);
+                }
+            }
+            Err(err) => warn(err),
+        }
+    }
     if let Ok(config) = config::Config::load(profile) {
-        let seeds = config
-            .seeds()
-            .cloned()
-            .map(|s| s.try_into())
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut seeds = Vec::new();
+
+        for cfg in config.seeds().cloned() {
+            let url = cfg.p2p.clone();
+
+            match cfg.try_into() {
+                Ok(seed) => seeds.push(seed),
+                Err(err) => warn(anyhow!("invalid seed '{}' in configuration: {}", url, err)),
+            }
+        }
         if let Ok(seeds) = seeds.try_into() {
             return Ok(seeds);
         }
@@ -123,3 +567,47 @@ pub fn seeds(profile: &Profile) -> anyhow::Result<NonEmpty<Seed<String>>> {
         .try_into()
         .map_err(|_| anyhow!("No seeds configured for profile {}", profile.id()))
 }
+
+/// Get the URL of a project on the web gateway, as it is on the first configured seed.
+pub fn gateway_url(profile: &Profile, urn: &Urn) -> Option<String> {
+    let seeds = seeds(profile, urn).ok()?;
+    let url = url::Url::parse(&format!("https://{}", seeds.first().addrs)).ok()?;
+
+    url.host_str()
+        .map(|host| format!("https://{}/seeds/{}/{}", GATEWAY_HOST, host, urn))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_filter_roundtrip() {
+        for s in ["blob:none", "blob:limit=1024", "tree:0"] {
+            assert_eq!(Filter::from_str(s).unwrap().to_string(), s);
+        }
+        assert!(Filter::from_str("blob:limit=1k").is_err());
+        assert!(Filter::from_str("sparse:oid=HEAD").is_err());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = Retry {
+            retries: u32::MAX,
+            backoff: time::Duration::from_millis(500),
+        };
+
+        assert_eq!(retry.delay(1), time::Duration::from_millis(500));
+        assert_eq!(retry.delay(2), time::Duration::from_secs(1));
+        assert_eq!(retry.delay(3), time::Duration::from_secs(2));
+        assert_eq!(retry.delay(16), MAX_RETRY_DELAY);
+        assert_eq!(retry.delay(u32::MAX), MAX_RETRY_DELAY);
+
+        let retry = Retry {
+            retries: 1,
+            backoff: time::Duration::MAX,
+        };
+        assert_eq!(retry.delay(2), MAX_RETRY_DELAY);
+    }
+}
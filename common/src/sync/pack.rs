@@ -0,0 +1,55 @@
+//! Progress of fetches over the peer-to-peer protocol.
+//!
+//! Replication doesn't report git's transfer progress, so it is measured from the
+//! packfiles being received into the monorepo: these are written to the pack directory
+//! as they are received, first as temporary files, which are renamed once indexed.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Packfiles being received into a repository.
+#[derive(Debug)]
+pub struct Incoming {
+    dir: PathBuf,
+    /// Size of the pack directory when the transfer started.
+    baseline: u64,
+    /// Bytes received so far.
+    received: u64,
+}
+
+impl Incoming {
+    /// Start measuring the packfiles received into the given bare repository.
+    pub fn new(repo: &Path) -> Self {
+        let dir = repo.join("objects").join("pack");
+        let baseline = size(&dir);
+
+        Self {
+            dir,
+            baseline,
+            received: 0,
+        }
+    }
+
+    /// Bytes received since the transfer started. Nb. Packfiles received by concurrent
+    /// fetches into the same repository are counted as well.
+    pub fn received(&mut self) -> u64 {
+        let received = size(&self.dir).saturating_sub(self.baseline);
+
+        // Packfiles may briefly disappear while they are renamed.
+        self.received = self.received.max(received);
+        self.received
+    }
+}
+
+/// Total size of the files in a directory, or zero if it can't be read.
+fn size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,998 @@
+//! Selective fetching of project refs from a seed's git endpoint.
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::{thread, time};
+
+use anyhow::anyhow;
+use url::Url;
+
+use librad::collaborative_objects::{ObjectId, TypeName};
+use librad::git::identities;
+use librad::git::refs::{Signed, Verified};
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+use librad::PeerId;
+
+use crate::cobs::{issue, patch};
+use crate::credentials::{self, Credential};
+use crate::git;
+
+use super::scope::Scopes;
+use super::throttle::Throttle;
+use super::Settings;
+
+/// Refs to sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refs {
+    /// All refs of the project.
+    All,
+    /// Only collaborative object refs, of all peers.
+    Cobs,
+    /// Only the refs of the given collaborative object, of all peers.
+    Cob(TypeName, ObjectId),
+    /// Only the refs of the given peer.
+    Peer(PeerId),
+    /// Only the refs of the given peers, eg. the tracked peers of a project.
+    Peers(Vec<PeerId>),
+}
+
+impl Default for Refs {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+impl Refs {
+    /// Parse a collaborative object of the form `<type>/<id>`, eg. `patch/<id>`.
+    pub fn cob(s: &str) -> anyhow::Result<Self> {
+        let (kind, id) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow!("invalid object '{}': expected `<type>/<id>`", s))?;
+        let typename = match kind {
+            "patch" => patch::TYPENAME.clone(),
+            "issue" => issue::TYPENAME.clone(),
+            other => {
+                TypeName::from_str(other).map_err(|_| anyhow!("invalid object type '{}'", other))?
+            }
+        };
+        let id = ObjectId::from_str(id).map_err(|_| anyhow!("invalid object id '{}'", id))?;
+
+        Ok(Self::Cob(typename, id))
+    }
+
+    /// Check whether all refs are selected.
+    pub fn is_all(&self) -> bool {
+        matches!(self, Self::All)
+    }
+
+    /// Check whether a remote ref is selected, given the peer it belongs to, and its name
+    /// relative to that peer, eg. `cobs/xyz.radicle.patch/<id>`.
+    pub fn matches(&self, peer: &PeerId, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Cobs => name.starts_with("cobs/"),
+            Self::Cob(typename, id) => name == format!("cobs/{}/{}", typename, id),
+            Self::Peer(p) => p == peer,
+            Self::Peers(peers) => peers.contains(peer),
+        }
+    }
+}
+
+/// Prefix under which fetched refs are kept until they are verified. Quarantined refs
+/// are kept if a fetch is interrupted, so that the next fetch can resume from them.
+pub const QUARANTINE_PREFIX: &str = "refs/rad/quarantine";
+/// Maximum number of refs fetched at once.
+pub const FETCH_BATCH_SIZE: usize = 32;
+/// Prefix of the promisor remotes registered in the monorepo by filtered fetches.
+pub const PROMISOR_PREFIX: &str = "rad-promisor";
+/// Maximum number of stalled fetches left running in the background, see [`fetch`].
+pub const MAX_STALLED_FETCHES: usize = 4;
+
+/// Worker threads of the stalled fetches that didn't stop when cancelled.
+static STALLED: Mutex<Vec<thread::JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// A fetched ref that was not applied to the monorepo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejected {
+    /// Peer the ref belongs to.
+    pub peer: PeerId,
+    /// Name of the ref, relative to the project namespace.
+    pub name: String,
+    /// Reason for rejecting the ref.
+    pub reason: String,
+}
+
+/// Outcome of a selective fetch.
+#[derive(Debug, Default)]
+pub struct Fetched {
+    /// Refs that were verified and updated, relative to the project namespace.
+    pub updated: Vec<(String, git::Oid)>,
+    /// Refs that failed verification, and were left untouched.
+    pub rejected: Vec<Rejected>,
+}
+
+/// Fetch the selected refs of a project from a seed's git URL into the monorepo.
+/// Refs belonging to the local peer, or out of their peer's fetch scope, are never fetched.
+///
+/// Refs are first fetched into a quarantine area, along with the `rad/signed_refs` of
+/// the peers they belong to. A ref is only applied to the project namespace if the
+/// peer's signed refs verify against the peer's key, and include the fetched ref at
+/// the fetched target.
+///
+/// Refs are fetched in batches of [`FETCH_BATCH_SIZE`], and quarantined refs are only
+/// removed once the fetch completes. If a fetch is interrupted, the next one skips the
+/// refs already in quarantine, and negotiates with them as known tips, resuming where
+/// the previous fetch left off.
+///
+/// Transfers are throttled to the configured rate limit, and aborted if no data is
+/// received for longer than the configured timeout.
+///
+/// If the settings ask for a filtered fetch, branches and tags are fetched with
+/// `git fetch`, since libgit2 doesn't support it. Signed refs, identities and
+/// collaborative objects are always fetched in full, as they must be verified. The
+/// seed is registered as a promisor remote of the monorepo, from which git fetches the
+/// omitted objects on demand.
+pub fn fetch(
+    monorepo: &git::Repository,
+    seed: &Url,
+    credential: Option<&Credential>,
+    urn: &Urn,
+    local: &PeerId,
+    refs: &Refs,
+    scopes: &Scopes,
+    settings: &Settings,
+    mut progress: impl FnMut(git::TransferProgress),
+) -> anyhow::Result<Fetched> {
+    let url = seed.join(&urn.encode_id())?;
+    let namespace = urn.encode_id();
+    let quarantine = format!("{}/{}/", QUARANTINE_PREFIX, namespace);
+    let mut remote = monorepo.remote_anonymous(url.as_str())?;
+    let mut selected = Vec::new();
+    let mut sigrefs = HashMap::new();
+
+    let connection = remote.connect_auth(
+        git::Direction::Fetch,
+        Some(credentials::callbacks(credential)),
+        None,
+    )?;
+    for head in connection.list()? {
+        if let Some((peer, name)) = git::parse_remote(head.name()) {
+            if peer == *local {
+                continue;
+            }
+            if name == SIGREFS {
+                sigrefs.insert(peer, (head.name().to_owned(), head.oid()));
+            } else if refs.matches(&peer, name) && scopes.matches(&peer, name) {
+                selected.push((peer, head.name().to_owned(), head.oid()));
+            }
+        }
+    }
+    drop(connection);
+
+    let mut fetched = Fetched::default();
+    if selected.is_empty() {
+        return Ok(fetched);
+    }
+
+    // Refs already in quarantine at the advertised target were fetched by a previous,
+    // interrupted run, and are skipped.
+    let refspecs = sigrefs
+        .iter()
+        .filter(|(peer, _)| selected.iter().any(|(p, _, _)| p == *peer))
+        .map(|(_, (name, oid))| (name, oid))
+        .chain(selected.iter().map(|(_, name, oid)| (name, oid)))
+        .filter(|(name, oid)| {
+            monorepo
+                .refname_to_id(&format!("{}{}", quarantine, name))
+                .map_or(true, |q| q != **oid)
+        })
+        .map(|(name, _)| format!("+{}:{}{}", name, quarantine, name))
+        .collect::<Vec<_>>();
+
+    let (partial, refspecs): (Vec<_>, Vec<_>) = refspecs
+        .into_iter()
+        .partition(|r| settings.is_partial() && is_branch(r));
+
+    // Fetch in batches, so that an interrupted fetch only loses the batch in progress.
+    for batch in partial.chunks(FETCH_BATCH_SIZE) {
+        fetch_partial(monorepo, &namespace, &url, credential, batch, settings)?;
+    }
+    for batch in refspecs.chunks(FETCH_BATCH_SIZE) {
+        fetch_batch(monorepo, &url, credential, batch, settings, &mut progress)?;
+    }
+
+    let mut verified: HashMap<PeerId, Result<Signed<Verified>, String>> = HashMap::new();
+    for (peer, name, oid) in selected {
+        let signed = verified.entry(peer).or_insert_with(|| {
+            let (sigrefs, _) = sigrefs
+                .get(&peer)
+                .ok_or_else(|| String::from("peer has no signed refs"))?;
+
+            verify(monorepo, &format!("{}{}", quarantine, sigrefs), &peer)
+        });
+        let result = match signed {
+            Ok(signed) => {
+                let relative = name
+                    .strip_prefix(&format!("refs/remotes/{}/", peer))
+                    .unwrap_or(&name);
+
+                match lookup(signed, relative) {
+                    Some(expected) if expected == oid => Ok(()),
+                    Some(_) => Err(String::from("ref target does not match signed refs")),
+                    None => Err(String::from("ref is not in signed refs")),
+                }
+            }
+            Err(err) => Err(err.clone()),
+        };
+
+        match result {
+            Ok(()) => {
+                monorepo.reference(
+                    &format!("refs/namespaces/{}/{}", namespace, name),
+                    oid,
+                    true,
+                    "rad sync: verified fetch",
+                )?;
+                fetched.updated.push((name, oid));
+            }
+            Err(reason) => {
+                fetched.rejected.push(Rejected { peer, name, reason });
+            }
+        }
+    }
+
+    for r in monorepo.references_glob(&format!("{}*", quarantine))? {
+        r?.delete()?;
+    }
+
+    Ok(fetched)
+}
+
+/// Event of a fetch running in a worker thread.
+enum Event {
+    Progress(git::TransferProgress),
+    Done(Result<(), git2::Error>),
+}
+
+/// Fetch refspecs with libgit2, throttled to the rate limit, and aborted if it stalls.
+///
+/// Nb. libgit2 doesn't call back while a transfer is stalled, so the fetch runs in a
+/// worker thread, which is cancelled if it stalls, through the return value of its next
+/// callback. The worker is joined if it stops within another timeout, and otherwise left
+/// running in the background, eg. until its connection fails. At most
+/// [`MAX_STALLED_FETCHES`] are left running: no fetch is started beyond that, so that
+/// they don't pile up, eg. with `rad sync --daemon`.
+fn fetch_batch(
+    monorepo: &git::Repository,
+    url: &Url,
+    credential: Option<&Credential>,
+    refspecs: &[String],
+    settings: &Settings,
+    progress: &mut impl FnMut(git::TransferProgress),
+) -> anyhow::Result<()> {
+    {
+        let mut stalled = STALLED.lock().unwrap_or_else(|err| err.into_inner());
+        stalled.retain(|worker| !worker.is_finished());
+
+        if stalled.len() >= MAX_STALLED_FETCHES {
+            anyhow::bail!(
+                "{} stalled fetches are still running, try again later",
+                stalled.len()
+            );
+        }
+    }
+    let (tx, rx) = mpsc::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let path = monorepo.path().to_path_buf();
+        let url = url.clone();
+        let credential = credential.cloned();
+        let refspecs = refspecs.to_vec();
+        let settings = *settings;
+        let cancelled = cancelled.clone();
+
+        move || {
+            let mut throttle = Throttle::new(settings.timeout, settings.rate_limit);
+            let mut callbacks = credentials::callbacks(credential.as_ref());
+            callbacks.transfer_progress(|p| {
+                throttle.update(p.received_bytes() as u64);
+                thread::sleep(throttle.delay());
+
+                tx.send(Event::Progress(p.into())).is_ok() && !cancelled.load(Ordering::Relaxed)
+            });
+            let mut opts = git2::FetchOptions::new();
+            opts.remote_callbacks(callbacks);
+
+            let result = git::Repository::open_bare(path).and_then(|repo| {
+                let mut remote = repo.remote_anonymous(url.as_str())?;
+                remote.fetch(&refspecs, Some(&mut opts), None)
+            });
+            drop(opts);
+            tx.send(Event::Done(result)).ok();
+        }
+    };
+    let worker = thread::spawn(worker);
+
+    let mut throttle = Throttle::new(settings.timeout, settings.rate_limit);
+    loop {
+        let timeout = throttle
+            .deadline()
+            .saturating_duration_since(time::Instant::now());
+
+        match rx.recv_timeout(timeout) {
+            Ok(Event::Progress(p)) => {
+                throttle.update(p.received_bytes as u64);
+                progress(p);
+            }
+            Ok(Event::Done(result)) => {
+                worker.join().ok();
+
+                return result.map_err(anyhow::Error::from);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) if throttle.is_stalled() => {
+                cancelled.store(true, Ordering::Relaxed);
+
+                // Give the worker another timeout to reach its next callback, and stop.
+                let deadline = time::Instant::now() + settings.timeout;
+                loop {
+                    match rx.recv_timeout(deadline.saturating_duration_since(time::Instant::now()))
+                    {
+                        Ok(Event::Progress(_)) => continue,
+                        Ok(Event::Done(_)) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                            worker.join().ok();
+                        }
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            STALLED
+                                .lock()
+                                .unwrap_or_else(|err| err.into_inner())
+                                .push(worker);
+                        }
+                    }
+                    break;
+                }
+                anyhow::bail!(
+                    "fetch from {} stalled for more than {}s",
+                    url,
+                    settings.timeout.as_secs()
+                );
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                worker.join().ok();
+
+                anyhow::bail!("fetch from {} was interrupted", url);
+            }
+        }
+    }
+}
+
+/// Fetch refspecs with `git fetch`, filtered as per the settings.
+fn fetch_partial(
+    monorepo: &git::Repository,
+    namespace: &str,
+    url: &Url,
+    credential: Option<&Credential>,
+    refspecs: &[String],
+    settings: &Settings,
+) -> anyhow::Result<()> {
+    let filter = match settings.filter {
+        Some(filter) => filter,
+        None => return Ok(()),
+    };
+    let remote = format!("{}/{}", PROMISOR_PREFIX, namespace);
+    let mut config = vec![
+        // Abort the transfer if it stalls for longer than the timeout.
+        ("http.lowSpeedLimit", String::from("1")),
+        (
+            "http.lowSpeedTime",
+            settings.timeout.as_secs().max(1).to_string(),
+        ),
+    ];
+    if let Some(credential) = credential {
+        let auth = base64::encode(format!("{}:{}", credentials::USERNAME, credential.token));
+
+        config.push(("http.extraHeader", format!("Authorization: Basic {}", auth)));
+    }
+
+    // Nb. `git fetch --filter` requires a named remote, which it registers as a
+    // promisor remote of the repository.
+    if monorepo.find_remote(&remote).is_ok() {
+        monorepo.remote_set_url(&remote, url.as_str())?;
+    } else {
+        monorepo.remote(&remote, url.as_str())?;
+    }
+    let mut args = ["fetch", "--no-tags", "--quiet"].map(String::from).to_vec();
+    args.push(format!("--filter={}", filter));
+    args.push(remote);
+    args.extend(refspecs.iter().cloned());
+
+    git::git_with_config(monorepo.path(), args, &config)
+        .map_err(|e| anyhow!("partial fetch from {} failed: {}", url, e))?;
+
+    Ok(())
+}
+
+/// Whether a refspec fetches a branch or tag of a peer, as opposed to eg. its identity.
+fn is_branch(refspec: &str) -> bool {
+    let name = refspec.trim_start_matches('+');
+    let name = name.split_once(':').map_or(name, |(src, _)| src);
+
+    matches!(
+        git::parse_remote(name),
+        Some((_, r)) if r.starts_with("heads/") || r.starts_with("tags/")
+    )
+}
+
+/// Name of a peer's signed refs, relative to the peer.
+pub const SIGREFS: &str = "rad/signed_refs";
+/// Name of the blob holding the signed refs, in the signed refs tree.
+const SIGREFS_BLOB: &str = "refs";
+
+/// Load and verify the signed refs at the given ref against the peer's key.
+fn verify(
+    monorepo: &git::Repository,
+    sigrefs: &str,
+    peer: &PeerId,
+) -> Result<Signed<Verified>, String> {
+    load(monorepo, sigrefs, peer).map(|(signed, _)| signed)
+}
+
+/// Load the signed refs at the given ref, and verify them against the peer's key.
+/// Returns the signed refs, as well as their raw JSON, which holds the signature.
+pub fn load(
+    monorepo: &git::Repository,
+    sigrefs: &str,
+    peer: &PeerId,
+) -> Result<(Signed<Verified>, Vec<u8>), String> {
+    let blob = monorepo
+        .find_reference(sigrefs)
+        .and_then(|r| r.peel_to_tree())
+        .and_then(|tree| {
+            tree.get_name(SIGREFS_BLOB)
+                .ok_or_else(|| git2::Error::from_str("signed refs blob not found"))?
+                .to_object(monorepo)?
+                .peel_to_blob()
+        })
+        .map_err(|e| format!("invalid signed refs: {}", e))?;
+
+    let signed = Signed::from_json(blob.content(), peer)
+        .map_err(|e| format!("signed refs failed verification: {}", e))?;
+
+    Ok((signed, blob.content().to_vec()))
+}
+
+/// Look up the target of a ref, relative to a peer, eg. `heads/master`, in its signed refs.
+fn lookup(signed: &Signed<Verified>, name: &str) -> Option<git::Oid> {
+    let (category, name) = name.split_once('/')?;
+
+    signed
+        .refs
+        .iter_categorised()
+        .find(|((n, _), c)| c.as_str() == category && n.as_str() == name)
+        .map(|((_, oid), _)| (*oid).into())
+}
+
+/// Kinds of refs of the local peer pushed to seeds, relative to the project namespace.
+/// Remote refs, ie. of other peers, are never pushed.
+pub const PUSHED_REFS: &[&str] = &["heads", "tags", "notes", "cobs", "rad"];
+
+/// A ref pushed to a seed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pushed {
+    /// Name of the ref on the seed, eg. `refs/remotes/<peer>/heads/master`.
+    pub name: String,
+    /// Why the seed rejected the ref, if it did.
+    pub rejected: Option<String>,
+}
+
+/// Push the refs of the local peer of a project to a seed's git URL, as the seed's
+/// `refs/remotes/<peer>/*` refs, ie. as they are advertised. The peer's signed refs are
+/// pushed along, for the seed to verify the others against. If `branches` isn't empty,
+/// only these branches are pushed, along with the signed refs.
+///
+/// If the seed asks for credentials, eg. because it requires authentication for pushes,
+/// the given credential is used. The transfer is throttled to the configured rate limit.
+/// Returns the refs pushed, including the rejected ones.
+pub fn push(
+    monorepo: &git::Repository,
+    seed: &Url,
+    credential: Option<&Credential>,
+    urn: &Urn,
+    local: &PeerId,
+    branches: &[String],
+    settings: &Settings,
+) -> anyhow::Result<Vec<Pushed>> {
+    let url = seed.join(&urn.encode_id())?;
+    let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+    let mut refspecs = Vec::new();
+
+    // Nb. Refspecs are listed one by one, as libgit2 doesn't push with glob refspecs.
+    for kind in PUSHED_REFS {
+        for r in monorepo.references_glob(&format!("{}{}/*", namespace, kind))? {
+            let r = r?;
+            if let Some(name) = r.name() {
+                let relative = name.trim_start_matches(&namespace);
+
+                if !branches.is_empty()
+                    && *kind != "rad"
+                    && !branches.iter().any(|b| relative == format!("heads/{}", b))
+                {
+                    continue;
+                }
+                refspecs.push(format!("+{}:refs/remotes/{}/{}", name, local, relative));
+            }
+        }
+    }
+    let mut pushed = Vec::new();
+    if refspecs.is_empty() {
+        return Ok(pushed);
+    }
+
+    let mut remote = monorepo.remote_anonymous(url.as_str())?;
+    let mut throttle = Throttle::new(settings.timeout, settings.rate_limit);
+    let mut callbacks = credentials::callbacks(credential);
+    callbacks.push_transfer_progress(|_, _, sent| {
+        throttle.update(sent as u64);
+        thread::sleep(throttle.delay());
+    });
+    callbacks.push_update_reference(|name, status| {
+        pushed.push(Pushed {
+            name: name.to_owned(),
+            rejected: status.map(|s| s.to_owned()),
+        });
+        Ok(())
+    });
+    let mut options = git2::PushOptions::new();
+    options.remote_callbacks(callbacks);
+    remote.push(&refspecs, Some(&mut options))?;
+    drop(options);
+
+    Ok(pushed)
+}
+
+/// A ref update that a sync would perform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// Name of the ref, relative to the project namespace.
+    pub name: String,
+    /// Current target of the ref, or `None` if the ref would be created.
+    pub old: Option<git::Oid>,
+    /// New target of the ref, or `None` if the ref would be pruned.
+    pub new: Option<git::Oid>,
+}
+
+/// Ref updates that syncing with a seed would perform.
+#[derive(Debug, Default)]
+pub struct Plan {
+    /// Updates to the local monorepo.
+    pub fetch: Vec<Update>,
+    /// Updates to the seed.
+    pub push: Vec<Update>,
+}
+
+/// Compute the ref updates that syncing the selected refs of a project with a seed would
+/// perform, by comparing the refs advertised by the seed's git URL with the monorepo.
+/// Nothing is transferred.
+pub fn plan(
+    monorepo: &git::Repository,
+    seed: &Url,
+    credential: Option<&Credential>,
+    urn: &Urn,
+    local: &PeerId,
+    refs: &Refs,
+) -> anyhow::Result<Plan> {
+    let url = seed.join(&urn.encode_id())?;
+    let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+    let mut remote = monorepo.remote_anonymous(url.as_str())?;
+    let mut published = HashMap::new();
+    let mut plan = Plan::default();
+
+    let connection = remote.connect_auth(
+        git::Direction::Fetch,
+        Some(credentials::callbacks(credential)),
+        None,
+    )?;
+    for head in connection.list()? {
+        let (peer, name) = if let Some(r) = git::parse_remote(head.name()) {
+            r
+        } else {
+            continue;
+        };
+        if peer == *local {
+            published.insert(name.to_owned(), head.oid());
+        } else if refs.matches(&peer, name) {
+            let old = monorepo
+                .refname_to_id(&format!(
+                    "{}{}",
+                    namespace,
+                    head.name().trim_start_matches("refs/")
+                ))
+                .ok();
+
+            if old != Some(head.oid()) {
+                plan.fetch.push(Update {
+                    name: head.name().to_owned(),
+                    old,
+                    new: Some(head.oid()),
+                });
+            }
+        }
+    }
+    drop(connection);
+
+    for r in monorepo.references_glob(&format!("{}*", namespace))? {
+        let r = r?;
+        let (name, oid) = match (r.name(), r.target()) {
+            (Some(name), Some(oid)) => (name, oid),
+            _ => continue,
+        };
+        let name = name.trim_start_matches(&namespace);
+
+        if name.starts_with("remotes/") || !refs.matches(local, name) {
+            continue;
+        }
+        let old = published.remove(name);
+
+        if old != Some(oid) {
+            plan.push.push(Update {
+                name: format!("refs/remotes/{}/{}", local, name),
+                old,
+                new: Some(oid),
+            });
+        }
+    }
+    for (name, oid) in published {
+        if refs.matches(local, &name) {
+            plan.push.push(Update {
+                name: format!("refs/remotes/{}/{}", local, name),
+                old: Some(oid),
+                new: None,
+            });
+        }
+    }
+    plan.push.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(plan)
+}
+
+/// Refs advertised by seeds, keyed by the peer they belong to. Ref names are relative
+/// to the peer, eg. `heads/master`.
+pub type Advertised = HashMap<PeerId, HashSet<String>>;
+
+/// List the refs of a project advertised by a seed's git URL, and add them to `advertised`.
+pub fn advertised(
+    monorepo: &git::Repository,
+    seed: &Url,
+    credential: Option<&Credential>,
+    urn: &Urn,
+    advertised: &mut Advertised,
+) -> anyhow::Result<()> {
+    let url = seed.join(&urn.encode_id())?;
+    let mut remote = monorepo.remote_anonymous(url.as_str())?;
+
+    let connection = remote.connect_auth(
+        git::Direction::Fetch,
+        Some(credentials::callbacks(credential)),
+        None,
+    )?;
+    for head in connection.list()? {
+        if let Some((peer, name)) = git::parse_remote(head.name()) {
+            advertised
+                .entry(peer)
+                .or_insert_with(HashSet::new)
+                .insert(name.to_owned());
+        }
+    }
+    drop(connection);
+
+    Ok(())
+}
+
+/// Prune the selected remote refs of a project that are no longer advertised by any seed.
+///
+/// Only refs of peers advertised by at least one seed are considered, so that the refs
+/// of peers a seed doesn't track are never pruned. The local peer's refs and the `rad/*`
+/// identity refs are never pruned. If `dry_run` is true, the refs are only returned.
+/// Returns the pruned refs, relative to the project namespace.
+pub fn prune(
+    monorepo: &git::Repository,
+    urn: &Urn,
+    local: &PeerId,
+    refs: &Refs,
+    advertised: &Advertised,
+    dry_run: bool,
+) -> anyhow::Result<Vec<(String, git::Oid)>> {
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let mut pruned = Vec::new();
+
+    for r in monorepo.references_glob(&format!("{}refs/remotes/*", namespace))? {
+        let mut r = r?;
+        let (name, oid) = match (r.name(), r.target()) {
+            (Some(name), Some(oid)) => (name.trim_start_matches(&namespace).to_owned(), oid),
+            _ => continue,
+        };
+        let (peer, relative) = if let Some(remote) = git::parse_remote(&name) {
+            remote
+        } else {
+            continue;
+        };
+        if peer == *local || relative.starts_with("rad/") || !refs.matches(&peer, relative) {
+            continue;
+        }
+        let refs = if let Some(refs) = advertised.get(&peer) {
+            refs
+        } else {
+            continue;
+        };
+        if refs.contains(relative) {
+            continue;
+        }
+        if !dry_run {
+            r.delete()?;
+        }
+        pruned.push((name, oid));
+    }
+    pruned.sort();
+
+    Ok(pruned)
+}
+
+/// Adopt the project identity of the fetched remotes, if the project doesn't have an
+/// identity in the monorepo yet, eg. when it was cloned over HTTP.
+///
+/// Only identities that verify are considered, ie. identities of the project whose
+/// revisions are each signed by a quorum of the delegates of the previous revision, so
+/// that a seed or peer can't have an identity of its own adopted. The identity ref
+/// `rad/id` is set to the most recent of them, and the `rad/ids/*` refs of the delegates
+/// are copied from the same remote. If none verify, or they diverge, nothing is adopted
+/// and an error is returned. Returns the peer whose identity was adopted, if any.
+pub fn adopt<S>(
+    storage: &S,
+    monorepo: &git::Repository,
+    urn: &Urn,
+) -> anyhow::Result<Option<PeerId>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let id = format!("{}refs/rad/id", namespace);
+
+    if monorepo.refname_to_id(&id).is_ok() {
+        return Ok(None);
+    }
+
+    let mut candidates = Vec::new();
+    let mut unverified = None;
+    for r in monorepo.references_glob(&format!("{}refs/remotes/*/rad/id", namespace))? {
+        let r = r?;
+        let name = r.name().unwrap_or_default().trim_start_matches(&namespace);
+
+        if let (Some((peer, _)), Some(oid)) = (git::parse_remote(name), r.target()) {
+            match verify_identity(storage.as_ref(), urn, &peer) {
+                Ok(()) => candidates.push((peer, oid)),
+                Err(err) => unverified = Some((peer, err)),
+            }
+        }
+    }
+    let latest = candidates.iter().find(|(_, oid)| {
+        candidates.iter().all(|(_, other)| {
+            oid == other || monorepo.graph_descendant_of(*oid, *other).unwrap_or(false)
+        })
+    });
+    let (peer, oid) = match latest {
+        Some(latest) => *latest,
+        None if candidates.is_empty() => {
+            return match unverified {
+                Some((peer, err)) => Err(anyhow!(
+                    "the identity of project {} published by {} could not be verified: {}",
+                    urn,
+                    peer,
+                    err
+                )),
+                None => Ok(None),
+            }
+        }
+        None => return Err(anyhow!("project {} has diverging identities", urn)),
+    };
+
+    let prefix = format!("{}refs/remotes/{}/rad/ids/", namespace, peer);
+    for r in monorepo.references_glob(&format!("{}*", prefix))? {
+        let r = r?;
+        if let (Some(name), Some(target)) = (r.name(), r.target()) {
+            let delegate = name.trim_start_matches(&prefix);
+
+            monorepo.reference(
+                &format!("{}refs/rad/ids/{}", namespace, delegate),
+                target,
+                false,
+                "rad sync: adopt delegate identity",
+            )?;
+        }
+    }
+    monorepo.reference(&id, oid, false, "rad sync: adopt project identity")?;
+
+    Ok(Some(peer))
+}
+
+/// Verify the identity of a project published by a remote peer, ie. its `rad/id`.
+fn verify_identity(storage: &ReadOnly, urn: &Urn, peer: &PeerId) -> anyhow::Result<()> {
+    let path = git::RefLike::try_from(format!("refs/remotes/{}/rad/id", peer))?;
+    let project = identities::project::verify(storage, &urn.clone().with_path(path))?
+        .ok_or_else(|| anyhow!("identity not found"))?;
+
+    if project.urn().id != urn.id {
+        return Err(anyhow!("identity is of project {}", project.urn()));
+    }
+    Ok(())
+}
+
+/// State of a ref on a seed, compared to local state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum State {
+    /// The seed has the ref at the same target.
+    InSync,
+    /// The local ref is ahead of the seed's.
+    Ahead,
+    /// The local ref is behind the seed's, or doesn't exist locally.
+    Behind,
+    /// The local and seed refs have diverged.
+    Diverged,
+    /// The seed doesn't have the ref.
+    Missing,
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::InSync => "in sync",
+            Self::Ahead => "ahead",
+            Self::Behind => "behind",
+            Self::Diverged => "diverged",
+            Self::Missing => "missing",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Status of a ref on a seed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    /// Name of the ref, relative to the project namespace.
+    pub name: String,
+    /// Local target.
+    pub local: Option<git::Oid>,
+    /// Target on the seed.
+    pub seed: Option<git::Oid>,
+    /// State of the ref on the seed.
+    pub state: State,
+}
+
+/// Compare the selected refs of a project in the monorepo with the refs advertised by a
+/// seed's git URL. The local peer's refs are compared with the seed's copy of them.
+pub fn status(
+    monorepo: &git::Repository,
+    seed: &Url,
+    credential: Option<&Credential>,
+    urn: &Urn,
+    local: &PeerId,
+    refs: &Refs,
+) -> anyhow::Result<Vec<Status>> {
+    let url = seed.join(&urn.encode_id())?;
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let mut remote = monorepo.remote_anonymous(url.as_str())?;
+    let mut targets: HashMap<String, (Option<git::Oid>, Option<git::Oid>)> = HashMap::new();
+
+    let connection = remote.connect_auth(
+        git::Direction::Fetch,
+        Some(credentials::callbacks(credential)),
+        None,
+    )?;
+    for head in connection.list()? {
+        if let Some((peer, name)) = git::parse_remote(head.name()) {
+            if !refs.matches(&peer, name) {
+                continue;
+            }
+            // The seed's copy of our refs is compared with our own refs.
+            let name = if peer == *local {
+                format!("refs/{}", name)
+            } else {
+                head.name().to_owned()
+            };
+            targets.entry(name).or_default().1 = Some(head.oid());
+        }
+    }
+    drop(connection);
+
+    for r in monorepo.references_glob(&format!("{}refs/*", namespace))? {
+        let r = r?;
+        let (name, oid) = match (r.name(), r.target()) {
+            (Some(name), Some(oid)) => (name.trim_start_matches(&namespace), oid),
+            _ => continue,
+        };
+        let selected = match git::parse_remote(name) {
+            Some((peer, relative)) => peer != *local && refs.matches(&peer, relative),
+            None => refs.matches(local, name.trim_start_matches("refs/")),
+        };
+        if selected {
+            targets.entry(name.to_owned()).or_default().0 = Some(oid);
+        }
+    }
+
+    let mut status = targets
+        .into_iter()
+        .map(|(name, (local, seed))| Status {
+            state: compare(monorepo, local, seed),
+            name,
+            local,
+            seed,
+        })
+        .collect::<Vec<_>>();
+    status.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(status)
+}
+
+/// Compare a local target with a seed's target. If the seed's target is not in the
+/// monorepo, the local ref is considered behind.
+fn compare(monorepo: &git::Repository, local: Option<git::Oid>, seed: Option<git::Oid>) -> State {
+    match (local, seed) {
+        (_, None) => State::Missing,
+        (None, Some(_)) => State::Behind,
+        (Some(local), Some(seed)) if local == seed => State::InSync,
+        (Some(local), Some(seed)) => {
+            if monorepo.find_object(seed, None).is_err() {
+                State::Behind
+            } else if monorepo.graph_descendant_of(local, seed).unwrap_or(false) {
+                State::Ahead
+            } else if monorepo.graph_descendant_of(seed, local).unwrap_or(false) {
+                State::Behind
+            } else {
+                State::Diverged
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_refs_matches() {
+        let peer =
+            PeerId::from_str("hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa").unwrap();
+        let other =
+            PeerId::from_str("hyd7wpd8p5aqnm9htsfoatxkckmw6ingnsdudns9code5xq17h1rhw").unwrap();
+
+        assert!(Refs::All.matches(&peer, "heads/master"));
+        assert!(Refs::Cobs.matches(&peer, "cobs/xyz.radicle.patch/abc"));
+        assert!(!Refs::Cobs.matches(&peer, "heads/master"));
+        assert!(Refs::Peer(peer).matches(&peer, "heads/master"));
+        assert!(!Refs::Peer(peer).matches(&other, "heads/master"));
+        assert!(Refs::Peers(vec![peer]).matches(&peer, "heads/master"));
+        assert!(!Refs::Peers(vec![peer]).matches(&other, "heads/master"));
+    }
+
+    #[test]
+    fn test_refs_cob_error() {
+        assert!(Refs::cob("patch").is_err());
+        assert!(Refs::cob("patch/not-an-id").is_err());
+    }
+
+    #[test]
+    fn test_is_branch() {
+        let peer = "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa";
+
+        assert!(is_branch(&format!(
+            "+refs/remotes/{}/heads/master:{}/refs/remotes/{}/heads/master",
+            peer, QUARANTINE_PREFIX, peer
+        )));
+        assert!(is_branch(&format!("refs/remotes/{}/tags/v1", peer)));
+        assert!(!is_branch(&format!("+refs/remotes/{}/rad/id", peer)));
+        assert!(!is_branch(&format!("refs/remotes/{}/cobs/patch/1", peer)));
+    }
+}
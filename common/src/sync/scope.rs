@@ -0,0 +1,219 @@
+//! Per-peer fetch scopes.
+//!
+//! The fetch scope of a tracked peer restricts which of its refs are fetched, eg. only its
+//! default branch, to limit the growth of storage. Scopes are patterns of refs relative to
+//! the peer, eg. `heads/master` or `tags/*`. All refs of peers without a scope are fetched.
+//!
+//! A scope is stored with the peer's tracking entry, as a tracking config that only allows
+//! the kinds of refs in scope, ie. the peer's data and/or collaborative objects, so that
+//! replication doesn't fetch the others. Since the tracking config can't express finer
+//! patterns, eg. a single branch, the patterns are stored in the profile's `scopes.toml`
+//! as well, by project, and refs out of scope are pruned after peer-to-peer syncs.
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use librad::git::storage::Storage;
+use librad::git::tracking::git::tracking;
+use librad::git::Urn;
+use librad::profile::Profile;
+use librad::PeerId;
+
+use crate::{git, profile, project};
+
+/// Scopes file name, in the profile scope.
+pub const FILE_NAME: &str = "scopes.toml";
+
+/// Refs of a peer to fetch.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Scope {
+    patterns: Vec<String>,
+}
+
+impl Scope {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Get the tracking config allowing the kinds of refs in scope: the peer's data, ie.
+    /// its branches, tags and notes, unless only collaborative objects are in scope, and
+    /// its collaborative objects, if any of them are in scope. An empty scope allows all.
+    pub fn config(&self) -> tracking::config::Config {
+        if self.patterns.is_empty() {
+            return tracking::config::Config::default();
+        }
+        let cobs = self
+            .patterns
+            .iter()
+            .any(|p| p.starts_with("cobs/") || "cobs/".starts_with(p.trim_end_matches('*')));
+
+        tracking::config::Config {
+            data: self.patterns.iter().any(|p| !p.starts_with("cobs/")),
+            cobs: if cobs {
+                tracking::config::Cobs::allow_all()
+            } else {
+                tracking::config::Cobs::deny_all()
+            },
+        }
+    }
+
+    /// Check whether a ref is in scope, given its name relative to the peer, eg.
+    /// `heads/master`. Patterns ending in `*` match all refs starting with the pattern.
+    /// The peer's `rad/` refs are always in scope, since they are needed to verify its
+    /// other refs.
+    pub fn matches(&self, name: &str) -> bool {
+        name.starts_with("rad/")
+            || self.patterns.iter().any(|p| match p.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == p,
+            })
+    }
+}
+
+/// Fetch scopes of the peers of a project.
+#[derive(Debug, Default, Clone)]
+pub struct Scopes {
+    scopes: HashMap<PeerId, Scope>,
+}
+
+impl Scopes {
+    /// Load the scopes of the given project.
+    pub fn load(profile: &Profile, urn: &Urn) -> Result<Self, anyhow::Error> {
+        let file: File = profile::load_toml(profile, FILE_NAME)?;
+        let scopes = file
+            .project
+            .get(&urn.to_string())
+            .into_iter()
+            .flatten()
+            .filter_map(|(peer, patterns)| {
+                PeerId::from_str(peer)
+                    .ok()
+                    .map(|peer| (peer, Scope::new(patterns.clone())))
+            })
+            .collect();
+
+        Ok(Self { scopes })
+    }
+
+    /// Set the scope of a peer, replacing its previous scope.
+    pub fn insert(&mut self, peer: PeerId, scope: Scope) {
+        self.scopes.insert(peer, scope);
+    }
+
+    /// Get the scope of a peer, if it has one.
+    pub fn get(&self, peer: &PeerId) -> Option<&Scope> {
+        self.scopes.get(peer)
+    }
+
+    /// Check whether a ref of a peer is in scope.
+    pub fn matches(&self, peer: &PeerId, name: &str) -> bool {
+        self.get(peer).map_or(true, |s| s.matches(name))
+    }
+
+    /// Delete the refs of peers that are out of their scope from a project's namespace in
+    /// the monorepo. Returns the number of refs deleted.
+    pub fn prune(&self, monorepo: &git::Repository, urn: &Urn) -> Result<usize, anyhow::Error> {
+        let mut pruned = 0;
+
+        for (peer, scope) in &self.scopes {
+            pruned += project::prune_refs(monorepo, urn, peer, |name| !scope.matches(name))?;
+        }
+        Ok(pruned)
+    }
+}
+
+/// Scopes file, see [`FILE_NAME`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct File {
+    /// Ref patterns, by peer, by project.
+    #[serde(default)]
+    project: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+/// Set the scope of a tracked peer of a project, and update its tracking entry accordingly.
+/// An empty scope removes the peer's scope, so that all of its refs are fetched.
+pub fn set(
+    profile: &Profile,
+    storage: &Storage,
+    urn: &Urn,
+    peer: &PeerId,
+    scope: &Scope,
+) -> Result<(), anyhow::Error> {
+    tracking::track(
+        storage,
+        urn,
+        Some(*peer),
+        scope.config(),
+        tracking::policy::Track::Any,
+    )??;
+    store(profile, urn, peer, scope)
+}
+
+/// Remove the scope of a peer of a project, eg. once it is untracked. Its tracking entry,
+/// if any, is left as it is.
+pub fn remove(profile: &Profile, urn: &Urn, peer: &PeerId) -> Result<(), anyhow::Error> {
+    store(profile, urn, peer, &Scope::default())
+}
+
+/// Store the patterns of a peer's scope in the scopes file.
+fn store(profile: &Profile, urn: &Urn, peer: &PeerId, scope: &Scope) -> Result<(), anyhow::Error> {
+    let mut file: File = profile::load_toml(profile, FILE_NAME)?;
+    let key = urn.to_string();
+
+    if scope.patterns.is_empty() {
+        if let Some(peers) = file.project.get_mut(&key) {
+            peers.remove(&peer.default_encoding());
+            if peers.is_empty() {
+                file.project.remove(&key);
+            }
+        }
+    } else {
+        file.project
+            .entry(key)
+            .or_default()
+            .insert(peer.default_encoding(), scope.patterns.clone());
+    }
+    profile::store_toml(profile, FILE_NAME, &file)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scope_matches() {
+        let scope = Scope::new(vec!["heads/master".to_owned(), "tags/*".to_owned()]);
+
+        assert!(scope.matches("heads/master"));
+        assert!(scope.matches("tags/v1.0"));
+        assert!(scope.matches("rad/signed_refs"));
+        assert!(!scope.matches("heads/master-2"));
+        assert!(!scope.matches("heads/feature"));
+        assert!(!scope.matches("cobs/xyz.radicle.patch/abc"));
+    }
+
+    #[test]
+    fn test_scope_config() {
+        let config = Scope::new(vec!["heads/master".to_owned()]).config();
+        assert!(config.data);
+        assert_eq!(config.cobs, tracking::config::Cobs::deny_all());
+
+        let config = Scope::new(vec!["cobs/*".to_owned()]).config();
+        assert!(!config.data);
+        assert_eq!(config.cobs, tracking::config::Cobs::allow_all());
+
+        let config = Scope::new(vec!["*".to_owned()]).config();
+        assert!(config.data);
+        assert_eq!(config.cobs, tracking::config::Cobs::allow_all());
+
+        assert_eq!(
+            Scope::default().config(),
+            tracking::config::Config::default()
+        );
+    }
+}
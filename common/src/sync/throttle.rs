@@ -0,0 +1,104 @@
+//! Rate limiting and stall detection of transfers.
+use std::time;
+
+/// Throttles a transfer to a rate limit, and detects stalled transfers.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    start: time::Instant,
+    /// When more bytes were last received.
+    last: time::Instant,
+    /// Bytes received so far.
+    received: u64,
+    timeout: time::Duration,
+    /// Rate limit, in bytes per second.
+    rate_limit: Option<u64>,
+}
+
+impl Throttle {
+    /// Start throttling a transfer, considered stalled once no bytes are received for
+    /// longer than the timeout.
+    pub fn new(timeout: time::Duration, rate_limit: Option<u64>) -> Self {
+        let now = time::Instant::now();
+
+        Self {
+            start: now,
+            last: now,
+            received: 0,
+            timeout,
+            rate_limit: rate_limit.filter(|l| *l > 0),
+        }
+    }
+
+    /// Update the throttle with the total number of bytes received. The stall timeout
+    /// is only reset if more bytes were received than before.
+    pub fn update(&mut self, received: u64) {
+        if received > self.received {
+            self.received = received;
+            self.last = time::Instant::now();
+        }
+    }
+
+    /// How long to pause the transfer for, to stay within the rate limit.
+    pub fn delay(&self) -> time::Duration {
+        self.resume()
+            .saturating_duration_since(time::Instant::now())
+    }
+
+    /// When the transfer is considered stalled, unless more bytes are received. Time
+    /// spent paused for the rate limit doesn't count towards the timeout.
+    pub fn deadline(&self) -> time::Instant {
+        self.last.max(self.resume()) + self.timeout
+    }
+
+    /// Whether the transfer stalled.
+    pub fn is_stalled(&self) -> bool {
+        time::Instant::now() >= self.deadline()
+    }
+
+    /// When the transfer may resume, given the rate limit.
+    fn resume(&self) -> time::Instant {
+        match self.rate_limit {
+            Some(limit) => {
+                self.start + time::Duration::from_secs_f64(self.received as f64 / limit as f64)
+            }
+            None => self.start,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stalled() {
+        let mut throttle = Throttle::new(time::Duration::ZERO, None);
+        assert!(throttle.is_stalled());
+
+        let mut throttle = Throttle::new(time::Duration::from_secs(60), None);
+        throttle.update(1024);
+        let last = throttle.last;
+
+        // Receiving no more bytes doesn't count as progress.
+        throttle.update(1024);
+        throttle.update(512);
+        assert_eq!(throttle.last, last);
+        assert_eq!(throttle.deadline(), last + time::Duration::from_secs(60));
+        assert!(!throttle.is_stalled());
+        assert_eq!(throttle.delay(), time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut throttle = Throttle::new(time::Duration::from_secs(1), Some(1024));
+        throttle.update(1024 * 60);
+
+        // The transfer is a minute ahead of the rate limit, and isn't stalled while paused.
+        assert!(throttle.delay() > time::Duration::from_secs(59));
+        assert_eq!(
+            throttle.deadline(),
+            throttle.start + time::Duration::from_secs(61)
+        );
+        assert!(!throttle.is_stalled());
+    }
+}
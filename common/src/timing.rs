@@ -0,0 +1,95 @@
+//! Time spent by a command in storage reads, collaborative object parsing and network
+//! requests, reported by `rad debug profile <command>`, to diagnose slow commands.
+//!
+//! Timing is off by default, in which case [`measure`] only calls the function it is
+//! given. Time is summed across threads, so it can exceed the time the command took.
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+/// What time is spent on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Category {
+    /// Reading local storage, eg. refs, identities and change graphs.
+    Storage,
+    /// Parsing collaborative objects, ie. materializing their change graphs.
+    Cobs,
+    /// Requests to seeds, and syncing with them.
+    Network,
+}
+
+impl Category {
+    /// All categories, in the order they are reported.
+    pub const ALL: [Category; 3] = [Category::Storage, Category::Cobs, Category::Network];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Storage => "storage",
+            Self::Cobs => "cobs",
+            Self::Network => "network",
+        }
+    }
+}
+
+/// Time spent in a category.
+#[derive(Debug, Copy, Clone)]
+pub struct Timing {
+    pub category: Category,
+    /// Total time spent.
+    pub time: Duration,
+    /// Number of measured calls.
+    pub calls: u64,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NANOS: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+static CALLS: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+lazy_static! {
+    static ref START: Instant = Instant::now();
+}
+
+/// Turn timing on, from now on.
+pub fn enable() {
+    lazy_static::initialize(&START);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether timing is on.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Call a function, adding the time it takes to the given category, if timing is on.
+pub fn measure<T>(category: Category, f: impl FnOnce() -> T) -> T {
+    if !is_enabled() {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+
+    NANOS[category as usize].fetch_add(nanos, Ordering::Relaxed);
+    CALLS[category as usize].fetch_add(1, Ordering::Relaxed);
+
+    result
+}
+
+/// Time elapsed since timing was turned on.
+pub fn elapsed() -> Duration {
+    START.elapsed()
+}
+
+/// Time spent in each category so far.
+pub fn timings() -> Vec<Timing> {
+    Category::ALL
+        .iter()
+        .map(|category| Timing {
+            category: *category,
+            time: Duration::from_nanos(NANOS[*category as usize].load(Ordering::Relaxed)),
+            calls: CALLS[*category as usize].load(Ordering::Relaxed),
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{cobs, keys, profile, project};
+use radicle_terminal as term;
+
+mod script;
+use script::Spec;
+
+pub const HELP: Help = Help {
+    name: "completions",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad completions <bash | zsh | fish>
+    rad completions --values <command>
+
+    Prints a completion script for the given shell. Besides commands and their
+    options, the script completes patch and issue ids, project URNs and tracked
+    peers, which it gets from local storage with `rad completions --values`.
+
+    To enable completions, add one of the following to the startup file of your
+    shell:
+
+        bash    eval "$(rad completions bash)"
+        zsh     eval "$(rad completions zsh)"
+        fish    rad completions fish | source
+
+    Patch and issue ids are only completed if the key of the profile is in
+    ssh-agent, so that completing never prompts for a passphrase.
+
+Options
+
+    --values <command>  Print the values to complete for the arguments of a command
+    --help              Print help
+"#,
+};
+
+/// A shell to generate completions for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            _ => Err(anyhow!(term::tr!(
+                "unsupported shell '{}': expected `bash`, `zsh` or `fish`",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Script(Shell),
+    Values(String),
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<Operation> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("values") if op.is_none() => {
+                    let val = parser.value()?;
+
+                    op = Some(Operation::Values(val.to_string_lossy().to_string()));
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => {
+                    let shell = Shell::from_str(&val.to_string_lossy())?;
+
+                    op = Some(Operation::Script(shell));
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                op: op.ok_or_else(|| anyhow!("a shell must be specified, eg. `bash`"))?,
+            },
+            vec![],
+        ))
+    }
+}
+
+/// Run the command. The commands to complete are given by `rad`, which knows them all.
+pub fn run(options: Options, ctx: impl term::Context, commands: &[Help]) -> anyhow::Result<()> {
+    match options.op {
+        Operation::Script(shell) => {
+            let specs = commands.iter().map(Spec::new).collect::<Vec<_>>();
+
+            print!("{}", script::generate(shell, &specs));
+        }
+        Operation::Values(command) => {
+            // Completion shouldn't show errors: there is simply nothing to complete.
+            if let Ok(values) = values(&command, ctx) {
+                for value in values {
+                    println!("{}", value);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Kind of values that the arguments of a command take.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Kind {
+    Patches,
+    Issues,
+    Projects,
+    Peers,
+}
+
+fn kinds(command: &str) -> &'static [Kind] {
+    match command {
+        "merge" | "review" | "patch" => &[Kind::Patches],
+        "issue" => &[Kind::Issues],
+        "comment" => &[Kind::Patches, Kind::Issues],
+        "checkout" | "inspect" | "rm" | "sync" | "fork" => &[Kind::Projects],
+        "track" | "untrack" | "block" => &[Kind::Peers],
+        _ => &[],
+    }
+}
+
+/// Get the values to complete for the arguments of a command, from local storage.
+/// Patches, issues and peers are those of the project of the current working copy.
+fn values(command: &str, ctx: impl term::Context) -> anyhow::Result<Vec<String>> {
+    let kinds = kinds(command);
+    if kinds.is_empty() {
+        return Ok(vec![]);
+    }
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let urn = || -> anyhow::Result<Urn> { Ok(project::cwd()?.0) };
+    let mut values = Vec::new();
+
+    for kind in kinds {
+        match kind {
+            Kind::Projects => {
+                for (urn, _, _) in project::list(&storage)? {
+                    values.push(urn.to_string());
+                }
+            }
+            Kind::Peers => {
+                let urn = urn()?;
+                let project = project::get(&storage, &urn)?
+                    .ok_or_else(|| anyhow!(term::tr!("project {} not found", urn)))?;
+
+                for peer in project::tracked(&project, &storage)?.keys() {
+                    values.push(peer.to_string());
+                }
+            }
+            Kind::Patches | Kind::Issues => {
+                let urn = urn()?;
+                let storage = keys::storage_read_only(&profile)?;
+                let cobs = cobs::store(&profile, &storage)?;
+
+                if *kind == Kind::Patches {
+                    for (id, _) in cobs.patches().proposed(&urn)? {
+                        values.push(id.to_string());
+                    }
+                } else {
+                    for (id, issue) in cobs.issues().all(&urn)? {
+                        if issue.state() == cobs::issue::State::Open {
+                            values.push(id.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(values)
+}
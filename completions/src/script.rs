@@ -0,0 +1,239 @@
+//! Generation of completion scripts.
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+use radicle_common::args::Help;
+
+use super::{kinds, Shell};
+
+/// Options of `rad` itself, given before the command.
+const GLOBAL_OPTIONS: &[&str] = &[
+    "--profile",
+    "--json",
+    "--porcelain",
+    "--color",
+    "--no-confirm",
+    "--ascii",
+    "--offline",
+    "--version",
+    "--help",
+];
+
+/// What is completed for a command.
+#[derive(Debug)]
+pub struct Spec {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Options, eg. `--help`, or `-m`.
+    pub options: Vec<String>,
+    /// Subcommands, eg. `new` for `rad issue new`.
+    pub subcommands: Vec<String>,
+    /// Whether values for the arguments are completed with `rad completions --values`.
+    pub values: bool,
+}
+
+impl Spec {
+    /// Get what to complete for a command from its usage, ie. the options in its usage
+    /// lines and `Options` section, and the subcommands in its usage lines.
+    pub fn new(help: &Help) -> Self {
+        let usage = format!("rad {}", help.name);
+        let mut options = BTreeSet::new();
+        let mut subcommands = BTreeSet::new();
+        let mut section = "";
+
+        for line in help.usage.lines() {
+            let trimmed = line.trim();
+
+            if !line.starts_with(' ') && !trimmed.is_empty() {
+                section = trimmed;
+                continue;
+            }
+            let spec = if trimmed == usage || trimmed.starts_with(&format!("{} ", usage)) {
+                let rest = trimmed[usage.len()..].trim_start();
+                let word = rest.split_whitespace().next().unwrap_or_default();
+
+                if word.starts_with(|c: char| c.is_ascii_lowercase())
+                    && word.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+                {
+                    subcommands.insert(word.to_owned());
+                }
+                rest
+            } else if section == "Options" && trimmed.starts_with('-') {
+                // Nb. Option descriptions are separated from the option by two spaces.
+                trimmed.split("  ").next().unwrap_or_default()
+            } else {
+                continue;
+            };
+
+            // Options like `--[no-]sync` stand for both `--sync` and `--no-sync`.
+            for spec in [
+                spec.replace("--[no-]", "--"),
+                spec.replace("--[no-]", "--no-"),
+            ] {
+                for token in spec.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')) {
+                    if is_option(token) {
+                        options.insert(token.to_owned());
+                    }
+                }
+            }
+        }
+
+        Self {
+            name: help.name,
+            description: help.description,
+            options: options.into_iter().collect(),
+            subcommands: subcommands.into_iter().collect(),
+            values: !kinds(help.name).is_empty(),
+        }
+    }
+}
+
+/// Check whether a token is an option, eg. `--help` or `-m`.
+fn is_option(token: &str) -> bool {
+    if let Some(name) = token.strip_prefix("--") {
+        name.starts_with(|c: char| c.is_ascii_alphabetic())
+    } else if let Some(name) = token.strip_prefix('-') {
+        name.len() == 1 && name.chars().all(|c| c.is_ascii_alphabetic())
+    } else {
+        false
+    }
+}
+
+pub fn generate(shell: Shell, specs: &[Spec]) -> String {
+    match shell {
+        Shell::Bash => bash(specs),
+        Shell::Zsh => zsh(specs),
+        Shell::Fish => fish(specs),
+    }
+}
+
+fn bash(specs: &[Spec]) -> String {
+    let mut s = String::new();
+    let commands = specs.iter().map(|s| s.name).collect::<Vec<_>>();
+    let dynamic = specs
+        .iter()
+        .filter(|s| s.values)
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+
+    s.push_str("# Completions of `rad`, generated by `rad completions bash`.\n");
+    s.push_str("_rad() {\n");
+    s.push_str(
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" cmd=\"${COMP_WORDS[1]}\" words=\"\"\n\n",
+    );
+    s.push_str("    if [ \"$COMP_CWORD\" -eq 1 ]; then\n");
+    writeln!(
+        s,
+        "        words=\"{} {}\"",
+        commands.join(" "),
+        GLOBAL_OPTIONS.join(" ")
+    )
+    .ok();
+    s.push_str("    elif [[ \"$cur\" == -* ]]; then\n");
+    s.push_str("        case \"$cmd\" in\n");
+    for spec in specs.iter().filter(|s| !s.options.is_empty()) {
+        writeln!(
+            s,
+            "            {}) words=\"{}\" ;;",
+            spec.name,
+            spec.options.join(" ")
+        )
+        .ok();
+    }
+    s.push_str("        esac\n");
+    s.push_str("    else\n");
+    s.push_str("        if [ \"$COMP_CWORD\" -eq 2 ]; then\n");
+    s.push_str("            case \"$cmd\" in\n");
+    for spec in specs.iter().filter(|s| !s.subcommands.is_empty()) {
+        writeln!(
+            s,
+            "                {}) words=\"{}\" ;;",
+            spec.name,
+            spec.subcommands.join(" ")
+        )
+        .ok();
+    }
+    s.push_str("            esac\n");
+    s.push_str("        fi\n");
+    if !dynamic.is_empty() {
+        s.push_str("        case \"$cmd\" in\n");
+        writeln!(
+            s,
+            "            {}) words=\"$words $(rad completions --values \"$cmd\" 2>/dev/null)\" ;;",
+            dynamic.join("|")
+        )
+        .ok();
+        s.push_str("        esac\n");
+    }
+    s.push_str("    fi\n");
+    s.push_str("    COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n");
+    s.push_str("}\n\n");
+    s.push_str("complete -o default -F _rad rad\n");
+
+    s
+}
+
+/// Zsh runs the bash completions, through its emulation of bash completion.
+fn zsh(specs: &[Spec]) -> String {
+    let mut s = String::new();
+
+    s.push_str("#compdef rad\n");
+    s.push_str("# Completions of `rad`, generated by `rad completions zsh`.\n\n");
+    s.push_str("autoload -U +X compinit && compinit\n");
+    s.push_str("autoload -U +X bashcompinit && bashcompinit\n\n");
+    s.push_str(&bash(specs));
+
+    s
+}
+
+fn fish(specs: &[Spec]) -> String {
+    let mut s = String::new();
+
+    s.push_str("# Completions of `rad`, generated by `rad completions fish`.\n");
+    for option in GLOBAL_OPTIONS {
+        writeln!(
+            s,
+            "complete -c rad -n __fish_use_subcommand -l {}",
+            option.trim_start_matches('-')
+        )
+        .ok();
+    }
+    for spec in specs {
+        writeln!(
+            s,
+            "complete -c rad -n __fish_use_subcommand -f -a {} -d '{}'",
+            spec.name,
+            spec.description.replace('\'', "\\'")
+        )
+        .ok();
+    }
+    for spec in specs {
+        let condition = format!("'__fish_seen_subcommand_from {}'", spec.name);
+
+        for option in &spec.options {
+            let flag = match option.strip_prefix("--") {
+                Some(long) => format!("-l {}", long),
+                None => format!("-s {}", option.trim_start_matches('-')),
+            };
+            writeln!(s, "complete -c rad -n {} {}", condition, flag).ok();
+        }
+        if !spec.subcommands.is_empty() {
+            writeln!(
+                s,
+                "complete -c rad -n {} -f -a '{}'",
+                condition,
+                spec.subcommands.join(" ")
+            )
+            .ok();
+        }
+        if spec.values {
+            writeln!(
+                s,
+                "complete -c rad -n {} -a '(rad completions --values {} 2>/dev/null)'",
+                condition, spec.name
+            )
+            .ok();
+        }
+    }
+    s
+}
@@ -0,0 +1,314 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::config::{self, get, parse, set, Config, Scope};
+use radicle_common::profile::Profile;
+use radicle_common::{exit, json};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "config",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad config list [--scope <scope>]
+    rad config get <key> [--scope <scope>]
+    rad config set <key> <value> [--scope <scope>]
+    rad config edit [--scope <scope>]
+
+    The configuration is layered from the following scopes, each overriding the
+    ones before:
+
+    user      ~/.config/radicle/config.toml, for all profiles
+    profile   config.toml, in the profile directory
+    project   rad/config, in the git directory of the working copy, eg. .git/rad/config
+
+    The project scope is kept out of the working copy, so that it can't be committed.
+    A Radicle.toml file in the working copy, which can be committed, is read under the
+    project scope, but may only set the keys of the team's workflow: `patch.sync` and
+    `patch.target`.
+
+    Environment variables override all scopes. They are named after the key, eg.
+    RAD_SYNC_RETRIES for `sync.retries`, and RAD_SEED for `seed`, as a comma-separated
+    list of seed URLs. RAD_NO_CONFIRM and RAD_NO_SYNC, when set, turn `confirm` and
+    `sync.auto` off.
+
+    `list` and `get` show the configuration in effect, unless a scope is given.
+    `set` and `edit` change the profile scope, unless another scope is given.
+
+    Keys are dotted paths, eg. `sync.retries`, with numbers for array elements,
+    eg. `seed.0.name`. Values are TOML values, eg. `false`, `3` or `["a", "b"]`;
+    other values are taken as strings.
+
+    With `rad --json`, `list` and `get` print their values as JSON.
+
+Examples
+
+    rad config set confirm false
+    rad config set editor "code --wait"
+    rad config set sync.retries 5 --scope project
+    rad config get sync.retries
+
+Options
+
+    --scope <scope>   Scope of the configuration file: user, profile or project
+    --help            Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    List,
+    Get,
+    Set,
+    Edit,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    List,
+    Get { key: String },
+    Set { key: String, value: String },
+    Edit,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub scope: Option<Scope>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut scope: Option<Scope> = None;
+        let mut key: Option<String> = None;
+        let mut value: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("scope") => {
+                    let val = parser.value()?;
+                    scope = Some(val.to_string_lossy().parse()?);
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "list" | "ls" => op = Some(OperationName::List),
+                    "get" => op = Some(OperationName::Get),
+                    "set" => op = Some(OperationName::Set),
+                    "edit" => op = Some(OperationName::Edit),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val)
+                    if key.is_none()
+                        && matches!(op, Some(OperationName::Get | OperationName::Set)) =>
+                {
+                    key = Some(val.to_string_lossy().to_string());
+                }
+                Value(val) if value.is_none() && op == Some(OperationName::Set) => {
+                    value = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::List => Operation::List,
+            OperationName::Get => Operation::Get {
+                key: key.ok_or_else(|| anyhow!("a key must be specified, eg. `sync.retries`"))?,
+            },
+            OperationName::Set => Operation::Set {
+                key: key.ok_or_else(|| anyhow!("a key must be specified, eg. `sync.retries`"))?,
+                value: value.ok_or_else(|| anyhow!("a value must be specified"))?,
+            },
+            OperationName::Edit => Operation::Edit,
+        };
+
+        Ok((Options { op, scope }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+
+    match options.op {
+        Operation::List => {
+            let config = match options.scope {
+                Some(scope) => read(&path(scope, &profile)?)?,
+                None => Config::layered(&profile)?,
+            };
+
+            if term::output::is_json() {
+                term::output::json(&json::to_value(&config)?)?;
+            } else {
+                for (key, value) in flatten("", &config) {
+                    term::print(format!("{} = {}", term::format::tertiary(key), value));
+                }
+            }
+        }
+        Operation::Get { key } => {
+            let config = match options.scope {
+                Some(scope) => read(&path(scope, &profile)?)?,
+                None => Config::layered(&profile)?,
+            };
+            let value = get(&config, &key).ok_or_else(|| {
+                exit::Error::NotFound(term::tr!("configuration key '{}' is not set", key))
+            })?;
+
+            if term::output::is_json() {
+                term::output::json(&json::to_value(value)?)?;
+            } else {
+                match value {
+                    toml::Value::String(s) => term::print(s),
+                    toml::Value::Table(_) | toml::Value::Array(_) => {
+                        for (key, value) in flatten(&key, value) {
+                            term::print(format!("{} = {}", term::format::tertiary(key), value));
+                        }
+                    }
+                    other => term::print(other),
+                }
+            }
+        }
+        Operation::Set { key, value } => {
+            let scope = options.scope.unwrap_or(Scope::Profile);
+            let path = path(scope, &profile)?;
+            let config = read(&path)?;
+            let config = vec![parse(&value), toml::Value::String(value.clone())]
+                .into_iter()
+                .find_map(|value| {
+                    let mut config = config.clone();
+                    set(&mut config, &key, value).ok()?;
+                    validate(&config, &key).ok()?;
+
+                    Some(config)
+                })
+                .ok_or_else(|| {
+                    // Get the reason the value couldn't be set.
+                    let mut config = config.clone();
+                    set(&mut config, &key, parse(&value))
+                        .and_then(|_| validate(&config, &key))
+                        .err()
+                        .unwrap_or_else(|| {
+                            anyhow!(term::tr!("invalid value '{}' for '{}'", value, key))
+                        })
+                })?;
+
+            write(&path, &config)?;
+
+            let effective = Config::layered(&profile)?;
+            if get(&effective, &key) != get(&config, &key) {
+                term::warning(&term::tr!(
+                    "'{}' is overridden by a more specific scope than the {} scope, or by `{}`",
+                    key,
+                    scope,
+                    config::env_var(&key)
+                ));
+            }
+            term::success!(
+                "Set {} in the {} configuration",
+                term::format::highlight(&key),
+                scope
+            );
+        }
+        Operation::Edit => {
+            let scope = options.scope.unwrap_or(Scope::Profile);
+            let path = path(scope, &profile)?;
+
+            if !path.exists() {
+                write(&path, &toml::Value::Table(Default::default()))?;
+            }
+            term::editor::open(&path)?;
+
+            if let Err(err) = Config::read(&path) {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("{}: {}", path.display(), err)),
+                    hint: "hint: fix the configuration with `rad config edit`",
+                }
+                .into());
+            }
+            term::success!("Updated the {} configuration", scope);
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the path of a scope's configuration file.
+fn path(scope: Scope, profile: &Profile) -> anyhow::Result<PathBuf> {
+    scope.path(profile).ok_or_else(|| {
+        Error::WithHint {
+            err: exit::Error::NotProject.into(),
+            hint: "hint: the project scope is only available within a working copy",
+        }
+        .into()
+    })
+}
+
+/// Read a configuration file. If it doesn't exist, it is empty.
+fn read(path: &Path) -> anyhow::Result<toml::Value> {
+    match Config::read_value(path) {
+        Ok(config) => Ok(config),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Ok(toml::Value::Table(Default::default()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Write a configuration file, creating its directory if needed, eg. `.rad`.
+fn write(path: &Path, config: &toml::Value) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string_pretty(config)?)?;
+
+    Ok(())
+}
+
+/// Check that a configuration file is valid, and that the given key is known.
+fn validate(config: &toml::Value, key: &str) -> anyhow::Result<()> {
+    let mut layered = Config::default().to_value()?;
+    config::merge(&mut layered, config.clone());
+
+    let parsed = Config::from_value(layered)?.to_value()?;
+    if get(&parsed, key).is_none() {
+        anyhow::bail!(term::tr!("unknown configuration key '{}'", key));
+    }
+    Ok(())
+}
+
+/// Flatten a value into its dotted keys and values, eg. `sync.retries = 2`.
+fn flatten(prefix: &str, value: &toml::Value) -> Vec<(String, toml::Value)> {
+    let key = |k: &str| {
+        if prefix.is_empty() {
+            k.to_owned()
+        } else {
+            format!("{}.{}", prefix, k)
+        }
+    };
+    match value {
+        toml::Value::Table(table) => table
+            .iter()
+            .flat_map(|(k, v)| flatten(&key(k), v))
+            .collect(),
+        toml::Value::Array(array) if array.iter().any(|v| v.is_table()) => array
+            .iter()
+            .enumerate()
+            .flat_map(|(ix, v)| flatten(&key(&ix.to_string()), v))
+            .collect(),
+        other => vec![(prefix.to_owned(), other.clone())],
+    }
+}
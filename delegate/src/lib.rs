@@ -0,0 +1,409 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use either::Either;
+
+use librad::git::identities::{self, person, project, Project};
+use librad::git::storage::Storage;
+use librad::git::Urn;
+use librad::identities::Person;
+use librad::PeerId;
+use librad::PublicKey;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::identity::quorum;
+use radicle_common::{exit, fmt, keys};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "delegate",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad delegate list [--project <urn>]
+    rad delegate add <urn | peer-id> [--project <urn>]
+    rad delegate remove <urn | peer-id> [--project <urn>]
+    rad delegate sign <peer-id> [--project <urn>]
+    rad delegate policy [--[no-]protect-default-branch] [--project <urn>]
+
+    Delegates are the persons, or keys, that control a project's identity. A delegate
+    is given either by the URN of a personal identity, which must be in local storage,
+    eg. by tracking one of its peers, or by a peer id for a single key.
+
+    `add` and `remove` create a new revision of the project identity, signed by you.
+    When a project has several delegates, a revision only takes effect once a quorum,
+    ie. a majority of the current delegates, has signed it. The other delegates sign
+    it by fetching it and running `rad delegate sign <peer-id>`, with the peer id of
+    the delegate who proposed it.
+
+    `policy` shows the project policy, or changes it with a new revision. When the
+    default branch is protected, only delegates may push to it with `rad push`, and
+    other peers propose their changes as patches.
+
+    If no project is given, the project of the current working copy is used.
+
+Options
+
+    --project <urn>                 Project to manage
+    --protect-default-branch        Only allow delegates to push to the default branch
+    --no-protect-default-branch     Allow any peer to push to the default branch
+    --help                          Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    List,
+    Add,
+    Remove,
+    Sign,
+    Policy,
+}
+
+/// A delegate, given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Person(Urn),
+    Key(PeerId),
+}
+
+impl FromStr for Target {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(urn) = Urn::from_str(s) {
+            Ok(Self::Person(urn))
+        } else if let Ok(peer) = PeerId::from_str(s) {
+            Ok(Self::Key(peer))
+        } else {
+            Err(anyhow!(term::tr!(
+                "invalid delegate '{}': expected a URN or peer id",
+                s
+            )))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    List,
+    Add { target: Target },
+    Remove { target: Target },
+    Sign { peer: PeerId },
+    Policy { protect: Option<bool> },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub project: Option<Urn>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut target: Option<Target> = None;
+        let mut project: Option<Urn> = None;
+        let mut protect: Option<bool> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("project") if project.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    project = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid project URN '{}'", val)))?,
+                    );
+                }
+                Long("protect-default-branch") if op == Some(OperationName::Policy) => {
+                    protect = Some(true);
+                }
+                Long("no-protect-default-branch") if op == Some(OperationName::Policy) => {
+                    protect = Some(false);
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "list" | "ls" => op = Some(OperationName::List),
+                    "add" => op = Some(OperationName::Add),
+                    "remove" | "rm" => op = Some(OperationName::Remove),
+                    "sign" => op = Some(OperationName::Sign),
+                    "policy" => op = Some(OperationName::Policy),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val)
+                    if target.is_none()
+                        && op != Some(OperationName::List)
+                        && op != Some(OperationName::Policy) =>
+                {
+                    target = Some(Target::from_str(&val.to_string_lossy())?);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::List => Operation::List,
+            OperationName::Add => Operation::Add {
+                target: target.ok_or_else(|| anyhow!("a delegate must be specified"))?,
+            },
+            OperationName::Remove => Operation::Remove {
+                target: target.ok_or_else(|| anyhow!("a delegate must be specified"))?,
+            },
+            OperationName::Sign => match target {
+                Some(Target::Key(peer)) => Operation::Sign { peer },
+                Some(Target::Person(_)) => {
+                    anyhow::bail!("the peer id of the proposing delegate must be specified")
+                }
+                None => anyhow::bail!("a peer id must be specified"),
+            },
+            OperationName::Policy => Operation::Policy { protect },
+        };
+
+        Ok((Options { op, project }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+
+    let urn = match options.project {
+        Some(urn) => urn,
+        None => radicle_common::project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("could not determine project"),
+                hint: "hint: run this command within a project working copy, or use `--project`",
+            })?,
+    };
+
+    match options.op {
+        Operation::List => {
+            list(&storage, &urn)?;
+        }
+        Operation::Add { target } => {
+            let mut delegations = delegations(&storage, &urn)?;
+
+            if delegations.iter().any(|d| matches(d, &target)) {
+                anyhow::bail!(term::tr!("{} is already a delegate", display(&target)));
+            }
+            let delegate = match &target {
+                Target::Person(person) => {
+                    Either::Right(person::get(&storage, person)?.ok_or_else(|| {
+                        Error::WithHint {
+                            err: exit::Error::NotFound(term::tr!(
+                                "person {} not found in local storage",
+                                person
+                            ))
+                            .into(),
+                            hint: "hint: track one of the person's peers, and sync the project",
+                        }
+                    })?)
+                }
+                Target::Key(peer) => Either::Left(*peer.as_public_key()),
+            };
+            delegations.push(delegate);
+
+            let project = update(&storage, &urn, delegations)?;
+            term::success!(
+                "Delegate {} added",
+                term::format::highlight(display(&target))
+            );
+            status(&storage, &project)?;
+        }
+        Operation::Remove { target } => {
+            let mut delegations = delegations(&storage, &urn)?;
+            let len = delegations.len();
+
+            delegations.retain(|d| !matches(d, &target));
+            if delegations.len() == len {
+                anyhow::bail!(term::tr!("{} is not a delegate", display(&target)));
+            }
+            if delegations.is_empty() {
+                anyhow::bail!("the last delegate of a project can't be removed");
+            }
+
+            let project = update(&storage, &urn, delegations)?;
+            term::success!(
+                "Delegate {} removed",
+                term::format::highlight(display(&target))
+            );
+            status(&storage, &project)?;
+        }
+        Operation::Sign { peer } => {
+            let project = project::merge(&storage, &urn, peer)?;
+
+            term::success!(
+                "Signed the revision proposed by {}",
+                term::format::tertiary(fmt::peer(&peer))
+            );
+            status(&storage, &project)?;
+        }
+        Operation::Policy { protect } => {
+            let proj = radicle_common::project::get(&storage, &urn)?
+                .ok_or_else(|| exit::Error::NotFound(term::tr!("project {} not found", urn)))?;
+            let mut policy = proj.policy;
+
+            match protect {
+                Some(protect) if protect != policy.protect_default_branch => {
+                    policy.protect_default_branch = protect;
+
+                    let project = radicle_common::project::set_policy(&storage, &urn, policy)?;
+                    term::success!(
+                        "Default branch protection {}",
+                        if protect { "enabled" } else { "disabled" }
+                    );
+                    status(&storage, &project)?;
+                }
+                _ => {
+                    term::info!(
+                        "{} {}",
+                        term::format::dim("Default branch protection"),
+                        if policy.protect_default_branch {
+                            term::format::positive("enabled")
+                        } else {
+                            term::format::dim("disabled")
+                        }
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the current delegations of a project, as owned values.
+fn delegations(storage: &Storage, urn: &Urn) -> anyhow::Result<Vec<Either<PublicKey, Person>>> {
+    let project = project::get(storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(term::tr!("project {} not found", urn)))?;
+    let delegations = project
+        .delegations()
+        .iter()
+        .map(|d| d.map_left(|pk| *pk).map_right(|p| p.clone()))
+        .collect();
+
+    Ok(delegations)
+}
+
+/// Create and sign a new revision of a project with the given delegations.
+fn update(
+    storage: &Storage,
+    urn: &Urn,
+    delegations: Vec<Either<PublicKey, Person>>,
+) -> anyhow::Result<Project> {
+    let delegations = identities::IndirectDelegation::try_from_iter(delegations)?;
+    let project = project::update(storage, urn, None, None, delegations)?;
+
+    Ok(project)
+}
+
+fn matches(delegate: &Either<PublicKey, Person>, target: &Target) -> bool {
+    match (delegate, target) {
+        (Either::Left(pk), Target::Key(peer)) => PeerId::from(*pk) == *peer,
+        (Either::Right(person), Target::Person(urn)) => person.urn() == *urn,
+        _ => false,
+    }
+}
+
+/// Check whether a delegate holds our key.
+fn is_self(delegate: Either<&PublicKey, &Person>, peer: &PeerId) -> bool {
+    match delegate {
+        Either::Left(pk) => PeerId::from(*pk) == *peer,
+        Either::Right(person) => person
+            .delegations()
+            .iter()
+            .any(|pk| PeerId::from(*pk) == *peer),
+    }
+}
+
+fn display(target: &Target) -> String {
+    match target {
+        Target::Person(urn) => urn.to_string(),
+        Target::Key(peer) => peer.default_encoding(),
+    }
+}
+
+/// Print whether the latest revision of a project has taken effect.
+fn status(storage: &Storage, latest: &Project) -> anyhow::Result<()> {
+    let verified = project::verify(storage, &latest.urn())?;
+
+    if verified.as_ref().map(|v| v.revision) == Some(latest.revision) {
+        term::info!("The new revision is in effect.");
+    } else {
+        // The quorum is that of the revision currently in effect.
+        let delegates = verified
+            .map(|v| v.delegations().iter().count())
+            .unwrap_or_else(|| latest.delegations().iter().count());
+
+        term::info!(
+            "The new revision has {} of the {} signatures it needs to take effect.",
+            latest.signatures.len(),
+            quorum(delegates)
+        );
+        term::tip!("Other delegates can sign it with `rad delegate sign <your-peer-id>`.");
+    }
+    term::tip!("To publish the revision, run `rad sync`.");
+
+    Ok(())
+}
+
+fn list(storage: &Storage, urn: &Urn) -> anyhow::Result<()> {
+    let latest = project::get(storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(term::tr!("project {} not found", urn)))?;
+    let verified = project::verify(storage, urn)?;
+    let current = verified.as_ref().map(|v| v.delegations().iter().count());
+    let mut table = term::Table::default();
+
+    for delegate in latest.delegations().iter() {
+        let (id, name, keys) = match delegate {
+            Either::Left(pk) => (PeerId::from(*pk).default_encoding(), String::new(), 1),
+            Either::Right(person) => (
+                person.urn().to_string(),
+                person.subject().name.to_string(),
+                person.delegations().len(),
+            ),
+        };
+        table.push([
+            term::format::bold(name),
+            term::format::tertiary(id),
+            term::format::dim(format!("{} key(s)", keys)),
+            if is_self(delegate, storage.peer_id()) {
+                term::format::badge_primary("you")
+            } else {
+                String::new()
+            },
+        ]);
+    }
+    table.render();
+
+    let delegates = current.unwrap_or_else(|| latest.delegations().iter().count());
+    term::blank();
+    term::info!(
+        "{} {} of {} delegate(s)",
+        term::format::dim("Quorum"),
+        quorum(delegates),
+        delegates
+    );
+
+    if verified.map(|v| v.revision) != Some(latest.revision) {
+        term::warning(&term::tr!(
+            "The latest revision has {} of the {} signatures it needs, and is not yet in effect",
+            latest.signatures.len(),
+            quorum(delegates),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,359 @@
+use std::ffi::OsString;
+use std::time;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::config::Config;
+use radicle_common::profile::Profile;
+use radicle_common::{git, json, keys, offline, profile, project, seed};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "doctor",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad doctor [--offline]
+
+    Checks your environment and storage, and prints the result of each check, with a
+    hint to fix it when it fails:
+
+    profile       the radicle profile exists
+    ssh-agent     the profile's key was added to ssh-agent, eg. with `rad auth`
+    storage       the local storage, or monorepo, can be read, with its projects,
+                  and has a commit-graph, which speeds up history traversal
+    git           the installed git version is supported
+    working copy  within a git repository, the "rad" remote points to a project
+                  that is in local storage
+    seeds         the configured seeds are reachable
+
+    Include the output in bug reports. With `rad --json`, the results are printed as
+    a JSON array. The command fails if any check fails; warnings don't fail it.
+
+Options
+
+    --offline   Skip the checks that need the network, ie. of seeds; this is
+                the default in offline mode, see `rad --offline`
+    --help      Print help
+"#,
+};
+
+#[derive(Default, Debug)]
+pub struct Options {
+    pub offline: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut offline = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("offline") => {
+                    offline = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options { offline }, vec![]))
+    }
+}
+
+/// Result of a check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    message: String,
+    hint: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, message: impl ToString) -> Self {
+        Self {
+            name,
+            status: Status::Pass,
+            message: message.to_string(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl ToString, hint: &'static str) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            message: message.to_string(),
+            hint: Some(hint),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl ToString, hint: &'static str) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            message: message.to_string(),
+            hint: Some(hint),
+        }
+    }
+
+    fn print(&self) {
+        let symbol = match self.status {
+            Status::Pass => term::format::positive("✓"),
+            Status::Warn => term::format::yellow("!"),
+            Status::Fail => term::format::negative("✗"),
+        };
+        term::print(format!(
+            "{} {} {}",
+            symbol,
+            term::format::bold(format!("{:-12}", self.name)),
+            self.message
+        ));
+        if let Some(hint) = self.hint {
+            term::print(format!("{:15}{}", "", term::format::dim(hint)));
+        }
+    }
+
+    fn to_json(&self) -> json::Value {
+        json::json!({
+            "check": self.name,
+            "status": self.status.as_str(),
+            "message": self.message,
+            "hint": self.hint,
+        })
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile();
+    let mut checks = Vec::new();
+
+    match &profile {
+        Ok(profile) => {
+            checks.push(Check::pass(
+                "profile",
+                format!(
+                    "{} in {}",
+                    profile.id(),
+                    Config::path(profile)
+                        .parent()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default()
+                ),
+            ));
+            checks.push(ssh_agent(profile));
+            checks.push(storage(profile));
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "profile",
+                err,
+                "hint: create a profile with `rad auth`, or select one with `rad --profile`",
+            ));
+        }
+    }
+    checks.push(git_version());
+
+    if let Some(check) = working_copy(profile.as_ref().ok()) {
+        checks.push(check);
+    }
+    if !options.offline && !offline::is_enabled() {
+        if let Ok(profile) = &profile {
+            checks.extend(seeds(profile));
+        }
+    }
+
+    if term::output::is_json() {
+        term::output::json(&json::Value::Array(
+            checks.iter().map(Check::to_json).collect(),
+        ))?;
+    } else {
+        for check in &checks {
+            check.print();
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        anyhow::bail!(term::tr!("{} of {} checks failed", failed, checks.len()));
+    }
+    Ok(())
+}
+
+fn ssh_agent(profile: &Profile) -> Check {
+    let sock = match keys::ssh_auth_sock() {
+        Ok(sock) => sock,
+        Err(_) if keys::read_env_passphrase().is_ok() => {
+            return Check::pass(
+                "ssh-agent",
+                "not running, the key is unsealed with RAD_PASSPHRASE",
+            );
+        }
+        Err(_) => {
+            return Check::warn(
+                "ssh-agent",
+                "not running, you will be asked for your passphrase",
+                "hint: start ssh-agent, eg. with `eval $(ssh-agent)`, then run `rad auth`",
+            );
+        }
+    };
+    match keys::is_ready(profile, sock) {
+        Ok(true) => Check::pass("ssh-agent", "the profile's key is added"),
+        Ok(false) => Check::warn(
+            "ssh-agent",
+            "the profile's key isn't added, you will be asked for your passphrase",
+            "hint: add the key to ssh-agent with `rad auth`",
+        ),
+        Err(err) => Check::fail(
+            "ssh-agent",
+            format!("could not query ssh-agent: {}", err),
+            "hint: check that `SSH_AUTH_SOCK` points to a running ssh-agent",
+        ),
+    }
+}
+
+fn storage(profile: &Profile) -> Check {
+    let hint = "hint: check that the profile directory exists, and is readable and writable by you";
+    let storage = match profile::read_only(profile) {
+        Ok(storage) => storage,
+        Err(err) => return Check::fail("storage", format!("could not open: {}", err), hint),
+    };
+    if let Err(err) = git::Repository::open_bare(profile.paths().git_dir()) {
+        return Check::fail("storage", format!("invalid monorepo: {}", err), hint);
+    }
+    match project::list(&storage) {
+        Ok(projects) => {
+            let message = format!(
+                "{} project(s) in {}",
+                projects.len(),
+                profile.paths().git_dir().display()
+            );
+            if git::has_commit_graph(profile.paths().git_dir()) {
+                Check::pass("storage", message)
+            } else {
+                Check::warn(
+                    "storage",
+                    format!("{}, without a commit-graph", message),
+                    "hint: write the commit-graph with `rad gc`",
+                )
+            }
+        }
+        Err(err) => Check::fail("storage", format!("could not list projects: {}", err), hint),
+    }
+}
+
+fn git_version() -> Check {
+    match git::check_version() {
+        Ok(version) => Check::pass("git", format!("version {}", version)),
+        Err(err) => Check::fail(
+            "git",
+            err,
+            "hint: install a more recent version of git, see https://git-scm.com",
+        ),
+    }
+}
+
+/// Check the working copy, if the working directory is in a git repository.
+fn working_copy(profile: Option<&Profile>) -> Option<Check> {
+    let repo = git::repository().ok()?;
+    let urn = match git::rad_remote(&repo) {
+        Ok(remote) => remote.url.urn,
+        Err(_) => {
+            return Some(Check::warn(
+                "working copy",
+                "no \"rad\" remote, this isn't a radicle project",
+                "hint: publish the repository with `rad init`, or check out a project with `rad checkout`",
+            ));
+        }
+    };
+    let storage = profile.and_then(|p| profile::read_only(p).ok());
+
+    let check = match storage.map(|s| project::get(&s, &urn)) {
+        Some(Ok(Some(project))) => {
+            Check::pass("working copy", format!("{} ({})", project.name, urn))
+        }
+        Some(Ok(None)) => Check::fail(
+            "working copy",
+            format!(
+                "project {} of the \"rad\" remote is not in local storage",
+                urn
+            ),
+            "hint: fetch the project with `rad sync`, or re-publish it with `rad init`",
+        ),
+        Some(Err(err)) => Check::fail(
+            "working copy",
+            format!("could not load project {}: {}", urn, err),
+            "hint: fetch the project again with `rad sync`",
+        ),
+        None => Check::pass("working copy", urn),
+    };
+    Some(check)
+}
+
+fn seeds(profile: &Profile) -> Vec<Check> {
+    let config = match Config::load(profile) {
+        Ok(config) => config,
+        Err(err) => {
+            return vec![Check::fail(
+                "seeds",
+                format!("invalid configuration: {}", err),
+                "hint: fix the configuration with `rad config edit`",
+            )];
+        }
+    };
+    if config.seeds().next().is_none() {
+        return vec![Check::warn(
+            "seeds",
+            "no seeds are configured",
+            "hint: add a seed with `rad seed add <url>`",
+        )];
+    }
+    config
+        .seeds()
+        .map(|cfg| {
+            let name = cfg
+                .name
+                .clone()
+                .unwrap_or_else(|| cfg.host().unwrap_or_default().to_owned());
+            let now = time::Instant::now();
+
+            match seed::get_seed_id(cfg.api.clone()) {
+                Ok(_) => Check::pass(
+                    "seeds",
+                    format!("{} is reachable ({}ms)", name, now.elapsed().as_millis()),
+                ),
+                Err(err) => Check::fail(
+                    "seeds",
+                    format!("{} is not reachable: {}", name, err),
+                    "hint: check your network, or remove the seed with `rad seed rm`",
+                ),
+            }
+        })
+        .collect()
+}
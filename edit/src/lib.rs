@@ -2,6 +2,7 @@ use std::ffi::OsString;
 use std::str::FromStr;
 
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::exit;
 use radicle_common::keys;
 use radicle_terminal as term;
 
@@ -53,7 +54,7 @@ impl Args for Options {
                     if let Ok(val) = Urn::from_str(&val) {
                         urn = Some(val);
                     } else {
-                        return Err(anyhow!("invalid URN '{}'", val));
+                        return Err(anyhow!(term::tr!("invalid URN '{}'", val)));
                     }
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
@@ -74,8 +75,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .or_else(|| radicle_common::project::cwd().ok().map(|(urn, _)| urn))
         .ok_or_else(|| anyhow!("Couldn't get URN from either command line or cwd"))?;
 
-    let identity = any::get(&storage, &urn)?
-        .ok_or_else(|| anyhow!("No project or person found for this URN"))?;
+    let identity = any::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound("No project or person found for this URN".to_owned())
+    })?;
 
     match identity {
         SomeIdentity::Project(_) => {
@@ -89,7 +91,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     let payload: ProjectPayload = serde_json::from_str(&updated_payload)?;
                     project::update(&storage, &urn, None, payload, None)?;
                 }
-                None => return Err(anyhow!("Operation aborted!")),
+                None => return Err(exit::Error::Aborted("Operation aborted!".to_owned()).into()),
             }
         }
         SomeIdentity::Person(_) => {
@@ -103,11 +105,14 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     let payload: PersonPayload = serde_json::from_str(&updated_payload)?;
                     person::update(&storage, &urn, None, payload, None)?;
                 }
-                None => return Err(anyhow!("Operation aborted!")),
+                None => return Err(exit::Error::Aborted("Operation aborted!".to_owned()).into()),
             }
         }
         _ => {
-            anyhow::bail!("Operation not supported for identity type of {}", urn)
+            anyhow::bail!(term::tr!(
+                "Operation not supported for identity type of {}",
+                urn
+            ))
         }
     };
 
@@ -146,7 +146,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             }
         }
         Operation::Setup(name) => {
-            term::headline(&format!(
+            term::headline(&term::tr!(
                 "Associating local 🌱 identity {} with ENS",
                 term::format::highlight(&id.urn()),
             ));
@@ -166,10 +166,10 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 fn set_ens_payload(name: &str, storage: &Storage) -> anyhow::Result<()> {
     term::info!("Setting ENS name for local 🌱 identity");
 
-    if term::confirm(format!(
+    if term::confirm(term::tr!(
         "Associate local identity with ENS name {}?",
         term::format::highlight(&name)
-    )) {
+    ))? {
         let doc = person::set_ens_payload(
             person::Ens {
                 name: name.to_owned(),
@@ -224,7 +224,7 @@ async fn setup(
         }
         Err(err) => {
             spinner.failed();
-            return Err(anyhow!("error querying seed: {}", err));
+            return Err(anyhow!(term::tr!("error querying seed: {}", err)));
         }
     };
     let address_current = resolver.address(name).await?;
@@ -0,0 +1,106 @@
+//! HTML pages of the static site.
+use radicle_common::cobs::Timestamp;
+use radicle_common::markup::escape;
+
+/// Style of all pages.
+const STYLE: &str = r#"
+body { max-width: 60rem; margin: 2rem auto; padding: 0 1rem; font-family: sans-serif; color: #222; }
+a { color: #2b59c3; text-decoration: none; }
+a:hover { text-decoration: underline; }
+nav a { margin-right: 1rem; }
+table { width: 100%; border-collapse: collapse; }
+td, th { text-align: left; padding: 0.4rem; border-bottom: 1px solid #ddd; }
+pre { background: #f6f6f6; padding: 1rem; overflow-x: auto; white-space: pre-wrap; }
+.meta { color: #777; font-size: 0.9rem; }
+.state { font-size: 0.8rem; padding: 0.1rem 0.4rem; border-radius: 0.3rem; background: #eee; }
+.comment { border-left: 3px solid #ddd; padding-left: 1rem; margin: 1rem 0; }
+.reply { margin-left: 2rem; }
+.add { color: #22863a; }
+.del { color: #cb2431; }
+.hunk { color: #6f42c1; }
+"#;
+
+/// A page of the site.
+pub struct Page {
+    /// Path of the root of the site, relative to the page, eg. `..` for a page in a
+    /// sub-directory.
+    pub root: &'static str,
+    pub project: String,
+    pub title: String,
+    pub body: String,
+}
+
+impl Page {
+    /// Render the page as an HTML document.
+    pub fn render(&self) -> String {
+        format!(
+            "<!DOCTYPE html>\n\
+             <html>\n\
+             <head>\n\
+             <meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title} · {project}</title>\n\
+             <style>{style}</style>\n\
+             </head>\n\
+             <body>\n\
+             <header>\n\
+             <h1><a href=\"{root}/index.html\">{project}</a></h1>\n\
+             <nav><a href=\"{root}/index.html\">Readme</a><a href=\"{root}/issues.html\">Issues</a><a href=\"{root}/patches.html\">Patches</a></nav>\n\
+             </header>\n\
+             <main>\n\
+             {body}\n\
+             </main>\n\
+             <footer class=\"meta\"><p>Exported with <code>rad export site</code>.</p></footer>\n\
+             </body>\n\
+             </html>\n",
+            title = escape(&self.title),
+            project = escape(&self.project),
+            style = STYLE,
+            root = self.root,
+            body = self.body,
+        )
+    }
+}
+
+/// Render text as preformatted HTML.
+pub fn pre(text: &str) -> String {
+    format!("<pre>{}</pre>", escape(text))
+}
+
+/// Render a unified diff, highlighting added, removed and hunk header lines.
+pub fn diff(diff: &str) -> String {
+    let mut html = String::from("<pre>");
+
+    for line in diff.lines() {
+        let class = if line.starts_with("+++") || line.starts_with("---") {
+            None
+        } else if line.starts_with('+') {
+            Some("add")
+        } else if line.starts_with('-') {
+            Some("del")
+        } else if line.starts_with("@@") {
+            Some("hunk")
+        } else {
+            None
+        };
+        match class {
+            Some(class) => html.push_str(&format!(
+                "<span class=\"{}\">{}</span>\n",
+                class,
+                escape(line)
+            )),
+            None => html.push_str(&format!("{}\n", escape(line))),
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+/// Render the author and time of a post.
+pub fn meta(author: &str, timestamp: Timestamp) -> String {
+    format!(
+        "<p class=\"meta\">{} · {}</p>",
+        escape(author),
+        escape(&timestamp.to_rfc2822())
+    )
+}
@@ -0,0 +1,420 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::block::Blocklist;
+use radicle_common::cobs::issue::{Issue, IssueId};
+use radicle_common::cobs::patch::{self, Patch, PatchId};
+use radicle_common::cobs::{Comment, Replies};
+use radicle_common::markup::escape;
+use radicle_common::{cobs, exit, fmt, git, keys, project};
+use radicle_terminal as term;
+
+mod html;
+use html::Page;
+
+pub const HELP: Help = Help {
+    name: "export",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad export site [<urn>] --output <dir>
+
+    Exports the collaboration state of a project from local storage, so that it can
+    be published anywhere.
+
+    The `site` operation generates a static HTML site, that can be browsed locally or
+    served by any web server. It has the following pages:
+
+        index.html              Readme of the project, from its default branch
+        issues.html             List of issues
+        issues/<id>.html        An issue, with its discussion
+        patches.html            List of patches
+        patches/<id>.html       A patch, with its revisions, discussions and diff
+
+    If no URN is given, the project of the current working copy is exported.
+
+Options
+
+    --output, -o <dir>  Directory to write the site to
+    --help              Print help
+"#,
+};
+
+/// Names of the files used as the readme of a project, in order of preference.
+const READMES: &[&str] = &["README.md", "README", "README.txt", "readme.md"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Site,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Site { output: PathBuf },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut urn: Option<Urn> = None;
+        let mut output: Option<PathBuf> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("output") | Short('o') if op == Some(OperationName::Site) => {
+                    output = Some(PathBuf::from(parser.value()?));
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "site" => op = Some(OperationName::Site),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    urn = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match op {
+            Some(OperationName::Site) => Operation::Site {
+                output: output.ok_or_else(|| Error::WithHint {
+                    err: anyhow!("an output directory must be provided"),
+                    hint: "hint: eg. `rad export site --output ./site`",
+                })?,
+            },
+            None => {
+                return Err(Error::WithHint {
+                    err: anyhow!("an operation must be provided"),
+                    hint: "hint: run `rad export --help` to see the supported operations",
+                }
+                .into())
+            }
+        };
+
+        Ok((Options { urn, op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("could not determine project"),
+                hint: "hint: run this command within a project working copy, or specify a URN",
+            })?,
+    };
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| Error::WithHint {
+        err: exit::Error::NotFound(term::tr!("project {} not found in local storage", urn)).into(),
+        hint: "hint: fetch the project with `rad sync --fetch`",
+    })?;
+    let cobs = cobs::store(&profile, &storage)?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let blocklist = Blocklist::load(&profile)?;
+
+    match options.op {
+        Operation::Site { output } => {
+            let site = Site {
+                proj: &proj,
+                monorepo: &monorepo,
+                git_dir: profile.paths().git_dir(),
+                output: &output,
+                blocklist: &blocklist,
+            };
+            let spinner = term::spinner("Exporting site...");
+
+            let mut issues = cobs.issues().all(&urn)?;
+            issues.retain(|(_, i)| !blocklist.contains(&i.author().peer));
+            for (_, issue) in &mut issues {
+                issue.resolve(&storage).ok();
+            }
+            issues.sort_by(|(_, a), (_, b)| b.timestamp().cmp(&a.timestamp()));
+
+            let mut patches = cobs.patches().all(&urn)?;
+            patches.retain(|(_, p)| !blocklist.contains(&p.author.peer));
+            for (_, patch) in &mut patches {
+                patch.resolve(&storage).ok();
+            }
+            patches.sort_by(|(_, a), (_, b)| b.timestamp.cmp(&a.timestamp));
+
+            let head = project::head(&storage, &proj);
+            if let Err(err) = site.write(head, &issues, &patches) {
+                return Err(spinner.error(err));
+            }
+            spinner.message(term::tr!(
+                "Exported site with {} issue(s) and {} patch(es) to {}",
+                issues.len(),
+                patches.len(),
+                term::format::highlight(output.display())
+            ));
+            spinner.finish();
+        }
+    }
+    Ok(())
+}
+
+/// A static site of a project.
+struct Site<'a> {
+    proj: &'a project::Metadata,
+    monorepo: &'a git::Repository,
+    git_dir: &'a Path,
+    output: &'a Path,
+    /// Peers whose issues, patches and comments are left out.
+    blocklist: &'a Blocklist,
+}
+
+impl<'a> Site<'a> {
+    fn write(
+        &self,
+        head: Option<git::Oid>,
+        issues: &[(IssueId, Issue)],
+        patches: &[(PatchId, Patch)],
+    ) -> anyhow::Result<()> {
+        fs::create_dir_all(self.output.join("issues"))?;
+        fs::create_dir_all(self.output.join("patches"))?;
+
+        self.page("index.html", ".", "Readme", self.index(head))?;
+        self.page("issues.html", ".", "Issues", self.issues(issues))?;
+        self.page("patches.html", ".", "Patches", self.patches(patches))?;
+
+        for (id, issue) in issues {
+            self.page(
+                &format!("issues/{}.html", id),
+                "..",
+                issue.title(),
+                self.issue(id, issue),
+            )?;
+        }
+        for (id, patch) in patches {
+            self.page(
+                &format!("patches/{}.html", id),
+                "..",
+                &patch.title,
+                self.patch(id, patch),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn page(
+        &self,
+        path: &str,
+        root: &'static str,
+        title: &str,
+        body: String,
+    ) -> anyhow::Result<()> {
+        let page = Page {
+            root,
+            project: self.proj.name.clone(),
+            title: title.to_owned(),
+            body,
+        };
+        let path = self.output.join(path);
+
+        fs::write(&path, page.render())
+            .with_context(|| term::tr!("could not write {}", path.display()))
+    }
+
+    fn index(&self, head: Option<git::Oid>) -> String {
+        let mut body = format!(
+            "<p>{}</p>\n<p class=\"meta\">{}</p>\n",
+            escape(&self.proj.description),
+            escape(&self.proj.urn.to_string())
+        );
+        match head.and_then(|oid| self.readme(oid)) {
+            Some((name, readme)) => body.push_str(&format!(
+                "<h2>{}</h2>\n{}",
+                escape(&name),
+                html::pre(&readme)
+            )),
+            None => body.push_str("<p><em>No readme.</em></p>"),
+        }
+        body
+    }
+
+    /// Get the readme of the project at the given commit, if any.
+    fn readme(&self, oid: git::Oid) -> Option<(String, String)> {
+        let tree = self.monorepo.find_commit(oid).ok()?.tree().ok()?;
+
+        READMES.iter().find_map(|name| {
+            let entry = tree.get_name(name)?;
+            let blob = entry.to_object(self.monorepo).ok()?.into_blob().ok()?;
+
+            Some((
+                name.to_string(),
+                String::from_utf8_lossy(blob.content()).to_string(),
+            ))
+        })
+    }
+
+    fn issues(&self, issues: &[(IssueId, Issue)]) -> String {
+        if issues.is_empty() {
+            return String::from("<p><em>No issues.</em></p>");
+        }
+        let mut body = String::from(
+            "<table>\n<tr><th>Issue</th><th>State</th><th>Author</th><th>Opened</th></tr>\n",
+        );
+
+        for (id, issue) in issues {
+            body.push_str(&format!(
+                "<tr><td><a href=\"issues/{}.html\">{}</a></td><td><span class=\"state\">{}</span></td><td>{}</td><td>{}</td></tr>\n",
+                id,
+                escape(issue.title()),
+                issue.state_name(),
+                escape(&issue.author().name()),
+                escape(&issue.timestamp().to_rfc2822()),
+            ));
+        }
+        body.push_str("</table>");
+        body
+    }
+
+    fn issue(&self, id: &IssueId, issue: &Issue) -> String {
+        let mut body = format!(
+            "<h2>{} <span class=\"state\">{}</span></h2>\n<p class=\"meta\">Issue {}</p>\n",
+            escape(issue.title()),
+            issue.state_name(),
+            fmt::cob(id)
+        );
+        body.push_str(&comment(&issue.comment));
+        body.push_str(&discussion(&self.blocklist.discussion(issue.comments())));
+        body
+    }
+
+    fn patches(&self, patches: &[(PatchId, Patch)]) -> String {
+        if patches.is_empty() {
+            return String::from("<p><em>No patches.</em></p>");
+        }
+        let mut body = String::from("<table>\n<tr><th>Patch</th><th>State</th><th>Author</th><th>Revisions</th><th>Opened</th></tr>\n");
+
+        for (id, patch) in patches {
+            body.push_str(&format!(
+                "<tr><td><a href=\"patches/{}.html\">{}</a></td><td><span class=\"state\">{}</span></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                id,
+                escape(&patch.title),
+                patch.state_name(),
+                escape(&patch.author.name()),
+                patch.revisions.len(),
+                escape(&patch.timestamp.to_rfc2822()),
+            ));
+        }
+        body.push_str("</table>");
+        body
+    }
+
+    fn patch(&self, id: &PatchId, patch: &Patch) -> String {
+        let mut body = format!(
+            "<h2>{} <span class=\"state\">{}</span></h2>\n<p class=\"meta\">Patch {} to {}</p>\n",
+            escape(&patch.title),
+            patch.state_name(),
+            fmt::cob(id),
+            escape(&self.proj.default_branch.to_string())
+        );
+
+        // Latest revisions first.
+        for (ix, revision) in patch.revisions.iter().enumerate().rev() {
+            body.push_str(&format!(
+                "<h3>Revision R{}</h3>\n<p class=\"meta\">{} · {} · {}..{}</p>\n",
+                ix,
+                escape(&revision.comment.author.name()),
+                escape(&revision.timestamp.to_rfc2822()),
+                fmt::oid(&revision.base),
+                fmt::oid(&revision.oid)
+            ));
+            if !revision.description().is_empty() {
+                body.push_str(&html::pre(revision.description()));
+            }
+            for review in revision
+                .reviews
+                .values()
+                .filter(|r| !self.blocklist.contains(&r.author.peer))
+            {
+                let verdict = match review.verdict {
+                    Some(patch::Verdict::Accept) => "accepted",
+                    Some(patch::Verdict::Reject) => "rejected",
+                    None => "reviewed",
+                };
+                body.push_str(&format!(
+                    "<p><span class=\"state\">{}</span> by {}</p>\n",
+                    verdict,
+                    escape(&review.author.name())
+                ));
+            }
+            body.push_str(&discussion(
+                &self.blocklist.discussion(&revision.discussion),
+            ));
+
+            // Nb. Commits of other peers' patches may not have been fetched.
+            let range = format!("{}..{}", revision.base, revision.oid);
+            match git::git(
+                self.git_dir,
+                ["diff", "--no-color", "--stat", "--patch", &range],
+            ) {
+                Ok(diff) => {
+                    body.push_str("<details>\n<summary>Diff</summary>\n");
+                    body.push_str(&html::diff(&diff));
+                    body.push_str("\n</details>\n");
+                }
+                Err(_) => body.push_str("<p><em>Diff not available.</em></p>\n"),
+            }
+        }
+        body
+    }
+}
+
+/// Render a comment and its replies.
+fn comment<R>(comment: &Comment<R>) -> String {
+    format!(
+        "<div class=\"comment\">\n{}\n{}\n</div>\n",
+        html::meta(&comment.author.name(), comment.timestamp),
+        html::pre(&comment.body)
+    )
+}
+
+/// Render a discussion.
+fn discussion(discussion: &[Comment<Replies>]) -> String {
+    let mut html = String::new();
+
+    for c in discussion {
+        html.push_str(&comment(c));
+        for reply in &c.replies {
+            html.push_str("<div class=\"reply\">\n");
+            html.push_str(&comment(reply));
+            html.push_str("</div>\n");
+        }
+    }
+    html
+}
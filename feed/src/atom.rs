@@ -0,0 +1,93 @@
+//! Atom feeds, as specified in RFC 4287.
+use radicle_common::cobs::Timestamp;
+use radicle_common::markup::escape;
+
+/// A feed.
+#[derive(Debug)]
+pub struct Feed {
+    /// Unique and permanent id of the feed, as an IRI.
+    pub id: String,
+    pub title: String,
+    pub subtitle: String,
+    /// Link to the web page the feed is about, if any.
+    pub link: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+/// An entry of a feed.
+#[derive(Debug)]
+pub struct Entry {
+    /// Unique and permanent id of the entry, as an IRI.
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub updated: Timestamp,
+    pub published: Timestamp,
+    pub link: Option<String>,
+    /// Categories of the entry, eg. the state of a patch.
+    pub categories: Vec<String>,
+    /// Plain text content.
+    pub content: String,
+}
+
+impl Feed {
+    /// Time of the latest update of the feed, ie. of its latest updated entry.
+    pub fn updated(&self) -> Timestamp {
+        self.entries
+            .iter()
+            .map(|e| e.updated)
+            .max()
+            .unwrap_or_else(Timestamp::now)
+    }
+
+    /// Render the feed as an XML document.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+
+        xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        element(&mut xml, 1, "id", &self.id);
+        element(&mut xml, 1, "title", &self.title);
+        element(&mut xml, 1, "subtitle", &self.subtitle);
+        element(&mut xml, 1, "updated", &self.updated().to_rfc3339());
+        element(&mut xml, 1, "generator", "rad feed");
+        if let Some(link) = &self.link {
+            xml.push_str(&format!("  <link href=\"{}\"/>\n", escape(link)));
+        }
+
+        for entry in &self.entries {
+            xml.push_str("  <entry>\n");
+            element(&mut xml, 2, "id", &entry.id);
+            element(&mut xml, 2, "title", &entry.title);
+            xml.push_str("    <author>\n");
+            element(&mut xml, 3, "name", &entry.author);
+            xml.push_str("    </author>\n");
+            element(&mut xml, 2, "published", &entry.published.to_rfc3339());
+            element(&mut xml, 2, "updated", &entry.updated.to_rfc3339());
+            if let Some(link) = &entry.link {
+                xml.push_str(&format!("    <link href=\"{}\"/>\n", escape(link)));
+            }
+            for category in &entry.categories {
+                xml.push_str(&format!("    <category term=\"{}\"/>\n", escape(category)));
+            }
+            xml.push_str(&format!(
+                "    <content type=\"text\">{}</content>\n",
+                escape(&entry.content)
+            ));
+            xml.push_str("  </entry>\n");
+        }
+        xml.push_str("</feed>\n");
+
+        xml
+    }
+}
+
+/// Append an element with text content, indented to the given depth.
+fn element(xml: &mut String, depth: usize, name: &str, text: &str) {
+    xml.push_str(&format!(
+        "{}<{}>{}</{}>\n",
+        "  ".repeat(depth),
+        name,
+        escape(text),
+        name
+    ));
+}
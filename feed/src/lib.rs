@@ -0,0 +1,256 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::patch::Patch;
+use radicle_common::cobs::{Comment, Replies, Timestamp};
+use radicle_common::{cobs, exit, git, keys, project, sync, Url};
+use radicle_terminal as term;
+
+mod atom;
+use atom::{Entry, Feed};
+
+pub const HELP: Help = Help {
+    name: "feed",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad feed [<urn>] [--output <dir>] [--limit <n>] [--link <url>]
+
+    Generates Atom feeds of the activity of a project, for publishing on a static
+    site, so that anyone can follow the project with a feed reader. Three feeds are
+    written to the output directory:
+
+        commits.atom    Latest commits of the default branch
+        patches.atom    Latest created or updated patches
+        issues.atom     Latest created or updated issues
+
+    Entries link to the project on the web, by default on the web gateway of the
+    default seed. Another base URL can be given with `--link`: entries then link to
+    `<url>/commits/<oid>`, `<url>/patches/<id>` and `<url>/issues/<id>`.
+
+    If no URN is given, the project of the current working copy is used.
+
+Options
+
+    --output, -o <dir>  Directory to write the feeds to (default: .)
+    --limit <n>         Maximum number of entries of each feed (default: 50)
+    --link <url>        Base URL of the project on the web
+    --help              Print help
+"#,
+};
+
+/// Default maximum number of entries of each feed.
+pub const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub output: PathBuf,
+    pub limit: usize,
+    pub link: Option<Url>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut output = PathBuf::from(".");
+        let mut limit = DEFAULT_LIMIT;
+        let mut link: Option<Url> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("output") | Short('o') => {
+                    output = PathBuf::from(parser.value()?);
+                }
+                Long("limit") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    limit = val
+                        .parse()
+                        .map_err(|_| anyhow!(term::tr!("invalid limit '{}'", val)))?;
+                }
+                Long("link") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    link = Some(
+                        Url::parse(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URL '{}'", val)))?,
+                    );
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    urn = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                urn,
+                output,
+                limit,
+                link,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("could not determine project"),
+                hint: "hint: run this command within a project working copy, or specify a URN",
+            })?,
+    };
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| Error::WithHint {
+        err: exit::Error::NotFound(term::tr!("project {} not found in local storage", urn)).into(),
+        hint: "hint: fetch the project with `rad sync --fetch`",
+    })?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let cobs = cobs::store(&profile, &storage)?;
+
+    let link = match options.link {
+        Some(url) => Some(url.as_str().trim_end_matches('/').to_owned()),
+        None => sync::gateway_url(&profile, &urn),
+    };
+    let link_to = |path: String| link.as_ref().map(|l| format!("{}/{}", l, path));
+
+    let mut commits = Vec::new();
+    if let Some(head) = project::head(&storage, &proj) {
+        let mut walk = monorepo.revwalk()?;
+        walk.push(head)?;
+
+        for oid in walk.take(options.limit) {
+            let commit = monorepo.find_commit(oid?)?;
+            let oid = commit.id();
+
+            commits.push(Entry {
+                id: format!("{}/commits/{}", urn, oid),
+                title: commit.summary().unwrap_or_default().to_owned(),
+                author: commit.author().name().unwrap_or_default().to_owned(),
+                published: Timestamp::new(commit.author().when().seconds().max(0) as u64),
+                updated: Timestamp::new(commit.time().seconds().max(0) as u64),
+                link: link_to(format!("commits/{}", oid)),
+                categories: vec![],
+                content: commit.message().unwrap_or_default().to_owned(),
+            });
+        }
+    }
+
+    let mut patches = Vec::new();
+    for (id, mut patch) in cobs.patches().all(&urn)? {
+        patch.author.resolve(&storage).ok();
+
+        patches.push(Entry {
+            id: format!("{}/patches/{}", urn, id),
+            title: patch.title.clone(),
+            author: patch.author.name(),
+            published: patch.timestamp,
+            updated: patch_updated(&patch),
+            link: link_to(format!("patches/{}", id)),
+            categories: std::iter::once(patch.state_name().to_owned())
+                .chain(patch.labels.iter().map(|l| l.name().to_owned()))
+                .collect(),
+            content: patch.description().to_owned(),
+        });
+    }
+
+    let mut issues = Vec::new();
+    for (id, mut issue) in cobs.issues().all(&urn)? {
+        issue.author.resolve(&storage).ok();
+
+        issues.push(Entry {
+            id: format!("{}/issues/{}", urn, id),
+            title: issue.title().to_owned(),
+            author: issue.author().name(),
+            published: issue.timestamp(),
+            updated: updated(issue.timestamp(), issue.comments()),
+            link: link_to(format!("issues/{}", id)),
+            categories: std::iter::once(issue.state_name().to_owned())
+                .chain(issue.labels().iter().map(|l| l.name().to_owned()))
+                .collect(),
+            content: issue.description().to_owned(),
+        });
+    }
+
+    fs::create_dir_all(&options.output)?;
+
+    for (name, title, mut entries) in [
+        ("commits.atom", "Commits", commits),
+        ("patches.atom", "Patches", patches),
+        ("issues.atom", "Issues", issues),
+    ] {
+        entries.sort_by(|a, b| b.updated.cmp(&a.updated));
+        entries.truncate(options.limit);
+
+        let count = entries.len();
+        let feed = Feed {
+            id: format!("{}/{}", urn, name.trim_end_matches(".atom")),
+            title: format!("{}: {}", proj.name, title),
+            subtitle: proj.description.clone(),
+            link: link.clone(),
+            entries,
+        };
+        let path = options.output.join(name);
+
+        fs::write(&path, feed.to_xml())
+            .with_context(|| term::tr!("could not write feed to {}", path.display()))?;
+
+        term::success!(
+            "Feed of {} with {} entries written to {}",
+            term::format::highlight(title.to_lowercase()),
+            count,
+            term::format::highlight(path.display())
+        );
+    }
+
+    Ok(())
+}
+
+/// Get the time of the latest activity of a patch: a revision or a comment.
+fn patch_updated(patch: &Patch) -> Timestamp {
+    patch
+        .revisions
+        .iter()
+        .map(|r| updated(r.timestamp, &r.discussion))
+        .max()
+        .unwrap_or(patch.timestamp)
+}
+
+/// Get the time of the latest comment or reply of a discussion, if later than the
+/// given time.
+fn updated(since: Timestamp, discussion: &[Comment<Replies>]) -> Timestamp {
+    discussion
+        .iter()
+        .flat_map(|c| std::iter::once(c.timestamp).chain(c.replies.iter().map(|r| r.timestamp)))
+        .fold(since, std::cmp::max)
+}
@@ -0,0 +1,191 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::refs::Refs;
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::sync::{self, Mode};
+use radicle_common::{exit, fmt, git, identity, keys, person, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "fork",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad fork [<urn>] [--peer <peer-id>] [--no-sync]
+
+    Creates your own view of a project you cloned, so that you can contribute to it.
+    Your copy of the project's default branch is created from the branch of a
+    delegate, your identity is linked to the project, and your refs are signed, so
+    that other peers can fetch your fork from your peer id. The fork is then synced
+    to your seeds, unless `--no-sync` is used.
+
+    If the project has more than one delegate, the peer to fork from must be given
+    with `--peer`. If no URN is given, the project of the current working copy is used.
+
+    Once forked, push your changes with `rad push`.
+
+Options
+
+    --peer <peer-id>    Fork the default branch of the given peer
+    --no-sync           Don't sync the fork to seeds
+    --help              Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub peer: Option<PeerId>,
+    pub sync: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut peer: Option<PeerId> = None;
+        let mut sync = sync::is_auto();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("peer") if peer.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    peer = Some(
+                        PeerId::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid peer id '{}'", val)))?,
+                    );
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    urn = Some(Urn::from_str(&val).context(format!("invalid URN '{}'", val))?);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options { urn, peer, sync }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("could not determine project"),
+                hint: "hint: run this command within a project working copy, or specify a URN",
+            })?,
+    };
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| Error::WithHint {
+        err: exit::Error::NotFound(term::tr!("project {} not found in local storage", urn)).into(),
+        hint: "hint: clone the project first with `rad clone`",
+    })?;
+    let branch = proj.default_branch.to_string();
+
+    term::headline(&term::tr!(
+        "Forking 🌱 {} ({})",
+        term::format::highlight(&urn),
+        proj.name
+    ));
+
+    if project::get_local_head(&storage, &urn, &branch)?.is_some() {
+        term::success!(
+            "Your {} branch already exists",
+            term::format::highlight(&branch)
+        );
+    } else {
+        let peer = match options.peer {
+            Some(peer) => peer,
+            None => match proj.remotes.iter().collect::<Vec<_>>()[..] {
+                [] => anyhow::bail!("project has no delegates to fork from"),
+                [peer] => *peer,
+                [_, _, ..] => {
+                    return Err(Error::WithHint {
+                        err: anyhow!("project has more than one delegate"),
+                        hint: "hint: specify the peer to fork from with `--peer`",
+                    }
+                    .into())
+                }
+            },
+        };
+        let head = project::get_remote_head(&storage, &urn, &peer, &branch)
+            .ok()
+            .flatten()
+            .ok_or_else(|| Error::WithHint {
+                err: anyhow!(term::tr!(
+                    "the {} branch of {} was not found in local storage",
+                    branch,
+                    fmt::peer(&peer)
+                )),
+                hint: "hint: track the peer with `rad track`, and fetch its refs with `rad sync --fetch-peers`",
+            })?;
+
+        // Nb. the git2 crate doesn't handle namespaces properly, so we specify it manually.
+        let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+        monorepo.reference(
+            &format!("refs/namespaces/{}/refs/heads/{}", urn.encode_id(), branch),
+            head,
+            false,
+            &format!("rad: fork from {}", peer),
+        )?;
+
+        term::success!(
+            "Branch {} created from {} at {}",
+            term::format::highlight(&branch),
+            term::format::tertiary(fmt::peer(&peer)),
+            term::format::secondary(fmt::oid(&head))
+        );
+    }
+
+    let spinner = term::spinner("Signing your refs...");
+    person::local(&storage)?
+        .link(&storage, &urn)
+        .context("could not link your identity to the project")?;
+    Refs::update(&storage, &urn)?;
+    spinner.finish();
+
+    term::success!(
+        "Fork published under your peer id {}",
+        term::format::tertiary(storage.peer_id())
+    );
+
+    if options.sync {
+        rad_sync::run(
+            rad_sync::Options {
+                origin: Some(identity::Origin::from_urn(urn)),
+                mode: Mode::Push,
+                ..rad_sync::Options::default()
+            },
+            profile,
+        )?;
+    } else {
+        term::tip!("To publish your fork to the network, run `rad sync`.");
+    }
+    term::tip!("To get a working copy of your fork, run `rad checkout`.");
+
+    Ok(())
+}
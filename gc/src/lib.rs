@@ -0,0 +1,271 @@
+use std::ffi::OsString;
+use std::io::Write as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::git;
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "gc",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad gc [--dry-run] [--now] [--aggressive]
+
+    Runs maintenance on local storage, which otherwise only ever grows: objects are
+    repacked, objects that are not reachable from the refs of any project or identity
+    are pruned, and the commit-graph is rewritten, which speeds up history traversal,
+    eg. when computing how far patches are ahead of, or behind, their target. The
+    commit-graph is also updated after each `rad sync` that fetches.
+
+    Unreachable objects are only pruned once they are two weeks old, so that objects
+    being written by a concurrent sync are never pruned. With `--now`, they are pruned
+    regardless of their age; only use it when no other rad command is running.
+
+    With `--dry-run`, nothing is changed, and the space taken by unreachable objects,
+    which can be reclaimed, is reported instead.
+
+Options
+
+    --dry-run       Report reclaimable space, without changing anything
+    --now           Prune unreachable objects regardless of their age
+    --aggressive    Optimize the repository more aggressively, at the cost of time
+    --help          Print help
+"#,
+};
+
+/// Age after which unreachable objects are pruned, by default.
+const PRUNE_EXPIRE: &str = "2.weeks.ago";
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub dry_run: bool,
+    pub now: bool,
+    pub aggressive: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut options = Options::default();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("dry-run") => {
+                    options.dry_run = true;
+                }
+                Long("now") => {
+                    options.now = true;
+                }
+                Long("aggressive") => {
+                    options.aggressive = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((options, vec![]))
+    }
+}
+
+/// Objects of a repository, as reported by `git count-objects`. Sizes are in KiB.
+#[derive(Debug, Default)]
+struct Objects {
+    loose: u64,
+    loose_size: u64,
+    packed: u64,
+    packs: u64,
+    packed_size: u64,
+    garbage_size: u64,
+}
+
+impl Objects {
+    fn load(repo: &Path) -> anyhow::Result<Self> {
+        let output = git::git(repo, ["count-objects", "-v"])?;
+        let mut objects = Self::default();
+
+        for line in output.lines() {
+            if let Some((key, val)) = line.split_once(": ") {
+                let val = val.trim().parse().unwrap_or_default();
+
+                match key {
+                    "count" => objects.loose = val,
+                    "size" => objects.loose_size = val,
+                    "in-pack" => objects.packed = val,
+                    "packs" => objects.packs = val,
+                    "size-pack" => objects.packed_size = val,
+                    "size-garbage" => objects.garbage_size = val,
+                    _ => {}
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Total size of the objects, in KiB.
+    fn size(&self) -> u64 {
+        self.loose_size + self.packed_size + self.garbage_size
+    }
+
+    fn print(&self) {
+        term::info!(
+            "{} {} loose object(s) ({}), {} packed object(s) in {} pack(s) ({})",
+            term::format::dim("Objects"),
+            self.loose,
+            format_size(self.loose_size * 1024),
+            self.packed,
+            self.packs,
+            format_size(self.packed_size * 1024),
+        );
+        if self.garbage_size > 0 {
+            term::info!(
+                "{} {}",
+                term::format::dim("Garbage"),
+                format_size(self.garbage_size * 1024)
+            );
+        }
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let monorepo = profile.paths().git_dir();
+    let before = Objects::load(monorepo)?;
+
+    term::info!(
+        "{} {}",
+        term::format::dim("Storage"),
+        term::format::tertiary(monorepo.display())
+    );
+    before.print();
+    term::blank();
+
+    if options.dry_run {
+        let spinner = term::spinner("Looking for unreachable objects...");
+        let (count, size) = match unreachable(monorepo) {
+            Ok(result) => result,
+            Err(err) => return Err(spinner.error(err)),
+        };
+        spinner.finish();
+
+        if count == 0 && before.garbage_size == 0 {
+            term::info!("There is no space to reclaim.");
+        } else {
+            term::info!(
+                "{} unreachable object(s), taking up to {}, can be pruned",
+                count,
+                term::format::highlight(format_size(size + before.garbage_size * 1024))
+            );
+            term::tip!("To reclaim this space, run `rad gc`.");
+        }
+        return Ok(());
+    }
+
+    let mut args = vec!["gc", "--quiet"];
+    let prune = format!("--prune={}", if options.now { "now" } else { PRUNE_EXPIRE });
+    args.push(&prune);
+    if options.aggressive {
+        args.push("--aggressive");
+    }
+
+    let spinner = term::spinner("Repacking, and pruning unreachable objects...");
+    if let Err(err) = git::git(monorepo, args) {
+        return Err(spinner.error(err));
+    }
+    spinner.finish();
+
+    let spinner = term::spinner("Writing commit-graph...");
+    if let Err(err) = git::write_commit_graph(monorepo) {
+        return Err(spinner.error(err));
+    }
+    spinner.finish();
+
+    let after = Objects::load(monorepo)?;
+    term::blank();
+    after.print();
+    term::success!(
+        "Storage reduced from {} to {}, {} reclaimed",
+        format_size(before.size() * 1024),
+        format_size(after.size() * 1024),
+        term::format::highlight(format_size(
+            before.size().saturating_sub(after.size()) * 1024
+        ))
+    );
+
+    Ok(())
+}
+
+/// Find the objects that aren't reachable from any ref. Returns their number, and the
+/// space they take on disk, in bytes.
+fn unreachable(repo: &Path) -> anyhow::Result<(usize, u64)> {
+    let output = git::git(
+        repo,
+        ["fsck", "--unreachable", "--no-reflogs", "--no-progress"],
+    )?;
+    let oids = output
+        .lines()
+        .filter_map(|l| l.strip_prefix("unreachable "))
+        .filter_map(|l| l.split_whitespace().nth(1))
+        .map(|oid| format!("{}\n", oid))
+        .collect::<Vec<_>>();
+
+    if oids.is_empty() {
+        return Ok((0, 0));
+    }
+    let count = oids.len();
+
+    let mut child = Command::new("git")
+        .current_dir(repo)
+        .args(["cat-file", "--batch-check=%(objectsize:disk)"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("couldn't write to `git cat-file`"))?;
+    // Nb. Input is written from a separate thread, so that output can be read as it is
+    // produced, without filling up the pipe.
+    let writer = thread::spawn(move || stdin.write_all(oids.concat().as_bytes()));
+    let output = child.wait_with_output()?;
+
+    writer
+        .join()
+        .map_err(|_| anyhow!("couldn't write to `git cat-file`"))??;
+
+    let size = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.trim().parse::<u64>().ok())
+        .sum();
+
+    Ok((count, size))
+}
+
+/// Format a size in bytes, eg. `1.5 MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.;
+    let mut unit = 0;
+
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,105 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{git, keys, profile};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "git",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad git configure-signing [--global]
+
+    `configure-signing` configures git to sign commits and tags with your radicle key,
+    using SSH signatures. Git is set up to sign via `rad-ssh-keygen`, which signs with
+    the key in ssh-agent, or else unlocks the key with your passphrase, like other
+    radicle commands. Signatures made with other keys are passed on to `ssh-keygen`.
+
+    Unless `--global` is used, only the repository in the current directory is
+    configured. To check the signature of a commit, run `rad verify <commit>`.
+
+Options
+
+    --global    Configure the global git config
+    --help      Print help
+"#,
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    ConfigureSigning { global: bool },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<String> = None;
+        let mut global = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("global") => {
+                    global = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => {
+                    op = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.as_deref() {
+            Some("configure-signing") => Operation::ConfigureSigning { global },
+            Some(unknown) => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+            None => return Err(Error::Usage.into()),
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let peer_id = storage.peer_id();
+
+    match options.op {
+        Operation::ConfigureSigning { global } => {
+            if global {
+                git::configure_rad_signing(None, peer_id)?;
+                term::success!("Signing with your radicle key configured globally");
+            } else {
+                let repo = git::repository()?;
+                let path = repo
+                    .workdir()
+                    .ok_or_else(|| anyhow!("the repository has no working directory"))?;
+
+                git::configure_rad_signing(Some(path), peer_id)?;
+                term::success!("Signing with your radicle key configured for this repository");
+            }
+            term::info!(
+                "{} {}",
+                term::format::dim("Key"),
+                term::format::tertiary(keys::to_ssh_fingerprint(peer_id)?)
+            );
+            term::tip!("To check the signature of a commit, run `rad verify <commit>`.");
+        }
+    }
+
+    Ok(())
+}
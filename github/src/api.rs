@@ -0,0 +1,201 @@
+//! Minimal client of the GitHub REST API, covering pull requests and their comments.
+use anyhow::anyhow;
+
+use radicle_common::json;
+
+/// Base URL of the GitHub REST API.
+pub const API_URL: &str = "https://api.github.com";
+/// Base URL of GitHub repositories, for git.
+pub const GIT_URL: &str = "https://github.com";
+
+/// Number of items requested per page, which is the maximum allowed.
+const PER_PAGE: usize = 100;
+
+/// A pull request.
+#[derive(Debug, Clone)]
+pub struct PullRequest {
+    pub number: u64,
+    pub open: bool,
+    pub body: String,
+    pub url: String,
+    /// Name of the branch the pull request merges.
+    pub head: String,
+    /// Repository of that branch, as `<owner>/<name>`, eg. a fork. `None` if the
+    /// repository was deleted.
+    pub head_repo: Option<String>,
+}
+
+impl PullRequest {
+    fn from_json(val: &json::Value) -> anyhow::Result<Self> {
+        Ok(Self {
+            number: val["number"]
+                .as_u64()
+                .ok_or_else(|| anyhow!("missing 'number' in pull request"))?,
+            open: val["state"].as_str() == Some("open"),
+            body: val["body"].as_str().unwrap_or_default().to_owned(),
+            url: val["html_url"].as_str().unwrap_or_default().to_owned(),
+            head: val["head"]["ref"].as_str().unwrap_or_default().to_owned(),
+            head_repo: val["head"]["repo"]["full_name"]
+                .as_str()
+                .map(|s| s.to_owned()),
+        })
+    }
+}
+
+/// A comment on a pull request, or a review of it.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    /// Login of the author.
+    pub author: String,
+    pub body: String,
+    /// Link to the comment, which also identifies it.
+    pub url: String,
+    /// State of the review, eg. `APPROVED`, if the comment is a review.
+    pub review: Option<String>,
+}
+
+impl Comment {
+    fn from_json(val: &json::Value, review: bool) -> Self {
+        Self {
+            author: val["user"]["login"].as_str().unwrap_or("ghost").to_owned(),
+            body: val["body"].as_str().unwrap_or_default().to_owned(),
+            url: val["html_url"].as_str().unwrap_or_default().to_owned(),
+            review: if review {
+                val["state"].as_str().map(|s| s.to_owned())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Client of the API, for a single repository.
+pub struct Client {
+    agent: ureq::Agent,
+    token: String,
+    /// Repository, as `<owner>/<name>`.
+    repo: String,
+}
+
+impl Client {
+    pub fn new(token: &str, repo: &str) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            token: token.to_owned(),
+            repo: repo.to_owned(),
+        }
+    }
+
+    /// Check whether a pull request merges a branch of the repository itself, rather
+    /// than of a fork.
+    pub fn is_own(&self, pr: &PullRequest) -> bool {
+        pr.head_repo
+            .as_deref()
+            .map_or(false, |repo| repo.eq_ignore_ascii_case(&self.repo))
+    }
+
+    /// Git URL of the repository.
+    pub fn git_url(&self) -> String {
+        format!("{}/{}.git", GIT_URL, self.repo)
+    }
+
+    /// Get all pull requests of the repository, open or closed.
+    pub fn pulls(&self) -> anyhow::Result<Vec<PullRequest>> {
+        self.get_all("pulls?state=all")?
+            .iter()
+            .map(PullRequest::from_json)
+            .collect()
+    }
+
+    /// Open a pull request.
+    pub fn create_pull(
+        &self,
+        title: &str,
+        head: &str,
+        base: &str,
+        body: &str,
+    ) -> anyhow::Result<PullRequest> {
+        let val = self
+            .request("POST", "pulls")
+            .send_json(json::json!({
+                "title": title,
+                "head": head,
+                "base": base,
+                "body": body,
+            }))
+            .map_err(error)?
+            .into_json::<json::Value>()?;
+
+        PullRequest::from_json(&val)
+    }
+
+    /// Close a pull request, without merging it.
+    pub fn close_pull(&self, number: u64) -> anyhow::Result<()> {
+        self.request("PATCH", &format!("pulls/{}", number))
+            .send_json(json::json!({ "state": "closed" }))
+            .map_err(error)?;
+
+        Ok(())
+    }
+
+    /// Get the comments and reviews of a pull request, comments first.
+    pub fn comments(&self, number: u64) -> anyhow::Result<Vec<Comment>> {
+        let comments = self.get_all(&format!("issues/{}/comments?", number))?;
+        let reviews = self.get_all(&format!("pulls/{}/reviews?", number))?;
+
+        Ok(comments
+            .iter()
+            .map(|c| Comment::from_json(c, false))
+            .chain(reviews.iter().map(|r| Comment::from_json(r, true)))
+            .collect())
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.agent
+            .request(method, &format!("{}/repos/{}/{}", API_URL, self.repo, path))
+            .set("Accept", "application/vnd.github+json")
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .set("User-Agent", "radicle-cli")
+    }
+
+    /// Get all the items of a listing, page by page. The path must include a query.
+    fn get_all(&self, path: &str) -> anyhow::Result<Vec<json::Value>> {
+        let mut items = Vec::new();
+
+        for page in 1.. {
+            let val = self
+                .request(
+                    "GET",
+                    &format!("{}&per_page={}&page={}", path, PER_PAGE, page),
+                )
+                .call()
+                .map_err(error)?
+                .into_json::<json::Value>()?;
+            let batch = val
+                .as_array()
+                .ok_or_else(|| anyhow!("unexpected response from GitHub: expected a list"))?;
+
+            items.extend(batch.iter().cloned());
+            if batch.len() < PER_PAGE {
+                break;
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// Turn a request error into an error with the message returned by GitHub, if any.
+fn error(err: ureq::Error) -> anyhow::Error {
+    match err {
+        ureq::Error::Status(status, response) => {
+            let message = response
+                .into_json::<json::Value>()
+                .ok()
+                .and_then(|v| v["message"].as_str().map(|m| m.to_owned()))
+                .unwrap_or_default();
+
+            anyhow!("GitHub request failed with status {}: {}", status, message)
+        }
+        err => anyhow!("GitHub request failed: {}", err),
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::process::Command;
+
+use anyhow::anyhow;
+
+use librad::git::Urn;
+use librad::profile::Profile;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::patch::{Patch, PatchId};
+use radicle_common::credentials::{Credential, Credentials};
+use radicle_common::{cobs, exit, fmt, keys, offline, project};
+use radicle_terminal as term;
+
+mod api;
+
+pub const HELP: Help = Help {
+    name: "github",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad github login [--token <token>]
+    rad github logout
+    rad github sync [--repo <owner>/<name>] [--dry-run]
+
+    Bridges the patches of a project with the pull requests of its GitHub mirror, so
+    that review discussions aren't split while a project moves to radicle.
+
+    `sync` opens a pull request for every proposed patch that doesn't have one yet,
+    and keeps its branch up to date with the latest revision of the patch. Pull
+    requests of patches that are merged or archived are closed. The comments and
+    reviews of these pull requests are then imported back into the patches, as
+    comments linking to their GitHub counterpart. Run `rad sync` afterwards to publish
+    the imported comments.
+
+    Patch branches are pushed to the GitHub repository as `radicle/patches/<id>`, and
+    pull requests refer to their patch with a `Radicle-Patch: <id>` line. Only the pull
+    requests of these branches, in the repository itself, are bridged: pull requests
+    from forks are ignored, whatever their description says.
+
+    The GitHub repository is given with `--repo`, and remembered in the working copy
+    configuration. GitHub requests are authenticated with the token stored by
+    `rad github login`, or the `GITHUB_TOKEN` environment variable. The token needs
+    write access to the repository's contents and pull requests.
+
+Options
+
+    --token <token>         Access token to log in with (default: prompt)
+    --repo <owner>/<name>   GitHub repository of the project
+    --dry-run               Show what would be done, without changing anything
+    --help                  Print help
+"#,
+};
+
+/// Git config key of the GitHub repository of a project, in its working copy.
+pub const CONFIG_REPO_KEY: &str = "rad.github.repo";
+/// Environment variable holding a GitHub token, which takes precedence over the stored one.
+pub const TOKEN_ENV: &str = "GITHUB_TOKEN";
+/// Prefix of the line referring to a patch, in a pull request description.
+pub const PATCH_TRAILER: &str = "Radicle-Patch:";
+/// Prefix of the GitHub branches of patches.
+pub const BRANCH_PREFIX: &str = "radicle/patches/";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Login,
+    Logout,
+    Sync,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Login { token: Option<String> },
+    Logout,
+    Sync { repo: Option<String>, dry_run: bool },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut token: Option<String> = None;
+        let mut repo: Option<String> = None;
+        let mut dry_run = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("token") if op == Some(OperationName::Login) => {
+                    token = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("repo") if op == Some(OperationName::Sync) => {
+                    let val = parser.value()?.to_string_lossy().to_string();
+
+                    if val.split('/').filter(|s| !s.is_empty()).count() != 2 {
+                        anyhow::bail!(term::tr!(
+                            "invalid repository '{}': expected <owner>/<name>",
+                            val
+                        ));
+                    }
+                    repo = Some(val);
+                }
+                Long("dry-run") if op == Some(OperationName::Sync) => {
+                    dry_run = true;
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "login" => op = Some(OperationName::Login),
+                    "logout" => op = Some(OperationName::Logout),
+                    "sync" => op = Some(OperationName::Sync),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.ok_or(Error::Usage)? {
+            OperationName::Login => Operation::Login { token },
+            OperationName::Logout => Operation::Logout,
+            OperationName::Sync => Operation::Sync { repo, dry_run },
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+/// Outcome of a sync.
+#[derive(Debug, Default)]
+struct Summary {
+    opened: usize,
+    updated: usize,
+    closed: usize,
+    imported: usize,
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+
+    match options.op {
+        Operation::Login { token } => {
+            let token = match token {
+                Some(token) => token,
+                None => term::secret_input_with_prompt("Token")
+                    .unsecure()
+                    .to_owned(),
+            };
+            if token.trim().is_empty() {
+                anyhow::bail!("the token must not be empty");
+            }
+            let mut credentials = Credentials::load(&profile)?;
+
+            credentials.github = Some(Credential {
+                token: token.trim().to_owned(),
+            });
+            credentials.write(&profile)?;
+
+            term::success!("Logged in to GitHub");
+        }
+        Operation::Logout => {
+            let mut credentials = Credentials::load(&profile)?;
+
+            if credentials.github.take().is_none() {
+                anyhow::bail!("not logged in to GitHub");
+            }
+            credentials.write(&profile)?;
+
+            term::success!("Logged out of GitHub");
+        }
+        Operation::Sync { repo, dry_run } => {
+            sync(&profile, repo, dry_run)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sync(profile: &Profile, repo: Option<String>, dry_run: bool) -> anyhow::Result<()> {
+    offline::check("syncing with GitHub")?;
+
+    let token = token(profile)?;
+    let (urn, working) = project::cwd().map_err(|_| Error::WithHint {
+        err: anyhow!("this command must be run within a project working copy"),
+        hint: "hint: checkout a project with `rad checkout`",
+    })?;
+    let repo = match repo {
+        Some(repo) => {
+            if !dry_run {
+                working.config()?.set_str(CONFIG_REPO_KEY, &repo)?;
+            }
+            repo
+        }
+        None => working
+            .config()?
+            .get_string(CONFIG_REPO_KEY)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("the GitHub repository of the project is not known"),
+                hint: "hint: give it with `--repo <owner>/<name>`",
+            })?,
+    };
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found in local storage", urn))
+    })?;
+    let cobs = cobs::store(profile, &storage)?;
+    let client = api::Client::new(&token, &repo);
+
+    let spinner = term::spinner(term::tr!(
+        "Fetching pull requests of {}...",
+        term::format::highlight(&repo)
+    ));
+    let pulls = match client.pulls() {
+        Ok(pulls) => pulls,
+        Err(err) => return Err(spinner.error(err)),
+    };
+    spinner.finish();
+
+    let mut bridged: HashMap<PatchId, api::PullRequest> = HashMap::new();
+    for pr in pulls.into_iter().filter(|pr| client.is_own(pr)) {
+        let id = match patch_id(&pr.head) {
+            Some(id) => id,
+            None => continue,
+        };
+        // A patch's branch may have had several pull requests, eg. if one was closed
+        // by hand; the open one is bridged.
+        if bridged
+            .get(&id)
+            .map_or(true, |other| pr.open && !other.open)
+        {
+            bridged.insert(id, pr);
+        }
+    }
+    let mut summary = Summary::default();
+    let base = proj.default_branch.to_string();
+
+    for (id, mut patch) in cobs.patches().all(&urn)? {
+        patch.author.resolve(&storage).ok();
+
+        match bridged.get(&id) {
+            Some(pr) if pr.open && !is_open(&patch) => {
+                term::info!(
+                    "Closing {} of {} patch {}",
+                    term::format::highlight(&pr.url),
+                    status(&patch),
+                    term::format::tertiary(fmt::cob(&id))
+                );
+                if !dry_run {
+                    client.close_pull(pr.number)?;
+                }
+                summary.closed += 1;
+            }
+            Some(pr) if pr.open => {
+                if !dry_run && push(profile, &client, &token, &id, &patch)? {
+                    term::info!(
+                        "Updated {} to revision {} of patch {}",
+                        term::format::highlight(&pr.url),
+                        patch.version(),
+                        term::format::tertiary(fmt::cob(&id))
+                    );
+                    summary.updated += 1;
+                }
+            }
+            Some(_) => {}
+            None if is_open(&patch) => {
+                if dry_run {
+                    term::info!(
+                        "Would open a pull request for patch {} {}",
+                        term::format::tertiary(fmt::cob(&id)),
+                        term::format::italic(&patch.title)
+                    );
+                } else {
+                    push(profile, &client, &token, &id, &patch)?;
+
+                    let pr = client.create_pull(
+                        &patch.title,
+                        &branch(&id),
+                        &base,
+                        &description(&id, &patch),
+                    )?;
+                    term::info!(
+                        "Opened {} for patch {} {}",
+                        term::format::highlight(&pr.url),
+                        term::format::tertiary(fmt::cob(&id)),
+                        term::format::italic(&patch.title)
+                    );
+                    bridged.insert(id, pr);
+                }
+                summary.opened += 1;
+            }
+            None => {}
+        }
+
+        if let Some(pr) = bridged.get(&id) {
+            summary.imported += import(&cobs, &client, &urn, &id, &patch, pr, dry_run)?;
+        }
+    }
+
+    term::blank();
+    term::success!(
+        "{} pull request(s) {}opened, {} updated, {} closed, and {} comment(s) imported",
+        summary.opened,
+        if dry_run { "would be " } else { "" },
+        summary.updated,
+        summary.closed,
+        summary.imported
+    );
+    if summary.imported > 0 && !dry_run {
+        term::tip!("To publish the imported comments, run `rad sync`.");
+    }
+    Ok(())
+}
+
+/// Get the GitHub token, from the environment, or from the stored credentials.
+fn token(profile: &Profile) -> anyhow::Result<String> {
+    if let Ok(token) = env::var(TOKEN_ENV) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_owned());
+        }
+    }
+    Credentials::load(profile)?
+        .github
+        .map(|c| c.token)
+        .ok_or_else(|| {
+            Error::WithHint {
+                err: anyhow!("not logged in to GitHub"),
+                hint: "hint: log in with `rad github login`, or set `GITHUB_TOKEN`",
+            }
+            .into()
+        })
+}
+
+/// Whether a patch is open for review, ie. proposed, and not merged yet.
+fn is_open(patch: &Patch) -> bool {
+    patch.is_proposed() && patch.latest().1.merges.is_empty()
+}
+
+/// Status of a patch that isn't open, for display.
+fn status(patch: &Patch) -> &'static str {
+    if !patch.latest().1.merges.is_empty() {
+        "merged"
+    } else if patch.is_archived() {
+        "archived"
+    } else {
+        "draft"
+    }
+}
+
+/// Name of the GitHub branch of a patch.
+fn branch(id: &PatchId) -> String {
+    format!("{}{}", BRANCH_PREFIX, id)
+}
+
+/// Get the patch of a pull request, from the name of its branch, see [`branch`].
+fn patch_id(head: &str) -> Option<PatchId> {
+    head.strip_prefix(BRANCH_PREFIX)?.parse().ok()
+}
+
+/// Description of the pull request of a patch.
+fn description(id: &PatchId, patch: &Patch) -> String {
+    let mut body = patch.description().trim().to_owned();
+
+    if !body.is_empty() {
+        body.push_str("\n\n---\n");
+    }
+    body.push_str(&format!(
+        "Proposed on radicle by {}.\n\n{} {}\n",
+        patch.author.name(),
+        PATCH_TRAILER,
+        id
+    ));
+    body
+}
+
+/// Push the latest revision of a patch to its GitHub branch. Returns whether the branch
+/// was changed.
+fn push(
+    profile: &Profile,
+    client: &api::Client,
+    token: &str,
+    id: &PatchId,
+    patch: &Patch,
+) -> anyhow::Result<bool> {
+    let refspec = format!("+{}:refs/heads/{}", patch.head(), branch(id));
+    // Nb. The revision is pushed from storage, where the commits of all peers are.
+    // The token is passed through the environment, so that it doesn't show up in
+    // the process list.
+    let output = Command::new("git")
+        .current_dir(profile.paths().git_dir())
+        .env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+        .env(
+            "GIT_CONFIG_VALUE_0",
+            format!(
+                "Authorization: Basic {}",
+                base64::encode(format!("x-access-token:{}", token))
+            ),
+        )
+        .args(["push", "--porcelain", &client.git_url(), &refspec])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!(term::tr!(
+            "failed to push patch {} to GitHub: {}",
+            fmt::cob(id),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // With `--porcelain`, up-to-date refs are flagged with `=`.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let changed = stdout
+        .lines()
+        .any(|l| l.starts_with(|c: char| c != '=') && l.contains("refs/heads/"));
+
+    Ok(changed)
+}
+
+/// Import the comments and reviews of a pull request into its patch, as comments on the
+/// latest revision. Comments that were already imported are skipped. Returns the number
+/// of comments imported.
+fn import(
+    cobs: &cobs::Store,
+    client: &api::Client,
+    urn: &Urn,
+    id: &PatchId,
+    patch: &Patch,
+    pr: &api::PullRequest,
+    dry_run: bool,
+) -> anyhow::Result<usize> {
+    let mut imported = 0;
+    let existing = patch
+        .revisions
+        .iter()
+        .flat_map(|r| r.discussion.iter())
+        .map(|c| c.body.as_str())
+        .collect::<Vec<_>>();
+
+    for comment in client.comments(pr.number)? {
+        if comment.url.is_empty() || existing.iter().any(|b| b.contains(&comment.url)) {
+            continue;
+        }
+        let action = match comment.review.as_deref() {
+            None => "commented",
+            Some("APPROVED") => "approved",
+            Some("CHANGES_REQUESTED") => "requested changes",
+            Some("DISMISSED") => "reviewed (dismissed)",
+            // Reviews without a summary only hold inline comments, which aren't imported.
+            Some("COMMENTED") if comment.body.trim().is_empty() => continue,
+            Some("COMMENTED") => "reviewed",
+            // Pending reviews aren't submitted yet.
+            Some(_) => continue,
+        };
+        let mut body = format!("**@{}** {} on GitHub", comment.author, action);
+        if !comment.body.trim().is_empty() {
+            body.push_str(&format!(":\n\n{}", comment.body.trim()));
+        }
+        body.push_str(&format!("\n\n{}", comment.url));
+
+        if !dry_run {
+            cobs.patches().comment(urn, id, patch.version(), &body)?;
+        }
+        imported += 1;
+    }
+    Ok(imported)
+}
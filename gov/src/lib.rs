@@ -165,7 +165,7 @@ where
     M: Middleware + 'static,
     crate::governance::Error<M>: From<<M as Middleware>::Error>,
 {
-    let spinner = term::spinner(&format!(
+    let spinner = term::spinner(&term::tr!(
         "Reading proposal file {}",
         term::format::highlight(&format!("{:?}", file))
     ));
@@ -1,35 +1,64 @@
 use std::ffi::OsString;
 
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::plugin;
 use radicle_terminal as term;
 
 #[cfg(feature = "ethereum")]
 pub use rad_account;
+pub use rad_archive;
 pub use rad_auth;
+pub use rad_blame;
+pub use rad_block;
 pub use rad_checkout;
+pub use rad_ci;
 pub use rad_clone;
+pub use rad_cob;
 pub use rad_comment;
+pub use rad_completions;
+pub use rad_config;
+pub use rad_delegate;
+pub use rad_doctor;
 pub use rad_edit;
 #[cfg(feature = "ethereum")]
 pub use rad_ens;
+pub use rad_export;
+pub use rad_feed;
+pub use rad_fork;
+pub use rad_gc;
+pub use rad_git;
+pub use rad_github;
 #[cfg(feature = "ethereum")]
 pub use rad_gov;
+pub use rad_hooks;
+pub use rad_id;
 pub use rad_init;
 pub use rad_inspect;
 pub use rad_issue;
 pub use rad_ls;
 pub use rad_merge;
+pub use rad_mirror;
+pub use rad_notify;
+pub use rad_org;
 pub use rad_patch;
 pub use rad_path;
 pub use rad_pull;
 pub use rad_push;
+pub use rad_release;
 pub use rad_remote;
 pub use rad_review;
 pub use rad_rm;
+pub use rad_search;
+pub use rad_seed;
 pub use rad_self;
+pub use rad_serve;
+pub use rad_setup;
 pub use rad_sync;
 pub use rad_track;
+pub use rad_tui;
 pub use rad_untrack;
+pub use rad_verify;
+pub use rad_workspace;
 
 pub const HELP: Help = Help {
     name: "help",
@@ -52,12 +81,96 @@ const COMMANDS: &[Help] = &[
     rad_track::HELP,
     rad_untrack::HELP,
     rad_sync::HELP,
+    rad_seed::HELP,
     #[cfg(feature = "ethereum")]
     rad_ens::HELP,
     #[cfg(feature = "ethereum")]
     rad_account::HELP,
     rad_rm::HELP,
     rad_edit::HELP,
+    rad_cob::HELP,
+    rad_block::HELP,
+    rad_delegate::HELP,
+    rad_git::HELP,
+    rad_verify::HELP,
+    rad_id::HELP,
+    rad_org::HELP,
+    rad_fork::HELP,
+    rad_gc::HELP,
+    rad_workspace::HELP,
+    rad_release::HELP,
+    rad_blame::HELP,
+    rad_archive::HELP,
+    rad_github::HELP,
+    rad_mirror::HELP,
+    rad_hooks::HELP,
+    rad_serve::HELP,
+    rad_feed::HELP,
+    rad_notify::HELP,
+    rad_export::HELP,
+    rad_ci::HELP,
+    rad_tui::HELP,
+    rad_completions::HELP,
+    rad_config::HELP,
+    rad_doctor::HELP,
+    rad_setup::HELP,
+    rad_search::HELP,
+    crate::HELP,
+];
+
+/// All commands, including those not listed by `rad help`, eg. for shell completions.
+pub const ALL: &[Help] = &[
+    #[cfg(feature = "ethereum")]
+    rad_account::HELP,
+    rad_archive::HELP,
+    rad_auth::HELP,
+    rad_blame::HELP,
+    rad_block::HELP,
+    rad_checkout::HELP,
+    rad_ci::HELP,
+    rad_clone::HELP,
+    rad_cob::HELP,
+    rad_comment::HELP,
+    rad_completions::HELP,
+    rad_delegate::HELP,
+    rad_edit::HELP,
+    #[cfg(feature = "ethereum")]
+    rad_ens::HELP,
+    rad_export::HELP,
+    rad_feed::HELP,
+    rad_fork::HELP,
+    rad_gc::HELP,
+    rad_git::HELP,
+    rad_github::HELP,
+    #[cfg(feature = "ethereum")]
+    rad_gov::HELP,
+    rad_hooks::HELP,
+    rad_id::HELP,
+    rad_init::HELP,
+    rad_inspect::HELP,
+    rad_issue::HELP,
+    rad_ls::HELP,
+    rad_merge::HELP,
+    rad_mirror::HELP,
+    rad_notify::HELP,
+    rad_org::HELP,
+    rad_patch::HELP,
+    rad_path::HELP,
+    rad_pull::HELP,
+    rad_push::HELP,
+    rad_release::HELP,
+    rad_remote::HELP,
+    rad_review::HELP,
+    rad_rm::HELP,
+    rad_seed::HELP,
+    rad_self::HELP,
+    rad_serve::HELP,
+    rad_sync::HELP,
+    rad_track::HELP,
+    rad_tui::HELP,
+    rad_untrack::HELP,
+    rad_verify::HELP,
+    rad_workspace::HELP,
     crate::HELP,
 ];
 
@@ -83,7 +196,9 @@ impl Args for Options {
 }
 
 pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    println!("Usage: rad <command> [--help]");
+    println!(
+        "Usage: rad [--profile <name>] [--json] [--color <when>] [--yes] [--ascii] [--offline] [-q | -v...] <command> [--help]"
+    );
 
     if ctx.profile().is_err() {
         println!();
@@ -109,8 +224,57 @@ pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         );
     }
     println!();
+
+    // External commands can't shadow built-in commands.
+    let external = plugin::list()
+        .into_iter()
+        .filter(|name| ALL.iter().all(|help| help.name != name.as_str()))
+        .collect::<Vec<_>>();
+    if !external.is_empty() {
+        println!("External commands, ie. `rad-<command>` executables in `PATH`:");
+        println!();
+
+        for name in external {
+            println!("\t{}", term::format::bold(name));
+        }
+        println!();
+    }
     println!("See `rad <command> --help` to learn about a specific command.");
     println!();
+    println!(
+        "With `--json`, the `ls`, `self`, `track`, `patch` and `issue` commands print their output as JSON."
+    );
+    println!(
+        "With `--color <when>`, output is colored `always`, `never`, or `auto`: when written to a terminal, unless `NO_COLOR` is set (default)."
+    );
+    println!(
+        "With `-y`, `--yes`, confirmations are answered with yes, eg. to run commands in CI, and questions about destructive actions with no. To never be asked, set `confirm = false` in the configuration, see `rad config`. Without either, commands that need a confirmation fail when the terminal isn't interactive."
+    );
+    println!(
+        "With `-q`, `--quiet`, progress, successes and tips are not shown. With `-v`, `--verbose`, logs are shown on stderr: `-v` info, `-vv` debug, `-vvv` trace, eg. of syncing."
+    );
+    println!(
+        "With `--ascii`, symbols, emojis and box-drawing characters are replaced with ASCII, for terminals that can't show them. This is the default when the locale isn't UTF-8, and can be set with `ascii = true` in the configuration."
+    );
+    println!(
+        "With `--offline`, commands work from local storage only: changes aren't synced, and commands that need the network, eg. `rad sync`, fail. Set `offline = true` in the configuration to always work offline."
+    );
+    println!(
+        "Configuration keys can be set with environment variables named after them, eg. `RAD_SYNC_RETRIES` for `sync.retries`, or `RAD_SEED` for a comma-separated list of seeds. `RAD_NO_SYNC` turns off syncing after changes, `RAD_HOME` sets the radicle home directory, and `RAD_PROFILE` the profile, as with `--profile`."
+    );
+    println!(
+        "Messages are shown in the language of the locale, eg. from `LANG`, where translated. Available: English, German (`de`)."
+    );
+    println!();
+    println!("Exit codes, for scripts:");
+    println!();
+    println!("\t0  success");
+    println!("\t1  failure, eg. invalid arguments");
+    println!("\t2  not in the working copy of a project");
+    println!("\t3  not found, eg. a project, patch or issue");
+    println!("\t4  aborted by the user, or a confirmation couldn't be asked");
+    println!("\t5  network failure, eg. seeds couldn't be reached");
+    println!();
 
     Ok(())
 }
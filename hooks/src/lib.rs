@@ -0,0 +1,269 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::hooks::{Event, Hook, Hooks};
+use radicle_common::{json, project, Url};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "hooks",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad hooks ls
+    rad hooks add <url> [--event <event>[,<event>...]]... [--project <urn>]
+    rad hooks rm <url>
+    rad hooks test <url>
+
+    Webhooks are HTTP endpoints that are notified of events, eg. to trigger CI builds,
+    or post to a chat. After a command changes a project, eg. `rad patch` creates a
+    patch, each webhook that subscribed to the event is sent a POST request, with a JSON
+    payload of the form:
+
+        { "event": "patch.created", "project": "<urn>", "timestamp": <secs>, "data": {...} }
+
+    The event is also sent in the `X-Radicle-Event` header. Failed deliveries are
+    reported, but are not retried.
+
+    Webhooks are stored in the profile, and are notified of the events of all projects,
+    unless limited to a project with `--project`. `test` sends a `ping` event.
+
+Events
+
+    patch.created      A patch was created, with `rad patch`
+    patch.updated      A patch revision was created, with `rad patch --update`
+    patch.commented    A patch was commented on, with `rad comment`
+    patch.merged       A patch was merged, with `rad merge`
+    issue.created      An issue was created, with `rad issue new`
+    issue.commented    An issue was commented on, with `rad comment`
+    issue.state        An issue was closed or reopened, with `rad issue state`
+    refs.pushed        Branches were pushed to storage, with `rad push`
+    refs.fetched       Refs of remote peers were fetched, with `rad sync`
+
+Options
+
+    --event <event>     Only notify the webhook of the given events (default: all events)
+    --project <urn>     Only notify the webhook of the events of the given project
+    --help              Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Add,
+    Remove,
+    Test,
+    List,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Add {
+        url: Url,
+        events: Vec<Event>,
+        project: Option<Urn>,
+    },
+    Remove {
+        url: Url,
+    },
+    Test {
+        url: Url,
+    },
+    List,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut url: Option<Url> = None;
+        let mut events = Vec::new();
+        let mut project: Option<Urn> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("event") if op == Some(OperationName::Add) => {
+                    let val = parser.value()?;
+
+                    for event in val.to_string_lossy().split(',') {
+                        events.push(Event::from_str(event.trim()).map_err(|err| {
+                            Error::WithHint {
+                                err,
+                                hint: "hint: run `rad hooks --help` to see the supported events",
+                            }
+                        })?);
+                    }
+                }
+                Long("project") if op == Some(OperationName::Add) => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    project = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "a" | "add" => op = Some(OperationName::Add),
+                    "r" | "rm" => op = Some(OperationName::Remove),
+                    "t" | "test" => op = Some(OperationName::Test),
+                    "l" | "ls" => op = Some(OperationName::List),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if op != Some(OperationName::List) && url.is_none() => {
+                    let val = val.to_string_lossy();
+                    let parsed = Url::parse(&val)
+                        .map_err(|_| anyhow!(term::tr!("invalid URL '{}'", val)))?;
+
+                    if !matches!(parsed.scheme(), "http" | "https") {
+                        anyhow::bail!(term::tr!(
+                            "invalid URL '{}': webhooks must be HTTP(S) URLs",
+                            val
+                        ));
+                    }
+                    url = Some(parsed);
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+        events.sort();
+        events.dedup();
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Add => Operation::Add {
+                url: url.ok_or_else(|| anyhow!("the URL of the webhook must be provided"))?,
+                events,
+                project,
+            },
+            OperationName::Remove => Operation::Remove {
+                url: url.ok_or_else(|| anyhow!("the URL of the webhook must be provided"))?,
+            },
+            OperationName::Test => Operation::Test {
+                url: url.ok_or_else(|| anyhow!("the URL of the webhook must be provided"))?,
+            },
+            OperationName::List => Operation::List,
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let mut hooks = Hooks::load(&profile)?;
+
+    match options.op {
+        Operation::Add {
+            url,
+            events,
+            project,
+        } => {
+            if hooks.hook.iter().any(|h| h.url == url) {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("webhook {} is already configured", url)),
+                    hint: "hint: remove it first with `rad hooks rm`",
+                }
+                .into());
+            }
+            hooks.hook.push(Hook {
+                url: url.clone(),
+                events,
+                project,
+            });
+            hooks.write(&profile)?;
+
+            term::success!("Webhook {} added", term::format::highlight(url));
+            term::tip!(
+                "To check that it is reachable, run `rad hooks test {}`.",
+                url
+            );
+        }
+        Operation::Remove { url } => {
+            let len = hooks.hook.len();
+
+            hooks.hook.retain(|h| h.url != url);
+            if hooks.hook.len() == len {
+                anyhow::bail!(term::tr!("webhook {} is not configured", url));
+            }
+            hooks.write(&profile)?;
+
+            term::success!("Webhook {} removed", term::format::highlight(url));
+        }
+        Operation::Test { url } => {
+            let hook = hooks
+                .hook
+                .iter()
+                .find(|h| h.url == url)
+                .ok_or_else(|| anyhow!(term::tr!("webhook {} is not configured", url)))?;
+            let urn = match &hook.project {
+                Some(urn) => urn.clone(),
+                None => project::cwd()
+                    .map(|(urn, _)| urn)
+                    .map_err(|_| Error::WithHint {
+                        err: anyhow!("a project is needed to test the webhook"),
+                        hint: "hint: run this command within a project working copy",
+                    })?,
+            };
+
+            let spinner = term::spinner(term::tr!(
+                "Sending {} event to {}...",
+                Event::Ping,
+                term::format::highlight(&url)
+            ));
+            if let Err(err) = hook.post(Event::Ping, &urn, json::json!({})) {
+                return Err(spinner.error(err));
+            }
+            spinner.finish();
+        }
+        Operation::List => {
+            let mut table = term::Table::default();
+
+            for hook in &hooks.hook {
+                table.push([
+                    term::format::highlight(&hook.url),
+                    if hook.events.is_empty() {
+                        term::format::dim("all events")
+                    } else {
+                        hook.events
+                            .iter()
+                            .map(|e| e.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    },
+                    hook.project
+                        .as_ref()
+                        .map(|urn| term::format::tertiary(urn))
+                        .unwrap_or_else(|| term::format::dim("all projects")),
+                ]);
+            }
+            if hooks.hook.is_empty() {
+                term::info!("{}", term::format::italic("No webhooks configured."));
+                term::tip!("Add a webhook with `rad hooks add <url>`.");
+            } else {
+                table.render();
+            }
+        }
+    }
+
+    Ok(())
+}
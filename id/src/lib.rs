@@ -0,0 +1,308 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::identities::{any, person, project, SomeIdentity};
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::alias::Aliases;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::Timestamp;
+use radicle_common::{exit, fmt, git, profile};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "id",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad id show [<urn>]
+    rad id verify [<urn>]
+
+    Identities are the documents of projects and persons, which state who controls
+    them: their delegates. Every change to an identity is a new revision, signed by
+    delegates, and only takes effect once a quorum of delegates has signed it.
+
+    `show` displays the current delegations of an identity, and its history of
+    revisions, with the delegations and signatures of each revision.
+
+    `verify` validates the whole history of an identity, from its first revision,
+    and reports the latest revision that is in effect. It fails if the history is
+    invalid.
+
+    If no URN is given, the project of the current working copy is used.
+
+Options
+
+    --help      Print help
+"#,
+};
+
+/// Commit trailer holding a revision signature.
+const SIGNATURE_TRAILER: &str = "x-rad-signature:";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation {
+    Show,
+    Verify,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub urn: Option<Urn>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<Operation> = None;
+        let mut urn: Option<Urn> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "show" => op = Some(Operation::Show),
+                    "verify" => op = Some(Operation::Verify),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    urn = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                op: op.ok_or(Error::Usage)?,
+                urn,
+            },
+            vec![],
+        ))
+    }
+}
+
+/// A revision of an identity, read from its history.
+struct Revision {
+    /// Revision id, ie. the tree of the identity document.
+    id: git::Oid,
+    /// Commit time.
+    timestamp: Timestamp,
+    /// Delegations stated by the document.
+    delegations: Vec<String>,
+    /// Keys that signed the revision.
+    signatures: Vec<String>,
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => radicle_common::project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("could not determine identity"),
+                hint: "hint: run this command within a project working copy, or specify a URN",
+            })?,
+    };
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let history = history(&monorepo, &urn)?;
+
+    match options.op {
+        Operation::Show => show(&storage, &urn, &history),
+        Operation::Verify => verify(&storage, &urn, &history),
+    }
+}
+
+fn show(storage: &ReadOnly, urn: &Urn, history: &[Revision]) -> anyhow::Result<()> {
+    let (kind, name) = match any::get(storage, urn)? {
+        Some(SomeIdentity::Project(p)) => ("project", p.subject().name.to_string()),
+        Some(SomeIdentity::Person(p)) => ("person", p.subject().name.to_string()),
+        _ => anyhow::bail!(term::tr!("no project or person found for {}", urn)),
+    };
+    let verified = verified(storage, urn, kind).ok().flatten();
+    let aliases = Aliases::cwd();
+    let mut table = term::Table::default();
+
+    table.push([String::from("URN"), term::format::tertiary(urn)]);
+    table.push([String::from("Type"), term::format::tertiary(kind)]);
+    table.push([String::from("Name"), term::format::tertiary(name)]);
+    table.push([
+        String::from("Revision"),
+        match verified {
+            Some(rev) => term::format::tertiary(rev),
+            None => term::format::negative("none in effect"),
+        },
+    ]);
+    table.render_tree();
+    term::blank();
+
+    for (i, rev) in history.iter().enumerate() {
+        let badge = if Some(rev.id) == verified {
+            term::format::badge_positive("in effect")
+        } else if i == 0 {
+            term::format::badge_secondary("pending")
+        } else {
+            String::new()
+        };
+        term::info!(
+            "{} {} {} {}",
+            term::format::yellow("revision"),
+            term::format::yellow(rev.id),
+            term::format::dim(rev.timestamp.to_rfc2822()),
+            badge
+        );
+        for delegation in &rev.delegations {
+            term::info!(
+                "{}{} {}",
+                term::TAB,
+                term::format::dim("delegate "),
+                display_key(delegation, &aliases)
+            );
+        }
+        for signature in &rev.signatures {
+            term::info!(
+                "{}{} {}",
+                term::TAB,
+                term::format::dim("signed by"),
+                display_key(signature, &aliases)
+            );
+        }
+        term::blank();
+    }
+    Ok(())
+}
+
+fn verify(storage: &ReadOnly, urn: &Urn, history: &[Revision]) -> anyhow::Result<()> {
+    let kind = match any::get(storage, urn)? {
+        Some(SomeIdentity::Project(_)) => "project",
+        Some(SomeIdentity::Person(_)) => "person",
+        _ => anyhow::bail!(term::tr!("no project or person found for {}", urn)),
+    };
+    let spinner = term::spinner(term::tr!(
+        "Verifying {} revision(s) of {}...",
+        history.len(),
+        term::format::highlight(urn)
+    ));
+
+    let verified = match verified(storage, urn, kind) {
+        Ok(Some(rev)) => rev,
+        Ok(None) => {
+            return Err(spinner.error(anyhow!(term::tr!("{} has no revision in effect", urn))));
+        }
+        Err(err) => {
+            return Err(spinner.error(anyhow!(term::tr!(
+                "history of {} is invalid: {}",
+                urn,
+                err
+            ))));
+        }
+    };
+    spinner.finish();
+
+    term::success!(
+        "History of {} {} is valid",
+        kind,
+        term::format::highlight(urn)
+    );
+    term::info!(
+        "{} {}",
+        term::format::dim("Revision in effect"),
+        term::format::tertiary(verified)
+    );
+
+    let pending = history.iter().take_while(|r| r.id != verified).count();
+    if pending > 0 {
+        term::warning(&term::tr!(
+            "{} newer revision(s) are not signed by a quorum of delegates, and are not in effect",
+            pending
+        ));
+    }
+    Ok(())
+}
+
+/// Verify the history of an identity, and get the revision in effect, if any.
+fn verified(storage: &ReadOnly, urn: &Urn, kind: &str) -> anyhow::Result<Option<git::Oid>> {
+    let revision = if kind == "project" {
+        project::verify(storage, urn)?.map(|p| p.revision)
+    } else {
+        person::verify(storage, urn)?.map(|p| p.revision)
+    };
+    Ok(revision.map(git::Oid::from))
+}
+
+/// Read the history of an identity from storage, latest revision first.
+fn history(monorepo: &git::Repository, urn: &Urn) -> anyhow::Result<Vec<Revision>> {
+    let name = format!("refs/namespaces/{}/refs/rad/id", urn.encode_id());
+    let mut commit = monorepo
+        .find_reference(&name)
+        .and_then(|r| r.peel_to_commit())
+        .map_err(|_| exit::Error::NotFound(term::tr!("identity {} not found in storage", urn)))?;
+    let mut history = Vec::new();
+
+    loop {
+        let tree = commit.tree()?;
+        let doc: serde_json::Value = match tree.get(0) {
+            Some(entry) => serde_json::from_slice(monorepo.find_blob(entry.id())?.content())?,
+            None => anyhow::bail!(term::tr!("revision {} has no identity document", tree.id())),
+        };
+        let delegations = doc["delegations"]
+            .as_array()
+            .map(|ds| {
+                ds.iter()
+                    .filter_map(|d| d.as_str().map(|s| s.to_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let signatures = commit
+            .message()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|l| l.strip_prefix(SIGNATURE_TRAILER))
+            .filter_map(|l| l.split_whitespace().next().map(|k| k.to_owned()))
+            .collect();
+
+        history.push(Revision {
+            id: tree.id(),
+            timestamp: Timestamp::new(commit.time().seconds() as u64),
+            delegations,
+            signatures,
+        });
+
+        match commit.parent(0) {
+            Ok(parent) => commit = parent,
+            Err(_) => break,
+        }
+    }
+    Ok(history)
+}
+
+/// Display a key or URN of a delegation or signature.
+fn display_key(key: &str, aliases: &Aliases) -> String {
+    match PeerId::from_str(key) {
+        Ok(peer) => format!(
+            "{} {}",
+            term::format::tertiary(fmt::peer(&peer)),
+            term::format::dim(aliases.get(&peer).unwrap_or_default())
+        ),
+        Err(_) => term::format::tertiary(key),
+    }
+}
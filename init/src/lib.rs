@@ -3,15 +3,20 @@ use std::convert::TryFrom;
 use std::env;
 use std::ffi::OsString;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use anyhow::{anyhow, bail, Context as _};
 
+use librad::crypto::BoxedSigner;
+use librad::git::storage::Storage;
+use librad::git::Urn;
 use librad::PeerId;
 
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::identities;
 use radicle_common::json;
 use radicle_common::Interactive;
-use radicle_common::{git, keys, profile, project};
+use radicle_common::{exit, git, identity, keys, profile, project, sync};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -22,15 +27,35 @@ pub const HELP: Help = Help {
 Usage
 
     rad init [<path>] [<option>...]
+    rad init [<path>] --name <name> --description <text> --default-branch <branch> --no-confirm [--no-sync]
+    rad init [<path>] --existing <urn>
+
+    Initializes a radicle project from a git repository, and publishes it to your
+    seeds, unless `--no-sync` is used.
+
+    With `--no-confirm`, nothing is asked, and errors are reported instead, so that
+    projects can be initialized by scripts. Values that aren't given are then set to
+    their defaults: the name of the directory, an empty description, and the branch
+    of `HEAD`.
+
+    With `--existing`, the repository is linked to a project that already exists, eg.
+    when the code was cloned with git rather than with `rad clone`, instead of creating
+    a new project. The project must be in local storage, eg. fetched with `rad sync`.
+    The repository's copy of the project's default branch must share its history with
+    the project's. The `rad` remote is then set up, and your copy of the branch is
+    published under your peer id.
 
 Options
 
-    --name               Name of the project
-    --description        Description of the project
-    --default-branch     The default branch of the project
-    --set-upstream, -u   Setup the upstream of the default branch
-    --no-confirm         Don't ask for confirmation during setup
-    --help               Print help
+    --name <name>              Name of the project
+    --description <text>       Description of the project
+    --default-branch <branch>  The default branch of the project
+    --existing <urn>           Link the repository to an existing project
+    --set-upstream, -u         Setup the upstream of the default branch
+    --no-confirm               Don't ask for confirmation during setup
+    --sync                     Sync the project to seeds after initializing (default: true)
+    --no-sync                  Don't sync the project after initializing
+    --help                     Print help
 "#,
 };
 
@@ -42,6 +67,8 @@ pub struct Options {
     pub branch: Option<String>,
     pub interactive: Interactive,
     pub set_upstream: bool,
+    pub sync: bool,
+    pub existing: Option<Urn>,
 }
 
 impl Args for Options {
@@ -56,6 +83,8 @@ impl Args for Options {
         let mut branch = None;
         let mut interactive = Interactive::Yes;
         let mut set_upstream = false;
+        let mut sync = sync::is_auto();
+        let mut existing = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -91,12 +120,25 @@ impl Args for Options {
 
                     branch = Some(value);
                 }
+                Long("existing") if existing.is_none() => {
+                    let value = parser.value()?;
+                    let value = value.to_string_lossy();
+                    let urn = Urn::from_str(&value).context(format!("invalid URN '{}'", value))?;
+
+                    existing = Some(urn);
+                }
                 Long("set-upstream") | Short('u') => {
                     set_upstream = true;
                 }
                 Long("no-confirm") => {
                     interactive = Interactive::No;
                 }
+                Long("sync") => {
+                    sync = true;
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -107,6 +149,10 @@ impl Args for Options {
             }
         }
 
+        if existing.is_some() && (name.is_some() || description.is_some() || branch.is_some()) {
+            bail!("`--existing` cannot be combined with `--name`, `--description` or `--default-branch`");
+        }
+
         Ok((
             Options {
                 path,
@@ -115,6 +161,8 @@ impl Args for Options {
                 branch,
                 interactive,
                 set_upstream,
+                sync,
+                existing,
             },
             vec![],
         ))
@@ -125,7 +173,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
 
     if git::check_version().is_err() {
-        term::warning(&format!(
+        term::warning(&term::tr!(
             "Your git version is unsupported, please upgrade to {} or later",
             git::VERSION_REQUIRED,
         ));
@@ -140,7 +188,7 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     let path = path.as_path().canonicalize()?;
     let interactive = options.interactive;
 
-    term::headline(&format!(
+    term::headline(&term::tr!(
         "Initializing local 🌱 project in {}",
         if path == cwd {
             term::format::highlight(".")
@@ -149,37 +197,55 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
         }
     ));
 
-    let repo = git::Repository::open(&path)?;
+    let repo = git::Repository::open(&path).map_err(|_| Error::WithHint {
+        err: anyhow!(term::tr!("'{}' is not a git repository", path.display())),
+        hint: "hint: create one with `git init`, and commit to it",
+    })?;
     if let Ok(remote) = git::rad_remote(&repo) {
-        bail!(
+        bail!(term::tr!(
             "repository is already initialized with remote {}",
             remote.url
-        );
+        ));
     }
 
     let signer = term::signer(profile)?;
     let storage = keys::storage(profile, signer.clone())?;
 
+    if let Some(urn) = options.existing {
+        return init_existing(urn, &repo, &storage, profile, signer, interactive);
+    }
+
     let head: String = repo
         .head()
         .ok()
         .and_then(|head| head.shorthand().map(|h| h.to_owned()))
-        .ok_or_else(|| anyhow!("error: repository head does not point to any commits"))?;
-
-    let name = options.name.unwrap_or_else(|| {
-        let default = path.file_name().map(|f| f.to_string_lossy().to_string());
-        term::text_input("Name", default).unwrap()
-    });
-    let description = options
-        .description
-        .unwrap_or_else(|| term::text_input("Description", None).unwrap());
-    let branch = options.branch.unwrap_or_else(|| {
-        if interactive.yes() {
-            term::text_input("Default branch", Some(head)).unwrap()
-        } else {
-            head
-        }
-    });
+        .ok_or_else(|| Error::WithHint {
+            err: anyhow!("repository head does not point to any commits"),
+            hint: "hint: commit to the repository first, or check out a branch",
+        })?;
+
+    let default_name = path.file_name().map(|f| f.to_string_lossy().to_string());
+    let name = match options.name {
+        Some(name) => name,
+        None if interactive.yes() => term::text_input("Name", default_name)?,
+        None => default_name.ok_or_else(|| Error::WithHint {
+            err: anyhow!("the project name could not be determined from the path"),
+            hint: "hint: specify the name with `--name`",
+        })?,
+    };
+    let description = match options.description {
+        Some(description) => description,
+        None if interactive.yes() => term::text_input("Description", None)?,
+        None => String::new(),
+    };
+    let branch = match options.branch {
+        Some(branch) => branch,
+        None if interactive.yes() => term::text_input("Default branch", Some(head))?,
+        None => head,
+    };
+    if name.trim().is_empty() {
+        bail!("the project name cannot be empty");
+    }
 
     let mut spinner = term::spinner("Initializing...");
     let payload = project::payload(name, description, branch.clone());
@@ -190,7 +256,7 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
         Ok(proj) => {
             let urn = proj.urn();
 
-            spinner.message(format!(
+            spinner.message(term::tr!(
                 "Project {} created",
                 term::format::highlight(&proj.subject().name)
             ));
@@ -220,6 +286,22 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
             term::indented(&term::format::secondary("rad ."));
 
             term::blank();
+
+            if options.sync {
+                let result = rad_sync::run(
+                    rad_sync::Options {
+                        origin: Some(identity::Origin::from_urn(urn)),
+                        ..rad_sync::Options::default()
+                    },
+                    profile.clone(),
+                );
+                if let Err(err) = result {
+                    term::warning(&term::tr!("Project could not be synced: {}", err));
+                    term::blank();
+                } else {
+                    return Ok(());
+                }
+            }
             term::info!("To publish your project to the network, run:");
             term::indented(&term::format::secondary("rad push"));
             term::blank();
@@ -233,15 +315,17 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
 
             match err.downcast_ref::<Error>() {
                 Some(Error::Validation(validation::Error::UrlMismatch { found, .. })) => {
-                    bail!(
+                    bail!(term::tr!(
                         "this repository is already initialized with remote {}",
                         found
-                    );
+                    ));
+                }
+                Some(Error::Validation(validation::Error::MissingDefaultBranch { .. })) => {
+                    bail!(term::tr!(
+                        "the `{}` branch was either not found, or has no commits",
+                        branch
+                    ))
                 }
-                Some(Error::Validation(validation::Error::MissingDefaultBranch { .. })) => bail!(
-                    "the `{}` branch was either not found, or has no commits",
-                    branch
-                ),
                 Some(_) | None => return Err(err),
             }
         }
@@ -250,6 +334,113 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     Ok(())
 }
 
+/// Link a repository to a project that already exists in local storage.
+fn init_existing(
+    urn: Urn,
+    repo: &git::Repository,
+    storage: &Storage,
+    profile: &profile::Profile,
+    signer: BoxedSigner,
+    interactive: Interactive,
+) -> anyhow::Result<()> {
+    let not_found = || Error::WithHint {
+        err: exit::Error::NotFound(term::tr!("project {} not found in local storage", urn)).into(),
+        hint: "hint: fetch it first with `rad sync <urn>`",
+    };
+    let proj = project::get(storage, &urn)?.ok_or_else(not_found)?;
+    let identity = identities::project::get(storage, &urn)?.ok_or_else(not_found)?;
+    let branch = proj.default_branch.clone();
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("cannot initialize a bare repository"))?;
+
+    let local = repo
+        .find_branch(&branch, git::BranchType::Local)
+        .and_then(|b| b.get().peel_to_commit())
+        .map(|c| c.id())
+        .map_err(|_| Error::WithHint {
+            err: exit::Error::NotFound(term::tr!(
+                "the project's default branch `{}` was not found",
+                branch
+            ))
+            .into(),
+            hint: "hint: the repository must have the project's default branch",
+        })?;
+
+    // The project's head is our own, if we have one, or else that of a delegate.
+    let namespace = format!("refs/namespaces/{}/refs", urn.encode_id());
+    let head = match project::get_local_head(storage, &urn, &branch)? {
+        Some(oid) => Some((format!("{}/heads/{}", namespace, branch), oid)),
+        None => proj.remotes.iter().find_map(|peer| {
+            project::get_remote_head(storage, &urn, peer, &branch)
+                .ok()
+                .flatten()
+                .map(|oid| {
+                    (
+                        format!("{}/remotes/{}/heads/{}", namespace, peer, branch),
+                        oid,
+                    )
+                })
+        }),
+    };
+    let (head_ref, head) = head.ok_or_else(|| Error::WithHint {
+        err: anyhow!(term::tr!(
+            "the project's `{}` branch was not found in local storage",
+            branch
+        )),
+        hint: "hint: fetch the project's delegates with `rad sync <urn>`",
+    })?;
+
+    let spinner = term::spinner("Verifying history...");
+    // Fetch the project's head, so that it can be compared with the local branch.
+    if let Err(err) = git::git(
+        workdir,
+        [
+            "fetch",
+            "--no-tags",
+            "--quiet",
+            &profile.paths().git_dir().display().to_string(),
+            &head_ref,
+        ],
+    ) {
+        return Err(spinner.error(err));
+    }
+    if repo.merge_base(local, head).is_err() {
+        return Err(spinner.error(anyhow!(term::tr!(
+            "the history of `{}` doesn't match the project's: they have no commits in common",
+            branch
+        ))));
+    }
+    let (ahead, behind) = repo.graph_ahead_behind(local, head)?;
+    spinner.finish();
+
+    term::info!(
+        "Your `{}` branch is {} commit(s) ahead of and {} commit(s) behind the project's",
+        branch,
+        ahead,
+        behind
+    );
+
+    let spinner = term::spinner("Linking...");
+    if let Err(err) = project::init(&identity, repo, storage, profile.paths(), signer) {
+        return Err(spinner.error(err));
+    }
+    spinner.finish();
+
+    let branch = git::OneLevel::from(git::RefLike::try_from(branch)?);
+    git::set_upstream(repo, &git::rad_remote(repo)?, branch)?;
+    self::setup_signing(storage.peer_id(), repo, interactive)?;
+
+    term::blank();
+    term::success!(
+        "Repository linked to project {}",
+        term::format::highlight(&urn)
+    );
+    term::tip!("To publish your branch to the network, run `rad push`.");
+
+    Ok(())
+}
+
 /// Setup radicle key as commit signing key in repository.
 pub fn setup_signing(
     peer_id: &PeerId,
@@ -261,16 +452,16 @@ pub fn setup_signing(
         .ok_or(anyhow!("cannot setup signing in bare repository"))?;
     let key = keys::to_ssh_fingerprint(peer_id)?;
     let yes = if !git::is_signing_configured(repo)? {
-        term::headline(&format!(
+        term::headline(&term::tr!(
             "Configuring 🌱 signing key {}...",
             term::format::tertiary(key)
         ));
         true
     } else if interactive.yes() {
-        term::confirm(&format!(
+        term::confirm(&term::tr!(
             "Configure 🌱 signing key {} in local checkout?",
             term::format::tertiary(key),
-        ))
+        ))?
     } else {
         true
     };
@@ -292,7 +483,9 @@ pub fn setup_signing(
 
                 if ssh_keys.contains(&ssh_key) {
                     term::success!("Signing key is already in {} file", gitsigners);
-                } else if term::confirm(&format!("Add signing key to {}?", gitsigners)) {
+                } else if interactive.no()
+                    || term::confirm(&term::tr!("Add signing key to {}?", gitsigners))?
+                {
                     git::add_gitsigners(repo, [peer_id])?;
                 }
             }
@@ -2,7 +2,6 @@
 use std::convert::TryFrom;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 use radicle_common::args::{Args, Error, Help};
@@ -33,11 +32,15 @@ Usage
     Inspects the given path or URN. If neither is specified,
     the current project is inspected.
 
+    The `--refs`, `--identity`, `--payload` and `--history` options can be combined,
+    to inspect the object's storage in one go. They are shown in that order.
+
 Options
 
     --id        Return the ID without the URN scheme
+    --refs      List the object's refs on the local device, with their targets
+    --identity  Show the object's current identity document, and its signatures
     --payload   Inspect the object's payload
-    --refs      Inspect the object's refs on the local device (requires `tree`)
     --history   Show object's history
     --help      Print help
 "#,
@@ -48,6 +51,7 @@ pub struct Options {
     pub path: Option<PathBuf>,
     pub urn: Option<Urn>,
     pub refs: bool,
+    pub identity: bool,
     pub payload: bool,
     pub history: bool,
     pub id: bool,
@@ -61,6 +65,7 @@ impl Args for Options {
         let mut path: Option<PathBuf> = None;
         let mut urn: Option<Urn> = None;
         let mut refs = false;
+        let mut identity = false;
         let mut payload = false;
         let mut history = false;
         let mut id = false;
@@ -73,6 +78,9 @@ impl Args for Options {
                 Long("refs") => {
                     refs = true;
                 }
+                Long("identity") => {
+                    identity = true;
+                }
                 Long("payload") => {
                     payload = true;
                 }
@@ -88,11 +96,11 @@ impl Args for Options {
                     if let Ok(val) = Urn::from_str(&val) {
                         urn = Some(val);
                     } else if val.starts_with("rad:git:") {
-                        return Err(anyhow!("invalid URN '{}'", val));
+                        return Err(anyhow!(term::tr!("invalid URN '{}'", val)));
                     } else if let Ok(val) = PathBuf::from_str(&val) {
                         path = Some(val);
                     } else {
-                        return Err(anyhow!("invalid path or URN '{}'", val));
+                        return Err(anyhow!(term::tr!("invalid path or URN '{}'", val)));
                     }
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
@@ -106,6 +114,7 @@ impl Args for Options {
                 payload,
                 history,
                 refs,
+                identity,
                 urn,
             },
             vec![],
@@ -113,6 +122,9 @@ impl Args for Options {
     }
 }
 
+/// Commit trailer holding an identity signature.
+const SIGNATURE_TRAILER: &str = "x-rad-signature:";
+
 // Used for JSON Colorizing for now
 fn colorizer() -> Colorizer {
     Colorizer::new()
@@ -124,6 +136,15 @@ fn colorizer() -> Colorizer {
         .build()
 }
 
+/// Render JSON, colorized if output is.
+fn colorize(colorizer: &Colorizer, json: String) -> anyhow::Result<String> {
+    if term::format::colors_enabled() {
+        Ok(colorizer.colorize_json_str(&json)?)
+    } else {
+        Ok(json)
+    }
+}
+
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let storage = profile::read_only(&profile)?;
@@ -139,17 +160,28 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     let colorizer = colorizer();
 
-    if options.refs {
-        let path = profile.paths().git_dir().join("refs").join("namespaces");
-
-        Command::new("tree")
-            .current_dir(path)
-            .args([&urn.encode_id(), "--noreport", "--prune"])
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()?
-            .wait()?;
-    } else if options.payload {
+    if options.id {
+        term::info!("{}", term::format::highlight(urn.encode_id()));
+
+        return Ok(());
+    }
+    if !(options.refs || options.identity || options.payload || options.history) {
+        term::info!("{}", term::format::highlight(urn));
+
+        return Ok(());
+    }
+
+    if options.refs || options.identity {
+        let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+
+        if options.refs {
+            refs(&monorepo, &urn)?;
+        }
+        if options.identity {
+            identity(&monorepo, &urn, &colorizer)?;
+        }
+    }
+    if options.payload {
         let payload = any::get(&storage, &urn)
             .map(|o| o.map(|p| p.payload()))
             .map_err(|_| anyhow::anyhow!("Couldn't load project or person."))?
@@ -157,9 +189,10 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
         println!(
             "{}",
-            colorizer.colorize_json_str(&serde_json::to_string_pretty(&payload)?)?
+            colorize(&colorizer, serde_json::to_string_pretty(&payload)?)?
         );
-    } else if options.history {
+    }
+    if options.history {
         let branch = Reference::try_from(&urn)?;
         match storage.reference(&branch) {
             Ok(Some(reference)) => {
@@ -199,8 +232,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                             term::format::dim(tip.id()),
                             term::format::dim(blob.id()),
                             term::format::dim(time),
-                            colorizer
-                                .colorize_json_str(&serde_json::to_string_pretty(&content)?)?,
+                            colorize(&colorizer, serde_json::to_string_pretty(&content)?)?,
                         ))
                         .first(i == 0)
                         .last(false)
@@ -216,13 +248,112 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 println!();
             }
 
-            _ => return Err(anyhow!("Couldn't find reference to {} in storage", urn)),
+            _ => {
+                return Err(anyhow!(term::tr!(
+                    "Couldn't find reference to {} in storage",
+                    urn
+                )))
+            }
         }
-    } else if options.id {
-        term::info!("{}", term::format::highlight(urn.encode_id()));
-    } else {
-        term::info!("{}", term::format::highlight(urn));
     }
 
     Ok(())
 }
+
+/// Print the refs of an object in storage, relative to its namespace, with their targets.
+fn refs(monorepo: &git::Repository, urn: &Urn) -> anyhow::Result<()> {
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let mut refs = Vec::new();
+
+    for r in monorepo.references_glob(&format!("{}*", namespace))? {
+        let r = r?;
+        let name = match r.name() {
+            Some(name) => name.trim_start_matches(&namespace).to_owned(),
+            None => continue,
+        };
+        let target = match (r.target(), r.symbolic_target()) {
+            (Some(oid), _) => term::format::secondary(oid),
+            (None, Some(target)) => {
+                term::format::dim(format!("-> {}", target.trim_start_matches(&namespace)))
+            }
+            (None, None) => continue,
+        };
+        refs.push((name, target));
+    }
+    refs.sort();
+
+    term::info!(
+        "{} {}",
+        term::format::dim("Storage"),
+        term::format::tertiary(monorepo.path().join(&namespace).display())
+    );
+    let mut table = term::Table::default();
+    for (name, target) in refs {
+        table.push([target, term::format::tertiary(name)]);
+    }
+    table.render();
+    term::blank();
+
+    Ok(())
+}
+
+/// Print the current identity document of an object, along with its signatures.
+fn identity(monorepo: &git::Repository, urn: &Urn, colorizer: &Colorizer) -> anyhow::Result<()> {
+    let commit = monorepo
+        .find_reference(&format!("refs/namespaces/{}/refs/rad/id", urn.encode_id()))
+        .and_then(|r| r.peel_to_commit())
+        .map_err(|_| {
+            anyhow!(term::tr!(
+                "Couldn't find the identity of {} in storage",
+                urn
+            ))
+        })?;
+    let tree = commit.tree()?;
+    let entry = tree
+        .get(0)
+        .ok_or(anyhow!("Couldn't get the first tree entry"))?;
+    let blob = monorepo
+        .find_blob(entry.id())
+        .map_err(|_| anyhow!("First tree entry is not a blob"))?;
+    let doc: serde_json::Value = serde_json::from_slice(blob.content())?;
+
+    let mut table = term::Table::default();
+    table.push([String::from("Revision"), term::format::yellow(tree.id())]);
+    table.push([String::from("Commit"), term::format::dim(commit.id())]);
+    table.push([String::from("Blob"), term::format::dim(blob.id())]);
+    table.render_tree();
+    term::blank();
+
+    println!(
+        "{}",
+        colorize(colorizer, serde_json::to_string_pretty(&doc)?)?
+    );
+    term::blank();
+
+    let signatures = commit
+        .message()
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.strip_prefix(SIGNATURE_TRAILER))
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            Some((parts.next()?.to_owned(), parts.next()?.to_owned()))
+        })
+        .collect::<Vec<_>>();
+
+    if signatures.is_empty() {
+        term::warning("The identity document is not signed");
+    }
+    let mut table = term::Table::default();
+    for (key, signature) in signatures {
+        table.push([
+            term::format::dim("signed by"),
+            term::format::tertiary(key),
+            term::format::dim(format!("{:.16}...", signature)),
+        ]);
+    }
+    table.render();
+    term::blank();
+
+    Ok(())
+}
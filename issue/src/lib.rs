@@ -4,9 +4,13 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
 
-use radicle_common::args::{Args, Error, Help};
+use radicle_common::args::{self, Args, Error, Help};
+use radicle_common::block::Blocklist;
 use radicle_common::cobs::issue::*;
-use radicle_common::{cobs, keys, project};
+use radicle_common::cobs::CommentId;
+use radicle_common::hooks::Event;
+use radicle_common::json;
+use radicle_common::{cobs, fmt, gitlab, keys, project, Urn};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -20,11 +24,34 @@ Usage
     rad issue state <id> [--closed | --open | --solved]
     rad issue delete <id>
     rad issue react <id> [--emoji <char>]
-    rad issue list
+    rad issue list [--format <format>] [--limit <n>] [--page <n>]
+    rad issue import --gitlab <project>
 
 Options
 
-    --help      Print help
+    --format <format>   Output format of `list`: `text` (default), `csv` or `json`
+    --limit <n>         List at most <n> issues
+    --page <n>          List the <n>th page of issues, of 50 issues unless `--limit`
+                        is given
+    --gitlab <project>  GitLab project to import the issues of
+    --help              Print help
+
+    The CSV output has the columns `id`, `title`, `state` (`open`, `closed` or
+    `solved`), `author`, `labels` (separated with `;`), `comments` and `created`
+    (RFC 3339). The JSON output has the same fields, with `labels` as an array.
+
+    `list` lists issues from the oldest. As text, they are shown as they are loaded
+    from storage, rather than once all are loaded.
+
+    With `rad --json`, `list` prints JSON, and `new` and `state` print the issue id
+    and title as a JSON object.
+
+    `import` imports the issues of a GitLab project, open or closed, for projects
+    migrating from GitLab. The project is given as `<group>/<project>` on gitlab.com,
+    or as the URL of the project on another GitLab instance. Requests are
+    authenticated with the `GITLAB_TOKEN` environment variable. Discussion threads are
+    imported as comments and replies, crediting their GitLab authors. Issues that were
+    already imported are skipped. Run `rad sync` afterwards to publish the issues.
 "#,
 };
 
@@ -34,6 +61,30 @@ pub struct Metadata {
     labels: Vec<cobs::Label>,
 }
 
+/// Output format of listings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(anyhow!(term::tr!(
+                "unknown format '{}': expected `text`, `csv` or `json`",
+                other
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum OperationName {
     Create,
@@ -41,6 +92,7 @@ pub enum OperationName {
     React,
     Delete,
     List,
+    Import,
 }
 
 impl Default for OperationName {
@@ -66,9 +118,18 @@ pub enum Operation {
         id: cobs::issue::IssueId,
         reaction: cobs::Reaction,
     },
-    List,
+    List {
+        format: Format,
+        page: args::Page,
+    },
+    Import {
+        from: gitlab::Project,
+    },
 }
 
+/// Number of issues rendered at once, when listing as text.
+const BATCH_SIZE: usize = 20;
+
 /// Tool options.
 #[derive(Debug)]
 pub struct Options {
@@ -86,6 +147,10 @@ impl Args for Options {
         let mut reaction: Option<cobs::Reaction> = None;
         let mut description: Option<String> = None;
         let mut state: Option<cobs::issue::State> = None;
+        let mut format = Format::Text;
+        let mut gitlab: Option<gitlab::Project> = None;
+        let mut limit: Option<usize> = None;
+        let mut page: Option<usize> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -116,17 +181,38 @@ impl Args for Options {
                         );
                     }
                 }
+                Long("format") if op == Some(OperationName::List) || op.is_none() => {
+                    let value = parser.value()?;
+
+                    format = args::parse_value("format", value)?;
+                }
+                Long("limit") if op == Some(OperationName::List) || op.is_none() => {
+                    let value = parser.value()?;
+
+                    limit = Some(args::parse_value("limit", value)?);
+                }
+                Long("page") if op == Some(OperationName::List) || op.is_none() => {
+                    let value = parser.value()?;
+
+                    page = Some(args::parse_value("page", value)?);
+                }
                 Long("description") if op == Some(OperationName::Create) => {
                     description = Some(parser.value()?.to_string_lossy().into());
                 }
+                Long("gitlab") if op == Some(OperationName::Import) => {
+                    let value = parser.value()?;
+
+                    gitlab = Some(args::parse_value("gitlab", value)?);
+                }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
                     "n" | "new" => op = Some(OperationName::Create),
                     "s" | "state" => op = Some(OperationName::State),
                     "d" | "delete" => op = Some(OperationName::Delete),
                     "l" | "list" => op = Some(OperationName::List),
                     "r" | "react" => op = Some(OperationName::React),
+                    "import" => op = Some(OperationName::Import),
 
-                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
                 },
                 Value(val) if op.is_some() => {
                     let val = val
@@ -135,7 +221,7 @@ impl Args for Options {
 
                     id = Some(
                         IssueId::from_str(val)
-                            .map_err(|_| anyhow!("invalid issue id '{}'", val))?,
+                            .map_err(|_| anyhow!(term::tr!("invalid issue id '{}'", val)))?,
                     );
                 }
                 _ => {
@@ -157,7 +243,16 @@ impl Args for Options {
             OperationName::Delete => Operation::Delete {
                 id: id.ok_or_else(|| anyhow!("an issue id to remove must be provided"))?,
             },
-            OperationName::List => Operation::List,
+            OperationName::List => Operation::List {
+                format,
+                page: args::Page::new(limit, page)?,
+            },
+            OperationName::Import => Operation::Import {
+                from: gitlab.ok_or_else(|| Error::WithHint {
+                    err: anyhow!("a project to import from must be given"),
+                    hint: "hint: give the GitLab project with `--gitlab <project>`",
+                })?,
+            },
         };
 
         Ok((Options { op }, vec![]))
@@ -166,8 +261,12 @@ impl Args for Options {
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
-    let signer = term::signer(&profile)?;
-    let storage = keys::storage(&profile, signer)?;
+    // Listing issues only reads storage, so the key isn't needed.
+    let storage = if let Operation::List { .. } = options.op {
+        keys::storage_read_only(&profile)?
+    } else {
+        keys::storage(&profile, term::signer(&profile)?)?
+    };
     let (project, _) = project::cwd()?;
     let cobs = cobs::store(&profile, &storage)?;
     let issues = cobs.issues();
@@ -177,14 +276,32 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             title: Some(title),
             description: Some(description),
         } => {
-            issues.create(&project, &title, &description, &[])?;
+            let id = issues.create(&project, &title, &description, &[])?;
+            let output = json::json!({ "id": id.to_string(), "title": title });
+
+            term::hooks::emit(&profile, Event::IssueCreated, &project, output.clone());
+            if term::output::is_json() {
+                term::output::json(&output)?;
+            }
         }
         Operation::State { id, state } => {
             issues.lifecycle(&project, &id, state)?;
+
+            let title = issues
+                .get(&project, &id)?
+                .map(|issue| issue.title().to_owned())
+                .unwrap_or_default();
+
+            let output = json::json!({ "id": id.to_string(), "title": title, "state": state });
+
+            term::hooks::emit(&profile, Event::IssueState, &project, output.clone());
+            if term::output::is_json() {
+                term::output::json(&output)?;
+            }
         }
         Operation::React { id, reaction } => {
             if let Some(issue) = issues.get(&project, &id)? {
-                let comment_id = term::comment_select(&issue).unwrap();
+                let comment_id = term::comment_select(&issue, &Blocklist::load(&profile)?).unwrap();
                 issues.react(&project, &id, comment_id, reaction)?;
             }
         }
@@ -224,18 +341,187 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 let meta: Metadata =
                     serde_yaml::from_str(&meta).context("failed to parse yaml front-matter")?;
 
-                issues.create(&project, &meta.title, description.trim(), &meta.labels)?;
+                let id = issues.create(&project, &meta.title, description.trim(), &meta.labels)?;
+                let output = json::json!({ "id": id.to_string(), "title": meta.title });
+
+                term::hooks::emit(&profile, Event::IssueCreated, &project, output.clone());
+                if term::output::is_json() {
+                    term::output::json(&output)?;
+                }
             }
         }
-        Operation::List => {
-            for (id, issue) in issues.all(&project)? {
-                println!("{} {}", id, issue.title());
+        Operation::List { mut format, page } => {
+            let blocklist = Blocklist::load(&profile)?;
+            let mut csv = term::Csv::new([
+                "id", "title", "state", "author", "labels", "comments", "created",
+            ]);
+            let mut table = term::Table::new(term::table::TableOptions {
+                truncate: Some(1),
+                ..Default::default()
+            });
+            let mut output = Vec::new();
+
+            if term::output::is_json() {
+                format = Format::Json;
+            }
+
+            let mut authors = cobs.authors();
+            let listed = issues.iter(&project)?.filter(|result| match result {
+                Ok((_, issue)) => !blocklist.contains(&issue.author().peer),
+                Err(_) => true,
+            });
+
+            for result in page.of(listed) {
+                let (id, mut issue) = result?;
+
+                authors.resolve(&mut issue.author, &storage).ok();
+                let comments = blocklist.discussion(issue.comments()).len();
+
+                let mut labels = issue
+                    .labels()
+                    .iter()
+                    .map(|l| l.name().to_owned())
+                    .collect::<Vec<_>>();
+                labels.sort();
+
+                let state = match issue.state() {
+                    State::Open => "open",
+                    State::Closed {
+                        reason: CloseReason::Solved,
+                    } => "solved",
+                    State::Closed { .. } => "closed",
+                };
+                if format == Format::Text {
+                    table.push([
+                        term::format::highlight(id),
+                        issue.title().to_owned(),
+                        if issue.state() == State::Open {
+                            term::format::positive(state)
+                        } else {
+                            term::format::negative(state)
+                        },
+                        term::format::tertiary(issue.author().name()),
+                        term::format::dim(labels.join(", ")),
+                        comments.to_string(),
+                        term::format::dim(issue.timestamp()),
+                    ]);
+                    if table.len() == BATCH_SIZE {
+                        table.flush();
+                    }
+                } else if format == Format::Json {
+                    output.push(json::json!({
+                        "id": id.to_string(),
+                        "title": issue.title(),
+                        "state": state,
+                        "author": issue.author().name(),
+                        "labels": labels,
+                        "comments": comments,
+                        "created": issue.timestamp().to_rfc3339(),
+                    }));
+                } else {
+                    csv.push([
+                        id.to_string(),
+                        issue.title().to_owned(),
+                        state.to_owned(),
+                        issue.author().name(),
+                        labels.join(";"),
+                        comments.to_string(),
+                        issue.timestamp().to_rfc3339(),
+                    ]);
+                }
+            }
+            authors.write().ok();
+
+            match format {
+                Format::Text => table.render(),
+                Format::Csv => csv.render(),
+                Format::Json => term::output::json(&json::Value::Array(output))?,
             }
         }
         Operation::Delete { id } => {
             issues.remove(&project, &id)?;
         }
+        Operation::Import { from } => {
+            import(&issues, &project, from)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Import the issues of a GitLab project.
+fn import(issues: &IssueStore, project: &Urn, from: gitlab::Project) -> anyhow::Result<()> {
+    let client = gitlab::Client::new(&gitlab::Client::token()?, from);
+    let existing = issues.all(project)?;
+
+    let spinner = term::spinner(term::tr!(
+        "Fetching issues of {}...",
+        term::format::highlight(client.project())
+    ));
+    let items = match client.issues() {
+        Ok(items) => items,
+        Err(err) => return Err(spinner.error(err)),
+    };
+    spinner.finish();
+
+    let mut imported = 0;
+    for item in items {
+        if existing
+            .iter()
+            .any(|(_, issue)| item.is_imported_as(issue.description()))
+        {
+            continue;
+        }
+        let mut spinner = term::spinner(term::tr!(
+            "Importing #{} {}...",
+            item.iid,
+            term::format::italic(&item.title)
+        ));
+        let id = issues.create(
+            project,
+            &item.title,
+            &item.imported_description(),
+            &item.labels(),
+        )?;
+
+        // Threads become comments, and their notes, replies.
+        let threads = match client.issue_discussions(item.iid) {
+            Ok(threads) => threads,
+            Err(err) => return Err(spinner.error(err)),
+        };
+        for (ix, thread) in threads.iter().enumerate() {
+            if let Some((note, replies)) = thread.split_first() {
+                issues.comment(project, &id, &note.imported_body())?;
+
+                for reply in replies {
+                    issues.reply(project, &id, CommentId::from(ix), &reply.imported_body())?;
+                }
+            }
+        }
+        if item.state == "closed" {
+            issues.lifecycle(
+                project,
+                &id,
+                State::Closed {
+                    reason: CloseReason::Other,
+                },
+            )?;
+        }
+        spinner.message(term::tr!(
+            "Imported #{} {} as issue {}",
+            item.iid,
+            term::format::italic(&item.title),
+            term::format::highlight(fmt::cob(&id))
+        ));
+        spinner.finish();
+        imported += 1;
     }
 
+    term::blank();
+    term::success!("{} issue(s) imported", imported);
+
+    if imported > 0 {
+        term::tip!("To publish the imported issues, run `rad sync`.");
+    }
     Ok(())
 }
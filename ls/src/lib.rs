@@ -1,7 +1,14 @@
 use std::ffi::OsString;
+use std::str::FromStr;
 
-use radicle_common::args::{Args, Error, Help};
-use radicle_common::{profile, project};
+use anyhow::anyhow;
+
+use librad::git::Urn;
+
+use radicle_common::args::{self, Args, Error, Help};
+use radicle_common::cobs::issue::State;
+use radicle_common::cobs::{self, Timestamp};
+use radicle_common::{git, json, keys, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -11,54 +18,233 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad ls [<option>...]
+    rad ls [<query>] [<option>...]
+
+    Lists the projects in local storage, with their head, number of tracked peers,
+    open patches and issues, and when they were last updated, ie. the time of the
+    latest commit on any of their branches.
+
+    If a <query> is given, only the projects whose name contains it are listed,
+    ignoring case.
 
 Options
 
-    --help    Print help
+    --sort <field>      Sort projects by `name` or by last `updated`, most recent first
+    --format <format>   Output format: `table` (default) or `json`
+    --help              Print help
+
+    The output is JSON as well when `rad --json` is used.
 "#,
 };
 
-pub struct Options {}
+/// Order of the listed projects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sort {
+    Name,
+    Updated,
+}
+
+impl FromStr for Sort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "updated" => Ok(Self::Updated),
+            other => Err(anyhow!(term::tr!(
+                "unknown sort field '{}': expected `name` or `updated`",
+                other
+            ))),
+        }
+    }
+}
+
+/// Output format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => Err(anyhow!(term::tr!(
+                "unknown format '{}': expected `table` or `json`",
+                other
+            ))),
+        }
+    }
+}
+
+pub struct Options {
+    pub query: Option<String>,
+    pub sort: Sort,
+    pub format: Format,
+}
 
 impl Args for Options {
     fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_args(args);
+        let mut query = None;
+        let mut sort = Sort::Name;
+        let mut format = Format::Table;
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
+                Long("sort") => {
+                    let value = parser.value()?;
+
+                    sort = args::parse_value("sort", value)?;
+                }
+                Long("format") => {
+                    let value = parser.value()?;
+
+                    format = args::parse_value("format", value)?;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Value(val) if query.is_none() => {
+                    query = Some(val.to_string_lossy().to_lowercase());
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
 
-        Ok((Options {}, vec![]))
+        Ok((
+            Options {
+                query,
+                sort,
+                format,
+            },
+            vec![],
+        ))
     }
 }
 
-pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+/// A listed project.
+struct Entry {
+    meta: project::Metadata,
+    head: Option<git::Oid>,
+    updated: Option<Timestamp>,
+    peers: usize,
+    patches: usize,
+    issues: usize,
+}
+
+impl Entry {
+    fn to_json(&self) -> json::Value {
+        json::json!({
+            "urn": self.meta.urn.to_string(),
+            "name": self.meta.name,
+            "description": self.meta.description,
+            "defaultBranch": self.meta.default_branch.to_string(),
+            "head": self.head.map(|h| h.to_string()),
+            "updated": self.updated.map(|t| t.as_secs()),
+            "trackedPeers": self.peers,
+            "openPatches": self.patches,
+            "openIssues": self.issues,
+        })
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
-    let storage = profile::read_only(&profile)?;
-    let projs = project::list(&storage)?;
-    let mut table = term::Table::default();
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let cobs = cobs::store(&profile, &storage)?;
+    let mut entries = Vec::new();
+
+    for (urn, meta, head) in project::list(&storage)? {
+        if let Some(query) = &options.query {
+            if !meta.name.to_lowercase().contains(query.as_str()) {
+                continue;
+            }
+        }
+        let peers = project::tracked(&meta, &storage)?.len();
+        let patches = cobs.patches().proposed(&urn)?.count();
+        let issues = cobs
+            .issues()
+            .all(&urn)?
+            .into_iter()
+            .filter(|(_, i)| i.state() == State::Open)
+            .count();
+
+        entries.push(Entry {
+            updated: updated(&monorepo, &urn)?,
+            meta,
+            head,
+            peers,
+            patches,
+            issues,
+        });
+    }
+
+    match options.sort {
+        Sort::Name => entries.sort_by(|a, b| a.meta.name.cmp(&b.meta.name)),
+        Sort::Updated => entries.sort_by(|a, b| b.updated.cmp(&a.updated)),
+    }
+
+    if options.format == Format::Json || term::output::is_json() {
+        let entries = entries.iter().map(Entry::to_json).collect::<Vec<_>>();
+        term::output::json(&json::Value::Array(entries))?;
+
+        return Ok(());
+    }
 
-    for (urn, meta, head) in projs {
-        let head = head
+    let mut table = term::Table::default();
+    for entry in entries {
+        let head = entry
+            .head
             .map(|h| format!("{:.7}", h.to_string()))
             .unwrap_or_else(String::new);
 
         table.push([
-            term::format::bold(meta.name),
-            term::format::tertiary(urn),
+            term::format::bold(entry.meta.name),
+            term::format::tertiary(entry.meta.urn),
             term::format::secondary(head),
-            term::format::italic(meta.description),
+            term::format::dim(format!("{} peer(s)", entry.peers)),
+            format!("{} patch(es)", entry.patches),
+            format!("{} issue(s)", entry.issues),
+            term::format::dim(
+                entry
+                    .updated
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(String::new),
+            ),
+            term::format::italic(entry.meta.description),
         ]);
     }
     table.render();
 
     Ok(())
 }
+
+/// Get the time of the latest commit on any branch of a project, local or remote.
+fn updated(monorepo: &git::Repository, urn: &Urn) -> anyhow::Result<Option<Timestamp>> {
+    let namespace = format!("refs/namespaces/{}/refs", urn.encode_id());
+    let mut latest = None;
+
+    for glob in [
+        format!("{}/heads/*", namespace),
+        format!("{}/remotes/*/heads/*", namespace),
+    ] {
+        for r in monorepo.references_glob(&glob)? {
+            if let Ok(commit) = r?.peel_to_commit() {
+                latest = latest.max(Some(commit.time().seconds().max(0) as u64));
+            }
+        }
+    }
+    // Guard against commit times in the future.
+    let now = Timestamp::now().as_secs();
+
+    Ok(latest.map(|secs| Timestamp::new(secs.min(now))))
+}
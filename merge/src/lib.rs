@@ -7,8 +7,10 @@ use anyhow::{anyhow, Context};
 use radicle_common as common;
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::cobs::patch::{Patch, PatchId};
+use radicle_common::hooks::Event;
+use radicle_common::json;
 use radicle_common::patch::MergeStyle;
-use radicle_common::{cobs, git, keys, project};
+use radicle_common::{cobs, exit, git, keys, project};
 use radicle_terminal as term;
 
 use cobs::patch::RevisionIx;
@@ -23,7 +25,8 @@ Usage
     rad merge [<id>] [<option>...]
 
     To specify a patch to merge, use the fully qualified patch id
-    or an unambiguous prefix of it.
+    or an unambiguous prefix of it. If no id is given, the patch is
+    picked among the open patches, by typing part of its title.
 
 Options
 
@@ -45,7 +48,7 @@ const MERGE_HELP_MSG: &[&str] = &[
 
 #[derive(Debug)]
 pub struct Options {
-    pub id: cobs::Identifier,
+    pub id: Option<cobs::Identifier>,
     pub interactive: bool,
     pub revision: Option<RevisionIx>,
 }
@@ -71,7 +74,10 @@ impl Args for Options {
                     let value = parser.value()?;
                     let id =
                         RevisionIx::from_str(value.to_str().unwrap_or_default()).map_err(|_| {
-                            anyhow!("invalid revision number `{}`", value.to_string_lossy())
+                            anyhow!(term::tr!(
+                                "invalid revision number `{}`",
+                                value.to_string_lossy()
+                            ))
                         })?;
                     revision = Some(id);
                 }
@@ -82,7 +88,7 @@ impl Args for Options {
 
                     id = Some(
                         cobs::Identifier::from_str(val)
-                            .map_err(|_| anyhow!("invalid patch id '{}'", val))?,
+                            .map_err(|_| anyhow!(term::tr!("invalid patch id '{}'", val)))?,
                     );
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
@@ -91,7 +97,7 @@ impl Args for Options {
 
         Ok((
             Options {
-                id: id.ok_or_else(|| anyhow!("a patch id to merge must be provided"))?,
+                id,
                 interactive,
                 revision,
             },
@@ -104,13 +110,13 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     //
     // Setup
     //
-    let (urn, repo) = project::cwd()
-        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let (urn, repo) = project::cwd()?;
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
     let storage = keys::storage(&profile, signer)?;
-    let _project = project::get(&storage, &urn)?
-        .ok_or_else(|| anyhow!("couldn't load project {} from local state", urn))?;
+    let _project = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("couldn't load project {} from local state", urn))
+    })?;
     let cobs = cobs::store(&profile, &storage)?;
     let patches = cobs.patches();
 
@@ -121,9 +127,18 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     //
     // Get patch information
     //
-    let (patch_id, mut patch) = patches
-        .resolve::<Patch>(&urn, &options.id)?
-        .ok_or_else(|| anyhow!("couldn't find patch {} locally", &options.id))?;
+    let (patch_id, mut patch) = match &options.id {
+        Some(id) => patches.resolve::<Patch>(&urn, id)?.ok_or_else(|| {
+            exit::Error::NotFound(term::tr!("couldn't find patch {} locally", id))
+        })?,
+        None => term::patch::select(
+            term::tr!("Which patch do you want to merge?"),
+            &patches,
+            &urn,
+            &storage,
+        )?
+        .ok_or_else(|| anyhow!("a patch id to merge must be provided"))?,
+    };
     patch.author.resolve(&storage).ok();
 
     let head = repo.head()?;
@@ -134,10 +149,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .target()
         .ok_or_else(|| anyhow!("cannot merge into detatched head; aborting"))?;
     let revision_id = options.revision.unwrap_or_else(|| patch.version());
-    let revision = patch
-        .revisions
-        .get(revision_id)
-        .ok_or_else(|| anyhow!("revision R{} does not exist", revision_id))?;
+    let revision = patch.revisions.get(revision_id).ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("revision R{} does not exist", revision_id))
+    })?;
 
     //
     // Analyze merge
@@ -165,7 +179,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
         if index.has_conflicts() {
             return Err(common::Error::WithHint {
-                err: anyhow!("patch conflicts with {}", branch),
+                err: anyhow!(term::tr!("patch conflicts with {}", branch)),
                 hint: "Patch must be rebased before it can be merged.",
             }
             .into());
@@ -182,11 +196,11 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     } else if merge.is_unborn() {
         anyhow::bail!("HEAD does not point to a valid commit");
     } else {
-        anyhow::bail!(
+        anyhow::bail!(term::tr!(
             "no merge is possible between {} and {}",
             head_oid,
             revision.oid
-        );
+        ));
     };
 
     let merge_style_pretty = match merge_style {
@@ -212,8 +226,8 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         merge_style_pretty
     );
 
-    if options.interactive && !term::confirm("Confirm?") {
-        anyhow::bail!("merge aborted by user");
+    if options.interactive && !term::confirm("Confirm?")? {
+        anyhow::bail!(exit::Error::Aborted("merge aborted by user".to_owned()));
     }
 
     //
@@ -241,6 +255,18 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     //
     // TODO: Don't allow merging the same revision twice?
     patches.merge(&urn, &patch_id, revision_id, head_oid.into())?;
+    term::hooks::emit(
+        &profile,
+        Event::PatchMerged,
+        &urn,
+        json::json!({
+            "id": patch_id.to_string(),
+            "title": patch.title,
+            "revision": revision_id,
+            "head": revision.oid.to_string(),
+            "branch": branch,
+        }),
+    );
 
     term::success!(
         "Patch state updated, use {} to publish",
@@ -301,12 +327,12 @@ fn merge_commit(
             .filter(|l| !l.starts_with('#'))
             .collect::<Vec<_>>()
             .join("\n"),
-        None => anyhow::bail!("user aborted merge"),
+        None => anyhow::bail!(exit::Error::Aborted("user aborted merge".to_owned())),
     };
 
     // Empty message aborts merge.
     if merge_msg.trim().is_empty() {
-        anyhow::bail!("user aborted merge");
+        anyhow::bail!(exit::Error::Aborted("user aborted merge".to_owned()));
     }
 
     // Perform merge (nb. this does not commit).
@@ -0,0 +1,369 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::profile::Profile;
+use radicle_common::sync::Mode;
+use radicle_common::{git, identity, keys, offline, project, sync};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "mirror",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad mirror add <git-url> [<path>] [--name <name>] [--description <text>] [--no-sync]
+    rad mirror sync [<path>...] [--no-sync]
+
+    Mirrors a repository hosted elsewhere, eg. on GitHub or GitLab, as a radicle project,
+    so that it can be seeded on the network.
+
+    With `add`, the repository is cloned into the given path, and a project is created
+    from it, with the upstream default branch as its default branch. All upstream
+    branches and tags are then published, and synced to your seeds.
+
+    With `sync`, upstream is fetched, and its branches and tags are republished as they
+    are: branches and tags that were force-pushed upstream are force-pushed, and those
+    that were deleted upstream are deleted. By default, the mirror in the current
+    directory is synced. Run it periodically, eg. from cron, to keep mirrors up to date.
+
+Options
+
+    --name <name>           Name of the project (default: the name of the repository)
+    --description <text>    Description of the project (default: "Mirror of <git-url>")
+    --no-sync               Don't sync the project to seeds after publishing
+    --help                  Print help
+"#,
+};
+
+/// Git configuration key holding the name of the upstream remote of a mirror.
+pub const CONFIG_UPSTREAM: &str = "rad.mirror.upstream";
+/// Name of the upstream remote, as set by `git clone`.
+const UPSTREAM: &str = "origin";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Add,
+    Sync,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Add {
+        url: String,
+        path: Option<PathBuf>,
+        name: Option<String>,
+        description: Option<String>,
+    },
+    Sync {
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub sync: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut url: Option<String> = None;
+        let mut name: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut paths = Vec::new();
+        let mut sync = sync::is_auto();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
+                Long("name") if op == Some(OperationName::Add) => {
+                    name = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("description") if op == Some(OperationName::Add) => {
+                    description = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "a" | "add" => op = Some(OperationName::Add),
+                    "s" | "sync" => op = Some(OperationName::Sync),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if op == Some(OperationName::Add) && url.is_none() => {
+                    url = Some(val.to_string_lossy().to_string());
+                }
+                Value(val) if op == Some(OperationName::Add) && paths.is_empty() => {
+                    paths.push(PathBuf::from(val));
+                }
+                Value(val) if op == Some(OperationName::Sync) => {
+                    paths.push(PathBuf::from(val));
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::Sync) {
+            OperationName::Add => Operation::Add {
+                url: url
+                    .ok_or_else(|| anyhow!("the git URL of the repository must be provided"))?,
+                path: paths.pop(),
+                name,
+                description,
+            },
+            OperationName::Sync => Operation::Sync { paths },
+        };
+
+        Ok((Options { op, sync }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    // Mirrors are cloned from, and fetch, their upstream repository.
+    offline::check("mirroring")?;
+
+    let profile = ctx.profile()?;
+
+    match options.op {
+        Operation::Add {
+            url,
+            path,
+            name,
+            description,
+        } => add(&url, path, name, description, options.sync, &profile),
+        Operation::Sync { paths } => {
+            let paths = if paths.is_empty() {
+                vec![PathBuf::from(".")]
+            } else {
+                paths
+            };
+            let mut failed = 0;
+
+            for path in &paths {
+                // When syncing several mirrors, a failing mirror shouldn't prevent the
+                // others from being synced.
+                if let Err(err) = update(path, options.sync, &profile) {
+                    if paths.len() == 1 {
+                        return Err(err);
+                    }
+                    term::error(term::tr!("{}: {}", path.display(), err));
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!(term::tr!(
+                    "{} of {} mirror(s) failed to sync",
+                    failed,
+                    paths.len()
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Clone an upstream repository, and create a project from it.
+fn add(
+    url: &str,
+    path: Option<PathBuf>,
+    name: Option<String>,
+    description: Option<String>,
+    sync: bool,
+    profile: &Profile,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer.clone())?;
+    let path = match path {
+        Some(path) => path,
+        None => PathBuf::from(repository_name(url).ok_or_else(|| Error::WithHint {
+            err: anyhow!(term::tr!(
+                "the repository name could not be determined from '{}'",
+                url
+            )),
+            hint: "hint: specify the path to clone the repository into",
+        })?),
+    };
+    if path.exists() {
+        anyhow::bail!(term::tr!("the path '{}' already exists", path.display()));
+    }
+
+    let spinner = term::spinner(term::tr!("Cloning {}...", term::format::highlight(url)));
+    if let Err(err) = git::clone(url, &path) {
+        return Err(spinner.error(err));
+    }
+    spinner.finish();
+
+    let repo = git::Repository::open(&path)?;
+    let branch = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(|h| h.to_owned()))
+        .ok_or_else(|| anyhow!("upstream repository has no default branch"))?;
+    let name = match name {
+        Some(name) => name,
+        None => path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("the project name could not be determined from the path"))?,
+    };
+    let description = description.unwrap_or_else(|| format!("Mirror of {}", url));
+
+    // The symbolic `HEAD` of the upstream remote would otherwise be published as a
+    // branch named `HEAD`.
+    git::git(&path, ["remote", "set-head", UPSTREAM, "--delete"]).ok();
+    repo.config()?.set_str(CONFIG_UPSTREAM, UPSTREAM)?;
+
+    let spinner = term::spinner("Creating project...");
+    let payload = project::payload(name, description, branch);
+    let proj = match project::create(payload, &storage).and_then(|proj| {
+        project::init(&proj, &repo, &storage, profile.paths(), signer).map(|_| proj)
+    }) {
+        Ok(proj) => proj,
+        Err(err) => return Err(spinner.error(err)),
+    };
+    spinner.message(term::tr!(
+        "Project {} created",
+        term::format::highlight(&proj.subject().name)
+    ));
+    spinner.finish();
+
+    publish(&path)?;
+
+    term::blank();
+    term::info!(
+        "Your project id is {}.",
+        term::format::highlight(&proj.urn().to_string())
+    );
+    term::blank();
+
+    if sync {
+        seed(proj.urn(), profile)?;
+    }
+    term::tip!(
+        "To keep the mirror up to date, run `rad mirror sync {}` periodically.",
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Fetch the upstream of a mirror, and republish its branches and tags.
+fn update(path: &Path, sync: bool, profile: &Profile) -> anyhow::Result<()> {
+    let repo = git::Repository::open(path)
+        .with_context(|| term::tr!("'{}' is not a git repository", path.display()))?;
+    let upstream = repo
+        .config()?
+        .get_string(CONFIG_UPSTREAM)
+        .map_err(|_| Error::WithHint {
+            err: anyhow!(term::tr!("'{}' is not a mirror", path.display())),
+            hint: "hint: create a mirror with `rad mirror add <git-url>`",
+        })?;
+    let urn = git::rad_remote(&repo)?.url.urn;
+
+    let spinner = term::spinner(term::tr!(
+        "Fetching {} into {}...",
+        term::format::highlight(&upstream),
+        term::format::highlight(path.display())
+    ));
+    if let Err(err) = git::git(
+        path,
+        [
+            "fetch",
+            "--prune",
+            "--prune-tags",
+            "--tags",
+            "--force",
+            &upstream,
+        ],
+    ) {
+        return Err(spinner.error(err));
+    }
+    spinner.finish();
+
+    publish(path)?;
+
+    if sync {
+        seed(urn, profile)?;
+    }
+    Ok(())
+}
+
+/// Publish the branches and tags of the upstream remote to the "rad" remote, ie. to
+/// storage, deleting those that no longer exist upstream.
+fn publish(path: &Path) -> anyhow::Result<()> {
+    let repo = git::Repository::open(path)?;
+    let upstream = repo
+        .config()?
+        .get_string(CONFIG_UPSTREAM)
+        .unwrap_or_else(|_| UPSTREAM.to_owned());
+    let branches = format!("refs/remotes/{}/*:refs/heads/*", upstream);
+
+    let spinner = term::spinner("Publishing branches and tags...");
+    match git::git(
+        path,
+        [
+            "push",
+            "--force",
+            "--prune",
+            "rad",
+            &branches,
+            "refs/tags/*:refs/tags/*",
+        ],
+    ) {
+        Ok(output) if output.contains("Everything up-to-date") => {
+            spinner.message("Mirror is up to date");
+            spinner.finish();
+        }
+        Ok(_) => {
+            spinner.message("Branches and tags published");
+            spinner.finish();
+        }
+        Err(err) => return Err(spinner.error(err)),
+    }
+    Ok(())
+}
+
+/// Sync a project to the configured seeds.
+fn seed(urn: Urn, profile: &Profile) -> anyhow::Result<()> {
+    rad_sync::run(
+        rad_sync::Options {
+            origin: Some(identity::Origin::from_urn(urn)),
+            mode: Mode::Push,
+            refs: sync::Refs::All,
+            ..rad_sync::Options::default()
+        },
+        profile.clone(),
+    )
+}
+
+/// Get the name of a repository from its URL, eg. `heartwood` for
+/// `https://github.com/radicle-dev/heartwood.git`.
+fn repository_name(url: &str) -> Option<&str> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(|c: char| c == '/' || c == ':')
+        .next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
@@ -0,0 +1,167 @@
+//! Patch notifications by email.
+//!
+//! Emails are sent with `git send-email`, and thus over the SMTP server configured in
+//! git, eg. with `sendemail.smtpServer`. The recipients are stored in the working copy's
+//! git config, under `rad.notify.email.to`.
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context as _};
+
+use radicle_common::cobs::patch::{PatchId, RevisionIx};
+use radicle_common::cobs::Timestamp;
+use radicle_common::{fmt, git, offline, project};
+
+/// Git configuration key holding the recipients of patch notifications.
+pub const CONFIG_TO: &str = "rad.notify.email.to";
+
+/// A patch revision to notify recipients of.
+pub struct Patch<'a> {
+    pub project: &'a project::Metadata,
+    pub id: PatchId,
+    pub revision: RevisionIx,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub base: git::Oid,
+    pub head: git::Oid,
+}
+
+/// Get the recipients of patch notifications of a working copy.
+pub fn recipients(repo: &git::Repository) -> anyhow::Result<Vec<String>> {
+    let config = repo.config()?;
+    let mut to = Vec::new();
+
+    if let Ok(entries) = config.multivar(CONFIG_TO, None) {
+        for entry in (&entries).flatten() {
+            if let Some(value) = entry.value() {
+                to.push(value.to_owned());
+            }
+        }
+    }
+    Ok(to)
+}
+
+/// Set the recipients of patch notifications of a working copy. If empty, patch
+/// notifications are turned off.
+pub fn set_recipients(repo: &git::Repository, to: &[String]) -> anyhow::Result<()> {
+    let mut config = repo.config()?;
+
+    // Nb. Removing fails if there are no recipients yet.
+    config.remove_multivar(CONFIG_TO, ".*").ok();
+    for addr in to {
+        config.set_multivar(CONFIG_TO, "^$", addr)?;
+    }
+    Ok(())
+}
+
+/// Email a patch revision to the configured recipients, if any. Returns the recipients.
+/// Fails in offline mode, if there are recipients.
+pub fn notify(repo: &git::Repository, patch: &Patch) -> anyhow::Result<Vec<String>> {
+    let to = recipients(repo)?;
+    if to.is_empty() {
+        return Ok(to);
+    }
+    offline::check("emailing patches")?;
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working copy"))?;
+    let message = message(repo, workdir, patch)?;
+    let path = std::env::temp_dir().join(format!(
+        "rad-notify-{}-{}.eml",
+        patch.id,
+        Timestamp::now().as_secs()
+    ));
+    fs::write(&path, message).context("could not write email")?;
+
+    let mut args = vec![
+        String::from("send-email"),
+        String::from("--confirm=never"),
+        String::from("--quiet"),
+        String::from("--suppress-cc=all"),
+        String::from("--8bit-encoding=UTF-8"),
+    ];
+    args.extend(to.iter().map(|addr| format!("--to={}", addr)));
+    args.push(path.to_string_lossy().to_string());
+
+    let result = git::git(workdir, args);
+    fs::remove_file(&path).ok();
+    result.context("`git send-email` failed")?;
+
+    Ok(to)
+}
+
+/// Compose the email of a patch revision: a summary, with the revision attached as an
+/// mbox of patches, that can be applied with `git am`.
+fn message(repo: &git::Repository, workdir: &Path, patch: &Patch) -> anyhow::Result<String> {
+    let signature = repo.signature()?;
+    let from = format!(
+        "{} <{}>",
+        signature.name().unwrap_or_default(),
+        signature.email().unwrap_or_default()
+    );
+    let range = format!("{}..{}", patch.base, patch.head);
+    let log = git::git(workdir, ["log", "--oneline", "--no-decorate", &range])?;
+    let stat = git::git(workdir, ["diff", "--stat", &range])?;
+    let mbox = git::git(workdir, ["format-patch", "--stdout", &range])?;
+    let commits = log.lines().count();
+    let name = format!("{}-R{}.patch", fmt::cob(&patch.id), patch.revision);
+    let boundary = format!("rad-{}-{}", patch.id, patch.revision);
+    let version = if patch.revision == 0 {
+        String::from("PATCH")
+    } else {
+        format!("PATCH R{}", patch.revision)
+    };
+
+    let mut summary = format!(
+        "{}\n\n{}",
+        patch.title,
+        if patch.description.trim().is_empty() {
+            "No description."
+        } else {
+            patch.description.trim()
+        }
+    );
+    summary.push_str(&format!(
+        "\n\n---\nPatch {} (R{}) of {} proposes {} commit(s) on {}:\n\n{}\n{}\n",
+        patch.id,
+        patch.revision,
+        patch.project.name,
+        commits,
+        patch.project.default_branch,
+        log.trim_end(),
+        stat.trim_end()
+    ));
+    summary.push_str(&format!(
+        "\nThe commits are attached, and can be applied with `git am {}`.\n\
+         The patch can also be fetched with radicle, from project {}.\n",
+        name, patch.project.urn
+    ));
+
+    Ok(format!(
+        "From: {from}\n\
+         Subject: [{version}] {title}\n\
+         MIME-Version: 1.0\n\
+         Content-Type: multipart/mixed; boundary=\"{boundary}\"\n\
+         \n\
+         --{boundary}\n\
+         Content-Type: text/plain; charset=utf-8\n\
+         Content-Transfer-Encoding: 8bit\n\
+         \n\
+         {summary}\n\
+         --{boundary}\n\
+         Content-Type: text/x-diff; charset=utf-8; name=\"{name}\"\n\
+         Content-Disposition: attachment; filename=\"{name}\"\n\
+         Content-Transfer-Encoding: 8bit\n\
+         \n\
+         {mbox}\n\
+         --{boundary}--\n",
+        from = from,
+        version = version,
+        title = patch.title.replace('\n', " "),
+        boundary = boundary,
+        summary = summary,
+        name = name,
+        mbox = mbox,
+    ))
+}
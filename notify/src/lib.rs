@@ -0,0 +1,332 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::hooks::Event;
+use radicle_common::notify::{self, Channel, Channels, Kind};
+use radicle_common::{exit, json, project, Url};
+use radicle_terminal as term;
+
+pub mod email;
+
+pub const HELP: Help = Help {
+    name: "notify",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad notify ls
+    rad notify add <kind> <target> [--project <urn>]
+    rad notify rm <target>
+    rad notify test <target>
+    rad notify email [--to <address>]... [--clear]
+
+    Notifies people of project activity.
+
+Channels
+
+    Channels are chat rooms that are sent a message when a patch is opened with
+    `rad patch` or merged with `rad merge`, and when an issue is opened or closed with
+    `rad issue`. Messages link to the patch or issue on the web gateway of the first
+    configured seed. The kind of a channel is one of:
+
+        matrix      A Matrix room webhook, eg. of the hookshot bridge
+        slack       A Slack incoming webhook
+        webhook     Any other HTTP(S) endpoint, posted a JSON message
+
+    Channels are stored in the profile, and are notified of the activity of all
+    projects, unless limited to a project with `--project`. `test` sends a test
+    message.
+
+Email
+
+    When a patch is created or updated with `rad patch`, the configured recipients,
+    eg. the mailing list of the project, are emailed a summary of the patch, with its
+    commits attached as an mbox that can be applied with `git am`. This lets people
+    who contribute by email follow the patches of the project.
+
+    Emails are sent with `git send-email`, which must be configured with an SMTP
+    server, eg.
+
+        git config --global sendemail.smtpServer smtp.example.com
+
+    With no options, the configured recipients are shown. Recipients are stored in
+    the git config of the working copy, under `rad.notify.email.to`.
+
+Options
+
+    --project <urn>     Only notify the channel of the activity of the given project
+    --to <address>      Email patch notifications to the given address; replaces
+                        the configured recipients
+    --clear             Stop emailing patch notifications
+    --help              Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Add,
+    Remove,
+    Test,
+    List,
+    Email,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Add {
+        kind: Kind,
+        target: Url,
+        project: Option<Urn>,
+    },
+    Remove {
+        target: Url,
+    },
+    Test {
+        target: Url,
+    },
+    List,
+    Email {
+        to: Vec<String>,
+        clear: bool,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut kind: Option<Kind> = None;
+        let mut target: Option<Url> = None;
+        let mut project: Option<Urn> = None;
+        let mut to: Vec<String> = Vec::new();
+        let mut clear = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("project") if op == Some(OperationName::Add) => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    project = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                Long("to") if op == Some(OperationName::Email) => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy().trim().to_owned();
+
+                    if !val.contains('@') {
+                        anyhow::bail!(term::tr!("invalid email address '{}'", val));
+                    }
+                    to.push(val);
+                }
+                Long("clear") if op == Some(OperationName::Email) => {
+                    clear = true;
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "a" | "add" => op = Some(OperationName::Add),
+                    "r" | "rm" => op = Some(OperationName::Remove),
+                    "t" | "test" => op = Some(OperationName::Test),
+                    "l" | "ls" => op = Some(OperationName::List),
+                    "email" => op = Some(OperationName::Email),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if op == Some(OperationName::Add) && kind.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    kind = Some(Kind::from_str(&val).map_err(|err| Error::WithHint {
+                        err,
+                        hint: "hint: channels are one of `matrix`, `slack` or `webhook`",
+                    })?);
+                }
+                Value(val)
+                    if matches!(
+                        op,
+                        Some(OperationName::Add | OperationName::Remove | OperationName::Test)
+                    ) && target.is_none() =>
+                {
+                    let val = val.to_string_lossy();
+                    let parsed = Url::parse(&val)
+                        .map_err(|_| anyhow!(term::tr!("invalid URL '{}'", val)))?;
+
+                    if !matches!(parsed.scheme(), "http" | "https") {
+                        anyhow::bail!(term::tr!(
+                            "invalid URL '{}': channels must be HTTP(S) URLs",
+                            val
+                        ));
+                    }
+                    target = Some(parsed);
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+        if clear && !to.is_empty() {
+            anyhow::bail!("`--clear` and `--to` cannot be used together");
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Add => Operation::Add {
+                kind: kind.ok_or_else(|| anyhow!("the kind of the channel must be provided"))?,
+                target: target
+                    .ok_or_else(|| anyhow!("the target URL of the channel must be provided"))?,
+                project,
+            },
+            OperationName::Remove => Operation::Remove {
+                target: target
+                    .ok_or_else(|| anyhow!("the target URL of the channel must be provided"))?,
+            },
+            OperationName::Test => Operation::Test {
+                target: target
+                    .ok_or_else(|| anyhow!("the target URL of the channel must be provided"))?,
+            },
+            OperationName::List => Operation::List,
+            OperationName::Email => Operation::Email { to, clear },
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let mut channels = Channels::load(&profile)?;
+
+    match options.op {
+        Operation::Add {
+            kind,
+            target,
+            project,
+        } => {
+            if channels.channel.iter().any(|c| c.target == target) {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("channel {} is already configured", target)),
+                    hint: "hint: remove it first with `rad notify rm`",
+                }
+                .into());
+            }
+            channels.channel.push(Channel {
+                kind,
+                target: target.clone(),
+                project,
+            });
+            channels.write(&profile)?;
+
+            term::success!(
+                "{} channel {} added",
+                kind,
+                term::format::highlight(&target)
+            );
+            term::tip!(
+                "To check that it is reachable, run `rad notify test {}`.",
+                target
+            );
+        }
+        Operation::Remove { target } => {
+            let len = channels.channel.len();
+
+            channels.channel.retain(|c| c.target != target);
+            if channels.channel.len() == len {
+                anyhow::bail!(term::tr!("channel {} is not configured", target));
+            }
+            channels.write(&profile)?;
+
+            term::success!("Channel {} removed", term::format::highlight(target));
+        }
+        Operation::Test { target } => {
+            let channel = channels
+                .channel
+                .iter()
+                .find(|c| c.target == target)
+                .ok_or_else(|| anyhow!(term::tr!("channel {} is not configured", target)))?;
+            let urn = match &channel.project {
+                Some(urn) => urn.clone(),
+                None => project::cwd()
+                    .map(|(urn, _)| urn)
+                    .map_err(|_| Error::WithHint {
+                        err: anyhow!("a project is needed to test the channel"),
+                        hint: "hint: run this command within a project working copy",
+                    })?,
+            };
+            let message = notify::message(&profile, Event::Ping, &urn, &json::json!({}))
+                .ok_or_else(|| anyhow!("no message to send"))?;
+
+            let spinner = term::spinner(term::tr!(
+                "Sending test message to {}...",
+                term::format::highlight(&target)
+            ));
+            if let Err(err) = channel.post(&urn, &message) {
+                return Err(spinner.error(err));
+            }
+            spinner.finish();
+        }
+        Operation::List => {
+            let mut table = term::Table::default();
+
+            for channel in &channels.channel {
+                table.push([
+                    term::format::highlight(&channel.target),
+                    channel.kind.to_string(),
+                    channel
+                        .project
+                        .as_ref()
+                        .map(|urn| term::format::tertiary(urn))
+                        .unwrap_or_else(|| term::format::dim("all projects")),
+                ]);
+            }
+            if channels.channel.is_empty() {
+                term::info!("{}", term::format::italic("No channels configured."));
+                term::tip!("Add a channel with `rad notify add <kind> <target>`.");
+            } else {
+                table.render();
+            }
+        }
+        Operation::Email { to, clear } => {
+            let (_, repo) = project::cwd().map_err(|_| Error::WithHint {
+                err: exit::Error::NotProject.into(),
+                hint: "hint: run this command within a project working copy",
+            })?;
+
+            if clear {
+                email::set_recipients(&repo, &[])?;
+                term::success!("Patch notifications will no longer be emailed");
+            } else if !to.is_empty() {
+                email::set_recipients(&repo, &to)?;
+                term::success!(
+                    "Patch notifications will be emailed to {}",
+                    term::format::highlight(to.join(", "))
+                );
+            } else {
+                let to = email::recipients(&repo)?;
+
+                if to.is_empty() {
+                    term::info!("Patch notifications are not emailed");
+                } else {
+                    for addr in to {
+                        term::info!("{}", term::format::highlight(addr));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
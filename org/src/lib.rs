@@ -0,0 +1,396 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::identities::person;
+use librad::git::storage::Storage;
+use librad::git::Urn;
+use librad::identities::Person;
+use librad::PeerId;
+
+use radicle_common::alias::Aliases;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::identity::quorum;
+use radicle_common::org::{self, Org};
+use radicle_common::{exit, fmt, keys};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "org",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad org create <name> [--member <peer-id>]... [--description <text>]
+    rad org list
+    rad org show <urn>
+    rad org add-member <urn> <peer-id>
+    rad org remove-member <urn> <peer-id>
+    rad org sign <urn> <peer-id>
+
+    An org is an identity held by the keys of its members, eg. a team. Orgs can be
+    project delegates, with `rad delegate add <urn>`, to make a project owned by
+    the team rather than by a single person.
+
+    You are always a member of the orgs you create. Changes to the members of an
+    org create a new revision of it, signed by you, which only takes effect once a
+    majority of the current members has signed it. The other members sign it by
+    fetching it and running `rad org sign <urn> <peer-id>`, with the peer id of
+    the member who proposed it.
+
+    To publish an org, or a new revision of it, run `rad sync <urn>`.
+
+Options
+
+    --member <peer-id>      Add a member to the org being created
+    --description <text>    Describe the org being created
+    --help                  Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Create,
+    List,
+    Show,
+    AddMember,
+    RemoveMember,
+    Sign,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Create {
+        name: String,
+        members: Vec<PeerId>,
+        description: Option<String>,
+    },
+    List,
+    Show {
+        urn: Urn,
+    },
+    AddMember {
+        urn: Urn,
+        peer: PeerId,
+    },
+    RemoveMember {
+        urn: Urn,
+        peer: PeerId,
+    },
+    Sign {
+        urn: Urn,
+        peer: PeerId,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut values: Vec<String> = Vec::new();
+        let mut members: Vec<PeerId> = Vec::new();
+        let mut description: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("member") if op == Some(OperationName::Create) => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let peer = PeerId::from_str(&val)
+                        .map_err(|_| anyhow!(term::tr!("invalid peer id '{}'", val)))?;
+
+                    members.push(peer);
+                }
+                Long("description") if op == Some(OperationName::Create) => {
+                    description = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "create" | "new" => op = Some(OperationName::Create),
+                    "list" | "ls" => op = Some(OperationName::List),
+                    "show" => op = Some(OperationName::Show),
+                    "add-member" => op = Some(OperationName::AddMember),
+                    "remove-member" => op = Some(OperationName::RemoveMember),
+                    "sign" => op = Some(OperationName::Sign),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if values.len() < 2 && op != Some(OperationName::List) => {
+                    values.push(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let mut values = values.into_iter();
+        let urn = |val: Option<String>| -> anyhow::Result<Urn> {
+            let val = val.ok_or_else(|| anyhow!("an org URN must be specified"))?;
+            Urn::from_str(&val).map_err(|_| anyhow!(term::tr!("invalid org URN '{}'", val)))
+        };
+        let peer = |val: Option<String>| -> anyhow::Result<PeerId> {
+            let val = val.ok_or_else(|| anyhow!("a peer id must be specified"))?;
+            PeerId::from_str(&val).map_err(|_| anyhow!(term::tr!("invalid peer id '{}'", val)))
+        };
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Create => Operation::Create {
+                name: values
+                    .next()
+                    .ok_or_else(|| anyhow!("an org name must be specified"))?,
+                members,
+                description,
+            },
+            OperationName::List => Operation::List,
+            OperationName::Show => Operation::Show {
+                urn: urn(values.next())?,
+            },
+            OperationName::AddMember => Operation::AddMember {
+                urn: urn(values.next())?,
+                peer: peer(values.next())?,
+            },
+            OperationName::RemoveMember => Operation::RemoveMember {
+                urn: urn(values.next())?,
+                peer: peer(values.next())?,
+            },
+            OperationName::Sign => Operation::Sign {
+                urn: urn(values.next())?,
+                peer: peer(values.next())?,
+            },
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer.clone())?;
+
+    match options.op {
+        Operation::Create {
+            name,
+            members,
+            description,
+        } => {
+            let members = members
+                .into_iter()
+                .filter(|peer| peer != storage.peer_id())
+                .map(|peer| *peer.as_public_key())
+                .collect();
+            let org = org::create(
+                &profile,
+                &name,
+                Org { description },
+                members,
+                signer,
+                &storage,
+            )?;
+
+            term::success!(
+                "Org {} created with {} member(s)",
+                term::format::highlight(&name),
+                org.delegations().len()
+            );
+            term::info!(
+                "{} {}",
+                term::format::dim("URN"),
+                term::format::tertiary(org.urn())
+            );
+            term::tip!(
+                "To publish the org, run `rad sync {}`. To make it a project delegate, run `rad delegate add {}`.",
+                org.urn(),
+                org.urn()
+            );
+        }
+        Operation::List => {
+            list(&storage)?;
+        }
+        Operation::Show { urn } => {
+            show(&storage, &urn)?;
+        }
+        Operation::AddMember { urn, peer } => {
+            let mut keys = members(&storage, &urn)?;
+
+            if keys.contains(&peer) {
+                anyhow::bail!(term::tr!("{} is already a member", peer));
+            }
+            keys.push(peer);
+
+            let org = org::set_members(&storage, &urn, keys.iter().map(|p| *p.as_public_key()))?;
+            term::success!("Member {} added", term::format::tertiary(fmt::peer(&peer)));
+            status(&storage, &org)?;
+        }
+        Operation::RemoveMember { urn, peer } => {
+            let mut keys = members(&storage, &urn)?;
+            let len = keys.len();
+
+            keys.retain(|p| *p != peer);
+            if keys.len() == len {
+                anyhow::bail!(term::tr!("{} is not a member", peer));
+            }
+            if keys.is_empty() {
+                anyhow::bail!("the last member of an org can't be removed");
+            }
+
+            let org = org::set_members(&storage, &urn, keys.iter().map(|p| *p.as_public_key()))?;
+            term::success!(
+                "Member {} removed",
+                term::format::tertiary(fmt::peer(&peer))
+            );
+            status(&storage, &org)?;
+        }
+        Operation::Sign { urn, peer } => {
+            org::get(&storage, &urn)?
+                .ok_or_else(|| exit::Error::NotFound(term::tr!("org {} not found", urn)))?;
+
+            let org = person::merge(&storage, &urn, peer)?;
+            term::success!(
+                "Signed the revision proposed by {}",
+                term::format::tertiary(fmt::peer(&peer))
+            );
+            status(&storage, &org)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the current members of an org.
+fn members(storage: &Storage, urn: &Urn) -> anyhow::Result<Vec<PeerId>> {
+    let (org, _) = org::get(storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(term::tr!("org {} not found", urn)))?;
+    let members = org
+        .delegations()
+        .iter()
+        .map(|pk| PeerId::from(*pk))
+        .collect::<Vec<_>>();
+
+    if !members.contains(storage.peer_id()) {
+        anyhow::bail!(term::tr!("you are not a member of org {}", urn));
+    }
+    Ok(members)
+}
+
+/// Print whether the latest revision of an org has taken effect.
+fn status(storage: &Storage, latest: &Person) -> anyhow::Result<()> {
+    let verified = person::verify(storage, &latest.urn())?;
+
+    if verified.as_ref().map(|v| v.revision) == Some(latest.revision) {
+        term::info!("The new revision is in effect.");
+    } else {
+        // The quorum is that of the revision currently in effect.
+        let members = verified
+            .map(|v| v.delegations().len())
+            .unwrap_or_else(|| latest.delegations().len());
+
+        term::info!(
+            "The new revision has {} of the {} signatures it needs to take effect.",
+            latest.signatures.len(),
+            quorum(members)
+        );
+        term::tip!(
+            "Other members can sign it with `rad org sign {} <your-peer-id>`.",
+            latest.urn()
+        );
+    }
+    term::tip!("To publish the revision, run `rad sync {}`.", latest.urn());
+
+    Ok(())
+}
+
+fn list(storage: &Storage) -> anyhow::Result<()> {
+    let orgs = org::list(storage)?;
+
+    if orgs.is_empty() {
+        term::info!("No orgs found.");
+        term::tip!("To create one, run `rad org create <name>`.");
+        return Ok(());
+    }
+    let mut table = term::Table::default();
+
+    for (org, _) in orgs {
+        let member = org
+            .delegations()
+            .iter()
+            .any(|pk| PeerId::from(*pk) == *storage.peer_id());
+
+        table.push([
+            term::format::bold(org.subject().name.to_string()),
+            term::format::tertiary(org.urn()),
+            term::format::dim(format!("{} member(s)", org.delegations().len())),
+            if member {
+                term::format::badge_primary("member")
+            } else {
+                String::new()
+            },
+        ]);
+    }
+    table.render();
+
+    Ok(())
+}
+
+fn show(storage: &Storage, urn: &Urn) -> anyhow::Result<()> {
+    let (latest, meta) = org::get(storage, urn)?
+        .ok_or_else(|| exit::Error::NotFound(term::tr!("org {} not found", urn)))?;
+    let verified = person::verify(storage, urn)?;
+    let current = verified
+        .as_ref()
+        .map(|v| v.delegations().len())
+        .unwrap_or_else(|| latest.delegations().len());
+    let aliases = Aliases::cwd();
+    let mut table = term::Table::default();
+
+    table.push([
+        String::from("Name"),
+        term::format::bold(latest.subject().name.to_string()),
+    ]);
+    table.push([String::from("URN"), term::format::tertiary(urn)]);
+    if let Some(description) = meta.description {
+        table.push([String::from("Description"), description]);
+    }
+    table.push([
+        String::from("Quorum"),
+        format!("{} of {} member(s)", quorum(current), current),
+    ]);
+    table.render_tree();
+    term::blank();
+
+    let mut members = term::Table::default();
+    for pk in latest.delegations().iter() {
+        let peer = PeerId::from(*pk);
+
+        members.push([
+            term::format::tertiary(peer.default_encoding()),
+            term::format::dim(aliases.get(&peer).unwrap_or_default()),
+            if peer == *storage.peer_id() {
+                term::format::badge_primary("you")
+            } else {
+                String::new()
+            },
+        ]);
+    }
+    members.render();
+
+    if verified.map(|v| v.revision) != Some(latest.revision) {
+        term::blank();
+        term::warning(&term::tr!(
+            "The latest revision has {} of the {} signatures it needs, and is not yet in effect",
+            latest.signatures.len(),
+            quorum(current),
+        ));
+    }
+    Ok(())
+}
@@ -1,12 +1,14 @@
 #![allow(clippy::or_fun_call)]
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::for_kv_map)]
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::ffi::OsString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::anyhow;
+use rayon::prelude::*;
 
 use common::cobs::patch::Verdict;
 use librad::git::identities::local::LocalIdentity;
@@ -14,15 +16,24 @@ use librad::git::storage::ReadOnlyStorage;
 use librad::git::Storage;
 use librad::git_ext::{Oid, RefLike};
 use librad::profile::Profile;
+use librad::PeerId;
 
 use radicle_common as common;
-use radicle_common::args::{Args, Error, Help};
+use radicle_common::alias::Aliases;
+use radicle_common::args::{self, Args, Error, Help};
+use radicle_common::block::Blocklist;
 use radicle_common::cobs::patch::{MergeTarget, Patch, PatchId, PatchStore};
+use radicle_common::cobs::CommentId;
+use radicle_common::config::Config;
+use radicle_common::hooks::Event;
+use radicle_common::json;
 use radicle_common::tokio;
-use radicle_common::{cobs, git, keys, patch, project, sync};
+use radicle_common::{cobs, exit, git, gitlab, keys, offline, patch, project, sync};
 use radicle_terminal as term;
 use radicle_terminal::patch::Comment;
 
+use rad_notify::email;
+
 pub const HELP: Help = Help {
     name: "patch",
     description: env!("CARGO_PKG_DESCRIPTION"),
@@ -31,19 +42,69 @@ pub const HELP: Help = Help {
 Usage
 
     rad patch [<option>...]
+    rad patch show [<id>] [--diff]
+    rad patch import --gitlab <project>
 
 Create options
 
     -u, --update [<id>]        Update an existing patch (default: no)
         --[no-]sync            Sync patch to seed (default: sync)
         --[no-]push            Push patch head to storage (default: true)
+        --target <peer-id>     Target the default branch of the given peer
     -m, --message [<string>]   Provide a comment message to the patch or revision (default: prompt)
         --no-message           Leave the patch or revision comment message blank
 
 Options
 
     -l, --list                 List all patches (default: false)
+        --format <format>      Output format of `--list`: `text` (default), `csv` or `json`
+        --limit <n>            List at most <n> patches, of each section as text
+        --page <n>             List the <n>th page of patches, of 50 patches unless
+                               `--limit` is given
         --help                 Print help
+
+    `--list` lists patches from the oldest. As text, the patches you proposed are
+    listed first, then the patches others proposed, and they are shown as they are
+    loaded from storage, rather than once all are loaded.
+
+    The CSV output lists the patches in any state, without syncing first. It has
+    the columns `id`, `title`, `state` (`open`, `draft`, `archived` or `merged`),
+    `author`, `labels` (separated with `;`), `revisions`, `base`, `head` and
+    `created` (RFC 3339). The JSON output has the same fields, with `labels` as an
+    array.
+
+    With `rad --json`, `--list` prints JSON, and creating or updating a patch prints
+    the patch id, revision, base and head as a JSON object.
+
+    A patch targets the default branch of the peer that doesn't have its changes yet.
+    When several peers don't, the target is given with `--target`.
+
+    Projects can set defaults for their team's workflow in a `Radicle.toml` file
+    committed to the working copy, see `rad config`: `patch.sync` to sync patches or
+    not, and `patch.target` for the peer targeted by default.
+
+Show options
+
+        --diff                 Show the changes of the latest revision
+
+    `show` shows a patch: its title, state, author, description and commits. If no
+    id is given, the patch is picked among the open patches, by typing part of its
+    title.
+
+Import options
+
+        --gitlab <project>     Import the open merge requests of a GitLab project
+
+    `import` turns the open merge requests of a GitLab project into patches, for
+    projects migrating from GitLab. The project is given as `<group>/<project>` on
+    gitlab.com, or as the URL of the project on another GitLab instance. Requests are
+    authenticated with the `GITLAB_TOKEN` environment variable.
+
+    The commits of merge requests are fetched into the working copy and pushed to
+    storage, as `gitlab/merge-requests/<iid>` branches. Their discussion threads are
+    imported as comments and replies, crediting their GitLab authors. Merge requests
+    that were already imported are skipped, so `import` can be run again to import
+    the merge requests opened since. Run `rad sync` afterwards to publish the patches.
 "#,
 };
 
@@ -79,14 +140,51 @@ impl Default for Update {
     }
 }
 
+/// Output format of listings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(anyhow!(term::tr!(
+                "unknown format '{}': expected `text`, `csv` or `json`",
+                other
+            ))),
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Options {
     pub list: bool,
+    pub format: Format,
+    pub page: args::Page,
     pub verbose: bool,
     pub sync: bool,
     pub push: bool,
+    pub target: Option<PeerId>,
     pub update: Update,
     pub message: Comment,
+    pub import: Option<gitlab::Project>,
+    pub show: bool,
+    pub id: Option<cobs::Identifier>,
+    pub diff: bool,
 }
 
 impl Args for Options {
@@ -94,18 +192,53 @@ impl Args for Options {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_args(args);
+        let config = Config::current().cloned().unwrap_or_default();
         let mut list = false;
+        let mut format = Format::default();
+        let mut limit: Option<usize> = None;
+        let mut page: Option<usize> = None;
         let mut verbose = false;
-        let mut sync = true;
+        let mut sync = !config.offline && config.patch.sync.unwrap_or(config.sync.auto);
+        let mut target = config
+            .patch
+            .target
+            .as_deref()
+            .map(|peer| {
+                PeerId::from_str(peer).map_err(|_| Error::WithHint {
+                    err: anyhow!(term::tr!("invalid peer id '{}' in `patch.target`", peer)),
+                    hint: "hint: fix it with `rad config set patch.target <peer-id>`",
+                })
+            })
+            .transpose()?;
         let mut message = Comment::default();
         let mut push = true;
         let mut update = Update::default();
+        let mut import = false;
+        let mut gitlab: Option<gitlab::Project> = None;
+        let mut show = false;
+        let mut id: Option<cobs::Identifier> = None;
+        let mut diff = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("list") | Short('l') => {
                     list = true;
                 }
+                Long("format") => {
+                    let value = parser.value()?;
+
+                    format = args::parse_value("format", value)?;
+                }
+                Long("limit") => {
+                    let value = parser.value()?;
+
+                    limit = Some(args::parse_value("limit", value)?);
+                }
+                Long("page") => {
+                    let value = parser.value()?;
+
+                    page = Some(args::parse_value("page", value)?);
+                }
                 Long("verbose") | Short('v') => {
                     verbose = true;
                 }
@@ -122,7 +255,7 @@ impl Args for Options {
                             .to_str()
                             .ok_or_else(|| anyhow!("patch id specified is not UTF-8"))?;
                         let id = cobs::Identifier::from_str(val)
-                            .map_err(|_| anyhow!("invalid patch id '{}'", val))?;
+                            .map_err(|_| anyhow!(term::tr!("invalid patch id '{}'", val)))?;
 
                         update = Update::Patch(id);
                     } else {
@@ -141,21 +274,68 @@ impl Args for Options {
                 Long("no-push") => {
                     push = false;
                 }
+                Long("target") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    target = Some(
+                        PeerId::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid peer id '{}'", val)))?,
+                    );
+                }
+                Long("gitlab") if import => {
+                    let value = parser.value()?;
+
+                    gitlab = Some(args::parse_value("gitlab", value)?);
+                }
+                Long("diff") if show => {
+                    diff = true;
+                }
+                Value(val) if !import && !show && val == "import" => {
+                    import = true;
+                }
+                Value(val) if !import && !show && val == "show" => {
+                    show = true;
+                }
+                Value(val) if show && id.is_none() => {
+                    let val = val
+                        .to_str()
+                        .ok_or_else(|| anyhow!("patch id specified is not UTF-8"))?;
+
+                    id = Some(
+                        cobs::Identifier::from_str(val)
+                            .map_err(|_| anyhow!(term::tr!("invalid patch id '{}'", val)))?,
+                    );
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
+        if import && gitlab.is_none() {
+            return Err(Error::WithHint {
+                err: anyhow!("a project to import from must be given"),
+                hint: "hint: give the GitLab project with `--gitlab <project>`",
+            }
+            .into());
+        }
 
         Ok((
             Options {
                 list,
+                format,
+                page: args::Page::new(limit, page)?,
                 sync,
                 message,
                 push,
+                target,
                 update,
                 verbose,
+                import: gitlab,
+                show,
+                id,
+                diff,
             },
             vec![],
         ))
@@ -163,16 +343,30 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    let (urn, repo) = project::cwd()
-        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let (urn, repo) = project::cwd()?;
 
     let profile = ctx.profile()?;
-    let signer = term::signer(&profile)?;
-    let storage = keys::storage(&profile, signer)?;
-    let project = project::get(&storage, &urn)?
-        .ok_or_else(|| anyhow!("couldn't load project {} from local state", urn))?;
-
-    if options.list {
+    // Showing and listing patches only reads storage, so the key isn't needed.
+    let storage = if options.import.is_none() && (options.show || options.list) {
+        keys::storage_read_only(&profile)?
+    } else {
+        keys::storage(&profile, term::signer(&profile)?)?
+    };
+    let project = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("couldn't load project {} from local state", urn))
+    })?;
+
+    if let Some(from) = options.import {
+        import(&storage, &profile, &project, &repo, from)?;
+    } else if options.show {
+        show(
+            &storage,
+            &profile,
+            &project,
+            options.id.as_ref(),
+            options.diff,
+        )?;
+    } else if options.list {
         list(&storage, Some(repo), &profile, &project, options)?;
     } else {
         create(&storage, &profile, &project, &repo, options)?;
@@ -188,12 +382,18 @@ fn list(
     project: &project::Metadata,
     options: Options,
 ) -> anyhow::Result<()> {
+    if term::output::is_json() {
+        return list_all(storage, profile, project, Format::Json, options.page);
+    }
+    if options.format != Format::Text {
+        return list_all(storage, profile, project, options.format, options.page);
+    }
     if options.sync {
         let rt = tokio::runtime::Runtime::new()?;
 
         term::sync::sync(
             project.urn.clone(),
-            sync::seeds(profile)?,
+            term::sync::seeds(profile, &project.urn)?,
             sync::Mode::Fetch,
             profile,
             term::signer(profile)?,
@@ -203,46 +403,367 @@ fn list(
 
     let cobs = cobs::store(profile, storage)?;
     let patches = cobs.patches();
-    let proposed = patches.proposed(&project.urn)?;
-    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let blocklist = Blocklist::load(profile)?;
+    let mut authors = cobs.authors();
+    let mut listing = Listing::new(profile, &repo)?;
+
+    // Patches the user authored, then patches other users authored. Each section is
+    // listed in batches, whose authors and ahead/behind counts are computed in parallel,
+    // and which are rendered before the next batch is loaded.
+    for own in vec![true, false] {
+        let badge = if own {
+            term::format::badge_positive(term::tr!("YOU PROPOSED"))
+        } else {
+            term::format::badge_secondary(term::tr!("OTHERS PROPOSED"))
+        };
+        term::blank();
+        term::print(&badge);
 
-    // Patches the user authored.
-    let mut own = Vec::new();
-    // Patches other users authored.
-    let mut other = Vec::new();
+        let proposed = patches.iter(&project.urn)?.filter(|result| match result {
+            Ok((_, patch)) => {
+                patch.is_proposed()
+                    && !blocklist.contains(&patch.author.peer)
+                    && (*patch.author.urn() == cobs.whoami.urn()) == own
+            }
+            Err(_) => true,
+        });
+        let mut proposed = options.page.of(proposed);
+        let mut table = term::Table::new(term::table::TableOptions {
+            truncate: Some(0),
+            ..Default::default()
+        });
+        let mut empty = true;
+
+        loop {
+            let mut batch = proposed
+                .by_ref()
+                .take(BATCH_SIZE)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if batch.is_empty() {
+                break;
+            }
+            resolve_authors(profile, &mut authors, batch.iter_mut().map(|(_, p)| p));
+            listing.extend(project, storage, batch.iter().map(|(_, p)| p))?;
 
-    for (id, patch) in proposed {
-        if *patch.author.urn() == cobs.whoami.urn() {
-            own.push((id, patch));
-        } else {
-            other.push((id, patch));
+            for (id, patch) in &batch {
+                table.push(row(&cobs.whoami, id, patch, &listing, &blocklist)?);
+            }
+            if empty {
+                term::blank();
+                empty = false;
+            }
+            table.flush();
+        }
+        if empty {
+            term::blank();
+            term::print(&term::format::italic(term::tr!("Nothing to show.")));
         }
     }
+    authors.write().ok();
     term::blank();
-    term::print(&term::format::badge_positive("YOU PROPOSED"));
 
-    if own.is_empty() {
-        term::blank();
-        term::print(&term::format::italic("Nothing to show."));
+    Ok(())
+}
+
+/// Resolve the authors of patches, from the author cache where possible. The other
+/// authors are resolved in parallel, once each, and added to the cache.
+fn resolve_authors<'a>(
+    profile: &Profile,
+    authors: &mut cobs::cache::Authors,
+    patches: impl Iterator<Item = &'a mut Patch>,
+) {
+    let mut patches = patches.collect::<Vec<_>>();
+    let mut missing = HashMap::new();
+
+    for patch in patches.iter_mut() {
+        if let Some(cached) = authors.get(patch.author.urn()) {
+            patch.author.profile = Some(cached);
+        } else {
+            missing
+                .entry(patch.author.urn().to_string())
+                .or_insert_with(|| patch.author.clone());
+        }
+    }
+    if missing.is_empty() {
+        return;
+    }
+
+    let resolved = missing
+        .into_par_iter()
+        .map_init(
+            || common::profile::read_only(profile).ok(),
+            |storage, (urn, mut author)| {
+                author.resolve(storage.as_ref()?).ok()?;
+
+                Some((urn, author))
+            },
+        )
+        .flatten()
+        .collect::<HashMap<_, _>>();
+
+    for patch in patches {
+        if let Some(author) = resolved.get(&patch.author.urn().to_string()) {
+            patch.author.profile = author.profile.clone();
+        }
+    }
+    for author in resolved.values() {
+        authors.insert(author);
+    }
+}
+
+/// Number of patches rendered at once, when listing as text.
+const BATCH_SIZE: usize = 20;
+
+/// What the rows of a patch listing need from storage and the working copy, computed
+/// once for all patches, rather than for every row.
+pub struct Listing {
+    /// Monorepo, whose commit graph is walked to count commits ahead and behind.
+    git_dir: PathBuf,
+    /// Peer aliases of the working copy.
+    aliases: Aliases,
+    /// Branches of the working copy, by the commit they point to.
+    branches: HashMap<git::Oid, Vec<String>>,
+    /// Heads of the merge targets.
+    targets: Vec<(MergeTarget, git::Oid)>,
+    /// Commits ahead and behind, by patch head and target head.
+    ahead_behind: HashMap<(git::Oid, git::Oid), (usize, usize)>,
+}
+
+impl Listing {
+    pub fn new(profile: &Profile, repo: &Option<git::Repository>) -> anyhow::Result<Self> {
+        let aliases = repo.as_ref().map(Aliases::load).unwrap_or_default();
+        let mut branches: HashMap<git::Oid, Vec<String>> = HashMap::new();
+
+        if let Some(repo) = repo {
+            for r in repo.references()?.flatten() {
+                if !r.is_branch() {
+                    continue;
+                }
+                if let (Some(oid), Some(name)) = (r.target(), r.shorthand()) {
+                    branches.entry(oid).or_default().push(name.to_owned());
+                }
+            }
+        }
+
+        Ok(Self {
+            git_dir: profile.paths().git_dir().to_path_buf(),
+            aliases,
+            branches,
+            targets: Vec::new(),
+            ahead_behind: HashMap::new(),
+        })
+    }
+
+    /// Add patches to the listing, computing what their rows need. Ahead/behind counts
+    /// that weren't computed yet are computed in parallel.
+    pub fn extend<'a>(
+        &mut self,
+        project: &project::Metadata,
+        storage: &Storage,
+        patches: impl Iterator<Item = &'a Patch>,
+    ) -> anyhow::Result<()> {
+        let mut heads = HashSet::new();
+
+        for patch in patches {
+            let target = match self.targets.iter().find(|(t, _)| *t == patch.target) {
+                Some((_, head)) => *head,
+                None => {
+                    let verified = project.verified(storage)?;
+                    let head =
+                        common::patch::patch_merge_target_oid(patch.target, verified, storage)?;
+
+                    self.targets.push((patch.target, head));
+                    head
+                }
+            };
+            let key = (*patch.revisions.last().oid, target);
+
+            if !self.ahead_behind.contains_key(&key) {
+                heads.insert(key);
+            }
+        }
+
+        // Each worker walks the graph with a repository of its own, whose object cache
+        // is shared by the walks of that worker.
+        let git_dir = self.git_dir.as_path();
+        let ahead_behind = heads
+            .into_par_iter()
+            .map_init(
+                || git::Repository::open_bare(git_dir),
+                |repo, (head, target)| -> anyhow::Result<_> {
+                    let repo = repo.as_ref().map_err(|e| anyhow!(term::tr!("{}", e)))?;
+                    let counts = repo.graph_ahead_behind(head, target)?;
+
+                    Ok(((head, target), counts))
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.ahead_behind.extend(ahead_behind);
+
+        Ok(())
+    }
+
+    /// Get the commits a patch's head is ahead and behind its merge target.
+    fn ahead_behind(&self, patch: &Patch) -> anyhow::Result<(usize, usize)> {
+        let head = *patch.revisions.last().oid;
+
+        self.targets
+            .iter()
+            .find(|(t, _)| *t == patch.target)
+            .and_then(|(_, target)| self.ahead_behind.get(&(head, *target)))
+            .copied()
+            .ok_or_else(|| anyhow!(term::tr!("patch head {} is not in the listing", head)))
+    }
+}
+
+/// List all patches as CSV or JSON.
+fn list_all(
+    storage: &Storage,
+    profile: &Profile,
+    project: &project::Metadata,
+    format: Format,
+    page: args::Page,
+) -> anyhow::Result<()> {
+    let cobs = cobs::store(profile, storage)?;
+    let blocklist = Blocklist::load(profile)?;
+    let mut csv = term::Csv::new([
+        "id",
+        "title",
+        "state",
+        "author",
+        "labels",
+        "revisions",
+        "base",
+        "head",
+        "created",
+    ]);
+    let mut output = Vec::new();
+    let mut authors = cobs.authors();
+
+    let patches = cobs.patches();
+    let listed = patches.iter(&project.urn)?.filter(|result| match result {
+        Ok((_, patch)) => !blocklist.contains(&patch.author.peer),
+        Err(_) => true,
+    });
+
+    for result in page.of(listed) {
+        let (id, mut patch) = result?;
+
+        authors.resolve(&mut patch.author, storage).ok();
+
+        let (_, revision) = patch.latest();
+        let state = state(&patch);
+        let mut labels = patch
+            .labels
+            .iter()
+            .map(|l| l.name().to_owned())
+            .collect::<Vec<_>>();
+        labels.sort();
+
+        if format == Format::Json {
+            output.push(json::json!({
+                "id": id.to_string(),
+                "title": patch.title,
+                "state": state,
+                "author": patch.author.name(),
+                "labels": labels,
+                "revisions": patch.revisions.len(),
+                "base": revision.base.to_string(),
+                "head": revision.oid.to_string(),
+                "created": patch.timestamp.to_rfc3339(),
+            }));
+        } else {
+            csv.push([
+                id.to_string(),
+                patch.title.clone(),
+                state.to_owned(),
+                patch.author.name(),
+                labels.join(";"),
+                patch.revisions.len().to_string(),
+                revision.base.to_string(),
+                revision.oid.to_string(),
+                patch.timestamp.to_rfc3339(),
+            ]);
+        }
+    }
+    authors.write().ok();
+
+    if format == Format::Json {
+        term::output::json(&json::Value::Array(output))?;
     } else {
-        for (id, patch) in &mut own {
-            term::blank();
+        csv.render();
+    }
+
+    Ok(())
+}
 
-            print(&cobs.whoami, id, patch, project, &monorepo, &repo, storage)?;
+/// Get the state of a patch, as shown to users.
+fn state(patch: &Patch) -> &'static str {
+    match patch.state {
+        cobs::patch::State::Draft => "draft",
+        cobs::patch::State::Archived => "archived",
+        cobs::patch::State::Proposed if patch.revisions.iter().any(|r| !r.merges.is_empty()) => {
+            "merged"
         }
+        cobs::patch::State::Proposed => "open",
     }
+}
+
+/// Show a patch, and optionally the changes of its latest revision.
+fn show(
+    storage: &Storage,
+    profile: &Profile,
+    project: &project::Metadata,
+    id: Option<&cobs::Identifier>,
+    diff: bool,
+) -> anyhow::Result<()> {
+    let cobs = cobs::store(profile, storage)?;
+    let patches = cobs.patches();
+    let (patch_id, mut patch) = match id {
+        Some(id) => patches.resolve::<Patch>(&project.urn, id)?.ok_or_else(|| {
+            exit::Error::NotFound(term::tr!("couldn't find patch {} locally", id))
+        })?,
+        None => term::patch::select(
+            term::tr!("Which patch do you want to show?"),
+            &patches,
+            &project.urn,
+            storage,
+        )?
+        .ok_or_else(|| anyhow!("a patch id to show must be provided"))?,
+    };
+    patch.author.resolve(storage).ok();
+
+    let (version, revision) = patch.latest();
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let commits = patch::patch_commits(&monorepo, &revision.base, &revision.oid)?;
+
+    term::blank();
+    term::info!(
+        "{} {} {}",
+        term::format::bold(&patch.title),
+        term::format::highlight(common::fmt::cob(&patch_id)),
+        term::format::dim(format!("R{}", version)),
+    );
+    term::info!(
+        "{} by {} {}",
+        state(&patch),
+        term::format::tertiary(patch.author.name()),
+        term::format::dim(patch.timestamp),
+    );
     term::blank();
-    term::print(&term::format::badge_secondary("OTHERS PROPOSED"));
 
-    if other.is_empty() {
-        term::blank();
-        term::print(&term::format::italic("Nothing to show."));
+    if patch.description().is_empty() {
+        term::print(term::format::italic(term::tr!("No description provided.")));
     } else {
-        for (id, patch) in &mut other {
-            term::blank();
+        term::markdown(patch.description());
+    }
+    term::blank();
+    term::patch::list_commits(&commits)?;
 
-            print(&cobs.whoami, id, patch, project, &monorepo, &repo, storage)?;
-        }
+    if diff {
+        term::patch::print_diff(profile.paths().git_dir(), revision)?;
     }
     term::blank();
 
@@ -281,8 +802,10 @@ fn update(
     term::patch::print_commits_ahead_behind(repo, *head, *current_revision.oid)?;
     term::blank();
 
-    if !term::confirm("Continue?") {
-        anyhow::bail!("patch update aborted by user");
+    if !term::confirm("Continue?")? {
+        anyhow::bail!(exit::Error::Aborted(
+            "patch update aborted by user".to_owned()
+        ));
     }
 
     let new = patches.update(&project.urn, &patch_id, message, *base, *head)?;
@@ -291,13 +814,35 @@ fn update(
     term::blank();
     term::success!("Patch {} updated 🌱", term::format::highlight(patch_id));
     term::blank();
+    let output = json::json!({
+        "id": patch_id.to_string(),
+        "revision": new,
+        "base": base.to_string(),
+        "head": head.to_string(),
+    });
+    term::hooks::emit(profile, Event::PatchUpdated, &project.urn, output.clone());
+    if term::output::is_json() {
+        term::output::json(&output)?;
+    }
+    notify(
+        repo,
+        &email::Patch {
+            project,
+            id: patch_id,
+            revision: new,
+            title: &patch.title,
+            description: patch.description(),
+            base: *base,
+            head: *head,
+        },
+    );
 
     if options.sync {
         let rt = tokio::runtime::Runtime::new()?;
 
         term::sync::sync(
             project.urn.clone(),
-            sync::seeds(profile)?,
+            term::sync::seeds(profile, &project.urn)?,
             sync::Mode::Push,
             profile,
             term::signer(profile)?,
@@ -315,7 +860,7 @@ fn create(
     repo: &git::Repository,
     options: Options,
 ) -> anyhow::Result<()> {
-    term::headline(&format!(
+    term::headline(&term::tr!(
         "🌱 Creating patch for {}",
         term::format::highlight(&project.name)
     ));
@@ -333,7 +878,7 @@ fn create(
 
     // Make sure the `HEAD` commit can be found in the monorepo. Otherwise there
     // is no way for anyone to merge this patch.
-    let mut spinner = term::spinner(format!(
+    let mut spinner = term::spinner(term::tr!(
         "Looking for HEAD ({}) in storage...",
         term::format::secondary(common::fmt::oid(&head_oid))
     ));
@@ -343,7 +888,10 @@ fn create(
             term::blank();
 
             return Err(Error::WithHint {
-                err: anyhow!("Current branch head was not found in storage"),
+                err: exit::Error::NotFound(
+                    "Current branch head was not found in storage".to_owned(),
+                )
+                .into(),
                 hint: "hint: run `git push rad` and try again",
             }
             .into());
@@ -361,7 +909,24 @@ fn create(
     // Determine the merge target for this patch. This can ben any tracked remote's "default"
     // branch, as well as your own (eg. `rad/master`).
     let mut spinner = term::spinner("Analyzing remotes...");
-    let targets = patch::find_merge_targets(&head_oid, storage, project)?;
+    let mut targets = patch::find_merge_targets(&head_oid, storage, project)?;
+
+    if let Some(peer) = options.target {
+        if targets.merged.iter().any(|p| p.id == peer) {
+            spinner.message("The target peer is up to date.");
+            return Ok(());
+        }
+        targets.not_merged.retain(|(p, _)| p.id == peer);
+
+        if targets.not_merged.is_empty() {
+            spinner.failed();
+            return Err(Error::WithHint {
+                err: anyhow!(term::tr!("target peer {} is not tracked", peer)),
+                hint: "hint: track the peer with `rad track`, or target another peer",
+            }
+            .into());
+        }
+    }
 
     // eg. `refs/namespaces/<proj>/refs/remotes/<peer>/heads/master`
     let (target_peer, target_oid) = match targets.not_merged.as_slice() {
@@ -371,8 +936,15 @@ fn create(
         }
         [target] => target,
         _ => {
-            // TODO: Let user select which branch to use as a target.
-            todo!();
+            spinner.failed();
+            return Err(Error::WithHint {
+                err: anyhow!(term::tr!(
+                    "{} peers could be targeted",
+                    targets.not_merged.len()
+                )),
+                hint: "hint: choose the peer with `--target <peer-id>`, or set `patch.target` with `rad config`",
+            }
+            .into());
         }
     };
     // TODO: Tell user how many peers don't have this change.
@@ -402,7 +974,7 @@ fn create(
 
             if let Some((id, patch)) = result.pop() {
                 if result.is_empty() {
-                    spinner.message(format!(
+                    spinner.message(term::tr!(
                         "Found existing patch {} {}",
                         term::format::tertiary(common::fmt::cob(&id)),
                         term::format::italic(&patch.title)
@@ -426,20 +998,25 @@ fn create(
             if let Some((id, patch)) = patches.resolve(&project.urn, identifier)? {
                 Some((id, patch))
             } else {
-                anyhow::bail!("Patch '{}' not found", identifier);
+                anyhow::bail!(exit::Error::NotFound(term::tr!(
+                    "Patch '{}' not found",
+                    identifier
+                )));
             }
         }
     };
 
     if let Some((id, patch)) = patch {
-        if term::confirm("Update?") {
+        if term::confirm("Update?")? {
             term::blank();
 
             return update(
                 patch, id, &base_oid, &head_oid, &patches, project, repo, options, profile,
             );
         } else {
-            anyhow::bail!("Patch update aborted by user");
+            anyhow::bail!(exit::Error::Aborted(
+                "Patch update aborted by user".to_owned()
+            ));
         }
     }
 
@@ -467,8 +1044,10 @@ fn create(
     term::patch::list_commits(&commits)?;
     term::blank();
 
-    if !term::confirm("Continue?") {
-        anyhow::bail!("patch proposal aborted by user");
+    if !term::confirm("Continue?")? {
+        anyhow::bail!(exit::Error::Aborted(
+            "patch proposal aborted by user".to_owned()
+        ));
     }
 
     let message = head_commit
@@ -490,7 +1069,7 @@ fn create(
     term::blank();
 
     if description.is_empty() {
-        term::print(term::format::italic("No description provided."));
+        term::print(term::format::italic(term::tr!("No description provided.")));
     } else {
         term::markdown(&description);
     }
@@ -502,8 +1081,10 @@ fn create(
     )));
     term::blank();
 
-    if !term::confirm("Create patch?") {
-        anyhow::bail!("patch proposal aborted by user");
+    if !term::confirm("Create patch?")? {
+        anyhow::bail!(exit::Error::Aborted(
+            "patch proposal aborted by user".to_owned()
+        ));
     }
 
     let id = patches.create(
@@ -518,13 +1099,44 @@ fn create(
 
     term::blank();
     term::success!("Patch {} created 🌱", term::format::highlight(id));
+    term::hooks::emit(
+        profile,
+        Event::PatchCreated,
+        &project.urn,
+        json::json!({
+            "id": id.to_string(),
+            "title": title,
+            "base": base_oid.to_string(),
+            "head": head_oid.to_string(),
+        }),
+    );
+    if term::output::is_json() {
+        term::output::json(&json::json!({
+            "id": id.to_string(),
+            "revision": 0,
+            "base": base_oid.to_string(),
+            "head": head_oid.to_string(),
+        }))?;
+    }
+    notify(
+        repo,
+        &email::Patch {
+            project,
+            id,
+            revision: 0,
+            title,
+            description: &description,
+            base: base_oid,
+            head: head_oid,
+        },
+    );
 
     if options.sync {
         let rt = tokio::runtime::Runtime::new()?;
 
         term::sync::sync(
             project.urn.clone(),
-            sync::seeds(profile)?,
+            term::sync::seeds(profile, &project.urn)?,
             sync::Mode::Push,
             profile,
             term::signer(profile)?,
@@ -535,21 +1147,170 @@ fn create(
     Ok(())
 }
 
-/// Create a human friendly message about git's sync status.
-fn pretty_sync_status(
+/// Import the open merge requests of a GitLab project as patches.
+fn import(
+    storage: &Storage,
+    profile: &Profile,
+    project: &project::Metadata,
+    repo: &git::Repository,
+    from: gitlab::Project,
+) -> anyhow::Result<()> {
+    offline::check("importing from GitLab")?;
+
+    let client = gitlab::Client::new(&gitlab::Client::token()?, from);
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("repository has no working copy"))?;
+    let cobs = cobs::store(profile, storage)?;
+    let patches = cobs.patches();
+    let existing = patches.all(&project.urn)?;
+
+    let spinner = term::spinner(term::tr!(
+        "Fetching merge requests of {}...",
+        term::format::highlight(client.project())
+    ));
+    let requests = match client.merge_requests() {
+        Ok(requests) => requests,
+        Err(err) => return Err(spinner.error(err)),
+    };
+    spinner.finish();
+
+    let mut imported = 0;
+    let mut failed = 0;
+
+    for mr in requests.iter().filter(|mr| mr.state == "opened") {
+        if existing.iter().any(|(_, patch)| {
+            patch
+                .revisions
+                .iter()
+                .any(|r| mr.is_imported_as(r.description()))
+        }) {
+            continue;
+        }
+        let mut spinner = term::spinner(term::tr!(
+            "Importing !{} {}...",
+            mr.iid,
+            term::format::italic(&mr.title)
+        ));
+
+        match import_merge_request(&patches, &client, storage, project, repo, workdir, mr) {
+            Ok((id, comments)) => {
+                spinner.message(term::tr!(
+                    "Imported !{} {} as patch {}, with {} comment(s)",
+                    mr.iid,
+                    term::format::italic(&mr.title),
+                    term::format::highlight(common::fmt::cob(&id)),
+                    comments
+                ));
+                spinner.finish();
+                imported += 1;
+            }
+            Err(err) => {
+                spinner.failed();
+                term::warning(&term::tr!("Failed to import !{}: {}", mr.iid, err));
+                failed += 1;
+            }
+        }
+    }
+
+    term::blank();
+    term::success!("{} merge request(s) imported", imported);
+
+    if failed > 0 {
+        anyhow::bail!(term::tr!(
+            "{} merge request(s) could not be imported",
+            failed
+        ));
+    }
+    if imported > 0 {
+        term::tip!("To publish the imported patches, run `rad sync`.");
+    }
+    Ok(())
+}
+
+/// Import a merge request as a patch, with its discussion threads. Returns the patch
+/// id and the number of comments imported.
+fn import_merge_request(
+    patches: &PatchStore,
+    client: &gitlab::Client,
+    storage: &Storage,
+    project: &project::Metadata,
     repo: &git::Repository,
-    revision_oid: git::Oid,
-    head_oid: git::Oid,
-) -> anyhow::Result<String> {
-    let (a, b) = repo.graph_ahead_behind(revision_oid, head_oid)?;
-    if a == 0 && b == 0 {
-        return Ok(term::format::dim("up to date"));
+    workdir: &Path,
+    mr: &gitlab::Item,
+) -> anyhow::Result<(PatchId, usize)> {
+    let branch = format!("gitlab/merge-requests/{}", mr.iid);
+    let target = mr
+        .target_branch
+        .clone()
+        .unwrap_or_else(|| project.default_branch.to_string());
+    let head_ref = format!("refs/remotes/{}", branch);
+    let target_ref = format!("refs/remotes/gitlab/{}", target);
+
+    // The target branch is fetched too, to find the commit the merge request is based on.
+    client.fetch(
+        workdir,
+        &[
+            format!("+refs/merge-requests/{}/head:{}", mr.iid, head_ref),
+            format!("+refs/heads/{}:{}", target, target_ref),
+        ],
+    )?;
+    let head_oid = repo.refname_to_id(&head_ref)?;
+    let base_oid = repo.merge_base(repo.refname_to_id(&target_ref)?, head_oid)?;
+
+    // Make sure the head can be found in storage, so that the patch can be merged.
+    if storage.find_object(Oid::from(head_oid))?.is_none() {
+        git::git(
+            workdir,
+            [
+                "push",
+                "rad",
+                &format!("+{}:refs/heads/{}", head_ref, branch),
+            ],
+        )?;
     }
 
-    let ahead = term::format::positive(a);
-    let behind = term::format::negative(b);
+    let id = patches.create(
+        &project.urn,
+        &mr.title,
+        &mr.imported_description(),
+        MergeTarget::default(),
+        base_oid,
+        head_oid,
+        &mr.labels(),
+    )?;
 
-    Ok(format!("ahead {}, behind {}", ahead, behind))
+    // Threads become comments on the first revision, and their notes, replies.
+    let mut comments = 0;
+    for (ix, thread) in client.merge_request_discussions(mr.iid)?.iter().enumerate() {
+        if let Some((note, replies)) = thread.split_first() {
+            patches.comment(&project.urn, &id, 0, &note.imported_body())?;
+
+            for reply in replies {
+                patches.reply(
+                    &project.urn,
+                    &id,
+                    0,
+                    CommentId::from(ix),
+                    &reply.imported_body(),
+                )?;
+            }
+            comments += thread.len();
+        }
+    }
+    Ok((id, comments))
+}
+
+/// Create a human friendly message about git's sync status.
+fn pretty_sync_status((ahead, behind): (usize, usize)) -> String {
+    if ahead == 0 && behind == 0 {
+        return term::format::dim("up to date");
+    }
+
+    let ahead = term::format::positive(ahead);
+    let behind = term::format::negative(behind);
+
+    format!("ahead {}, behind {}", ahead, behind)
 }
 
 /// Make a human friendly string for commit version information.
@@ -557,135 +1318,71 @@ fn pretty_sync_status(
 /// For example '<oid> (branch1[, branch2])'.
 fn pretty_commit_version(
     revision_oid: &git::Oid,
-    repo: &Option<git::Repository>,
-) -> anyhow::Result<String> {
-    let mut oid = term::format::secondary(common::fmt::oid(revision_oid));
-    let mut branches: Vec<String> = vec![];
-
-    if let Some(repo) = repo {
-        for r in repo.references()?.flatten() {
-            if !r.is_branch() {
-                continue;
-            }
-            if let (Some(oid), Some(name)) = (&r.target(), &r.shorthand()) {
-                if oid == revision_oid {
-                    branches.push(name.to_string());
-                };
-            };
-        }
-    };
-    if !branches.is_empty() {
-        oid = format!(
+    branches: &HashMap<git::Oid, Vec<String>>,
+) -> String {
+    let oid = term::format::secondary(common::fmt::oid(revision_oid));
+
+    match branches.get(revision_oid) {
+        Some(branches) if !branches.is_empty() => format!(
             "{} {}",
             oid,
             term::format::yellow(format!("({})", branches.join(", "))),
-        );
+        ),
+        _ => oid,
     }
-
-    Ok(oid)
 }
 
-/// Adds patch details as a new row to `table` and render later.
-pub fn print(
+/// Get the row of a patch in the listing: its title, id, author, number of revisions,
+/// merges and reviews of its latest revision, head and sync status, and creation time.
+/// The patch's author should be resolved beforehand, see [`resolve_authors`].
+pub fn row(
     whoami: &LocalIdentity,
     patch_id: &PatchId,
-    patch: &mut Patch,
-    project: &project::Metadata,
-    monorepo: &git::Repository,
-    repo: &Option<git::Repository>,
-    storage: &Storage,
-) -> anyhow::Result<()> {
-    for r in patch.revisions.iter_mut() {
-        for (_, r) in &mut r.reviews {
-            r.author.resolve(storage).ok();
-        }
-    }
-    patch.author.resolve(storage).ok();
-
-    let verified = project.verified(storage)?;
-    let target_head = common::patch::patch_merge_target_oid(patch.target, verified, storage)?;
-
-    let you = patch.author.urn() == &whoami.urn();
-    let prefix = "└─ ";
-    let mut author_info = vec![format!(
-        "{}* opened by {}",
-        prefix,
-        term::format::tertiary(patch.author.name()),
-    )];
-
-    if you {
-        author_info.push(term::format::secondary("(you)"));
+    patch: &Patch,
+    listing: &Listing,
+    blocklist: &Blocklist,
+) -> anyhow::Result<[String; 8]> {
+    let mut author = term::format::tertiary(
+        listing
+            .aliases
+            .name_or(&patch.author.peer, || patch.author.name()),
+    );
+    if patch.author.urn() == &whoami.urn() {
+        author = format!("{} {}", author, term::format::secondary("(you)"));
     }
-    author_info.push(term::format::dim(patch.timestamp));
 
     let revision = patch.revisions.last();
-    term::info!(
-        "{} {} {} {} {}",
-        term::format::bold(&patch.title),
-        term::format::highlight(common::fmt::cob(patch_id)),
-        term::format::dim(format!("R{}", patch.version())),
-        pretty_commit_version(&revision.oid, repo)?,
-        pretty_sync_status(monorepo, *revision.oid, target_head)?,
-    );
-    term::info!("{}", author_info.join(" "));
-
-    let mut timeline = Vec::new();
-    for merge in &revision.merges {
-        let peer = project::PeerInfo::get(&merge.peer, project, storage);
-        let mut badges = Vec::new();
+    let mut status = Vec::new();
 
-        if peer.delegate {
-            badges.push(term::format::secondary("(delegate)"));
-        }
-        if peer.id == *storage.peer_id() {
-            badges.push(term::format::secondary("(you)"));
-        }
-
-        timeline.push((
-            merge.timestamp,
-            format!(
-                "{}{} by {} {}",
-                " ".repeat(term::text_width(prefix)),
-                term::format::secondary(term::format::dim("✓ merged")),
-                term::format::tertiary(peer.name()),
-                badges.join(" "),
-            ),
-        ));
+    if revision.merges.iter().any(|m| !blocklist.contains(&m.peer)) {
+        status.push(term::format::secondary("✓ merged"));
     }
-    for (_, review) in &revision.reviews {
-        let verdict = match review.verdict {
-            Some(Verdict::Accept) => term::format::positive(term::format::dim("✓ accepted")),
-            Some(Verdict::Reject) => term::format::negative(term::format::dim("✗ rejected")),
-            None => term::format::negative(term::format::dim("⋄ reviewed")),
-        };
-        let peer = project::PeerInfo::get(&review.author.peer, project, storage);
-        let mut badges = Vec::new();
-
-        if peer.delegate {
-            badges.push(term::format::secondary("(delegate)"));
-        }
-        if peer.id == *storage.peer_id() {
-            badges.push(term::format::secondary("(you)"));
-        }
-
-        timeline.push((
-            review.timestamp,
-            format!(
-                "{}{} by {} {}",
-                " ".repeat(term::text_width(prefix)),
-                verdict,
-                term::format::tertiary(review.author.name()),
-                badges.join(" "),
-            ),
-        ));
+    let (accepted, rejected) = revision
+        .reviews
+        .iter()
+        .filter(|(_, r)| !blocklist.contains(&r.author.peer))
+        .fold((0, 0), |(a, r), (_, review)| match review.verdict {
+            Some(Verdict::Accept) => (a + 1, r),
+            Some(Verdict::Reject) => (a, r + 1),
+            None => (a, r),
+        });
+    if accepted > 0 {
+        status.push(term::format::positive(format!("✓ {}", accepted)));
     }
-    timeline.sort_by_key(|(t, _)| *t);
-
-    for (time, event) in timeline.iter().rev() {
-        term::info!("{} {}", event, term::format::dim(time));
+    if rejected > 0 {
+        status.push(term::format::negative(format!("✗ {}", rejected)));
     }
 
-    Ok(())
+    Ok([
+        term::format::bold(&patch.title),
+        term::format::highlight(common::fmt::cob(patch_id)),
+        author,
+        patch.revisions.len().to_string(),
+        status.join(" "),
+        pretty_commit_version(&revision.oid, &listing.branches),
+        pretty_sync_status(listing.ahead_behind(patch)?),
+        term::format::dim(patch.timestamp),
+    ])
 }
 
 /// Find patches with a merge base equal to the one provided.
@@ -720,3 +1417,32 @@ fn find_unmerged_with_base(
     }
     Ok(matches)
 }
+
+/// Email a patch revision to the recipients configured with `rad notify email`, if any.
+/// Failures are reported, but don't fail the command. Skipped in offline mode.
+fn notify(repo: &git::Repository, patch: &email::Patch) {
+    let to = email::recipients(repo).unwrap_or_default();
+    if to.is_empty() {
+        return;
+    }
+    if let Err(err) = offline::check("emailing patches") {
+        term::warning(&term::tr!(
+            "Patch not emailed to {}: {}",
+            to.join(", "),
+            err
+        ));
+        return;
+    }
+    let spinner = term::spinner(term::tr!(
+        "Emailing patch to {}...",
+        term::format::highlight(to.join(", "))
+    ));
+
+    match email::notify(repo, patch) {
+        Ok(_) => spinner.finish(),
+        Err(err) => {
+            spinner.failed();
+            term::warning(&term::tr!("Failed to email patch: {}", err));
+        }
+    }
+}
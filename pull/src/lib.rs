@@ -42,7 +42,7 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut seeds = Vec::new();
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
                 Long("seed") => {
                     let seed = seed::parse_value(&mut parser)?;
@@ -60,8 +60,7 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    let (urn, repo) = project::cwd()
-        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let (urn, repo) = project::cwd()?;
 
     let _head = repo
         .head()
@@ -1,11 +1,16 @@
 use std::ffi::OsString;
 use std::path::Path;
 
+use librad::git::refs::Refs;
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::git;
+use radicle_common::hooks::Event;
 
 use radicle_common::sync::Mode;
-use radicle_common::{seed, sync};
+use radicle_common::{exit, fmt, json, profile, project, seed, sync};
 use radicle_terminal as term;
 
 use anyhow::anyhow;
@@ -17,32 +22,51 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad push [--seed <host>] [--all] [--[no-]sync] [<option>...]
+    rad push [<branch>...] [--seed <addr>]... [--all] [--tags] [--[no-]sync] [<option>...]
+
+    Pushes the given branches to the "rad" remote, ie. to your storage, and syncs them.
+    By default, only the current branch is pushed. A branch is pushed to its upstream
+    branch, if it tracks a branch of the "rad" remote, and to the branch of the same
+    name otherwise.
 
-    By default, only the current branch is synced.
+    Pushes that would not be a fast-forward of the branch in your storage are refused.
+    With `--force-with-lease`, they are allowed as long as the branch in your storage is
+    still at the commit you last published, ie. the one in your signed refs, so that
+    commits pushed from another working copy, but not yet published, are never lost.
+    With `--force`, they are always allowed.
+
+    If the project protects its default branch, only delegates may push to it: other
+    peers propose their changes as patches, with `rad patch`. Delegates set this policy
+    with `rad delegate policy --protect-default-branch`.
 
 Options
 
-    --seed <host>       Use the given seed node for syncing
+    --seed <addr>       Use the given seed node for syncing (may be specified multiple times,
+                        default: `sync.seed` of the configuration, if any)
     --all               Push all branches (default: false)
+    --tags              Push all tags, in addition to the branches
     --sync              Sync after pushing to the "rad" remote (default: true)
     --no-sync           Do not sync after pushing to the "rad" remote
     --help              Print help
 
 Git options
 
-    -f, --force           Force push
-    -u, --set-upstream    Set upstream tracking branch
+    -f, --force             Force push
+    --force-with-lease      Force push, if the branch is still at its published commit
+    -u, --set-upstream      Set upstream tracking branch
 
 "#,
 };
 
 #[derive(Default, Debug)]
 pub struct Options {
-    pub seed: Option<sync::Seed<String>>,
+    pub branches: Vec<String>,
+    pub seeds: Vec<sync::Seed<String>>,
     pub verbose: bool,
     pub force: bool,
+    pub force_with_lease: bool,
     pub all: bool,
+    pub tags: bool,
     pub set_upstream: bool,
     pub sync: bool,
 }
@@ -54,15 +78,18 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut verbose = false;
         let mut force = false;
+        let mut force_with_lease = false;
         let mut all = false;
-        let mut sync = true;
-        let mut seed = None;
+        let mut tags = false;
+        let mut branches = Vec::new();
+        let mut sync = sync::is_auto();
+        let mut seeds = Vec::new();
         let mut set_upstream = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("seed") => {
-                    seed = Some(seed::parse_value(&mut parser)?);
+                    seeds.push(seed::parse_value(&mut parser)?);
                 }
                 Long("verbose") | Short('v') => {
                     verbose = true;
@@ -73,6 +100,9 @@ impl Args for Options {
                 Long("all") => {
                     all = true;
                 }
+                Long("tags") => {
+                    tags = true;
+                }
                 Long("set-upstream") | Short('u') => {
                     set_upstream = true;
                 }
@@ -85,17 +115,39 @@ impl Args for Options {
                 Long("force") | Short('f') => {
                     force = true;
                 }
+                Long("force-with-lease") => {
+                    force_with_lease = true;
+                }
+                Value(val) => {
+                    branches.push(val.to_string_lossy().to_string());
+                }
                 arg => {
                     return Err(anyhow!(arg.unexpected()));
                 }
             }
         }
 
+        if all && !branches.is_empty() {
+            return Err(anyhow!("`--all` cannot be used with branch names"));
+        }
+        if force && force_with_lease {
+            return Err(anyhow!(
+                "`--force` and `--force-with-lease` cannot be used together"
+            ));
+        }
+        // Without `--seed`, the seed configured with `sync.seed` is used, if any.
+        if seeds.is_empty() {
+            seeds.extend(sync::default_seed()?);
+        }
+
         Ok((
             Options {
-                seed,
+                branches,
+                seeds,
                 force,
+                force_with_lease,
                 all,
+                tags,
                 set_upstream,
                 sync,
                 verbose,
@@ -105,44 +157,222 @@ impl Args for Options {
     }
 }
 
+/// A branch to push, and the branch of the "rad" remote it is pushed to.
+struct Push {
+    branch: String,
+    upstream: String,
+}
+
+impl Push {
+    fn refspec(&self) -> String {
+        format!("refs/heads/{}:refs/heads/{}", self.branch, self.upstream)
+    }
+}
+
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    ctx.profile()?;
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let (urn, repo) = project::cwd().map_err(|_| Error::WithHint {
+        err: anyhow!("this command must be run within a project working copy"),
+        hint: "hint: initialize the project with `rad init`, or check out one with `rad checkout`",
+    })?;
+
+    let branches = if options.all {
+        repo.branches(Some(git::BranchType::Local))?
+            .filter_map(|b| b.ok())
+            .filter_map(|(b, _)| b.name().ok().flatten().map(|n| n.to_owned()))
+            .collect()
+    } else if options.branches.is_empty() {
+        let head = repo.head()?;
+        if !head.is_branch() {
+            return Err(Error::WithHint {
+                err: anyhow!("HEAD is detached: there is no current branch to push"),
+                hint: "hint: check out a branch, or give the branches to push",
+            }
+            .into());
+        }
+        vec![head.shorthand().unwrap_or_default().to_owned()]
+    } else {
+        options.branches.clone()
+    };
+    let mut pushes = branches
+        .into_iter()
+        .map(|branch| {
+            repo.find_branch(&branch, git::BranchType::Local)
+                .map_err(|_| exit::Error::NotFound(term::tr!("branch '{}' not found", branch)))?;
+
+            Ok(Push {
+                upstream: upstream(&repo, &branch).unwrap_or_else(|| branch.clone()),
+                branch,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found in local storage", urn))
+    })?;
+    if !proj.can_push_default_branch(storage.peer_id()) {
+        let default_branch = proj.default_branch.to_string();
+
+        if options.all {
+            // Nb. When pushing all branches, the protected branch is simply skipped.
+            let len = pushes.len();
+            pushes.retain(|p| p.upstream != default_branch);
+
+            if pushes.len() < len {
+                term::warning(&term::tr!(
+                    "Skipping protected branch '{}': only delegates may push to it",
+                    default_branch
+                ));
+            }
+        } else if pushes.iter().any(|p| p.upstream == default_branch) {
+            return Err(Error::WithHint {
+                err: anyhow!(term::tr!(
+                    "branch '{}' is protected: only delegates may push to it",
+                    default_branch
+                )),
+                hint:
+                    "hint: push your changes to another branch, and propose them with `rad patch`",
+            }
+            .into());
+        }
+    }
+
+    // Nb. The branches in storage are those of the "rad" remote, as git sees them.
+    let published = published(&storage, &urn)?;
+    let mut leases = Vec::new();
+    let mut previous = Vec::new();
+
+    for push in &pushes {
+        let stored = project::get_local_head(&storage, &urn, &push.upstream)?;
+        previous.push(stored);
+
+        if options.force_with_lease {
+            let signed = published
+                .iter()
+                .find(|(name, _)| name == &push.upstream)
+                .map(|(_, oid)| *oid);
+
+            if stored != signed {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!(
+                        "branch '{}' in your storage is at {}, but you last published {}",
+                        push.upstream,
+                        stored.map(|o| fmt::oid(&o)).unwrap_or_else(|| "nothing".to_owned()),
+                        signed.map(|o| fmt::oid(&o)).unwrap_or_else(|| "nothing".to_owned()),
+                    )),
+                    hint: "hint: the branch was pushed to without being published; integrate its changes, or push with `--force`",
+                }
+                .into());
+            }
+            leases.push(format!(
+                "--force-with-lease=refs/heads/{}:{}",
+                push.upstream,
+                signed.map(|o| o.to_string()).unwrap_or_default()
+            ));
+        } else if !options.force {
+            let local = repo.refname_to_id(&format!("refs/heads/{}", push.branch))?;
+
+            if let Some(stored) = stored {
+                let is_fast_forward =
+                    local == stored || repo.graph_descendant_of(local, stored).unwrap_or(false);
+
+                if !is_fast_forward {
+                    return Err(Error::WithHint {
+                        err: anyhow!(term::tr!(
+                            "pushing '{}' to '{}' would not be a fast-forward: your storage has {}, which '{}' doesn't contain",
+                            push.branch,
+                            push.upstream,
+                            fmt::oid(&stored),
+                            push.branch,
+                        )),
+                        hint: "hint: integrate the changes with `rad pull`, or push with `--force-with-lease`",
+                    }
+                    .into());
+                }
+            }
+        }
+    }
 
     term::info!("Pushing 🌱 to remote `rad`");
 
-    let mut args = vec!["push"];
+    let mut args = vec!["push".to_owned()];
 
     if options.force {
-        args.push("--force");
+        args.push("--force".to_owned());
     }
+    args.extend(leases);
     if options.set_upstream {
-        args.push("--set-upstream");
+        args.push("--set-upstream".to_owned());
     }
-    if options.all {
-        args.push("--all");
+    if options.tags {
+        args.push("--tags".to_owned());
     }
     if options.verbose {
-        args.push("--verbose");
+        args.push("--verbose".to_owned());
     }
-    args.push("rad"); // Push to "rad" remote.
+    args.push("rad".to_owned()); // Push to "rad" remote.
+    args.extend(pushes.iter().map(Push::refspec));
 
     term::subcommand(&format!("git {}", args.join(" ")));
 
     // Push to monorepo.
     match git::git(Path::new("."), args) {
         Ok(output) => term::blob(output),
+        Err(err) if err.to_string().contains("non-fast-forward") => return Err(Error::WithHint {
+            err: anyhow!("push was rejected: your storage has commits your branch doesn't"),
+            hint: "hint: integrate the changes with `rad pull`, or push with `--force-with-lease`",
+        }
+        .into()),
+        Err(err) if err.to_string().contains("stale info") => {
+            return Err(Error::WithHint {
+                err: anyhow!("push was rejected: your storage changed since you last published"),
+                hint: "hint: integrate the changes, or push with `--force`",
+            }
+            .into())
+        }
         Err(err) => return Err(err),
     }
 
+    let mut updated = Vec::new();
+    for (push, old) in pushes.iter().zip(previous) {
+        let new = project::get_local_head(&storage, &urn, &push.upstream)?;
+
+        if new != old {
+            updated.push(json::json!({
+                "name": format!("refs/heads/{}", push.upstream),
+                "old": old.map(|o| o.to_string()),
+                "new": new.map(|o| o.to_string()),
+            }));
+        }
+    }
+    if !updated.is_empty() {
+        term::hooks::emit(
+            &profile,
+            Event::RefsPushed,
+            &urn,
+            json::json!({ "refs": updated }),
+        );
+    }
+
     if options.sync {
         // Sync monorepo to seed.
         rad_sync::run(
             rad_sync::Options {
-                seeds: options.seed.into_iter().collect(),
+                seeds: options.seeds,
                 verbose: options.verbose,
                 mode: Mode::Push,
                 origin: None,
-                sync_self: false,
+                operation: rad_sync::Operation::Sync,
+                save: false,
+                refs: sync::Refs::All,
+                prune: false,
+                fetch_peers: false,
+                recurse_submodules: false,
+                transport: sync::Transport::default(),
+                timeout: None,
+                rate_limit: None,
+                filter: None,
             },
             ctx,
         )?;
@@ -150,3 +380,36 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Get the branch of the "rad" remote that the given branch tracks, if any.
+fn upstream(repo: &git::Repository, branch: &str) -> Option<String> {
+    if git::branch_remote(repo, branch).ok()? != "rad" {
+        return None;
+    }
+    let merge = repo
+        .config()
+        .ok()?
+        .get_string(&format!("branch.{}.merge", branch))
+        .ok()?;
+
+    merge.strip_prefix("refs/heads/").map(|b| b.to_owned())
+}
+
+/// Get the branches of the project, as last published, ie. as found in our signed refs.
+fn published(storage: &ReadOnly, urn: &Urn) -> anyhow::Result<Vec<(String, git::Oid)>> {
+    let refs = match Refs::load(storage, urn, None)? {
+        Some(refs) => refs,
+        None => return Ok(vec![]),
+    };
+    let heads = refs
+        .heads()
+        .map(|(name, oid)| {
+            let name = name.to_string();
+            let name = name.strip_prefix("refs/heads/").unwrap_or(&name).to_owned();
+
+            (name, git::Oid::from(oid.to_owned()))
+        })
+        .collect();
+
+    Ok(heads)
+}
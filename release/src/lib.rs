@@ -0,0 +1,340 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as _};
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::release::{self, Artifact};
+use radicle_common::{cobs, exit, fmt, git, keys, project, sshsig};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "release",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad release create <tag> [--notes-file <file>] [--artifact <path>]...
+    rad release list
+    rad release verify <tag> [<path>...]
+
+    Releases are published as collaborative objects of the project, so that they are
+    synced along with it. A release refers to a tag, has release notes, and lists the
+    SHA-256 checksums of its artifacts, eg. binaries or source archives, which are
+    signed with your device key.
+
+    With `create`, the given tag is released. If it doesn't exist yet, an annotated tag
+    is created at HEAD, and pushed to the "rad" remote. The artifacts themselves are not
+    published: distribute them as you wish, eg. on a website.
+
+    With `verify`, the signature of the release is checked, as well as whether it was
+    made by a delegate of the project. The given artifacts, or the artifacts of the
+    release found in the current directory, are then checked against the release. The
+    signed checksums can also be verified with `ssh-keygen -Y verify -n file`.
+
+Options
+
+    --notes-file <file>     Read the release notes from the given file
+    --artifact <path>       Sign the checksum of the given file (may be specified multiple times)
+    --help                  Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Create,
+    List,
+    Verify,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Create {
+        tag: String,
+        notes: Option<PathBuf>,
+        artifacts: Vec<PathBuf>,
+    },
+    List,
+    Verify {
+        tag: String,
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut tag: Option<String> = None;
+        let mut notes: Option<PathBuf> = None;
+        let mut artifacts = Vec::new();
+        let mut paths = Vec::new();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("notes-file") if op == Some(OperationName::Create) => {
+                    notes = Some(PathBuf::from(parser.value()?));
+                }
+                Long("artifact") if op == Some(OperationName::Create) => {
+                    artifacts.push(PathBuf::from(parser.value()?));
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "c" | "create" => op = Some(OperationName::Create),
+                    "l" | "list" => op = Some(OperationName::List),
+                    "v" | "verify" => op = Some(OperationName::Verify),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if op != Some(OperationName::List) && tag.is_none() => {
+                    tag = Some(val.to_string_lossy().to_string());
+                }
+                Value(val) if op == Some(OperationName::Verify) => {
+                    paths.push(PathBuf::from(val));
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Create => Operation::Create {
+                tag: tag.ok_or_else(|| anyhow!("a tag to release must be provided"))?,
+                notes,
+                artifacts,
+            },
+            OperationName::List => Operation::List,
+            OperationName::Verify => Operation::Verify {
+                tag: tag.ok_or_else(|| anyhow!("the tag of the release must be provided"))?,
+                paths,
+            },
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer.clone())?;
+    let (urn, repo) = project::cwd().map_err(|_| Error::WithHint {
+        err: anyhow!("this command must be run within a project working copy"),
+        hint: "hint: initialize the project with `rad init`, or check out one with `rad checkout`",
+    })?;
+    let cobs = cobs::store(&profile, &storage)?;
+    let releases = cobs.releases();
+
+    match options.op {
+        Operation::Create {
+            tag,
+            notes,
+            artifacts,
+        } => {
+            if releases.find(&urn, &tag)?.is_some() {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("tag '{}' was already released", tag)),
+                    hint: "hint: run `rad release list` to see the project's releases",
+                }
+                .into());
+            }
+            let notes = match notes {
+                Some(path) => fs::read_to_string(&path)
+                    .with_context(|| term::tr!("could not read notes from {}", path.display()))?,
+                None => String::new(),
+            };
+            let mut checksums = Vec::new();
+            for path in &artifacts {
+                let artifact = Artifact::from_path(path)
+                    .with_context(|| term::tr!("could not read artifact {}", path.display()))?;
+
+                if checksums.iter().any(|a: &Artifact| a.name == artifact.name) {
+                    anyhow::bail!(term::tr!(
+                        "more than one artifact is named '{}'",
+                        artifact.name
+                    ));
+                }
+                checksums.push(artifact);
+            }
+            let commit = match repo.revparse_single(&format!("refs/tags/{}", tag)) {
+                Ok(obj) => obj.peel_to_commit()?.id(),
+                Err(_) => {
+                    let message = notes
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .map(|l| l.trim().to_owned())
+                        .unwrap_or_else(|| format!("Release {}", tag));
+
+                    git::git(
+                        Path::new("."),
+                        ["tag", "--annotate", &tag, "--message", &message],
+                    )?;
+                    term::success!("Tag {} created", term::format::highlight(&tag));
+
+                    let spinner = term::spinner("Pushing tag to the \"rad\" remote...");
+                    if let Err(err) = git::push_tag(&tag) {
+                        return Err(spinner.error(err));
+                    }
+                    spinner.finish();
+
+                    repo.head()?.peel_to_commit()?.id()
+                }
+            };
+
+            let signature = sshsig::sign(
+                &signer,
+                storage.peer_id(),
+                release::SIGNATURE_NAMESPACE,
+                release::manifest(&checksums).as_bytes(),
+            )?;
+            let id = releases.create(&urn, &tag, commit.into(), &notes, &checksums, &signature)?;
+
+            term::success!(
+                "Release {} of {} created, with {} signed artifact(s)",
+                term::format::highlight(&tag),
+                term::format::secondary(fmt::oid(&commit)),
+                checksums.len()
+            );
+            term::info!("{} {}", term::format::dim("Id"), fmt::cob(&id));
+            term::tip!("To publish the release, run `rad sync`.");
+        }
+        Operation::List => {
+            let mut table = term::Table::default();
+
+            for (id, mut release) in releases.all(&urn)?.into_iter().rev() {
+                release.author.resolve(&storage).ok();
+
+                table.push([
+                    term::format::bold(&release.tag),
+                    term::format::secondary(fmt::oid(&release.commit.into())),
+                    format!("{} artifact(s)", release.artifacts.len()),
+                    term::format::tertiary(release.author.name()),
+                    term::format::dim(release.timestamp),
+                    term::format::dim(fmt::cob(&id)),
+                ]);
+            }
+            table.render();
+        }
+        Operation::Verify { tag, paths } => {
+            let (_, release) = releases.find(&urn, &tag)?.ok_or_else(|| Error::WithHint {
+                err: anyhow!(term::tr!("no release of tag '{}' was found", tag)),
+                hint: "hint: fetch the project's releases with `rad sync`",
+            })?;
+            let proj = project::get(&storage, &urn)?.ok_or_else(|| {
+                exit::Error::NotFound(term::tr!("project {} not found in local storage", urn))
+            })?;
+
+            verify(&release, &proj, &repo, &paths)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verify a release, and the given artifacts against it.
+fn verify(
+    release: &release::Release,
+    project: &project::Metadata,
+    repo: &git::Repository,
+    paths: &[PathBuf],
+) -> anyhow::Result<()> {
+    let author = release.author.peer;
+    let signature = release.verify().context("invalid release signature")?;
+
+    if !signature.is_signed_by(&author) {
+        anyhow::bail!(term::tr!(
+            "the release signature was not made by its author {}",
+            fmt::peer(&author)
+        ));
+    }
+    term::success!(
+        "Signature of {} by {} is valid",
+        term::format::highlight(&release.tag),
+        term::format::tertiary(fmt::peer(&author))
+    );
+
+    if project.remotes.contains(&author) {
+        term::success!("Signer is a delegate of the project");
+    } else {
+        term::warning("Signer is not a delegate of the project");
+    }
+
+    if let Ok(obj) = repo.revparse_single(&format!("refs/tags/{}", release.tag)) {
+        let commit = obj.peel_to_commit()?.id();
+
+        if git::Oid::from(release.commit) != commit {
+            term::warning(&term::tr!(
+                "Tag {} points to {} in the working copy, but {} was released",
+                release.tag,
+                fmt::oid(&commit),
+                fmt::oid(&release.commit.into())
+            ));
+        }
+    }
+
+    // If no artifacts are given, those of the release found in the current directory are
+    // checked.
+    let paths = if paths.is_empty() {
+        release
+            .artifacts
+            .iter()
+            .map(|a| PathBuf::from(&a.name))
+            .filter(|p| p.is_file())
+            .collect()
+    } else {
+        paths.to_vec()
+    };
+    if paths.is_empty() {
+        term::info!("No artifacts of the release were found to verify");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for path in &paths {
+        let artifact = Artifact::from_path(path)
+            .with_context(|| term::tr!("could not read artifact {}", path.display()))?;
+
+        match release.artifacts.iter().find(|a| a.name == artifact.name) {
+            Some(expected) if expected.sha256 == artifact.sha256 => {
+                term::success!(
+                    "Artifact {} matches",
+                    term::format::highlight(&artifact.name)
+                );
+            }
+            Some(_) => {
+                term::error(term::tr!(
+                    "Artifact {} doesn't match its checksum",
+                    artifact.name
+                ));
+                failed += 1;
+            }
+            None => {
+                term::error(term::tr!(
+                    "Artifact {} is not part of the release",
+                    artifact.name
+                ));
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!(term::tr!(
+            "{} of {} artifact(s) failed verification",
+            failed,
+            paths.len()
+        ));
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::ffi::OsString;
+use std::path::Path;
 
 use anyhow::anyhow;
 use anyhow::Context as _;
@@ -6,7 +7,9 @@ use anyhow::Context as _;
 use librad::git::tracking;
 use librad::PeerId;
 
+use radicle_common::alias::{self, Aliases};
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::block::Blocklist;
 use radicle_common::{git, keys, project, sync, tokio};
 use radicle_terminal as term;
 
@@ -17,16 +20,27 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad remote add <name> <peer-id> [-f | --fetch]
+    rad remote add <peer-id> [--name <name>] [-f | --fetch]
     rad remote rm <name | peer-id>
     rad remote ls
 
+    Remotes point at a tracked peer's refs in local storage, so that its branches can be
+    fetched with `git fetch <name>`, and compared with eg. `git diff <name>/master`.
+    Adding a remote tracks the peer, if it isn't tracked yet.
+
+    If no name is given, the peer's alias is used, or else the name of its identity, or
+    else its peer id. A name given with `--name` is saved as the peer's alias, unless
+    the peer already has one.
+
+    Removing a remote also removes its remote-tracking branches.
+
 Examples
 
-    rad remote add cloudhead hyn9diwfnytahjq8u3iw63h9jte1ydcatxax3saymwdxqu1zo645pe
+    rad remote add hyn9diwfnytahjq8u3iw63h9jte1ydcatxax3saymwdxqu1zo645pe --name cloudhead
 
 Options
 
+    --name <name>   Name of the remote
     -f, --fetch     Fetch the remote immediately after it is setup
         --help      Print help
 "#,
@@ -35,7 +49,7 @@ Options
 #[derive(Debug)]
 pub enum Operation {
     Add {
-        name: String,
+        name: Option<String>,
         peer: PeerId,
         fetch: bool,
     },
@@ -58,6 +72,7 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut peer: Option<PeerId> = None;
         let mut remote: Option<String> = None;
+        let mut name: Option<String> = None;
         let mut op: Option<String> = None;
         let mut fetch = false;
 
@@ -69,15 +84,28 @@ impl Args for Options {
                 Long("fetch") | Short('f') if op.is_some() => {
                     fetch = true;
                 }
+                Long("name") if op.as_deref() == Some("add") => {
+                    let value = parser.value()?;
+                    let value = value.to_string_lossy();
+
+                    if value.trim().is_empty() {
+                        anyhow::bail!("invalid remote name: must not be empty");
+                    }
+                    name = Some(value.trim().to_owned());
+                }
                 Value(val) if op.is_none() => {
                     op = Some(val.to_string_lossy().to_string());
                 }
+                Value(val) if op.as_deref() == Some("add") && peer.is_none() => {
+                    peer = Some(
+                        val.to_string_lossy()
+                            .parse()
+                            .context("invalid value specified for peer")?,
+                    );
+                }
                 Value(val) if remote.is_none() => {
                     remote = Some(val.to_string_lossy().to_string());
                 }
-                Value(val) if peer.is_none() => {
-                    peer = Some(val.parse().context("invalid value specified for peer")?);
-                }
                 _ => {
                     return Err(anyhow!(arg.unexpected()));
                 }
@@ -87,7 +115,7 @@ impl Args for Options {
         let op = match op {
             Some(op) => match op.as_str() {
                 "add" => Operation::Add {
-                    name: remote.ok_or(Error::Usage)?,
+                    name,
                     peer: peer.ok_or(Error::Usage)?,
                     fetch,
                 },
@@ -96,7 +124,7 @@ impl Args for Options {
                 },
                 "ls" => Operation::List,
 
-                unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
             },
             None => Operation::List,
         };
@@ -113,26 +141,55 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match options.op {
         Operation::Add { name, peer, fetch } => {
+            if &peer == storage.peer_id() {
+                anyhow::bail!("you can't add yourself as a remote");
+            }
+            if Blocklist::load(&profile)?.contains(&peer) {
+                anyhow::bail!(term::tr!("peer {} is blocked", peer));
+            }
+            let aliases = Aliases::load(&repo);
+            let name = match name {
+                Some(name) => {
+                    if aliases.get(&peer).is_none() {
+                        alias::set(Path::new("."), &peer, &name)?;
+                    }
+                    name
+                }
+                None => match aliases.get(&peer) {
+                    Some(alias) => alias.to_owned(),
+                    None => match project::person(&storage, urn.clone(), &peer)? {
+                        Some(person) => person.subject().name.to_string(),
+                        None => peer.default_encoding(),
+                    },
+                },
+            };
+            if repo.find_remote(&name).is_ok() {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("remote '{}' already exists", name)),
+                    hint: "hint: choose a different name with `--name`",
+                }
+                .into());
+            }
             let mut remote = project::remote(&urn, &peer, &name)?;
             remote.save(&repo)?;
 
-            tracking::track(
+            let result = tracking::track(
                 &storage,
                 &urn,
                 Some(peer),
                 tracking::config::Config::default(),
-                tracking::policy::Track::Any,
-            )??;
-
-            // TODO: Only show this if new.
-            term::success!(
-                "Tracking relationship established with {}",
-                term::format::highlight(peer)
-            );
+                tracking::policy::Track::MustNotExist,
+            )?;
+            if result.is_ok() {
+                term::success!(
+                    "Tracking relationship established with {}",
+                    term::format::highlight(peer)
+                );
+            }
 
             if fetch {
                 let rt = tokio::runtime::Runtime::new()?;
-                let seeds = sync::seeds(&profile)?;
+                let seeds = term::sync::seeds(&profile, &urn)?;
 
                 term::sync::sync(urn, seeds, sync::Mode::Fetch, &profile, signer.clone(), &rt)?;
                 git::fetch_remote(&mut remote, &repo, signer, &profile)?;
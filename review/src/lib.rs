@@ -8,7 +8,7 @@ use radicle_common as common;
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::cobs::patch::Patch;
 use radicle_common::tokio;
-use radicle_common::{cobs, keys, project, sync};
+use radicle_common::{cobs, exit, keys, project, sync};
 use radicle_terminal as term;
 use radicle_terminal::patch::Comment;
 
@@ -24,11 +24,13 @@ Usage
     rad review [<id>] [--accept|--reject] [-m [<string>]] [<option>...]
 
     To specify a patch to review, use the fully qualified patch id
-    or an unambiguous prefix of it.
+    or an unambiguous prefix of it. If no id is given, the patch is
+    picked among the open patches, by typing part of its title.
 
 Options
 
     -r, --revision <number>   Revision number to review, defaults to the latest
+        --diff                Show the changes of the revision before reviewing
         --[no-]sync           Sync review to seed (default: sync)
     -m, --message [<string>]  Provide a comment with the review (default: prompt)
         --no-message          Don't provide a comment with the review
@@ -48,12 +50,13 @@ Markdown supported.
 
 #[derive(Debug)]
 pub struct Options {
-    pub id: cobs::Identifier,
+    pub id: Option<cobs::Identifier>,
     pub revision: Option<RevisionIx>,
     pub message: Comment,
     pub sync: bool,
     pub verbose: bool,
     pub verdict: Option<Verdict>,
+    pub diff: bool,
 }
 
 impl Args for Options {
@@ -64,9 +67,10 @@ impl Args for Options {
         let mut id: Option<cobs::Identifier> = None;
         let mut revision: Option<RevisionIx> = None;
         let mut message = Comment::default();
-        let mut sync = true;
+        let mut sync = sync::is_auto();
         let mut verbose = false;
         let mut verdict = None;
+        let mut diff = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -77,7 +81,10 @@ impl Args for Options {
                     let value = parser.value()?;
                     let id =
                         RevisionIx::from_str(value.to_str().unwrap_or_default()).map_err(|_| {
-                            anyhow!("invalid revision number `{}`", value.to_string_lossy())
+                            anyhow!(term::tr!(
+                                "invalid revision number `{}`",
+                                value.to_string_lossy()
+                            ))
                         })?;
                     revision = Some(id);
                 }
@@ -94,6 +101,9 @@ impl Args for Options {
                 Long("no-message") => {
                     message = Comment::Blank;
                 }
+                Long("diff") => {
+                    diff = true;
+                }
                 Long("verbose") | Short('v') => {
                     verbose = true;
                 }
@@ -110,7 +120,7 @@ impl Args for Options {
 
                     id = Some(
                         cobs::Identifier::from_str(val)
-                            .map_err(|_| anyhow!("invalid patch id '{}'", val))?,
+                            .map_err(|_| anyhow!(term::tr!("invalid patch id '{}'", val)))?,
                     );
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
@@ -119,12 +129,13 @@ impl Args for Options {
 
         Ok((
             Options {
-                id: id.ok_or_else(|| anyhow!("a patch id to review must be provided"))?,
+                id,
                 message,
                 sync,
                 revision,
                 verbose,
                 verdict,
+                diff,
             },
             vec![],
         ))
@@ -132,23 +143,35 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    let (urn, _) = project::cwd()
-        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let (urn, _) = project::cwd()?;
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
     let storage = keys::storage(&profile, signer.clone())?;
     let cobs = cobs::store(&profile, &storage)?;
     let patches = cobs.patches();
 
-    let (patch_id, mut patch) = patches
-        .resolve::<Patch>(&urn, &options.id)?
-        .ok_or_else(|| anyhow!("couldn't find patch {} locally", options.id))?;
+    let (patch_id, mut patch) = match &options.id {
+        Some(id) => patches.resolve::<Patch>(&urn, id)?.ok_or_else(|| {
+            exit::Error::NotFound(term::tr!("couldn't find patch {} locally", id))
+        })?,
+        None => term::patch::select(
+            term::tr!("Which patch do you want to review?"),
+            &patches,
+            &urn,
+            &storage,
+        )?
+        .ok_or_else(|| anyhow!("a patch id to review must be provided"))?,
+    };
     let patch_id_pretty = term::format::tertiary(common::fmt::cob(&patch_id));
     let revision_ix = options.revision.unwrap_or_else(|| patch.version());
-    let _revision = patch
-        .revisions
-        .get(revision_ix)
-        .ok_or_else(|| anyhow!("revision R{} does not exist", revision_ix))?;
+    let revision = patch.revisions.get(revision_ix).ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("revision R{} does not exist", revision_ix))
+    })?;
+
+    if options.diff {
+        term::patch::print_diff(profile.paths().git_dir(), revision)?;
+        term::blank();
+    }
     let message = options.message.get(REVIEW_HELP_MSG);
 
     patch.author.resolve(&storage).ok();
@@ -158,14 +181,14 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         Some(Verdict::Reject) => term::format::negative("Reject"),
         None => term::format::dim("Review"),
     };
-    if !term::confirm(format!(
+    if !term::confirm(term::tr!(
         "{} {} {} by {}?",
         verdict_pretty,
         patch_id_pretty,
         term::format::dim(format!("R{}", revision_ix)),
         term::format::tertiary(patch.author.name())
-    )) {
-        anyhow::bail!("Patch review aborted");
+    ))? {
+        anyhow::bail!(exit::Error::Aborted("Patch review aborted".to_owned()));
     }
 
     patches.review(
@@ -202,7 +225,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
         term::sync::sync(
             urn,
-            sync::seeds(&profile)?,
+            term::sync::seeds(&profile, &urn)?,
             sync::Mode::Push,
             &profile,
             signer,
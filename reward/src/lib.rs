@@ -72,7 +72,10 @@ impl FromStr for Strategy {
         match strategy.to_lowercase().as_str() {
             "equally" => Ok(Strategy::Equally),
             "weighted" => Ok(Strategy::Weighted),
-            _ => Err(anyhow::anyhow!("Strategy undefined: {}", strategy)),
+            _ => Err(anyhow::anyhow!(term::tr!(
+                "Strategy undefined: {}",
+                strategy
+            ))),
         }
     }
 }
@@ -362,7 +365,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         return Ok(());
     }
 
-    if term::confirm("Do you wish to proceed?") {
+    if term::confirm("Do you wish to proceed?")? {
         let sum = amounts
             .iter()
             .fold(Some(U256::from(0_u64)), |sum, amt| {
@@ -443,7 +446,10 @@ fn calculate_rewards(
             let this_reward = reward_per_share
                 .checked_mul(U256::from(share))
                 .ok_or_else(|| {
-                    anyhow!("Failed to multiply reward by number of shares for {}", ens)
+                    anyhow!(term::tr!(
+                        "Failed to multiply reward by number of shares for {}",
+                        ens
+                    ))
                 })?;
             rewards.insert(ens.to_string(), this_reward);
         }
@@ -1,15 +1,16 @@
 use std::convert::From;
 use std::ffi::OsString;
-use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::anyhow;
 
+use librad::git::refs::Refs;
 use librad::git::Urn;
 use librad::PeerId;
 
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::{keys, profile, project};
+use radicle_common::{exit, fmt, git, keys, profile, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -20,13 +21,25 @@ pub const HELP: Help = Help {
 Usage
 
     rad rm <urn | peer-id> [<option>...]
+    rad rm <urn> --cobs-only
+    rad rm <urn> --remotes <peer-id> [--remotes <peer-id>]...
 
     Removes a project if URN is given or user if Peer ID is given.
 
+    Removing a project irreversibly deletes it from local storage. To confirm, the
+    name of the project must be typed in. Working copies of the project, in the
+    current directory or below it, are reported, as they will no longer be usable.
+
+    With `--cobs-only`, only the project's collaborative objects, eg. patches and
+    issues, are removed. With `--remotes`, only the refs of the given peers are
+    removed, and the peers are untracked.
+
 Options
 
     --no-confirm        Do not ask for confirmation before removal
                         (default: false)
+    --cobs-only         Only remove the project's collaborative objects
+    --remotes <peer-id> Only remove the refs of the given peer
     --no-passphrase     If Peer ID is given, bypass passphrase prompt and
                         neither read environment variable `RAD_PASSPHRASE`
                         nor standard input stream (default: false)
@@ -55,6 +68,8 @@ impl From<&str> for Object {
 
 pub struct Options {
     object: Object,
+    cobs_only: bool,
+    remotes: Vec<PeerId>,
     confirm: bool,
     passphrase: bool,
     stdin: bool,
@@ -66,6 +81,8 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut object: Option<Object> = None;
+        let mut cobs_only = false;
+        let mut remotes = Vec::new();
         let mut confirm = true;
         let mut passphrase = true;
         let mut stdin = false;
@@ -75,6 +92,17 @@ impl Args for Options {
                 Long("no-confirm") => {
                     confirm = false;
                 }
+                Long("cobs-only") => {
+                    cobs_only = true;
+                }
+                Long("remotes") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let peer = PeerId::from_str(&val)
+                        .map_err(|_| anyhow!(term::tr!("invalid peer id '{}'", val)))?;
+
+                    remotes.push(peer);
+                }
                 Long("no-passphrase") => {
                     passphrase = false;
                 }
@@ -93,11 +121,21 @@ impl Args for Options {
             }
         }
 
+        let object = object.ok_or_else(|| {
+            anyhow!("Urn or peer id to remove must be provided; see `rad rm --help`")
+        })?;
+        if cobs_only && !remotes.is_empty() {
+            anyhow::bail!("`--cobs-only` cannot be combined with `--remotes`");
+        }
+        if (cobs_only || !remotes.is_empty()) && !matches!(object, Object::Project(_)) {
+            anyhow::bail!("`--cobs-only` and `--remotes` require a project URN");
+        }
+
         Ok((
             Options {
-                object: object.ok_or_else(|| {
-                    anyhow!("Urn or peer id to remove must be provided; see `rad rm --help`")
-                })?,
+                object,
+                cobs_only,
+                remotes,
                 confirm,
                 passphrase,
                 stdin,
@@ -115,25 +153,105 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match &options.object {
         Object::Project(urn) => {
-            if let Ok(Some(_)) = project::get(&storage, urn) {
-                let monorepo = profile.paths().git_dir();
-                let namespace = monorepo
-                    .join("refs")
-                    .join("namespaces")
-                    .join(&urn.encode_id());
-
-                if !options.confirm
-                    || term::confirm(format!(
-                        "Are you sure you would like to delete {}?",
-                        term::format::dim(namespace.display())
-                    ))
+            let proj = match project::get(&storage, urn) {
+                Ok(Some(proj)) => proj,
+                _ => anyhow::bail!(exit::Error::NotFound(term::tr!(
+                    "project {} does not exist",
+                    &urn
+                ))),
+            };
+            let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+            let namespace = format!("refs/namespaces/{}/refs", urn.encode_id());
+
+            if options.cobs_only {
+                let refs = [
+                    format!("{}/cobs/*", namespace),
+                    format!("{}/remotes/*/cobs/*", namespace),
+                ];
+                if options.confirm
+                    && !term::confirm(term::tr!(
+                        "Are you sure you would like to delete the collaborative objects of {}?",
+                        term::format::highlight(&proj.name)
+                    ))?
                 {
-                    rad_untrack::execute(urn, None, rad_untrack::Options { peer: None }, &profile)?;
-                    fs::remove_dir_all(namespace)?;
-                    term::success!("Successfully removed project {}", &urn);
+                    return Ok(());
                 }
+                let removed = remove_refs(&monorepo, &refs)?;
+
+                // Our own collaborative objects are part of our signed refs.
+                let signer = term::signer(&profile)?;
+                let storage = keys::storage(&profile, signer)?;
+                Refs::update(&storage, urn)?;
+
+                term::success!(
+                    "Successfully removed {} collaborative object ref(s) of {}",
+                    removed,
+                    &urn
+                );
+            } else if !options.remotes.is_empty() {
+                let peers = options
+                    .remotes
+                    .iter()
+                    .map(|p| term::format::tertiary(fmt::peer(p)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                if options.confirm
+                    && !term::confirm(term::tr!(
+                        "Are you sure you would like to delete the refs of {}?",
+                        peers
+                    ))?
+                {
+                    return Ok(());
+                }
+                for peer in &options.remotes {
+                    rad_untrack::execute(
+                        urn,
+                        None,
+                        rad_untrack::Options {
+                            peer: Some(peer.default_encoding()),
+                            confirm: false,
+                        },
+                        &profile,
+                    )?;
+                    remove_refs(&monorepo, &[format!("{}/remotes/{}/*", namespace, peer)])?;
+                }
+                term::success!("Successfully removed the refs of {}", peers);
             } else {
-                anyhow::bail!("project {} does not exist", &urn)
+                for path in working_copies(urn) {
+                    term::warning(&term::tr!(
+                        "The working copy at {} references this project, and won't be usable once it's removed",
+                        path.display()
+                    ));
+                }
+                if options.confirm {
+                    let name = term::text_input::<String, _>(
+                        &format!(
+                            "This will irreversibly delete {} from local storage. Type the project name to confirm",
+                            term::format::highlight(&proj.name)
+                        ),
+                        None,
+                    )?;
+                    if name.trim() != proj.name {
+                        anyhow::bail!(exit::Error::Aborted(
+                            "project name does not match, aborting".to_owned()
+                        ));
+                    }
+                }
+                rad_untrack::execute(
+                    urn,
+                    None,
+                    rad_untrack::Options {
+                        peer: None,
+                        confirm: false,
+                    },
+                    &profile,
+                )?;
+                remove_refs(
+                    &monorepo,
+                    &[format!("refs/namespaces/{}/*", urn.encode_id())],
+                )?;
+                term::success!("Successfully removed project {}", &urn);
             }
         }
         Object::User(peer_id) => {
@@ -151,11 +269,11 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     let config = read_only.config()?;
                     let username = config.user_name()?;
                     if options.confirm
-                        && !term::confirm(format!(
+                        && !term::confirm(term::tr!(
                             "Are you sure you would like to remove {} ({})?",
                             term::format::dim(peer_id),
                             term::format::dim(username)
-                        ))
+                        ))?
                     {
                         return Ok(());
                     }
@@ -168,7 +286,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     profile::remove(other)?;
                     term::success!("Successfully removed user {}", peer_id);
                 } else {
-                    anyhow::bail!("No user found with Peer ID: {}", peer_id);
+                    anyhow::bail!(term::tr!("No user found with Peer ID: {}", peer_id));
                 }
             } else {
                 anyhow::bail!("Cannot remove active user; see `rad rm --help`");
@@ -181,3 +299,49 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Remove the refs matching the given globs from the monorepo. Returns the number of
+/// refs removed.
+fn remove_refs(monorepo: &git::Repository, globs: &[String]) -> anyhow::Result<usize> {
+    let mut removed = 0;
+
+    for glob in globs {
+        let refs = monorepo
+            .references_glob(glob)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for mut r in refs {
+            r.delete()?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Find the working copies of a project: the current directory, and the directories
+/// right below it, which is where `rad checkout` creates them.
+fn working_copies(urn: &Urn) -> Vec<PathBuf> {
+    let references = |path: &Path| {
+        git::Repository::open(path)
+            .ok()
+            .and_then(|repo| git::rad_remote(&repo).ok())
+            .map_or(false, |remote| remote.url.urn == *urn)
+    };
+    let mut paths = Vec::new();
+
+    if let Ok(cwd) = std::env::current_dir() {
+        if references(&cwd) {
+            paths.push(cwd.clone());
+        }
+        if let Ok(entries) = std::fs::read_dir(&cwd) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+
+                if path.join(".git").exists() && references(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    paths
+}
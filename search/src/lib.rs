@@ -0,0 +1,162 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::search::{Index, Kind};
+use radicle_common::{json, keys, Urn};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "search",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad search <query>... [--project <urn>]
+
+    Searches the projects in local storage, by name and description, as well as their
+    issues and patches, by title and description. Results contain all words of the
+    query, in any case; the last word also matches words it starts with. Best matches
+    are listed first.
+
+    Searches go through a local index, which is updated with the projects that changed
+    since they were indexed before searching, and after each `rad sync` that fetches.
+
+    With `rad --json`, results are printed as a JSON array.
+
+Options
+
+    --project <urn>   Only search the given project, and its issues and patches
+    --help            Print help
+"#,
+};
+
+#[derive(Default, Debug)]
+pub struct Options {
+    pub query: String,
+    pub project: Option<Urn>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut words: Vec<String> = Vec::new();
+        let mut project: Option<Urn> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("project") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    project = Some(
+                        Urn::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid URN '{}'", val)))?,
+                    );
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) => {
+                    words.push(val.to_string_lossy().into_owned());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+        if words.iter().all(|w| w.trim().is_empty()) {
+            return Err(Error::WithHint {
+                err: anyhow!("a query must be given"),
+                hint: "hint: search with eg. `rad search sync error`",
+            }
+            .into());
+        }
+
+        Ok((
+            Options {
+                query: words.join(" "),
+                project,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = keys::storage_read_only(&profile)?;
+    let mut index = Index::load(&profile)?;
+
+    let spinner = term::spinner("Indexing...");
+    let indexed = match &options.project {
+        Some(urn) => index.update(&profile, &storage, urn).map(usize::from),
+        None => index.update_all(&profile, &storage),
+    };
+    match indexed {
+        Ok(0) => spinner.finish(),
+        Ok(_) => {
+            spinner.finish();
+            index.write(&profile)?;
+        }
+        Err(err) => return Err(spinner.error(err)),
+    }
+
+    let matches = index.search(&options.query, options.project.as_ref());
+
+    if term::output::is_json() {
+        let results = matches
+            .iter()
+            .map(|m| {
+                json::json!({
+                    "kind": m.document.kind,
+                    "project": m.document.project,
+                    "id": m.document.id,
+                    "title": m.document.title,
+                    "score": m.score,
+                })
+            })
+            .collect();
+        term::output::json(&json::Value::Array(results))?;
+
+        return Ok(());
+    }
+    if matches.is_empty() {
+        term::print(term::format::italic("No results."));
+        return Ok(());
+    }
+
+    let mut table = term::Table::new(term::table::TableOptions {
+        truncate: Some(1),
+        ..Default::default()
+    });
+    for m in &matches {
+        let doc = m.document;
+        let project = index
+            .project(&doc.project)
+            .map(|p| p.title.clone())
+            .unwrap_or_else(|| doc.project.clone());
+        let id = match &doc.id {
+            Some(id) => format!("{:.11}", id),
+            None => doc.project.clone(),
+        };
+        let kind = match doc.kind {
+            Kind::Project => term::format::positive(doc.kind.as_str()),
+            Kind::Issue => term::format::yellow(doc.kind.as_str()),
+            Kind::Patch => term::format::secondary(doc.kind.as_str()),
+        };
+
+        table.push([
+            kind,
+            term::format::bold(&doc.title),
+            term::format::highlight(id),
+            term::format::dim(project),
+        ]);
+    }
+    table.render();
+
+    Ok(())
+}
@@ -0,0 +1,413 @@
+#![allow(clippy::or_fun_call)]
+use std::convert::TryFrom;
+use std::ffi::OsString;
+use std::io;
+use std::str::FromStr;
+use std::time;
+
+use anyhow::anyhow;
+use url::Url;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::config::{Config, SeedConfig};
+use radicle_common::credentials::{Credential, Credentials};
+use radicle_common::profile::Profile;
+use radicle_common::seed::{self, Address, Protocol};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "seed",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad seed ls
+    rad seed add <url> [--name <name>] [--default]
+    rad seed rm <name | host>
+    rad seed set-default <name | host>
+    rad seed ping <name | host | url>
+    rad seed login <host | url> [--stdin]
+    rad seed logout <host | url>
+
+    Seeds are stored in the profile configuration, and used when a project
+    doesn't configure its own seeds. The default seed is the first seed synced with.
+
+    The <url> given to `add` is either a seed's `rad://` URL, eg.
+    rad://<peer-id>@<host>:<port>, or its HTTPS URL, in which case the seed's
+    peer id is queried from its API.
+
+    `login` stores an access token for a seed that requires authentication, eg. for
+    pushes. The token is prompted for, or read from the standard input stream with
+    `--stdin`. Tokens are stored in the profile's `credentials.toml`, readable only by
+    you, and are sent to the seed's HTTP git endpoint when it asks for credentials, ie.
+    when fetching from and pushing to it with `rad sync --transport http`. Peer-to-peer
+    syncs are authenticated by your peer key, and don't use tokens.
+
+Examples
+
+    rad seed add https://pine.radicle.garden --name pine
+    rad seed ping pine.radicle.garden
+    rad seed login seed.example.com
+
+Options
+
+    --name <name>   Name of the seed (default: the seed's host)
+    --default       Make the added seed the default seed
+    --stdin         Read the access token to log in with from stdin
+    --help          Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Add,
+    Remove,
+    SetDefault,
+    Ping,
+    Login,
+    Logout,
+    List,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Add {
+        url: Url,
+        name: Option<String>,
+        default: bool,
+    },
+    Remove {
+        seed: String,
+    },
+    SetDefault {
+        seed: String,
+    },
+    Ping {
+        seed: String,
+    },
+    Login {
+        seed: String,
+        stdin: bool,
+    },
+    Logout {
+        seed: String,
+    },
+    List,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut seed: Option<String> = None;
+        let mut name: Option<String> = None;
+        let mut default = false;
+        let mut stdin = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("name") if op == Some(OperationName::Add) => {
+                    name = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("default") if op == Some(OperationName::Add) => {
+                    default = true;
+                }
+                Long("stdin") if op == Some(OperationName::Login) => {
+                    stdin = true;
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "add" => op = Some(OperationName::Add),
+                    "rm" => op = Some(OperationName::Remove),
+                    "set-default" => op = Some(OperationName::SetDefault),
+                    "ping" => op = Some(OperationName::Ping),
+                    "login" => op = Some(OperationName::Login),
+                    "logout" => op = Some(OperationName::Logout),
+                    "ls" => op = Some(OperationName::List),
+
+                    unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if seed.is_none() => {
+                    seed = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Add => {
+                let seed = seed.ok_or_else(|| anyhow!("a seed URL must be specified"))?;
+                let url = Url::from_str(&seed).map_err(|_| Error::WithHint {
+                    err: anyhow!(term::tr!("invalid seed URL specified: '{}'", seed)),
+                    hint: "hint: seed URLs have the format rad://<peer-id>@<host>:<port> or https://<host>",
+                })?;
+
+                Operation::Add { url, name, default }
+            }
+            OperationName::Remove => Operation::Remove {
+                seed: seed.ok_or_else(|| anyhow!("a seed name or host must be specified"))?,
+            },
+            OperationName::SetDefault => Operation::SetDefault {
+                seed: seed.ok_or_else(|| anyhow!("a seed name or host must be specified"))?,
+            },
+            OperationName::Ping => Operation::Ping {
+                seed: seed.ok_or_else(|| anyhow!("a seed must be specified"))?,
+            },
+            OperationName::Login => Operation::Login {
+                seed: seed.ok_or_else(|| anyhow!("a seed host must be specified"))?,
+                stdin,
+            },
+            OperationName::Logout => Operation::Logout {
+                seed: seed.ok_or_else(|| anyhow!("a seed host must be specified"))?,
+            },
+            OperationName::List => Operation::List,
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let mut config = load(&profile)?;
+
+    match options.op {
+        Operation::Add { url, name, default } => {
+            let mut seed = seed_config(url)?;
+            if let Some(name) = name {
+                seed.name = Some(name);
+            }
+            let host = seed.host().unwrap_or_default().to_owned();
+
+            if config.seeds().any(|s| s.host() == Some(host.as_str())) {
+                anyhow::bail!(term::tr!("seed {} is already configured", host));
+            }
+            let name = seed.name.clone().unwrap_or(host);
+
+            if default {
+                config.seed.insert(0, seed);
+            } else {
+                config.seed.push(seed);
+            }
+            config.write(Config::path(&profile))?;
+
+            term::success!("Seed {} added", term::format::highlight(name));
+        }
+        Operation::Remove { seed } => {
+            let query = host(&seed);
+            let len = config.seed.len();
+
+            config.seed.retain(|s| !s.matches(&query));
+            if config.seed.len() == len {
+                anyhow::bail!(term::tr!("seed {} is not configured", query));
+            }
+            config.write(Config::path(&profile))?;
+
+            term::success!("Seed {} removed", term::format::highlight(query));
+        }
+        Operation::SetDefault { seed } => {
+            let query = host(&seed);
+            let ix = config
+                .seeds()
+                .position(|s| s.matches(&query))
+                .ok_or_else(|| anyhow!(term::tr!("seed {} is not configured", query)))?;
+            let seed = config.seed.remove(ix);
+
+            config.seed.insert(0, seed);
+            config.write(Config::path(&profile))?;
+
+            term::success!(
+                "Seed {} set as the default seed",
+                term::format::highlight(query)
+            );
+        }
+        Operation::Ping { seed } => {
+            ping(&config, &seed)?;
+        }
+        Operation::Login { seed, stdin } => {
+            login(&profile, &seed, stdin)?;
+        }
+        Operation::Logout { seed } => {
+            let query = host(&seed);
+            let mut credentials = Credentials::load(&profile)?;
+
+            if credentials.seed.remove(&query).is_none() {
+                anyhow::bail!(term::tr!("not logged in to seed {}", query));
+            }
+            credentials.write(&profile)?;
+
+            term::success!("Logged out of seed {}", term::format::highlight(query));
+        }
+        Operation::List => {
+            let credentials = Credentials::load(&profile)?;
+
+            list(&config, &credentials);
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the profile configuration. If it doesn't exist yet, no seeds are configured.
+fn load(profile: &Profile) -> anyhow::Result<Config> {
+    match Config::profile(profile) {
+        Ok(config) => Ok(config),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config {
+            seed: Vec::new(),
+            ..Default::default()
+        }),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn list(config: &Config, credentials: &Credentials) {
+    let mut table = term::Table::default();
+
+    for (i, seed) in config.seeds().enumerate() {
+        table.push([
+            term::format::bold(seed.name.clone().unwrap_or_default()),
+            term::format::highlight(&seed.p2p),
+            term::format::dim(&seed.api),
+            if seed.host().and_then(|h| credentials.get(h)).is_some() {
+                term::format::badge_secondary("logged in")
+            } else {
+                String::new()
+            },
+            if i == 0 {
+                term::format::badge_primary("default")
+            } else {
+                String::new()
+            },
+        ]);
+    }
+
+    if config.seed.is_empty() {
+        term::info!("{}", term::format::italic("No seeds configured."));
+        term::tip!("Add a seed with `rad seed add <url>`.");
+    } else {
+        table.render();
+    }
+}
+
+fn login(profile: &Profile, seed: &str, stdin: bool) -> anyhow::Result<()> {
+    let query = host(seed);
+    let token = if stdin {
+        term::secret_stdin()?.unsecure().to_owned()
+    } else {
+        term::secret_input_with_prompt("Token")
+            .unsecure()
+            .to_owned()
+    };
+    if token.trim().is_empty() {
+        anyhow::bail!("the token must not be empty");
+    }
+    let mut credentials = Credentials::load(profile)?;
+
+    credentials.seed.insert(
+        query.clone(),
+        Credential {
+            token: token.trim().to_owned(),
+        },
+    );
+    credentials.write(profile)?;
+
+    term::success!("Logged in to seed {}", term::format::highlight(query));
+
+    Ok(())
+}
+
+fn ping(config: &Config, seed: &str) -> anyhow::Result<()> {
+    let api = if let Some(cfg) = config.seeds().find(|s| s.matches(&host(seed))) {
+        cfg.api.clone()
+    } else {
+        seed_api(seed)?
+    };
+    let spinner = term::spinner(term::tr!("Pinging {}...", term::format::highlight(&api)));
+    let now = time::Instant::now();
+
+    let id = match seed::get_seed_id(api.clone()) {
+        Ok(id) => id,
+        Err(err) => {
+            return Err(spinner.error(anyhow!(term::tr!(
+                "seed {} is not reachable: {}",
+                api,
+                err
+            ))));
+        }
+    };
+    let elapsed = now.elapsed();
+    let version = seed::get_seed_version(api).ok();
+
+    spinner.finish();
+
+    term::info!(
+        "{} {}",
+        term::format::dim("Peer ID"),
+        term::format::highlight(id)
+    );
+    term::info!(
+        "{} {}",
+        term::format::dim("Version"),
+        version.unwrap_or_else(|| term::format::italic("unknown"))
+    );
+    term::info!("{} {}ms", term::format::dim("Latency"), elapsed.as_millis());
+
+    Ok(())
+}
+
+/// Create a seed configuration from a seed URL, querying the seed for its peer id if
+/// the URL doesn't include it.
+fn seed_config(url: Url) -> anyhow::Result<SeedConfig> {
+    let addr = Address::try_from(url)?;
+    let peer = match addr.peer() {
+        Some(peer) => peer,
+        None => {
+            let api: Url = Address::new(addr.host.clone(), Protocol::Api { local: false }).into();
+            let spinner = term::spinner(term::tr!(
+                "Querying peer id of {}...",
+                term::format::highlight(&addr.host)
+            ));
+
+            match seed::get_seed_id(api) {
+                Ok(peer) => {
+                    spinner.finish();
+                    peer
+                }
+                Err(err) => return Err(spinner.error(err)),
+            }
+        }
+    };
+    let mut seed = SeedConfig::new(addr.host.clone(), Some(peer));
+
+    if let (Protocol::Link { .. }, Some(port)) = (addr.protocol, addr.port) {
+        seed.p2p.set_port(Some(port)).ok();
+    }
+    Ok(seed)
+}
+
+/// Get the API URL of a seed given by host or URL.
+fn seed_api(seed: &str) -> anyhow::Result<Url> {
+    let addr = Address::from_str(seed)
+        .or_else(|_| Address::from_str(&format!("https://{}", seed)))
+        .map_err(|_| anyhow!(term::tr!("invalid seed '{}'", seed)))?;
+
+    Ok(Address::new(addr.host, Protocol::Api { local: false }).into())
+}
+
+/// Get the host of a seed given by URL, or return the value as-is.
+fn host(seed: &str) -> String {
+    Url::from_str(seed)
+        .ok()
+        .and_then(|url| url.host_str().map(|h| h.to_owned()))
+        .unwrap_or_else(|| seed.to_owned())
+}
@@ -1,9 +1,19 @@
 use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use anyhow::anyhow;
+use serde_json::json;
+
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::cobs::Timestamp;
+use radicle_common::Url;
 
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::{keys, person, profile};
+use radicle_common::identity::quorum;
+use radicle_common::{backup, exit, fmt, git, keys, person, profile};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -14,14 +24,53 @@ pub const HELP: Help = Help {
 Usage
 
     rad self [<option>...]
+    rad self --json
+    rad self set [--name <name>] [--avatar <url>] [--url <url>]
+    rad self export --output <file> [--stdin]
+    rad self devices
+    rad self revoke-device <peer-id>
+
+    `set` updates your personal identity with a new name, avatar image URL, or
+    homepage URL, and signs the new revision of the identity. Values that aren't given
+    are kept. To publish the new revision, run `rad sync --self`.
+
+    `export` writes an encrypted archive of your profile, to back it up or move it to
+    another machine. The archive holds your secret key, your personal identity, and
+    your profile configuration, including seed credentials and blocked peers. Projects
+    are not included; they can be fetched again from seeds.
+
+    The archive is encrypted with your passphrase, which is verified before exporting.
+    To restore the archive, run `rad auth --import <file>`.
+
+    `devices` lists the devices your identity is linked to, ie. the keys of your
+    identity, with the last time each device was seen, ie. the latest time it signed
+    its refs, as known to this device.
+
+    `revoke-device` removes the key of a device from your identity, eg. when it was
+    lost. As with any change to an identity, the new revision only takes effect once
+    signed by a majority of your devices: the other devices sign it by fetching your
+    identity and running the same command. To publish the revision, run
+    `rad sync --self`.
+
+    With `--json`, all the details of your identity and device are printed as a JSON
+    object, including the paths of your profile and the signer commands use: either
+    `ssh-agent`, or your secret key, unlocked with your passphrase. With `rad --json`,
+    this is also the default output, single details are printed as JSON strings, and
+    `devices` prints a JSON array.
 
 Options
 
-    --name       Show name
-    --urn        Show URN
-    --peer       Show Peer ID
-    --profile    Show Profile ID
-    --help       Show help
+    --name              Show name
+    --urn               Show URN
+    --peer              Show Peer ID
+    --profile           Show Profile ID
+    --json              Show all details as JSON
+    --name <name>       Set name (with `set`)
+    --avatar <url>      Set avatar image URL (with `set`)
+    --url <url>         Set homepage URL (with `set`)
+    --output <file>     Archive file to export to
+    --stdin             Read passphrase from stdin (default: false)
+    --help              Show help
 "#,
 };
 
@@ -32,11 +81,38 @@ enum Show {
     Peer,
     Profile,
     All,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum OperationName {
+    Set,
+    Export,
+    Devices,
+    RevokeDevice,
+}
+
+#[derive(Debug)]
+enum Operation {
+    Show(Show),
+    Set {
+        name: Option<String>,
+        avatar: Option<Url>,
+        url: Option<Url>,
+    },
+    Export {
+        output: PathBuf,
+        stdin: bool,
+    },
+    Devices,
+    RevokeDevice {
+        peer: PeerId,
+    },
 }
 
 #[derive(Debug)]
 pub struct Options {
-    show: Show,
+    op: Operation,
 }
 
 impl Args for Options {
@@ -45,9 +121,53 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut show: Option<Show> = None;
+        let mut op: Option<OperationName> = None;
+        let mut name: Option<String> = None;
+        let mut avatar: Option<Url> = None;
+        let mut url: Option<Url> = None;
+        let mut output: Option<PathBuf> = None;
+        let mut stdin = false;
+        let mut device: Option<PeerId> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
+                Value(val) if op.is_none() && show.is_none() => {
+                    match val.to_string_lossy().as_ref() {
+                        "set" => op = Some(OperationName::Set),
+                        "export" => op = Some(OperationName::Export),
+                        "devices" => op = Some(OperationName::Devices),
+                        "revoke-device" => op = Some(OperationName::RevokeDevice),
+
+                        unknown => anyhow::bail!(term::tr!("unknown operation '{}'", unknown)),
+                    }
+                }
+                Value(val) if op == Some(OperationName::RevokeDevice) && device.is_none() => {
+                    let val = val.to_string_lossy();
+                    let peer = PeerId::from_str(&val)
+                        .map_err(|_| anyhow!(term::tr!("invalid peer id '{}'", val)))?;
+
+                    device = Some(peer);
+                }
+                Long("name") if op == Some(OperationName::Set) && name.is_none() => {
+                    let val = parser.value()?.to_string_lossy().to_string();
+
+                    if val.is_empty() || val.contains(char::is_whitespace) {
+                        anyhow::bail!("name must be non-empty and cannot contain whitespaces");
+                    }
+                    name = Some(val);
+                }
+                Long("avatar") if op == Some(OperationName::Set) && avatar.is_none() => {
+                    avatar = Some(url_value(&mut parser, "--avatar")?);
+                }
+                Long("url") if op == Some(OperationName::Set) && url.is_none() => {
+                    url = Some(url_value(&mut parser, "--url")?);
+                }
+                Long("output") if op == Some(OperationName::Export) && output.is_none() => {
+                    output = Some(PathBuf::from(parser.value()?));
+                }
+                Long("stdin") if op == Some(OperationName::Export) => {
+                    stdin = true;
+                }
                 Long("name") if show.is_none() => {
                     show = Some(Show::Name);
                 }
@@ -60,6 +180,9 @@ impl Args for Options {
                 Long("profile") if show.is_none() => {
                     show = Some(Show::Profile);
                 }
+                Long("json") if show.is_none() => {
+                    show = Some(Show::Json);
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -67,12 +190,26 @@ impl Args for Options {
             }
         }
 
-        Ok((
-            Options {
-                show: show.unwrap_or(Show::All),
+        let op = match op {
+            Some(OperationName::Set) => {
+                if name.is_none() && avatar.is_none() && url.is_none() {
+                    anyhow::bail!("at least one of `--name`, `--avatar` or `--url` must be given");
+                }
+                Operation::Set { name, avatar, url }
+            }
+            Some(OperationName::Export) => Operation::Export {
+                output: output
+                    .ok_or_else(|| anyhow!("an output file must be specified with `--output`"))?,
+                stdin,
             },
-            vec![],
-        ))
+            Some(OperationName::Devices) => Operation::Devices,
+            Some(OperationName::RevokeDevice) => Operation::RevokeDevice {
+                peer: device.ok_or_else(|| anyhow!("the peer id of a device must be specified"))?,
+            },
+            None => Operation::Show(show.unwrap_or(Show::All)),
+        };
+
+        Ok((Options { op }, vec![]))
     }
 }
 
@@ -80,34 +217,336 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let storage = profile::read_only(&profile)?;
 
-    match options.show {
+    let show = match options.op {
+        Operation::Show(show) => show,
+        Operation::Set { name, avatar, url } => return set(&profile, name, avatar, url),
+        Operation::Export { output, stdin } => return export(&profile, &output, stdin),
+        Operation::Devices => return devices(&profile),
+        Operation::RevokeDevice { peer } => return revoke_device(&profile, &peer),
+    };
+
+    match show {
         Show::Name => {
             if let Some(urn) = storage.config()?.user()? {
                 if let Some(person) = person::get(&storage, &urn)? {
-                    term::print(&person.subject().name.to_string());
+                    value(&person.subject().name)?;
                 }
             }
         }
         Show::Profile => {
-            term::print(profile.id());
+            value(profile.id())?;
         }
         Show::Peer => {
-            term::print(storage.peer_id());
+            value(storage.peer_id())?;
         }
         Show::Urn => {
-            term::print(
+            value(
                 storage
                     .config()?
                     .user()?
                     .ok_or_else(|| anyhow!("no user found"))?,
-            );
+            )?;
         }
+        Show::All if term::output::is_json() => json(&profile)?,
         Show::All => all(&profile)?,
+        Show::Json => json(&profile)?,
+    }
+
+    Ok(())
+}
+
+/// Print a single detail, as a JSON string in JSON mode.
+fn value(val: impl ToString) -> anyhow::Result<()> {
+    if term::output::is_json() {
+        term::output::json(&json!(val.to_string()))
+    } else {
+        term::print(val.to_string());
+        Ok(())
+    }
+}
+
+fn url_value(parser: &mut lexopt::Parser, option: &str) -> anyhow::Result<Url> {
+    let val = parser.value()?;
+    let val = val.to_string_lossy();
+
+    Url::parse(&val).map_err(|_| {
+        anyhow!(term::tr!(
+            "invalid URL '{}' specified with `{}`",
+            val,
+            option
+        ))
+    })
+}
+
+fn set(
+    profile: &profile::Profile,
+    name: Option<String>,
+    avatar: Option<Url>,
+    url: Option<Url>,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+    let person = person::set_metadata(&storage, name.as_deref(), avatar, url)?;
+
+    term::success!("Identity {} updated", term::format::highlight(person.urn()));
+    term::tip!("To publish the new revision, run `rad sync --self`.");
+
+    Ok(())
+}
+
+fn export(profile: &profile::Profile, output: &Path, stdin: bool) -> anyhow::Result<()> {
+    if output.exists() {
+        anyhow::bail!(term::tr!("'{}' already exists", output.display()));
+    }
+    term::headline(&term::tr!(
+        "🌱 Exporting {}",
+        term::display::Identity::new(profile).styled()
+    ));
+
+    let passphrase = term::read_passphrase(stdin, false)?;
+    let spinner = term::spinner("Encrypting profile...");
+
+    match backup::export(profile, passphrase, output) {
+        Ok(()) => spinner.finish(),
+        Err(err) => return Err(spinner.error(err)),
     }
+    term::success!(
+        "Profile exported to {}",
+        term::format::highlight(output.display())
+    );
+    term::tip!("Keep the archive safe: it can be used to sign as you, given your passphrase.");
 
     Ok(())
 }
 
+fn devices(profile: &profile::Profile) -> anyhow::Result<()> {
+    let storage = profile::read_only(profile)?;
+    let urn = storage
+        .config()?
+        .user()?
+        .ok_or_else(|| anyhow!("no user found"))?;
+    let person = person::get(&storage, &urn)?
+        .ok_or_else(|| exit::Error::NotFound(term::tr!("identity {} not found", urn)))?;
+    let verified = person::verify(&storage, &urn)?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let mut table = term::Table::default();
+    let mut output = Vec::new();
+
+    for key in person.delegations().iter() {
+        let peer = PeerId::from(*key);
+        let this = peer == *storage.peer_id();
+        let seen = last_seen(&monorepo, &peer, this)?;
+
+        output.push(json!({
+            "peerId": peer.to_string(),
+            "fingerprint": keys::to_ssh_fingerprint(&peer)?,
+            "seen": seen.map(|t| t.as_secs()),
+            "this": this,
+        }));
+
+        table.push([
+            term::format::tertiary(peer.default_encoding()),
+            term::format::dim(keys::to_ssh_fingerprint(&peer)?),
+            match seen {
+                Some(t) => format!("seen {}", t),
+                None => String::from("never seen"),
+            },
+            if this {
+                term::format::badge_primary("this device")
+            } else {
+                String::new()
+            },
+        ]);
+    }
+    if term::output::is_json() {
+        term::output::json(&serde_json::Value::Array(output))?;
+    } else {
+        table.render();
+    }
+
+    if verified.as_ref().map(|v| v.revision) != Some(person.revision) {
+        // The quorum is that of the revision currently in effect.
+        let devices = verified
+            .map(|v| v.delegations().len())
+            .unwrap_or_else(|| person.delegations().len());
+
+        term::blank();
+        term::warning(&term::tr!(
+            "The latest revision of your identity has {} of the {} signatures it needs, and is not yet in effect",
+            person.signatures.len(),
+            quorum(devices),
+        ));
+    }
+    Ok(())
+}
+
+fn revoke_device(profile: &profile::Profile, peer: &PeerId) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+    let urn = person::local(&storage)?.urn();
+    let current = person::get(&storage, &urn)?
+        .ok_or_else(|| exit::Error::NotFound(term::tr!("identity {} not found", urn)))?;
+    let keys = current
+        .delegations()
+        .iter()
+        .filter(|k| PeerId::from(**k) != *peer)
+        .copied()
+        .collect::<Vec<_>>();
+
+    if peer == storage.peer_id() {
+        anyhow::bail!("this device can't revoke itself; revoke it from another device");
+    }
+    if keys.len() == current.delegations().len() {
+        anyhow::bail!(term::tr!("{} is not a device of your identity", peer));
+    }
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+
+    // If another device already proposed the revocation, sign its revision, rather than
+    // creating a competing one.
+    let proposer = keys
+        .iter()
+        .map(|k| PeerId::from(*k))
+        .filter(|p| p != storage.peer_id())
+        .find(|p| {
+            proposed_delegations(&monorepo, &urn, p)
+                .map_or(false, |ds| ds.len() == keys.len() && !ds.contains(peer))
+        });
+    let person = match proposer {
+        Some(proposer) => {
+            let person = person::merge(&storage, &urn, proposer)?;
+            term::success!(
+                "Signed the revocation of {} proposed by {}",
+                term::format::tertiary(fmt::peer(peer)),
+                term::format::tertiary(fmt::peer(&proposer))
+            );
+            person
+        }
+        None => {
+            let person = person::set_delegations(&storage, keys)?;
+            term::success!("Device {} revoked", term::format::tertiary(fmt::peer(peer)));
+            person
+        }
+    };
+    person::set_local(&storage, &person)?;
+
+    let verified = person::verify(&storage, &urn)?;
+    if verified.as_ref().map(|v| v.revision) == Some(person.revision) {
+        term::info!("The new revision is in effect.");
+    } else {
+        // The quorum is that of the revision currently in effect.
+        let devices = verified
+            .map(|v| v.delegations().len())
+            .unwrap_or_else(|| current.delegations().len());
+
+        term::info!(
+            "The new revision has {} of the {} signatures it needs to take effect.",
+            person.signatures.len(),
+            quorum(devices)
+        );
+        term::tip!(
+            "Your other devices sign it by fetching your identity and running `rad self revoke-device {}`.",
+            peer
+        );
+    }
+    term::tip!("To publish the revision, run `rad sync --self`.");
+
+    Ok(())
+}
+
+/// Get the keys of the latest revision of an identity, as proposed by another peer.
+fn proposed_delegations(
+    monorepo: &git::Repository,
+    urn: &Urn,
+    peer: &PeerId,
+) -> Option<Vec<PeerId>> {
+    let name = format!(
+        "refs/namespaces/{}/refs/remotes/{}/rad/id",
+        urn.encode_id(),
+        peer.default_encoding()
+    );
+    let commit = monorepo.find_reference(&name).ok()?.peel_to_commit().ok()?;
+    let tree = commit.tree().ok()?;
+    let blob = monorepo.find_blob(tree.get(0)?.id()).ok()?;
+    let doc: serde_json::Value = serde_json::from_slice(blob.content()).ok()?;
+
+    doc["delegations"]
+        .as_array()?
+        .iter()
+        .map(|d| d.as_str().and_then(|s| PeerId::from_str(s).ok()))
+        .collect()
+}
+
+/// Get the last time a device signed its refs, in any namespace of local storage.
+fn last_seen(
+    monorepo: &git::Repository,
+    peer: &PeerId,
+    this: bool,
+) -> anyhow::Result<Option<Timestamp>> {
+    let glob = if this {
+        String::from("refs/namespaces/*/refs/rad/signed_refs")
+    } else {
+        format!(
+            "refs/namespaces/*/refs/remotes/{}/rad/signed_refs",
+            peer.default_encoding()
+        )
+    };
+    let mut seen = None;
+
+    for r in monorepo.references_glob(&glob)? {
+        if let Ok(commit) = r?.peel_to_commit() {
+            seen = seen.max(Some(commit.time().seconds()));
+        }
+    }
+    Ok(seen.map(|secs| Timestamp::new(secs as u64)))
+}
+
+fn json(profile: &profile::Profile) -> anyhow::Result<()> {
+    let storage = profile::read_only(profile)?;
+    let peer_id = storage.peer_id();
+    let mut identity = json!(null);
+
+    if let Some(urn) = storage.config()?.user()? {
+        identity = json!({ "urn": urn.to_string() });
+
+        if let Some(person) = person::get(&storage, &urn)? {
+            let links = person
+                .payload()
+                .get_ext::<person::Links>()?
+                .unwrap_or_default();
+
+            identity["name"] = json!(person.subject().name.to_string());
+            identity["avatar"] = json!(links.avatar);
+            identity["url"] = json!(links.url);
+        }
+    }
+    let signer = match keys::ssh_auth_sock() {
+        Ok(sock) if keys::is_ready(profile, sock).unwrap_or(false) => "ssh-agent",
+        _ => "secret-key",
+    };
+    let paths = profile.paths();
+    let output = json!({
+        "identity": identity,
+        "peerId": peer_id.to_string(),
+        "key": {
+            "fingerprint": keys::to_ssh_fingerprint(peer_id)?,
+            "ssh": keys::to_ssh_key(peer_id)?,
+        },
+        "profile": {
+            "id": profile.id().to_string(),
+            "paths": {
+                "keys": paths.keys_dir().display().to_string(),
+                "git": paths.git_dir().display().to_string(),
+                "seeds": paths.seeds_file().display().to_string(),
+                "cobCache": paths.cob_cache_dir().display().to_string(),
+            },
+        },
+        "monorepo": paths.git_dir().display().to_string(),
+        "signer": signer,
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    term::output::json(&output)
+}
+
 fn all(profile: &profile::Profile) -> anyhow::Result<()> {
     term::info!("Profile {}", term::format::secondary(profile.id()));
 
@@ -120,6 +559,14 @@ fn all(profile: &profile::Profile) -> anyhow::Result<()> {
                 String::from("Name"),
                 term::format::tertiary(&person.subject().name),
             ]);
+            if let Some(links) = person.payload().get_ext::<person::Links>()? {
+                if let Some(avatar) = links.avatar {
+                    table.push([String::from("Avatar"), term::format::tertiary(avatar)]);
+                }
+                if let Some(url) = links.url {
+                    table.push([String::from("URL"), term::format::tertiary(url)]);
+                }
+            }
         }
         table.push([String::from("URN"), term::format::tertiary(&urn)]);
     }
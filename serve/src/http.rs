@@ -0,0 +1,164 @@
+//! Minimal HTTP/1.1 server side, for serving JSON to local clients.
+//!
+//! Every connection serves a single request, and is then closed.
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+use radicle_common::json;
+
+/// Maximum length of the request line and of each header, in bytes.
+const MAX_LINE: usize = 8 * 1024;
+/// Maximum number of request headers.
+const MAX_HEADERS: usize = 64;
+
+/// A request.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// Percent-decoded path, without the query.
+    pub path: String,
+    /// Value of the `Host` header, if any.
+    pub host: Option<String>,
+}
+
+impl Request {
+    /// Path segments, ignoring empty segments, eg. `["v1", "projects"]` for `/v1/projects/`.
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|s| !s.is_empty()).collect()
+    }
+}
+
+/// A response, with a JSON body.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: json::Value,
+}
+
+impl Response {
+    pub fn ok(body: json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    pub fn error(status: u16, message: impl ToString) -> Self {
+        Self {
+            status,
+            body: json::json!({ "error": message.to_string() }),
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Self::error(404, "not found")
+    }
+}
+
+/// Read a request from a connection. The request body, if any, is ignored.
+pub fn read(stream: &TcpStream) -> io::Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let line = read_line(&mut reader)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method.to_owned(), target.to_owned())
+        }
+        _ => return Err(invalid("malformed request line")),
+    };
+    let mut host = None;
+
+    for _ in 0..MAX_HEADERS {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            let path = target.split('?').next().unwrap_or_default();
+
+            return Ok(Request {
+                method,
+                path: decode(path).ok_or_else(|| invalid("malformed path"))?,
+                host,
+            });
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_owned());
+            }
+        }
+    }
+    Err(invalid("too many headers"))
+}
+
+/// Write a response to a connection.
+pub fn write(
+    mut stream: &TcpStream,
+    response: &Response,
+    allow_origin: Option<&str>,
+) -> io::Result<()> {
+    let body = json::to_string_pretty(&response.body)? + "\n";
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        body.len()
+    );
+    if let Some(origin) = allow_origin {
+        head.push_str(&format!("Access-Control-Allow-Origin: {}\r\n", origin));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+/// Read a line, without its line ending.
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = Vec::new();
+    let n = reader
+        .take(MAX_LINE as u64 + 2)
+        .read_until(b'\n', &mut line)?;
+
+    if n == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed",
+        ));
+    }
+    if !line.ends_with(b"\n") {
+        return Err(invalid("line too long"));
+    }
+    let line = String::from_utf8(line).map_err(|_| invalid("request is not UTF-8"))?;
+
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_owned())
+}
+
+/// Decode a percent-encoded path.
+fn decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = path.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
@@ -0,0 +1,372 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::net::{SocketAddr, TcpListener};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use librad::git::storage::Storage;
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::{self, issue, patch, Identifier};
+use radicle_common::{git, json, keys, project};
+use radicle_terminal as term;
+
+mod http;
+use http::{Request, Response};
+
+pub const HELP: Help = Help {
+    name: "serve",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad serve [--listen <addr>] [--allow-origin <origin>]
+
+    Serves a read-only HTTP API of the projects in local storage, so that editors,
+    dashboards and other local tools can integrate with radicle without linking to
+    its libraries. Responses are JSON. The API is only served on the loopback
+    interface, and requests from other hosts are refused.
+
+    Stop the server with Ctrl-C.
+
+Endpoints
+
+    GET /v1                                   Version and peer id
+    GET /v1/projects                          Projects in local storage
+    GET /v1/projects/<urn>                    A project
+    GET /v1/projects/<urn>/refs               Branches and tags of the project's peers
+    GET /v1/projects/<urn>/patches            Patches of the project
+    GET /v1/projects/<urn>/patches/<id>       A patch, by id or id prefix
+    GET /v1/projects/<urn>/issues             Issues of the project
+    GET /v1/projects/<urn>/issues/<id>        An issue, by id or id prefix
+
+Options
+
+    --listen <addr>           Address to listen on (default: 127.0.0.1:8777)
+    --allow-origin <origin>   Allow web pages of the given origin to use the API,
+                              eg. `http://localhost:3000` (default: none)
+    --help                    Print help
+"#,
+};
+
+/// Default address to listen on.
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8777";
+/// Timeout of reading a request from, or writing a response to a client.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub struct Options {
+    pub listen: SocketAddr,
+    pub allow_origin: Option<String>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut listen: Option<SocketAddr> = None;
+        let mut allow_origin: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("listen") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    listen = Some(
+                        SocketAddr::from_str(&val)
+                            .map_err(|_| anyhow!(term::tr!("invalid address '{}'", val)))?,
+                    );
+                }
+                Long("allow-origin") => {
+                    allow_origin = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let listen = match listen {
+            Some(addr) => addr,
+            None => SocketAddr::from_str(DEFAULT_LISTEN)?,
+        };
+        if !listen.ip().is_loopback() {
+            return Err(Error::WithHint {
+                err: anyhow!(term::tr!(
+                    "refusing to listen on non-loopback address {}",
+                    listen
+                )),
+                hint: "hint: the API is for local tools only; listen on eg. 127.0.0.1",
+            }
+            .into());
+        }
+
+        Ok((
+            Options {
+                listen,
+                allow_origin,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let cobs = cobs::store(&profile, &storage)?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let listener = TcpListener::bind(options.listen)
+        .map_err(|err| anyhow!(term::tr!("could not listen on {}: {}", options.listen, err)))?;
+    let api = Api {
+        storage: &storage,
+        cobs,
+        monorepo,
+    };
+
+    term::success!(
+        "Serving the API on {}",
+        term::format::highlight(format!("http://{}/v1", options.listen))
+    );
+    term::info!("{}", term::format::dim("Press Ctrl-C to stop"));
+    term::blank();
+
+    // Nb. Clients are served one at a time: local tools make few requests.
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        stream.set_read_timeout(Some(TIMEOUT)).ok();
+        stream.set_write_timeout(Some(TIMEOUT)).ok();
+
+        let response = match http::read(&stream) {
+            Ok(req) => {
+                let response = api.handle(&req);
+
+                term::info!(
+                    "{} {} {}",
+                    term::format::dim(response.status),
+                    req.method,
+                    req.path
+                );
+                response
+            }
+            Err(err) => Response::error(400, err),
+        };
+        if let Err(err) = http::write(&stream, &response, options.allow_origin.as_deref()) {
+            term::warning(&term::tr!("Failed to respond to client: {}", err));
+        }
+    }
+    Ok(())
+}
+
+/// The API, over local storage.
+struct Api<'a> {
+    storage: &'a Storage,
+    cobs: cobs::Store<'a>,
+    monorepo: git::Repository,
+}
+
+impl<'a> Api<'a> {
+    fn handle(&self, req: &Request) -> Response {
+        // Requests for other hosts are refused, so that web pages can't reach the API
+        // through a domain name that resolves to the loopback address.
+        let host = req
+            .host
+            .as_deref()
+            .map(|h| h.rsplit_once(':').map_or(h, |(host, _)| host))
+            .unwrap_or_default();
+        if !matches!(host, "localhost" | "127.0.0.1" | "[::1]") {
+            return Response::error(403, "requests must be made to localhost");
+        }
+        if req.method != "GET" {
+            return Response::error(405, "only GET requests are supported");
+        }
+
+        match self.route(&req.segments()) {
+            Ok(Some(body)) => Response::ok(body),
+            Ok(None) => Response::not_found(),
+            Err(err) => Response::error(500, err),
+        }
+    }
+
+    /// Get the body of the response to a request for the given path. Returns `None` if
+    /// nothing is found.
+    fn route(&self, segments: &[&str]) -> anyhow::Result<Option<json::Value>> {
+        let (urn, rest) = match segments {
+            ["v1"] => {
+                return Ok(Some(json::json!({
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "peer": self.storage.peer_id().default_encoding(),
+                })))
+            }
+            ["v1", "projects"] => return self.projects().map(Some),
+            ["v1", "projects", urn, rest @ ..] => match Urn::from_str(urn) {
+                Ok(urn) => (urn, rest),
+                Err(_) => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let proj = match project::get(self.storage, &urn)? {
+            Some(proj) => proj,
+            None => return Ok(None),
+        };
+
+        match rest {
+            [] => Ok(Some(
+                self.project(&proj, project::head(self.storage, &proj)),
+            )),
+            ["refs"] => self.refs(&urn).map(Some),
+            ["patches"] => {
+                let patches = self
+                    .cobs
+                    .patches()
+                    .all(&urn)?
+                    .into_iter()
+                    .map(|(id, p)| self.patch(id, p))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                Ok(Some(json::Value::Array(patches)))
+            }
+            ["patches", id] => {
+                let id = Identifier::from_str(id)?;
+
+                match self.cobs.resolve::<patch::Patch>(&urn, &id)? {
+                    Some((id, p)) => self.patch(id, p).map(Some),
+                    None => Ok(None),
+                }
+            }
+            ["issues"] => {
+                let issues = self
+                    .cobs
+                    .issues()
+                    .all(&urn)?
+                    .into_iter()
+                    .map(|(id, i)| self.issue(id, i))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                Ok(Some(json::Value::Array(issues)))
+            }
+            ["issues", id] => {
+                let id = Identifier::from_str(id)?;
+
+                match self.cobs.resolve::<issue::Issue>(&urn, &id)? {
+                    Some((id, i)) => self.issue(id, i).map(Some),
+                    None => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn projects(&self) -> anyhow::Result<json::Value> {
+        let mut projects = project::list(self.storage)?;
+        projects.sort_by(|(_, a, _), (_, b, _)| a.name.cmp(&b.name));
+
+        Ok(json::Value::Array(
+            projects
+                .iter()
+                .map(|(_, meta, _)| self.project(meta, project_head(self.storage, meta)))
+                .collect(),
+        ))
+    }
+
+    fn project(&self, meta: &project::Metadata, head: Option<git::Oid>) -> json::Value {
+        let mut delegates = meta
+            .remotes
+            .iter()
+            .map(|p| p.default_encoding())
+            .collect::<Vec<_>>();
+        delegates.sort();
+
+        json::json!({
+            "urn": meta.urn.to_string(),
+            "name": meta.name,
+            "description": meta.description,
+            "defaultBranch": meta.default_branch.to_string(),
+            "head": head.map(|h| h.to_string()),
+            "delegates": delegates,
+        })
+    }
+
+    /// Get the branches and tags of each peer of a project, ours included.
+    fn refs(&self, urn: &Urn) -> anyhow::Result<json::Value> {
+        let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+        let local = self.storage.peer_id().default_encoding();
+        let mut peers: BTreeMap<String, BTreeMap<&str, BTreeMap<String, String>>> = BTreeMap::new();
+
+        for r in self
+            .monorepo
+            .references_glob(&format!("{}*", namespace))?
+            .flatten()
+        {
+            let (name, oid) = match (r.name(), r.target()) {
+                (Some(name), Some(oid)) => (name, oid),
+                _ => continue,
+            };
+            let name = name.trim_start_matches(&namespace);
+            let (peer, name) = match name.strip_prefix("remotes/") {
+                Some(rest) => match rest.split_once('/') {
+                    Some((peer, name)) => (peer.to_owned(), name),
+                    None => continue,
+                },
+                None => (local.clone(), name),
+            };
+            let (category, name) = if let Some(name) = name.strip_prefix("heads/") {
+                ("heads", name)
+            } else if let Some(name) = name.strip_prefix("tags/") {
+                ("tags", name)
+            } else {
+                continue;
+            };
+
+            peers
+                .entry(peer)
+                .or_default()
+                .entry(category)
+                .or_default()
+                .insert(name.to_owned(), oid.to_string());
+        }
+
+        Ok(json::Value::Array(
+            peers
+                .into_iter()
+                .map(|(peer, mut refs)| {
+                    json::json!({
+                        "peer": peer,
+                        "self": peer == local,
+                        "heads": refs.remove("heads").unwrap_or_default(),
+                        "tags": refs.remove("tags").unwrap_or_default(),
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    fn patch(&self, id: patch::PatchId, mut patch: patch::Patch) -> anyhow::Result<json::Value> {
+        patch.resolve(self.storage).ok();
+
+        let mut val = json::to_value(&patch)?;
+        val["id"] = json::Value::String(id.to_string());
+
+        Ok(val)
+    }
+
+    fn issue(&self, id: issue::IssueId, mut issue: issue::Issue) -> anyhow::Result<json::Value> {
+        issue.resolve(self.storage).ok();
+
+        let mut val = json::to_value(&issue)?;
+        val["id"] = json::Value::String(id.to_string());
+
+        Ok(val)
+    }
+}
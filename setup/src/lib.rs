@@ -0,0 +1,271 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::config::Config;
+use radicle_common::profile::Profile;
+use radicle_common::Interactive;
+use radicle_common::{git, plugin, profile, sync, Url};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "setup",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad setup [--skip-project]
+
+    Walks you through setting up radicle, step by step:
+
+    1. profile       create your profile and identity, as with `rad auth`
+    2. seed          pick the default seed, or add one, as with `rad seed`
+    3. project       publish the repository of the working directory as a project,
+                     as with `rad init`, or clone a project, as with `rad clone`
+    4. integration   sync the project after every `git push rad`, as with
+                     `rad sync --install-hook`, and check that `git-remote-rad` is
+                     installed, for `git clone rad://...`
+
+    Steps that are already done are skipped, so `rad setup` can be run again, eg. to
+    set up another project. It asks for input, and can't be used in scripts; use the
+    commands of each step instead.
+
+Options
+
+    --skip-project   Skip the project and integration steps
+    --help           Print help
+"#,
+};
+
+#[derive(Default, Debug)]
+pub struct Options {
+    pub skip_project: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut skip_project = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("skip-project") => {
+                    skip_project = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options { skip_project }, vec![]))
+    }
+}
+
+/// Number of steps of the setup.
+const STEPS: usize = 4;
+
+pub fn run(options: Options, _ctx: impl term::Context) -> anyhow::Result<()> {
+    if !term::is_interactive() {
+        return Err(Error::WithHint {
+            err: anyhow!("`rad setup` must be run from a terminal"),
+            hint: "hint: use `rad auth`, `rad seed` and `rad init` to set up radicle from a script",
+        }
+        .into());
+    }
+
+    step(1, "Profile");
+    let profile = self::profile()?;
+
+    step(2, "Seed");
+    self::seed(&profile)?;
+
+    if options.skip_project {
+        return done();
+    }
+
+    step(3, "Project");
+    let path = self::project(&profile)?;
+
+    step(4, "Integration");
+    self::integration(path.as_deref())?;
+
+    done()
+}
+
+fn step(n: usize, name: &str) {
+    term::headline(&term::tr!(
+        "{} {}",
+        term::format::dim(format!("[{}/{}]", n, STEPS)),
+        name
+    ));
+}
+
+fn done() -> anyhow::Result<()> {
+    term::blank();
+    term::success!("Radicle is set up 🌱");
+    term::tip!(
+        "Run {} to check your setup at any time.",
+        term::format::secondary("`rad doctor`")
+    );
+
+    Ok(())
+}
+
+/// Use the active profile, or create one if there is none.
+fn profile() -> anyhow::Result<Profile> {
+    if let Ok(profile) = profile::default() {
+        term::success!(
+            "Using profile {}",
+            term::format::highlight(profile.id().to_string())
+        );
+        return Ok(profile);
+    }
+
+    rad_auth::init(rad_auth::Options {
+        init: true,
+        active: false,
+        stdin: false,
+        name: None,
+        peer_id: None,
+        list: false,
+        switch: None,
+        change_passphrase: false,
+        hardware: None,
+        import: None,
+        rotate_key: false,
+        agent_ttl: None,
+        agent_confirm: false,
+    })?;
+
+    Ok(profile::default()?)
+}
+
+/// Pick the default seed among the configured seeds, or add one.
+fn seed(profile: &Profile) -> anyhow::Result<()> {
+    const OTHER: &str = "Another seed...";
+
+    let config = Config::load(profile)?;
+    let mut choices = config
+        .seeds()
+        .map(|s| s.host().unwrap_or_default().to_owned())
+        .collect::<Vec<_>>();
+    let default = choices.first().cloned().unwrap_or_else(|| OTHER.to_owned());
+
+    choices.push(OTHER.to_owned());
+
+    let choice = term::select_with_prompt("Default seed", &choices, &default)
+        .ok_or_else(|| anyhow!("no seed was picked"))?;
+    let op = if choice == OTHER {
+        let url: Url = term::text_input("Seed URL, eg. https://seed.example.com", None)?;
+
+        rad_seed::Operation::Add {
+            url,
+            name: None,
+            default: true,
+        }
+    } else if *choice == default {
+        term::success!(
+            "Seed {} is the default seed",
+            term::format::highlight(choice)
+        );
+        return Ok(());
+    } else {
+        rad_seed::Operation::SetDefault {
+            seed: choice.clone(),
+        }
+    };
+
+    rad_seed::run(rad_seed::Options { op }, profile.clone())
+}
+
+/// Publish the repository of the working directory, or clone a project. Returns the
+/// path of the project's working copy, if any.
+fn project(profile: &Profile) -> anyhow::Result<Option<PathBuf>> {
+    let cwd = env::current_dir()?;
+
+    if let Ok(repo) = git::repository() {
+        if git::rad_remote(&repo).is_ok() {
+            term::success!(
+                "The repository in {} is already a project",
+                term::format::highlight(cwd.display())
+            );
+            return Ok(Some(cwd));
+        }
+        if term::ask(
+            term::tr!(
+                "Publish the repository in {} as a project?",
+                term::format::highlight(cwd.display())
+            ),
+            true,
+        )? {
+            rad_init::init(
+                rad_init::Options {
+                    interactive: Interactive::Yes,
+                    sync: sync::is_auto(),
+                    ..Default::default()
+                },
+                profile,
+            )?;
+            return Ok(Some(cwd));
+        }
+    }
+
+    if !term::ask("Clone a project?", true)? {
+        term::tip!(
+            "To publish a repository later, run {} from it.",
+            term::format::secondary("`rad init`")
+        );
+        return Ok(None);
+    }
+    let origin: String = term::text_input("Project URN or URL", None)?;
+    let (options, _) = rad_clone::Options::from_args(vec![origin.into()])?;
+    let path = rad_clone::execute(options, profile.clone())?;
+
+    Ok(Some(path))
+}
+
+/// Install the sync hook in the project's working copy, and check that the git remote
+/// helper is installed.
+fn integration(path: Option<&Path>) -> anyhow::Result<()> {
+    if let Some(path) = path {
+        let repo = git::Repository::open(path)?;
+
+        if git::rad_remote(&repo).is_ok()
+            && term::ask("Sync the project after every `git push rad`?", true)?
+        {
+            match git::install_hook(
+                &repo,
+                "pre-push",
+                rad_sync::PRE_PUSH_HOOK,
+                rad_sync::HOOK_MARKER,
+            ) {
+                Ok(hook) => term::success!(
+                    "Installed sync hook at {}",
+                    term::format::highlight(hook.display())
+                ),
+                Err(err) => term::warning(&term::tr!("Could not install the sync hook: {}", err)),
+            }
+        }
+    }
+
+    match plugin::which("git-remote-rad") {
+        Some(helper) => term::success!(
+            "Found the git remote helper at {}",
+            term::format::highlight(helper.display())
+        ),
+        None => term::warning(
+            "The git remote helper `git-remote-rad` isn't in PATH: git can't use `rad://` URLs. \
+            It is installed with `rad`.",
+        ),
+    }
+
+    Ok(())
+}
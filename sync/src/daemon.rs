@@ -0,0 +1,162 @@
+//! Background sync of local changes.
+//!
+//! The monorepo is polled for updates to the local refs of every project. Once a
+//! project's refs stop changing for [`DEBOUNCE`], the project is pushed to the seeds.
+use std::collections::HashMap;
+use std::thread;
+use std::time;
+
+use librad::crypto::BoxedSigner;
+use librad::git::Storage;
+use librad::git::Urn;
+use librad::profile::Profile;
+
+use radicle_common::nonempty::NonEmpty;
+use radicle_common::{git, project, sync, tokio};
+use radicle_terminal as term;
+
+use crate::Options;
+
+/// Interval at which the monorepo is polled for ref updates.
+pub const POLL_INTERVAL: time::Duration = time::Duration::from_secs(1);
+/// Time to wait after the last ref update of a project, before syncing it.
+pub const DEBOUNCE: time::Duration = time::Duration::from_secs(5);
+
+/// Local refs of each project, keyed by namespace.
+type Snapshot = HashMap<String, Vec<(String, git::Oid)>>;
+
+/// Watch the monorepo for local ref updates, and push them to the seeds. Never returns,
+/// unless the monorepo can't be read.
+pub fn run(
+    profile: &Profile,
+    signer: BoxedSigner,
+    seeds: Option<NonEmpty<sync::Seed<String>>>,
+    storage: Storage,
+    options: Options,
+    rt: tokio::runtime::Runtime,
+) -> anyhow::Result<()> {
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let settings = crate::settings(profile, &options);
+    let mut previous = snapshot(&monorepo)?;
+    let mut pending: HashMap<String, time::Instant> = HashMap::new();
+
+    term::headline(&term::tr!(
+        "Watching 🌱 local projects for changes, syncing with {}",
+        match &seeds {
+            Some(seeds) => format!("{} seed(s)", term::format::dim(seeds.len())),
+            None => String::from("their seeds"),
+        }
+    ));
+    term::tip!("Press Ctrl-C to stop.");
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let current = snapshot(&monorepo)?;
+        for (namespace, refs) in &current {
+            if previous.get(namespace) != Some(refs) {
+                // Restart the debounce period on every update.
+                pending.insert(namespace.clone(), time::Instant::now());
+            }
+        }
+        previous = current;
+
+        let ready = pending
+            .iter()
+            .filter(|(_, since)| since.elapsed() >= DEBOUNCE)
+            .map(|(namespace, _)| namespace.clone())
+            .collect::<Vec<_>>();
+        if ready.is_empty() {
+            continue;
+        }
+        let projects = project::list(&storage)?
+            .into_iter()
+            .map(|(urn, meta, _)| (urn.encode_id(), (urn, meta.name)))
+            .collect::<HashMap<_, _>>();
+
+        for namespace in ready {
+            pending.remove(&namespace);
+
+            if let Some((urn, name)) = projects.get(&namespace) {
+                push(urn, name, profile, &signer, seeds.as_ref(), settings, &rt);
+            }
+        }
+    }
+}
+
+/// Push a project to the given seeds, or else to its own, reporting errors without failing.
+fn push(
+    urn: &Urn,
+    name: &str,
+    profile: &Profile,
+    signer: &BoxedSigner,
+    seeds: Option<&NonEmpty<sync::Seed<String>>>,
+    settings: sync::Settings,
+    rt: &tokio::runtime::Runtime,
+) {
+    term::blank();
+    term::info!(
+        "{} changed, syncing {}",
+        term::format::highlight(name),
+        term::format::dim(urn)
+    );
+
+    let seeds = match seeds {
+        Some(seeds) => Ok(seeds.clone()),
+        None => term::sync::seeds(profile, urn),
+    };
+    let result = seeds.and_then(|seeds| {
+        term::sync::sync_with(
+            urn.clone(),
+            seeds,
+            sync::Mode::Push,
+            settings,
+            profile,
+            signer.clone(),
+            rt,
+        )
+    });
+    match result {
+        Ok(results) => {
+            let synced = results.iter().filter(|r| r.is_success()).count();
+
+            if synced == results.len() {
+                term::success!("Synced {} with {} seed(s)", name, synced);
+            } else {
+                term::sync::report(&results);
+            }
+        }
+        Err(err) => term::error(term::tr!("Failed to sync {}: {}", name, err)),
+    }
+}
+
+/// Get the local refs of every project in the monorepo. Remote refs are ignored.
+fn snapshot(monorepo: &git::Repository) -> anyhow::Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+
+    for r in monorepo.references_glob("refs/namespaces/*")? {
+        let r = r?;
+        let (name, oid) = match (r.name(), r.target()) {
+            (Some(name), Some(oid)) => (name, oid),
+            _ => continue,
+        };
+        let (namespace, name) = match name
+            .strip_prefix("refs/namespaces/")
+            .and_then(|n| n.split_once('/'))
+        {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if name.starts_with("refs/remotes/") {
+            continue;
+        }
+        snapshot
+            .entry(namespace.to_owned())
+            .or_insert_with(Vec::new)
+            .push((name.to_owned(), oid));
+    }
+    for refs in snapshot.values_mut() {
+        refs.sort();
+    }
+    Ok(snapshot)
+}
@@ -1,24 +1,74 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::ffi::OsString;
 use std::iter;
+use std::path::Path;
 use std::str::FromStr;
+use std::time;
 
+use librad::crypto::BoxedSigner;
 use librad::git::Storage;
 use librad::git::Urn;
 use librad::profile::Profile;
+use librad::PeerId;
 
+use radicle_common::alias::Aliases;
 use radicle_common::args;
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::block::Blocklist;
+use radicle_common::credentials::Credentials;
+use radicle_common::hooks::{Event, Hooks};
 use radicle_common::nonempty::NonEmpty;
+use radicle_common::project::TrackingPolicy;
+use radicle_common::sync::scope::{Scope, Scopes};
 use radicle_common::sync::Mode;
-use radicle_common::{identity, keys, person, project, sync, tokio};
+use radicle_common::{
+    exit, git, identity, json, keys, offline, person, project, search, seed, sync, tokio,
+};
 use radicle_terminal as term;
 
 use anyhow::anyhow;
 use url::Url;
 
-pub const GATEWAY_HOST: &str = "app.radicle.xyz";
+mod daemon;
+
+pub use sync::GATEWAY_HOST;
+/// Marker identifying the hook installed by `--install-hook`.
+pub const HOOK_MARKER: &str = "# Installed by `rad sync --install-hook`.";
+/// Hook installed by `--install-hook`. Git has no `post-push` hook, so the `pre-push`
+/// hook waits in the background for the push to complete before syncing. The pushed
+/// branches are read from the hook's input, which is not passed on to `rad`.
+pub const PRE_PUSH_HOOK: &str = r#"#!/bin/sh
+# Installed by `rad sync --install-hook`.
+#
+# Pushes the branches pushed with `git push rad` to the project's seeds, once the
+# push completes. Output is logged to `rad-sync.log` in the git directory.
+
+[ "$1" = "rad" ] || exit 0
+
+# Git writes one line per pushed ref: <local ref> <local sha> <remote ref> <remote sha>.
+# Deleted branches have an all-zero local sha, and are skipped.
+branches=""
+while read -r _ sha ref _; do
+    case "$ref" in
+        refs/heads/*)
+            case "$sha" in
+                *[!0]*) branches="$branches --branch ${ref#refs/heads/}" ;;
+            esac
+            ;;
+    esac
+done
+[ -n "$branches" ] || exit 0
+
+log="$(git rev-parse --absolute-git-dir)/rad-sync.log"
+(
+    while kill -0 "$PPID" 2>/dev/null; do sleep 1; done
+    rad --no-confirm sync $branches >>"$log" 2>&1
+) </dev/null >/dev/null 2>&1 &
+
+exit 0
+"#;
 pub const HELP: Help = Help {
     name: "sync",
     description: env!("CARGO_PKG_DESCRIPTION"),
@@ -26,18 +76,134 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad sync [<urn> | <url>] [--seed <address>]... [<options>...]
+    rad sync [<urn> | <url>] [--seed <address>]... [--save] [<options>...]
+    rad sync [<urn> | <url>] [--seed <address>]... --transport http [<options>...]
+    rad sync [<urn> | <url>] [--seed <address>]... (--cobs | --cob <type>/<id> | --peer <peer-id>)
+    rad sync [<urn> | <url>] [--seed <address>]... --fetch-peers
+    rad sync [<urn> | <url>] [--seed <address>]... --branch <name>...
+    rad sync [<urn> | <url>] [--seed <address>]... --dry-run
+    rad sync [<urn> | <url>] [--seed <address>]... --status
     rad sync --self [--seed <address>]...
+    rad sync --all [--seed <address>]...
+    rad sync --install-hook
+    rad sync --daemon [--seed <address>]...
+
+    All forms except `--install-hook` accept `--timeout <secs>`, and the forms over
+    `--transport http` accept `--rate-limit <KiB/s>`.
 
     If a <urn> is specified, seeds may be given via the `--seed` option.
     If a <url> is specified, the seed is implied.
     If neither is specified, the URN and seed of the current project is used.
     If the project has no configured seed, the active profile's default seed list is used.
+    A project's seeds are configured under the `rad.seed` key of its git config, and can
+    be set with `--save`. Without `--seed`, the seed set with `sync.seed` in the
+    configuration is used if any, eg. for a team's project, see `rad config`.
+
+    The `--cobs`, `--cob` and `--peer` options fetch only the selected refs of the
+    project from the seeds' git endpoints, instead of syncing the whole project.
+    Nothing is pushed in that case. Fetched refs are only applied if they are
+    included in their peer's verified signed refs.
+    These fetches are resumable: if interrupted, running the same command again
+    continues from the refs that were already fetched. So are fetches over
+    `--transport http`, see below.
+
+    Nb. Fetches over the peer-to-peer protocol, ie. a plain `rad sync`, are not
+    resumable: the protocol doesn't keep partially received data, so an interrupted
+    fetch starts from scratch when run again. For large projects on unreliable
+    networks, use `--transport http` instead.
+
+    With `--transport http`, the project is fetched from the seeds' smart HTTP git
+    endpoints instead of over the peer-to-peer protocol, for networks where the seeds'
+    peer port is blocked. Fetched refs are verified against their peer's signed refs.
+    Your refs are pushed to the same endpoints, along with your signed refs. Seeds that
+    require authentication, eg. for pushes, are sent the token stored for them with
+    `rad seed login`, when they ask for credentials. Over the peer-to-peer protocol,
+    you are authenticated by your peer key instead.
+
+    Over HTTP, `--filter <filter>` leaves out the objects matched by the filter, eg.
+    `blob:none` for all blobs, `blob:limit=<bytes>` for large blobs, or `tree:0` for all
+    trees and blobs. Omitted objects are fetched from the seed on demand. Identities,
+    signed refs and collaborative objects are always fetched in full. History is always
+    fetched in full, as local storage is shared by all projects.
+
+    When run from a project's working copy, the project's tracking policy, as set with
+    `rad clone --track` or `rad track --policy`, decides whose refs are fetched:
+    the delegates and explicitly tracked peers (`delegates-only`, the default), all
+    peers (`all`), or only explicitly tracked peers (`manual`).
+
+    With `--fetch-peers`, the refs of every tracked peer of the project are fetched
+    in one go. When run from a working copy, the git remotes of these peers, eg. as
+    set up by `rad track`, are then fetched as well, so that their branches can be
+    reviewed locally.
+
+    With `--branch`, only the given branches of yours are pushed to the seeds, and
+    nothing is fetched. Over HTTP, only these branches are pushed, along with your
+    signed refs. Over the peer-to-peer protocol, the seeds replicate all your refs,
+    so the branches are published along with them.
+
+    With `--dry-run`, the refs that would be fetched and pushed are computed from
+    the refs advertised by the seeds' git endpoints, and printed without transferring
+    anything. It can be combined with the above options.
+
+    With `--status`, the refs advertised by each seed are compared with local state,
+    without transferring anything. Each ref is reported as in sync, ahead (the local
+    ref is ahead of the seed's), behind (the seed has changes that are not local yet),
+    diverged, or missing (the seed doesn't have the ref). It can be combined with
+    the `--cobs`, `--cob`, `--peer` and `--fetch-peers` options.
+
+    With `--prune`, remote refs, eg. branches and collaborative objects of other peers,
+    that are no longer advertised by any of the seeds are removed after syncing. Only
+    the refs of peers known to the seeds are pruned.
+
+    With `--install-hook`, a `pre-push` git hook is installed in the working copy, which
+    runs `rad --no-confirm sync --branch <name>` in the background for the pushed
+    branches, every time `git push rad` completes.
+
+    With `--daemon`, the command keeps running in the foreground, watching local
+    storage for changes to your refs, eg. after a `git push rad` or a new patch.
+    Once a project has stopped changing for five seconds, it is pushed to the seeds.
+
+    With `--all`, every project in local storage is synced with the given seeds, or
+    the profile's seeds.
+
+    When run from a project's working copy that has submodules which are radicle
+    projects, ie. whose URL is a project URN, eg. `rad:git:<id>`, you are offered to
+    sync these projects as well, once the project is synced. With `--recurse-submodules`,
+    they are synced without asking.
+
+    When syncing with multiple seeds, up to four seeds are synced with at a time.
+    Failed attempts are retried with an exponential backoff, as configured under
+    the `[sync]` section of the configuration, eg. `retries = 2` and `backoff = 500`
+    (in milliseconds).
+
+    Every operation with a seed times out after `--timeout` seconds without progress
+    (default: 9). Fetches from and pushes to the seeds' git endpoints are throttled to
+    `--rate-limit` KiB/s. Transfers over the peer-to-peer protocol can't be throttled,
+    so `--rate-limit` requires `--transport http`. Both can be configured under the
+    `[sync]` section of the configuration, as `timeout` and `rate_limit`.
 
 Options
 
     --seed <address>    Sync to the given seed (may be specified multiple times)
+    --save              Save the given seeds as the project's default seeds
     --self              Sync your local identity only
+    --all               Sync all projects in local storage
+    --install-hook      Install a git hook that syncs after every `git push rad`
+    --daemon            Keep running, and push local changes to the seeds as they happen
+    --cobs              Only fetch collaborative objects, eg. patches and issues
+    --cob <type>/<id>   Only fetch the given collaborative object, eg. `patch/<id>`
+    --peer <peer-id>    Only fetch the refs of the given peer
+    --fetch-peers       Only fetch the refs of all tracked peers
+    --branch <name>     Only push the given branch (may be specified multiple times)
+    --recurse-submodules
+                        Sync the submodules of the working copy that are radicle projects
+    --dry-run           Print the refs that would be synced, without syncing
+    --status            Compare the project's refs on the seeds with local state
+    --prune             Remove remote refs that no longer exist on the seeds
+    --transport <name>  Transport to sync over: `link` (default) or `http`
+    --timeout <secs>    Timeout of each operation with a seed, in seconds
+    --rate-limit <n>    Limit git transfers to <n> KiB/s
+    --filter <filter>   Only fetch the objects not matched by <filter>, over HTTP
     --help              Print help
 
 Seed addresses
@@ -50,13 +216,63 @@ Seed addresses
 "#,
 };
 
+/// Operation performed by `rad sync`. Only one operation can be given at a time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Sync a project with its seeds.
+    Sync,
+    /// Print the refs that would be synced, without transferring anything.
+    DryRun,
+    /// Compare the refs of the seeds with local state.
+    Status,
+    /// Sync the local identity.
+    Identity,
+    /// Sync every project in local storage.
+    All,
+    /// Install a git hook that syncs after every push.
+    InstallHook,
+    /// Keep running, and push local changes as they happen.
+    Daemon,
+}
+
+impl Default for Operation {
+    fn default() -> Self {
+        Self::Sync
+    }
+}
+
+impl Operation {
+    /// Option selecting the operation.
+    pub fn option(&self) -> &'static str {
+        match self {
+            Self::Sync => "<urn>",
+            Self::DryRun => "--dry-run",
+            Self::Status => "--status",
+            Self::Identity => "--self",
+            Self::All => "--all",
+            Self::InstallHook => "--install-hook",
+            Self::Daemon => "--daemon",
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Options {
     pub origin: Option<identity::Origin>,
     pub seeds: Vec<sync::Seed<String>>,
     pub mode: Mode,
+    pub operation: Operation,
     pub verbose: bool,
-    pub sync_self: bool,
+    pub save: bool,
+    pub refs: sync::Refs,
+    pub prune: bool,
+    pub fetch_peers: bool,
+    pub branches: Vec<String>,
+    pub transport: sync::Transport,
+    pub timeout: Option<u64>,
+    pub rate_limit: Option<u64>,
+    pub filter: Option<sync::Filter>,
+    pub recurse_submodules: bool,
 }
 
 impl Args for Options {
@@ -66,11 +282,43 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut verbose = false;
         let mut origin = None;
-        let mut sync_self = false;
+        let mut operation = Operation::default();
+        let mut save = false;
+        let mut refs = sync::Refs::All;
+        let mut prune = false;
+        let mut fetch_peers = false;
+        let mut branches = Vec::new();
+        let mut recurse_submodules = false;
+        let mut transport = sync::Transport::default();
+        let mut timeout = None;
+        let mut rate_limit = None;
+        let mut filter = None;
         let mut unparsed = Vec::new();
         let mut seeds = Vec::new();
 
         while let Some(arg) = parser.next()? {
+            let op = match arg {
+                Long("self") => Some(Operation::Identity),
+                Long("install-hook") => Some(Operation::InstallHook),
+                Long("daemon") => Some(Operation::Daemon),
+                Long("all") => Some(Operation::All),
+                Long("dry-run") => Some(Operation::DryRun),
+                Long("status") => Some(Operation::Status),
+                _ => None,
+            };
+            if let Some(op) = op {
+                if operation != Operation::Sync && operation != op {
+                    anyhow::bail!(term::tr!(
+                        "`{}` cannot be combined with `{}`",
+                        op.option(),
+                        operation.option()
+                    ));
+                }
+                operation = op;
+
+                continue;
+            }
+
             match arg {
                 Long("verbose") | Short('v') => {
                     verbose = true;
@@ -78,15 +326,71 @@ impl Args for Options {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
-                Long("self") => {
-                    sync_self = true;
+                Long("prune") => {
+                    prune = true;
+                }
+                Long("save") => {
+                    save = true;
+                }
+                Long("transport") => {
+                    let value = parser.value()?;
+
+                    transport = args::parse_value("transport", value)?;
+                }
+                Long("timeout") => {
+                    let value = parser.value()?;
+                    let secs: u64 = args::parse_value("timeout", value)?;
+
+                    if secs == 0 {
+                        anyhow::bail!("`--timeout` must be at least one second");
+                    }
+                    timeout = Some(secs);
+                }
+                Long("rate-limit") => {
+                    let value = parser.value()?;
+                    let kib: u64 = args::parse_value("rate-limit", value)?;
+
+                    if kib == 0 {
+                        anyhow::bail!("`--rate-limit` must be at least 1 KiB/s");
+                    }
+                    rate_limit = Some(kib);
+                }
+                Long("filter") => {
+                    let value = parser.value()?;
+
+                    filter = Some(args::parse_value("filter", value)?);
+                }
+                Long("cobs") if refs.is_all() => {
+                    refs = sync::Refs::Cobs;
+                }
+                Long("cob") if refs.is_all() => {
+                    let value = parser.value()?;
+
+                    refs = sync::Refs::cob(&value.to_string_lossy())?;
+                }
+                Long("peer") if refs.is_all() => {
+                    let value = parser.value()?;
+                    let peer = args::parse_value("peer", value)?;
+
+                    refs = sync::Refs::Peer(peer);
+                }
+                Long("fetch-peers") => {
+                    fetch_peers = true;
+                }
+                Long("branch") => {
+                    let value = parser.value()?;
+
+                    branches.push(value.to_string_lossy().to_string());
+                }
+                Long("recurse-submodules") => {
+                    recurse_submodules = true;
                 }
                 Long("seed") => {
                     let value = parser.value()?;
                     let value = value.to_string_lossy();
                     let value = value.as_ref();
                     let addr = sync::Seed::from_str(value).map_err(|_| Error::WithHint {
-                        err: anyhow!("invalid seed address specified: '{}'", value),
+                        err: anyhow!(term::tr!("invalid seed address specified: '{}'", value)),
                         hint: "hint: valid seed addresses have the format <peer-id>@<host>:<port>, see `rad sync --help` for more information",
                     })?;
 
@@ -108,6 +412,98 @@ impl Args for Options {
             }
         }
 
+        if save && seeds.is_empty() {
+            anyhow::bail!("`--save` requires at least one seed to be specified with `--seed`");
+        }
+        if save && operation != Operation::Sync {
+            anyhow::bail!(term::tr!(
+                "`--save` cannot be combined with `{}`",
+                operation.option()
+            ));
+        }
+        if prune
+            && matches!(
+                operation,
+                Operation::Identity | Operation::All | Operation::Status | Operation::Daemon
+            )
+        {
+            anyhow::bail!(term::tr!(
+                "`--prune` cannot be combined with `{}`",
+                operation.option()
+            ));
+        }
+        if fetch_peers
+            && matches!(
+                operation,
+                Operation::Identity | Operation::All | Operation::Daemon
+            )
+        {
+            anyhow::bail!(term::tr!(
+                "`--fetch-peers` cannot be combined with `{}`",
+                operation.option()
+            ));
+        }
+        if fetch_peers && !refs.is_all() {
+            anyhow::bail!("`--fetch-peers` cannot be combined with `--cobs`, `--cob` or `--peer`");
+        }
+        if !branches.is_empty() {
+            if operation != Operation::Sync {
+                anyhow::bail!(term::tr!(
+                    "`--branch` cannot be combined with `{}`",
+                    operation.option()
+                ));
+            }
+            if !refs.is_all() || fetch_peers || prune {
+                anyhow::bail!(
+                    "`--branch` cannot be combined with `--cobs`, `--cob`, `--peer`, \
+                     `--fetch-peers` or `--prune`"
+                );
+            }
+        }
+        if matches!(operation, Operation::All | Operation::Daemon) && origin.is_some() {
+            anyhow::bail!(term::tr!(
+                "`{}` cannot be combined with a URN",
+                operation.option()
+            ));
+        }
+        if matches!(
+            operation,
+            Operation::Identity | Operation::All | Operation::Daemon
+        ) && !refs.is_all()
+        {
+            anyhow::bail!(term::tr!(
+                "`{}` cannot be combined with `--cobs`, `--cob` or `--peer`",
+                operation.option()
+            ));
+        }
+        if matches!(
+            operation,
+            Operation::Identity | Operation::All | Operation::Daemon
+        ) && transport == sync::Transport::Http
+        {
+            anyhow::bail!(term::tr!(
+                "`--transport http` cannot be combined with `{}`",
+                operation.option()
+            ));
+        }
+        if rate_limit.is_some() && transport != sync::Transport::Http {
+            return Err(Error::WithHint {
+                err: anyhow!(
+                    "`--rate-limit` is only supported over HTTP, as transfers over the \
+                     peer-to-peer protocol can't be throttled"
+                ),
+                hint: "hint: use `--transport http` to sync with the seeds' git endpoints",
+            }
+            .into());
+        }
+        if filter.is_some() && transport != sync::Transport::Http {
+            return Err(Error::WithHint {
+                err: anyhow!("`--filter` is only supported over HTTP"),
+                hint: "hint: use `--transport http` to fetch from the seeds' git endpoints",
+            }
+            .into());
+        }
+
         if let (
             &[_, ..],
             Some(identity::Origin {
@@ -115,76 +511,389 @@ impl Args for Options {
             }),
         ) = (seeds.as_slice(), &origin)
         {
-            anyhow::bail!(
+            anyhow::bail!(term::tr!(
                 "unexpected argument `--seed`, seed already set to '{}'",
                 addr
-            );
+            ));
+        }
+        // Without `--seed`, the seed configured with `sync.seed` is used, if any.
+        if seeds.is_empty() && origin.as_ref().map_or(true, |o| o.seed.is_none()) {
+            seeds.extend(sync::default_seed()?);
         }
 
         Ok((
             Options {
                 origin,
                 seeds,
-                mode: Mode::default(),
-                sync_self,
+                // Branches are only ever pushed.
+                mode: if branches.is_empty() {
+                    Mode::default()
+                } else {
+                    Mode::Push
+                },
+                operation,
                 verbose,
+                save,
+                refs,
+                prune,
+                fetch_peers,
+                branches,
+                recurse_submodules,
+                transport,
+                timeout,
+                rate_limit,
+                filter,
             },
             unparsed,
         ))
     }
 }
 
-pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+pub fn run(mut options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if options.operation == Operation::InstallHook {
+        return self::install_hook();
+    }
+    offline::check("syncing")?;
+
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
-    let storage = keys::storage(&profile, signer)?;
+    let storage = keys::storage(&profile, signer.clone())?;
     let rt = tokio::runtime::Runtime::new()?;
+    // Seeds given on the command line are used for every project synced. Otherwise, each
+    // project is synced with its own seeds, see `term::sync::seeds`.
+    let seeds: Option<NonEmpty<_>> =
+        if let Some(seed) = options.origin.as_ref().and_then(|o| o.seed.clone()) {
+            Some(NonEmpty::new(seed))
+        } else {
+            options.seeds.clone().try_into().ok()
+        };
+
+    if options.operation == Operation::Daemon {
+        return daemon::run(&profile, signer, seeds, storage, options, rt);
+    }
+    if options.operation == Operation::All {
+        return sync_all(&profile, signer, seeds, storage, options, rt);
+    }
+
     let urn = if let Some(origin) = &options.origin {
         origin.urn.clone()
     } else {
         project::cwd().map(|(urn, _)| urn)?
     };
 
-    let seeds = if let Some(seed) = options.origin.as_ref().and_then(|o| o.seed.clone()) {
-        NonEmpty::new(seed)
-    } else if let Ok(seeds) = options.seeds.clone().try_into() {
-        seeds
+    if options.operation == Operation::Identity {
+        return sync_self(&profile, seeds, storage, options, rt);
+    }
+    let seeds = match seeds {
+        Some(seeds) => seeds,
+        None => term::sync::seeds(&profile, &urn)?,
+    };
+
+    if options.save {
+        let (cwd, _) = project::cwd()
+            .map_err(|_| anyhow!("`--save` must be used from within a project's working copy"))?;
+        if cwd != urn {
+            anyhow::bail!("`--save` can only be used with the project of the working copy");
+        }
+        seed::set_seeds(Path::new("."), &options.seeds)?;
+
+        term::success!(
+            "Saved {} seed(s) as the default for this project",
+            options.seeds.len()
+        );
+    }
+
+    if options.fetch_peers {
+        let peers = tracked(&urn, &storage)?;
+
+        if peers.is_empty() {
+            term::info!("{}", term::format::italic("No tracked peers."));
+            term::tip!("Track a peer with `rad track <peer-id>`.");
+
+            return Ok(());
+        }
+        options.refs = sync::Refs::Peers(peers);
+    }
+
+    // Nb. Submodules are only synced along with whole projects, over the peer-to-peer protocol.
+    let submodules = if options.operation != Operation::Sync
+        || !options.refs.is_all()
+        || options.transport == sync::Transport::Http
+    {
+        vec![]
     } else {
-        sync::seeds(&profile)?
+        submodules(&urn)
     };
+    let recurse = options.recurse_submodules;
+    let mode = options.mode;
+    let settings = settings(&profile, &options);
 
-    if options.sync_self {
-        sync_self(&profile, seeds, storage, options, rt)
+    let fetches = mode.is_fetch() && options.operation == Operation::Sync;
+
+    // Only look for fetched refs if a webhook wants to be notified of them.
+    let before = if fetches
+        && Hooks::load(&profile)
+            .map(|h| h.wants(Event::RefsFetched, &urn))
+            .unwrap_or(false)
+    {
+        Some(remote_refs(&profile, &urn)?)
     } else {
-        sync(urn, &profile, seeds, storage, options, rt)
+        None
+    };
+
+    sync(urn.clone(), &profile, seeds.clone(), storage, options, rt)?;
+
+    // Keep the commit-graph up to date with the fetched history, so that it is used when
+    // computing eg. how far patches are ahead of their target, and the search index up
+    // to date with the fetched issues and patches. This is best-effort.
+    if fetches {
+        git::write_commit_graph(profile.paths().git_dir()).ok();
+        search::update(&profile, &urn).ok();
+    }
+
+    if let Some(before) = before {
+        let after = remote_refs(&profile, &urn)?;
+        let updated = after
+            .iter()
+            .filter(|(name, oid)| before.get(*name) != Some(*oid))
+            .map(|(name, oid)| {
+                json::json!({
+                    "name": name,
+                    "old": before.get(name).map(|o| o.to_string()),
+                    "new": oid.to_string(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if !updated.is_empty() {
+            term::hooks::emit(
+                &profile,
+                Event::RefsFetched,
+                &urn,
+                json::json!({ "refs": updated }),
+            );
+        }
+    }
+
+    if !submodules.is_empty()
+        && (recurse
+            || term::confirm(term::tr!(
+                "Sync the {} submodule(s) of this project as well?",
+                submodules.len()
+            ))?)
+    {
+        sync_submodules(&submodules, &profile, seeds, mode, settings)?;
     }
+    Ok(())
 }
 
-pub fn sync_self(
+/// Get the refs of the remote peers of a project in storage, relative to the project
+/// namespace, eg. `refs/remotes/<peer>/heads/master`.
+fn remote_refs(profile: &Profile, urn: &Urn) -> anyhow::Result<BTreeMap<String, git::Oid>> {
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let mut refs = BTreeMap::new();
+
+    for r in monorepo
+        .references_glob(&format!("{}refs/remotes/*", namespace))?
+        .flatten()
+    {
+        if let (Some(name), Some(oid)) = (r.name(), r.target()) {
+            refs.insert(name.trim_start_matches(&namespace).to_owned(), oid);
+        }
+    }
+    Ok(refs)
+}
+
+/// Get the submodules that are radicle projects, of the working copy of the given project,
+/// if run from it.
+fn submodules(urn: &Urn) -> Vec<project::Submodule> {
+    match project::cwd() {
+        Ok((cwd, repo)) if &cwd == urn => project::submodules(&repo).unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Sync the projects of the given submodules.
+fn sync_submodules(
+    submodules: &[project::Submodule],
     profile: &Profile,
     seeds: NonEmpty<sync::Seed<String>>,
+    mode: Mode,
+    settings: sync::Settings,
+) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let signer = term::signer(profile)?;
+
+    for submodule in submodules {
+        term::headline(&term::tr!(
+            "Syncing 🌱 submodule {} ({}) with {} seed(s)",
+            term::format::highlight(submodule.path.display()),
+            submodule.urn,
+            term::format::dim(seeds.len())
+        ));
+
+        let result = term::sync::sync_with(
+            submodule.urn.clone(),
+            seeds.clone(),
+            mode,
+            settings,
+            profile,
+            signer.clone(),
+            &rt,
+        )?;
+        term::sync::report(&result);
+        term::blank();
+    }
+    Ok(())
+}
+
+pub fn sync_self(
+    profile: &Profile,
+    seeds: Option<NonEmpty<sync::Seed<String>>>,
     storage: Storage,
     options: Options,
     rt: tokio::runtime::Runtime,
 ) -> anyhow::Result<()> {
     let identity = person::local(&storage)?;
     let urn = identity.urn();
+    let seeds = match seeds {
+        Some(seeds) => seeds,
+        None => term::sync::seeds(profile, &urn)?,
+    };
 
-    term::headline(&format!(
+    term::headline(&term::tr!(
         "Syncing 🌱 self to {} seed(s)",
         term::format::dim(seeds.len())
     ));
 
     let signer = term::signer(profile)?;
-    let _result = term::sync::sync(urn, seeds, options.mode, profile, signer, &rt)?;
+    let settings = settings(profile, &options);
+    let result = term::sync::sync_with(urn, seeds, options.mode, settings, profile, signer, &rt)?;
+    term::sync::report(&result);
+
+    Ok(())
+}
+
+/// Get the sync settings of the profile, overridden by the given options.
+pub fn settings(profile: &Profile, options: &Options) -> sync::Settings {
+    let mut settings = sync::Settings::load(profile);
 
-    if options.verbose {
-        // TODO: When sync result is usable, output should go here.
+    if let Some(secs) = options.timeout {
+        settings.timeout = time::Duration::from_secs(secs);
     }
+    if let Some(kib) = options.rate_limit {
+        settings.rate_limit = Some(kib * 1024);
+    }
+    settings.filter = options.filter;
+
+    settings
+}
+
+/// Install a `pre-push` hook in the current working copy, to sync after every push.
+pub fn install_hook() -> anyhow::Result<()> {
+    let (_, repo) = project::cwd().map_err(|_| {
+        anyhow!("`--install-hook` must be used from within a project's working copy")
+    })?;
+    let path = git::install_hook(&repo, "pre-push", PRE_PUSH_HOOK, HOOK_MARKER)?;
+
+    term::success!(
+        "Installed sync hook at {}",
+        term::format::highlight(path.display())
+    );
+    term::tip!("The project will now be synced in the background after every `git push rad`.");
 
     Ok(())
 }
 
+pub fn sync_all(
+    profile: &Profile,
+    signer: BoxedSigner,
+    seeds: Option<NonEmpty<sync::Seed<String>>>,
+    storage: Storage,
+    options: Options,
+    rt: tokio::runtime::Runtime,
+) -> anyhow::Result<()> {
+    let projects = project::list(&storage)?;
+    let settings = settings(profile, &options);
+    let mut table = term::Table::default();
+    let mut failed = 0;
+
+    term::headline(&term::tr!(
+        "Syncing 🌱 {} project(s) with {}",
+        term::format::dim(projects.len()),
+        match &seeds {
+            Some(seeds) => format!("{} seed(s)", term::format::dim(seeds.len())),
+            None => String::from("their seeds"),
+        }
+    ));
+
+    for (urn, meta, _) in projects {
+        term::blank();
+        term::info!(
+            "{} {}",
+            term::format::highlight(&meta.name),
+            term::format::dim(&urn)
+        );
+
+        let seeds = match &seeds {
+            Some(seeds) => Ok(seeds.clone()),
+            None => term::sync::seeds(profile, &urn),
+        };
+        let results = match seeds.and_then(|seeds| {
+            term::sync::sync_with(
+                urn.clone(),
+                seeds,
+                options.mode,
+                settings,
+                profile,
+                signer.clone(),
+                &rt,
+            )
+        }) {
+            Ok(results) => results,
+            Err(err) => {
+                term::error(err);
+                failed += 1;
+                table.push([
+                    term::format::negative("!!"),
+                    term::format::highlight(&meta.name),
+                    term::format::negative("failed"),
+                    String::new(),
+                ]);
+                continue;
+            }
+        };
+        let synced = results.iter().filter(|r| r.is_success()).count();
+        let refs = results.iter().map(|r| r.refs().len()).sum::<usize>();
+
+        if synced < results.len() {
+            failed += 1;
+        }
+        table.push([
+            if synced == results.len() {
+                term::format::positive("ok")
+            } else {
+                term::format::negative("!!")
+            },
+            term::format::highlight(&meta.name),
+            format!("{}/{} seed(s)", synced, results.len()),
+            format!("{} ref(s) updated", refs),
+        ]);
+    }
+    term::blank();
+    table.render();
+    term::blank();
+
+    if failed > 0 {
+        anyhow::bail!(exit::Error::Network(term::tr!(
+            "{} project(s) failed to sync",
+            failed
+        )));
+    }
+    Ok(())
+}
+
 pub fn sync(
     urn: Urn,
     profile: &Profile,
@@ -193,7 +902,77 @@ pub fn sync(
     options: Options,
     rt: tokio::runtime::Runtime,
 ) -> anyhow::Result<()> {
-    term::headline(&format!(
+    match options.operation {
+        Operation::DryRun => return dry_run(&urn, profile, seeds, &storage, &options),
+        Operation::Status => return status(&urn, profile, seeds, &storage, &options.refs),
+        _ => {}
+    }
+    let settings = settings(profile, &options);
+    let mut refs = options.refs.clone();
+
+    if !options.branches.is_empty() {
+        let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+
+        for branch in &options.branches {
+            let name = format!("refs/namespaces/{}/refs/heads/{}", urn.encode_id(), branch);
+
+            if monorepo.find_reference(&name).is_err() {
+                return Err(Error::WithHint {
+                    err: anyhow!(term::tr!("branch '{}' was not found in storage", branch)),
+                    hint: "hint: push the branch to storage with `git push rad` first",
+                }
+                .into());
+            }
+        }
+    }
+
+    // Respect the tracking policy of the project, if we're in its working copy. The
+    // policy is applied to the tracking config when it is set, so peer-to-peer syncs,
+    // which only replicate tracked peers, already follow it. HTTP fetches have to select
+    // the peers explicitly.
+    if options.transport == sync::Transport::Http && refs.is_all() {
+        if let Some(policy) = policy(&urn) {
+            if let Some(proj) = project::get(&storage, &urn)? {
+                if let Some(peers) = policy.peers(&proj, &storage)? {
+                    refs = sync::Refs::Peers(peers);
+                }
+            }
+        }
+    }
+
+    if !refs.is_all() || options.transport == sync::Transport::Http {
+        if options.mode.is_fetch() || !options.refs.is_all() {
+            fetch_refs(&urn, profile, seeds.clone(), &storage, &refs, &settings)?;
+        }
+        if options.transport == sync::Transport::Http
+            && options.mode.is_push()
+            && options.refs.is_all()
+            && project::get(&storage, &urn)?.is_some()
+        {
+            push_refs(
+                &urn,
+                profile,
+                seeds.clone(),
+                &storage,
+                &options.branches,
+                &settings,
+            )?;
+        }
+
+        if options.prune {
+            prune(&urn, profile, &seeds, storage.peer_id(), &refs, false)?;
+        }
+        if let (true, sync::Refs::Peers(peers)) = (options.fetch_peers, &options.refs) {
+            if let Ok((cwd, repo)) = project::cwd() {
+                if cwd == urn {
+                    fetch_remotes(&repo, peers)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    term::headline(&term::tr!(
         "Syncing 🌱 identity {} with {} seed(s)",
         term::format::highlight(&urn),
         term::format::dim(seeds.len())
@@ -201,22 +980,38 @@ pub fn sync(
 
     let storage = storage.read_only();
     let signer = term::signer(profile)?;
-    let _result = term::sync::sync(
+    let result = term::sync::sync_with(
         urn.clone(),
         seeds.clone(),
         options.mode,
+        settings,
         profile,
         signer,
         &rt,
     )?;
+    term::sync::report(&result);
     term::blank();
 
-    if options.verbose {
-        // TODO: When sync result is usable, output should go here.
-        // TODO: Depending on the result, we can show `~` as in partial success, `ok` as in total
-        //       success and `!!` as in no success.
-        // TODO: NoConnection can be due to invalid PeerId!
-        // TODO: Success with no refs updated can mean the server is not tracking us.
+    if result.iter().all(|r| !r.is_success()) {
+        term::tip!("If the seeds' peer port is blocked on your network, try `--transport http`.");
+    }
+    if options.mode.is_fetch() && result.iter().any(|r| !r.is_success()) {
+        term::tip!(
+            "Fetches over the peer-to-peer protocol start from scratch when retried; \
+            for resumable fetches, try `--transport http`."
+        );
+    }
+
+    if options.prune {
+        prune(
+            &urn,
+            profile,
+            &seeds,
+            storage.peer_id(),
+            &options.refs,
+            false,
+        )?;
+        term::blank();
     }
 
     if let Some(proj) = project::get(&storage, &urn)? {
@@ -228,21 +1023,14 @@ pub fn sync(
                 url.set_port(None).ok();
 
                 if let Some(host) = url.host() {
-                    let is_routable = match host {
-                        url::Host::Domain("localhost") => false,
-                        url::Host::Domain(_) => true,
-                        url::Host::Ipv4(ip) => {
-                            !ip.is_loopback() && !ip.is_unspecified() && !ip.is_private()
-                        }
-                        url::Host::Ipv6(ip) => !ip.is_loopback() && !ip.is_unspecified(),
-                    };
+                    let is_routable = is_routable(&host);
 
                     term::info!("🍃 Your project is available at:");
                     term::blank();
 
                     if is_routable {
                         if proj.remotes.contains(peer_id) {
-                            term::indented(&format!(
+                            term::indented(&term::tr!(
                                 "{} {}",
                                 term::format::dim("(web)"),
                                 term::format::highlight(format!(
@@ -251,7 +1039,7 @@ pub fn sync(
                                 ))
                             ));
                         }
-                        term::indented(&format!(
+                        term::indented(&term::tr!(
                             "{} {}",
                             term::format::dim("(web)"),
                             term::format::highlight(format!(
@@ -266,7 +1054,7 @@ pub fn sync(
                     let id = urn.encode_id();
                     let git_url = url.join(&id)?;
 
-                    term::indented(&format!(
+                    term::indented(&term::tr!(
                         "{} {}",
                         term::format::dim("(git)"),
                         term::format::highlight(format!("{}.git", git_url)),
@@ -279,3 +1067,551 @@ pub fn sync(
 
     Ok(())
 }
+
+/// Get the tracking policy of a project, if its working copy is the current directory.
+fn policy(urn: &Urn) -> Option<TrackingPolicy> {
+    match project::cwd() {
+        Ok((cwd, _)) if &cwd == urn => TrackingPolicy::get(Path::new(".")).ok(),
+        _ => None,
+    }
+}
+
+/// Get the fetch scopes of a project's peers. Blocked peers get an empty scope, so that
+/// only their `rad/` refs are fetched.
+fn scopes(urn: &Urn, profile: &Profile) -> anyhow::Result<Scopes> {
+    let mut scopes = Scopes::load(profile, urn)?;
+    for peer in Blocklist::load(profile)?.peers() {
+        scopes.insert(peer, Scope::default());
+    }
+    Ok(scopes)
+}
+
+/// Get the tracked peers of a project.
+pub fn tracked(urn: &Urn, storage: &Storage) -> anyhow::Result<Vec<PeerId>> {
+    let proj = project::get(storage, urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found in local storage", urn))
+    })?;
+    let mut peers = project::tracked(&proj, storage)?
+        .into_keys()
+        .collect::<Vec<_>>();
+    peers.sort();
+
+    Ok(peers)
+}
+
+/// Fetch the git remotes of the working copy that belong to the given peers.
+pub fn fetch_remotes(repo: &git::Repository, peers: &[PeerId]) -> anyhow::Result<()> {
+    let mut remotes = git::remotes(repo)?
+        .into_iter()
+        .filter(|(_, peer)| peers.contains(peer))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    remotes.sort();
+    remotes.dedup();
+
+    if remotes.is_empty() {
+        return Ok(());
+    }
+    term::blank();
+
+    for name in remotes {
+        let spinner = term::spinner(term::tr!(
+            "Fetching remote {}...",
+            term::format::highlight(&name)
+        ));
+        match git::git(Path::new("."), ["fetch", name.as_str()]) {
+            Ok(_) => spinner.finish(),
+            Err(err) => {
+                spinner.failed();
+                term::warning(&term::tr!("Failed to fetch remote {}: {}", name, err));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fetch the selected refs of a project from the git endpoint of each seed.
+/// If the project has no identity yet, the identity of the fetched remotes is adopted.
+pub fn fetch_refs(
+    urn: &Urn,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+    storage: &Storage,
+    refs: &sync::Refs,
+    settings: &sync::Settings,
+) -> anyhow::Result<()> {
+    term::headline(&term::tr!(
+        "Fetching 🌱 {} of {} from {} seed(s)",
+        if refs.is_all() {
+            "refs"
+        } else {
+            "selected refs"
+        },
+        term::format::highlight(urn),
+        term::format::dim(seeds.len())
+    ));
+
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let credentials = Credentials::load(profile)?;
+    let aliases = Aliases::cwd();
+    let scopes = scopes(urn, profile)?;
+    let local = storage.peer_id();
+    let mut table = term::Table::default();
+
+    for seed in &seeds {
+        let peer = term::format::tertiary(aliases.name(&seed.peer));
+        let url = if let Some(url) = git_url(seed) {
+            url
+        } else {
+            term::warning(&term::tr!("Skipping seed {}: invalid address", seed.addrs));
+            continue;
+        };
+        let mut transfer = term::transfer(format!("Fetching from {}", url));
+        let result = sync::refs::fetch(
+            &monorepo,
+            &url,
+            credentials.for_url(&url),
+            urn,
+            local,
+            refs,
+            &scopes,
+            settings,
+            |p| transfer.update(&p),
+        );
+
+        match result {
+            Ok(fetched) => {
+                transfer.finish();
+
+                for (name, oid) in &fetched.updated {
+                    term::info!(
+                        "{} {} {}",
+                        term::format::positive("✓"),
+                        term::format::tertiary(name),
+                        term::format::secondary(radicle_common::fmt::oid(oid))
+                    );
+                }
+                for rejected in &fetched.rejected {
+                    term::eprintln(
+                        term::format::negative("✗"),
+                        format!(
+                            "{} {} {}",
+                            term::format::tertiary(&rejected.name),
+                            term::format::dim(format!("({})", aliases.name(&rejected.peer))),
+                            term::format::negative(&rejected.reason)
+                        ),
+                    );
+                }
+                table.push([
+                    if fetched.rejected.is_empty() {
+                        term::format::positive("ok")
+                    } else {
+                        term::format::negative("!!")
+                    },
+                    peer,
+                    format!("{} ref(s) fetched", fetched.updated.len()),
+                    if fetched.rejected.is_empty() {
+                        String::new()
+                    } else {
+                        term::format::negative(format!("{} rejected", fetched.rejected.len()))
+                    },
+                ]);
+            }
+            Err(err) => {
+                transfer.error(err);
+                table.push([
+                    term::format::negative("!!"),
+                    peer,
+                    term::format::negative("failed"),
+                    String::new(),
+                ]);
+            }
+        }
+    }
+    term::blank();
+    table.render();
+
+    let pruned = Blocklist::load(profile)?.prune(&monorepo, urn)?;
+    if pruned > 0 {
+        term::blank();
+        term::info!("Dropped {} ref(s) of blocked peers", pruned);
+    }
+
+    if let Some(peer) = sync::refs::adopt(storage, &monorepo, urn)? {
+        term::blank();
+        term::success!(
+            "Adopted project identity of {}",
+            term::format::tertiary(aliases.name(&peer))
+        );
+    }
+
+    Ok(())
+}
+
+/// Push your refs of a project to the git endpoint of each seed, with the credential
+/// stored for the seed by `rad seed login`, if the seed asks for one.
+pub fn push_refs(
+    urn: &Urn,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+    storage: &Storage,
+    branches: &[String],
+    settings: &sync::Settings,
+) -> anyhow::Result<()> {
+    term::headline(&term::tr!(
+        "Pushing 🌱 {} to {} seed(s)",
+        term::format::highlight(urn),
+        term::format::dim(seeds.len())
+    ));
+
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let credentials = Credentials::load(profile)?;
+    let aliases = Aliases::cwd();
+    let mut table = term::Table::default();
+
+    for seed in &seeds {
+        let peer = term::format::tertiary(aliases.name(&seed.peer));
+        let url = if let Some(url) = git_url(seed) {
+            url
+        } else {
+            term::warning(&term::tr!("Skipping seed {}: invalid address", seed.addrs));
+            continue;
+        };
+        let spinner = term::spinner(term::tr!("Pushing to {}...", url));
+        let result = sync::refs::push(
+            &monorepo,
+            &url,
+            credentials.for_url(&url),
+            urn,
+            storage.peer_id(),
+            branches,
+            settings,
+        );
+
+        match result {
+            Ok(pushed) => {
+                spinner.finish();
+
+                let rejected = pushed
+                    .iter()
+                    .filter_map(|p| p.rejected.as_ref().map(|reason| (&p.name, reason)))
+                    .collect::<Vec<_>>();
+                for (name, reason) in &rejected {
+                    term::eprintln(
+                        term::format::negative("✗"),
+                        format!(
+                            "{} {}",
+                            term::format::tertiary(name),
+                            term::format::negative(reason)
+                        ),
+                    );
+                }
+                table.push([
+                    if rejected.is_empty() {
+                        term::format::positive("ok")
+                    } else {
+                        term::format::negative("!!")
+                    },
+                    peer,
+                    term::tr!("{} ref(s) pushed", pushed.len() - rejected.len()),
+                    if rejected.is_empty() {
+                        String::new()
+                    } else {
+                        term::format::negative(term::tr!("{} rejected", rejected.len()))
+                    },
+                ]);
+            }
+            Err(err) => {
+                spinner.error(err);
+                table.push([
+                    term::format::negative("!!"),
+                    peer,
+                    term::format::negative("failed"),
+                    String::new(),
+                ]);
+            }
+        }
+    }
+    term::blank();
+    table.render();
+
+    Ok(())
+}
+
+/// Print the refs that syncing with each seed would update, without syncing.
+pub fn dry_run(
+    urn: &Urn,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+    storage: &Storage,
+    options: &Options,
+) -> anyhow::Result<()> {
+    // Selective syncs only ever fetch.
+    let (is_fetch, is_push) = if options.refs.is_all() {
+        (options.mode.is_fetch(), options.mode.is_push())
+    } else {
+        (true, false)
+    };
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let credentials = Credentials::load(profile)?;
+    let aliases = Aliases::cwd();
+    let local = storage.peer_id();
+
+    term::headline(&term::tr!(
+        "Computing 🌱 sync of {} with {} seed(s) {}",
+        term::format::highlight(urn),
+        term::format::dim(seeds.len()),
+        term::format::dim("(dry run)")
+    ));
+
+    for seed in &seeds {
+        let url = if let Some(url) = git_url(seed) {
+            url
+        } else {
+            term::warning(&term::tr!("Skipping seed {}: invalid address", seed.addrs));
+            continue;
+        };
+        let spinner = term::spinner(term::tr!("Listing refs of {}...", url));
+        let plan = match sync::refs::plan(
+            &monorepo,
+            &url,
+            credentials.for_url(&url),
+            urn,
+            local,
+            &options.refs,
+        ) {
+            Ok(plan) => {
+                spinner.clear();
+                plan
+            }
+            Err(err) => {
+                spinner.error(err);
+                continue;
+            }
+        };
+        let mut table = term::Table::default();
+
+        for (enabled, direction, updates) in [
+            (is_fetch, "fetch", &plan.fetch),
+            (is_push, "push", &plan.push),
+        ] {
+            if !enabled {
+                continue;
+            }
+            for update in updates {
+                table.push([
+                    term::format::dim(direction),
+                    term::format::tertiary(&update.name),
+                    format!(
+                        "{} {} {}",
+                        oid(update.old),
+                        term::format::dim("→"),
+                        oid(update.new)
+                    ),
+                ]);
+            }
+        }
+
+        term::info!(
+            "{} {}",
+            term::format::bold(aliases.name(&seed.peer)),
+            term::format::dim(format!("({})", url))
+        );
+        if table.is_empty() {
+            term::indented(&term::format::italic("Up to date."));
+        } else {
+            table.render();
+        }
+        term::blank();
+    }
+    if options.prune {
+        prune(urn, profile, &seeds, local, &options.refs, true)?;
+    }
+    term::tip!("Run without `--dry-run` to sync.");
+
+    Ok(())
+}
+
+/// Compare the refs of a project on each seed with local state.
+pub fn status(
+    urn: &Urn,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+    storage: &Storage,
+    refs: &sync::Refs,
+) -> anyhow::Result<()> {
+    use sync::refs::State;
+
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let credentials = Credentials::load(profile)?;
+    let aliases = Aliases::cwd();
+    let local = storage.peer_id();
+    let mut unreachable = 0;
+
+    term::headline(&term::tr!(
+        "Comparing 🌱 {} with {} seed(s)",
+        term::format::highlight(urn),
+        term::format::dim(seeds.len())
+    ));
+
+    for seed in &seeds {
+        let url = if let Some(url) = git_url(seed) {
+            url
+        } else {
+            term::warning(&term::tr!("Skipping seed {}: invalid address", seed.addrs));
+            continue;
+        };
+        let spinner = term::spinner(term::tr!("Listing refs of {}...", url));
+        let status = match sync::refs::status(
+            &monorepo,
+            &url,
+            credentials.for_url(&url),
+            urn,
+            local,
+            refs,
+        ) {
+            Ok(status) => {
+                spinner.clear();
+                status
+            }
+            Err(err) => {
+                spinner.error(err);
+                unreachable += 1;
+                continue;
+            }
+        };
+        let mut table = term::Table::default();
+        let mut in_sync = 0;
+
+        for s in &status {
+            let state = match s.state {
+                State::InSync => {
+                    in_sync += 1;
+                    term::format::positive(s.state)
+                }
+                State::Ahead | State::Behind => term::format::yellow(s.state),
+                State::Diverged | State::Missing => term::format::negative(s.state),
+            };
+            table.push([
+                state,
+                term::format::tertiary(&s.name),
+                oid(s.local),
+                oid(s.seed),
+            ]);
+        }
+
+        term::info!(
+            "{} {}",
+            term::format::bold(aliases.name(&seed.peer)),
+            term::format::dim(format!("({})", url))
+        );
+        if table.is_empty() {
+            term::indented(&term::format::italic("No refs."));
+        } else {
+            table.render();
+            term::indented(&term::format::dim(format!(
+                "{}/{} ref(s) in sync",
+                in_sync,
+                status.len()
+            )));
+        }
+        term::blank();
+    }
+
+    if unreachable > 0 {
+        anyhow::bail!(exit::Error::Network(term::tr!(
+            "{} seed(s) could not be reached",
+            unreachable
+        )));
+    }
+    Ok(())
+}
+
+/// Prune the remote refs of a project that are no longer advertised by any of the seeds.
+pub fn prune(
+    urn: &Urn,
+    profile: &Profile,
+    seeds: &NonEmpty<sync::Seed<String>>,
+    local: &PeerId,
+    refs: &sync::Refs,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let credentials = Credentials::load(profile)?;
+    let mut advertised = sync::refs::Advertised::new();
+    let spinner = term::spinner("Listing refs advertised by seeds...");
+
+    for seed in seeds {
+        let result = git_url(seed)
+            .ok_or_else(|| anyhow!(term::tr!("invalid address for seed {}", seed.addrs)))
+            .and_then(|url| {
+                sync::refs::advertised(
+                    &monorepo,
+                    &url,
+                    credentials.for_url(&url),
+                    urn,
+                    &mut advertised,
+                )
+            });
+
+        // Nb. Pruning based on a partial view of the seeds could remove refs that still
+        // exist upstream, so we don't prune anything in that case.
+        if let Err(err) = result {
+            spinner.failed();
+            term::warning(&term::tr!(
+                "Not pruning: failed to list refs of seed: {}",
+                err
+            ));
+
+            return Ok(());
+        }
+    }
+    spinner.clear();
+
+    let pruned = sync::refs::prune(&monorepo, urn, local, refs, &advertised, dry_run)?;
+    for (name, oid) in &pruned {
+        term::info!(
+            "{} {} {}",
+            term::format::negative("-"),
+            term::format::tertiary(name),
+            term::format::dim(radicle_common::fmt::oid(oid))
+        );
+    }
+    if dry_run {
+        term::info!("{} ref(s) would be pruned", pruned.len());
+    } else if pruned.is_empty() {
+        term::info!("{}", term::format::italic("Nothing to prune."));
+    } else {
+        term::success!("Pruned {} ref(s)", pruned.len());
+    }
+    Ok(())
+}
+
+/// Format an optional ref target for display.
+fn oid(oid: Option<git::Oid>) -> String {
+    match oid {
+        Some(oid) => term::format::secondary(radicle_common::fmt::oid(&oid)),
+        None => term::format::dim("(none)"),
+    }
+}
+
+/// Get the git URL of a seed, based on its address.
+fn git_url(seed: &sync::Seed<String>) -> Option<Url> {
+    let mut url = Url::from_str(&format!("https://{}", seed.addrs)).ok()?;
+    url.set_port(None).ok();
+
+    if !is_routable(&url.host()?) {
+        url.set_scheme("http").ok();
+    }
+    Some(url)
+}
+
+/// Check whether a host is routable on the public internet.
+fn is_routable(host: &url::Host<&str>) -> bool {
+    match host {
+        url::Host::Domain("localhost") => false,
+        url::Host::Domain(_) => true,
+        url::Host::Ipv4(ip) => !ip.is_loopback() && !ip.is_unspecified() && !ip.is_private(),
+        url::Host::Ipv6(ip) => !ip.is_loopback() && !ip.is_unspecified(),
+    }
+}
@@ -0,0 +1,114 @@
+//! ASCII fallback, for terminals that can't show unicode, eg. with a `C` or `latin1`
+//! locale, where emojis, check marks and box-drawing characters would be garbled.
+//!
+//! The fallback is enabled with the global `--ascii` option of `rad`, with `ascii = true`
+//! in the configuration, or when the locale isn't UTF-8. Output printed with
+//! the `term` functions then has its symbols replaced with ASCII equivalents, and its
+//! emojis left out.
+use std::borrow::Cow;
+use std::env;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use radicle_common::config::Config;
+
+/// Environment var that enables the ASCII fallback, so that it is inherited by the
+/// commands `rad` runs. Set by the `--ascii` option of `rad`. Read as part of the
+/// configuration, see `radicle_common::config`.
+pub const RAD_ASCII: &str = "RAD_ASCII";
+
+/// Whether the fallback is enabled: unknown until first checked, as it may take reading
+/// the configuration.
+static ENABLED: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+const UNKNOWN: u8 = 0;
+const YES: u8 = 1;
+const NO: u8 = 2;
+
+/// Whether output is restricted to ASCII.
+pub fn is_enabled() -> bool {
+    match ENABLED.load(Ordering::Relaxed) {
+        YES => true,
+        NO => false,
+        _ => {
+            // Nb. The configuration includes `RAD_ASCII`.
+            let enabled = !is_utf8_locale() || Config::current().map_or(false, |c| c.ascii);
+
+            ENABLED.store(if enabled { YES } else { NO }, Ordering::Relaxed);
+            enabled
+        }
+    }
+}
+
+/// Whether the locale's character set is UTF-8. Without a locale, eg. on Windows, or in
+/// minimal containers, UTF-8 is assumed, as most terminals use it.
+fn is_utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|val| !val.is_empty())
+        .map_or(true, |val| {
+            let val = val.to_ascii_lowercase();
+
+            val.contains("utf-8") || val.contains("utf8")
+        })
+}
+
+/// Pick a symbol, or its ASCII equivalent if the fallback is enabled.
+pub fn symbol(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if is_enabled() {
+        ascii
+    } else {
+        unicode
+    }
+}
+
+/// Replace the symbols of a text with their ASCII equivalent, and leave out its emojis,
+/// if the fallback is enabled. Other characters, eg. accented letters, are kept.
+pub fn fallback(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() || !is_enabled() {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if let Some(replacement) = replacement(c) {
+            output.push_str(replacement);
+        } else if is_emoji(c) {
+            // Emojis usually follow a space, eg. "Patch created 🌱".
+            if output.ends_with(' ') {
+                output.pop();
+            }
+        } else {
+            output.push(c);
+        }
+    }
+    Cow::Owned(output)
+}
+
+fn replacement(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        '✓' | '✔' => "+",
+        '✗' | '✘' => "x",
+        '⋄' | '•' => "*",
+        '·' => ".",
+        '…' => "...",
+        '→' => "->",
+        '←' => "<-",
+        '⤷' => ">",
+        '⤹' => "<",
+        '─' | '━' | '┄' => "-",
+        '│' | '┃' => "|",
+        '├' | '┤' | '┬' | '┴' | '┼' | '┌' | '┐' | '└' | '┘' | '╭' | '╮' | '╰' | '╯' => {
+            "+"
+        }
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F300..=0x1FAFF | 0x2600..=0x27BF | 0x2B50..=0x2B55 | 0xFE0F | 0x200D
+    )
+}
@@ -0,0 +1,51 @@
+//! CSV output, as specified in RFC 4180, eg. for importing into spreadsheets.
+
+/// A CSV document, with a header row of `W` columns.
+#[derive(Debug)]
+pub struct Csv<const W: usize> {
+    header: [&'static str; W],
+    rows: Vec<[String; W]>,
+}
+
+impl<const W: usize> Csv<W> {
+    pub fn new(header: [&'static str; W]) -> Self {
+        Self {
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: [String; W]) {
+        self.rows.push(row);
+    }
+
+    /// Render the document, with CRLF line endings.
+    pub fn to_csv(&self) -> String {
+        let mut csv = line(self.header.iter().copied());
+
+        for row in &self.rows {
+            csv.push_str(&line(row.iter().map(String::as_str)));
+        }
+        csv
+    }
+
+    /// Print the document to standard output.
+    pub fn render(self) {
+        print!("{}", self.to_csv());
+    }
+}
+
+fn line<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    let mut line = fields.map(field).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Quote a field if needed, ie. if it contains a separator, quote or line break.
+fn field(field: &str) -> String {
+    if field.contains(&[',', '"', '\r', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,191 @@
+//! Rendering of unified diffs, eg. the output of `git diff`, for reviewing patches in
+//! the terminal.
+//!
+//! Removed and added lines are paired up, and the words that changed between them are
+//! highlighted. Context lines of languages known to syntect are syntax colored.
+use std::path::Path;
+
+use console::style;
+use once_cell::sync::Lazy;
+use similar::{ChangeTag, TextDiff};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+use crate as term;
+
+/// Syntaxes of the languages that are colored. Nb. Diff lines don't end with a newline.
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_nonewlines);
+/// Theme of syntax coloring.
+static THEME: Lazy<Theme> = Lazy::new(|| {
+    ThemeSet::load_defaults()
+        .themes
+        .remove(THEME_NAME)
+        .unwrap_or_default()
+});
+/// Name of the theme of syntax coloring, among syntect's default themes.
+const THEME_NAME: &str = "base16-ocean.dark";
+
+/// Minimum similarity of a removed and an added line for their changed words to be
+/// highlighted. Below it, the lines are considered unrelated.
+const MIN_SIMILARITY: f32 = 0.5;
+
+/// Render a unified diff as colored lines.
+pub fn render(diff: &str) -> Vec<String> {
+    let mut renderer = Renderer::default();
+
+    for line in diff.lines() {
+        renderer.line(&line.replace('\t', "    "));
+    }
+    renderer.flush();
+    renderer.output
+}
+
+/// Print a unified diff.
+pub fn print(diff: &str) {
+    for line in render(diff) {
+        term::print(line);
+    }
+}
+
+#[derive(Default)]
+struct Renderer {
+    output: Vec<String>,
+    /// Whether we're in a hunk, as opposed to the header of a file.
+    hunk: bool,
+    /// Highlighter of the current file, if its language is known. It is kept across
+    /// context lines, so that eg. multi-line strings and comments are colored.
+    highlighter: Option<HighlightLines<'static>>,
+    /// Removed lines, not yet rendered, to pair up with the added lines that follow.
+    removed: Vec<String>,
+    added: Vec<String>,
+}
+
+impl Renderer {
+    fn line(&mut self, line: &str) {
+        if line.starts_with("diff ") {
+            self.flush();
+            self.hunk = false;
+            self.highlighter = line
+                .rsplit(' ')
+                .next()
+                .and_then(|path| Path::new(path).extension())
+                .and_then(|ext| SYNTAXES.find_syntax_by_extension(&ext.to_string_lossy()))
+                .map(|syntax| HighlightLines::new(syntax, &THEME));
+            self.output.push(String::new());
+            self.output.push(term::format::bold(line));
+        } else if line.starts_with("@@") {
+            self.flush();
+            self.hunk = true;
+            self.output.push(hunk_header(line));
+        } else if !self.hunk {
+            self.output.push(term::format::bold(line));
+        } else if let Some(removed) = line.strip_prefix('-') {
+            if !self.added.is_empty() {
+                self.flush();
+            }
+            self.removed.push(removed.to_owned());
+        } else if let Some(added) = line.strip_prefix('+') {
+            self.added.push(added.to_owned());
+        } else {
+            self.flush();
+
+            if let Some(context) = line.strip_prefix(' ') {
+                let context = match &mut self.highlighter {
+                    Some(highlighter) => highlight(highlighter, context),
+                    None => context.to_owned(),
+                };
+                self.output.push(format!(" {}", context));
+            } else {
+                // Eg. "\ No newline at end of file".
+                self.output.push(term::format::dim(line));
+            }
+        }
+    }
+
+    /// Render the pending removed and added lines.
+    fn flush(&mut self) {
+        let removed = std::mem::take(&mut self.removed);
+        let added = std::mem::take(&mut self.added);
+        let pairs = removed.len().min(added.len());
+        let mut old = Vec::new();
+        let mut new = Vec::new();
+
+        for (r, a) in removed.iter().zip(added.iter()) {
+            let (r, a) = words(r, a);
+
+            old.push(r);
+            new.push(a);
+        }
+        old.extend(removed[pairs..].iter().map(|r| style(r).red().to_string()));
+        new.extend(added[pairs..].iter().map(|a| style(a).green().to_string()));
+
+        for line in old {
+            self.output.push(format!("{}{}", style("-").red(), line));
+        }
+        for line in new {
+            self.output.push(format!("{}{}", style("+").green(), line));
+        }
+    }
+}
+
+/// Render a removed and an added line, highlighting the words that changed.
+fn words(removed: &str, added: &str) -> (String, String) {
+    let diff = TextDiff::from_words(removed, added);
+
+    if diff.ratio() < MIN_SIMILARITY {
+        return (
+            style(removed).red().to_string(),
+            style(added).green().to_string(),
+        );
+    }
+    let mut old = String::new();
+    let mut new = String::new();
+
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+
+        match change.tag() {
+            ChangeTag::Equal => {
+                old.push_str(&style(value).red().to_string());
+                new.push_str(&style(value).green().to_string());
+            }
+            ChangeTag::Delete => {
+                old.push_str(&style(value).red().bold().reverse().to_string());
+            }
+            ChangeTag::Insert => {
+                new.push_str(&style(value).green().bold().reverse().to_string());
+            }
+        }
+    }
+    (old, new)
+}
+
+/// Render a hunk header, eg. `@@ -1,4 +1,5 @@ fn main() {`: the line ranges, then the
+/// enclosing section, if any.
+fn hunk_header(line: &str) -> String {
+    match line[2..].find("@@") {
+        Some(end) => {
+            let (ranges, section) = line.split_at(end + 4);
+
+            format!(
+                "{}{}",
+                term::format::tertiary(ranges),
+                term::format::dim(section)
+            )
+        }
+        None => term::format::tertiary(line),
+    }
+}
+
+/// Highlight a line of code. Colors are only used if enabled on stdout.
+fn highlight(highlighter: &mut HighlightLines<'static>, code: &str) -> String {
+    if !console::colors_enabled() {
+        return code.to_owned();
+    }
+    match highlighter.highlight_line(code, &SYNTAXES) {
+        Ok(ranges) => format!("{}\x1b[0m", as_24_bit_terminal_escaped(&ranges, false)),
+        Err(_) => code.to_owned(),
+    }
+}
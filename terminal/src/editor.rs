@@ -0,0 +1,163 @@
+//! Editing text, eg. patch and issue messages, with the user's editor.
+//!
+//! The editor is, in order of preference: `RAD_EDITOR`, the `editor` key of the
+//! configuration, `VISUAL`, `EDITOR`, or else `vi`. It may include arguments, eg.
+//! `code --wait`, and quotes, eg. for paths with spaces.
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+use radicle_common::config::Config;
+
+/// Environment var that sets the editor, overriding the other settings. Read as part
+/// of the configuration, see `radicle_common::config`.
+pub const RAD_EDITOR: &str = "RAD_EDITOR";
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad.exe";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Get the editor command, with its arguments.
+pub fn editor() -> Vec<String> {
+    // Nb. The configuration includes `RAD_EDITOR`.
+    let var = |name: &str| env::var(name).ok();
+    let command = Config::current()
+        .ok()
+        .and_then(|c| c.editor.clone())
+        .or_else(|| var("VISUAL"))
+        .or_else(|| var("EDITOR"))
+        .filter(|c| !c.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_owned());
+
+    split(&command)
+}
+
+/// Open a file in the user's editor, and wait for the editor to exit.
+pub fn open(path: &Path) -> io::Result<()> {
+    let editor = editor();
+    let (program, args) = editor
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no editor is configured"))?;
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("could not run editor `{}`: {}", editor.join(" "), err),
+            )
+        })?;
+
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("editor `{}` exited with {}", editor.join(" "), status),
+        ));
+    }
+    Ok(())
+}
+
+/// Split a command into words, on whitespace outside of quotes.
+fn split(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Lets the user edit text in their editor, through a temporary file.
+pub struct Editor {
+    extension: String,
+    require_save: bool,
+    trim_newlines: bool,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self {
+            extension: ".txt".to_owned(),
+            require_save: true,
+            trim_newlines: true,
+        }
+    }
+
+    /// Set the extension of the file edited, eg. for syntax highlighting.
+    pub fn extension(&mut self, extension: &str) -> &mut Self {
+        self.extension = extension.to_owned();
+        self
+    }
+
+    /// Whether the text must be saved for the edit to count. If not saved, the edit is
+    /// cancelled.
+    pub fn require_save(&mut self, require_save: bool) -> &mut Self {
+        self.require_save = require_save;
+        self
+    }
+
+    /// Whether to remove trailing newlines from the edited text.
+    pub fn trim_newlines(&mut self, trim_newlines: bool) -> &mut Self {
+        self.trim_newlines = trim_newlines;
+        self
+    }
+
+    /// Edit the given text. Returns `None` if the edit was cancelled.
+    pub fn edit(&self, text: &str) -> io::Result<Option<String>> {
+        let mut file = tempfile::Builder::new()
+            .prefix("edit-")
+            .suffix(&self.extension)
+            .rand_bytes(12)
+            .tempfile()?;
+        file.write_all(text.as_bytes())?;
+        file.flush()?;
+
+        let modified = fs::metadata(file.path())?.modified()?;
+
+        open(file.path())?;
+
+        if self.require_save && fs::metadata(file.path())?.modified()? <= modified {
+            return Ok(None);
+        }
+        let mut edited = fs::read_to_string(file.path())?;
+
+        if self.trim_newlines {
+            let len = edited.trim_end_matches(&['\r', '\n'][..]).len();
+            edited.truncate(len);
+        }
+        Ok(Some(edited))
+    }
+}
@@ -23,7 +23,7 @@ where
         let spinner = term::spinner("Decrypting keystore...");
         let signer = LocalWallet::decrypt_keystore(keypath, password.unsecure())
             // Nb. Can fail if the file isn't found.
-            .map_err(|e| anyhow!("keystore decryption failed: {}", e))?
+            .map_err(|e| anyhow!(term::tr!("keystore decryption failed: {}", e)))?
             .with_chain_id(chain_id);
 
         spinner.finish();
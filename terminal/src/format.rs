@@ -1,5 +1,92 @@
+use std::env;
+use std::str::FromStr;
+
 pub use dialoguer::console::style;
 
+use dialoguer::console;
+
+use crate::ascii;
+use crate::output;
+
+/// Environment var that sets when to color output, so that it is inherited by the
+/// commands `rad` runs. Set by the `--color` option of `rad`.
+pub const RAD_COLOR: &str = "RAD_COLOR";
+/// Environment var that disables colors when set to a non-empty value, in `auto` mode.
+/// See <https://no-color.org>.
+pub const NO_COLOR: &str = "NO_COLOR";
+
+/// When to color output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Color {
+    /// Color output written to a terminal, unless `NO_COLOR` is set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+
+    /// Get the color mode, as set with `rad --color`.
+    pub fn get() -> Self {
+        env::var(RAD_COLOR)
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(Self::Auto)
+    }
+
+    /// Set the color mode, of this process and the commands it runs.
+    pub fn set(self) {
+        env::set_var(RAD_COLOR, self.as_str());
+    }
+}
+
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => Err(anyhow::anyhow!(
+                "unknown color mode '{}': expected `auto`, `always` or `never`",
+                other
+            )),
+        }
+    }
+}
+
+/// Decide whether to color output, according to the color mode. All styling, eg. of the
+/// helpers below, is plain text when colors are disabled. Called before running commands.
+pub fn init_colors() {
+    let (stdout, stderr) = match Color::get() {
+        Color::Always => (true, true),
+        Color::Never => (false, false),
+        Color::Auto if env::var_os(NO_COLOR).map_or(false, |v| !v.is_empty()) => (false, false),
+        Color::Auto => (
+            console::Term::stdout().features().colors_supported(),
+            console::Term::stderr().features().colors_supported(),
+        ),
+    };
+    // In JSON mode, human output goes to stderr, though it is styled for stdout.
+    let stdout = if output::is_json() { stderr } else { stdout };
+
+    console::set_colors_enabled(stdout);
+    console::set_colors_enabled_stderr(stderr);
+}
+
+/// Whether output is colored.
+pub fn colors_enabled() -> bool {
+    console::colors_enabled()
+}
+
 pub fn negative<D: std::fmt::Display>(msg: D) -> String {
     style(msg).red().bright().to_string()
 }
@@ -28,23 +115,30 @@ pub fn highlight<D: std::fmt::Display>(input: D) -> String {
     style(input).green().bright().to_string()
 }
 
+/// Text of a badge: padded, or in brackets with the ASCII fallback, as some limited
+/// terminals don't show reverse video.
+fn badge<D: std::fmt::Display>(input: D) -> String {
+    if ascii::is_enabled() {
+        format!("[{}]", input)
+    } else {
+        format!(" {} ", input)
+    }
+}
+
 pub fn badge_primary<D: std::fmt::Display>(input: D) -> String {
-    style(format!(" {} ", input))
-        .magenta()
-        .reverse()
-        .to_string()
+    style(badge(input)).magenta().reverse().to_string()
 }
 
 pub fn badge_positive<D: std::fmt::Display>(input: D) -> String {
-    style(format!(" {} ", input)).green().reverse().to_string()
+    style(badge(input)).green().reverse().to_string()
 }
 
 pub fn badge_negative<D: std::fmt::Display>(input: D) -> String {
-    style(format!(" {} ", input)).red().reverse().to_string()
+    style(badge(input)).red().reverse().to_string()
 }
 
 pub fn badge_secondary<D: std::fmt::Display>(input: D) -> String {
-    style(format!(" {} ", input)).blue().reverse().to_string()
+    style(badge(input)).blue().reverse().to_string()
 }
 
 pub fn bold<D: std::fmt::Display>(input: D) -> String {
@@ -0,0 +1,154 @@
+//! Fuzzy selection, to pick an item of a long list by typing part of it, eg. a patch
+//! by a few letters of its title instead of its full id.
+use std::fmt;
+use std::io;
+
+use console::{Key, Term};
+
+use crate::io::{is_interactive, theme};
+use crate::tr;
+
+/// Maximum number of matching options shown at once.
+const PAGE_SIZE: usize = 10;
+
+/// Let the user pick one of the options, by typing a query that fuzzy-matches it, and
+/// moving through the matches with the arrow keys. Returns the index of the picked
+/// option, or `None` if the user cancels, or there is no terminal to prompt on.
+pub fn fuzzy_select<T>(prompt: &str, options: &[T]) -> Option<usize>
+where
+    T: fmt::Display,
+{
+    if !is_interactive() {
+        return None;
+    }
+    let items = options.iter().map(|o| o.to_string()).collect::<Vec<_>>();
+    let term = Term::stderr();
+    let result = select(&term, prompt, &items);
+
+    term.show_cursor().ok();
+    result.ok().flatten()
+}
+
+fn select(term: &Term, prompt: &str, items: &[String]) -> io::Result<Option<usize>> {
+    let theme = theme();
+    let mut query = String::new();
+    let mut cursor = 0;
+    let mut drawn = 0;
+
+    term.hide_cursor()?;
+
+    let picked = loop {
+        let matches = matches(&query, items);
+        cursor = cursor.min(matches.len().saturating_sub(1));
+
+        if drawn > 0 {
+            term.clear_last_lines(drawn)?;
+        }
+        term.write_line(&format!(
+            "{} {} {} {}",
+            theme.prompt_prefix,
+            theme.prompt_style.apply_to(prompt),
+            theme.prompt_suffix,
+            query
+        ))?;
+        drawn = 1;
+
+        if matches.is_empty() {
+            term.write_line(&format!(
+                "  {}",
+                theme.error_style.apply_to(tr!("No matches"))
+            ))?;
+            drawn += 1;
+        }
+        let offset = (cursor + 1).saturating_sub(PAGE_SIZE);
+        for (i, ix) in matches.iter().enumerate().skip(offset).take(PAGE_SIZE) {
+            let item = &items[*ix];
+
+            if i == cursor {
+                term.write_line(&format!(
+                    "{} {}",
+                    theme.active_item_prefix,
+                    theme
+                        .active_item_style
+                        .apply_to(console::strip_ansi_codes(item))
+                ))?;
+            } else {
+                term.write_line(&format!("{} {}", theme.inactive_item_prefix, item))?;
+            }
+            drawn += 1;
+        }
+
+        match term.read_key()? {
+            Key::Enter => {
+                if let Some(ix) = matches.get(cursor) {
+                    break Some(*ix);
+                }
+            }
+            Key::Escape => break None,
+            Key::ArrowUp => cursor = cursor.saturating_sub(1),
+            // Nb. The cursor is kept within the matches when drawing.
+            Key::ArrowDown => cursor += 1,
+            Key::Backspace => {
+                query.pop();
+                cursor = 0;
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                cursor = 0;
+            }
+            _ => {}
+        }
+    };
+    term.clear_last_lines(drawn)?;
+
+    if let Some(ix) = picked {
+        term.write_line(&format!(
+            "{} {} {} {}",
+            theme.success_prefix,
+            theme.prompt_style.apply_to(prompt),
+            theme.success_suffix,
+            items[ix]
+        ))?;
+    }
+    Ok(picked)
+}
+
+/// Indices of the items matching the query, best matches first. All items match an
+/// empty query, in their original order.
+fn matches(query: &str, items: &[String]) -> Vec<usize> {
+    let mut scored = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| score(query, &console::strip_ansi_codes(item)).map(|s| (s, i)))
+        .collect::<Vec<_>>();
+    scored.sort_by(|(a, i), (b, j)| b.cmp(a).then(i.cmp(j)));
+
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Score how well a text matches a query, if at all. The characters of the query must
+/// appear in the text in the same order, ignoring case and whitespace. Runs of
+/// consecutive characters, and characters at the start of words, score higher.
+fn score(query: &str, text: &str) -> Option<i64> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|c| *c == q)?;
+
+        score += 1;
+        match last {
+            Some(last) if found == last + 1 => score += 5,
+            Some(last) => score -= (found - last - 1).min(5) as i64,
+            None => {}
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
@@ -0,0 +1,34 @@
+use librad::git::Urn;
+
+use radicle_common::exit;
+use radicle_common::hooks::{self, Event};
+use radicle_common::json;
+use radicle_common::notify;
+use radicle_common::profile::Profile;
+
+use crate as term;
+
+/// Notify the webhooks and chat channels of the profile of an event, warning about
+/// failed deliveries. Notifications never cause the command that emitted the event to fail,
+/// and are skipped in offline mode.
+pub fn emit(profile: &Profile, event: Event, project: &Urn, data: json::Value) {
+    for err in notify::emit(profile, event, project, &data) {
+        if is_offline(&err) {
+            term::warning(&term::tr!("Notification of {} skipped: {}", event, err));
+        } else {
+            term::warning(&term::tr!("Notification of {} failed: {}", event, err));
+        }
+    }
+    for err in hooks::emit(profile, event, project, data) {
+        if is_offline(&err) {
+            term::warning(&term::tr!("Webhooks for {} skipped: {}", event, err));
+        } else {
+            term::warning(&term::tr!("Webhook for {} failed: {}", event, err));
+        }
+    }
+}
+
+/// Whether a notification wasn't delivered because offline mode is on.
+fn is_offline(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref(), Some(exit::Error::Offline(_)))
+}
@@ -0,0 +1,237 @@
+//! Translation of user-facing messages.
+//!
+//! Messages are looked up by their English text in the catalog of the user's locale,
+//! which is selected from `LC_ALL`, `LC_MESSAGES` or `LANG`, in that order, as with
+//! other command-line programs. Messages missing from a catalog are shown in English,
+//! so that translations can be completed over time.
+//!
+//! The catalog is loaded once per process, on the first lookup.
+//!
+//! The output functions and macros of this crate, eg. `success!`, `warning`, `spinner`,
+//! prompts, usage and errors, translate the messages they are given. Messages built
+//! with arguments must be built with [`tr!`] instead of `format!`, so that the message
+//! is looked up before its arguments are filled in.
+//!
+//! To add a language, add a [`Locale`], and a catalog mapping English messages to their
+//! translation. Messages with arguments use `{}` placeholders, filled in order.
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+use once_cell::sync::OnceCell;
+
+/// Catalog of the user's locale.
+static CATALOG: OnceCell<Catalog> = OnceCell::new();
+
+/// Translate a message, with `{}` placeholders filled with the given arguments, eg.
+/// `tr!("Pushing to {}...", seed)`. Without arguments, a `&'static str` is returned,
+/// otherwise a `String`.
+#[macro_export]
+macro_rules! tr {
+    ($msg:literal) => {
+        $crate::i18n::tr($msg)
+    };
+    ($msg:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format(
+            $crate::i18n::tr($msg),
+            &[$(&$arg as &dyn std::fmt::Display),+],
+        )
+    };
+}
+
+/// A language the CLI is translated to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    German,
+}
+
+impl Locale {
+    /// Get the locale of the user, from the environment.
+    pub fn get() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|val| !val.is_empty())
+            .map_or(Self::English, |val| Self::from_env(&val))
+    }
+
+    /// Get the locale from the value of a locale variable, eg. `de_DE.UTF-8`.
+    fn from_env(val: &str) -> Self {
+        let language = val
+            .split(|c| c == '_' || c == '-' || c == '.' || c == '@')
+            .next()
+            .unwrap_or_default();
+
+        match language {
+            "de" => Self::German,
+            _ => Self::English,
+        }
+    }
+
+    fn messages(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::English => &[],
+            Self::German => GERMAN,
+        }
+    }
+}
+
+/// Translations of English messages to a locale.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    messages: HashMap<&'static str, &'static str>,
+}
+
+impl Catalog {
+    /// Load the catalog of a locale.
+    pub fn new(locale: Locale) -> Self {
+        Self {
+            messages: locale.messages().iter().copied().collect(),
+        }
+    }
+
+    /// Translate a message, or return it as is if it has no translation.
+    pub fn get<'a>(&self, msg: &'a str) -> &'a str {
+        self.messages.get(msg).copied().unwrap_or(msg)
+    }
+}
+
+/// Translate a message to the user's locale. Messages that aren't in the catalog, eg.
+/// messages built at runtime, are returned as is.
+pub fn tr(msg: &str) -> &str {
+    CATALOG.get_or_init(|| Catalog::new(Locale::get())).get(msg)
+}
+
+/// Fill the `{}` placeholders of a message with the given arguments, in order.
+pub fn format(msg: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut output = String::new();
+    let mut args = args.iter();
+    let mut parts = msg.split("{}");
+
+    if let Some(first) = parts.next() {
+        output.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            output.push_str(&arg.to_string());
+        }
+        output.push_str(part);
+    }
+    output
+}
+
+const GERMAN: &[(&str, &str)] = &[
+    // Errors, warnings and prompts.
+    ("Error:", "Fehler:"),
+    ("Warning:", "Warnung:"),
+    ("{} failed", "{} fehlgeschlagen"),
+    (
+        "Error: rad-{}: invalid usage",
+        "Fehler: rad-{}: ungültige Verwendung",
+    ),
+    (
+        "a confirmation is needed, but the terminal isn't interactive: {}",
+        "eine Bestätigung ist nötig, aber das Terminal ist nicht interaktiv: {}",
+    ),
+    (
+        "hint: pass `--yes` to go ahead without confirmation, eg. `rad --yes <command>`",
+        "Tipp: mit `--yes` ohne Bestätigung fortfahren, z.B. `rad --yes <Befehl>`",
+    ),
+    ("Repeat passphrase", "Passphrase wiederholen"),
+    (
+        "Error: the passphrases don't match.",
+        "Fehler: die Passphrasen stimmen nicht überein.",
+    ),
+    ("Unsealing key...", "Schlüssel wird entsperrt..."),
+    (
+        "Which comment do you want to react to?",
+        "Auf welchen Kommentar möchtest du reagieren?",
+    ),
+    ("No matches", "Keine Treffer"),
+    // Syncing.
+    ("Syncing...", "Synchronisiere..."),
+    ("Fetching from {}...", "Lade von {}..."),
+    ("Pushing to {}...", "Übertrage an {}..."),
+    ("Pushing to {}... {}", "Übertrage an {}... {}"),
+    ("Fetching from {}... {}", "Lade von {}... {}"),
+    ("{} received", "{} empfangen"),
+    (
+        "Attempt {} with {} failed, retrying in {}s...",
+        "Versuch {} mit {} fehlgeschlagen, neuer Versuch in {}s...",
+    ),
+    // Notifications.
+    (
+        "Notification of {} failed: {}",
+        "Benachrichtigung über {} fehlgeschlagen: {}",
+    ),
+    (
+        "Webhook for {} failed: {}",
+        "Webhook für {} fehlgeschlagen: {}",
+    ),
+    // Patches and issues.
+    ("YOU PROPOSED", "VON DIR VORGESCHLAGEN"),
+    ("OTHERS PROPOSED", "VON ANDEREN VORGESCHLAGEN"),
+    ("Nothing to show.", "Nichts anzuzeigen."),
+    ("No description provided.", "Keine Beschreibung angegeben."),
+    (
+        "Which patch do you want to review?",
+        "Welchen Patch möchtest du begutachten?",
+    ),
+    (
+        "Which patch do you want to merge?",
+        "Welchen Patch möchtest du zusammenführen?",
+    ),
+    (
+        "Which patch do you want to show?",
+        "Welchen Patch möchtest du anzeigen?",
+    ),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_env() {
+        assert_eq!(Locale::from_env("de_DE.UTF-8"), Locale::German);
+        assert_eq!(Locale::from_env("de"), Locale::German);
+        assert_eq!(Locale::from_env("en_US.UTF-8"), Locale::English);
+        assert_eq!(Locale::from_env("C"), Locale::English);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let catalog = Catalog::new(Locale::German);
+
+        assert_eq!(catalog.get("Warning:"), "Warnung:");
+        assert_eq!(
+            format(catalog.get("Fetching from {}..."), &[&"seed.example.com"]),
+            "Lade von seed.example.com..."
+        );
+    }
+
+    #[test]
+    fn test_fallback() {
+        let german = Catalog::new(Locale::German);
+        let english = Catalog::new(Locale::English);
+
+        assert_eq!(german.get("Not translated yet"), "Not translated yet");
+        assert_eq!(english.get("Warning:"), "Warning:");
+        assert_eq!(format("{} of {}", &[&1]), "1 of ");
+        assert_eq!(format("no placeholders", &[&1]), "no placeholders");
+    }
+
+    #[test]
+    fn test_catalog_placeholders() {
+        // Translations must fill in as many arguments as the English message.
+        for (english, translated) in GERMAN {
+            assert_eq!(
+                english.matches("{}").count(),
+                translated.matches("{}").count(),
+                "{}",
+                english
+            );
+        }
+    }
+}
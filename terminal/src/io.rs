@@ -1,4 +1,5 @@
 use std::fmt;
+use std::iter;
 use std::str::FromStr;
 
 use zeroize::Zeroizing;
@@ -9,31 +10,50 @@ use librad::profile::Profile;
 
 use dialoguer::{console::style, console::Style, theme::ColorfulTheme, Input, Password};
 
+use radicle_common::block::Blocklist;
 use radicle_common::cobs::issue::Issue;
 use radicle_common::cobs::shared::CommentId;
+use radicle_common::config::Config;
+use radicle_common::exit;
 use radicle_common::signer::ToSigner;
 
+use super::ascii;
 use super::command;
 use super::display;
 use super::format;
 use super::keys;
+use super::output;
 use super::spinner::spinner;
+use super::verbosity;
 use super::Error;
+use crate::i18n;
+use crate::tr;
 
 #[cfg(feature = "ethereum")]
 pub use super::ethereum;
 
 pub const TAB: &str = "   ";
 
+/// Environment var that makes confirmations take their default answer, instead of
+/// asking. Set by the `--yes` and `--no-confirm` options of `rad`. Read as part of the
+/// configuration, see `radicle_common::config`.
+pub const RAD_NO_CONFIRM: &str = "RAD_NO_CONFIRM";
+
 #[macro_export]
 macro_rules! info {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => ({
+        $crate::io::println_args(format_args!("{}", $crate::tr!($fmt $(, $arg)*)));
+    });
     ($($arg:tt)*) => ({
-        println!("{}", format_args!($($arg)*));
+        $crate::io::println_args(format_args!($($arg)*));
     })
 }
 
 #[macro_export]
 macro_rules! success {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => ({
+        $crate::io::success_args(format_args!("{}", $crate::tr!($fmt $(, $arg)*)));
+    });
     ($($arg:tt)*) => ({
         $crate::io::success_args(format_args!($($arg)*));
     })
@@ -41,21 +61,45 @@ macro_rules! success {
 
 #[macro_export]
 macro_rules! tip {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => ({
+        $crate::io::tip_args(format_args!("{}", $crate::tr!($fmt $(, $arg)*)));
+    });
     ($($arg:tt)*) => ({
         $crate::io::tip_args(format_args!($($arg)*));
     })
 }
 
+/// Print a line of output for humans. In JSON mode, it is printed to stderr, so that
+/// stdout only has the JSON output.
+pub fn println_args(args: fmt::Arguments) {
+    let text = args.to_string();
+    let text = ascii::fallback(&text);
+
+    if output::is_json() {
+        eprintln!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+/// Print a success message, unless quiet.
 pub fn success_args(args: fmt::Arguments) {
-    println!("{} {}", style("ok").green().reverse(), args);
+    if verbosity::is_quiet() {
+        return;
+    }
+    println_args(format_args!("{} {}", style("ok").green().reverse(), args));
 }
 
+/// Print a tip, unless quiet.
 pub fn tip_args(args: fmt::Arguments) {
-    println!(
+    if verbosity::is_quiet() {
+        return;
+    }
+    println_args(format_args!(
         "{} {}",
         style("=>").blue(),
         style(format!("{}", args)).dim()
-    );
+    ));
 }
 
 pub use info;
@@ -68,21 +112,24 @@ pub fn width() -> usize {
 }
 
 pub fn headline(headline: &str) {
-    println!();
-    println!("{}", style(headline).bold());
-    println!();
+    if verbosity::is_quiet() {
+        return;
+    }
+    blank();
+    println_args(format_args!("{}", style(i18n::tr(headline)).bold()));
+    blank();
 }
 
 pub fn blob(text: impl fmt::Display) {
-    println!("{}", style(text.to_string().trim()).dim());
+    println_args(format_args!("{}", style(text.to_string().trim()).dim()));
 }
 
 pub fn blank() {
-    println!()
+    println_args(format_args!(""))
 }
 
 pub fn print(msg: impl fmt::Display) {
-    println!("{}", msg);
+    println_args(format_args!("{}", msg));
 }
 
 pub fn prefixed(prefix: &str, text: &str) -> String {
@@ -92,46 +139,57 @@ pub fn prefixed(prefix: &str, text: &str) -> String {
 }
 
 pub fn help(name: &str, version: &str, description: &str, usage: &str) {
-    println!("rad-{} {}\n{}\n{}", name, version, description, usage);
+    println!(
+        "rad-{} {}\n{}\n{}",
+        name,
+        version,
+        i18n::tr(description),
+        i18n::tr(usage)
+    );
 }
 
 pub fn usage(name: &str, usage: &str) {
     eprintln!(
         "{} {}\n{}",
         style("==").red(),
-        style(format!("Error: rad-{}: invalid usage", name)).red(),
-        style(prefixed(TAB, usage)).red().dim()
+        style(tr!("Error: rad-{}: invalid usage", name)).red(),
+        style(prefixed(TAB, i18n::tr(usage))).red().dim()
     );
 }
 
 pub fn eprintln(prefix: impl fmt::Display, msg: impl fmt::Display) {
-    eprintln!("{} {}", prefix, msg);
+    eprintln!("{}", ascii::fallback(&format!("{} {}", prefix, msg)));
 }
 
 pub fn indented(msg: &str) {
-    println!("{}{}", TAB, msg);
+    println_args(format_args!("{}{}", TAB, msg));
 }
 
 pub fn subcommand(msg: impl fmt::Display) {
-    println!("{} {}", style("$").dim(), style(msg).dim());
+    println_args(format_args!("{} {}", style("$").dim(), style(msg).dim()));
 }
 
 pub fn warning(warning: &str) {
     eprintln!(
         "{} {} {}",
         style("**").yellow(),
-        style("Warning:").yellow().bold(),
-        style(warning).yellow()
+        style(tr!("Warning:")).yellow().bold(),
+        style(ascii::fallback(i18n::tr(warning))).yellow()
     );
 }
 
 pub fn error(error: impl fmt::Display) {
-    eprintln!("{} {}", style("==").red(), style(error).red());
+    eprintln!(
+        "{} {}",
+        style("==").red(),
+        style(ascii::fallback(i18n::tr(&error.to_string()))).red()
+    );
 }
 
+/// Print the failure of a command. Error messages without arguments are translated.
 pub fn fail(header: &str, error: &anyhow::Error) {
     let err = error.to_string();
-    let err = err.trim_end();
+    let err = ascii::fallback(i18n::tr(err.trim_end()));
     let separator = if err.len() > 160 || err.contains('\n') {
         "\n"
     } else {
@@ -143,7 +201,7 @@ pub fn fail(header: &str, error: &anyhow::Error) {
         style("==").red(),
         style(header).red().reverse(),
         separator,
-        style(error).red().bold(),
+        style(&err).red().bold(),
     );
 
     let cause = error.root_cause();
@@ -157,35 +215,87 @@ pub fn fail(header: &str, error: &anyhow::Error) {
     }
 
     if let Some(Error::WithHint { hint, .. }) = error.downcast_ref::<Error>() {
-        eprintln!("{} {}", style("==").yellow(), style(hint).yellow(),);
+        eprintln!(
+            "{} {}",
+            style("==").yellow(),
+            style(i18n::tr(hint)).yellow(),
+        );
         blank();
     }
 }
 
-pub fn ask<D: fmt::Display>(prompt: D, default: bool) -> bool {
-    dialoguer::Confirm::new()
-        .with_prompt(format!("{} {}", style(" ⤷".to_owned()).cyan(), prompt))
+/// Whether the user can be prompted, ie. whether output is shown on a terminal. In JSON
+/// mode, stdout isn't meant for the user, so only stderr, where prompts go, matters.
+pub fn is_interactive() -> bool {
+    let stdout = console::Term::stdout().features().is_attended() || output::is_json();
+
+    stdout && console::Term::stderr().features().is_attended()
+}
+
+/// Whether confirmations are skipped, with `rad --yes`, ie. `RAD_NO_CONFIRM`, or with
+/// `confirm = false` in the configuration.
+fn skip_confirmations() -> bool {
+    Config::current().map_or(false, |c| !c.confirm)
+}
+
+/// Ask a yes/no question. With `rad --yes`, the default answer is taken, which is yes
+/// for confirmations, see [`confirm`], and no for questions about destructive actions,
+/// see [`abort`]. When not running interactively, eg. in CI, an [`exit::Error::Aborted`]
+/// error is returned instead of hanging, and the caller decides whether to go on.
+pub fn ask<D: fmt::Display>(prompt: D, default: bool) -> anyhow::Result<bool> {
+    if skip_confirmations() {
+        return Ok(default);
+    }
+    if !is_interactive() {
+        return Err(Error::WithHint {
+            err: exit::Error::Aborted(tr!(
+                "a confirmation is needed, but the terminal isn't interactive: {}",
+                prompt
+            ))
+            .into(),
+            hint: tr!(
+                "hint: pass `--yes` to go ahead without confirmation, eg. `rad --yes <command>`"
+            ),
+        }
+        .into());
+    }
+    let answer = dialoguer::Confirm::new()
+        .with_prompt(format!(
+            "{} {}",
+            style(ascii::symbol(" ⤷", " >")).cyan(),
+            ascii::fallback(i18n::tr(&prompt.to_string()))
+        ))
         .wait_for_newline(false)
         .default(true)
         .default(default)
         .interact()
-        .unwrap_or_default()
+        .unwrap_or_default();
+
+    Ok(answer)
 }
 
-pub fn confirm<D: fmt::Display>(prompt: D) -> bool {
+/// Ask for confirmation, answered with yes by default.
+pub fn confirm<D: fmt::Display>(prompt: D) -> anyhow::Result<bool> {
     ask(format::tertiary(prompt), true)
 }
 
-pub fn abort<D: fmt::Display>(prompt: D) -> bool {
+/// Ask whether to take a destructive action, answered with no by default.
+pub fn abort<D: fmt::Display>(prompt: D) -> anyhow::Result<bool> {
     ask(format::tertiary(prompt), false)
 }
 
-/// Get the signer. First we try getting it from ssh-agent, otherwise we prompt the user.
+/// Get the signer. First we try getting it from ssh-agent, otherwise we unseal the secret
+/// key, see [`secret_key`].
+///
+/// ssh-agent is only used if it holds the key: if it doesn't, or can't be reached, the
+/// secret key is unsealed, eg. with the passphrase read from the standard input stream
+/// in CI jobs, which often run an agent without the key.
 pub fn signer(profile: &Profile) -> anyhow::Result<BoxedSigner> {
-    let signer = if let Ok(sock) = keys::ssh_auth_sock() {
-        sock.to_signer(profile)?
-    } else {
-        secret_key(profile)?.to_signer(profile)?
+    let signer = match keys::ssh_auth_sock() {
+        Ok(sock) if keys::is_ready(profile, sock.clone()).unwrap_or(false) => {
+            sock.to_signer(profile)?
+        }
+        _ => secret_key(profile)?.to_signer(profile)?,
     };
     Ok(signer)
 }
@@ -193,16 +303,31 @@ pub fn signer(profile: &Profile) -> anyhow::Result<BoxedSigner> {
 pub fn theme() -> ColorfulTheme {
     ColorfulTheme {
         success_prefix: style("ok".to_owned()).for_stderr().green().reverse(),
-        prompt_prefix: style(" ⤷".to_owned()).cyan().dim().for_stderr(),
-        prompt_suffix: style("·".to_owned()).cyan().for_stderr(),
+        prompt_prefix: style(ascii::symbol(" ⤷", " >").to_owned())
+            .cyan()
+            .dim()
+            .for_stderr(),
+        prompt_suffix: style(ascii::symbol("·", ":").to_owned())
+            .cyan()
+            .for_stderr(),
         prompt_style: Style::new().cyan().bold().for_stderr(),
         active_item_style: Style::new().for_stderr().yellow().reverse(),
         active_item_prefix: style("*".to_owned()).yellow().for_stderr(),
         picked_item_prefix: style("*".to_owned()).yellow().for_stderr(),
         inactive_item_prefix: style(" ".to_string()).for_stderr(),
         inactive_item_style: Style::new().yellow().for_stderr(),
-        error_prefix: style("⤹  Error:".to_owned()).red().for_stderr(),
-        success_suffix: style("·".to_owned()).cyan().for_stderr(),
+        error_prefix: style(ascii::symbol("⤹  Error:", "<  Error:").to_owned())
+            .red()
+            .for_stderr(),
+        success_suffix: style(ascii::symbol("·", ":").to_owned())
+            .cyan()
+            .for_stderr(),
+        checked_item_prefix: style(ascii::symbol("✔", "[x]").to_owned())
+            .green()
+            .for_stderr(),
+        unchecked_item_prefix: style(ascii::symbol("✔", "[ ]").to_owned())
+            .black()
+            .for_stderr(),
 
         ..ColorfulTheme::default()
     }
@@ -214,6 +339,7 @@ where
     E: fmt::Debug + fmt::Display,
 {
     let theme = theme();
+    let message = i18n::tr(message);
     let mut input: Input<S> = Input::with_theme(&theme);
 
     let value = match default {
@@ -263,6 +389,7 @@ where
     E: fmt::Debug + fmt::Display,
 {
     let theme = theme();
+    let message = i18n::tr(message);
     let mut input: Input<Optional<S>> = Input::with_theme(&theme);
 
     if let Some(init) = initial {
@@ -277,7 +404,7 @@ where
 }
 
 pub fn secret_input() -> SecUtf8 {
-    secret_input_with_prompt("Passphrase")
+    secret_input_with_prompt(tr!("Passphrase"))
 }
 
 // TODO: This prompt shows success just for entering a password,
@@ -287,17 +414,24 @@ pub fn secret_input_with_prompt(prompt: &str) -> SecUtf8 {
     SecUtf8::from(
         Password::with_theme(&theme())
             .allow_empty_password(true)
-            .with_prompt(prompt)
+            .with_prompt(i18n::tr(prompt))
             .interact()
             .unwrap(),
     )
 }
 
 pub fn secret_input_with_confirmation() -> SecUtf8 {
+    secret_input_with_confirmation_prompt(tr!("Passphrase"))
+}
+
+pub fn secret_input_with_confirmation_prompt(prompt: &str) -> SecUtf8 {
     SecUtf8::from(
         Password::with_theme(&theme())
-            .with_prompt("Passphrase")
-            .with_confirmation("Repeat passphrase", "Error: the passphrases don't match.")
+            .with_prompt(i18n::tr(prompt))
+            .with_confirmation(
+                tr!("Repeat passphrase"),
+                tr!("Error: the passphrases don't match."),
+            )
             .interact()
             .unwrap(),
     )
@@ -334,9 +468,12 @@ pub fn read_passphrase_from_env_var() -> Result<SecUtf8, anyhow::Error> {
     Ok(SecUtf8::from(input.trim_end()))
 }
 
+/// Unseal the secret key of the profile. The passphrase is read from `RAD_PASSPHRASE` if set,
+/// or else from the standard input stream if there is no terminal to prompt on, eg. in
+/// scripts and CI jobs. Otherwise, the user is prompted.
 pub fn secret_key(profile: &Profile) -> Result<keys::signer::ZeroizingSecretKey, anyhow::Error> {
-    let passphrase = secret_input();
-    let spinner = spinner("Unsealing key..."); // Nb. Spinner ends when dropped.
+    let passphrase = read_passphrase(!console::user_attended_stderr(), false)?;
+    let spinner = spinner(tr!("Unsealing key...")); // Nb. Spinner ends when dropped.
     let key = keys::load_secret_key(profile, passphrase)?;
 
     spinner.finish();
@@ -370,7 +507,7 @@ where
     let theme = theme();
     let active = options.iter().position(|o| o == active);
     let mut selection = dialoguer::Select::with_theme(&theme);
-    selection.with_prompt(prompt);
+    selection.with_prompt(i18n::tr(prompt));
 
     if let Some(active) = active {
         selection.default(active);
@@ -383,6 +520,20 @@ where
     result.map(|i| &options[i])
 }
 
+/// Let the user pick any number of options. Returns the indices of the picked options.
+/// Returns no options if the user cancels.
+pub fn multi_select<T>(prompt: &str, options: &[T]) -> Vec<usize>
+where
+    T: fmt::Display,
+{
+    dialoguer::MultiSelect::with_theme(&theme())
+        .with_prompt(i18n::tr(prompt))
+        .items(&options.iter().map(|o| o.to_string()).collect::<Vec<_>>())
+        .interact_opt()
+        .unwrap()
+        .unwrap_or_default()
+}
+
 pub fn profile_select<'a>(profiles: &'a [Profile], active: &Profile) -> Option<&'a Profile> {
     let active = profiles.iter().position(|p| p.id() == active.id()).unwrap();
     let selection = dialoguer::Select::with_theme(&theme())
@@ -399,32 +550,43 @@ pub fn profile_select<'a>(profiles: &'a [Profile], active: &Profile) -> Option<&
     selection.map(|i| &profiles[i])
 }
 
-pub fn comment_select(issue: &Issue) -> Option<CommentId> {
-    let selection = dialoguer::Select::with_theme(&theme())
-        .with_prompt("Which comment do you want to react to?")
-        .item(&issue.description().to_string())
-        .items(
-            &issue
+/// Select a comment of an issue, or its description. Comments of blocked peers are
+/// left out.
+pub fn comment_select(issue: &Issue, blocklist: &Blocklist) -> Option<CommentId> {
+    // Indices of the selectable comments, the description being the root comment.
+    let (ids, items): (Vec<_>, Vec<_>) = iter::once((0, issue.description().to_owned()))
+        .chain(
+            issue
                 .comments()
                 .iter()
-                .map(|p| p.body.clone())
-                .collect::<Vec<_>>(),
+                .enumerate()
+                .filter(|(_, c)| !blocklist.contains(&c.author.peer))
+                .map(|(ix, c)| (ix + 1, c.body.clone())),
         )
+        .unzip();
+    let selection = dialoguer::Select::with_theme(&theme())
+        .with_prompt(tr!("Which comment do you want to react to?"))
+        .items(&items)
         .default(CommentId::root().into())
         .interact_opt()
         .unwrap();
 
-    selection.map(CommentId::from)
+    selection.map(|i| CommentId::from(ids[i]))
 }
 
 pub fn markdown(content: &str) {
     if !content.is_empty() {
-        if command::bat(["-p", "-l", "md"], content).is_err() {
+        let color = if format::colors_enabled() {
+            "--color=auto"
+        } else {
+            "--color=never"
+        };
+        if command::bat(["-p", "-l", "md", color], content).is_err() {
             blob(content);
         }
     }
 }
 
 fn _info(args: std::fmt::Arguments) {
-    println!("{}", args);
+    println_args(args);
 }
@@ -1,30 +1,46 @@
 #![allow(clippy::collapsible_if)]
 
+pub mod ascii;
 pub mod command;
+pub mod csv;
+pub mod diff;
 pub mod display;
+pub mod editor;
 #[cfg(feature = "ethereum")]
 pub mod ethereum;
 pub mod format;
+pub mod fuzzy;
+pub mod hooks;
+pub mod i18n;
 pub mod io;
 pub mod keys;
+pub mod output;
 pub mod patch;
+pub mod progress;
 pub mod remote;
 pub mod spinner;
 pub mod sync;
 pub mod table;
 pub mod textbox;
+pub mod verbosity;
 
 use std::ffi::OsString;
 use std::process;
+use std::time::Duration;
 
 use dialoguer::console::style;
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::exit;
 use radicle_common::profile;
 use radicle_common::profile::Profile;
+use radicle_common::timing;
 
 pub use console::measure_text_width as text_width;
-pub use dialoguer::Editor;
+pub use csv::Csv;
+pub use editor::Editor;
+pub use fuzzy::fuzzy_select;
 pub use io::*;
+pub use progress::{transfer, Transfer};
 pub use spinner::{spinner, Spinner};
 pub use table::Table;
 pub use textbox::TextBox;
@@ -82,6 +98,9 @@ where
 {
     use crate::io as term;
 
+    format::init_colors();
+    verbosity::init_logger();
+
     let options = match A::from_args(args) {
         Ok((opts, unparsed)) => {
             if let Err(err) = radicle_common::args::finish(unparsed) {
@@ -105,7 +124,7 @@ where
             eprintln!(
                 "{} {} {} {}",
                 style("==").red(),
-                style("Error:").red(),
+                style(crate::tr!("Error:")).red(),
                 style(format!("rad-{}:", help.name)).red(),
                 style(&err).red()
             );
@@ -118,11 +137,44 @@ where
         }
     };
 
-    match cmd.run(options, profile::default) {
+    let result = cmd.run(options, profile::default);
+
+    if timing::is_enabled() {
+        print_timings();
+    }
+    match result {
         Ok(()) => process::exit(0),
         Err(err) => {
-            term::fail(&format!("{} failed", action), &err);
-            process::exit(1);
+            term::fail(&crate::tr!("{} failed", action), &err);
+            process::exit(exit::code(&err).into());
         }
     }
 }
+
+/// Print where the time of a command was spent, for `rad debug profile`. Nb. this is
+/// printed to stderr, so that the command's output can still be piped.
+fn print_timings() {
+    let elapsed = timing::elapsed();
+    let timings = timing::timings();
+    let measured = timings.iter().map(|t| t.time).sum::<Duration>();
+
+    eprintln!();
+    eprintln!(
+        "{} {}",
+        style("Profile").bold(),
+        style(format!("{:.3}s in total", elapsed.as_secs_f64())).dim()
+    );
+    for t in &timings {
+        eprintln!(
+            "  {:<10}{:>9.3}s {}",
+            t.category.as_str(),
+            t.time.as_secs_f64(),
+            style(format!("in {} call(s)", t.calls)).dim()
+        );
+    }
+    eprintln!(
+        "  {:<10}{:>9.3}s",
+        "other",
+        elapsed.saturating_sub(measured).as_secs_f64()
+    );
+}
@@ -0,0 +1,56 @@
+//! Output mode of commands.
+//!
+//! With the global `--json` option of `rad`, commands print their result as JSON to
+//! stdout, for scripts to consume, while all messages meant for humans, eg. progress or
+//! tips, go to stderr. Commands that don't support JSON output are unaffected, other
+//! than their messages going to stderr.
+use std::env;
+
+use radicle_common::json;
+
+/// Environment var that sets the output mode, so that it is inherited by the commands
+/// `rad` runs. Set by the `--json` option of `rad`.
+pub const RAD_OUTPUT: &str = "RAD_OUTPUT";
+
+/// Output mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Output for humans, on stdout.
+    Human,
+    /// JSON output on stdout, and output for humans on stderr.
+    Json,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Get the output mode.
+pub fn mode() -> Mode {
+    match env::var(RAD_OUTPUT).as_deref() {
+        Ok("json") => Mode::Json,
+        _ => Mode::Human,
+    }
+}
+
+/// Set the output mode, of this process and the commands it runs.
+pub fn set(mode: Mode) {
+    env::set_var(RAD_OUTPUT, mode.as_str());
+}
+
+/// Whether commands should print JSON.
+pub fn is_json() -> bool {
+    mode() == Mode::Json
+}
+
+/// Print the JSON result of a command to stdout.
+pub fn json(value: &json::Value) -> anyhow::Result<()> {
+    println!("{}", json::to_string_pretty(value)?);
+
+    Ok(())
+}
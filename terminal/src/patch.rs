@@ -1,4 +1,10 @@
+use std::path::Path;
+
+use librad::git::storage::Storage;
+use librad::git::Urn;
+
 use radicle_common as common;
+use radicle_common::cobs::patch::{Patch, PatchId, PatchStore, Revision};
 use radicle_common::git;
 
 use crate as term;
@@ -48,6 +54,38 @@ impl Default for Comment {
     }
 }
 
+/// Let the user pick one of the open patches of a project, by fuzzy-matching its id,
+/// title or author. Returns `None` if the user cancels, or there is no terminal to
+/// prompt on.
+pub fn select(
+    prompt: &str,
+    patches: &PatchStore,
+    project: &Urn,
+    storage: &Storage,
+) -> anyhow::Result<Option<(PatchId, Patch)>> {
+    let mut proposed = patches.proposed(project)?.collect::<Vec<_>>();
+    if proposed.is_empty() {
+        anyhow::bail!("there are no open patches");
+    }
+    proposed.sort_by(|(_, a), (_, b)| b.timestamp.cmp(&a.timestamp));
+
+    let items = proposed
+        .iter_mut()
+        .map(|(id, patch)| {
+            patch.author.resolve(storage).ok();
+
+            format!(
+                "{} {} {}",
+                term::format::tertiary(common::fmt::cob(id)),
+                patch.title,
+                term::format::dim(patch.author.name()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(term::fuzzy_select(prompt, &items).map(|i| proposed.swap_remove(i)))
+}
+
 /// List the given commits in a table.
 pub fn list_commits(commits: &[git::Commit]) -> anyhow::Result<()> {
     let mut table = term::Table::default();
@@ -66,6 +104,21 @@ pub fn list_commits(commits: &[git::Commit]) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Print the changes of a patch revision, from its base to its head, as found in the
+/// given repository, eg. the monorepo.
+pub fn print_diff(repo: &Path, revision: &Revision) -> anyhow::Result<()> {
+    let diff = git::git(
+        repo,
+        [
+            "diff".to_owned(),
+            format!("{}..{}", revision.base, revision.oid),
+        ],
+    )?;
+    term::diff::print(&diff);
+
+    Ok(())
+}
+
 /// Print commits ahead and behind.
 pub fn print_commits_ahead_behind(
     repo: &git::Repository,
@@ -0,0 +1,87 @@
+use std::time::Instant;
+
+use dialoguer::console::style;
+use indicatif::{HumanBytes, ProgressBar, ProgressFinish, ProgressStyle};
+
+use radicle_common::git;
+
+use crate as term;
+
+/// Progress bar of a git transfer: objects received, then deltas resolved, along with
+/// the bytes received and the transfer rate.
+pub struct Transfer {
+    progress: ProgressBar,
+    message: String,
+    start: Instant,
+    objects: usize,
+    bytes: usize,
+}
+
+impl Transfer {
+    pub fn update(&mut self, p: &git::TransferProgress) {
+        self.objects = p.received_objects;
+        self.bytes = p.received_bytes;
+
+        if p.total_deltas > 0 && p.received_objects == p.total_objects {
+            self.progress.set_length(p.total_deltas as u64);
+            self.progress.set_position(p.indexed_deltas as u64);
+            self.progress
+                .set_message(format!("deltas, {}", self.stats()));
+        } else {
+            self.progress.set_length(p.total_objects as u64);
+            self.progress.set_position(p.received_objects as u64);
+            self.progress
+                .set_message(format!("objects, {}", self.stats()));
+        }
+    }
+
+    pub fn finish(&self) {
+        self.progress.finish_and_clear();
+
+        if self.objects > 0 {
+            term::success!(
+                "{} {}",
+                &self.message,
+                term::format::dim(format!("({} objects, {})", self.objects, self.stats()))
+            );
+        } else {
+            term::success!("{}", &self.message);
+        }
+    }
+
+    pub fn error(self, err: anyhow::Error) -> anyhow::Error {
+        self.progress.finish_and_clear();
+        term::eprintln(style("!!").red().reverse(), style(&err).red());
+
+        err
+    }
+
+    /// Bytes received and transfer rate.
+    fn stats(&self) -> String {
+        let elapsed = self.start.elapsed().as_secs_f64().max(0.001);
+        let rate = (self.bytes as f64 / elapsed) as u64;
+
+        format!("{}, {}/s", HumanBytes(self.bytes as u64), HumanBytes(rate))
+    }
+}
+
+pub fn transfer(message: impl ToString) -> Transfer {
+    let message = message.to_string();
+    let style = ProgressStyle::default_bar()
+        .template("{prefix} [{bar:30.yellow}] {pos}/{len} {msg}")
+        .progress_chars("=> ")
+        .on_finish(ProgressFinish::AndClear);
+
+    let progress = ProgressBar::new(0);
+    progress.set_style(style);
+    progress.set_prefix(message.clone());
+    progress.enable_steady_tick(250);
+
+    Transfer {
+        progress,
+        message,
+        start: Instant::now(),
+        objects: 0,
+        bytes: 0,
+    }
+}
@@ -3,17 +3,20 @@ use std::collections::HashSet;
 use librad::git::storage::Storage;
 use librad::git::Urn;
 
-use radicle_common::{git, project};
+use radicle_common::alias::Aliases;
+use radicle_common::{exit, git, project};
 
 use crate as term;
 
 pub fn list(storage: &Storage, repo: &git::Repository, urn: &Urn) -> anyhow::Result<()> {
     let mut table = term::Table::default();
-    let proj = project::get(&storage, urn)?
-        .ok_or_else(|| anyhow::anyhow!("project {} not found on local device", urn))?;
+    let proj = project::get(&storage, urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found on local device", urn))
+    })?;
     let mut peers = HashSet::new();
+    let aliases = Aliases::load(repo);
 
-    for (_, peer) in git::remotes(repo)? {
+    for (name, peer) in git::remotes(repo)? {
         if !peers.insert(peer) {
             // Don't show duplicate peers.
             continue;
@@ -25,14 +28,27 @@ pub fn list(storage: &Storage, repo: &git::Repository, urn: &Urn) -> anyhow::Res
             String::new()
         };
 
-        if let Some(person) = project::person(&storage, urn.clone(), &peer)? {
+        if let Some(alias) = aliases.get(&peer) {
             table.push([
-                term::format::bold(person.subject().name.to_string()),
+                term::format::bold(name),
+                term::format::highlight(alias),
+                term::format::tertiary(peer),
+                delegate,
+            ]);
+        } else if let Some(person) = project::person(&storage, urn.clone(), &peer)? {
+            table.push([
+                term::format::bold(name),
+                person.subject().name.to_string(),
                 term::format::tertiary(peer),
                 delegate,
             ]);
         } else {
-            table.push([String::new(), term::format::tertiary(peer), delegate]);
+            table.push([
+                term::format::bold(name),
+                String::new(),
+                term::format::tertiary(peer),
+                delegate,
+            ]);
         }
     }
     table.render();
@@ -56,7 +72,10 @@ pub fn remove(
             );
         }
         None => {
-            anyhow::bail!("remote '{}' not found", name)
+            anyhow::bail!(exit::Error::NotFound(term::tr!(
+                "remote '{}' not found",
+                name
+            )))
         }
     }
 
@@ -25,7 +25,10 @@ impl Spinner {
 
     pub fn done(self) {
         self.progress.finish_and_clear();
-        term::info!("{}", &self.message);
+
+        if !term::verbosity::is_quiet() {
+            term::info!("{}", &self.message);
+        }
     }
 
     pub fn failed(mut self) {
@@ -44,9 +47,11 @@ impl Spinner {
     }
 
     pub fn message(&mut self, msg: impl Into<String>) {
-        let msg = msg.into();
+        let msg: String = msg.into();
+        let msg = term::i18n::tr(&msg).to_owned();
 
-        self.progress.set_message(msg.clone());
+        self.progress
+            .set_message(term::ascii::fallback(&msg).into_owned());
         self.message = msg;
     }
 
@@ -56,8 +61,26 @@ impl Spinner {
     }
 }
 
+/// Create a spinner, drawn on stderr. When stderr isn't a terminal, eg. in CI, the
+/// spinner is hidden, and its message is printed as a plain line instead. When quiet,
+/// the spinner is hidden, and nothing is printed.
 pub fn spinner(message: impl ToString) -> Spinner {
-    let message = message.to_string();
+    let message = term::i18n::tr(&message.to_string()).to_owned();
+
+    if term::verbosity::is_quiet() {
+        return Spinner {
+            message,
+            progress: ProgressBar::hidden(),
+        };
+    }
+    if !console::Term::stderr().features().is_attended() {
+        term::info!("{}", message);
+
+        return Spinner {
+            message,
+            progress: ProgressBar::hidden(),
+        };
+    }
     let style = ProgressStyle::default_spinner()
         .tick_strings(&[
             &style("\\ ").yellow().to_string(),
@@ -71,7 +94,7 @@ pub fn spinner(message: impl ToString) -> Spinner {
     let progress = ProgressBar::new(!0);
     progress.set_style(style);
     progress.enable_steady_tick(99);
-    progress.set_message(message.clone());
+    progress.set_message(term::ascii::fallback(&message).into_owned());
 
     Spinner { message, progress }
 }
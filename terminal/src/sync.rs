@@ -1,14 +1,22 @@
 use std::convert::TryInto;
-use std::time;
+
+use indicatif::HumanBytes;
 
 use librad::git::Urn;
 
 use radicle_common as common;
+use radicle_common::alias::Aliases;
+use radicle_common::block::Blocklist;
+use radicle_common::exit;
+use radicle_common::git;
 use radicle_common::nonempty::NonEmpty;
+use radicle_common::offline;
 use radicle_common::profile::Profile;
 use radicle_common::signer::ToSigner;
 use radicle_common::sync;
+use radicle_common::sync::scope::Scopes;
 use radicle_common::sync::SyncResult;
+use radicle_common::timing;
 
 use crate as term;
 
@@ -20,29 +28,197 @@ pub fn sync(
     signer: impl ToSigner,
     rt: &common::tokio::runtime::Runtime,
 ) -> anyhow::Result<NonEmpty<SyncResult>> {
+    let settings = sync::Settings::load(profile);
+
+    sync_with(urn, seeds, mode, settings, profile, signer, rt)
+}
+
+/// Get the seeds to sync a project with, see [`sync::seeds`]. Configured seeds that are
+/// invalid are skipped, with a warning.
+pub fn seeds(profile: &Profile, urn: &Urn) -> anyhow::Result<NonEmpty<sync::Seed<String>>> {
+    sync::seeds_with(profile, urn, |err| {
+        term::warning(&term::tr!("{}, skipping", err));
+    })
+}
+
+/// Like [`sync`], with the given settings instead of the profile's.
+pub fn sync_with(
+    urn: Urn,
+    seeds: NonEmpty<sync::Seed<String>>,
+    mode: sync::Mode,
+    settings: sync::Settings,
+    profile: &Profile,
+    signer: impl ToSigner,
+    rt: &common::tokio::runtime::Runtime,
+) -> anyhow::Result<NonEmpty<SyncResult>> {
+    // Nb. Checked before getting the signer, which may ask for a passphrase.
+    offline::check("syncing")?;
+
     let signer = signer.to_signer(profile)?;
-    let timeout = time::Duration::from_secs(9);
-    let spinner = term::spinner("Syncing...");
-    let result = rt.block_on(async {
-        let (seeds, _errors) = sync::Seeds::resolve(seeds.iter()).await;
-        let client = sync::client(signer, profile).await?;
-        let result = sync::sync(&client, urn, seeds, mode, timeout).await;
-
-        Ok::<Vec<SyncResult>, anyhow::Error>(result)
+    let blocklist = Blocklist::load(profile)?;
+
+    // Refuse the data of blocked peers, including for projects that weren't fetched yet.
+    if !blocklist.is_empty() {
+        let storage = common::keys::storage(profile, signer.clone())?;
+        blocklist.track(&storage, &urn)?;
+    }
+    let aliases = Aliases::cwd();
+    let mut spinner = term::spinner(term::tr!("Syncing..."));
+    let result = timing::measure(timing::Category::Network, || {
+        rt.block_on(async {
+            let (seeds, _errors) = sync::Seeds::resolve(seeds.iter()).await;
+            let client = sync::client(signer, profile).await?;
+            let result = sync::sync(&client, profile, urn.clone(), seeds, mode, settings, |p| {
+                spinner.message(progress(p, &aliases))
+            })
+            .await;
+
+            Ok::<Vec<SyncResult>, anyhow::Error>(result)
+        })
     })?;
 
     let results = if let Ok(results) = result.try_into() {
         results
     } else {
-        return Err(anyhow::anyhow!(
-            "No seeds attempted: all seeds failed to resolve"
-        ));
+        return Err(exit::Error::Network(
+            "No seeds attempted: all seeds failed to resolve".to_owned(),
+        )
+        .into());
     };
 
+    // Drop the refs of blocked peers, in case they were fetched from a seed that didn't
+    // honour the tracking config, as well as refs that are out of their peer's fetch scope,
+    // since the tracking config only restricts which kinds of refs are replicated.
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    blocklist.prune(&monorepo, &urn)?;
+    Scopes::load(profile, &urn)?.prune(&monorepo, &urn)?;
+
     match mode {
-        sync::Mode::Push | sync::Mode::All => spinner.finish(),
+        sync::Mode::Push | sync::Mode::All => {
+            spinner.message(term::tr!("Syncing..."));
+            spinner.finish()
+        }
         sync::Mode::Fetch => spinner.clear(),
     }
 
     Ok(results)
 }
+
+/// Format a sync progress event.
+fn progress(progress: sync::Progress, aliases: &Aliases) -> String {
+    match progress {
+        sync::Progress::Fetching(seed) => {
+            term::tr!(
+                "Fetching from {}...",
+                term::format::tertiary(aliases.name(&seed.peer))
+            )
+        }
+        sync::Progress::Pushing(seed) => {
+            term::tr!(
+                "Pushing to {}...",
+                term::format::tertiary(aliases.name(&seed.peer))
+            )
+        }
+        sync::Progress::Retrying(seed, attempt, delay) => {
+            term::tr!(
+                "Attempt {} with {} failed, retrying in {}s...",
+                attempt,
+                term::format::tertiary(aliases.name(&seed.peer)),
+                format!("{:.1}", delay.as_secs_f32())
+            )
+        }
+        sync::Progress::Message(seed, msg) => {
+            term::tr!(
+                "Pushing to {}... {}",
+                term::format::tertiary(aliases.name(&seed.peer)),
+                term::format::dim(msg)
+            )
+        }
+        sync::Progress::Received(seed, bytes) => {
+            term::tr!(
+                "Fetching from {}... {}",
+                term::format::tertiary(aliases.name(&seed.peer)),
+                term::format::dim(term::tr!("{} received", HumanBytes(bytes)))
+            )
+        }
+    }
+}
+
+/// Print the outcome of a sync: the refs updated with each seed, followed by a summary table.
+pub fn report(results: &NonEmpty<SyncResult>) {
+    let mut table = term::Table::default();
+    let aliases = Aliases::cwd();
+
+    for result in results.iter() {
+        let peer = aliases.name(&result.seed.peer);
+        let refs = result.refs();
+
+        if let Some(Err(err)) = &result.fetch {
+            term::eprintln(
+                term::format::negative("!!"),
+                format!("fetch {} {}", peer, term::format::negative(err)),
+            );
+        }
+        if let Some(Err(err)) = &result.push {
+            term::eprintln(
+                term::format::negative("!!"),
+                format!("push {} {}", peer, term::format::negative(err)),
+            );
+        }
+        for rejected in result.rejected() {
+            term::eprintln(
+                term::format::negative("✗"),
+                format!(
+                    "fetch {} {}",
+                    peer,
+                    term::format::negative(format!("rejected: {}", rejected))
+                ),
+            );
+        }
+        for r in &refs {
+            let target = match &r.oid {
+                Some(oid) => term::format::secondary(format!("{:.7}", oid)),
+                None => term::format::dim("(pruned)"),
+            };
+            let direction = match r.direction {
+                sync::Direction::Fetch => "fetch",
+                sync::Direction::Push => "push",
+            };
+            term::info!(
+                "{} {} {} {}",
+                term::format::positive("✓"),
+                term::format::dim(direction),
+                term::format::tertiary(&r.name),
+                target
+            );
+        }
+
+        let count = |direction| refs.iter().filter(|r| r.direction == direction).count();
+        table.push([
+            if result.is_success() {
+                term::format::positive("ok")
+            } else {
+                term::format::negative("!!")
+            },
+            term::format::tertiary(peer),
+            status(&result.fetch, count(sync::Direction::Fetch), "fetched"),
+            status(&result.push, count(sync::Direction::Push), "pushed"),
+            if result.attempts > 1 {
+                term::format::dim(format!("{} attempts", result.attempts))
+            } else {
+                String::new()
+            },
+        ]);
+    }
+    term::blank();
+    table.render();
+}
+
+/// Format the status of a sync operation with a seed.
+fn status<T, E>(result: &Option<Result<T, E>>, refs: usize, verb: &str) -> String {
+    match result {
+        Some(Ok(_)) => format!("{} ref(s) {}", refs, verb),
+        Some(Err(_)) => term::format::negative("failed"),
+        None => term::format::dim("-"),
+    }
+}
@@ -2,9 +2,15 @@ use std::fmt::Write;
 
 use crate as term;
 
+/// Minimum width a column is truncated to, to fit the terminal.
+const MIN_TRUNCATED_WIDTH: usize = 12;
+
 #[derive(Debug, Default)]
 pub struct TableOptions {
     pub overflow: bool,
+    /// Column truncated with an ellipsis when rows are wider than the terminal, eg. a
+    /// title. Otherwise, rows are cut off at the terminal width.
+    pub truncate: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -38,43 +44,121 @@ impl<const W: usize> Table<W> {
         self.rows.push(row);
     }
 
-    pub fn render(self) {
+    /// Number of rows that weren't rendered yet.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Render the table, with columns padded to their widest cell, and numeric columns
+    /// aligned to the right. When the output isn't a terminal, eg. when piped, rows are
+    /// rendered as is, with cells separated by tabs.
+    pub fn render(mut self) {
+        self.flush();
+    }
+
+    /// Render the rows pushed so far, and remove them from the table, so that long
+    /// listings can be rendered while their next rows are computed. Column widths are
+    /// kept, so rendered rows only line up with the next ones if those aren't wider.
+    pub fn flush(&mut self) {
+        self.render_rows();
+        self.rows.clear();
+    }
+
+    fn render_rows(&self) {
+        if !console::Term::stdout().is_term() {
+            for row in &self.rows {
+                term::print(row.join("\t"));
+            }
+            return;
+        }
         let width = term::width(); // Terminal width.
+        let mut widths = self.widths;
+        let alignments = self.alignments();
+
+        if let Some(col) = self.opts.truncate.filter(|c| *c < W) {
+            // Nb. Columns are separated by a space, and the last terminal column is left free.
+            let total = widths.iter().sum::<usize>() + W;
+
+            if total > width {
+                widths[col] = widths[col]
+                    .saturating_sub(total - width)
+                    .max(MIN_TRUNCATED_WIDTH.min(widths[col]));
+            }
+        }
 
         for row in &self.rows {
             let mut output = String::new();
             let cells = row.len();
 
             for (i, cell) in row.iter().enumerate() {
-                if i == cells - 1 || self.opts.overflow {
+                let cell = if self.opts.truncate == Some(i) {
+                    console::truncate_str(cell, widths[i], "…")
+                } else {
+                    cell.into()
+                };
+
+                if self.opts.overflow
+                    || (i == cells - 1 && alignments[i] == console::Alignment::Left)
+                {
                     write!(output, "{}", cell).ok();
                 } else {
                     write!(
                         output,
                         "{} ",
-                        console::pad_str(cell, self.widths[i], console::Alignment::Left, None)
+                        console::pad_str(&cell, widths[i], alignments[i], None)
                     )
                     .ok();
                 }
             }
-            println!("{}", console::truncate_str(&output, width - 1, "…"));
+            term::print(console::truncate_str(
+                output.trim_end(),
+                width.saturating_sub(1),
+                "…",
+            ));
+        }
+    }
+
+    /// Alignment of each column: to the right if all its cells are numbers.
+    fn alignments(&self) -> [console::Alignment; W] {
+        let mut alignments = [console::Alignment::Left; W];
+
+        for (i, alignment) in alignments.iter_mut().enumerate() {
+            let numeric = !self.rows.is_empty()
+                && self.rows.iter().all(|row| {
+                    let cell = console::strip_ansi_codes(&row[i]);
+                    let cell = cell.trim();
+
+                    !cell.is_empty() && cell.parse::<f64>().is_ok()
+                });
+            if numeric {
+                *alignment = console::Alignment::Right;
+            }
         }
+        alignments
     }
 
     pub fn render_tree(self) {
         for (r, row) in self.rows.iter().enumerate() {
+            let mut output = String::new();
+
             if r != self.rows.len() - 1 {
-                print!("├── ");
+                output.push_str("├── ");
             } else {
-                print!("└── ");
+                output.push_str("└── ");
             }
             for (i, cell) in row.iter().enumerate() {
-                print!(
+                write!(
+                    output,
                     "{} ",
                     console::pad_str(cell, self.widths[i], console::Alignment::Left, None)
-                );
+                )
+                .ok();
             }
-            println!();
+            term::print(output);
         }
     }
 }
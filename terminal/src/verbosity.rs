@@ -0,0 +1,112 @@
+//! Verbosity of commands, set with the global `-q` and `-v` options of `rad`.
+//!
+//! Quiet commands only print their results, warnings and errors: progress, successes
+//! and tips are left out. Verbose commands also log to stderr, including the radicle
+//! libraries, whose `tracing` events are forwarded as logs when there is no subscriber:
+//! `-v` shows info logs, `-vv` debug logs, and `-vvv` trace logs, eg. of the transport
+//! while syncing.
+use std::env;
+use std::io::{self, Write};
+
+use dialoguer::console::style;
+
+/// Environment var that sets the verbosity, so that it is inherited by the commands
+/// `rad` runs: `-1` when quiet, or the number of `-v` options given.
+pub const RAD_VERBOSITY: &str = "RAD_VERBOSITY";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    /// Verbose, at the given level, from `1`.
+    Verbose(u8),
+}
+
+impl Verbosity {
+    /// Get the verbosity of this process.
+    pub fn get() -> Self {
+        match env::var(RAD_VERBOSITY)
+            .ok()
+            .and_then(|v| v.parse::<i16>().ok())
+        {
+            Some(v) if v < 0 => Self::Quiet,
+            Some(v) if v > 0 => Self::Verbose(v.min(u8::MAX as i16) as u8),
+            _ => Self::Normal,
+        }
+    }
+
+    /// Set the verbosity, of this process and the commands it runs.
+    pub fn set(self) {
+        let level = match self {
+            Self::Quiet => -1,
+            Self::Normal => 0,
+            Self::Verbose(n) => n as i16,
+        };
+        env::set_var(RAD_VERBOSITY, level.to_string());
+    }
+
+    /// One level more verbose, eg. for each `-v` option.
+    pub fn more(self) -> Self {
+        match self {
+            Self::Verbose(n) => Self::Verbose(n.saturating_add(1)),
+            _ => Self::Verbose(1),
+        }
+    }
+
+    /// Level of the logs shown, if any.
+    pub fn log_level(self) -> Option<log::LevelFilter> {
+        match self {
+            Self::Quiet | Self::Normal => None,
+            Self::Verbose(1) => Some(log::LevelFilter::Info),
+            Self::Verbose(2) => Some(log::LevelFilter::Debug),
+            Self::Verbose(_) => Some(log::LevelFilter::Trace),
+        }
+    }
+}
+
+/// Whether messages other than results, warnings and errors should be left out.
+pub fn is_quiet() -> bool {
+    Verbosity::get() == Verbosity::Quiet
+}
+
+/// Log to stderr, if verbose.
+pub fn init_logger() {
+    if let Some(level) = Verbosity::get().log_level() {
+        if log::set_boxed_logger(Box::new(Logger { level })).is_ok() {
+            log::set_max_level(level);
+        }
+    }
+}
+
+struct Logger {
+    level: log::LevelFilter,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            log::Level::Error => style("ERROR").red(),
+            log::Level::Warn => style("WARN ").yellow(),
+            log::Level::Info => style("INFO ").blue(),
+            log::Level::Debug => style("DEBUG").dim(),
+            log::Level::Trace => style("TRACE").dim(),
+        };
+        eprintln!(
+            "{} {} {}",
+            level.for_stderr(),
+            style(record.target()).dim().for_stderr(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        io::stderr().flush().ok();
+    }
+}
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::path::Path;
 
 use anyhow::anyhow;
-use anyhow::Context as _;
 
 use librad::crypto::BoxedSigner;
 use librad::git::storage::{ReadOnly, Storage};
@@ -10,12 +10,18 @@ use librad::git::tracking;
 use librad::profile::Profile;
 use librad::PeerId;
 
-use radicle_common::args::Help;
+use radicle_common::alias::{self, Aliases};
+use radicle_common::args::{self, Help};
+use radicle_common::block::Blocklist;
+use radicle_common::cobs::issue;
+use radicle_common::config::Config;
+use radicle_common::json;
 use radicle_common::nonempty::NonEmpty;
-use radicle_common::project::PeerInfo;
+use radicle_common::project::{PeerInfo, TrackingPolicy};
+use radicle_common::sync::scope::{self, Scope};
 use radicle_common::tokio;
 use radicle_common::Url;
-use radicle_common::{git, keys, project, seed, sync, Urn};
+use radicle_common::{cobs, exit, git, keys, project, seed, sync, Urn};
 use radicle_terminal as term;
 
 mod options;
@@ -45,19 +51,52 @@ Usage
 
     rad track           [--local | --remote]
     rad track           [--seed <url>]
-    rad track <peer-id> [--seed <url>] [--no-sync] [--no-upstream] [--no-fetch]
+    rad track           --suggestions [--seed <url>]
+    rad track           --policy <delegates-only | all | manual>
+    rad track           --list [--verbose]
+    rad track <peer-id> [--seed <url>] [--alias <name>] [--scope <refs>]... [--no-sync] [--no-upstream] [--no-fetch]
 
     If a peer id is supplied, track this peer in the context of the current project. By default,
     a remote is created in the repository and an upstream tracking branch is setup. If a seed
     is supplied as well, the seed will be associated with this peer in the local git configuration.
+    If an alias is supplied, it is stored in the local git configuration, and shown instead of
+    the peer id wherever the peer is displayed in the context of this project.
+
+    With `--scope`, only the given refs of the peer are fetched when syncing, to limit the
+    growth of storage from prolific forks. The scope is either `default` (the project's default
+    branch), `branches`, `tags`, `cobs`, or a ref pattern relative to the peer, eg. `heads/dev`
+    or `heads/release/*`, and can be given multiple times. The scope `all` fetches all refs again,
+    which is the default. Scopes are stored with the peer's tracking entry, so that only the kinds
+    of refs in scope are replicated, and in the profile's `scopes.toml`.
 
     If no peer id is supplied, show the local or remote tracking graph of the current project.
 
+    With `rad --json`, tracking a peer prints the outcome as a JSON object, and `--list`
+    prints the tracked peers as a JSON array.
+
+    With `--suggestions`, the seed is queried for the peers that published refs for the current
+    project, and that you don't track yet. You can then pick which of them to track. If no seed
+    is supplied, the default seed of your profile is used.
+
+    With `--list`, the tracked peers of the project are listed, with their alias or name, and
+    whether they are delegates. With `--verbose`, the time of each peer's last ref update, as
+    well as the number of open patches and issues they authored, are shown as well.
+
+    With `--policy`, the tracking policy of the project is set, which decides whose refs are
+    fetched when syncing: the delegates and explicitly tracked peers (`delegates-only`, the
+    default), all peers (`all`), or only explicitly tracked peers (`manual`). Switching away
+    from `all` stops tracking all peers.
+
 Options
 
     --local                Show the local project tracking graph
     --remote               Show the remote project tracking graph from a seed
     --seed <url>           Seed URL to fetch refs from
+    --alias <name>         Name to show for the peer, instead of its id
+    --scope <refs>         Refs of the peer to fetch (default: all)
+    --suggestions          Discover untracked peers on the seed, and pick which to track
+    --policy <policy>      Set the tracking policy of the project
+    --list                 List the tracked peers of the project
     --no-upstream          Don't setup a tracking branch for the remote
     --no-sync              Don't sync the peer's refs
     --no-fetch             Don't fetch the peer's refs into the working copy
@@ -71,12 +110,18 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let signer = term::signer(&profile)?;
     let storage = keys::storage(&profile, signer.clone())?;
 
-    let (urn, repo) =
-        project::cwd().context("this command must be run in the context of a project")?;
-    let proj = project::get(&storage, &urn)?
-        .ok_or_else(|| anyhow!("project {} not found in local storage", &urn))?;
-
-    if let Some(peer) = options.peer {
+    let (urn, repo) = project::cwd()?;
+    let proj = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("project {} not found in local storage", &urn))
+    })?;
+
+    if let Some(policy) = options.policy {
+        set_policy(policy, proj, storage)?;
+    } else if options.list {
+        list(proj, repo, storage, profile, options)?;
+    } else if options.suggestions {
+        suggest(proj, repo, storage, profile, options)?;
+    } else if let Some(peer) = options.peer {
         // Track peer.
         track(peer, proj, repo, storage, profile, signer, options)?;
     } else {
@@ -87,6 +132,35 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub fn set_policy(
+    policy: TrackingPolicy,
+    project: project::Metadata,
+    storage: Storage,
+) -> anyhow::Result<()> {
+    policy.set(Path::new("."))?;
+
+    let applied = policy.apply(&storage, &project)?;
+    if applied.untracked_all {
+        term::success!("Tracking relationship with all peers removed");
+    }
+    for peer in applied.tracked {
+        match peer {
+            Some(peer) => term::success!(
+                "Tracking relationship with {} established",
+                term::format::tertiary(peer)
+            ),
+            None => term::success!("Tracking relationship with all peers established"),
+        }
+    }
+    term::success!(
+        "Tracking policy of {} set to {}",
+        term::format::highlight(&project.name),
+        term::format::highlight(policy)
+    );
+
+    Ok(())
+}
+
 pub fn track(
     peer: PeerId,
     project: project::Metadata,
@@ -99,6 +173,13 @@ pub fn track(
     if &peer == storage.peer_id() {
         anyhow::bail!("you can't track yourself");
     }
+    if Blocklist::load(&profile)?.contains(&peer) {
+        return Err(args::Error::WithHint {
+            err: anyhow!(term::tr!("peer {} is blocked", peer)),
+            hint: "hint: to unblock the peer, run `rad block --remove <peer-id>`",
+        }
+        .into());
+    }
     let urn = &project.urn;
 
     term::info!(
@@ -123,15 +204,42 @@ pub fn track(
         if existing { "exists" } else { "established" },
     );
 
+    if let Some(alias) = &options.alias {
+        alias::set(Path::new("."), &peer, alias)?;
+        term::success!(
+            "Alias {} set for {}",
+            term::format::highlight(alias),
+            term::format::tertiary(radicle_common::fmt::peer(&peer))
+        );
+    }
+
+    if !options.scope.is_empty() {
+        let scope = fetch_scope(&options.scope, &project.default_branch)?;
+
+        scope::set(&profile, &storage, urn, &peer, &scope)?;
+        if scope.patterns().is_empty() {
+            term::success!(
+                "All refs of {} will be fetched",
+                term::format::tertiary(radicle_common::fmt::peer(&peer))
+            );
+        } else {
+            term::success!(
+                "Fetch scope of {} set to {}",
+                term::format::tertiary(radicle_common::fmt::peer(&peer)),
+                term::format::highlight(scope.patterns().join(", "))
+            );
+        }
+    }
+
     if options.sync {
         let seeds = if let Some(addr) = &options.seed {
             let seed = addr
                 .clone()
                 .try_into()
-                .map_err(|e| anyhow!("invalid seed specified: {}", e))?;
+                .map_err(|e| anyhow!(term::tr!("invalid seed specified: {}", e)))?;
             NonEmpty::new(seed)
         } else {
-            sync::seeds(&profile)?
+            term::sync::seeds(&profile, urn)?
         };
 
         let rt = tokio::runtime::Runtime::new()?;
@@ -150,7 +258,7 @@ pub fn track(
         let seed = addr
             .clone()
             .try_into()
-            .map_err(|e| anyhow!("invalid seed specified: {}", e))?;
+            .map_err(|e| anyhow!(term::tr!("invalid seed specified: {}", e)))?;
 
         seed::set_peer_seed(&seed, &peer)?;
         term::success!(
@@ -159,6 +267,7 @@ pub fn track(
         );
     }
 
+    let mut upstream = None;
     if options.upstream {
         let name = if let Some(person) = project::person(&storage, urn.clone(), &peer)? {
             person.subject().name.to_string()
@@ -177,14 +286,282 @@ pub fn track(
         .run(&peer, &name, &profile)?;
 
         if let Some((remote, branch)) = branch {
-            term::success!("Remote {} set", term::format::highlight(remote.name),);
+            let (remote, branch) = (remote.name.to_string(), branch.to_string());
+
+            term::success!("Remote {} set", term::format::highlight(&remote),);
             term::success!(
                 "Remote-tracking branch {} set",
-                term::format::highlight(branch),
+                term::format::highlight(&branch),
+            );
+            upstream = Some((remote, branch));
+        }
+    }
+
+    if term::output::is_json() {
+        term::output::json(&json::json!({
+            "project": urn.to_string(),
+            "peerId": peer.to_string(),
+            "existing": existing,
+            "alias": options.alias,
+            "remote": upstream.as_ref().map(|(remote, _)| remote),
+            "branch": upstream.as_ref().map(|(_, branch)| branch),
+        }))?;
+    }
+    Ok(())
+}
+
+/// Resolve the given scopes to a fetch scope. The `all` scope results in an empty scope.
+fn fetch_scope(values: &[String], default_branch: &str) -> anyhow::Result<Scope> {
+    let mut patterns = Vec::new();
+
+    for value in values {
+        let pattern = match value.as_str() {
+            "all" => return Ok(Scope::default()),
+            "default" => format!("heads/{}", default_branch),
+            "branches" => "heads/*".to_owned(),
+            "tags" => "tags/*".to_owned(),
+            "cobs" => "cobs/*".to_owned(),
+            pattern if pattern.contains('/') => pattern.to_owned(),
+            other => {
+                return Err(args::Error::WithHint {
+                    err: anyhow!(term::tr!("invalid scope '{}'", other)),
+                    hint: "hint: scopes are `default`, `branches`, `tags`, `cobs`, `all`, or ref patterns, eg. `heads/dev`",
+                }
+                .into())
+            }
+        };
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    Ok(Scope::new(patterns))
+}
+
+/// List the tracked peers of a project.
+pub fn list(
+    project: project::Metadata,
+    repo: git::Repository,
+    storage: Storage,
+    profile: Profile,
+    options: Options,
+) -> anyhow::Result<()> {
+    let aliases = Aliases::load(&repo);
+    let mut tracked = project::tracked(&project, &storage)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    tracked.sort_by_key(|(id, _)| *id);
+
+    if tracked.is_empty() {
+        if term::output::is_json() {
+            return term::output::json(&json::json!([]));
+        }
+        term::info!("{}", term::format::italic("No peers tracked."));
+        term::tip!("Discover peers to track with `rad track --suggestions`.");
+
+        return Ok(());
+    }
+
+    // Authors of open patches and issues.
+    let (patches, issues) = if options.verbose {
+        let cobs = cobs::store(&profile, &storage)?;
+        let patches = cobs
+            .patches()
+            .proposed(&project.urn)?
+            .map(|(_, p)| p.author.peer)
+            .collect::<Vec<_>>();
+        let issues = cobs
+            .issues()
+            .all(&project.urn)?
+            .into_iter()
+            .filter(|(_, i)| i.state() == issue::State::Open)
+            .map(|(_, i)| i.author().peer)
+            .collect::<Vec<_>>();
+
+        (patches, issues)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let mut table = term::Table::default();
+    let mut output = Vec::new();
+
+    for (id, info) in tracked {
+        if term::output::is_json() {
+            let mut entry = json::json!({
+                "peerId": id.to_string(),
+                "alias": aliases.get(&id),
+                "name": info.person.as_ref().map(|p| p.name.as_str()),
+                "delegate": info.delegate,
+            });
+            if options.verbose {
+                entry["updated"] =
+                    json::json!(last_update(&monorepo, &project.urn, &id).map(|t| t.as_secs()));
+                entry["openPatches"] = json::json!(patches.iter().filter(|p| **p == id).count());
+                entry["openIssues"] = json::json!(issues.iter().filter(|p| **p == id).count());
+            }
+            output.push(entry);
+
+            continue;
+        }
+        let name = if let Some(alias) = aliases.get(&id) {
+            term::format::highlight(alias)
+        } else if let Some(person) = &info.person {
+            term::format::bold(&person.name)
+        } else {
+            String::new()
+        };
+        let delegate = if info.delegate {
+            term::format::badge_primary("delegate")
+        } else {
+            String::new()
+        };
+
+        if options.verbose {
+            let updated = match last_update(&monorepo, &project.urn, &id) {
+                Some(time) => format!("updated {}", time),
+                None => "never updated".to_owned(),
+            };
+            table.push([
+                name,
+                term::format::tertiary(id),
+                delegate,
+                term::format::dim(updated),
+                format!("{} patch(es)", patches.iter().filter(|p| **p == id).count()),
+                format!("{} issue(s)", issues.iter().filter(|p| **p == id).count()),
+            ]);
+        } else {
+            table.push([
+                name,
+                term::format::tertiary(id),
+                delegate,
+                String::new(),
+                String::new(),
+                String::new(),
+            ]);
+        }
+    }
+    if term::output::is_json() {
+        term::output::json(&json::Value::Array(output))?;
+    } else {
+        table.render();
+    }
+
+    Ok(())
+}
+
+/// Get the time of a peer's last ref update, ie. the time its signed refs were last
+/// updated.
+fn last_update(monorepo: &git::Repository, urn: &Urn, peer: &PeerId) -> Option<cobs::Timestamp> {
+    let name = format!(
+        "refs/namespaces/{}/refs/remotes/{}/rad/signed_refs",
+        urn.encode_id(),
+        peer
+    );
+    let commit = monorepo.find_reference(&name).ok()?.peel_to_commit().ok()?;
+    let seconds = commit.time().seconds().max(0) as u64;
+
+    // Clamp to the present, in case of clock skew.
+    Some(cobs::Timestamp::new(
+        seconds.min(cobs::Timestamp::now().as_secs()),
+    ))
+}
+
+/// Suggest peers to track, among the peers that published refs on the seed.
+pub fn suggest(
+    project: project::Metadata,
+    repo: git::Repository,
+    storage: Storage,
+    profile: Profile,
+    options: Options,
+) -> anyhow::Result<()> {
+    let seed = if let Some(seed) = &options.seed {
+        if !matches!(seed.protocol, seed::Protocol::Git { .. }) {
+            anyhow::bail!(
+                "invalid seed specified with `--seed`: must start with `http` or `https`"
             );
         }
+        seed.url()
+    } else if let Some(seed) = Config::load(&profile)
+        .ok()
+        .and_then(|c| c.default_seed().cloned())
+    {
+        seed.git
+    } else {
+        anyhow::bail!("a seed node must be specified with `--seed`");
+    };
+    let tracked = project::tracked(&project, &storage)?;
+    let blocklist = Blocklist::load(&profile)?;
+
+    let spinner = term::spinner(&term::tr!(
+        "Querying peers of {} on {}...",
+        term::format::highlight(&project.name),
+        term::format::dim(seed.host_str().unwrap_or_default()),
+    ));
+    let peers = show_remote(&project, &repo, &seed)?
+        .into_iter()
+        .filter(|p| {
+            &p.id != storage.peer_id() && !tracked.contains_key(&p.id) && !blocklist.contains(&p.id)
+        })
+        .collect::<Vec<_>>();
+    spinner.finish();
+
+    if peers.is_empty() {
+        term::info!(
+            "{}",
+            term::format::italic("No untracked peers found on the seed.")
+        );
+        return Ok(());
     }
 
+    let mut table = term::Table::default();
+    let mut items = Vec::new();
+    for peer in &peers {
+        let name = peer.meta.as_ref().map(|m| m.name()).unwrap_or_default();
+        let head = peer
+            .branches
+            .iter()
+            .find(|b| b.name == project.default_branch)
+            .or_else(|| peer.branches.first());
+
+        table.push([
+            term::format::bold(&name),
+            term::format::tertiary(radicle_common::fmt::peer(&peer.id)),
+            if peer.meta.as_ref().map_or(false, |m| m.delegate) {
+                term::format::badge_primary("delegate")
+            } else {
+                String::new()
+            },
+            term::format::dim(format!("{} branch(es)", peer.branches.len())),
+            head.map(|b| term::format::italic(&b.message))
+                .unwrap_or_default(),
+        ]);
+        items.push(format!("{} {}", radicle_common::fmt::peer(&peer.id), name));
+    }
+    term::blank();
+    table.render();
+    term::blank();
+
+    let selected = term::multi_select("Which peers do you want to track?", &items);
+    if selected.is_empty() {
+        return Ok(());
+    }
+    for i in selected {
+        let peer = &peers[i];
+
+        tracking::track(
+            &storage,
+            &project.urn,
+            Some(peer.id),
+            tracking::config::Config::default(),
+            tracking::policy::Track::Any,
+        )??;
+        term::success!(
+            "Tracking relationship with {} established",
+            term::format::tertiary(radicle_common::fmt::peer(&peer.id))
+        );
+    }
+    term::tip!("Run `rad sync --fetch-peers` to fetch the refs of the peers you track.");
+
     Ok(())
 }
 
@@ -215,7 +592,7 @@ pub fn show(
             );
         }
 
-        let spinner = term::spinner(&format!(
+        let spinner = term::spinner(&term::tr!(
             "{} {} {}",
             term::format::highlight(&project.name),
             &project.urn,
@@ -232,11 +609,17 @@ pub fn show(
         return Ok(());
     }
 
+    let aliases = Aliases::load(&repo);
+
     // TODO: Deterministic ordering of peers when printed.
     for (i, peer) in peers.iter().enumerate() {
         let you = &peer.id == storage.peer_id();
         let mut header = vec![term::format::bold(peer.id)];
 
+        if let Some(alias) = aliases.get(&peer.id) {
+            header.push(term::format::highlight(alias));
+        }
+
         if let Some(meta) = &peer.meta {
             if let Some(name) = meta.person.as_ref().map(|p| &p.name) {
                 header.push(term::format::tertiary(name));
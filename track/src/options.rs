@@ -6,8 +6,10 @@ use anyhow::Context as _;
 
 use librad::PeerId;
 
-use radicle_common::args::{Args, Error};
+use radicle_common::args::{self, Args, Error};
+use radicle_common::project::TrackingPolicy;
 use radicle_common::seed;
+use radicle_common::sync;
 
 /// Tool options.
 #[derive(Debug)]
@@ -18,6 +20,11 @@ pub struct Options {
     pub fetch: bool,
     pub local: bool,
     pub seed: Option<seed::Address>,
+    pub alias: Option<String>,
+    pub scope: Vec<String>,
+    pub suggestions: bool,
+    pub policy: Option<TrackingPolicy>,
+    pub list: bool,
     pub verbose: bool,
 }
 
@@ -29,10 +36,15 @@ impl Args for Options {
         let mut peer: Option<PeerId> = None;
         let mut local: Option<bool> = None;
         let mut upstream = true;
-        let mut sync = true;
+        let mut sync = sync::is_auto();
         let mut fetch = true;
         let mut verbose = false;
         let mut seed = None;
+        let mut alias = None;
+        let mut scope = Vec::new();
+        let mut suggestions = false;
+        let mut policy = None;
+        let mut list = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -51,6 +63,25 @@ impl Args for Options {
 
                     seed = Some(seed::Address::from_str(value)?);
                 }
+                Long("alias") => {
+                    let value = parser.value()?;
+                    let value = value.to_string_lossy();
+
+                    if value.trim().is_empty() {
+                        return Err(anyhow!("invalid alias: must not be empty"));
+                    }
+                    alias = Some(value.trim().to_owned());
+                }
+                Long("scope") => {
+                    let value = parser.value()?;
+                    scope.push(value.to_string_lossy().trim().to_owned());
+                }
+                Long("suggestions") => suggestions = true,
+                Long("list") => list = true,
+                Long("policy") => {
+                    let value = parser.value()?;
+                    policy = Some(args::parse_value("policy", value)?);
+                }
                 Long("sync") => sync = true,
                 Long("local") => local = Some(true),
                 Long("remote") => local = Some(false),
@@ -77,6 +108,30 @@ impl Args for Options {
             }
         }
 
+        if alias.is_some() && peer.is_none() {
+            return Err(anyhow!("`--alias` requires a <peer-id> to be specified"));
+        }
+
+        if !scope.is_empty() && peer.is_none() {
+            return Err(anyhow!("`--scope` requires a <peer-id> to be specified"));
+        }
+
+        if suggestions && peer.is_some() {
+            return Err(anyhow!("`--suggestions` cannot be used with a <peer-id>"));
+        }
+
+        if policy.is_some() && (peer.is_some() || suggestions) {
+            return Err(anyhow!(
+                "`--policy` cannot be used with a <peer-id> or `--suggestions`"
+            ));
+        }
+
+        if list && (peer.is_some() || suggestions || policy.is_some()) {
+            return Err(anyhow!(
+                "`--list` cannot be used with a <peer-id>, `--suggestions` or `--policy`"
+            ));
+        }
+
         // If a seed is specified, and `--local` isn't, we assume remote.
         // Otherwise, we assume local.
         let local = if let Some(local) = local {
@@ -93,6 +148,11 @@ impl Args for Options {
                 upstream,
                 local,
                 seed,
+                alias,
+                scope,
+                suggestions,
+                policy,
+                list,
                 verbose,
             },
             vec![],
@@ -0,0 +1,717 @@
+//! State of the TUI, its rendering, and the handling of keys.
+use std::cmp;
+
+use console::Key;
+
+use librad::git::storage::Storage;
+
+use radicle_common::block::Blocklist;
+use radicle_common::cobs::issue::{self, CloseReason, Issue, IssueId};
+use radicle_common::cobs::patch::{Patch, PatchId};
+use radicle_common::cobs::{Author, Comment, Identifier, Replies, Store, Timestamp};
+use radicle_common::profile::Profile;
+use radicle_common::{fmt, git, project};
+use radicle_terminal as term;
+
+use crate::screen::Screen;
+
+/// Maximum number of commits of the default branch shown in the activity tab.
+const MAX_COMMITS: usize = 50;
+/// Rows taken by the header and the footer.
+const CHROME_ROWS: usize = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Tab {
+    Patches,
+    Issues,
+    Activity,
+}
+
+impl Tab {
+    const ALL: [Tab; 3] = [Tab::Patches, Tab::Issues, Tab::Activity];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Patches => "Patches",
+            Self::Issues => "Issues",
+            Self::Activity => "Activity",
+        }
+    }
+
+    fn ix(&self) -> usize {
+        *self as usize
+    }
+
+    fn next(&self) -> Self {
+        Self::ALL[(self.ix() + 1) % Self::ALL.len()]
+    }
+}
+
+/// A patch or an issue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Target {
+    Patch(PatchId),
+    Issue(IssueId),
+}
+
+/// An event shown in the activity tab.
+struct Event {
+    timestamp: Timestamp,
+    kind: &'static str,
+    summary: String,
+    author: String,
+    /// Patch or issue the event is about, if any.
+    target: Option<Target>,
+}
+
+enum View {
+    /// List of the items of the current tab.
+    List,
+    /// A single patch or issue.
+    Detail {
+        target: Target,
+        lines: Vec<String>,
+        scroll: usize,
+    },
+}
+
+pub struct App<'a> {
+    profile: &'a Profile,
+    storage: &'a Storage,
+    cobs: &'a Store<'a>,
+    project: project::Metadata,
+    blocklist: Blocklist,
+
+    tab: Tab,
+    /// Selected item of each tab.
+    selected: [usize; 3],
+    view: View,
+    /// Outcome of the last action.
+    status: String,
+
+    patches: Vec<(PatchId, Patch)>,
+    issues: Vec<(IssueId, Issue)>,
+    activity: Vec<Event>,
+}
+
+impl<'a> App<'a> {
+    pub fn new(
+        profile: &'a Profile,
+        storage: &'a Storage,
+        cobs: &'a Store<'a>,
+        project: project::Metadata,
+    ) -> anyhow::Result<Self> {
+        let mut app = Self {
+            profile,
+            storage,
+            cobs,
+            project,
+            blocklist: Blocklist::load(profile)?,
+            tab: Tab::Patches,
+            selected: [0; 3],
+            view: View::List,
+            status: String::new(),
+            patches: Vec::new(),
+            issues: Vec::new(),
+            activity: Vec::new(),
+        };
+        app.reload()?;
+
+        Ok(app)
+    }
+
+    /// Draw the screen and handle keys, until the user quits.
+    pub fn run(&mut self, screen: &mut Screen) -> anyhow::Result<()> {
+        loop {
+            let (rows, cols) = screen.size();
+
+            screen.draw(&self.render(rows, cols))?;
+
+            let key = screen.read_key()?;
+            self.status.clear();
+
+            match self.handle(key, screen, rows.saturating_sub(CHROME_ROWS)) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(err) => self.status = term::format::negative(format!("Error: {}", err)),
+            }
+        }
+    }
+
+    /// Load the patches, issues and activity of the project.
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let urn = &self.project.urn;
+        let blocklist = &self.blocklist;
+
+        self.patches = self.cobs.patches().all(urn)?;
+        self.patches
+            .retain(|(_, p)| !blocklist.contains(&p.author.peer));
+        self.patches
+            .sort_by(|(_, a), (_, b)| b.timestamp.cmp(&a.timestamp));
+        for (_, patch) in &mut self.patches {
+            patch.resolve(self.storage).ok();
+        }
+
+        self.issues = self.cobs.issues().all(urn)?;
+        self.issues
+            .retain(|(_, i)| !blocklist.contains(&i.author().peer));
+        self.issues
+            .sort_by(|(_, a), (_, b)| b.timestamp().cmp(&a.timestamp()));
+        for (_, issue) in &mut self.issues {
+            issue.resolve(self.storage).ok();
+        }
+
+        self.activity = self.activity()?;
+
+        for tab in Tab::ALL {
+            let len = self.len(tab);
+            self.selected[tab.ix()] = cmp::min(self.selected[tab.ix()], len.saturating_sub(1));
+        }
+        if let View::Detail { target, scroll, .. } = self.view {
+            self.view = match self.detail(target) {
+                Some(lines) => View::Detail {
+                    target,
+                    scroll: cmp::min(scroll, lines.len().saturating_sub(1)),
+                    lines,
+                },
+                None => View::List,
+            };
+        }
+        Ok(())
+    }
+
+    /// Handle a key. Returns whether to quit.
+    fn handle(&mut self, key: Key, screen: &mut Screen, height: usize) -> anyhow::Result<bool> {
+        let page = cmp::max(height, 1);
+
+        match key {
+            Key::Char('q') | Key::Escape => match self.view {
+                View::List => return Ok(true),
+                View::Detail { .. } => self.view = View::List,
+            },
+            Key::Tab => self.switch(self.tab.next()),
+            Key::Char(c @ '1'..='3') => self.switch(Tab::ALL[c as usize - '1' as usize]),
+            Key::Char('j') | Key::ArrowDown => self.scroll(1, height),
+            Key::Char('k') | Key::ArrowUp => self.scroll(-1, height),
+            Key::PageDown => self.scroll(page as isize, height),
+            Key::PageUp => self.scroll(-(page as isize), height),
+            Key::Enter => {
+                if let Some(target) = self.target() {
+                    if let Some(lines) = self.detail(target) {
+                        self.view = View::Detail {
+                            target,
+                            lines,
+                            scroll: 0,
+                        };
+                    }
+                }
+            }
+            Key::Char('r') => {
+                self.reload()?;
+                self.status = "Reloaded".to_owned();
+            }
+            Key::Char('c') => self.comment(screen)?,
+            Key::Char('m') => self.merge(screen)?,
+            Key::Char('x') => self.close()?,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn switch(&mut self, tab: Tab) {
+        self.tab = tab;
+        self.view = View::List;
+    }
+
+    /// Move the selection, or scroll the detail view.
+    fn scroll(&mut self, delta: isize, height: usize) {
+        let items = self.len(self.tab);
+        let (pos, len) = match &mut self.view {
+            View::List => (&mut self.selected[self.tab.ix()], items),
+            View::Detail { lines, scroll, .. } => {
+                (scroll, lines.len().saturating_sub(height.saturating_sub(1)))
+            }
+        };
+        let max = len.saturating_sub(1) as isize;
+
+        *pos = (*pos as isize + delta).max(0).min(max) as usize;
+    }
+
+    fn len(&self, tab: Tab) -> usize {
+        match tab {
+            Tab::Patches => self.patches.len(),
+            Tab::Issues => self.issues.len(),
+            Tab::Activity => self.activity.len(),
+        }
+    }
+
+    /// The patch or issue that actions apply to.
+    fn target(&self) -> Option<Target> {
+        if let View::Detail { target, .. } = self.view {
+            return Some(target);
+        }
+        let selected = self.selected[self.tab.ix()];
+
+        match self.tab {
+            Tab::Patches => self.patches.get(selected).map(|(id, _)| Target::Patch(*id)),
+            Tab::Issues => self.issues.get(selected).map(|(id, _)| Target::Issue(*id)),
+            Tab::Activity => self.activity.get(selected).and_then(|e| e.target),
+        }
+    }
+
+    fn patch(&self, id: &PatchId) -> Option<&Patch> {
+        self.patches.iter().find(|(i, _)| i == id).map(|(_, p)| p)
+    }
+
+    fn issue(&self, id: &IssueId) -> Option<&Issue> {
+        self.issues.iter().find(|(i, _)| i == id).map(|(_, i)| i)
+    }
+
+    //
+    // Actions
+    //
+
+    /// Comment on the selected patch, on its latest revision, or issue.
+    fn comment(&mut self, screen: &mut Screen) -> anyhow::Result<()> {
+        let target = match self.target() {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        let body = screen.read_line("Comment (empty to cancel): ")?;
+
+        if body.is_empty() {
+            self.status = "Cancelled".to_owned();
+            return Ok(());
+        }
+        let urn = &self.project.urn;
+
+        match target {
+            Target::Patch(id) => {
+                let version = self.patch(&id).map(|p| p.version()).unwrap_or_default();
+                self.cobs.patches().comment(urn, &id, version, &body)?;
+            }
+            Target::Issue(id) => {
+                self.cobs.issues().comment(urn, &id, &body)?;
+            }
+        }
+        self.reload()?;
+        self.status = term::format::positive("Comment added");
+
+        Ok(())
+    }
+
+    /// Merge the selected patch into the current branch of the working copy, with
+    /// `rad merge`, which is shown outside of the TUI.
+    fn merge(&mut self, screen: &mut Screen) -> anyhow::Result<()> {
+        let id = match self.target() {
+            Some(Target::Patch(id)) => id,
+            Some(Target::Issue(_)) => {
+                self.status = "Only patches can be merged".to_owned();
+                return Ok(());
+            }
+            None => return Ok(()),
+        };
+        screen.leave()?;
+
+        let result = rad_merge::run(
+            rad_merge::Options {
+                id: Some(Identifier::Full(id)),
+                interactive: false,
+                revision: None,
+            },
+            self.profile.clone(),
+        );
+        if let Err(err) = &result {
+            term::error(term::tr!("Error: {}", err));
+        }
+        term::blank();
+        term::info!("{}", term::format::dim("Press any key to return"));
+        screen.read_key()?;
+        screen.enter()?;
+
+        self.reload()?;
+        self.status = match result {
+            Ok(()) => term::format::positive("Merge done"),
+            Err(_) => term::format::negative("Merge failed"),
+        };
+        Ok(())
+    }
+
+    /// Close the selected issue, or re-open it if it is closed.
+    fn close(&mut self) -> anyhow::Result<()> {
+        let id = match self.target() {
+            Some(Target::Issue(id)) => id,
+            Some(Target::Patch(_)) => {
+                self.status = "Only issues can be closed".to_owned();
+                return Ok(());
+            }
+            None => return Ok(()),
+        };
+        let (state, status) = match self.issue(&id).map(|i| i.state()) {
+            Some(issue::State::Open) => (
+                issue::State::Closed {
+                    reason: CloseReason::Other,
+                },
+                "Issue closed",
+            ),
+            _ => (issue::State::Open, "Issue re-opened"),
+        };
+        self.cobs
+            .issues()
+            .lifecycle(&self.project.urn, &id, state)?;
+        self.reload()?;
+        self.status = term::format::positive(status);
+
+        Ok(())
+    }
+
+    //
+    // Rendering
+    //
+
+    fn render(&self, rows: usize, cols: usize) -> Vec<String> {
+        let height = rows.saturating_sub(CHROME_ROWS);
+        let mut lines = Vec::with_capacity(rows);
+
+        let tabs = Tab::ALL
+            .iter()
+            .map(|tab| {
+                let label = format!(" {} {} ({}) ", tab.ix() + 1, tab.title(), self.len(*tab));
+
+                if *tab == self.tab {
+                    term::format::badge_primary(label)
+                } else {
+                    term::format::dim(label)
+                }
+            })
+            .collect::<Vec<_>>();
+        lines.push(format!(
+            "{}  {}",
+            term::format::bold(&self.project.name),
+            tabs.join(" ")
+        ));
+        lines.push(term::format::dim("─".repeat(cols)));
+
+        let mut body = match &self.view {
+            View::List => self.list(height),
+            View::Detail { lines, scroll, .. } => {
+                lines.iter().skip(*scroll).take(height).cloned().collect()
+            }
+        };
+        body.resize(height, String::new());
+        lines.extend(body);
+
+        lines.push(self.status.clone());
+        lines.push(term::format::dim(match self.view {
+            View::List => "j/k move · enter open · tab switch · c comment · m merge · x close/re-open · r reload · q quit",
+            View::Detail { .. } => "j/k scroll · c comment · m merge · x close/re-open · r reload · esc back",
+        }));
+        lines
+    }
+
+    /// Lines of the list of the current tab, scrolled to show the selected item.
+    fn list(&self, height: usize) -> Vec<String> {
+        let selected = self.selected[self.tab.ix()];
+        let offset = (selected + 1).saturating_sub(height);
+        let rows: Vec<String> = match self.tab {
+            Tab::Patches => self
+                .patches
+                .iter()
+                .map(|(id, patch)| {
+                    format!(
+                        "{} {:<8} {} {} {}",
+                        term::format::highlight(fmt::cob(id)),
+                        patch.state_name(),
+                        term::format::bold(&patch.title),
+                        term::format::tertiary(patch.author.name()),
+                        term::format::dim(patch.timestamp),
+                    )
+                })
+                .collect(),
+            Tab::Issues => self
+                .issues
+                .iter()
+                .map(|(id, issue)| {
+                    format!(
+                        "{} {:<8} {} {} {}",
+                        term::format::highlight(fmt::cob(id)),
+                        issue.state_name(),
+                        term::format::bold(issue.title()),
+                        term::format::tertiary(issue.author().name()),
+                        term::format::dim(issue.timestamp()),
+                    )
+                })
+                .collect(),
+            Tab::Activity => self
+                .activity
+                .iter()
+                .map(|e| {
+                    format!(
+                        "{:<8} {} {} {}",
+                        e.kind,
+                        e.summary,
+                        term::format::tertiary(&e.author),
+                        term::format::dim(e.timestamp),
+                    )
+                })
+                .collect(),
+        };
+
+        if rows.is_empty() {
+            return vec![term::format::dim(format!(
+                "  No {} yet",
+                self.tab.title().to_lowercase()
+            ))];
+        }
+        rows.into_iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(i, row)| {
+                if i == selected {
+                    format!("{} {}", term::format::highlight("›"), row)
+                } else {
+                    format!("  {}", row)
+                }
+            })
+            .collect()
+    }
+
+    /// Lines of the detail view of a patch or issue.
+    fn detail(&self, target: Target) -> Option<Vec<String>> {
+        match target {
+            Target::Patch(id) => self.patch(&id).map(|p| self.patch_detail(&id, p)),
+            Target::Issue(id) => self
+                .issue(&id)
+                .map(|i| issue_detail(&id, i, &self.blocklist)),
+        }
+    }
+
+    fn patch_detail(&self, id: &PatchId, patch: &Patch) -> Vec<String> {
+        let (version, revision) = patch.latest();
+        let mut lines = vec![
+            format!(
+                "{} {} {}",
+                term::format::bold(&patch.title),
+                term::format::highlight(fmt::cob(id)),
+                term::format::dim(format!("R{}", version)),
+            ),
+            format!(
+                "{} · opened by {} {}",
+                patch.state_name(),
+                term::format::tertiary(patch.author.name()),
+                term::format::dim(patch.timestamp),
+            ),
+            String::new(),
+        ];
+        text(&mut lines, patch.description(), 0);
+        lines.push(String::new());
+
+        lines.push(term::format::bold(format!(
+            "Changes {}..{}",
+            fmt::oid(&revision.base),
+            fmt::oid(&revision.oid)
+        )));
+        match git::git(
+            self.profile.paths().git_dir(),
+            [
+                "diff".to_owned(),
+                format!("{}..{}", revision.base, revision.oid),
+            ],
+        ) {
+            Ok(diff) => lines.extend(term::diff::render(&diff)),
+            Err(err) => lines.push(term::format::negative(format!("Diff unavailable: {}", err))),
+        }
+
+        for (ix, revision) in patch.revisions.iter().enumerate() {
+            let comments = self.blocklist.discussion(&revision.discussion);
+
+            if !comments.is_empty() {
+                lines.push(String::new());
+                lines.push(term::format::bold(format!("Discussion on R{}", ix)));
+                discussion(&mut lines, &comments);
+            }
+        }
+        lines
+    }
+
+    /// Latest commits of the default branch, and patch and issue events, newest first.
+    fn activity(&self) -> anyhow::Result<Vec<Event>> {
+        let mut events = Vec::new();
+
+        if let Some(head) = project::head(self.storage, &self.project) {
+            let monorepo = git::Repository::open_bare(self.profile.paths().git_dir())?;
+            let mut walk = monorepo.revwalk()?;
+            walk.push(head)?;
+
+            for oid in walk.take(MAX_COMMITS) {
+                let commit = monorepo.find_commit(oid?)?;
+
+                events.push(Event {
+                    timestamp: Timestamp::new(commit.time().seconds().max(0) as u64),
+                    kind: "commit",
+                    summary: format!(
+                        "{} {}",
+                        term::format::secondary(fmt::oid(&commit.id())),
+                        commit.summary().unwrap_or_default()
+                    ),
+                    author: commit.author().name().unwrap_or_default().to_owned(),
+                    target: None,
+                });
+            }
+        }
+
+        for (id, patch) in &self.patches {
+            let target = Some(Target::Patch(*id));
+            let title = term::format::bold(&patch.title);
+
+            for (ix, revision) in patch.revisions.iter().enumerate() {
+                events.push(Event {
+                    timestamp: revision.timestamp,
+                    kind: "patch",
+                    summary: if ix == 0 {
+                        format!("opened {}", title)
+                    } else {
+                        format!("updated {} to R{}", title, ix)
+                    },
+                    author: patch.author.name(),
+                    target,
+                });
+                for merge in &revision.merges {
+                    events.push(Event {
+                        timestamp: merge.timestamp,
+                        kind: "patch",
+                        summary: format!("merged {} R{}", title, ix),
+                        author: project::PeerInfo::get(&merge.peer, &self.project, self.storage)
+                            .name(),
+                        target,
+                    });
+                }
+                comment_events(
+                    &mut events,
+                    "patch",
+                    &title,
+                    &self.blocklist.discussion(&revision.discussion),
+                    target,
+                );
+            }
+        }
+
+        for (id, issue) in &self.issues {
+            let target = Some(Target::Issue(*id));
+            let title = term::format::bold(issue.title());
+
+            events.push(Event {
+                timestamp: issue.timestamp(),
+                kind: "issue",
+                summary: format!("opened {}", title),
+                author: issue.author().name(),
+                target,
+            });
+            comment_events(
+                &mut events,
+                "issue",
+                &title,
+                &self.blocklist.discussion(issue.comments()),
+                target,
+            );
+        }
+        events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        Ok(events)
+    }
+}
+
+fn comment_events(
+    events: &mut Vec<Event>,
+    kind: &'static str,
+    title: &str,
+    discussion: &[Comment<Replies>],
+    target: Option<Target>,
+) {
+    let event = |timestamp, author: &Author| Event {
+        timestamp,
+        kind,
+        summary: format!("commented on {}", title),
+        author: author.name(),
+        target,
+    };
+
+    for comment in discussion {
+        events.push(event(comment.timestamp, &comment.author));
+
+        for reply in &comment.replies {
+            events.push(event(reply.timestamp, &reply.author));
+        }
+    }
+}
+
+fn issue_detail(id: &IssueId, issue: &Issue, blocklist: &Blocklist) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{} {}",
+            term::format::bold(issue.title()),
+            term::format::highlight(fmt::cob(id)),
+        ),
+        format!(
+            "{} · opened by {} {}",
+            issue.state_name(),
+            term::format::tertiary(issue.author().name()),
+            term::format::dim(issue.timestamp()),
+        ),
+    ];
+    if !issue.labels().is_empty() {
+        lines.push(term::format::secondary(
+            issue
+                .labels()
+                .iter()
+                .map(|l| l.name())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
+    lines.push(String::new());
+    text(&mut lines, issue.description(), 0);
+
+    let comments = blocklist.discussion(issue.comments());
+
+    if !comments.is_empty() {
+        lines.push(String::new());
+        lines.push(term::format::bold("Discussion"));
+        discussion(&mut lines, &comments);
+    }
+    lines
+}
+
+/// Add the comments of a discussion, with their replies.
+fn discussion(lines: &mut Vec<String>, discussion: &[Comment<Replies>]) {
+    for comment in discussion {
+        lines.push(String::new());
+        lines.push(format!(
+            "{} {}",
+            term::format::tertiary(comment.author.name()),
+            term::format::dim(comment.timestamp)
+        ));
+        text(lines, &comment.body, 2);
+
+        for reply in &comment.replies {
+            lines.push(format!(
+                "    {} {}",
+                term::format::tertiary(reply.author.name()),
+                term::format::dim(reply.timestamp)
+            ));
+            text(lines, &reply.body, 6);
+        }
+    }
+}
+
+/// Add the lines of a text, indented.
+fn text(lines: &mut Vec<String>, text: &str, indent: usize) {
+    for line in text.trim().lines() {
+        lines.push(format!(
+            "{}{}",
+            " ".repeat(indent),
+            line.replace('\t', "    ")
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{cobs, exit, keys, project};
+use radicle_terminal as term;
+
+mod app;
+mod screen;
+
+use app::App;
+use screen::Screen;
+
+pub const HELP: Help = Help {
+    name: "tui",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad tui [--help]
+
+    Opens a full-screen view of the patches, issues and latest activity of the
+    project of the current working copy.
+
+Keys
+
+    j, k, ↑, ↓          Move the selection, or scroll
+    enter               Show the selected patch, with its diff, or issue
+    esc, q              Go back, or quit
+    tab, 1, 2, 3        Switch between patches, issues and activity
+    pgup, pgdn          Scroll by a page
+    c                   Comment on the selected patch or issue
+    m                   Merge the selected patch into the current branch
+    x                   Close or re-open the selected issue
+    r                   Reload
+
+Options
+
+    --help              Print help
+"#,
+};
+
+#[derive(Default, Eq, PartialEq)]
+pub struct Options {}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+
+        if let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options {}, vec![]))
+    }
+}
+
+pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if !term::is_interactive() {
+        return Err(Error::WithHint {
+            err: anyhow!("the TUI can only be used on a terminal"),
+            hint: "hint: use `rad patch` and `rad issue` in scripts",
+        }
+        .into());
+    }
+    let (urn, _) = project::cwd().map_err(|_| Error::WithHint {
+        err: anyhow!("could not determine project"),
+        hint: "hint: run this command within a project working copy",
+    })?;
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let project = project::get(&storage, &urn)?.ok_or_else(|| {
+        exit::Error::NotFound(term::tr!("couldn't load project {} from local state", urn))
+    })?;
+    let cobs = cobs::store(&profile, &storage)?;
+
+    let mut app = App::new(&profile, &storage, &cobs, project)?;
+    let mut screen = Screen::new();
+
+    screen.enter()?;
+    app.run(&mut screen)?;
+    screen.leave()?;
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Drawing full screens of text on the terminal.
+use std::io::{self, Write};
+
+use console::{Key, Term};
+
+/// Switch to the alternate screen, so that the terminal's content is restored on exit.
+const ENTER_ALTERNATE: &str = "\x1b[?1049h";
+const LEAVE_ALTERNATE: &str = "\x1b[?1049l";
+/// Move the cursor to the top-left corner.
+const HOME: &str = "\x1b[H";
+/// Reset styles, then clear to the end of the line.
+const END_OF_LINE: &str = "\x1b[0m\x1b[K";
+
+/// The terminal, while it is used by the TUI.
+pub struct Screen {
+    term: Term,
+    active: bool,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self {
+            term: Term::stdout(),
+            active: false,
+        }
+    }
+
+    /// Take over the terminal.
+    pub fn enter(&mut self) -> io::Result<()> {
+        if !self.active {
+            self.term.write_str(ENTER_ALTERNATE)?;
+            self.term.hide_cursor()?;
+            self.active = true;
+        }
+        Ok(())
+    }
+
+    /// Give the terminal back, eg. to run a command.
+    pub fn leave(&mut self) -> io::Result<()> {
+        if self.active {
+            self.term.show_cursor()?;
+            self.term.write_str(LEAVE_ALTERNATE)?;
+            self.active = false;
+        }
+        Ok(())
+    }
+
+    /// Number of rows and columns of the terminal.
+    pub fn size(&self) -> (usize, usize) {
+        let (rows, cols) = self.term.size();
+
+        (rows as usize, cols as usize)
+    }
+
+    /// Draw a screen of lines, cutting off what doesn't fit.
+    pub fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        let (rows, cols) = self.size();
+        let mut frame = String::from(HOME);
+
+        for row in 0..rows {
+            if let Some(line) = lines.get(row) {
+                frame.push_str(&console::truncate_str(line, cols, "…"));
+            }
+            frame.push_str(END_OF_LINE);
+
+            if row + 1 < rows {
+                frame.push_str("\r\n");
+            }
+        }
+        self.term.write_str(&frame)?;
+        self.term.flush()
+    }
+
+    pub fn read_key(&self) -> io::Result<Key> {
+        self.term.read_key()
+    }
+
+    /// Read a line of input on the last row.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<String> {
+        let (rows, _) = self.size();
+
+        self.term.move_cursor_to(0, rows.saturating_sub(1))?;
+        self.term.clear_line()?;
+        self.term.write_str(prompt)?;
+        self.term.show_cursor()?;
+
+        let line = self.term.read_line();
+        self.term.hide_cursor()?;
+
+        line.map(|l| l.trim().to_owned())
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        self.leave().ok();
+    }
+}
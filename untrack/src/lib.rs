@@ -2,7 +2,6 @@ use std::ffi::OsString;
 use std::str::FromStr;
 
 use anyhow::anyhow;
-use anyhow::Context as _;
 
 use librad::git::storage::Storage;
 use librad::git::tracking::git::tracking;
@@ -11,7 +10,8 @@ use librad::PeerId;
 
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::profile::Profile;
-use radicle_common::{fmt, git, keys, project};
+use radicle_common::sync::scope;
+use radicle_common::{block, exit, fmt, git, keys, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -21,12 +21,18 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad untrack [<peer-id>] [--all]
+    rad untrack [<peer-id>] [--all] [--no-confirm]
 
     Must be run within a project working copy.
 
+    Untracking a peer removes its remote refs from local storage, except for its `rad/`
+    refs, which are needed to verify the project, as well as its git remotes, remote-tracking branches, and the local branches tracking them, from the
+    working copy. A summary of what is removed is shown, and confirmation is asked for.
+    A local branch that is checked out is kept.
+
 Options
 
+    --no-confirm        Don't ask for confirmation before removing refs and branches
     --help              Print help
 "#,
 };
@@ -35,6 +41,7 @@ Options
 #[derive(Debug)]
 pub struct Options {
     pub peer: Option<String>,
+    pub confirm: bool,
 }
 
 impl Args for Options {
@@ -44,12 +51,16 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut peer: Option<String> = None;
         let mut all = false;
+        let mut confirm = true;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("all") if peer.is_none() => {
                     all = true;
                 }
+                Long("no-confirm") => {
+                    confirm = false;
+                }
                 Value(val) if peer.is_none() => {
                     let val = val.to_string_lossy();
                     peer = Some(val.to_string());
@@ -67,13 +78,12 @@ impl Args for Options {
             return Err(Error::Usage.into());
         }
 
-        Ok((Options { peer }, vec![]))
+        Ok((Options { peer, confirm }, vec![]))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    let (urn, repo) =
-        project::cwd().context("this command must be run in the context of a project")?;
+    let (urn, repo) = project::cwd()?;
     let profile = ctx.profile()?;
 
     execute(&urn, Some(&repo), options, &profile)
@@ -101,34 +111,78 @@ pub fn execute(
 ) -> anyhow::Result<()> {
     let signer = term::signer(profile)?;
     let storage = keys::storage(profile, signer)?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    // Nb. Peers can be untracked from projects that were not fetched yet.
+    let project = project::get(&storage, urn)?;
 
-    if let Some(peer_str) = options.peer {
-        let peer = if let Ok(val) = PeerId::from_str(&peer_str) {
-            val
+    let peer = if let Some(peer_str) = &options.peer {
+        if let Ok(val) = PeerId::from_str(peer_str) {
+            Some(val)
         } else {
-            let project = project::get(&storage, urn)?
-                .ok_or_else(|| anyhow!("project {} not found in local storage", &urn))?;
+            // Peers are looked up by name in the project, which has to be in storage.
+            let project = project.as_ref().ok_or_else(|| {
+                exit::Error::NotFound(term::tr!("project {} not found in local storage", &urn))
+            })?;
 
-            if let Some(v) = get_peer_id(&project, &storage, &peer_str)? {
-                v
+            match get_peer_id(project, &storage, peer_str)? {
+                Some(v) => Some(v),
+                None => anyhow::bail!(term::tr!("invalid <peer-id> '{}'", peer_str)),
+            }
+        }
+    } else {
+        None
+    };
+    // Blocked peers stay tracked, so that their data is refused.
+    let mut tracked = Vec::new();
+    let mut blocked = Vec::new();
+    for entry in tracking::tracked(storage.read_only(), Some(urn))? {
+        let entry = entry?;
+        if let Some(id) = entry.peer_id() {
+            if block::is_blocked(entry.config()) {
+                blocked.push(id);
             } else {
-                anyhow::bail!("invalid <peer-id> '{}'", peer_str)
+                tracked.push(id);
             }
-        };
+        }
+    }
+    let peers = match peer {
+        Some(peer) if blocked.contains(&peer) => {
+            anyhow::bail!(term::tr!(
+                "peer {} is blocked, unblock it with `rad block --remove`",
+                peer
+            ))
+        }
+        Some(peer) => vec![peer],
+        None => tracked,
+    };
+    let cleanup = Cleanup::new(urn, &peers, &monorepo, repo)?;
+
+    if !cleanup.is_empty() {
+        cleanup.print();
+
+        if options.confirm && !term::confirm("Remove the above refs and branches?")? {
+            anyhow::bail!(exit::Error::Aborted("untrack aborted".to_owned()));
+        }
+    }
 
+    if let Some(peer) = peer {
         tracking::untrack(
             &storage,
             urn,
             peer,
             tracking::UntrackArgs {
                 policy: tracking::policy::Untrack::MustExist,
-                prune: true,
+                prune: false,
             },
         )??;
+        cleanup.prune(urn, &monorepo)?;
+        for peer in &peers {
+            scope::remove(profile, urn, peer)?;
+        }
 
         if let Some(repo) = repo {
-            term::remote::remove(&peer.to_string(), &storage, repo, urn)?;
-        };
+            cleanup.run(repo)?;
+        }
 
         term::success!(
             "Tracking relationship {} removed for {}",
@@ -136,20 +190,31 @@ pub fn execute(
             term::format::highlight(urn)
         );
     } else {
-        let all_untracked = tracking::untrack_all(
+        tracking::untrack_all(
             &storage,
             urn,
             tracking::UntrackAllArgs {
                 policy: tracking::policy::UntrackAll::Any,
-                prune: true,
+                prune: false,
             },
         )?;
+        for peer in blocked {
+            tracking::track(
+                &storage,
+                urn,
+                Some(peer),
+                block::config(),
+                tracking::policy::Track::Any,
+            )??;
+        }
+        cleanup.prune(urn, &monorepo)?;
+        for peer in &peers {
+            scope::remove(profile, urn, peer)?;
+        }
 
         if let Some(repo) = repo {
-            for p in all_untracked.untracked.flatten() {
-                term::remote::remove(&p.remote.to_string(), &storage, repo, urn)?;
-            }
-        };
+            cleanup.run(repo)?;
+        }
 
         term::success!(
             "Tracking relationships for {} removed",
@@ -159,3 +224,132 @@ pub fn execute(
 
     Ok(())
 }
+
+/// Refs and branches of untracked peers, that are removed along with the tracking
+/// relationships.
+#[derive(Debug, Default)]
+struct Cleanup {
+    /// Number of remote refs of each peer in storage, not counting its `rad/` refs, which
+    /// are kept.
+    refs: Vec<(PeerId, usize)>,
+    /// Git remotes of the peers in the working copy, with their number of
+    /// remote-tracking branches.
+    remotes: Vec<(String, usize)>,
+    /// Local branches of the working copy tracking one of the remotes.
+    branches: Vec<String>,
+}
+
+impl Cleanup {
+    fn new(
+        urn: &Urn,
+        peers: &[PeerId],
+        monorepo: &git::Repository,
+        repo: Option<&git::Repository>,
+    ) -> anyhow::Result<Self> {
+        let mut cleanup = Self::default();
+
+        for peer in peers {
+            let count = project::prunable_refs(monorepo, urn, peer)?.len();
+
+            if count > 0 {
+                cleanup.refs.push((*peer, count));
+            }
+        }
+
+        if let Some(repo) = repo {
+            let mut remotes = git::remotes(repo)?
+                .into_iter()
+                .filter(|(_, peer)| peers.contains(peer))
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            remotes.sort();
+            remotes.dedup();
+
+            for name in remotes {
+                let count = repo
+                    .references_glob(&format!("refs/remotes/{}/*", name))?
+                    .count();
+                cleanup.remotes.push((name, count));
+            }
+
+            for branch in repo.branches(Some(git::BranchType::Local))? {
+                let (branch, _) = branch?;
+                let name = match branch.name()? {
+                    Some(name) => name,
+                    None => continue,
+                };
+                if let Ok(remote) = git::branch_remote(repo, name) {
+                    if cleanup.remotes.iter().any(|(r, _)| *r == remote) {
+                        cleanup.branches.push(name.to_owned());
+                    }
+                }
+            }
+        }
+        Ok(cleanup)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.refs.is_empty() && self.remotes.is_empty() && self.branches.is_empty()
+    }
+
+    fn print(&self) {
+        term::info!("The following will be removed:");
+        term::blank();
+
+        for (peer, count) in &self.refs {
+            term::info!(
+                "{} {} remote ref(s) of {} in storage",
+                term::format::negative("-"),
+                count,
+                term::format::tertiary(fmt::peer(peer))
+            );
+        }
+        for (name, count) in &self.remotes {
+            term::info!(
+                "{} remote {} and its {} remote-tracking branch(es)",
+                term::format::negative("-"),
+                term::format::highlight(name),
+                count
+            );
+        }
+        for name in &self.branches {
+            term::info!(
+                "{} branch {}",
+                term::format::negative("-"),
+                term::format::highlight(name)
+            );
+        }
+        term::blank();
+    }
+
+    /// Remove the remote refs of the peers from storage.
+    fn prune(&self, urn: &Urn, monorepo: &git::Repository) -> anyhow::Result<()> {
+        for (peer, _) in &self.refs {
+            project::prune_refs(monorepo, urn, peer, |_| true)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the branches and remotes from the working copy.
+    fn run(&self, repo: &git::Repository) -> anyhow::Result<()> {
+        for name in &self.branches {
+            let mut branch = repo.find_branch(name, git::BranchType::Local)?;
+
+            if branch.is_head() {
+                term::warning(&term::tr!(
+                    "Branch {} is checked out, and was kept",
+                    term::format::highlight(name)
+                ));
+                continue;
+            }
+            branch.delete()?;
+            term::success!("Branch {} removed", term::format::highlight(name));
+        }
+        for (name, _) in &self.remotes {
+            // Deleting a remote also deletes its remote-tracking branches.
+            repo.remote_delete(name)?;
+            term::success!("Remote {} removed", term::format::highlight(name));
+        }
+        Ok(())
+    }
+}
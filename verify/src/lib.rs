@@ -0,0 +1,181 @@
+use std::ffi::OsString;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::PeerId;
+
+use radicle_common::alias::Aliases;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::sshsig::{self, Signature};
+use radicle_common::{fmt, git, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "verify",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad verify [<commit>]
+
+    Verifies the SSH signature of a commit, and checks that it was made by a radicle
+    key: yours, or the key of a delegate or tracked peer of the current project.
+    Fails if the commit is unsigned, the signature is bad, or the key is unknown.
+
+    If no commit is given, `HEAD` is verified. To sign commits with your radicle key,
+    run `rad git configure-signing`.
+
+Options
+
+    --help      Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub rev: String,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut rev: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if rev.is_none() => {
+                    rev = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                rev: rev.unwrap_or_else(|| String::from("HEAD")),
+            },
+            vec![],
+        ))
+    }
+}
+
+/// Signer of a commit.
+struct Signer {
+    peer: PeerId,
+    name: String,
+    delegate: bool,
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let repo = git::repository()?;
+
+    let commit = repo
+        .revparse_single(&options.rev)
+        .and_then(|obj| obj.peel_to_commit())
+        .with_context(|| term::tr!("commit '{}' not found", options.rev))?;
+    let (signature, data) = repo
+        .extract_signature(&commit.id(), None)
+        .map_err(|_| anyhow!(term::tr!("commit {} is not signed", fmt::oid(&commit.id()))))?;
+    let signature = signature
+        .as_str()
+        .ok_or_else(|| anyhow!("invalid commit signature"))?;
+
+    if signature.starts_with("-----BEGIN PGP") {
+        anyhow::bail!("commit is signed with GPG: only SSH signatures can be verified");
+    }
+    let signature = Signature::from_armored(signature)?;
+
+    if signature.namespace != sshsig::NAMESPACE_GIT {
+        anyhow::bail!(term::tr!(
+            "signature has namespace '{}', expected '{}'",
+            signature.namespace,
+            sshsig::NAMESPACE_GIT
+        ));
+    }
+    signature.verify(&data)?;
+
+    let signer = find_signer(&signature, &storage)?.ok_or_else(|| Error::WithHint {
+        err: anyhow!(term::tr!(
+            "good signature, but key {} doesn't belong to a known radicle peer",
+            signature.fingerprint()
+        )),
+        hint: "hint: track the signer's peer to verify their commits",
+    })?;
+
+    term::success!(
+        "Good signature on {} from {} {}{}",
+        term::format::secondary(fmt::oid(&commit.id())),
+        term::format::highlight(&signer.name),
+        term::format::tertiary(fmt::peer(&signer.peer)),
+        if signer.delegate {
+            format!(" {}", term::format::badge_primary("delegate"))
+        } else {
+            String::new()
+        }
+    );
+
+    Ok(())
+}
+
+/// Find the signer among our own key and the peers of the current project.
+fn find_signer(
+    signature: &Signature,
+    storage: &librad::git::storage::ReadOnly,
+) -> anyhow::Result<Option<Signer>> {
+    let proj = match project::cwd() {
+        Ok((urn, _)) => project::get(storage, &urn)?,
+        Err(_) => None,
+    };
+    let delegate = |peer: &PeerId| {
+        proj.as_ref()
+            .map_or(false, |p| p.delegates.iter().any(|d| d.contains(peer)))
+    };
+
+    if signature.is_signed_by(storage.peer_id()) {
+        return Ok(Some(Signer {
+            peer: *storage.peer_id(),
+            name: String::from("you"),
+            delegate: delegate(storage.peer_id()),
+        }));
+    }
+    let proj = match &proj {
+        Some(proj) => proj,
+        None => return Ok(None),
+    };
+    let aliases = Aliases::cwd();
+
+    if let Some(info) = project::tracked(proj, storage)?
+        .into_values()
+        .find(|info| signature.is_signed_by(&info.id))
+    {
+        return Ok(Some(Signer {
+            peer: info.id,
+            name: aliases.name_or(&info.id, || info.name()),
+            delegate: info.delegate,
+        }));
+    }
+
+    // Delegates are trusted even if they aren't tracked.
+    for d in &proj.delegates {
+        let peers = match d {
+            project::Delegate::Direct { id } => vec![*id],
+            project::Delegate::Indirect { ids, .. } => ids.iter().copied().collect(),
+        };
+        if let Some(peer) = peers.into_iter().find(|p| signature.is_signed_by(p)) {
+            return Ok(Some(Signer {
+                peer,
+                name: aliases.name(&peer),
+                delegate: true,
+            }));
+        }
+    }
+    Ok(None)
+}
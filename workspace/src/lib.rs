@@ -0,0 +1,230 @@
+use std::ffi::OsString;
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use anyhow::anyhow;
+
+use librad::git::Urn;
+
+use radicle_common::args::{self, Args, Error, Help};
+use radicle_common::{git, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "workspace",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad workspace [--path <dir>] [--depth <n>]
+    rad workspace [--path <dir>] [--depth <n>] <command> [<arg>...]
+
+    Discovers the project working copies under a directory, ie. the git repositories
+    with a `rad` remote, and lists them, or runs the given rad command in each of them,
+    eg. `rad workspace sync` or `rad workspace patch list`. A summary of the outcome
+    in each working copy is printed once all commands are done.
+
+    Working copies are looked up in the current directory, or the directory given
+    with `--path`, and its subdirectories, up to `--depth` levels deep (default: 3).
+    Hidden directories, and directories within working copies, are skipped.
+
+Options
+
+    --path <dir>    Directory to discover working copies in (default: .)
+    --depth <n>     Maximum depth of the working copies under the directory (default: 3)
+    --help          Print help
+"#,
+};
+
+/// Default maximum depth of working copies under the workspace directory.
+pub const DEFAULT_DEPTH: usize = 3;
+
+#[derive(Debug)]
+pub struct Options {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub command: Vec<OsString>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut path = PathBuf::from(".");
+        let mut depth = DEFAULT_DEPTH;
+        let mut command = Vec::new();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("path") => {
+                    path = PathBuf::from(parser.value()?);
+                }
+                Long("depth") => {
+                    let value = parser.value()?;
+
+                    depth = args::parse_value("depth", value)?;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                // Everything from the command onwards is passed on to it.
+                Value(val) => {
+                    command = iter::once(val)
+                        .chain(iter::from_fn(|| parser.value().ok()))
+                        .collect();
+
+                    break;
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                path,
+                depth,
+                command,
+            },
+            vec![],
+        ))
+    }
+}
+
+/// A working copy found in the workspace.
+struct WorkingCopy {
+    path: PathBuf,
+    urn: Urn,
+    name: String,
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let mut copies = Vec::new();
+
+    discover(&options.path, options.depth, &mut |path, urn| {
+        let name = project::get(&storage, &urn)
+            .ok()
+            .flatten()
+            .map(|p| p.name)
+            .or_else(|| path.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default();
+
+        copies.push(WorkingCopy {
+            path: path.to_path_buf(),
+            urn,
+            name,
+        });
+    })?;
+    copies.sort_by(|a, b| a.path.cmp(&b.path));
+
+    if copies.is_empty() {
+        term::info!(
+            "No working copies found under {}",
+            term::format::highlight(options.path.display())
+        );
+        return Ok(());
+    }
+
+    if options.command.is_empty() {
+        let mut table = term::Table::default();
+        for copy in copies {
+            table.push([
+                term::format::bold(copy.name),
+                term::format::tertiary(copy.urn),
+                term::format::dim(copy.path.display()),
+            ]);
+        }
+        table.render();
+
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe()?;
+    let command = options
+        .command
+        .iter()
+        .map(|a| a.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut table = term::Table::default();
+    let mut failed = 0;
+
+    for copy in &copies {
+        term::headline(&term::tr!(
+            "Running `rad {}` in {} ({})",
+            command,
+            term::format::highlight(copy.path.display()),
+            copy.name
+        ));
+        let status = process::Command::new(&exe)
+            .args(&options.command)
+            .current_dir(&copy.path)
+            .status();
+        let ok = matches!(status, Ok(status) if status.success());
+
+        if let Err(err) = status {
+            term::error(err);
+        }
+        if !ok {
+            failed += 1;
+        }
+        table.push([
+            if ok {
+                term::format::positive("ok")
+            } else {
+                term::format::negative("!!")
+            },
+            term::format::bold(&copy.name),
+            term::format::dim(copy.path.display()),
+        ]);
+    }
+    term::blank();
+    table.render();
+
+    if failed > 0 {
+        anyhow::bail!(term::tr!(
+            "`rad {}` failed in {} of {} working copies",
+            command,
+            failed,
+            copies.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Discover the working copies under a directory, up to the given depth.
+fn discover(dir: &Path, depth: usize, found: &mut impl FnMut(&Path, Urn)) -> anyhow::Result<()> {
+    if dir.join(".git").exists() {
+        if let Ok(urn) = git::Repository::open(dir)
+            .map_err(anyhow::Error::from)
+            .and_then(|repo| git::rad_remote(&repo))
+            .map(|remote| remote.url.urn)
+        {
+            found(dir, urn);
+            return Ok(());
+        }
+    }
+    if depth == 0 {
+        return Ok(());
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // Unreadable directories are skipped.
+        Err(_) => return Ok(()),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        if !hidden && entry.file_type().map_or(false, |t| t.is_dir()) {
+            discover(&path, depth - 1, found)?;
+        }
+    }
+    Ok(())
+}